@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+#
+# Re-pins the solana crates to $SOLANA_VERSION when it differs from the version the manifests
+# are pinned to, so the program test suite can be run against other solana releases.
+#
+# Does nothing when $SOLANA_VERSION is unset or already matches.
+#
+
+set -e
+cd "$(dirname "$0")/.."
+
+if [[ -z $SOLANA_VERSION ]]; then
+  exit 0
+fi
+
+target_version=${SOLANA_VERSION#v}
+pinned_version=$(sed -n 's#^solana-program = "=\(.*\)"#\1#p' token-lending/program/Cargo.toml)
+
+if [[ $target_version == "$pinned_version" ]]; then
+  echo "solana crates already pinned to $pinned_version"
+  exit 0
+fi
+
+set -x
+
+# the exact pins in the manifests make cargo re-resolve Cargo.lock on the next build
+./update-solana-dependencies.sh "$target_version"
@@ -25,7 +25,7 @@ impl SolendState {
     ) -> Self {
         let obligation = {
             let data = rpc_client.get_account(&obligation_pubkey).unwrap();
-            Obligation::unpack(&data.data).unwrap()
+            Obligation::unpack_account(&data.data).unwrap()
         };
 
         // get reserve pubkeys
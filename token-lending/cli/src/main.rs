@@ -1,11 +1,12 @@
 use lending_state::SolendState;
 
+use serde::{de, Deserialize, Deserializer};
 use serde_json::Value;
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::rpc_config::{RpcProgramAccountsConfig, RpcSendTransactionConfig};
 use solana_client::{rpc_config::RpcAccountInfoConfig, rpc_filter::RpcFilterType};
 use solana_sdk::bs58;
-use solana_sdk::instruction::Instruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::{commitment_config::CommitmentLevel, compute_budget::ComputeBudgetInstruction};
 use solend_program::{
     instruction::set_lending_market_owner_and_config,
@@ -13,11 +14,13 @@ use solend_program::{
 };
 use solend_sdk::{
     instruction::{
-        liquidate_obligation_and_redeem_reserve_collateral, redeem_reserve_collateral,
-        refresh_obligation, refresh_reserve,
+        liquidate_obligation_and_redeem_reserve_collateral, redeem_fees,
+        redeem_fees_with_insurance_fund, redeem_reserve_collateral, refresh_obligation,
+        refresh_reserve,
     },
     state::Obligation,
     state::ReserveType,
+    state::{find_reserve_spread_fee_receiver_address, ReserveSpreadFeeReceiver},
 };
 
 mod lending_state;
@@ -46,7 +49,9 @@ use {
     },
     solend_sdk::{
         self,
-        instruction::{init_lending_market, init_reserve, update_reserve_config},
+        instruction::{
+            init_lending_market, init_reserve_with_collateral_decimals, update_reserve_config,
+        },
         math::WAD,
         state::{LendingMarket, Reserve, ReserveConfig, ReserveFees},
     },
@@ -71,7 +76,10 @@ struct Config {
     dry_run: bool,
 }
 
-/// Reserve config with optional fields
+/// Reserve config with optional fields. Can also be read from a TOML file, which takes the
+/// same field names except for the extra oracle
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 struct PartialReserveConfig {
     /// Optimal utilization rate, as a percentage
     pub optimal_utilization_rate: Option<u8>,
@@ -96,6 +104,10 @@ struct PartialReserveConfig {
     pub max_borrow_rate: Option<u8>,
     /// Supermax borrow apr
     pub super_max_borrow_rate: Option<u64>,
+    /// Utilization rate of the kink between optimal and max utilization, 0 to disable
+    pub super_optimal_utilization_rate: Option<u8>,
+    /// Borrow APY at the super optimal utilization rate
+    pub super_optimal_borrow_rate: Option<u8>,
     /// Program owner fees assessed, separate from gains due to interest accrual
     pub fees: PartialReserveFees,
     /// Deposit limit
@@ -103,6 +115,7 @@ struct PartialReserveConfig {
     /// Borrow limit
     pub borrow_limit: Option<u64>,
     /// Liquidity fee receiver
+    #[serde(deserialize_with = "deserialize_from_str")]
     pub fee_receiver: Option<Pubkey>,
     /// Cut of the liquidation bonus that the protocol receives, in deca bps
     pub protocol_liquidation_fee: Option<u8>,
@@ -115,19 +128,59 @@ struct PartialReserveConfig {
     /// Added borrow weight in basis points
     pub added_borrow_weight_bps: Option<u64>,
     /// Type of the reseerve (Regular, Isolated)
+    #[serde(deserialize_with = "deserialize_from_str")]
     pub reserve_type: Option<ReserveType>,
     /// scaled price offset in basis points. Exclusively used to calculate a more reliable asset price for
     /// staked assets (mSOL, stETH). Not used on extra oracle
     pub scaled_price_offset_bps: Option<i64>,
     /// Extra oracle. Only used to limit borrows and withdrawals.
+    #[serde(skip)]
     pub extra_oracle_pubkey: Option<Option<Pubkey>>,
     /// Open Attributed Borrow limit in USD
     pub attributed_borrow_limit_open: Option<u64>,
     /// Close Attributed Borrow limit in USD
     pub attributed_borrow_limit_close: Option<u64>,
+    /// Close factor for deeply unhealthy obligations, in basis points
+    pub max_close_factor_bps: Option<u16>,
+    /// Max deviation of spot price from EMA price allowed for liquidations, in basis points. 0 to disable
+    pub liquidation_price_band_bps: Option<u16>,
+    /// Max age of an oracle price before it is considered stale, in seconds. 0 to use the default
+    pub max_oracle_staleness_secs: Option<u64>,
+    /// Max oracle confidence interval as a fraction of price, in basis points. 0 to use the default
+    pub max_oracle_confidence_bps: Option<u16>,
+    /// Max share of the reserve's liquidity one obligation may borrow, in bps. 0 means no limit
+    pub max_single_borrower_share_bps: Option<u16>,
+    /// Reject deposits into the reserve
+    pub deposits_paused: Option<bool>,
+    /// Reject borrows from the reserve
+    pub borrows_paused: Option<bool>,
+    /// Reject withdrawals from the reserve
+    pub withdrawals_paused: Option<bool>,
+    /// Min value of an obligation's borrow from the reserve after a borrow, in USD. 0 means no minimum
+    pub min_borrow_value: Option<u64>,
+    /// Max borrowing power a deposit of the reserve's collateral adds to an obligation, in USD. 0 means no limit
+    pub max_collateral_value_contribution: Option<u64>,
+    /// Lowest price the reserve's liquidity is valued at, in ten-thousandths of USD. 0 means no bound
+    pub min_price: Option<u64>,
+    /// Highest price the reserve's liquidity is valued at, in ten-thousandths of USD. 0 means no bound
+    pub max_price: Option<u64>,
+    /// Ramp the liquidation bonus up from 0 after obligations become unhealthy
+    pub liquidation_auction: Option<bool>,
+    /// Take the protocol liquidation fee in the repaid liquidity instead of the collateral bonus
+    pub protocol_liquidation_fee_in_liquidity: Option<bool>,
+    /// Asset category of the reserve in the lending market's asset categories. 0 means none
+    pub asset_category: Option<u8>,
+    /// Borrow APR, in percent, above which capped borrows are paid by the insurance fund. 0 means rate caps aren't offered
+    pub borrow_rate_cap: Option<u8>,
+    /// Upfront premium for a rate cap, in basis points of the borrowed amount it covers
+    pub rate_cap_premium_bps: Option<u16>,
+    /// Max liquidity the flash borrows of a transaction can take combined. 0 means no cap
+    pub max_flash_loan_amount: Option<u64>,
 }
 
 /// Reserve Fees with optional fields
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 struct PartialReserveFees {
     pub borrow_fee_wad: Option<u64>,
     /// Fee for flash loan, expressed as a Wad.
@@ -137,6 +190,137 @@ struct PartialReserveFees {
     pub host_fee_percentage: Option<u8>,
 }
 
+impl PartialReserveConfig {
+    /// Fill the fields that aren't set with those of `fallback`
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            optimal_utilization_rate: self
+                .optimal_utilization_rate
+                .or(fallback.optimal_utilization_rate),
+            max_utilization_rate: self.max_utilization_rate.or(fallback.max_utilization_rate),
+            loan_to_value_ratio: self.loan_to_value_ratio.or(fallback.loan_to_value_ratio),
+            liquidation_bonus: self.liquidation_bonus.or(fallback.liquidation_bonus),
+            max_liquidation_bonus: self
+                .max_liquidation_bonus
+                .or(fallback.max_liquidation_bonus),
+            liquidation_threshold: self
+                .liquidation_threshold
+                .or(fallback.liquidation_threshold),
+            max_liquidation_threshold: self
+                .max_liquidation_threshold
+                .or(fallback.max_liquidation_threshold),
+            min_borrow_rate: self.min_borrow_rate.or(fallback.min_borrow_rate),
+            optimal_borrow_rate: self.optimal_borrow_rate.or(fallback.optimal_borrow_rate),
+            max_borrow_rate: self.max_borrow_rate.or(fallback.max_borrow_rate),
+            super_max_borrow_rate: self
+                .super_max_borrow_rate
+                .or(fallback.super_max_borrow_rate),
+            super_optimal_utilization_rate: self
+                .super_optimal_utilization_rate
+                .or(fallback.super_optimal_utilization_rate),
+            super_optimal_borrow_rate: self
+                .super_optimal_borrow_rate
+                .or(fallback.super_optimal_borrow_rate),
+            fees: self.fees.or(fallback.fees),
+            deposit_limit: self.deposit_limit.or(fallback.deposit_limit),
+            borrow_limit: self.borrow_limit.or(fallback.borrow_limit),
+            fee_receiver: self.fee_receiver.or(fallback.fee_receiver),
+            protocol_liquidation_fee: self
+                .protocol_liquidation_fee
+                .or(fallback.protocol_liquidation_fee),
+            protocol_take_rate: self.protocol_take_rate.or(fallback.protocol_take_rate),
+            rate_limiter_window_duration: self
+                .rate_limiter_window_duration
+                .or(fallback.rate_limiter_window_duration),
+            rate_limiter_max_outflow: self
+                .rate_limiter_max_outflow
+                .or(fallback.rate_limiter_max_outflow),
+            added_borrow_weight_bps: self
+                .added_borrow_weight_bps
+                .or(fallback.added_borrow_weight_bps),
+            reserve_type: self.reserve_type.or(fallback.reserve_type),
+            scaled_price_offset_bps: self
+                .scaled_price_offset_bps
+                .or(fallback.scaled_price_offset_bps),
+            extra_oracle_pubkey: self.extra_oracle_pubkey.or(fallback.extra_oracle_pubkey),
+            attributed_borrow_limit_open: self
+                .attributed_borrow_limit_open
+                .or(fallback.attributed_borrow_limit_open),
+            attributed_borrow_limit_close: self
+                .attributed_borrow_limit_close
+                .or(fallback.attributed_borrow_limit_close),
+            max_close_factor_bps: self.max_close_factor_bps.or(fallback.max_close_factor_bps),
+            liquidation_price_band_bps: self
+                .liquidation_price_band_bps
+                .or(fallback.liquidation_price_band_bps),
+            max_oracle_staleness_secs: self
+                .max_oracle_staleness_secs
+                .or(fallback.max_oracle_staleness_secs),
+            max_oracle_confidence_bps: self
+                .max_oracle_confidence_bps
+                .or(fallback.max_oracle_confidence_bps),
+            max_single_borrower_share_bps: self
+                .max_single_borrower_share_bps
+                .or(fallback.max_single_borrower_share_bps),
+            deposits_paused: self.deposits_paused.or(fallback.deposits_paused),
+            borrows_paused: self.borrows_paused.or(fallback.borrows_paused),
+            withdrawals_paused: self.withdrawals_paused.or(fallback.withdrawals_paused),
+            min_borrow_value: self.min_borrow_value.or(fallback.min_borrow_value),
+            max_collateral_value_contribution: self
+                .max_collateral_value_contribution
+                .or(fallback.max_collateral_value_contribution),
+            min_price: self.min_price.or(fallback.min_price),
+            max_price: self.max_price.or(fallback.max_price),
+            liquidation_auction: self.liquidation_auction.or(fallback.liquidation_auction),
+            protocol_liquidation_fee_in_liquidity: self
+                .protocol_liquidation_fee_in_liquidity
+                .or(fallback.protocol_liquidation_fee_in_liquidity),
+            asset_category: self.asset_category.or(fallback.asset_category),
+            borrow_rate_cap: self.borrow_rate_cap.or(fallback.borrow_rate_cap),
+            rate_cap_premium_bps: self.rate_cap_premium_bps.or(fallback.rate_cap_premium_bps),
+            max_flash_loan_amount: self
+                .max_flash_loan_amount
+                .or(fallback.max_flash_loan_amount),
+        }
+    }
+}
+
+impl PartialReserveFees {
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            borrow_fee_wad: self.borrow_fee_wad.or(fallback.borrow_fee_wad),
+            flash_loan_fee_wad: self.flash_loan_fee_wad.or(fallback.flash_loan_fee_wad),
+            host_fee_percentage: self.host_fee_percentage.or(fallback.host_fee_percentage),
+        }
+    }
+}
+
+/// Read a reserve config from a TOML file, eg
+/// ```toml
+/// loan_to_value_ratio = 75
+/// reserve_type = "Regular"
+/// fee_receiver = "5Gdxn4yquneifE6uk9tK8X4CqHfWKjW2BvYU25hAykwP"
+///
+/// [fees]
+/// borrow_fee_wad = 100000000000000
+/// ```
+fn read_reserve_config_file(path: &str) -> Result<PartialReserveConfig, Error> {
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Deserialize an optional value from its string representation, eg a pubkey
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
 type Error = Box<dyn std::error::Error>;
 type CommandResult = Result<(), Error>;
 
@@ -385,6 +569,19 @@ fn main() {
                         .help("amount of ctokens to redeem"),
                 )
         )
+        .subcommand(
+            SubCommand::with_name("redeem-fees")
+                .about("Send a reserve's accumulated protocol fees to its fee receiver")
+                .arg(
+                    Arg::with_name("reserve")
+                        .long("reserve")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Reserve address"),
+                )
+        )
         .subcommand(
             SubCommand::with_name("add-reserve")
                 .about("Add a reserve to a lending market")
@@ -436,6 +633,15 @@ fn main() {
                         .required(true)
                         .help("Initial amount of liquidity to deposit into the new reserve"),
                 )
+                .arg(
+                    Arg::with_name("collateral_decimals")
+                        .long("collateral-decimals")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Decimals of the reserve's collateral mint. Defaults to the liquidity mint's decimals"),
+                )
                 .arg(
                     Arg::with_name("pyth_product")
                         .long("pyth-product")
@@ -573,6 +779,26 @@ fn main() {
                         .default_value("100")
                         .help("super max borrow APY: min <= optimal <= max <= super_max"),
                 )
+                .arg(
+                    Arg::with_name("super_optimal_utilization_rate")
+                        .long("super-optimal-utilization-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Utilization rate of the kink between optimal and max utilization, 0 to disable: [optimal, max]"),
+                )
+                .arg(
+                    Arg::with_name("super_optimal_borrow_rate")
+                        .long("super-optimal-borrow-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Borrow APY at the super optimal utilization rate: optimal <= super_optimal <= max"),
+                )
                 .arg(
                     Arg::with_name("borrow_fee")
                         .long("borrow-fee")
@@ -701,6 +927,96 @@ fn main() {
                         .default_value("0")
                         .help("Close Attributed Borrow limit in USD"),
                 )
+                .arg(
+                    Arg::with_name("max_close_factor_bps")
+                        .long("max-close-factor-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("2000")
+                        .help("Close factor for deeply unhealthy obligations, in basis points"),
+                )
+                .arg(
+                    Arg::with_name("liquidation_price_band_bps")
+                        .long("liquidation-price-band-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Max deviation of spot price from EMA price allowed for liquidations, in basis points. 0 to disable"),
+                )
+                .arg(
+                    Arg::with_name("max_oracle_staleness_secs")
+                        .long("max-oracle-staleness-secs")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Max age of an oracle price before it is considered stale, in seconds. 0 to use the default"),
+                )
+                .arg(
+                    Arg::with_name("max_oracle_confidence_bps")
+                        .long("max-oracle-confidence-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Max oracle confidence interval as a fraction of price, in basis points. 0 to use the default"),
+                )
+                .arg(
+                    Arg::with_name("max_single_borrower_share_bps")
+                        .long("max-single-borrower-share-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Max share of the reserve's liquidity one obligation may borrow, in bps. 0 means no limit"),
+                )
+                .arg(
+                    Arg::with_name("min_borrow_value")
+                        .long("min-borrow-value")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Min value of an obligation's borrow from the reserve after a borrow, in USD. 0 means no minimum"),
+                )
+                .arg(
+                    Arg::with_name("max_collateral_value_contribution")
+                        .long("max-collateral-value-contribution")
+                        .validator(is_parsable::<u32>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Max borrowing power a deposit of the reserve's collateral adds to an obligation, in USD. 0 means no limit"),
+                )
+                .arg(
+                    Arg::with_name("min_price")
+                        .long("min-price")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Lowest price the reserve's liquidity is valued at whatever the oracles report, in ten-thousandths of USD. 0 means no bound"),
+                )
+                .arg(
+                    Arg::with_name("max_price")
+                        .long("max-price")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("Highest price the reserve's liquidity is valued at whatever the oracles report, in ten-thousandths of USD. 0 means no bound"),
+                )
         )
         .subcommand(
             SubCommand::with_name("set-lending-market-owner-and-config")
@@ -799,6 +1115,14 @@ fn main() {
                         .required(true)
                         .help("Lending market address"),
                 )
+                .arg(
+                    Arg::with_name("reserve_config")
+                        .long("reserve-config")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(false)
+                        .help("TOML file with the reserve config fields to update. Flags take precedence over the file"),
+                )
                 .arg(
                     Arg::with_name("optimal_utilization_rate")
                         .long("optimal-utilization-rate")
@@ -898,6 +1222,24 @@ fn main() {
                         .required(false)
                         .help("super max borrow APY: min <= optimal <= max <= super_max"),
                 )
+                .arg(
+                    Arg::with_name("super_optimal_utilization_rate")
+                        .long("super-optimal-utilization-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Utilization rate of the kink between optimal and max utilization, 0 to disable: [optimal, max]"),
+                )
+                .arg(
+                    Arg::with_name("super_optimal_borrow_rate")
+                        .long("super-optimal-borrow-rate")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Borrow APY at the super optimal utilization rate: optimal <= super_optimal <= max"),
+                )
                 .arg(
                     Arg::with_name("borrow_fee")
                         .long("borrow-fee")
@@ -1068,6 +1410,168 @@ fn main() {
                         .required(false)
                         .help("Close Attributed Borrow limit in USD"),
                 )
+                .arg(
+                    Arg::with_name("max_close_factor_bps")
+                        .long("max-close-factor-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Close factor for deeply unhealthy obligations, in basis points"),
+                )
+                .arg(
+                    Arg::with_name("liquidation_price_band_bps")
+                        .long("liquidation-price-band-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Max deviation of spot price from EMA price allowed for liquidations, in basis points. 0 to disable"),
+                )
+                .arg(
+                    Arg::with_name("max_oracle_staleness_secs")
+                        .long("max-oracle-staleness-secs")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Max age of an oracle price before it is considered stale, in seconds. 0 to use the default"),
+                )
+                .arg(
+                    Arg::with_name("max_oracle_confidence_bps")
+                        .long("max-oracle-confidence-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Max oracle confidence interval as a fraction of price, in basis points. 0 to use the default"),
+                )
+                .arg(
+                    Arg::with_name("max_single_borrower_share_bps")
+                        .long("max-single-borrower-share-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Max share of the reserve's liquidity one obligation may borrow, in bps. 0 means no limit"),
+                )
+                .arg(
+                    Arg::with_name("min_borrow_value")
+                        .long("min-borrow-value")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Min value of an obligation's borrow from the reserve after a borrow, in USD. 0 means no minimum"),
+                )
+                .arg(
+                    Arg::with_name("max_collateral_value_contribution")
+                        .long("max-collateral-value-contribution")
+                        .validator(is_parsable::<u32>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Max borrowing power a deposit of the reserve's collateral adds to an obligation, in USD. 0 means no limit"),
+                )
+                .arg(
+                    Arg::with_name("min_price")
+                        .long("min-price")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Lowest price the reserve's liquidity is valued at whatever the oracles report, in ten-thousandths of USD. 0 means no bound"),
+                )
+                .arg(
+                    Arg::with_name("max_price")
+                        .long("max-price")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Highest price the reserve's liquidity is valued at whatever the oracles report, in ten-thousandths of USD. 0 means no bound"),
+                )
+                .arg(
+                    Arg::with_name("deposits_paused")
+                        .long("deposits-paused")
+                        .validator(is_parsable::<bool>)
+                        .value_name("BOOL")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Reject deposits into the reserve"),
+                )
+                .arg(
+                    Arg::with_name("borrows_paused")
+                        .long("borrows-paused")
+                        .validator(is_parsable::<bool>)
+                        .value_name("BOOL")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Reject borrows and flash borrows from the reserve"),
+                )
+                .arg(
+                    Arg::with_name("withdrawals_paused")
+                        .long("withdrawals-paused")
+                        .validator(is_parsable::<bool>)
+                        .value_name("BOOL")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Reject collateral withdrawals and redemptions from the reserve"),
+                )
+                .arg(
+                    Arg::with_name("liquidation_auction")
+                        .long("liquidation-auction")
+                        .validator(is_parsable::<bool>)
+                        .value_name("BOOL")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Ramp the liquidation bonus up from 0 to the max liquidation bonus after obligations become unhealthy"),
+                )
+                .arg(
+                    Arg::with_name("protocol_liquidation_fee_in_liquidity")
+                        .long("protocol-liquidation-fee-in-liquidity")
+                        .validator(is_parsable::<bool>)
+                        .value_name("BOOL")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Take the protocol liquidation fee in the repaid liquidity, on top of the repay amount, instead of out of the collateral bonus"),
+                )
+                .arg(
+                    Arg::with_name("asset_category")
+                        .long("asset-category")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Asset category of the reserve in the lending market's asset categories, 0 for none"),
+                )
+                .arg(
+                    Arg::with_name("borrow_rate_cap")
+                        .long("borrow-rate-cap")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Borrow APR above which borrows with a rate cap have their interest paid by the insurance fund, 0 to not offer rate caps"),
+                )
+                .arg(
+                    Arg::with_name("rate_cap_premium_bps")
+                        .long("rate-cap-premium-bps")
+                        .validator(is_parsable::<u16>)
+                        .value_name("INTEGER_BPS")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Upfront premium for a rate cap, in basis points of the borrowed amount it covers"),
+                )
+                .arg(
+                    Arg::with_name("max_flash_loan_amount")
+                        .long("max-flash-loan-amount")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required(false)
+                        .help("Max liquidity the flash borrows of a transaction can take combined, 0 for no cap"),
+                )
         )
         .get_matches();
 
@@ -1125,7 +1629,7 @@ fn main() {
         ("view-obligation", Some(arg_matches)) => {
             let obligation = pubkey_of(arg_matches, "obligation").unwrap();
             let data = config.rpc_client.get_account_data(&obligation).unwrap();
-            print!("{:#?}", Obligation::unpack(&data));
+            print!("{:#?}", Obligation::unpack_account(&data));
 
             Ok(())
         }
@@ -1203,6 +1707,7 @@ fn main() {
             let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
             let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
             let ui_amount = value_of(arg_matches, "liquidity_amount").unwrap();
+            let collateral_decimals = value_of(arg_matches, "collateral_decimals");
             let pyth_product_pubkey = pubkey_of(arg_matches, "pyth_product").unwrap();
             let pyth_price_pubkey = pubkey_of(arg_matches, "pyth_price").unwrap();
             let switchboard_feed_pubkey = pubkey_of(arg_matches, "switchboard_feed").unwrap();
@@ -1219,6 +1724,10 @@ fn main() {
             let optimal_borrow_rate = value_of(arg_matches, "optimal_borrow_rate").unwrap();
             let max_borrow_rate = value_of(arg_matches, "max_borrow_rate").unwrap();
             let super_max_borrow_rate = value_of(arg_matches, "super_max_borrow_rate").unwrap();
+            let super_optimal_utilization_rate =
+                value_of(arg_matches, "super_optimal_utilization_rate").unwrap();
+            let super_optimal_borrow_rate =
+                value_of(arg_matches, "super_optimal_borrow_rate").unwrap();
             let borrow_fee = value_of::<f64>(arg_matches, "borrow_fee").unwrap();
             let flash_loan_fee = value_of::<f64>(arg_matches, "flash_loan_fee").unwrap();
             let host_fee_percentage = value_of(arg_matches, "host_fee_percentage").unwrap();
@@ -1233,6 +1742,20 @@ fn main() {
                 value_of(arg_matches, "attributed_borrow_limit_open").unwrap();
             let attributed_borrow_limit_close =
                 value_of(arg_matches, "attributed_borrow_limit_close").unwrap();
+            let max_close_factor_bps = value_of(arg_matches, "max_close_factor_bps").unwrap();
+            let liquidation_price_band_bps =
+                value_of(arg_matches, "liquidation_price_band_bps").unwrap();
+            let max_oracle_staleness_secs =
+                value_of(arg_matches, "max_oracle_staleness_secs").unwrap();
+            let max_oracle_confidence_bps =
+                value_of(arg_matches, "max_oracle_confidence_bps").unwrap();
+            let max_single_borrower_share_bps =
+                value_of(arg_matches, "max_single_borrower_share_bps").unwrap();
+            let min_borrow_value = value_of(arg_matches, "min_borrow_value").unwrap();
+            let max_collateral_value_contribution =
+                value_of(arg_matches, "max_collateral_value_contribution").unwrap();
+            let min_price = value_of(arg_matches, "min_price").unwrap();
+            let max_price = value_of(arg_matches, "max_price").unwrap();
 
             let borrow_fee_wad = (borrow_fee * WAD as f64) as u64;
             let flash_loan_fee_wad = (flash_loan_fee * WAD as f64) as u64;
@@ -1274,6 +1797,8 @@ fn main() {
                     optimal_borrow_rate,
                     max_borrow_rate,
                     super_max_borrow_rate,
+                    super_optimal_utilization_rate,
+                    super_optimal_borrow_rate,
                     fees: ReserveFees {
                         borrow_fee_wad,
                         flash_loan_fee_wad,
@@ -1290,6 +1815,24 @@ fn main() {
                     extra_oracle_pubkey,
                     attributed_borrow_limit_open,
                     attributed_borrow_limit_close,
+                    max_close_factor_bps,
+                    liquidation_price_band_bps,
+                    max_oracle_staleness_secs,
+                    max_oracle_confidence_bps,
+                    max_single_borrower_share_bps,
+                    deposits_paused: false,
+                    borrows_paused: false,
+                    withdrawals_paused: false,
+                    min_borrow_value,
+                    max_collateral_value_contribution,
+                    min_price,
+                    max_price,
+                    liquidation_auction: false,
+                    protocol_liquidation_fee_in_liquidity: false,
+                    asset_category: 0,
+                    borrow_rate_cap: 0,
+                    rate_cap_premium_bps: 0,
+                    max_flash_loan_amount: 0,
                 },
                 source_liquidity_pubkey,
                 source_liquidity_owner_keypair,
@@ -1300,6 +1843,7 @@ fn main() {
                 switchboard_feed_pubkey,
                 liquidity_fee_receiver_keypair,
                 source_liquidity,
+                collateral_decimals,
             )
         }
         ("set-lending-market-owner-and-config", Some(arg_matches)) => {
@@ -1340,6 +1884,9 @@ fn main() {
             let optimal_borrow_rate = value_of(arg_matches, "optimal_borrow_rate");
             let max_borrow_rate = value_of(arg_matches, "max_borrow_rate");
             let super_max_borrow_rate = value_of(arg_matches, "super_max_borrow_rate");
+            let super_optimal_utilization_rate =
+                value_of(arg_matches, "super_optimal_utilization_rate");
+            let super_optimal_borrow_rate = value_of(arg_matches, "super_optimal_borrow_rate");
             let borrow_fee = value_of::<f64>(arg_matches, "borrow_fee");
             let flash_loan_fee = value_of::<f64>(arg_matches, "flash_loan_fee");
             let host_fee_percentage = value_of(arg_matches, "host_fee_percentage");
@@ -1362,54 +1909,107 @@ fn main() {
                 value_of(arg_matches, "attributed_borrow_limit_open");
             let attributed_borrow_limit_close =
                 value_of(arg_matches, "attributed_borrow_limit_close");
+            let max_close_factor_bps = value_of(arg_matches, "max_close_factor_bps");
+            let liquidation_price_band_bps = value_of(arg_matches, "liquidation_price_band_bps");
+            let max_oracle_staleness_secs = value_of(arg_matches, "max_oracle_staleness_secs");
+            let max_oracle_confidence_bps = value_of(arg_matches, "max_oracle_confidence_bps");
+            let max_single_borrower_share_bps =
+                value_of(arg_matches, "max_single_borrower_share_bps");
+            let deposits_paused = value_of(arg_matches, "deposits_paused");
+            let borrows_paused = value_of(arg_matches, "borrows_paused");
+            let withdrawals_paused = value_of(arg_matches, "withdrawals_paused");
+            let min_borrow_value = value_of(arg_matches, "min_borrow_value");
+            let max_collateral_value_contribution =
+                value_of(arg_matches, "max_collateral_value_contribution");
+            let min_price = value_of(arg_matches, "min_price");
+            let max_price = value_of(arg_matches, "max_price");
+            let liquidation_auction = value_of(arg_matches, "liquidation_auction");
+            let protocol_liquidation_fee_in_liquidity =
+                value_of(arg_matches, "protocol_liquidation_fee_in_liquidity");
+            let asset_category = value_of(arg_matches, "asset_category");
+            let borrow_rate_cap = value_of(arg_matches, "borrow_rate_cap");
+            let rate_cap_premium_bps = value_of(arg_matches, "rate_cap_premium_bps");
+            let max_flash_loan_amount = value_of(arg_matches, "max_flash_loan_amount");
 
             let borrow_fee_wad = borrow_fee.map(|fee| (fee * WAD as f64) as u64);
             let flash_loan_fee_wad = flash_loan_fee.map(|fee| (fee * WAD as f64) as u64);
 
-            command_update_reserve(
-                &mut config,
-                PartialReserveConfig {
-                    optimal_utilization_rate,
-                    max_utilization_rate,
-                    loan_to_value_ratio,
-                    liquidation_bonus,
-                    max_liquidation_bonus,
-                    liquidation_threshold,
-                    max_liquidation_threshold,
-                    min_borrow_rate,
-                    optimal_borrow_rate,
-                    max_borrow_rate,
-                    super_max_borrow_rate,
-                    fees: PartialReserveFees {
-                        borrow_fee_wad,
-                        flash_loan_fee_wad,
-                        host_fee_percentage,
-                    },
-                    deposit_limit,
-                    borrow_limit,
-                    fee_receiver,
-                    protocol_liquidation_fee,
-                    protocol_take_rate,
-                    rate_limiter_window_duration,
-                    rate_limiter_max_outflow,
-                    added_borrow_weight_bps,
-                    reserve_type,
-                    scaled_price_offset_bps,
-                    extra_oracle_pubkey: if arg_matches.is_present("extra_oracle_pubkey") {
-                        Some(extra_oracle_pubkey)
-                    } else {
-                        None
-                    },
-                    attributed_borrow_limit_open,
-                    attributed_borrow_limit_close,
-                },
-                pyth_product_pubkey,
-                pyth_price_pubkey,
-                switchboard_feed_pubkey,
-                reserve_pubkey,
-                lending_market_pubkey,
-                lending_market_owner_keypair,
-            )
+            let reserve_config_file = match arg_matches.value_of("reserve_config") {
+                Some(path) => read_reserve_config_file(path),
+                None => Ok(PartialReserveConfig::default()),
+            };
+
+            reserve_config_file.and_then(|reserve_config_file| {
+                command_update_reserve(
+                    &mut config,
+                    PartialReserveConfig {
+                        optimal_utilization_rate,
+                        max_utilization_rate,
+                        loan_to_value_ratio,
+                        liquidation_bonus,
+                        max_liquidation_bonus,
+                        liquidation_threshold,
+                        max_liquidation_threshold,
+                        min_borrow_rate,
+                        optimal_borrow_rate,
+                        max_borrow_rate,
+                        super_max_borrow_rate,
+                        super_optimal_utilization_rate,
+                        super_optimal_borrow_rate,
+                        fees: PartialReserveFees {
+                            borrow_fee_wad,
+                            flash_loan_fee_wad,
+                            host_fee_percentage,
+                        },
+                        deposit_limit,
+                        borrow_limit,
+                        fee_receiver,
+                        protocol_liquidation_fee,
+                        protocol_take_rate,
+                        rate_limiter_window_duration,
+                        rate_limiter_max_outflow,
+                        added_borrow_weight_bps,
+                        reserve_type,
+                        scaled_price_offset_bps,
+                        extra_oracle_pubkey: if arg_matches.is_present("extra_oracle_pubkey") {
+                            Some(extra_oracle_pubkey)
+                        } else {
+                            None
+                        },
+                        attributed_borrow_limit_open,
+                        attributed_borrow_limit_close,
+                        max_close_factor_bps,
+                        liquidation_price_band_bps,
+                        max_oracle_staleness_secs,
+                        max_oracle_confidence_bps,
+                        max_single_borrower_share_bps,
+                        deposits_paused,
+                        borrows_paused,
+                        withdrawals_paused,
+                        min_borrow_value,
+                        max_collateral_value_contribution,
+                        min_price,
+                        max_price,
+                        liquidation_auction,
+                        protocol_liquidation_fee_in_liquidity,
+                        asset_category,
+                        borrow_rate_cap,
+                        rate_cap_premium_bps,
+                        max_flash_loan_amount,
+                    }
+                    .or(reserve_config_file),
+                    pyth_product_pubkey,
+                    pyth_price_pubkey,
+                    switchboard_feed_pubkey,
+                    reserve_pubkey,
+                    lending_market_pubkey,
+                    lending_market_owner_keypair,
+                )
+            })
+        }
+        ("redeem-fees", Some(arg_matches)) => {
+            let reserve_pubkey = pubkey_of(arg_matches, "reserve").unwrap();
+            command_redeem_fees(&config, &reserve_pubkey)
         }
         _ => unreachable!(),
     }
@@ -1541,6 +2141,77 @@ fn command_redeem_collateral(
     Ok(())
 }
 
+fn command_redeem_fees(config: &Config, reserve_pubkey: &Pubkey) -> CommandResult {
+    let reserve = {
+        let data = config.rpc_client.get_account(reserve_pubkey)?;
+        Reserve::unpack(&data.data)?
+    };
+    let reserve_spread_fee_receiver_pubkey =
+        find_reserve_spread_fee_receiver_address(&config.lending_program_id, reserve_pubkey).0;
+    let spread_fee_receiver = if reserve.has_spread_fee_receiver {
+        let data = config
+            .rpc_client
+            .get_account(&reserve_spread_fee_receiver_pubkey)?;
+        ReserveSpreadFeeReceiver::unpack(&data.data)?.spread_fee_receiver
+    } else {
+        reserve.config.fee_receiver
+    };
+    println!(
+        "Redeeming {} of protocol fees to {}",
+        reserve.calculate_redeem_fees()?,
+        spread_fee_receiver
+    );
+
+    let mut redeem_fees_instruction = if reserve.has_insurance_fund {
+        redeem_fees_with_insurance_fund(
+            config.lending_program_id,
+            *reserve_pubkey,
+            reserve.config.fee_receiver,
+            reserve.liquidity.supply_pubkey,
+            reserve.liquidity.mint_pubkey,
+            reserve.lending_market,
+        )
+    } else {
+        redeem_fees(
+            config.lending_program_id,
+            *reserve_pubkey,
+            reserve.config.fee_receiver,
+            reserve.liquidity.supply_pubkey,
+            reserve.lending_market,
+        )
+    };
+    if reserve.has_spread_fee_receiver {
+        redeem_fees_instruction.accounts.extend([
+            AccountMeta::new_readonly(reserve_spread_fee_receiver_pubkey, false),
+            AccountMeta::new(spread_fee_receiver, false),
+        ]);
+    }
+
+    let recent_blockhash = config.rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new(
+        &vec![config.fee_payer.as_ref()],
+        Message::new_with_blockhash(
+            &[
+                refresh_reserve(
+                    config.lending_program_id,
+                    *reserve_pubkey,
+                    reserve.liquidity.pyth_oracle_pubkey,
+                    reserve.liquidity.switchboard_oracle_pubkey,
+                    reserve.config.extra_oracle_pubkey,
+                ),
+                redeem_fees_instruction,
+            ],
+            Some(&config.fee_payer.pubkey()),
+            &recent_blockhash,
+        ),
+        recent_blockhash,
+    );
+
+    send_transaction(config, transaction)?;
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn command_withdraw_collateral(
     config: &Config,
@@ -1590,7 +2261,7 @@ fn command_liquidate_obligation(
 ) -> CommandResult {
     let obligation_state = {
         let data = config.rpc_client.get_account(&obligation_pubkey)?;
-        Obligation::unpack(&data.data)?
+        Obligation::unpack_account(&data.data)?
     };
 
     // get reserve pubkeys
@@ -1721,6 +2392,7 @@ fn command_add_reserve(
     switchboard_feed_pubkey: Pubkey,
     liquidity_fee_receiver_keypair: Keypair,
     source_liquidity: Token,
+    collateral_decimals: Option<u8>,
 ) -> CommandResult {
     let reserve_keypair = Keypair::new();
     let collateral_mint_keypair = Keypair::new();
@@ -1846,7 +2518,7 @@ fn command_add_reserve(
                 liquidity_amount,
             )
             .unwrap(),
-            init_reserve(
+            init_reserve_with_collateral_decimals(
                 config.lending_program_id,
                 liquidity_amount,
                 reserve_config,
@@ -1863,6 +2535,7 @@ fn command_add_reserve(
                 lending_market_pubkey,
                 lending_market_owner_keypair.pubkey(),
                 user_transfer_authority_keypair.pubkey(),
+                collateral_decimals,
             ),
             revoke(
                 &spl_token::id(),
@@ -2123,6 +2796,34 @@ fn command_update_reserve(
         reserve.config.super_max_borrow_rate = reserve_config.super_max_borrow_rate.unwrap();
     }
 
+    if reserve_config.super_optimal_utilization_rate.is_some()
+        && reserve.config.super_optimal_utilization_rate
+            != reserve_config.super_optimal_utilization_rate.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating super_optimal_utilization_rate from {} to {}",
+            reserve.config.super_optimal_utilization_rate,
+            reserve_config.super_optimal_utilization_rate.unwrap(),
+        );
+        reserve.config.super_optimal_utilization_rate =
+            reserve_config.super_optimal_utilization_rate.unwrap();
+    }
+
+    if reserve_config.super_optimal_borrow_rate.is_some()
+        && reserve.config.super_optimal_borrow_rate
+            != reserve_config.super_optimal_borrow_rate.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating super_optimal_borrow_rate from {} to {}",
+            reserve.config.super_optimal_borrow_rate,
+            reserve_config.super_optimal_borrow_rate.unwrap(),
+        );
+        reserve.config.super_optimal_borrow_rate =
+            reserve_config.super_optimal_borrow_rate.unwrap();
+    }
+
     if reserve_config.fees.borrow_fee_wad.is_some()
         && reserve.config.fees.borrow_fee_wad != reserve_config.fees.borrow_fee_wad.unwrap()
     {
@@ -2357,6 +3058,241 @@ fn command_update_reserve(
             reserve_config.attributed_borrow_limit_close.unwrap();
     }
 
+    if reserve_config.max_close_factor_bps.is_some()
+        && reserve.config.max_close_factor_bps != reserve_config.max_close_factor_bps.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_close_factor_bps from {} to {}",
+            reserve.config.max_close_factor_bps,
+            reserve_config.max_close_factor_bps.unwrap(),
+        );
+        reserve.config.max_close_factor_bps = reserve_config.max_close_factor_bps.unwrap();
+    }
+
+    if reserve_config.liquidation_price_band_bps.is_some()
+        && reserve.config.liquidation_price_band_bps
+            != reserve_config.liquidation_price_band_bps.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating liquidation_price_band_bps from {} to {}",
+            reserve.config.liquidation_price_band_bps,
+            reserve_config.liquidation_price_band_bps.unwrap(),
+        );
+        reserve.config.liquidation_price_band_bps =
+            reserve_config.liquidation_price_band_bps.unwrap();
+    }
+
+    if reserve_config.max_oracle_staleness_secs.is_some()
+        && reserve.config.max_oracle_staleness_secs
+            != reserve_config.max_oracle_staleness_secs.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_oracle_staleness_secs from {} to {}",
+            reserve.config.max_oracle_staleness_secs,
+            reserve_config.max_oracle_staleness_secs.unwrap(),
+        );
+        reserve.config.max_oracle_staleness_secs =
+            reserve_config.max_oracle_staleness_secs.unwrap();
+    }
+
+    if reserve_config.max_oracle_confidence_bps.is_some()
+        && reserve.config.max_oracle_confidence_bps
+            != reserve_config.max_oracle_confidence_bps.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_oracle_confidence_bps from {} to {}",
+            reserve.config.max_oracle_confidence_bps,
+            reserve_config.max_oracle_confidence_bps.unwrap(),
+        );
+        reserve.config.max_oracle_confidence_bps =
+            reserve_config.max_oracle_confidence_bps.unwrap();
+    }
+
+    if reserve_config.max_single_borrower_share_bps.is_some()
+        && reserve.config.max_single_borrower_share_bps
+            != reserve_config.max_single_borrower_share_bps.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_single_borrower_share_bps from {} to {}",
+            reserve.config.max_single_borrower_share_bps,
+            reserve_config.max_single_borrower_share_bps.unwrap(),
+        );
+        reserve.config.max_single_borrower_share_bps =
+            reserve_config.max_single_borrower_share_bps.unwrap();
+    }
+
+    if reserve_config.min_borrow_value.is_some()
+        && reserve.config.min_borrow_value != reserve_config.min_borrow_value.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating min_borrow_value from {} to {}",
+            reserve.config.min_borrow_value,
+            reserve_config.min_borrow_value.unwrap(),
+        );
+        reserve.config.min_borrow_value = reserve_config.min_borrow_value.unwrap();
+    }
+
+    if reserve_config.max_collateral_value_contribution.is_some()
+        && reserve.config.max_collateral_value_contribution
+            != reserve_config.max_collateral_value_contribution.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_collateral_value_contribution from {} to {}",
+            reserve.config.max_collateral_value_contribution,
+            reserve_config.max_collateral_value_contribution.unwrap(),
+        );
+        reserve.config.max_collateral_value_contribution =
+            reserve_config.max_collateral_value_contribution.unwrap();
+    }
+
+    if reserve_config.min_price.is_some()
+        && reserve.config.min_price != reserve_config.min_price.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating min_price from {} to {}",
+            reserve.config.min_price,
+            reserve_config.min_price.unwrap(),
+        );
+        reserve.config.min_price = reserve_config.min_price.unwrap();
+    }
+
+    if reserve_config.max_price.is_some()
+        && reserve.config.max_price != reserve_config.max_price.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_price from {} to {}",
+            reserve.config.max_price,
+            reserve_config.max_price.unwrap(),
+        );
+        reserve.config.max_price = reserve_config.max_price.unwrap();
+    }
+
+    if reserve_config.deposits_paused.is_some()
+        && reserve.config.deposits_paused != reserve_config.deposits_paused.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating deposits_paused from {} to {}",
+            reserve.config.deposits_paused,
+            reserve_config.deposits_paused.unwrap(),
+        );
+        reserve.config.deposits_paused = reserve_config.deposits_paused.unwrap();
+    }
+
+    if reserve_config.borrows_paused.is_some()
+        && reserve.config.borrows_paused != reserve_config.borrows_paused.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating borrows_paused from {} to {}",
+            reserve.config.borrows_paused,
+            reserve_config.borrows_paused.unwrap(),
+        );
+        reserve.config.borrows_paused = reserve_config.borrows_paused.unwrap();
+    }
+
+    if reserve_config.withdrawals_paused.is_some()
+        && reserve.config.withdrawals_paused != reserve_config.withdrawals_paused.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating withdrawals_paused from {} to {}",
+            reserve.config.withdrawals_paused,
+            reserve_config.withdrawals_paused.unwrap(),
+        );
+        reserve.config.withdrawals_paused = reserve_config.withdrawals_paused.unwrap();
+    }
+
+    if reserve_config.liquidation_auction.is_some()
+        && reserve.config.liquidation_auction != reserve_config.liquidation_auction.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating liquidation_auction from {} to {}",
+            reserve.config.liquidation_auction,
+            reserve_config.liquidation_auction.unwrap(),
+        );
+        reserve.config.liquidation_auction = reserve_config.liquidation_auction.unwrap();
+    }
+
+    if reserve_config
+        .protocol_liquidation_fee_in_liquidity
+        .is_some()
+        && reserve.config.protocol_liquidation_fee_in_liquidity
+            != reserve_config
+                .protocol_liquidation_fee_in_liquidity
+                .unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating protocol_liquidation_fee_in_liquidity from {} to {}",
+            reserve.config.protocol_liquidation_fee_in_liquidity,
+            reserve_config
+                .protocol_liquidation_fee_in_liquidity
+                .unwrap(),
+        );
+        reserve.config.protocol_liquidation_fee_in_liquidity = reserve_config
+            .protocol_liquidation_fee_in_liquidity
+            .unwrap();
+    }
+
+    if reserve_config.asset_category.is_some()
+        && reserve.config.asset_category != reserve_config.asset_category.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating asset_category from {} to {}",
+            reserve.config.asset_category,
+            reserve_config.asset_category.unwrap(),
+        );
+        reserve.config.asset_category = reserve_config.asset_category.unwrap();
+    }
+
+    if reserve_config.borrow_rate_cap.is_some()
+        && reserve.config.borrow_rate_cap != reserve_config.borrow_rate_cap.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating borrow_rate_cap from {} to {}",
+            reserve.config.borrow_rate_cap,
+            reserve_config.borrow_rate_cap.unwrap(),
+        );
+        reserve.config.borrow_rate_cap = reserve_config.borrow_rate_cap.unwrap();
+    }
+
+    if reserve_config.rate_cap_premium_bps.is_some()
+        && reserve.config.rate_cap_premium_bps != reserve_config.rate_cap_premium_bps.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating rate_cap_premium_bps from {} to {}",
+            reserve.config.rate_cap_premium_bps,
+            reserve_config.rate_cap_premium_bps.unwrap(),
+        );
+        reserve.config.rate_cap_premium_bps = reserve_config.rate_cap_premium_bps.unwrap();
+    }
+
+    if reserve_config.max_flash_loan_amount.is_some()
+        && reserve.config.max_flash_loan_amount != reserve_config.max_flash_loan_amount.unwrap()
+    {
+        no_change = false;
+        println!(
+            "Updating max_flash_loan_amount from {} to {}",
+            reserve.config.max_flash_loan_amount,
+            reserve_config.max_flash_loan_amount.unwrap(),
+        );
+        reserve.config.max_flash_loan_amount = reserve_config.max_flash_loan_amount.unwrap();
+    }
+
     if validate_reserve_config(reserve.config).is_err() {
         println!("Error: invalid reserve config");
         return Err("Error: invalid reserve config".into());
@@ -2418,6 +3354,7 @@ fn check_fee_payer_balance(config: &Config, required_balance: u64) -> Result<(),
     }
 }
 
+#[allow(clippy::result_large_err)]
 fn send_transaction_to_jito(
     config: &Config,
     ixes: Vec<Vec<Instruction>>,
@@ -2496,6 +3433,7 @@ fn send_transaction_to_jito(
     }
     Ok(())
 }
+#[allow(clippy::result_large_err)]
 fn send_transactions_to_jito(
     config: &Config,
     transactions: &mut Vec<Transaction>,
@@ -2567,6 +3505,7 @@ fn send_transactions_to_jito(
     Ok(())
 }
 
+#[allow(clippy::result_large_err)]
 fn send_transaction(
     config: &Config,
     transaction: Transaction,
@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_program::program_pack::Pack;
+use solend_sdk::state::{
+    AccountTag, EmergencyPrice, LendingMarket, Obligation, ObligationCounter, Reserve,
+    ReserveRateStats, RewardRule,
+};
+
+// classifies the data the way clients scanning program accounts do, then unpacks it as the
+// type it was classified as
+fuzz_target!(|data: &[u8]| {
+    let _ = match AccountTag::from_account_data(data) {
+        Some(AccountTag::LendingMarket) => LendingMarket::unpack(data).map(drop),
+        Some(AccountTag::Reserve) => Reserve::unpack(data).map(drop),
+        Some(AccountTag::Obligation) => Obligation::unpack_account(data).map(drop),
+        Some(AccountTag::RewardRule) => RewardRule::unpack(data).map(drop),
+        Some(AccountTag::EmergencyPrice) => EmergencyPrice::unpack(data).map(drop),
+        Some(AccountTag::ObligationCounter) => ObligationCounter::unpack(data).map(drop),
+        Some(AccountTag::ReserveRateStats) => ReserveRateStats::unpack(data).map(drop),
+        _ => return,
+    };
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solend_sdk::instruction::LendingInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(instruction) = LendingInstruction::unpack(data) {
+        // trailing bytes are ignored, so only the repacked form has to round trip
+        let packed = instruction.pack();
+        assert_eq!(LendingInstruction::unpack(&packed).unwrap(), instruction);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solend_sdk::state::Obligation;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(obligation) = Obligation::unpack_account(data) {
+        let mut packed = vec![0u8; data.len()];
+        Obligation::pack_account(obligation.clone(), &mut packed).unwrap();
+        assert_eq!(Obligation::unpack_account(&packed).unwrap(), obligation);
+    }
+});
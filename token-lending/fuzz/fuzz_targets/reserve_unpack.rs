@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_program::program_pack::Pack;
+use solend_sdk::state::Reserve;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(reserve) = Reserve::unpack(data) {
+        let mut packed = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve.clone(), &mut packed).unwrap();
+        assert_eq!(Reserve::unpack(&packed).unwrap(), reserve);
+    }
+});
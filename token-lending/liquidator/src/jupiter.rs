@@ -0,0 +1,137 @@
+use crate::Error;
+
+use serde_json::Value;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+
+pub const DEFAULT_JUPITER_URL: &str = "https://quote-api.jup.ag/v6";
+
+/// Best route for a swap, as returned by the quote API
+pub struct Quote {
+    pub out_amount: u64,
+    response: Value,
+}
+
+/// Instructions of a Jupiter swap, to be sent in a versioned transaction with the lookup tables
+pub struct Swap {
+    pub instructions: Vec<Instruction>,
+    pub address_lookup_table_addresses: Vec<Pubkey>,
+}
+
+pub struct Jupiter {
+    client: reqwest::blocking::Client,
+    url: String,
+}
+
+impl Jupiter {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::blocking::Client::new(),
+            url,
+        }
+    }
+
+    /// Best route for swapping `amount` of `input_mint` into `output_mint`
+    pub fn quote(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> Result<Quote, Error> {
+        let response: Value = self
+            .client
+            .get(format!("{}/quote", self.url))
+            .query(&[
+                ("inputMint", input_mint.to_string()),
+                ("outputMint", output_mint.to_string()),
+                ("amount", amount.to_string()),
+                ("slippageBps", slippage_bps.to_string()),
+            ])
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        Ok(Quote {
+            out_amount: response["outAmount"]
+                .as_str()
+                .ok_or("Quote has no out amount")?
+                .parse()?,
+            response,
+        })
+    }
+
+    /// Instructions swapping the quoted amount out of `user`'s token accounts
+    pub fn swap(&self, quote: &Quote, user: &Pubkey) -> Result<Swap, Error> {
+        let response: Value = self
+            .client
+            .post(format!("{}/swap-instructions", self.url))
+            .json(&serde_json::json!({
+                "quoteResponse": quote.response,
+                "userPublicKey": user.to_string(),
+                "wrapAndUnwrapSol": true,
+            }))
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        let mut instructions = Vec::new();
+        for key in ["computeBudgetInstructions", "setupInstructions"] {
+            if let Some(values) = response[key].as_array() {
+                for value in values {
+                    instructions.push(parse_instruction(value)?);
+                }
+            }
+        }
+        instructions.push(parse_instruction(&response["swapInstruction"])?);
+        if !response["cleanupInstruction"].is_null() {
+            instructions.push(parse_instruction(&response["cleanupInstruction"])?);
+        }
+
+        let address_lookup_table_addresses = response["addressLookupTableAddresses"]
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|value| parse_pubkey(value))
+                    .collect::<Result<Vec<_>, Error>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Swap {
+            instructions,
+            address_lookup_table_addresses,
+        })
+    }
+}
+
+fn parse_pubkey(value: &Value) -> Result<Pubkey, Error> {
+    Ok(Pubkey::from_str(
+        value.as_str().ok_or("Expected a pubkey")?,
+    )?)
+}
+
+fn parse_instruction(value: &Value) -> Result<Instruction, Error> {
+    let accounts = value["accounts"]
+        .as_array()
+        .ok_or("Instruction has no accounts")?
+        .iter()
+        .map(|account| {
+            Ok(AccountMeta {
+                pubkey: parse_pubkey(&account["pubkey"])?,
+                is_signer: account["isSigner"].as_bool().unwrap_or(false),
+                is_writable: account["isWritable"].as_bool().unwrap_or(false),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Instruction {
+        program_id: parse_pubkey(&value["programId"])?,
+        accounts,
+        data: base64::decode(value["data"].as_str().ok_or("Instruction has no data")?)?,
+    })
+}
@@ -0,0 +1,413 @@
+use jupiter::{Jupiter, Swap, DEFAULT_JUPITER_URL};
+use market::{Liquidation, MarketState};
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_address_lookup_table_program::state::AddressLookupTable;
+use solana_client::rpc_config::{
+    RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig,
+};
+use solana_sdk::{
+    account::Account,
+    address_lookup_table_account::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    message::{v0, VersionedMessage},
+    transaction::VersionedTransaction,
+};
+
+mod jupiter;
+mod market;
+
+use {
+    clap::{crate_description, crate_name, crate_version, value_t, App, Arg},
+    solana_clap_utils::{
+        input_parsers::{pubkey_of, value_of},
+        input_validators::{is_parsable, is_pubkey, is_url, is_valid_signer},
+        keypair::signer_from_path,
+    },
+    solana_client::rpc_client::RpcClient,
+    solana_program::{program_pack::Pack, pubkey::Pubkey},
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        signature::{Signature, Signer},
+        transaction::Transaction,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account_idempotent,
+    },
+    spl_token::state::Account as Token,
+    std::{process::exit, thread::sleep, time::Duration},
+};
+
+pub struct Config {
+    rpc_client: RpcClient,
+    liquidator: Box<dyn Signer>,
+    lending_program_id: Pubkey,
+    lending_market: Option<Pubkey>,
+    jupiter: Option<Jupiter>,
+    slippage_bps: u16,
+    dry_run: bool,
+}
+
+type Error = Box<dyn std::error::Error>;
+type CommandResult = Result<(), Error>;
+
+/// Compute units requested for a liquidation, which refreshes up to every reserve of the
+/// obligation
+const LIQUIDATION_COMPUTE_UNITS: u32 = 1_000_000;
+
+fn main() {
+    solana_logger::setup_with_default("solana=info");
+
+    let default_lending_program_id: &str = &solend_sdk::solend_mainnet::id().to_string();
+
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .validator(is_url)
+                .help("JSON RPC URL for the cluster.  Default from the configuration file."),
+        )
+        .arg(
+            Arg::with_name("liquidator")
+                .long("liquidator")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .validator(is_valid_signer)
+                .help("Liquidator wallet, which pays fees and repays debt from its token accounts. Default from the configuration file."),
+        )
+        .arg(
+            Arg::with_name("lending_program_id")
+                .long("program")
+                .validator(is_pubkey)
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .default_value(default_lending_program_id)
+                .help("Lending program ID"),
+        )
+        .arg(
+            Arg::with_name("lending_market")
+                .long("market")
+                .validator(is_pubkey)
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .help("Only liquidate obligations of this lending market"),
+        )
+        .arg(
+            Arg::with_name("swap")
+                .long("swap")
+                .takes_value(false)
+                .help("Swap the redeemed collateral back to the repaid asset on Jupiter"),
+        )
+        .arg(
+            Arg::with_name("jupiter_url")
+                .long("jupiter-url")
+                .validator(is_url)
+                .value_name("URL")
+                .takes_value(true)
+                .default_value(DEFAULT_JUPITER_URL)
+                .help("Jupiter swap API"),
+        )
+        .arg(
+            Arg::with_name("slippage_bps")
+                .long("slippage-bps")
+                .validator(is_parsable::<u16>)
+                .value_name("BPS")
+                .takes_value(true)
+                .default_value("50")
+                .help("Max slippage of swaps, in basis points"),
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .validator(is_parsable::<u64>)
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value("10")
+                .help("Seconds to wait between scans"),
+        )
+        .arg(
+            Arg::with_name("once")
+                .long("once")
+                .takes_value(false)
+                .help("Scan once and exit"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .takes_value(false)
+                .help("Simulate liquidations and quote swaps instead of executing them"),
+        )
+        .get_matches();
+
+    let mut wallet_manager = None;
+    let config = {
+        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+            solana_cli_config::Config::load(config_file).unwrap_or_default()
+        } else {
+            solana_cli_config::Config::default()
+        };
+        let json_rpc_url = value_t!(matches, "json_rpc_url", String)
+            .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
+
+        let liquidator = signer_from_path(
+            &matches,
+            matches
+                .value_of("liquidator")
+                .unwrap_or(&cli_config.keypair_path),
+            "liquidator",
+            &mut wallet_manager,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            exit(1);
+        });
+
+        Config {
+            rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
+            liquidator,
+            lending_program_id: pubkey_of(&matches, "lending_program_id").unwrap(),
+            lending_market: pubkey_of(&matches, "lending_market"),
+            jupiter: matches
+                .is_present("swap")
+                .then(|| Jupiter::new(value_of(&matches, "jupiter_url").unwrap())),
+            slippage_bps: value_of(&matches, "slippage_bps").unwrap(),
+            dry_run: matches.is_present("dry_run"),
+        }
+    };
+    let interval = Duration::from_secs(value_of(&matches, "interval").unwrap());
+
+    loop {
+        if let Err(err) = scan(&config) {
+            eprintln!("error: {}", err);
+        }
+        if matches.is_present("once") {
+            break;
+        }
+        sleep(interval);
+    }
+}
+
+/// Check every obligation and liquidate the unhealthy ones
+fn scan(config: &Config) -> CommandResult {
+    let state = MarketState::load(config)?;
+    let obligations = state.load_obligations(config)?;
+    println!(
+        "Scanning {} obligations at slot {}",
+        obligations.len(),
+        state.slot
+    );
+
+    for (obligation_pubkey, obligation) in obligations {
+        let liquidation = match state.find_liquidation(
+            &config.liquidator.pubkey(),
+            obligation_pubkey,
+            obligation,
+        ) {
+            Ok(Some(liquidation)) => liquidation,
+            Ok(None) => continue,
+            Err(err) => {
+                eprintln!("Could not check obligation {}: {}", obligation_pubkey, err);
+                continue;
+            }
+        };
+
+        println!(
+            "Obligation {} is unhealthy, borrowed value {} with unhealthy borrow value {}",
+            obligation_pubkey, liquidation.borrowed_value, liquidation.unhealthy_borrow_value
+        );
+        if let Err(err) = liquidate(config, &state, &liquidation) {
+            eprintln!(
+                "Could not liquidate obligation {}: {}",
+                obligation_pubkey, err
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Repay as much of the obligation's largest borrow as the program allows, or as the liquidator
+/// holds, and redeem its largest deposit. The redeemed liquidity is then swapped back to the
+/// repaid asset if swaps are enabled.
+fn liquidate(config: &Config, state: &MarketState, liquidation: &Liquidation) -> CommandResult {
+    let liquidator = config.liquidator.pubkey();
+    let repay_reserve = state
+        .find_reserve(&liquidation.repay_reserve)
+        .ok_or("Repay reserve not found")?;
+    let withdraw_reserve = state
+        .find_reserve(&liquidation.withdraw_reserve)
+        .ok_or("Withdraw reserve not found")?;
+    let repay_mint = repay_reserve.liquidity.mint_pubkey;
+    let withdraw_mint = withdraw_reserve.liquidity.mint_pubkey;
+
+    let liquidity_amount = token_balance(
+        config,
+        &get_associated_token_address(&liquidator, &repay_mint),
+    )?;
+    if liquidity_amount == 0 {
+        println!("  skipping, liquidator holds no {} to repay", repay_mint);
+        return Ok(());
+    }
+
+    let mut instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(LIQUIDATION_COMPUTE_UNITS),
+        create_associated_token_account_idempotent(
+            &liquidator,
+            &liquidator,
+            &withdraw_reserve.collateral.mint_pubkey,
+            &spl_token::id(),
+        ),
+        create_associated_token_account_idempotent(
+            &liquidator,
+            &liquidator,
+            &withdraw_mint,
+            &spl_token::id(),
+        ),
+    ];
+    instructions.extend(state.refresh_instructions(
+        config.lending_program_id,
+        liquidation.obligation_pubkey,
+        &liquidation.obligation,
+    )?);
+    instructions.push(state.liquidate_instruction(
+        config.lending_program_id,
+        liquidator,
+        liquidation,
+        liquidity_amount,
+    )?);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&liquidator),
+        &vec![config.liquidator.as_ref()],
+        config.rpc_client.get_latest_blockhash()?,
+    );
+
+    let destination_liquidity = get_associated_token_address(&liquidator, &withdraw_mint);
+    let balance_before = token_balance(config, &destination_liquidity)?;
+    let balance_after = if config.dry_run {
+        let result = config
+            .rpc_client
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    accounts: Some(RpcSimulateTransactionAccountsConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        addresses: vec![destination_liquidity.to_string()],
+                    }),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )?
+            .value;
+        if let Some(err) = result.err {
+            return Err(format!(
+                "simulation failed: {}, logs: {:#?}",
+                err,
+                result.logs.unwrap_or_default()
+            )
+            .into());
+        }
+
+        result
+            .accounts
+            .and_then(|accounts| accounts.into_iter().next().flatten())
+            .and_then(|account| account.decode::<Account>())
+            .map(|account| Token::unpack(&account.data))
+            .transpose()?
+            .map_or(0, |token| token.amount)
+    } else {
+        let signature = config
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)?;
+        println!("  liquidated in {}", signature);
+
+        token_balance(config, &destination_liquidity)?
+    };
+    let redeemed_amount = balance_after.saturating_sub(balance_before);
+    println!(
+        "  repaid up to {} of {} for {} of {}",
+        liquidity_amount, repay_mint, redeemed_amount, withdraw_mint
+    );
+
+    match &config.jupiter {
+        Some(jupiter) if withdraw_mint != repay_mint && redeemed_amount > 0 => {
+            let quote = jupiter.quote(
+                &withdraw_mint,
+                &repay_mint,
+                redeemed_amount,
+                config.slippage_bps,
+            )?;
+            println!(
+                "  swapping {} of {} for {} of {}",
+                redeemed_amount, withdraw_mint, quote.out_amount, repay_mint
+            );
+            if !config.dry_run {
+                let signature = send_swap(config, jupiter.swap(&quote, &liquidator)?)?;
+                println!("  swapped in {}", signature);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Send a Jupiter swap in a versioned transaction using its address lookup tables
+fn send_swap(config: &Config, swap: Swap) -> Result<Signature, Error> {
+    let address_lookup_table_accounts = swap
+        .address_lookup_table_addresses
+        .iter()
+        .map(|key| {
+            let account = config.rpc_client.get_account(key)?;
+            let address_lookup_table = AddressLookupTable::deserialize(&account.data)?;
+            Ok(AddressLookupTableAccount {
+                key: *key,
+                addresses: address_lookup_table.addresses.to_vec(),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let message = v0::Message::try_compile(
+        &config.liquidator.pubkey(),
+        &swap.instructions,
+        &address_lookup_table_accounts,
+        config.rpc_client.get_latest_blockhash()?,
+    )?;
+    let transaction = VersionedTransaction::try_new(
+        VersionedMessage::V0(message),
+        &vec![config.liquidator.as_ref()],
+    )?;
+
+    Ok(config
+        .rpc_client
+        .send_and_confirm_transaction(&transaction)?)
+}
+
+/// Balance of a token account, zero if it doesn't exist yet
+fn token_balance(config: &Config, token_account: &Pubkey) -> Result<u64, Error> {
+    match config
+        .rpc_client
+        .get_account_with_commitment(token_account, config.rpc_client.commitment())?
+        .value
+    {
+        Some(account) => Ok(Token::unpack(&account.data)?.amount),
+        None => Ok(0),
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{Config, Error};
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::RpcFilterType,
+};
+use solana_program::{
+    clock::Slot,
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_sdk::account::Account;
+use solend_sdk::{
+    instruction::{
+        liquidate_obligation_and_redeem_reserve_collateral, refresh_obligation,
+        refresh_obligation_with_asset_categories, refresh_obligation_with_pair_borrow_weights,
+        refresh_reserve, refresh_reserve_with_tertiary_oracle,
+    },
+    math::Decimal,
+    state::{
+        find_liquidator_whitelist_address, lending_market_filters, obligation_filters,
+        reserve_filters, shared_asset_category, simulate_refresh_obligation, AssetCategories,
+        LendingMarket, Obligation, Reserve, ReservePairBorrowWeights,
+    },
+};
+use spl_associated_token_account::get_associated_token_address;
+
+/// Lending markets, reserves and the reserve accounts needed to refresh them, as of `slot`
+pub struct MarketState {
+    pub slot: Slot,
+    pub lending_markets: Vec<(Pubkey, LendingMarket)>,
+    pub reserves: Vec<(Pubkey, Reserve)>,
+    pub pair_borrow_weights: Vec<ReservePairBorrowWeights>,
+    pub asset_categories: Vec<AssetCategories>,
+}
+
+/// Unhealthy obligation and the borrow and deposit picked to liquidate
+pub struct Liquidation {
+    pub obligation_pubkey: Pubkey,
+    /// Obligation as stored on chain, which is what the refresh instructions are built from
+    pub obligation: Obligation,
+    pub borrowed_value: Decimal,
+    pub unhealthy_borrow_value: Decimal,
+    pub repay_reserve: Pubkey,
+    pub withdraw_reserve: Pubkey,
+}
+
+impl MarketState {
+    /// Load every lending market of the program, or only `config.lending_market` if set
+    pub fn load(config: &Config) -> Result<Self, Error> {
+        let slot = config.rpc_client.get_slot()?;
+
+        let lending_markets = program_accounts(config, lending_market_filters(None))?
+            .into_iter()
+            .filter(|(pubkey, _)| {
+                config.lending_market.is_none() || config.lending_market == Some(*pubkey)
+            })
+            .map(|(pubkey, account)| Ok((pubkey, LendingMarket::unpack(&account.data)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let reserves = program_accounts(config, reserve_filters(config.lending_market.as_ref()))?
+            .into_iter()
+            .map(|(pubkey, account)| Ok((pubkey, Reserve::unpack(&account.data)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let pair_borrow_weights = program_accounts(
+            config,
+            vec![RpcFilterType::DataSize(
+                ReservePairBorrowWeights::LEN as u64,
+            )],
+        )?
+        .into_iter()
+        .map(|(_, account)| Ok(ReservePairBorrowWeights::unpack(&account.data)?))
+        .collect::<Result<Vec<_>, Error>>()?;
+        let asset_categories = program_accounts(
+            config,
+            vec![RpcFilterType::DataSize(AssetCategories::LEN as u64)],
+        )?
+        .into_iter()
+        .map(|(_, account)| Ok(AssetCategories::unpack(&account.data)?))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self {
+            slot,
+            lending_markets,
+            reserves,
+            pair_borrow_weights,
+            asset_categories,
+        })
+    }
+
+    /// Load the obligations of the markets, of both the standard and the extended size
+    pub fn load_obligations(&self, config: &Config) -> Result<Vec<(Pubkey, Obligation)>, Error> {
+        let mut obligations = Vec::new();
+        for filters in obligation_filters(config.lending_market.as_ref(), None) {
+            for (pubkey, account) in program_accounts(config, filters)? {
+                obligations.push((pubkey, Obligation::unpack_account(&account.data)?));
+            }
+        }
+        Ok(obligations)
+    }
+
+    pub fn find_lending_market(&self, pubkey: &Pubkey) -> Option<&LendingMarket> {
+        self.lending_markets
+            .iter()
+            .find(|(p, _)| p == pubkey)
+            .map(|(_, lending_market)| lending_market)
+    }
+
+    pub fn find_reserve(&self, pubkey: &Pubkey) -> Option<&Reserve> {
+        self.reserves
+            .iter()
+            .find(|(p, _)| p == pubkey)
+            .map(|(_, reserve)| reserve)
+    }
+
+    /// Refresh the obligation with the SDK's copy of the on-chain math at the cached reserve
+    /// prices, and pick its largest borrow and deposit if it can be liquidated. Obligations of
+    /// markets that only allow another liquidator are left alone.
+    pub fn find_liquidation(
+        &self,
+        liquidator: &Pubkey,
+        obligation_pubkey: Pubkey,
+        obligation: Obligation,
+    ) -> Result<Option<Liquidation>, Error> {
+        if obligation.borrows.is_empty() || obligation.deposits.is_empty() {
+            return Ok(None);
+        }
+        match self
+            .find_lending_market(&obligation.lending_market)
+            .and_then(|lending_market| lending_market.whitelisted_liquidator)
+        {
+            Some(whitelisted_liquidator) if whitelisted_liquidator != *liquidator => {
+                return Ok(None)
+            }
+            _ => (),
+        }
+
+        let refreshed = simulate_refresh_obligation(
+            &obligation,
+            &self.reserves,
+            &self.pair_borrow_weights,
+            self.asset_categories.iter().find(|asset_categories| {
+                asset_categories.lending_market == obligation.lending_market
+            }),
+            self.slot,
+        )?;
+        if refreshed.borrowed_value == Decimal::zero()
+            || (refreshed.borrowed_value < refreshed.unhealthy_borrow_value && !refreshed.closeable)
+        {
+            return Ok(None);
+        }
+
+        let repay_reserve = refreshed
+            .borrows
+            .iter()
+            .max_by(|a, b| a.market_value.cmp(&b.market_value))
+            .map(|liquidity| liquidity.borrow_reserve);
+        let withdraw_reserve = refreshed
+            .deposits
+            .iter()
+            .max_by(|a, b| a.market_value.cmp(&b.market_value))
+            .map(|collateral| collateral.deposit_reserve);
+
+        Ok(match (repay_reserve, withdraw_reserve) {
+            (Some(repay_reserve), Some(withdraw_reserve)) => Some(Liquidation {
+                obligation_pubkey,
+                obligation,
+                borrowed_value: refreshed.borrowed_value,
+                unhealthy_borrow_value: refreshed.unhealthy_borrow_value,
+                repay_reserve,
+                withdraw_reserve,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Refresh every reserve of the obligation, then the obligation itself
+    pub fn refresh_instructions(
+        &self,
+        lending_program_id: Pubkey,
+        obligation_pubkey: Pubkey,
+        obligation: &Obligation,
+    ) -> Result<Vec<Instruction>, Error> {
+        let reserve_pubkeys: Vec<Pubkey> = obligation
+            .deposits
+            .iter()
+            .map(|collateral| collateral.deposit_reserve)
+            .chain(
+                obligation
+                    .borrows
+                    .iter()
+                    .map(|liquidity| liquidity.borrow_reserve),
+            )
+            .collect();
+
+        let pair_borrow_weights_reserve_pubkeys: Vec<Pubkey> = obligation
+            .borrows
+            .iter()
+            .map(|liquidity| liquidity.borrow_reserve)
+            .filter(|reserve_pubkey| {
+                matches!(
+                    self.find_reserve(reserve_pubkey),
+                    Some(reserve) if reserve.has_pair_borrow_weights
+                )
+            })
+            .collect();
+
+        let reserve_asset_categories: Vec<u8> = reserve_pubkeys
+            .iter()
+            .filter_map(|reserve_pubkey| self.find_reserve(reserve_pubkey))
+            .map(|reserve| reserve.config.asset_category)
+            .collect();
+
+        let mut instructions = Vec::new();
+        let mut refreshed = Vec::new();
+        for reserve_pubkey in &reserve_pubkeys {
+            let reserve = self
+                .find_reserve(reserve_pubkey)
+                .ok_or_else(|| format!("Reserve {} not found", reserve_pubkey))?;
+            if refreshed.contains(reserve_pubkey) {
+                continue;
+            }
+            refreshed.push(*reserve_pubkey);
+
+            instructions.push(
+                if let Some(tertiary_oracle_pubkey) = reserve.tertiary_oracle_pubkey {
+                    refresh_reserve_with_tertiary_oracle(
+                        lending_program_id,
+                        *reserve_pubkey,
+                        reserve.liquidity.pyth_oracle_pubkey,
+                        reserve.liquidity.switchboard_oracle_pubkey,
+                        tertiary_oracle_pubkey,
+                        reserve.config.extra_oracle_pubkey,
+                    )
+                } else {
+                    refresh_reserve(
+                        lending_program_id,
+                        *reserve_pubkey,
+                        reserve.liquidity.pyth_oracle_pubkey,
+                        reserve.liquidity.switchboard_oracle_pubkey,
+                        reserve.config.extra_oracle_pubkey,
+                    )
+                },
+            );
+        }
+
+        instructions.push(
+            if shared_asset_category(&reserve_asset_categories).is_some() {
+                refresh_obligation_with_asset_categories(
+                    lending_program_id,
+                    obligation_pubkey,
+                    reserve_pubkeys,
+                    pair_borrow_weights_reserve_pubkeys,
+                    obligation.lending_market,
+                )
+            } else if pair_borrow_weights_reserve_pubkeys.is_empty() {
+                refresh_obligation(lending_program_id, obligation_pubkey, reserve_pubkeys)
+            } else {
+                refresh_obligation_with_pair_borrow_weights(
+                    lending_program_id,
+                    obligation_pubkey,
+                    reserve_pubkeys,
+                    pair_borrow_weights_reserve_pubkeys,
+                )
+            },
+        );
+
+        Ok(instructions)
+    }
+
+    /// Repay up to `liquidity_amount` of the obligation's debt from the liquidator's token
+    /// account and redeem the seized collateral into the liquidator's withdraw liquidity account
+    pub fn liquidate_instruction(
+        &self,
+        lending_program_id: Pubkey,
+        liquidator: Pubkey,
+        liquidation: &Liquidation,
+        liquidity_amount: u64,
+    ) -> Result<Instruction, Error> {
+        let repay_reserve = self
+            .find_reserve(&liquidation.repay_reserve)
+            .ok_or("Repay reserve not found")?;
+        let withdraw_reserve = self
+            .find_reserve(&liquidation.withdraw_reserve)
+            .ok_or("Withdraw reserve not found")?;
+        let lending_market = self
+            .find_lending_market(&liquidation.obligation.lending_market)
+            .ok_or("Lending market not found")?;
+
+        let mut instruction = liquidate_obligation_and_redeem_reserve_collateral(
+            lending_program_id,
+            liquidity_amount,
+            get_associated_token_address(&liquidator, &repay_reserve.liquidity.mint_pubkey),
+            get_associated_token_address(&liquidator, &withdraw_reserve.collateral.mint_pubkey),
+            get_associated_token_address(&liquidator, &withdraw_reserve.liquidity.mint_pubkey),
+            liquidation.repay_reserve,
+            repay_reserve.liquidity.supply_pubkey,
+            liquidation.withdraw_reserve,
+            withdraw_reserve.collateral.mint_pubkey,
+            withdraw_reserve.collateral.supply_pubkey,
+            withdraw_reserve.liquidity.supply_pubkey,
+            withdraw_reserve.config.fee_receiver,
+            liquidation.obligation_pubkey,
+            liquidation.obligation.lending_market,
+            liquidator,
+        );
+        if lending_market.has_liquidator_whitelist {
+            let (liquidator_whitelist_pubkey, _bump_seed) = find_liquidator_whitelist_address(
+                &lending_program_id,
+                &liquidation.obligation.lending_market,
+            );
+            instruction.accounts.push(AccountMeta::new_readonly(
+                liquidator_whitelist_pubkey,
+                false,
+            ));
+        }
+
+        Ok(instruction)
+    }
+}
+
+/// Accounts of the lending program matching the filters
+fn program_accounts(
+    config: &Config,
+    filters: Vec<RpcFilterType>,
+) -> Result<Vec<(Pubkey, Account)>, Error> {
+    Ok(config.rpc_client.get_program_accounts_with_config(
+        &config.lending_program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64Zstd),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: Some(false),
+        },
+    )?)
+}
@@ -8,10 +8,12 @@ use crate::switchboard::get_switchboard_price;
 use crate::switchboard::get_switchboard_price_on_demand;
 use crate::switchboard::get_switchboard_price_v2;
 use solana_program::{
-    account_info::AccountInfo, msg, program_error::ProgramError, sysvar::clock::Clock,
+    account_info::AccountInfo, clock::UnixTimestamp, msg, program_error::ProgramError,
+    sysvar::clock::Clock,
 };
 use solend_sdk::error::LendingError;
 use solend_sdk::math::Decimal;
+use solend_sdk::state::ReserveConfig;
 
 pub enum OracleType {
     Pyth,
@@ -39,30 +41,98 @@ pub fn get_oracle_type(oracle_info: &AccountInfo) -> Result<OracleType, ProgramE
     Err(LendingError::InvalidOracleConfig.into())
 }
 
+/// Default max age of an oracle price, in seconds
+pub const DEFAULT_MAX_STALENESS_SECS: u64 = 120;
+/// Default max width of an oracle confidence interval, in basis points of the price
+pub const DEFAULT_MAX_CONFIDENCE_BPS: u64 = 1_000;
+/// Slot based oracles are converted from seconds assuming roughly 500ms slots
+const SLOTS_PER_SECOND: u64 = 2;
+
+/// Staleness and confidence bounds an oracle price must satisfy to be used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OracleLimits {
+    /// Max age of a price, in seconds
+    pub max_staleness_secs: u64,
+    /// Max width of the confidence interval, in basis points of the price
+    pub max_confidence_bps: u64,
+}
+
+impl Default for OracleLimits {
+    fn default() -> Self {
+        Self {
+            max_staleness_secs: DEFAULT_MAX_STALENESS_SECS,
+            max_confidence_bps: DEFAULT_MAX_CONFIDENCE_BPS,
+        }
+    }
+}
+
+impl OracleLimits {
+    /// Limits configured on a reserve. Unset (zero) values fall back to the defaults.
+    pub fn from_reserve_config(config: &ReserveConfig) -> Self {
+        let default = Self::default();
+        Self {
+            max_staleness_secs: match config.max_oracle_staleness_secs {
+                0 => default.max_staleness_secs,
+                secs => secs,
+            },
+            max_confidence_bps: match config.max_oracle_confidence_bps {
+                0 => default.max_confidence_bps,
+                bps => bps as u64,
+            },
+        }
+    }
+
+    /// Max age of a price for oracles that only track the slot they were updated in
+    pub fn max_staleness_slots(&self) -> u64 {
+        self.max_staleness_secs.saturating_mul(SLOTS_PER_SECOND)
+    }
+
+    /// Returns true if the confidence interval is too wide relative to the price
+    pub fn is_confidence_too_wide(&self, price: u64, conf: u64) -> bool {
+        (conf as u128) * 10_000 > (price as u128) * (self.max_confidence_bps as u128)
+    }
+}
+
 pub fn get_single_price(
     oracle_account_info: &AccountInfo,
     clock: &Clock,
+    limits: &OracleLimits,
 ) -> Result<(Decimal, Option<Decimal>), ProgramError> {
     match get_oracle_type(oracle_account_info)? {
         OracleType::Pyth => {
-            let price = pyth::get_pyth_price(oracle_account_info, clock)?;
+            let price = pyth::get_pyth_price(oracle_account_info, clock, limits)?;
             Ok((price.0, Some(price.1)))
         }
         OracleType::PythPull => {
-            let price = get_pyth_pull_price(oracle_account_info, clock)?;
+            let price = get_pyth_pull_price(oracle_account_info, clock, limits)?;
             Ok((price.0, Some(price.1)))
         }
         OracleType::Switchboard => {
-            let price = get_switchboard_price(oracle_account_info, clock)?;
+            let price = get_switchboard_price(oracle_account_info, clock, limits)?;
             Ok((price, None))
         }
         OracleType::SbOnDemand => {
-            let price = get_switchboard_price(oracle_account_info, clock)?;
+            let price = get_switchboard_price(oracle_account_info, clock, limits)?;
             Ok((price, None))
         }
     }
 }
 
+/// Publish time of the price get_single_price returns for the oracle
+pub fn get_single_price_publish_time(
+    oracle_account_info: &AccountInfo,
+    clock: &Clock,
+    limits: &OracleLimits,
+) -> Result<UnixTimestamp, ProgramError> {
+    match get_oracle_type(oracle_account_info)? {
+        OracleType::Pyth => pyth::get_pyth_publish_time(oracle_account_info, clock, limits),
+        OracleType::PythPull => pyth::get_pyth_pull_publish_time(oracle_account_info),
+        OracleType::Switchboard | OracleType::SbOnDemand => {
+            switchboard::get_switchboard_publish_time(oracle_account_info)
+        }
+    }
+}
+
 pub fn get_single_price_unchecked(
     oracle_account_info: &AccountInfo,
     clock: &Clock,
@@ -70,8 +140,15 @@ pub fn get_single_price_unchecked(
     match get_oracle_type(oracle_account_info)? {
         OracleType::Pyth => get_pyth_price_unchecked(oracle_account_info),
         OracleType::PythPull => get_pyth_pull_price_unchecked(oracle_account_info),
-        OracleType::Switchboard => get_switchboard_price_v2(oracle_account_info, clock, false),
-        OracleType::SbOnDemand => get_switchboard_price_on_demand(oracle_account_info, clock, true),
+        OracleType::Switchboard => {
+            get_switchboard_price_v2(oracle_account_info, clock, &OracleLimits::default(), false)
+        }
+        OracleType::SbOnDemand => get_switchboard_price_on_demand(
+            oracle_account_info,
+            clock,
+            &OracleLimits::default(),
+            false,
+        ),
     }
 }
 
@@ -1,5 +1,5 @@
 #![allow(missing_docs)]
-use crate::{get_oracle_type, pyth_mainnet, pyth_pull_mainnet, OracleType};
+use crate::{get_oracle_type, pyth_mainnet, pyth_pull_mainnet, OracleLimits, OracleType};
 use anchor_lang::Key;
 use solend_sdk::{
     error::LendingError,
@@ -10,15 +10,11 @@ use anchor_lang::AccountDeserialize;
 
 use pyth_solana_receiver_sdk::price_update::{PriceUpdateV2, VerificationLevel};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
-    sysvar::clock::Clock,
+    account_info::AccountInfo, clock::UnixTimestamp, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, sysvar::clock::Clock,
 };
 use std::{convert::TryInto, result::Result};
 
-const PYTH_CONFIDENCE_RATIO: u64 = 10;
-const STALE_AFTER_SLOTS_ELAPSED: u64 = 240; // roughly 2 min
-const STALE_AFTER_SECONDS_ELAPSED: u64 = 120; // roughly 2 min
-
 /// validates pyth AccountInfos
 #[inline(always)]
 pub fn validate_pyth_keys(pyth_price_info: &AccountInfo) -> ProgramResult {
@@ -108,6 +104,7 @@ pub fn get_pyth_pull_price_unchecked(
 pub fn get_pyth_price(
     pyth_price_info: &AccountInfo,
     clock: &Clock,
+    limits: &OracleLimits,
 ) -> Result<(Decimal, Decimal), ProgramError> {
     if *pyth_price_info.key == solend_sdk::NULL_PUBKEY {
         return Err(LendingError::NullOracleConfig.into());
@@ -119,7 +116,7 @@ pub fn get_pyth_price(
         LendingError::InvalidOracleConfig
     })?;
     let pyth_price = price_account
-        .get_price_no_older_than(clock, STALE_AFTER_SLOTS_ELAPSED)
+        .get_price_no_older_than(clock, limits.max_staleness_slots())
         .ok_or_else(|| {
             msg!("Pyth oracle price is too stale!");
             LendingError::InvalidOracleConfig
@@ -130,10 +127,7 @@ pub fn get_pyth_price(
         LendingError::InvalidOracleConfig
     })?;
 
-    // Perhaps confidence_ratio should exist as a per reserve config
-    // 100/confidence_ratio = maximum size of confidence range as a percent of price
-    // confidence_ratio of 10 filters out pyth prices with conf > 10% of price
-    if pyth_price.conf.saturating_mul(PYTH_CONFIDENCE_RATIO) > price {
+    if limits.is_confidence_too_wide(price, pyth_price.conf) {
         msg!(
             "Oracle price confidence is too wide. price: {}, conf: {}",
             price,
@@ -157,6 +151,35 @@ pub fn get_pyth_price(
     Ok((market_price?, ema_price))
 }
 
+/// Publish time of the price get_pyth_price returns
+pub fn get_pyth_publish_time(
+    pyth_price_info: &AccountInfo,
+    clock: &Clock,
+    limits: &OracleLimits,
+) -> Result<UnixTimestamp, ProgramError> {
+    let data = &pyth_price_info.try_borrow_data()?;
+    let price_account = pyth_sdk_solana::state::load_price_account(data).map_err(|e| {
+        msg!("Couldn't load price feed from account info: {:?}", e);
+        LendingError::InvalidOracleConfig
+    })?;
+    let pyth_price = price_account
+        .get_price_no_older_than(clock, limits.max_staleness_slots())
+        .ok_or_else(|| {
+            msg!("Pyth oracle price is too stale!");
+            LendingError::InvalidOracleConfig
+        })?;
+
+    Ok(pyth_price.publish_time)
+}
+
+/// Publish time of the price get_pyth_pull_price returns
+pub fn get_pyth_pull_publish_time(
+    pyth_price_info: &AccountInfo,
+) -> Result<UnixTimestamp, ProgramError> {
+    let price_feed_account: PriceUpdateV2 = account_deserialize(pyth_price_info)?;
+    Ok(price_feed_account.price_message.publish_time)
+}
+
 pub fn account_deserialize<T: AccountDeserialize>(
     account: &AccountInfo<'_>,
 ) -> Result<T, ProgramError> {
@@ -174,6 +197,7 @@ pub fn account_deserialize<T: AccountDeserialize>(
 pub fn get_pyth_pull_price(
     pyth_price_info: &AccountInfo,
     clock: &Clock,
+    limits: &OracleLimits,
 ) -> Result<(Decimal, Decimal), ProgramError> {
     if *pyth_price_info.key == solend_sdk::NULL_PUBKEY {
         return Err(LendingError::NullOracleConfig.into());
@@ -184,7 +208,7 @@ pub fn get_pyth_pull_price(
     let pyth_price = price_feed_account
         .get_price_no_older_than_with_custom_verification_level(
             clock,
-            STALE_AFTER_SECONDS_ELAPSED, // MAXIMUM_AGE, // this should be filtered by the caller
+            limits.max_staleness_secs,
             &price_feed_account.price_message.feed_id,
             VerificationLevel::Full, // All our prices and the sponsored feeds are full verified
         )
@@ -198,10 +222,7 @@ pub fn get_pyth_pull_price(
         LendingError::InvalidOracleConfig
     })?;
 
-    // Perhaps confidence_ratio should exist as a per reserve config
-    // 100/confidence_ratio = maximum size of confidence range as a percent of price
-    // confidence_ratio of 10 filters out pyth prices with conf > 10% of price
-    if pyth_price.conf.saturating_mul(PYTH_CONFIDENCE_RATIO) > price {
+    if limits.is_confidence_too_wide(price, pyth_price.conf) {
         msg!(
             "Oracle price confidence is too wide. price: {}, conf: {}",
             price,
@@ -596,7 +617,7 @@ mod test {
                 0,
             );
 
-            let result = get_pyth_price(&account_info, &test_case.clock);
+            let result = get_pyth_price(&account_info, &test_case.clock, &OracleLimits::default());
             assert_eq!(
                 result,
                 test_case.expected_result,
@@ -650,6 +671,72 @@ mod test {
         );
     }
 
+    #[test]
+    fn pyth_price_custom_limits() {
+        let mut price_account = PriceAccount {
+            magic: MAGIC,
+            ver: VERSION_2,
+            atype: AccountType::Price as u32,
+            ptype: PriceType::Price,
+            expo: 1,
+            timestamp: 1,
+            ema_price: Rational {
+                val: 11,
+                numer: 110,
+                denom: 10,
+            },
+            agg: PriceInfo {
+                price: 200,
+                conf: 40,
+                status: PriceStatus::Trading,
+                corp_act: CorpAction::NoCorpAct,
+                pub_slot: 100,
+            },
+            ..PriceAccount::default()
+        };
+
+        let mut lamports = 20;
+        let pubkey = Pubkey::new_unique();
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            false,
+            &mut lamports,
+            bytes_of_mut(&mut price_account),
+            &pubkey,
+            false,
+            0,
+        );
+        let clock = Clock {
+            slot: 300,
+            ..Clock::default()
+        };
+
+        // confidence is 20% of the price
+        assert_eq!(
+            get_pyth_price(&account_info, &clock, &OracleLimits::default()),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+        let loose = OracleLimits {
+            max_confidence_bps: 2_000,
+            ..OracleLimits::default()
+        };
+        assert_eq!(
+            get_pyth_price(&account_info, &clock, &loose),
+            Ok((Decimal::from(2000_u64), Decimal::from(110_u64)))
+        );
+
+        // 200 slots is within the default staleness window but not a 60 second one
+        let tight = OracleLimits {
+            max_staleness_secs: 60,
+            ..loose
+        };
+        assert_eq!(
+            get_pyth_price(&account_info, &clock, &tight),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+    }
+
     fn read_file<P: AsRef<Path>>(path: P) -> Vec<u8> {
         let path = path.as_ref();
         let mut file = File::open(path)
@@ -697,7 +784,7 @@ mod test {
             ..Clock::default()
         };
         assert_eq!(
-            get_pyth_pull_price(&account_info, &clock).unwrap(),
+            get_pyth_pull_price(&account_info, &clock, &OracleLimits::default()).unwrap(),
             (price, ema_price)
         );
     }
@@ -1,4 +1,5 @@
 use crate::get_oracle_type;
+use crate::OracleLimits;
 use crate::OracleType;
 use solend_sdk::math::TryDiv;
 use solend_sdk::math::TryMul;
@@ -8,8 +9,8 @@ use crate::{
     switchboard_v2_mainnet,
 };
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
-    sysvar::clock::Clock,
+    account_info::AccountInfo, clock::UnixTimestamp, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, sysvar::clock::Clock,
 };
 use solend_sdk::{error::LendingError, math::Decimal};
 use std::result::Result;
@@ -20,6 +21,7 @@ use switchboard_v2::AggregatorAccountData;
 pub fn get_switchboard_price(
     switchboard_feed_info: &AccountInfo,
     clock: &Clock,
+    limits: &OracleLimits,
 ) -> Result<Decimal, ProgramError> {
     if *switchboard_feed_info.key == solend_sdk::NULL_PUBKEY {
         return Err(LendingError::NullOracleConfig.into());
@@ -27,13 +29,35 @@ pub fn get_switchboard_price(
     if switchboard_feed_info.owner == &switchboard_v2_mainnet::id()
         || switchboard_feed_info.owner == &switchboard_v2_devnet::id()
     {
-        return get_switchboard_price_v2(switchboard_feed_info, clock, true);
+        return get_switchboard_price_v2(switchboard_feed_info, clock, limits, true);
     }
 
     if switchboard_feed_info.owner == &switchboard_on_demand_devnet::id()
         || switchboard_feed_info.owner == &switchboard_on_demand_mainnet::id()
     {
-        return get_switchboard_price_on_demand(switchboard_feed_info, clock, true);
+        return get_switchboard_price_on_demand(switchboard_feed_info, clock, limits, true);
+    }
+    Err(LendingError::NullOracleConfig.into())
+}
+
+/// Publish time of the price get_switchboard_price returns
+pub fn get_switchboard_publish_time(
+    switchboard_feed_info: &AccountInfo,
+) -> Result<UnixTimestamp, ProgramError> {
+    if switchboard_feed_info.owner == &switchboard_v2_mainnet::id()
+        || switchboard_feed_info.owner == &switchboard_v2_devnet::id()
+    {
+        let data = &switchboard_feed_info.try_borrow_data()?;
+        let feed = AggregatorAccountData::new_from_bytes(data)?;
+        return Ok(feed.latest_confirmed_round.round_open_timestamp);
+    }
+
+    if switchboard_feed_info.owner == &switchboard_on_demand_devnet::id()
+        || switchboard_feed_info.owner == &switchboard_on_demand_mainnet::id()
+    {
+        let data = switchboard_feed_info.try_borrow_data()?;
+        let feed = SbOnDemandFeed::parse(data).map_err(|_| ProgramError::InvalidAccountData)?;
+        return Ok(feed.last_update_timestamp);
     }
     Err(LendingError::NullOracleConfig.into())
 }
@@ -41,16 +65,16 @@ pub fn get_switchboard_price(
 pub fn get_switchboard_price_on_demand(
     switchboard_feed_info: &AccountInfo,
     clock: &Clock,
+    limits: &OracleLimits,
     check_staleness: bool,
 ) -> Result<Decimal, ProgramError> {
-    const STALE_AFTER_SLOTS_ELAPSED: u64 = 240;
     let data = switchboard_feed_info.try_borrow_data()?;
     let feed = SbOnDemandFeed::parse(data).map_err(|_| ProgramError::InvalidAccountData)?;
     let slots_elapsed = clock
         .slot
         .checked_sub(feed.result.slot)
         .ok_or(LendingError::MathOverflow)?;
-    if check_staleness && slots_elapsed >= STALE_AFTER_SLOTS_ELAPSED {
+    if check_staleness && slots_elapsed >= limits.max_staleness_slots() {
         msg!("Switchboard oracle price is stale");
         return Err(LendingError::InvalidOracleConfig.into());
     }
@@ -63,20 +87,21 @@ pub fn get_switchboard_price_on_demand(
     let exp = Decimal::from((10u128).checked_pow(price_desc.scale()).unwrap());
     let price = price_mantissa.try_div(exp)?;
 
-    let range_desc = feed.range().ok_or(ProgramError::InvalidAccountData)?;
-    if range_desc.mantissa() < 0 {
-        msg!("Switchboard oracle price range is negative which is not allowed");
+    // the standard deviation of the oracle submissions plays the role of pyth's confidence
+    let std_dev_desc = feed.std_dev().ok_or(ProgramError::InvalidAccountData)?;
+    if std_dev_desc.mantissa() < 0 {
+        msg!("Switchboard oracle price std dev is negative which is not allowed");
         return Err(LendingError::InvalidOracleConfig.into());
     }
-    let range_mantissa = Decimal::from(range_desc.mantissa() as u128);
-    let range_exp = Decimal::from((10u128).checked_pow(range_desc.scale()).unwrap());
-    let range = range_mantissa.try_div(range_exp)?;
+    let std_dev_mantissa = Decimal::from(std_dev_desc.mantissa() as u128);
+    let std_dev_exp = Decimal::from((10u128).checked_pow(std_dev_desc.scale()).unwrap());
+    let std_dev = std_dev_mantissa.try_div(std_dev_exp)?;
 
-    if range.try_mul(10_u64)? > price {
+    if std_dev.try_mul(10_000_u64)? > price.try_mul(limits.max_confidence_bps)? {
         msg!(
-            "Oracle price range is too wide. price: {}, conf: {}",
+            "Oracle price std dev is too wide. price: {}, conf: {}",
             price,
-            range,
+            std_dev,
         );
         return Err(LendingError::InvalidOracleConfig.into());
     }
@@ -87,9 +112,9 @@ pub fn get_switchboard_price_on_demand(
 pub fn get_switchboard_price_v2(
     switchboard_feed_info: &AccountInfo,
     clock: &Clock,
+    limits: &OracleLimits,
     check_staleness: bool,
 ) -> Result<Decimal, ProgramError> {
-    const STALE_AFTER_SLOTS_ELAPSED: u64 = 240;
     let data = &switchboard_feed_info.try_borrow_data()?;
     let feed = AggregatorAccountData::new_from_bytes(data)?;
 
@@ -97,7 +122,7 @@ pub fn get_switchboard_price_v2(
         .slot
         .checked_sub(feed.latest_confirmed_round.round_open_slot)
         .ok_or(LendingError::MathOverflow)?;
-    if check_staleness && slots_elapsed >= STALE_AFTER_SLOTS_ELAPSED {
+    if check_staleness && slots_elapsed >= limits.max_staleness_slots() {
         msg!("Switchboard oracle price is stale");
         return Err(LendingError::InvalidOracleConfig.into());
     }
@@ -160,3 +185,98 @@ pub fn validate_sb_on_demand_keys(switchboard_feed_info: &AccountInfo) -> Progra
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+    use std::mem::size_of;
+
+    /// Feed account data with the given price and std dev (both with 18 decimals) signed at `slot`.
+    /// The returned buffer is offset so the feed after the discriminator is 16 byte aligned.
+    fn feed_data(value: i128, std_dev: i128, slot: u64) -> Vec<u128> {
+        let mut buf = vec![0u128; (size_of::<SbOnDemandFeed>() + 24) / 16 + 1];
+        let data = &mut bytemuck::cast_slice_mut::<u128, u8>(&mut buf)[8..];
+        data[..8].copy_from_slice(&SbOnDemandFeed::discriminator());
+        let feed: &mut SbOnDemandFeed =
+            bytemuck::from_bytes_mut(&mut data[8..size_of::<SbOnDemandFeed>() + 8]);
+        feed.result.value = value;
+        feed.result.std_dev = std_dev;
+        feed.result.slot = slot;
+        buf
+    }
+
+    fn get_price(
+        buf: &mut [u128],
+        clock_slot: u64,
+        check_staleness: bool,
+    ) -> Result<Decimal, ProgramError> {
+        let data = &mut bytemuck::cast_slice_mut::<u128, u8>(buf)[8..];
+        let key = Pubkey::new_unique();
+        let owner = switchboard_on_demand_mainnet::id();
+        let mut lamports = 0;
+        let account_info =
+            AccountInfo::new(&key, false, false, &mut lamports, data, &owner, false, 0);
+        let clock = Clock {
+            slot: clock_slot,
+            ..Clock::default()
+        };
+
+        get_switchboard_price_on_demand(
+            &account_info,
+            &clock,
+            &OracleLimits::default(),
+            check_staleness,
+        )
+    }
+
+    #[test]
+    fn test_switchboard_on_demand_price() {
+        let one = 10i128.pow(18);
+
+        // 5% std dev is within the default 10% limit
+        let mut buf = feed_data(2 * one, one / 10, 100);
+        assert_eq!(get_price(&mut buf, 110, true).unwrap(), Decimal::from(2u64));
+
+        // 15% std dev is not
+        let mut buf = feed_data(2 * one, 3 * one / 10, 100);
+        assert_eq!(
+            get_price(&mut buf, 110, true),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+
+        // negative prices are rejected
+        let mut buf = feed_data(-2 * one, 0, 100);
+        assert_eq!(
+            get_price(&mut buf, 110, true),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+    }
+
+    #[test]
+    fn test_switchboard_on_demand_staleness() {
+        let one = 10i128.pow(18);
+        let max_staleness_slots = OracleLimits::default().max_staleness_slots();
+
+        let mut buf = feed_data(one, 0, 100);
+        assert_eq!(
+            get_price(&mut buf, 100 + max_staleness_slots - 1, true).unwrap(),
+            Decimal::one()
+        );
+        assert_eq!(
+            get_price(&mut buf, 100 + max_staleness_slots, true),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+        assert_eq!(
+            get_price(&mut buf, 100 + max_staleness_slots, false).unwrap(),
+            Decimal::one()
+        );
+
+        // a feed that was never updated has no price
+        let mut buf = feed_data(one, 0, 0);
+        assert_eq!(
+            get_price(&mut buf, 100, false),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
@@ -0,0 +1,2565 @@
+//! Instructions signed by the lending market owner or the other market authorities
+
+use super::*;
+
+pub(super) fn process_init_lending_market(
+    program_id: &Pubkey,
+    owner: Pubkey,
+    quote_currency: [u8; 32],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let oracle_program_id = next_account_info(account_info_iter)?;
+    let switchboard_oracle_program_id = next_account_info(account_info_iter)?;
+
+    assert_rent_exempt(rent, lending_market_info)?;
+    let mut lending_market = assert_uninitialized::<LendingMarket>(lending_market_info)?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    if token_program_id.key != &spl_token::id() {
+        msg!("Token program provided is not the spl token program");
+        return Err(LendingError::InvalidTokenProgram.into());
+    }
+
+    lending_market.init(InitLendingMarketParams {
+        bump_seed: Pubkey::find_program_address(&[lending_market_info.key.as_ref()], program_id).1,
+        owner,
+        quote_currency,
+        token_program_id: *token_program_id.key,
+        oracle_program_id: *oracle_program_id.key,
+        switchboard_oracle_program_id: *switchboard_oracle_program_id.key,
+    });
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_set_lending_market_owner_and_config(
+    program_id: &Pubkey,
+    new_owner: Pubkey,
+    rate_limiter_config: RateLimiterConfig,
+    whitelisted_liquidator: Option<Pubkey>,
+    risk_authority: Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let market_change_authority_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    if !market_change_authority_info.is_signer {
+        msg!("Lending market owner or risk authority provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    if market_change_authority_info.key == &lending_market.owner {
+        lending_market.owner = new_owner;
+        lending_market.risk_authority = risk_authority;
+
+        if rate_limiter_config != lending_market.rate_limiter.config {
+            lending_market.rate_limiter = RateLimiter::new(rate_limiter_config, Clock::get()?.slot);
+        }
+
+        lending_market.whitelisted_liquidator = whitelisted_liquidator;
+    } else if market_change_authority_info.key == &lending_market.risk_authority {
+        // only can disable outflows
+        if rate_limiter_config != lending_market.rate_limiter.config
+            && rate_limiter_config.window_duration > 0
+            && rate_limiter_config.max_outflow == 0
+        {
+            lending_market.rate_limiter = RateLimiter::new(rate_limiter_config, Clock::get()?.slot);
+        }
+    } else {
+        msg!("Signer must be the lending market owner or risk authority");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+pub(super) fn process_init_reserve(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    config: ReserveConfig,
+    collateral_decimals: Option<u8>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Reserve must be initialized with liquidity");
+        return Err(LendingError::InvalidAmount.into());
+    }
+    validate_reserve_config(config)?;
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_mint_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_supply_info = next_account_info(account_info_iter)?;
+    let pyth_product_info = next_account_info(account_info_iter)?;
+    let pyth_price_info = next_account_info(account_info_iter)?;
+    let switchboard_feed_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+
+    let clock = &Clock::get()?;
+
+    let rent_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(rent_info)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    assert_rent_exempt(rent, reserve_info)?;
+    let mut reserve = assert_uninitialized::<Reserve>(reserve_info)?;
+    if reserve_info.owner != program_id {
+        msg!(
+            "Reserve provided is not owned by the lending program {} != {}",
+            &reserve_info.owner.to_string(),
+            &program_id.to_string(),
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    if reserve_liquidity_supply_info.key == source_liquidity_info.key {
+        msg!("Reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let lending_market = Box::new(LendingMarket::unpack(&lending_market_info.data.borrow())?);
+    if lending_market_info.owner != program_id {
+        msg!(
+            "Lending market provided is not owned by the lending program  {} != {}",
+            &lending_market_info.owner.to_string(),
+            &program_id.to_string(),
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    validate_token_program(&lending_market, token_program_id)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+    if *switchboard_feed_info.key == solend_program::NULL_PUBKEY
+        && (*pyth_price_info.key == solend_program::NULL_PUBKEY
+            || *pyth_product_info.key == solend_program::NULL_PUBKEY)
+    {
+        msg!("Both price oracles are null. At least one must be non-null");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    validate_pyth_oracle(pyth_price_info)?;
+    validate_switchboard_keys(switchboard_feed_info)?;
+
+    if let Some(extra_oracle_pubkey) = config.extra_oracle_pubkey {
+        let extra_oracle_info = next_account_info(account_info_iter)?;
+        validate_extra_oracle(extra_oracle_pubkey, extra_oracle_info)?;
+    }
+
+    let (market_price, smoothed_market_price, price_publish_time) = get_price(
+        &[pyth_price_info, switchboard_feed_info],
+        clock,
+        &OracleLimits::from_reserve_config(&config),
+    )?;
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    let reserve_liquidity_mint = unpack_mint(&reserve_liquidity_mint_info.data.borrow())?;
+    if reserve_liquidity_mint_info.owner != token_program_id.key {
+        msg!("Reserve liquidity mint is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    if source_liquidity_info.owner != token_program_id.key {
+        msg!("Source liquidity is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    if reserve_liquidity_supply_info.owner != token_program_id.key {
+        msg!("Reserve liquidity supply is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    if reserve_liquidity_fee_receiver_info.owner != token_program_id.key {
+        msg!("Reserve liquidity fee receiver is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    if reserve_collateral_mint_info.owner != token_program_id.key {
+        msg!("Reserve collateral mint is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    if reserve_collateral_supply_info.owner != token_program_id.key {
+        msg!("Reserve collateral supply is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    if destination_collateral_info.owner != token_program_id.key {
+        msg!("Destination collateral is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+
+    let collateral_decimals = collateral_decimals.unwrap_or(reserve_liquidity_mint.decimals);
+    let decimals_offset = collateral_decimals as i16 - reserve_liquidity_mint.decimals as i16;
+    if decimals_offset.unsigned_abs() > MAX_COLLATERAL_DECIMALS_OFFSET as u16 {
+        msg!(
+            "Collateral decimals must be within {} of the liquidity decimals",
+            MAX_COLLATERAL_DECIMALS_OFFSET
+        );
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    reserve.init(InitReserveParams {
+        current_slot: clock.slot,
+        lending_market: *lending_market_info.key,
+        liquidity: ReserveLiquidity::new(NewReserveLiquidityParams {
+            mint_pubkey: *reserve_liquidity_mint_info.key,
+            mint_decimals: reserve_liquidity_mint.decimals,
+            supply_pubkey: *reserve_liquidity_supply_info.key,
+            pyth_oracle_pubkey: *pyth_price_info.key,
+            switchboard_oracle_pubkey: *switchboard_feed_info.key,
+            market_price,
+            smoothed_market_price: smoothed_market_price.unwrap_or(market_price),
+            price_slot: clock.slot,
+            price_publish_time,
+        }),
+        collateral: ReserveCollateral::new(NewReserveCollateralParams {
+            mint_pubkey: *reserve_collateral_mint_info.key,
+            supply_pubkey: *reserve_collateral_supply_info.key,
+            decimals_offset: decimals_offset as i8,
+        }),
+        config,
+        rate_limiter_config: RateLimiterConfig::default(),
+    });
+
+    let collateral_amount = reserve.deposit_liquidity(liquidity_amount)?;
+    Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
+
+    spl_token_init_account(TokenInitializeAccountParams {
+        account: reserve_liquidity_supply_info.clone(),
+        mint: reserve_liquidity_mint_info.clone(),
+        owner: lending_market_authority_info.clone(),
+        rent: rent_info.clone(),
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_init_account(TokenInitializeAccountParams {
+        account: reserve_liquidity_fee_receiver_info.clone(),
+        mint: reserve_liquidity_mint_info.clone(),
+        owner: lending_market_authority_info.clone(),
+        rent: rent_info.clone(),
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_init_mint(TokenInitializeMintParams {
+        mint: reserve_collateral_mint_info.clone(),
+        authority: lending_market_authority_info.key,
+        rent: rent_info.clone(),
+        decimals: collateral_decimals,
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_init_account(TokenInitializeAccountParams {
+        account: reserve_collateral_supply_info.clone(),
+        mint: reserve_collateral_mint_info.clone(),
+        owner: lending_market_authority_info.clone(),
+        rent: rent_info.clone(),
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_init_account(TokenInitializeAccountParams {
+        account: destination_collateral_info.clone(),
+        mint: reserve_collateral_mint_info.clone(),
+        owner: user_transfer_authority_info.clone(),
+        rent: rent_info.clone(),
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: reserve_liquidity_supply_info.clone(),
+        amount: liquidity_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_mint_to(TokenMintToParams {
+        mint: reserve_collateral_mint_info.clone(),
+        destination: destination_collateral_info.clone(),
+        amount: collateral_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_update_reserve_config(
+    program_id: &Pubkey,
+    config: ReserveConfig,
+    rate_limiter_config: RateLimiterConfig,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    validate_reserve_config(config)?;
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let _pyth_product_info = next_account_info(account_info_iter)?;
+    let pyth_price_info = next_account_info(account_info_iter)?;
+    let switchboard_feed_info = next_account_info(account_info_iter)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    if reserve_info.owner != program_id {
+        msg!(
+            "Reserve provided is not owned by the lending program {} != {}",
+            &reserve_info.owner.to_string(),
+            &program_id.to_string(),
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &reserve.lending_market != lending_market_info.key {
+        msg!("Reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let lending_market = Box::new(LendingMarket::unpack(&lending_market_info.data.borrow())?);
+    if lending_market_info.owner != program_id {
+        msg!(
+            "Lending market provided is not owned by the lending program  {} != {}",
+            &lending_market_info.owner.to_string(),
+            &program_id.to_string(),
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    // if it's a permissionless market
+    if &solend_market_owner::id() != signer_info.key {
+        if reserve.config.protocol_liquidation_fee != config.protocol_liquidation_fee {
+            msg!("permissionless markets can't edit protocol liquidation fees");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if reserve.config.protocol_take_rate != config.protocol_take_rate {
+            msg!("permissionless markets can't edit protocol take rate");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if reserve.config.fee_receiver != config.fee_receiver {
+            msg!("permissionless markets can't edit fee receiver");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if reserve.config.fees != config.fees {
+            msg!("permissionless markets can't edit fee configs!");
+            return Err(LendingError::InvalidConfig.into());
+        }
+    }
+
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    if !signer_info.is_signer {
+        msg!("Lending market owner or risk authority provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let old_config = reserve.config;
+    let old_rates = reserve_rates(&reserve);
+
+    if signer_info.key == &lending_market.owner {
+        // if window duration or max outflow are different, then create a new rate limiter instance.
+        if rate_limiter_config != reserve.rate_limiter.config {
+            reserve.rate_limiter = RateLimiter::new(rate_limiter_config, Clock::get()?.slot);
+        }
+
+        if *pyth_price_info.key != reserve.liquidity.pyth_oracle_pubkey {
+            validate_pyth_oracle(pyth_price_info)?;
+            reserve.liquidity.pyth_oracle_pubkey = *pyth_price_info.key;
+        }
+
+        if *switchboard_feed_info.key != reserve.liquidity.switchboard_oracle_pubkey {
+            validate_switchboard_keys(switchboard_feed_info)?;
+            reserve.liquidity.switchboard_oracle_pubkey = *switchboard_feed_info.key;
+        }
+        if reserve.liquidity.switchboard_oracle_pubkey == solend_program::NULL_PUBKEY
+            && reserve.liquidity.pyth_oracle_pubkey == solend_program::NULL_PUBKEY
+        {
+            msg!("At least one price oracle must have a non-null pubkey");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+
+        if let Some(extra_oracle_pubkey) = config.extra_oracle_pubkey {
+            let extra_oracle_info = next_account_info(account_info_iter)?;
+            validate_extra_oracle(extra_oracle_pubkey, extra_oracle_info)?;
+        }
+
+        reserve.config = config;
+    } else if signer_info.key == &lending_market.risk_authority {
+        // only can disable outflows
+        if rate_limiter_config.window_duration > 0 && rate_limiter_config.max_outflow == 0 {
+            reserve.rate_limiter = RateLimiter::new(rate_limiter_config, Clock::get()?.slot);
+        }
+
+        // only certain reserve config fields can be changed by the risk authority, and only in the
+        // safer direction for now
+        if config.borrow_limit < reserve.config.borrow_limit {
+            reserve.config.borrow_limit = config.borrow_limit;
+        }
+
+        if config.deposit_limit < reserve.config.deposit_limit {
+            reserve.config.deposit_limit = config.deposit_limit;
+        }
+
+        // lowering the LTV only limits new borrows and withdrawals, it can't make an obligation
+        // liquidatable
+        if config.loan_to_value_ratio < reserve.config.loan_to_value_ratio {
+            reserve.config.loan_to_value_ratio = config.loan_to_value_ratio;
+        }
+
+        if config.max_single_borrower_share_bps > 0
+            && (reserve.config.max_single_borrower_share_bps == 0
+                || config.max_single_borrower_share_bps
+                    < reserve.config.max_single_borrower_share_bps)
+        {
+            reserve.config.max_single_borrower_share_bps = config.max_single_borrower_share_bps;
+        }
+
+        if config.min_borrow_value > reserve.config.min_borrow_value {
+            reserve.config.min_borrow_value = config.min_borrow_value;
+        }
+
+        if config.max_collateral_value_contribution > 0
+            && (reserve.config.max_collateral_value_contribution == 0
+                || config.max_collateral_value_contribution
+                    < reserve.config.max_collateral_value_contribution)
+        {
+            reserve.config.max_collateral_value_contribution =
+                config.max_collateral_value_contribution;
+        }
+
+        // pausing is always safe, unpausing is left to the owner
+        reserve.config.deposits_paused |= config.deposits_paused;
+        reserve.config.borrows_paused |= config.borrows_paused;
+        reserve.config.withdrawals_paused |= config.withdrawals_paused;
+    } else if *signer_info.key == solend_market_owner::id()
+    // 5ph has the ability to change the
+    // fees on permissionless markets
+    {
+        reserve.config.fees = config.fees;
+        reserve.config.protocol_liquidation_fee = config.protocol_liquidation_fee;
+        reserve.config.protocol_take_rate = config.protocol_take_rate;
+        reserve.config.fee_receiver = config.fee_receiver;
+    } else {
+        msg!("Signer must be the Lending market owner or risk authority");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    if rate_curve_changed(&old_config, &reserve.config) {
+        log_reserve_rates_update(reserve_info.key, &reserve, old_rates);
+    }
+    ConfigUpdateEvent {
+        reserve: *reserve_info.key,
+        authority: *signer_info.key,
+    }
+    .log();
+
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Whether a config update touches the inputs to the borrow and supply rates
+fn rate_curve_changed(old: &ReserveConfig, new: &ReserveConfig) -> bool {
+    old.optimal_utilization_rate != new.optimal_utilization_rate
+        || old.max_utilization_rate != new.max_utilization_rate
+        || old.min_borrow_rate != new.min_borrow_rate
+        || old.optimal_borrow_rate != new.optimal_borrow_rate
+        || old.max_borrow_rate != new.max_borrow_rate
+        || old.super_max_borrow_rate != new.super_max_borrow_rate
+        || old.super_optimal_utilization_rate != new.super_optimal_utilization_rate
+        || old.super_optimal_borrow_rate != new.super_optimal_borrow_rate
+        || old.protocol_take_rate != new.protocol_take_rate
+}
+
+/// Borrow APR and supply APY at the reserve's current utilization
+fn reserve_rates(reserve: &Reserve) -> Result<(Rate, Rate), ProgramError> {
+    Ok((
+        reserve.current_borrow_rate()?,
+        reserve.current_supply_apy()?,
+    ))
+}
+
+/// Logs the borrow APR and supply APY at the reserve's current utilization before and after a
+/// config update, so a parameter change can be checked as soon as it lands. Never fails the
+/// update itself.
+fn log_reserve_rates_update(
+    reserve_key: &Pubkey,
+    reserve: &Reserve,
+    old_rates: Result<(Rate, Rate), ProgramError>,
+) {
+    let (
+        Ok(utilization_rate),
+        Ok((old_borrow_rate, old_supply_apy)),
+        Ok((new_borrow_rate, new_supply_apy)),
+    ) = (
+        reserve.liquidity.utilization_rate(),
+        old_rates,
+        reserve_rates(reserve),
+    )
+    else {
+        msg!("Reserve rates could not be calculated for the config update");
+        return;
+    };
+
+    msg!(
+        "Reserve rates at {} utilization: borrow APR {} -> {}, supply APY {} -> {}",
+        utilization_rate,
+        old_borrow_rate,
+        new_borrow_rate,
+        old_supply_apy,
+        new_supply_apy
+    );
+    sol_log_data(&[
+        b"ReserveRatesUpdated",
+        reserve_key.as_ref(),
+        &utilization_rate.to_scaled_val().to_le_bytes(),
+        &old_borrow_rate.to_scaled_val().to_le_bytes(),
+        &new_borrow_rate.to_scaled_val().to_le_bytes(),
+        &old_supply_apy.to_scaled_val().to_le_bytes(),
+        &new_supply_apy.to_scaled_val().to_le_bytes(),
+    ]);
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_redeem_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    _redeem_fees(program_id, None, accounts)
+}
+
+/// Redeems the reserve's protocol fees if they're above `min_amount`, and does nothing otherwise.
+/// Permissionless.
+pub(super) fn process_redeem_fees_if_above(
+    program_id: &Pubkey,
+    min_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    _redeem_fees(program_id, Some(min_amount), accounts)
+}
+
+fn _redeem_fees(
+    program_id: &Pubkey,
+    min_amount: Option<u64>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let reserve_supply_liquidity_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    if reserve_info.owner != program_id {
+        msg!(
+            "Reserve provided is not owned by the lending program {} != {}",
+            &reserve_info.owner.to_string(),
+            &program_id.to_string(),
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    if &reserve.config.fee_receiver != reserve_liquidity_fee_receiver_info.key {
+        msg!("Reserve liquidity fee receiver does not match the reserve liquidity fee receiver provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey != reserve_supply_liquidity_info.key {
+        msg!("Reserve liquidity supply must be used as the reserve supply liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.lending_market != lending_market_info.key {
+        msg!("Reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if reserve.last_update.is_stale(clock.slot)? {
+        msg!("reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    let insurance_fund_info = if reserve.has_insurance_fund {
+        let insurance_fund_info = next_account_info(account_info_iter)?;
+        validate_insurance_fund(
+            program_id,
+            insurance_fund_info,
+            lending_market_info,
+            &reserve,
+        )?;
+        Some(insurance_fund_info)
+    } else {
+        None
+    };
+
+    let spread_fee_receiver_info = if reserve.has_spread_fee_receiver {
+        let reserve_spread_fee_receiver_info = next_account_info(account_info_iter)?;
+        let spread_fee_receiver_info = next_account_info(account_info_iter)?;
+        if reserve_spread_fee_receiver_info.owner != program_id {
+            msg!("Reserve spread fee receiver provided is not owned by the lending program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+        let reserve_spread_fee_receiver =
+            ReserveSpreadFeeReceiver::unpack(&reserve_spread_fee_receiver_info.data.borrow())?;
+        let reserve_spread_fee_receiver_key = Pubkey::create_program_address(
+            &[
+                reserve_info.key.as_ref(),
+                RESERVE_SPREAD_FEE_RECEIVER_SEED,
+                &[reserve_spread_fee_receiver.bump_seed],
+            ],
+            program_id,
+        )?;
+        if reserve_spread_fee_receiver_key != *reserve_spread_fee_receiver_info.key {
+            msg!("Reserve spread fee receiver provided is not the reserve's spread fee receiver account");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        if &reserve_spread_fee_receiver.spread_fee_receiver != spread_fee_receiver_info.key {
+            msg!("Spread fee receiver does not match the reserve's spread fee receiver");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        spread_fee_receiver_info
+    } else {
+        reserve_liquidity_fee_receiver_info
+    };
+
+    let withdraw_amount = reserve.calculate_redeem_fees()?;
+    match min_amount {
+        Some(min_amount) if withdraw_amount <= min_amount => {
+            msg!(
+                "Protocol fees of {} are not above {}, nothing to redeem",
+                withdraw_amount,
+                min_amount
+            );
+            return Ok(());
+        }
+        None if withdraw_amount == 0 => {
+            return Err(LendingError::InsufficientProtocolFeesToRedeem.into());
+        }
+        _ => {}
+    }
+
+    reserve.liquidity.redeem_fees(withdraw_amount)?;
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    let mut fee_receiver_amount = withdraw_amount;
+    if let Some(insurance_fund_info) = insurance_fund_info {
+        let insurance_amount = lending_market.insurance_share(withdraw_amount);
+        if insurance_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: reserve_supply_liquidity_info.clone(),
+                destination: insurance_fund_info.clone(),
+                amount: insurance_amount,
+                authority: lending_market_authority_info.clone(),
+                authority_signer_seeds,
+                token_program: token_program_id.clone(),
+            })?;
+            log_fee(
+                FeeKind::Spread,
+                reserve_info.key,
+                reserve_supply_liquidity_info.key,
+                insurance_fund_info.key,
+                insurance_amount,
+                None,
+            );
+            fee_receiver_amount -= insurance_amount;
+        }
+    }
+
+    if fee_receiver_amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: reserve_supply_liquidity_info.clone(),
+            destination: spread_fee_receiver_info.clone(),
+            amount: fee_receiver_amount,
+            authority: lending_market_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_id.clone(),
+        })?;
+        log_fee(
+            FeeKind::Spread,
+            reserve_info.key,
+            reserve_supply_liquidity_info.key,
+            spread_fee_receiver_info.key,
+            fee_receiver_amount,
+            None,
+        );
+    }
+
+    Ok(())
+}
+
+pub(super) fn process_forgive_debt(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!(
+            "Lending market provided is not owned by the lending program  {} != {}",
+            &lending_market_info.owner.to_string(),
+            &program_id.to_string(),
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if reserve.last_update.is_stale(Clock::get()?.slot)? {
+        msg!("Reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    if obligation.last_update.is_stale(Clock::get()?.slot)? {
+        msg!("Obligation is stale and must be refreshed in the current slot");
+        return Err(LendingError::ObligationStale.into());
+    }
+    if obligation
+        .deposits
+        .iter()
+        .any(|collateral| collateral.deposited_amount > 0)
+    {
+        msg!("Obligation hasn't been fully liquidated!");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    // in the case where the entire reserve got rugged for whatever reason, we still don't
+    // want to forgive the entire reserve's supply because that'll mess with the ctoken ratio
+    // and cause overflow/div by zero issues in other places. therefore, we want to make sure the ctoken
+    // ratio is >= 1% after forgiveness.
+    //
+    // new ctoken ratio = (total_liquidity_supply - forgive_amount) / collateral_mint_supply >= 0.01
+    // -> forgive_amount <= (total_liquidity_supply - collateral_mint_supply * 0.01)
+    const MIN_CTOKEN_RATIO_PERCENT: u8 = 1;
+    let max_forgive_amount = reserve.liquidity.total_supply()?.try_sub(
+        Decimal::from(reserve.collateral.mint_total_supply)
+            .try_mul(Decimal::from_percent(MIN_CTOKEN_RATIO_PERCENT))?,
+    )?;
+
+    obligation.settle_rewards(reserve_info.key, &reserve)?;
+    let (liquidity, liquidity_index) = obligation.find_liquidity_in_borrows(*reserve_info.key)?;
+    let forgive_amount = min(
+        Decimal::from(liquidity_amount),
+        min(liquidity.borrowed_amount_wads, max_forgive_amount),
+    );
+
+    reserve.liquidity.forgive_debt(forgive_amount)?;
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    obligation.repay(forgive_amount, liquidity_index)?;
+    obligation.last_update.mark_stale();
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+pub(super) fn process_update_market_metadata(
+    program_id: &Pubkey,
+    metadata: &LendingMarketMetadata,
+    accounts: &[AccountInfo],
+) -> Result<(), ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program",);
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let metadata_seeds = &[lending_market_info.key.as_ref(), b"MetaData"];
+    let (metadata_key, bump_seed) = Pubkey::find_program_address(metadata_seeds, program_id);
+    if metadata_key != *metadata_info.key {
+        msg!("Provided metadata account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if bump_seed != metadata.bump_seed {
+        msg!("Provided bump seed does not match the expected derived bump seed");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    // initialize
+    if metadata_info.data_is_empty() {
+        msg!("Creating metadata account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                metadata_info.key,
+                Rent::get()?.minimum_balance(std::mem::size_of::<LendingMarketMetadata>()),
+                std::mem::size_of::<LendingMarketMetadata>() as u64,
+                program_id,
+            ),
+            &[lending_market_owner_info.clone(), metadata_info.clone()],
+            &[&[lending_market_info.key.as_ref(), br"MetaData", &[bump_seed]]],
+        )?;
+    }
+
+    if metadata_info.owner != program_id {
+        msg!("Metadata provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let mut metadata_account_data = metadata_info.try_borrow_mut_data()?;
+    metadata_account_data.copy_from_slice(bytes_of(metadata));
+
+    Ok(())
+}
+
+/// process mark obligation as closable
+pub(super) fn process_set_obligation_closeability_status(
+    program_id: &Pubkey,
+    closeable: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    if reserve.attributed_borrow_value < Decimal::from(reserve.config.attributed_borrow_limit_close)
+    {
+        msg!("Reserve attributed borrow value is below the attributed borrow limit");
+        return Err(LendingError::BorrowAttributionLimitNotExceeded.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    if &obligation.lending_market != lending_market_info.key {
+        msg!("Obligation lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if obligation.last_update.is_stale(clock.slot)? {
+        msg!("Obligation is stale and must be refreshed");
+        return Err(LendingError::ObligationStale.into());
+    }
+
+    if &lending_market.risk_authority != signer_info.key && &lending_market.owner != signer_info.key
+    {
+        msg!("Signer must be risk authority or lending market owner");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if !signer_info.is_signer {
+        msg!("Risk authority or lending market owner must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    if obligation.borrowed_value == Decimal::zero() {
+        msg!("Obligation borrowed value is zero");
+        return Err(LendingError::ObligationBorrowsZero.into());
+    }
+
+    obligation
+        .find_collateral_in_deposits(*reserve_info.key)
+        .map_err(|_| {
+            msg!("Obligation does not have a deposit for the reserve provided");
+            LendingError::ObligationCollateralEmpty
+        })?;
+
+    obligation.closeable = closeable;
+
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process rotate reserve collateral supply
+pub(super) fn process_rotate_reserve_collateral_supply(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let current_collateral_supply_info = next_account_info(account_info_iter)?;
+    let new_collateral_supply_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.collateral.supply_pubkey != current_collateral_supply_info.key {
+        msg!("Reserve collateral supply does not match the reserve collateral supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if current_collateral_supply_info.key == new_collateral_supply_info.key {
+        msg!("New collateral supply cannot be the current collateral supply");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    if new_collateral_supply_info.owner != token_program_id.key {
+        msg!("New collateral supply is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    let new_collateral_supply = unpack_token_account(&new_collateral_supply_info.data.borrow())?;
+    if new_collateral_supply.mint != reserve.collateral.mint_pubkey {
+        msg!("New collateral supply mint does not match the reserve collateral mint");
+        return Err(LendingError::InvalidTokenMint.into());
+    }
+    if new_collateral_supply.owner != *lending_market_authority_info.key
+        || new_collateral_supply.delegate.is_some()
+        || new_collateral_supply.close_authority.is_some()
+    {
+        msg!("New collateral supply must be controlled only by the lending market authority");
+        return Err(LendingError::InvalidTokenAccount.into());
+    }
+
+    let current_collateral_supply =
+        unpack_token_account(&current_collateral_supply_info.data.borrow())?;
+
+    reserve.collateral.supply_pubkey = *new_collateral_supply_info.key;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    if current_collateral_supply.amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: current_collateral_supply_info.clone(),
+            destination: new_collateral_supply_info.clone(),
+            amount: current_collateral_supply.amount,
+            authority: lending_market_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_id.clone(),
+        })?;
+    }
+    spl_token_close_account(TokenCloseAccountParams {
+        account: current_collateral_supply_info.clone(),
+        destination: lending_market_owner_info.clone(),
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    Ok(())
+}
+
+/// process rotate reserve fee receiver
+pub(super) fn process_rotate_reserve_fee_receiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let current_fee_receiver_info = next_account_info(account_info_iter)?;
+    let new_fee_receiver_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    // fee receivers can only be changed by the solend market owner, see
+    // process_update_reserve_config
+    if &solend_market_owner::id() != signer_info.key {
+        msg!("Signer must be the solend market owner to edit the fee receiver");
+        return Err(LendingError::InvalidSigner.into());
+    }
+    if !signer_info.is_signer {
+        msg!("Solend market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.config.fee_receiver != current_fee_receiver_info.key {
+        msg!("Reserve liquidity fee receiver does not match the reserve liquidity fee receiver provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if current_fee_receiver_info.key == new_fee_receiver_info.key {
+        msg!("New fee receiver cannot be the current fee receiver");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    if new_fee_receiver_info.owner != token_program_id.key {
+        msg!("New fee receiver is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    let new_fee_receiver = unpack_token_account(&new_fee_receiver_info.data.borrow())?;
+    if new_fee_receiver.mint != reserve.liquidity.mint_pubkey {
+        msg!("New fee receiver mint does not match the reserve liquidity mint");
+        return Err(LendingError::InvalidTokenMint.into());
+    }
+
+    reserve.config.fee_receiver = *new_fee_receiver_info.key;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    // fee receivers created by InitReserve are owned by the lending market authority, so their
+    // balance can be migrated and the account closed. externally owned fee receivers are only
+    // repointed.
+    if current_fee_receiver_info.owner == token_program_id.key {
+        let current_fee_receiver = unpack_token_account(&current_fee_receiver_info.data.borrow());
+        if let Ok(current_fee_receiver) = current_fee_receiver {
+            if current_fee_receiver.owner == *lending_market_authority_info.key {
+                if current_fee_receiver.amount > 0 {
+                    spl_token_transfer(TokenTransferParams {
+                        source: current_fee_receiver_info.clone(),
+                        destination: new_fee_receiver_info.clone(),
+                        amount: current_fee_receiver.amount,
+                        authority: lending_market_authority_info.clone(),
+                        authority_signer_seeds,
+                        token_program: token_program_id.clone(),
+                    })?;
+                }
+                spl_token_close_account(TokenCloseAccountParams {
+                    account: current_fee_receiver_info.clone(),
+                    destination: signer_info.clone(),
+                    authority: lending_market_authority_info.clone(),
+                    authority_signer_seeds,
+                    token_program: token_program_id.clone(),
+                })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// process set default host fee receiver
+pub(super) fn process_set_default_host_fee_receiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let default_host_fee_receiver_info = next_account_info(account_info_iter).ok();
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    lending_market.default_host_fee_receiver = default_host_fee_receiver_info
+        .map(|default_host_fee_receiver_info| *default_host_fee_receiver_info.key);
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set risk authority
+pub(super) fn process_set_risk_authority(
+    program_id: &Pubkey,
+    risk_authority: Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    msg!(
+        "Risk authority changed from {} to {}",
+        lending_market.risk_authority,
+        risk_authority
+    );
+    lending_market.risk_authority = risk_authority;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set asset category
+pub(super) fn process_set_asset_category(
+    program_id: &Pubkey,
+    asset_category: u8,
+    category: AssetCategory,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let asset_categories_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let asset_categories_seeds = &[lending_market_info.key.as_ref(), ASSET_CATEGORIES_SEED];
+    let (asset_categories_key, bump_seed) =
+        Pubkey::find_program_address(asset_categories_seeds, program_id);
+    if asset_categories_key != *asset_categories_info.key {
+        msg!("Provided asset categories account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let mut asset_categories = if asset_categories_info.data_is_empty() {
+        msg!("Creating asset categories account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                asset_categories_info.key,
+                Rent::get()?.minimum_balance(AssetCategories::LEN),
+                AssetCategories::LEN as u64,
+                program_id,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                asset_categories_info.clone(),
+            ],
+            &[&[
+                lending_market_info.key.as_ref(),
+                ASSET_CATEGORIES_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+
+        AssetCategories {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: *lending_market_info.key,
+            ..AssetCategories::default()
+        }
+    } else {
+        AssetCategories::unpack(&asset_categories_info.data.borrow())?
+    };
+
+    if asset_categories_info.owner != program_id {
+        msg!("Asset categories provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    asset_categories.set(asset_category, category)?;
+    msg!(
+        "Asset category {} set to {}% LTV, {}% liquidation threshold and {}% max liquidation threshold",
+        asset_category,
+        category.loan_to_value_ratio,
+        category.liquidation_threshold,
+        category.max_liquidation_threshold
+    );
+
+    AssetCategories::pack(
+        asset_categories,
+        &mut asset_categories_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// process set max obligations per owner
+pub(super) fn process_set_max_obligations_per_owner(
+    program_id: &Pubkey,
+    max_obligations_per_owner: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+    msg!(
+        "Max obligations per owner changed from {} to {}",
+        lending_market.max_obligations_per_owner,
+        max_obligations_per_owner
+    );
+    lending_market.max_obligations_per_owner = max_obligations_per_owner;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set dust borrow value
+pub(super) fn process_set_dust_borrow_value(
+    program_id: &Pubkey,
+    dust_borrow_value: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    msg!(
+        "Dust borrow value changed from {} to {}",
+        lending_market.dust_borrow_value,
+        dust_borrow_value
+    );
+    lending_market.dust_borrow_value = dust_borrow_value;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set max price age
+pub(super) fn process_set_max_price_age(
+    program_id: &Pubkey,
+    max_price_age_slots: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let max_price_age_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    if max_price_age_slots > MAX_PRICE_AGE_SLOTS {
+        msg!(
+            "Max price age can't be more than {} slots",
+            MAX_PRICE_AGE_SLOTS
+        );
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    let max_price_age_seeds = &[lending_market_info.key.as_ref(), MAX_PRICE_AGE_SEED];
+    let (max_price_age_key, bump_seed) =
+        Pubkey::find_program_address(max_price_age_seeds, program_id);
+    if max_price_age_key != *max_price_age_info.key {
+        msg!("Provided max price age account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if max_price_age_info.data_is_empty() {
+        msg!("Creating max price age account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                max_price_age_info.key,
+                Rent::get()?.minimum_balance(MaxPriceAge::LEN),
+                MaxPriceAge::LEN as u64,
+                program_id,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                max_price_age_info.clone(),
+            ],
+            &[&[
+                lending_market_info.key.as_ref(),
+                MAX_PRICE_AGE_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if max_price_age_info.owner != program_id {
+        msg!("Max price age provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    msg!("Max price age set to {} slots", max_price_age_slots);
+    MaxPriceAge::pack(
+        MaxPriceAge {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: *lending_market_info.key,
+            max_price_age_slots,
+        },
+        &mut max_price_age_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// process set obligation max outflow
+pub(super) fn process_set_obligation_max_outflow(
+    program_id: &Pubkey,
+    obligation_max_outflow: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    msg!(
+        "Obligation max outflow changed from {} to {}",
+        lending_market.obligation_max_outflow,
+        obligation_max_outflow
+    );
+    lending_market.obligation_max_outflow = obligation_max_outflow;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process reset rate limiter
+pub(super) fn process_reset_rate_limiter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    lending_market.rate_limiter.reset(clock.slot);
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    for reserve_info in account_info_iter {
+        let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+        validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+        reserve.rate_limiter.reset(clock.slot);
+        Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    }
+
+    Ok(())
+}
+
+/// process set protocol fee config
+pub(super) fn process_set_protocol_fee_config(
+    program_id: &Pubkey,
+    fee_authority: Pubkey,
+    treasury: Pubkey,
+    rate_limiter_config: RateLimiterConfig,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    if rate_limiter_config != lending_market.protocol_fee_rate_limiter.config {
+        lending_market.protocol_fee_rate_limiter =
+            RateLimiter::new(rate_limiter_config, clock.slot);
+    }
+    lending_market.fee_authority = fee_authority;
+    lending_market.treasury = treasury;
+
+    msg!(
+        "Protocol fee authority set to {} and treasury to {}",
+        fee_authority,
+        treasury
+    );
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process withdraw protocol fees
+pub(super) fn process_withdraw_protocol_fees(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let treasury_token_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let fee_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    if &lending_market.fee_authority != fee_authority_info.key {
+        msg!("Fee authority does not match the lending market's fee authority");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if !fee_authority_info.is_signer {
+        msg!("Fee authority provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.config.fee_receiver != reserve_liquidity_fee_receiver_info.key {
+        msg!("Reserve liquidity fee receiver does not match the reserve liquidity fee receiver provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if reserve.last_update.is_stale(clock.slot)? {
+        msg!("Reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    if lending_market.treasury_token_address(token_program_id.key, &reserve.liquidity.mint_pubkey)
+        != *treasury_token_info.key
+    {
+        msg!("Treasury token account must be the treasury's associated token account for the reserve liquidity mint");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let liquidity_amount = if liquidity_amount == u64::MAX {
+        unpack_token_account(&reserve_liquidity_fee_receiver_info.data.borrow())?.amount
+    } else {
+        liquidity_amount
+    };
+    if liquidity_amount == 0 {
+        msg!("Protocol fee withdrawal amount must be greater than zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let withdraw_value = reserve.market_value_upper_bound(Decimal::from(liquidity_amount))?;
+    if let Err(err) = lending_market
+        .protocol_fee_rate_limiter
+        .update(clock.slot, withdraw_value)
+    {
+        msg!("Protocol fee outflow limit exceeded! Please try again later.");
+        return Err(err);
+    }
+    LendingMarket::pack(
+        lending_market.clone(),
+        &mut lending_market_info.data.borrow_mut(),
+    )?;
+
+    sol_log_data(&[
+        b"WithdrawProtocolFees",
+        reserve_info.key.as_ref(),
+        treasury_token_info.key.as_ref(),
+        &liquidity_amount.to_le_bytes(),
+    ]);
+    spl_token_transfer(TokenTransferParams {
+        source: reserve_liquidity_fee_receiver_info.clone(),
+        destination: treasury_token_info.clone(),
+        amount: liquidity_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    Ok(())
+}
+
+/// process set insurance fee
+pub(super) fn process_set_insurance_fee(
+    program_id: &Pubkey,
+    insurance_fee_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if insurance_fee_bps > MAX_INSURANCE_FEE_BPS {
+        msg!(
+            "Insurance fee must be in range [0, {}] bps",
+            MAX_INSURANCE_FEE_BPS
+        );
+        return Err(LendingError::InvalidConfig.into());
+    }
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    lending_market.insurance_fee_bps = insurance_fee_bps;
+    msg!("Insurance fee set to {} bps", insurance_fee_bps);
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process init insurance fund
+pub(super) fn process_init_insurance_fund(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let insurance_fund_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_mint_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.mint_pubkey != reserve_liquidity_mint_info.key {
+        msg!("Reserve liquidity mint does not match the reserve liquidity mint provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if reserve.has_insurance_fund {
+        msg!("Reserve already pays into the insurance fund");
+        return Err(LendingError::AlreadyInitialized.into());
+    }
+
+    let insurance_fund_seeds = &[
+        lending_market_info.key.as_ref(),
+        reserve_liquidity_mint_info.key.as_ref(),
+        INSURANCE_FUND_SEED,
+    ];
+    let (insurance_fund_key, bump_seed) =
+        Pubkey::find_program_address(insurance_fund_seeds, program_id);
+    if insurance_fund_key != *insurance_fund_info.key {
+        msg!("Provided insurance fund account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    // reserves sharing a liquidity mint share its insurance fund
+    if insurance_fund_info.data_is_empty() {
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                insurance_fund_info.key,
+                Rent::from_account_info(rent_info)?.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                token_program_id.key,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                insurance_fund_info.clone(),
+            ],
+            &[&[
+                lending_market_info.key.as_ref(),
+                reserve_liquidity_mint_info.key.as_ref(),
+                INSURANCE_FUND_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+
+        spl_token_init_account(TokenInitializeAccountParams {
+            account: insurance_fund_info.clone(),
+            mint: reserve_liquidity_mint_info.clone(),
+            owner: lending_market_authority_info.clone(),
+            rent: rent_info.clone(),
+            token_program: token_program_id.clone(),
+        })?;
+    }
+
+    reserve.has_insurance_fund = true;
+    reserve.insurance_fund_bump_seed = bump_seed;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process cover bad debt
+pub(super) fn process_cover_bad_debt(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+    let account_info_iter = &mut accounts.iter();
+    let insurance_fund_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.supply_pubkey != reserve_liquidity_supply_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    validate_insurance_fund(
+        program_id,
+        insurance_fund_info,
+        lending_market_info,
+        &reserve,
+    )?;
+
+    reserve.liquidity.donate(liquidity_amount)?;
+    spl_token_transfer(TokenTransferParams {
+        source: insurance_fund_info.clone(),
+        destination: reserve_liquidity_supply_info.clone(),
+        amount: liquidity_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set reserve utilization kill switch
+pub(super) fn process_set_reserve_utilization_kill_switch(
+    program_id: &Pubkey,
+    emergency_utilization_threshold: u8,
+    emergency_utilization_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if emergency_utilization_threshold > 100 {
+        msg!("Emergency utilization threshold must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    reserve.emergency_utilization_threshold = emergency_utilization_threshold;
+    reserve.emergency_utilization_slots = emergency_utilization_slots;
+    reserve.high_utilization_slots = 0;
+    reserve.emergency_mode = false;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set emergency price
+pub(super) fn process_set_emergency_price(
+    program_id: &Pubkey,
+    price: Decimal,
+    max_duration_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let emergency_price_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    if &lending_market.risk_authority != signer_info.key && &lending_market.owner != signer_info.key
+    {
+        msg!("Signer must be risk authority or lending market owner");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if !signer_info.is_signer {
+        msg!("Risk authority or lending market owner must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    if max_duration_slots > MAX_EMERGENCY_PRICE_DURATION_SLOTS {
+        msg!(
+            "Emergency price duration must be at most {} slots",
+            MAX_EMERGENCY_PRICE_DURATION_SLOTS
+        );
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if max_duration_slots > 0 && price == Decimal::zero() {
+        msg!("Emergency price must be non-zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let emergency_price_seeds = &[reserve_info.key.as_ref(), EMERGENCY_PRICE_SEED];
+    let (emergency_price_key, bump_seed) =
+        Pubkey::find_program_address(emergency_price_seeds, program_id);
+    if emergency_price_key != *emergency_price_info.key {
+        msg!("Provided emergency price account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if emergency_price_info.data_is_empty() {
+        msg!("Creating emergency price account");
+
+        invoke_signed(
+            &create_account(
+                signer_info.key,
+                emergency_price_info.key,
+                Rent::get()?.minimum_balance(EmergencyPrice::LEN),
+                EmergencyPrice::LEN as u64,
+                program_id,
+            ),
+            &[signer_info.clone(), emergency_price_info.clone()],
+            &[&[
+                reserve_info.key.as_ref(),
+                EMERGENCY_PRICE_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if emergency_price_info.owner != program_id {
+        msg!("Emergency price provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let emergency_price = EmergencyPrice {
+        version: PROGRAM_VERSION,
+        bump_seed,
+        reserve: *reserve_info.key,
+        price,
+        set_slot: clock.slot,
+        expiry_slot: clock.slot.saturating_add(max_duration_slots),
+    };
+    msg!(
+        "Emergency price for reserve {} set to {} until slot {}",
+        reserve_info.key,
+        price,
+        emergency_price.expiry_slot
+    );
+    sol_log_data(&[
+        b"SetEmergencyPrice",
+        reserve_info.key.as_ref(),
+        &price.to_scaled_val()?.to_le_bytes(),
+        &emergency_price.expiry_slot.to_le_bytes(),
+    ]);
+    EmergencyPrice::pack(emergency_price, &mut emergency_price_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process close reserve
+pub(super) fn process_close_reserve(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_supply_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let reserve_pair_borrow_weights_info = next_account_info(account_info_iter)?;
+    let reserve_rate_stats_info = next_account_info(account_info_iter)?;
+    let reserve_activity_info = next_account_info(account_info_iter)?;
+    let reserve_ctoken_price_info = next_account_info(account_info_iter)?;
+    let reserve_spread_fee_receiver_info = next_account_info(account_info_iter)?;
+    let emergency_price_info = next_account_info(account_info_iter)?;
+    let deposit_reward_rule_info = next_account_info(account_info_iter)?;
+    let borrow_reward_rule_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.supply_pubkey != reserve_liquidity_supply_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.collateral.supply_pubkey != reserve_collateral_supply_info.key {
+        msg!("Reserve collateral supply does not match the reserve collateral supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.config.fee_receiver != reserve_liquidity_fee_receiver_info.key {
+        msg!("Reserve liquidity fee receiver does not match the reserve liquidity fee receiver provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey == destination_liquidity_info.key {
+        msg!("Reserve liquidity supply cannot be used as the destination liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reserve.liquidity.borrowed_amount_wads > Decimal::zero() {
+        msg!("Reserve has outstanding borrows and cannot be closed");
+        return Err(LendingError::ReserveNotEmpty.into());
+    }
+    if reserve.collateral.mint_total_supply > 0 {
+        msg!("Reserve has collateral in circulation and cannot be closed");
+        return Err(LendingError::ReserveNotEmpty.into());
+    }
+    if reserve
+        .liquidity
+        .accumulated_protocol_fees_wads
+        .try_floor_u64()?
+        > 0
+    {
+        msg!("Reserve protocol fees must be redeemed before it can be closed");
+        return Err(LendingError::ReserveNotEmpty.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    // whatever liquidity is left once every cToken is redeemed is rounding dust or donations
+    let reserve_liquidity_supply =
+        unpack_token_account(&reserve_liquidity_supply_info.data.borrow())?;
+    if reserve_liquidity_supply.amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: reserve_liquidity_supply_info.clone(),
+            destination: destination_liquidity_info.clone(),
+            amount: reserve_liquidity_supply.amount,
+            authority: lending_market_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_id.clone(),
+        })?;
+    }
+    spl_token_close_account(TokenCloseAccountParams {
+        account: reserve_liquidity_supply_info.clone(),
+        destination: lending_market_owner_info.clone(),
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    spl_token_close_account(TokenCloseAccountParams {
+        account: reserve_collateral_supply_info.clone(),
+        destination: lending_market_owner_info.clone(),
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    // the fee receiver holds protocol fees, so it has to be emptied with WithdrawProtocolFees
+    // first. externally owned fee receivers are left alone.
+    if reserve_liquidity_fee_receiver_info.owner == token_program_id.key {
+        let reserve_liquidity_fee_receiver =
+            unpack_token_account(&reserve_liquidity_fee_receiver_info.data.borrow());
+        if let Ok(reserve_liquidity_fee_receiver) = reserve_liquidity_fee_receiver {
+            if reserve_liquidity_fee_receiver.owner == *lending_market_authority_info.key {
+                if reserve_liquidity_fee_receiver.amount > 0 {
+                    msg!("Reserve liquidity fee receiver must be emptied before the reserve can be closed");
+                    return Err(LendingError::ReserveNotEmpty.into());
+                }
+                spl_token_close_account(TokenCloseAccountParams {
+                    account: reserve_liquidity_fee_receiver_info.clone(),
+                    destination: lending_market_owner_info.clone(),
+                    authority: lending_market_authority_info.clone(),
+                    authority_signer_seeds,
+                    token_program: token_program_id.clone(),
+                })?;
+            }
+        }
+    }
+
+    for (name, seed, account_info) in [
+        (
+            "reserve pair borrow weights",
+            RESERVE_PAIR_BORROW_WEIGHTS_SEED,
+            reserve_pair_borrow_weights_info,
+        ),
+        (
+            "reserve rate stats",
+            RESERVE_RATE_STATS_SEED,
+            reserve_rate_stats_info,
+        ),
+        (
+            "reserve activity",
+            RESERVE_ACTIVITY_SEED,
+            reserve_activity_info,
+        ),
+        (
+            "reserve cToken price",
+            RESERVE_CTOKEN_PRICE_SEED,
+            reserve_ctoken_price_info,
+        ),
+        (
+            "reserve spread fee receiver",
+            RESERVE_SPREAD_FEE_RECEIVER_SEED,
+            reserve_spread_fee_receiver_info,
+        ),
+        (
+            "emergency price",
+            EMERGENCY_PRICE_SEED,
+            emergency_price_info,
+        ),
+    ] {
+        close_derived_account(
+            program_id,
+            name,
+            &[reserve_info.key.as_ref(), seed],
+            account_info,
+            lending_market_owner_info,
+        )?;
+    }
+    for (side, reward_rule_info) in [
+        (RewardSide::Deposit, deposit_reward_rule_info),
+        (RewardSide::Borrow, borrow_reward_rule_info),
+    ] {
+        close_derived_account(
+            program_id,
+            "reward rule",
+            &[reserve_info.key.as_ref(), REWARD_RULE_SEED, &[side as u8]],
+            reward_rule_info,
+            lending_market_owner_info,
+        )?;
+    }
+
+    sol_log_data(&[b"CloseReserve", reserve_info.key.as_ref()]);
+
+    // close the reserve account
+    let lending_market_owner_lamports = lending_market_owner_info.lamports();
+    **lending_market_owner_info.lamports.borrow_mut() = lending_market_owner_lamports
+        .checked_add(reserve_info.lamports())
+        .ok_or(LendingError::MathOverflow)?;
+    **reserve_info.lamports.borrow_mut() = 0;
+    reserve_info.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// process set reserve spread fee receiver
+pub(super) fn process_set_reserve_spread_fee_receiver(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_spread_fee_receiver_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let spread_fee_receiver_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    if spread_fee_receiver_info.owner != token_program_id.key {
+        msg!("Spread fee receiver is not owned by the token program provided");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    let spread_fee_receiver = unpack_token_account(&spread_fee_receiver_info.data.borrow())?;
+    if spread_fee_receiver.mint != reserve.liquidity.mint_pubkey {
+        msg!("Spread fee receiver mint does not match the reserve liquidity mint");
+        return Err(LendingError::InvalidTokenMint.into());
+    }
+
+    let reserve_spread_fee_receiver_seeds =
+        &[reserve_info.key.as_ref(), RESERVE_SPREAD_FEE_RECEIVER_SEED];
+    let (reserve_spread_fee_receiver_key, bump_seed) =
+        Pubkey::find_program_address(reserve_spread_fee_receiver_seeds, program_id);
+    if reserve_spread_fee_receiver_key != *reserve_spread_fee_receiver_info.key {
+        msg!("Provided reserve spread fee receiver account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reserve_spread_fee_receiver_info.data_is_empty() {
+        msg!("Creating reserve spread fee receiver account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                reserve_spread_fee_receiver_info.key,
+                Rent::get()?.minimum_balance(ReserveSpreadFeeReceiver::LEN),
+                ReserveSpreadFeeReceiver::LEN as u64,
+                program_id,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                reserve_spread_fee_receiver_info.clone(),
+            ],
+            &[&[
+                reserve_info.key.as_ref(),
+                RESERVE_SPREAD_FEE_RECEIVER_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if reserve_spread_fee_receiver_info.owner != program_id {
+        msg!("Reserve spread fee receiver provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    msg!(
+        "Interest spread of reserve {} redeemed to {}",
+        reserve_info.key,
+        spread_fee_receiver_info.key
+    );
+    ReserveSpreadFeeReceiver::pack(
+        ReserveSpreadFeeReceiver {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            reserve: *reserve_info.key,
+            spread_fee_receiver: *spread_fee_receiver_info.key,
+        },
+        &mut reserve_spread_fee_receiver_info.data.borrow_mut(),
+    )?;
+
+    reserve.has_spread_fee_receiver = spread_fee_receiver_info.key != &reserve.config.fee_receiver;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set reserve tertiary oracle
+pub(super) fn process_set_reserve_tertiary_oracle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let oracle_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    let tertiary_oracle_pubkey = if *oracle_info.key != solend_program::NULL_PUBKEY {
+        // any supported oracle type will do
+        validate_extra_oracle(*oracle_info.key, oracle_info)?;
+        Some(*oracle_info.key)
+    } else {
+        None
+    };
+
+    msg!(
+        "Tertiary oracle of reserve {} set to {}",
+        reserve_info.key,
+        oracle_info.key
+    );
+    reserve.tertiary_oracle_pubkey = tertiary_oracle_pubkey;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set reserve pair borrow weight
+pub(super) fn process_set_reserve_pair_borrow_weight(
+    program_id: &Pubkey,
+    borrow_weight_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_pair_borrow_weights_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_info = next_account_info(account_info_iter)?;
+    let collateral_reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let mut borrow_reserve = Box::new(Reserve::unpack(&borrow_reserve_info.data.borrow())?);
+    validate_reserve_account(
+        program_id,
+        borrow_reserve_info,
+        &borrow_reserve,
+        lending_market_info,
+    )?;
+    let collateral_reserve = Box::new(Reserve::unpack(&collateral_reserve_info.data.borrow())?);
+    validate_reserve_account(
+        program_id,
+        collateral_reserve_info,
+        &collateral_reserve,
+        lending_market_info,
+    )?;
+
+    let reserve_pair_borrow_weights_seeds = &[
+        borrow_reserve_info.key.as_ref(),
+        RESERVE_PAIR_BORROW_WEIGHTS_SEED,
+    ];
+    let (reserve_pair_borrow_weights_key, bump_seed) =
+        Pubkey::find_program_address(reserve_pair_borrow_weights_seeds, program_id);
+    if reserve_pair_borrow_weights_key != *reserve_pair_borrow_weights_info.key {
+        msg!("Provided reserve pair borrow weights account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let mut reserve_pair_borrow_weights = if reserve_pair_borrow_weights_info.data_is_empty() {
+        msg!("Creating reserve pair borrow weights account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                reserve_pair_borrow_weights_info.key,
+                Rent::get()?.minimum_balance(ReservePairBorrowWeights::LEN),
+                ReservePairBorrowWeights::LEN as u64,
+                program_id,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                reserve_pair_borrow_weights_info.clone(),
+            ],
+            &[&[
+                borrow_reserve_info.key.as_ref(),
+                RESERVE_PAIR_BORROW_WEIGHTS_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+
+        ReservePairBorrowWeights {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            reserve: *borrow_reserve_info.key,
+            pair_borrow_weights: vec![],
+        }
+    } else {
+        ReservePairBorrowWeights::unpack(&reserve_pair_borrow_weights_info.data.borrow())?
+    };
+
+    if reserve_pair_borrow_weights_info.owner != program_id {
+        msg!("Reserve pair borrow weights provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    reserve_pair_borrow_weights.set(*collateral_reserve_info.key, borrow_weight_bps)?;
+    msg!(
+        "Borrow weight of reserve {} against collateral reserve {} set to {} bps",
+        borrow_reserve_info.key,
+        collateral_reserve_info.key,
+        borrow_weight_bps
+    );
+
+    borrow_reserve.has_pair_borrow_weights =
+        !reserve_pair_borrow_weights.pair_borrow_weights.is_empty();
+    ReservePairBorrowWeights::pack(
+        reserve_pair_borrow_weights,
+        &mut reserve_pair_borrow_weights_info.data.borrow_mut(),
+    )?;
+    Reserve::pack(*borrow_reserve, &mut borrow_reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set whitelist required
+pub(super) fn process_set_whitelist_required(
+    program_id: &Pubkey,
+    whitelist_required: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    msg!(
+        "Whitelist required changed from {} to {}",
+        lending_market.whitelist_required,
+        whitelist_required
+    );
+    lending_market.whitelist_required = whitelist_required;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process update market member
+pub(super) fn process_update_market_member(
+    program_id: &Pubkey,
+    is_member: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let market_member_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let market_member_seeds = &[
+        lending_market_info.key.as_ref(),
+        member_info.key.as_ref(),
+        MARKET_MEMBER_SEED,
+    ];
+    let (market_member_key, bump_seed) =
+        Pubkey::find_program_address(market_member_seeds, program_id);
+    if market_member_key != *market_member_info.key {
+        msg!("Provided market member account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if market_member_info.data_is_empty() {
+        msg!("Creating market member account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                market_member_info.key,
+                Rent::get()?.minimum_balance(MarketMember::LEN),
+                MarketMember::LEN as u64,
+                program_id,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                market_member_info.clone(),
+            ],
+            &[&[
+                lending_market_info.key.as_ref(),
+                member_info.key.as_ref(),
+                MARKET_MEMBER_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if market_member_info.owner != program_id {
+        msg!("Market member provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    msg!(
+        "Membership of {} in the lending market set to {}",
+        member_info.key,
+        is_member
+    );
+    MarketMember::pack(
+        MarketMember {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: *lending_market_info.key,
+            member: *member_info.key,
+            is_member,
+        },
+        &mut market_member_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// process set liquidator whitelist
+pub(super) fn process_set_liquidator_whitelist(
+    program_id: &Pubkey,
+    protected_window_slots: u64,
+    liquidators: Vec<Pubkey>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let liquidator_whitelist_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    if liquidators.len() > MAX_WHITELISTED_LIQUIDATORS {
+        msg!(
+            "Liquidator whitelist cannot have more than {} liquidators",
+            MAX_WHITELISTED_LIQUIDATORS
+        );
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    let liquidator_whitelist_seeds = &[lending_market_info.key.as_ref(), LIQUIDATOR_WHITELIST_SEED];
+    let (liquidator_whitelist_key, bump_seed) =
+        Pubkey::find_program_address(liquidator_whitelist_seeds, program_id);
+    if liquidator_whitelist_key != *liquidator_whitelist_info.key {
+        msg!("Provided liquidator whitelist account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if liquidator_whitelist_info.data_is_empty() {
+        msg!("Creating liquidator whitelist account");
+
+        invoke_signed(
+            &create_account(
+                lending_market_owner_info.key,
+                liquidator_whitelist_info.key,
+                Rent::get()?.minimum_balance(LiquidatorWhitelist::LEN),
+                LiquidatorWhitelist::LEN as u64,
+                program_id,
+            ),
+            &[
+                lending_market_owner_info.clone(),
+                liquidator_whitelist_info.clone(),
+            ],
+            &[&[
+                lending_market_info.key.as_ref(),
+                LIQUIDATOR_WHITELIST_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if liquidator_whitelist_info.owner != program_id {
+        msg!("Liquidator whitelist provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    msg!(
+        "Protected liquidation window set to {} slots for {} liquidators",
+        protected_window_slots,
+        liquidators.len()
+    );
+    LiquidatorWhitelist::pack(
+        LiquidatorWhitelist {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: *lending_market_info.key,
+            protected_window_slots,
+            liquidators,
+        },
+        &mut liquidator_whitelist_info.data.borrow_mut(),
+    )?;
+
+    lending_market.has_liquidator_whitelist = protected_window_slots > 0;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Reallocates a version 1 lending market or reserve to the current layout
+pub(super) fn process_migrate_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let account_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if account_info.owner != program_id {
+        msg!("Account provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if !is_v1_layout_account(&account_info.data.borrow()) {
+        msg!("Account provided is not a version 1 lending market, reserve or obligation");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if !payer_info.is_signer {
+        msg!("Payer provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let obligation_len = Obligation::migrated_len(account_info.data_len());
+    let new_len = if let Some(obligation_len) = obligation_len {
+        obligation_len
+    } else if account_info.data_len() == LENDING_MARKET_LEN_V1 {
+        LendingMarket::LEN
+    } else {
+        Reserve::LEN
+    };
+    let rent = Rent::get()?.minimum_balance(new_len);
+    if account_info.lamports() < rent {
+        invoke(
+            &transfer(
+                payer_info.key,
+                account_info.key,
+                rent - account_info.lamports(),
+            ),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    // zeroes the fields added since version 1, which leaves them disabled
+    account_info.realloc(new_len, true)?;
+
+    if obligation_len.is_some() {
+        let mut obligation = Obligation::unpack_account(&account_info.data.borrow())?;
+        let clock = Clock::get()?;
+        // positions start at their reserve's current reward index, so they only earn rewards
+        // accrued from now on
+        for collateral in obligation.deposits.iter_mut() {
+            let mut reserve =
+                unpack_migrate_reserve(program_id, account_info_iter, &collateral.deposit_reserve)?;
+            reserve.accrue_rewards(clock.slot)?;
+            collateral.rewards.reward_index = reserve.deposit_rewards.reward_index;
+        }
+        for liquidity in obligation.borrows.iter_mut() {
+            let mut reserve =
+                unpack_migrate_reserve(program_id, account_info_iter, &liquidity.borrow_reserve)?;
+            reserve.accrue_rewards(clock.slot)?;
+            liquidity.rewards.reward_index = reserve.borrow_rewards.reward_index;
+        }
+        obligation.version = PROGRAM_VERSION;
+        Obligation::pack_account(obligation, &mut account_info.data.borrow_mut())?;
+    } else if new_len == LendingMarket::LEN {
+        let mut lending_market = LendingMarket::unpack(&account_info.data.borrow())?;
+        lending_market.version = PROGRAM_VERSION;
+        LendingMarket::pack(lending_market, &mut account_info.data.borrow_mut())?;
+    } else {
+        let mut reserve = Reserve::unpack(&account_info.data.borrow())?;
+        reserve.version = PROGRAM_VERSION;
+        Reserve::pack(reserve, &mut account_info.data.borrow_mut())?;
+    }
+    msg!(
+        "Migrated account {} to version {}",
+        account_info.key,
+        PROGRAM_VERSION
+    );
+
+    Ok(())
+}
+
+fn unpack_migrate_reserve(
+    program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+    reserve_pubkey: &Pubkey,
+) -> Result<Box<Reserve>, ProgramError> {
+    let reserve_info = next_account_info(account_info_iter)?;
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if reserve_info.key != reserve_pubkey {
+        msg!("Reserve provided does not match the obligation's reserve");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(Box::new(Reserve::unpack(&reserve_info.data.borrow())?))
+}
@@ -0,0 +1,1069 @@
+//! Borrowing and repaying obligation liquidity
+
+use super::*;
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_borrow_obligation_liquidity(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    min_liquidity_received: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        obligation_owner_info,
+        market_member_info,
+    )?;
+
+    let mut borrow_reserve = Box::new(Reserve::unpack(&borrow_reserve_info.data.borrow())?);
+    if borrow_reserve_info.owner != program_id {
+        msg!("Borrow reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &borrow_reserve.lending_market != lending_market_info.key {
+        msg!("Borrow reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &borrow_reserve.liquidity.supply_pubkey != source_liquidity_info.key {
+        msg!("Borrow reserve liquidity supply must be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &borrow_reserve.liquidity.supply_pubkey == destination_liquidity_info.key {
+        msg!(
+            "Borrow reserve liquidity supply cannot be used as the destination liquidity provided"
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &borrow_reserve.config.fee_receiver != borrow_reserve_liquidity_fee_receiver_info.key {
+        msg!("Borrow reserve liquidity fee receiver does not match the borrow reserve liquidity fee receiver provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if borrow_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Borrow reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+    if borrow_reserve.emergency_mode {
+        msg!("Borrow reserve is in emergency mode, borrows are blocked");
+        return Err(LendingError::ReserveEmergencyMode.into());
+    }
+    if borrow_reserve.config.borrows_paused {
+        msg!("Borrows are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+    if borrow_reserve.emergency_price_active {
+        msg!("Borrow reserve is priced from its emergency price");
+        return Err(LendingError::ReserveEmergencyMode.into());
+    }
+    if liquidity_amount != u64::MAX
+        && Decimal::from(liquidity_amount)
+            .try_add(borrow_reserve.liquidity.borrowed_amount_wads)?
+            .try_floor_u64()?
+            > borrow_reserve.config.borrow_limit
+    {
+        msg!("Cannot borrow above the borrow limit");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    // the approved borrower of the obligation signs in place of the owner
+    let approved_borrower_borrows = obligation_owner_info.key != &obligation.owner;
+    if approved_borrower_borrows {
+        validate_approved_borrower(&obligation, obligation_owner_info)?;
+    } else {
+        validate_obligation_owner(&obligation, obligation_owner_info)?;
+    }
+    if obligation.is_borrow_locked(clock.slot) {
+        msg!("Obligation owner has locked new borrows from this obligation");
+        return Err(LendingError::ObligationBorrowsLocked.into());
+    }
+    if obligation.last_update.is_stale(clock.slot)? {
+        msg!("Obligation is stale and must be refreshed in the current slot");
+        return Err(LendingError::ObligationStale.into());
+    }
+    if obligation.deposits.is_empty() {
+        msg!("Obligation has no deposits to borrow against");
+        return Err(LendingError::ObligationDepositsEmpty.into());
+    }
+    if obligation.deposited_value == Decimal::zero() {
+        msg!("Obligation deposits have zero value");
+        return Err(LendingError::ObligationDepositsZero.into());
+    }
+    let obligation_rate_limited =
+        sync_obligation_rate_limiter(&lending_market, &mut obligation, clock);
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    // fully repaid borrows are kept while they still hold unclaimed rewards, so they don't count
+    let open_borrows: Vec<_> = obligation
+        .borrows
+        .iter()
+        .filter(|liquidity| liquidity.borrowed_amount_wads > Decimal::zero())
+        .collect();
+    match borrow_reserve.config.reserve_type {
+        ReserveType::Isolated => match open_borrows.len() {
+            0 => {}
+            1 => {
+                if &open_borrows[0].borrow_reserve != borrow_reserve_info.key {
+                    msg!("If you want to borrow an isolated tier asset, there can't be any other borrows in your obligation");
+                    return Err(LendingError::IsolatedTierAssetViolation.into());
+                }
+            }
+            // it's possible that the obligation already has a borrow from this reserve (consider
+            // case the where we change a reserve asset type from regular to isolated), but in that
+            // case we don't want to let more borrows happen anyways.
+            _ => {
+                msg!("If you want to borrow an isolated tier asset, there can't be any other borrows in your obligation");
+                return Err(LendingError::IsolatedTierAssetViolation.into());
+            }
+        },
+        ReserveType::Regular => {
+            if obligation.borrowing_isolated_asset {
+                msg!(
+                    "Cannot borrow a regular tier asset if you have an isolated tier asset borrow"
+                );
+                return Err(LendingError::IsolatedTierAssetViolation.into());
+            }
+        }
+    };
+
+    let remaining_borrow_value = obligation
+        .remaining_borrow_value()
+        .unwrap_or_else(|_| Decimal::zero());
+    if remaining_borrow_value == Decimal::zero() {
+        msg!("Remaining borrow value is zero");
+        return Err(LendingError::BorrowTooLarge.into());
+    }
+
+    let remaining_reserve_capacity = Decimal::from(borrow_reserve.config.borrow_limit)
+        .try_sub(borrow_reserve.liquidity.borrowed_amount_wads)
+        .unwrap_or_else(|_| Decimal::zero());
+
+    // account for rate limiter restrictions when calculating max borrow amount.
+    let max_outflow_liquidity_amount = {
+        let mut max_outflow_usd = lending_market.rate_limiter.remaining_outflow(clock.slot)?;
+        if obligation_rate_limited {
+            max_outflow_usd = min(
+                max_outflow_usd,
+                obligation.rate_limiter.remaining_outflow(clock.slot)?,
+            );
+        }
+        max_outflow_usd = min(max_outflow_usd, obligation.remaining_borrow_cap());
+        if approved_borrower_borrows {
+            max_outflow_usd = min(max_outflow_usd, obligation.delegated_value);
+        }
+        let max_outflow_tokens = borrow_reserve.rate_limiter.remaining_outflow(clock.slot)?;
+
+        min(
+            borrow_reserve.usd_to_liquidity_amount_lower_bound(min(
+                max_outflow_usd,
+                // min here bc this function can overflow if max_outflow_usd is u64::MAX
+                remaining_borrow_value,
+            ))?,
+            max_outflow_tokens,
+        )
+    };
+
+    let CalculateBorrowResult {
+        borrow_amount,
+        receive_amount,
+        borrow_fee,
+        host_fee,
+    } = borrow_reserve.calculate_borrow(
+        liquidity_amount,
+        remaining_borrow_value,
+        min(remaining_reserve_capacity, max_outflow_liquidity_amount),
+    )?;
+
+    if receive_amount == 0 {
+        msg!("Borrow amount is too small to receive liquidity after fees");
+        return Err(LendingError::BorrowTooSmall.into());
+    }
+    if receive_amount < min_liquidity_received {
+        msg!(
+            "Borrow would receive {} liquidity, less than the minimum of {}",
+            receive_amount,
+            min_liquidity_received
+        );
+        return Err(LendingError::BorrowSlippageExceeded.into());
+    }
+
+    if obligation.borrow_cap > 0
+        && borrow_reserve.market_value_upper_bound(borrow_amount)?
+            > obligation.remaining_borrow_cap()
+    {
+        msg!(
+            "Borrow would take the obligation over its ${} borrow cap",
+            obligation.borrow_cap
+        );
+        return Err(LendingError::ObligationBorrowCapExceeded.into());
+    }
+
+    if approved_borrower_borrows {
+        obligation
+            .borrow_delegated_value(borrow_reserve.market_value_upper_bound(borrow_amount)?)?;
+    }
+
+    let cumulative_borrow_rate_wads = borrow_reserve.liquidity.cumulative_borrow_rate_wads;
+    let liquidity_mint_pubkey = borrow_reserve.liquidity.mint_pubkey;
+    let referrer = obligation.referrer;
+
+    // check outflow rate limits
+    {
+        lending_market
+            .rate_limiter
+            .update(
+                clock.slot,
+                borrow_reserve.market_value_upper_bound(borrow_amount)?,
+            )
+            .inspect_err(|_| {
+                msg!("Market outflow limit exceeded! Please try again later.");
+            })?;
+
+        borrow_reserve
+            .rate_limiter
+            .update(clock.slot, borrow_amount)
+            .inspect_err(|_| {
+                msg!("Reserve outflow limit exceeded! Please try again later");
+            })?;
+
+        update_obligation_rate_limiter(
+            &mut obligation,
+            obligation_rate_limited,
+            clock,
+            borrow_reserve.market_value_upper_bound(borrow_amount)?,
+        )?;
+    }
+
+    LendingMarket::pack(
+        lending_market.clone(),
+        &mut lending_market_info.data.borrow_mut(),
+    )?;
+
+    borrow_reserve.liquidity.borrow(borrow_amount)?;
+    borrow_reserve.last_update.mark_stale();
+
+    let max_single_borrower_amount = match borrow_reserve.config.max_single_borrower_share_bps {
+        0 => None,
+        share_bps => Some(
+            borrow_reserve
+                .liquidity
+                .total_supply()?
+                .try_mul(share_bps as u64)?
+                .try_div(10_000)?,
+        ),
+    };
+
+    if borrow_reserve.config.min_borrow_value > 0 {
+        let borrowed_amount_wads = obligation
+            .borrows
+            .iter()
+            .find(|liquidity| liquidity.borrow_reserve == *borrow_reserve_info.key)
+            .map_or(Decimal::zero(), |liquidity| liquidity.borrowed_amount_wads)
+            .try_add(borrow_amount)?;
+        let borrowed_value = borrow_reserve.market_value(borrowed_amount_wads)?;
+        if borrowed_value < Decimal::from(borrow_reserve.config.min_borrow_value) {
+            msg!(
+                "Obligation borrow from reserve {} would be worth {}, less than the min borrow value of {}",
+                borrow_reserve_info.key,
+                borrowed_value,
+                borrow_reserve.config.min_borrow_value
+            );
+            return Err(LendingError::BorrowBelowMinValue.into());
+        }
+    }
+
+    // updating these fields is needed to a correct borrow attribution value update later
+    obligation.borrowed_value = obligation.borrowed_value.try_add(
+        borrow_reserve
+            .borrow_market_value(borrow_amount)?
+            .try_mul(borrow_reserve.borrow_weight())?,
+    )?;
+
+    obligation.unweighted_borrowed_value = obligation
+        .unweighted_borrowed_value
+        .try_add(borrow_reserve.borrow_market_value(borrow_amount)?)?;
+
+    let obligation_liquidity = obligation
+        .find_or_add_liquidity_to_borrows(*borrow_reserve_info.key, cumulative_borrow_rate_wads)?;
+    if obligation_liquidity.rate_cap > 0 {
+        msg!("Borrow has a rate cap and can't be increased");
+        return Err(LendingError::BorrowRateCapped.into());
+    }
+
+    obligation_liquidity.settle_rewards(&borrow_reserve)?;
+    obligation_liquidity.borrow(borrow_amount)?;
+    let obligation_borrowed_amount_wads = obligation_liquidity.borrowed_amount_wads;
+    Reserve::pack(*borrow_reserve, &mut borrow_reserve_info.data.borrow_mut())?;
+    obligation.last_update.mark_stale();
+
+    if let Some(max_borrowed_amount) = max_single_borrower_amount {
+        if obligation_borrowed_amount_wads > max_borrowed_amount {
+            msg!(
+                "Obligation would borrow {} of reserve {}, more than its max single borrower share of {}",
+                obligation_borrowed_amount_wads,
+                borrow_reserve_info.key,
+                max_borrowed_amount
+            );
+            return Err(LendingError::BorrowerConcentrationLimitExceeded.into());
+        }
+    }
+
+    let (open_exceeded, _) = update_borrow_attribution_values(&mut obligation, &accounts[9..])?;
+    if let Some(reserve_pubkey) = open_exceeded {
+        msg!(
+            "Open borrow attribution limit exceeded for reserve {:?}",
+            reserve_pubkey
+        );
+        return Err(LendingError::BorrowAttributionLimitExceeded.into());
+    }
+
+    // HACK: fast forward through the deposit reserve infos
+    for _ in 0..obligation.deposits.len() {
+        next_account_info(account_info_iter)?;
+    }
+
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    let mut owner_fee = borrow_fee;
+    let host_fee_receiver_info = next_account_info(account_info_iter).ok();
+    match (referrer, host_fee_receiver_info) {
+        (Some(referrer), Some(host_fee_receiver_info)) => {
+            check_referrer_host_fee_receiver(&referrer, host_fee_receiver_info)?;
+        }
+        (Some(_), None) => {
+            msg!("Obligation has a referrer, its token account must be provided as the host fee receiver");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        // borrows without a referrer pay the host fee to the lending market's default host fee
+        // receiver, if it has one
+        (None, Some(host_fee_receiver_info)) => {
+            check_default_host_fee_receiver(
+                &lending_market,
+                host_fee_receiver_info,
+                token_program_id.key,
+                &liquidity_mint_pubkey,
+            )?;
+        }
+        (None, None) => {}
+    }
+    if let Some(host_fee_receiver_info) = host_fee_receiver_info {
+        if host_fee > 0 {
+            owner_fee = owner_fee
+                .checked_sub(host_fee)
+                .ok_or(LendingError::MathOverflow)?;
+
+            spl_token_transfer(TokenTransferParams {
+                source: source_liquidity_info.clone(),
+                destination: host_fee_receiver_info.clone(),
+                amount: host_fee,
+                authority: lending_market_authority_info.clone(),
+                authority_signer_seeds,
+                token_program: token_program_id.clone(),
+            })?;
+            log_fee(
+                FeeKind::Host,
+                borrow_reserve_info.key,
+                obligation_owner_info.key,
+                host_fee_receiver_info.key,
+                host_fee,
+                referrer,
+            );
+        }
+    }
+    if owner_fee > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: source_liquidity_info.clone(),
+            destination: borrow_reserve_liquidity_fee_receiver_info.clone(),
+            amount: owner_fee,
+            authority: lending_market_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_id.clone(),
+        })?;
+        log_fee(
+            FeeKind::Origination,
+            borrow_reserve_info.key,
+            obligation_owner_info.key,
+            borrow_reserve_liquidity_fee_receiver_info.key,
+            owner_fee,
+            referrer,
+        );
+    }
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: destination_liquidity_info.clone(),
+        amount: receive_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    BorrowEvent {
+        kind: BorrowKind::Borrow,
+        obligation: *obligation_info.key,
+        reserve: *borrow_reserve_info.key,
+        liquidity_amount: receive_amount.saturating_add(borrow_fee),
+        borrowed_amount: obligation_borrowed_amount_wads,
+    }
+    .log();
+    record_reserve_activity(
+        reserve_activity_infos,
+        borrow_reserve_info,
+        ReserveAction::Borrow,
+        receive_amount.saturating_add(borrow_fee),
+    )?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_repay_obligation_liquidity(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let repay_reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+
+    let repay_amount = _repay_obligation_liquidity(
+        program_id,
+        TokenAmount(liquidity_amount),
+        source_liquidity_info,
+        destination_liquidity_info,
+        repay_reserve_info,
+        obligation_info.key,
+        &mut obligation,
+        lending_market_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+    record_reserve_activity(
+        reserve_activity_infos,
+        repay_reserve_info,
+        ReserveAction::Repay,
+        repay_amount.0,
+    )?;
+
+    Ok(())
+}
+
+/// process repay obligation liquidity many
+pub(super) fn process_repay_obligation_liquidity_many(
+    program_id: &Pubkey,
+    liquidity_amounts: &[u64],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amounts.is_empty() {
+        msg!("At least one liquidity amount must be provided");
+        return Err(LendingError::InvalidAmount.into());
+    }
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    if account_info_iter.len() != liquidity_amounts.len() * 3 {
+        msg!("Each liquidity amount must be followed by a source, destination and repay reserve account");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    // each reserve is repaid once, clients add up the amounts of a reserve instead of passing
+    // it twice
+    let repay_reserve_infos = account_info_iter
+        .as_slice()
+        .chunks(3)
+        .map(|repay_infos| &repay_infos[2]);
+    for (i, repay_reserve_info) in repay_reserve_infos.clone().enumerate() {
+        if repay_reserve_infos
+            .clone()
+            .take(i)
+            .any(|previous_info| previous_info.key == repay_reserve_info.key)
+        {
+            msg!(
+                "Repay reserve {} is provided more than once",
+                repay_reserve_info.key
+            );
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+
+    for liquidity_amount in liquidity_amounts {
+        let source_liquidity_info = next_account_info(account_info_iter)?;
+        let destination_liquidity_info = next_account_info(account_info_iter)?;
+        let repay_reserve_info = next_account_info(account_info_iter)?;
+
+        let repay_amount = _repay_obligation_liquidity(
+            program_id,
+            TokenAmount(*liquidity_amount),
+            source_liquidity_info,
+            destination_liquidity_info,
+            repay_reserve_info,
+            obligation_info.key,
+            &mut obligation,
+            lending_market_info,
+            user_transfer_authority_info,
+            clock,
+            token_program_id,
+        )?;
+        record_reserve_activity(
+            reserve_activity_infos,
+            repay_reserve_info,
+            ReserveAction::Repay,
+            repay_amount.0,
+        )?;
+    }
+
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _repay_obligation_liquidity<'a>(
+    program_id: &Pubkey,
+    TokenAmount(liquidity_amount): TokenAmount,
+    source_liquidity_info: &AccountInfo<'a>,
+    destination_liquidity_info: &AccountInfo<'a>,
+    repay_reserve_info: &AccountInfo<'a>,
+    obligation_key: &Pubkey,
+    obligation: &mut Obligation,
+    lending_market_info: &AccountInfo<'a>,
+    user_transfer_authority_info: &AccountInfo<'a>,
+    clock: &Clock,
+    token_program_id: &AccountInfo<'a>,
+) -> Result<TokenAmount, ProgramError> {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    _refresh_reserve_interest(program_id, repay_reserve_info, clock)?;
+    let mut repay_reserve = Box::new(Reserve::unpack(&repay_reserve_info.data.borrow())?);
+    if repay_reserve_info.owner != program_id {
+        msg!("Repay reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &repay_reserve.lending_market != lending_market_info.key {
+        msg!("Repay reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &repay_reserve.liquidity.supply_pubkey == source_liquidity_info.key {
+        msg!("Repay reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &repay_reserve.liquidity.supply_pubkey != destination_liquidity_info.key {
+        msg!("Repay reserve liquidity supply must be used as the destination liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if repay_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Repay reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let (liquidity, liquidity_index) =
+        obligation.find_liquidity_in_borrows_mut(*repay_reserve_info.key)?;
+    if liquidity.borrowed_amount_wads == Decimal::zero() {
+        msg!("Liquidity borrowed amount is zero");
+        return Err(LendingError::ObligationLiquidityEmpty.into());
+    }
+
+    // refreshing specific borrow instead of checking obligation stale
+    liquidity.settle_rewards(&repay_reserve)?;
+    liquidity.accrue_interest_with_rate_cap(
+        repay_reserve.liquidity.cumulative_borrow_rate_wads,
+        clock.slot,
+    )?;
+
+    let CalculateRepayResult {
+        settle_amount,
+        repay_amount,
+    } = repay_reserve.calculate_repay(liquidity_amount, liquidity.borrowed_amount_wads)?;
+
+    if repay_amount == 0 {
+        msg!("Repay amount is too small to transfer liquidity");
+        return Err(LendingError::RepayTooSmall.into());
+    }
+
+    repay_reserve.liquidity.repay(repay_amount, settle_amount)?;
+    repay_reserve.last_update.mark_stale();
+    Reserve::pack(*repay_reserve, &mut repay_reserve_info.data.borrow_mut())?;
+
+    let borrowed_amount = liquidity.borrowed_amount_wads.try_sub(settle_amount)?;
+    obligation.repay(settle_amount, liquidity_index)?;
+    obligation.last_update.mark_stale();
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: destination_liquidity_info.clone(),
+        amount: repay_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+    BorrowEvent {
+        kind: BorrowKind::Repay,
+        obligation: *obligation_key,
+        reserve: *repay_reserve_info.key,
+        liquidity_amount: repay_amount,
+        borrowed_amount,
+    }
+    .log();
+
+    Ok(TokenAmount(repay_amount))
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_repay_obligation_liquidity_with_collateral(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let repay_reserve_info = next_account_info(account_info_iter)?;
+    let repay_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_collateral_supply_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let lending_market = Box::new(LendingMarket::unpack(&lending_market_info.data.borrow())?);
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let mut repay_reserve = Box::new(Reserve::unpack(&repay_reserve_info.data.borrow())?);
+    validate_reserve_account(
+        program_id,
+        repay_reserve_info,
+        &repay_reserve,
+        lending_market_info,
+    )?;
+    if &repay_reserve.liquidity.supply_pubkey != repay_reserve_liquidity_supply_info.key {
+        msg!("Repay reserve liquidity supply does not match the repay reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if repay_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Repay reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+    validate_reserve_account(
+        program_id,
+        withdraw_reserve_info,
+        &withdraw_reserve,
+        lending_market_info,
+    )?;
+    if &withdraw_reserve.collateral.mint_pubkey != withdraw_reserve_collateral_mint_info.key {
+        msg!("Withdraw reserve collateral mint does not match the withdraw reserve collateral mint provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.collateral.supply_pubkey != withdraw_reserve_collateral_supply_info.key {
+        msg!("Withdraw reserve collateral supply does not match the withdraw reserve collateral supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.liquidity.supply_pubkey != withdraw_reserve_liquidity_supply_info.key {
+        msg!("Withdraw reserve liquidity supply does not match the withdraw reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if withdraw_reserve.liquidity.mint_pubkey != repay_reserve.liquidity.mint_pubkey {
+        msg!("Withdraw reserve liquidity mint does not match the repay reserve liquidity mint");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if withdraw_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Withdraw reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+    if withdraw_reserve.config.withdrawals_paused {
+        msg!("Withdrawals are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+    if obligation.last_update.is_stale(clock.slot)? {
+        msg!("Obligation is stale and must be refreshed in the current slot");
+        return Err(LendingError::ObligationStale.into());
+    }
+    obligation.settle_rewards(repay_reserve_info.key, &repay_reserve)?;
+    obligation.settle_rewards(withdraw_reserve_info.key, &withdraw_reserve)?;
+
+    let (liquidity, liquidity_index) =
+        obligation.find_liquidity_in_borrows(*repay_reserve_info.key)?;
+    if liquidity.borrowed_amount_wads == Decimal::zero() {
+        msg!("Liquidity borrowed amount is zero");
+        return Err(LendingError::ObligationLiquidityEmpty.into());
+    }
+    let borrowed_amount = liquidity.borrowed_amount_wads;
+
+    let (collateral, collateral_index) =
+        obligation.find_collateral_in_deposits(*withdraw_reserve_info.key)?;
+    if collateral.deposited_amount == 0 {
+        msg!("Collateral deposited amount is zero");
+        return Err(LendingError::ObligationCollateralEmpty.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    // the collateral is rounded down so the redeemed liquidity never exceeds the requested repay,
+    // which then makes the redeemed liquidity exactly the amount repaid
+    let collateral_exchange_rate = withdraw_reserve.collateral_exchange_rate()?;
+    let requested_repay_amount = repay_reserve
+        .calculate_repay(liquidity_amount, borrowed_amount)?
+        .repay_amount;
+    let collateral_amount = min(
+        collateral_exchange_rate.liquidity_to_collateral(requested_repay_amount)?,
+        collateral.deposited_amount,
+    );
+    let redeemed_liquidity_amount =
+        collateral_exchange_rate.collateral_to_liquidity(collateral_amount)?;
+
+    let CalculateRepayResult {
+        settle_amount,
+        repay_amount,
+    } = repay_reserve.calculate_repay(redeemed_liquidity_amount, borrowed_amount)?;
+
+    if repay_amount == 0 {
+        msg!("Collateral is too small to repay any liquidity");
+        return Err(LendingError::RepayTooSmall.into());
+    }
+
+    // the repay must lower the borrowed value at least as much as the withdraw lowers the borrow
+    // limit, so the obligation can't end up less healthy than it was
+    let allowed_borrow_value_decrease = withdraw_reserve
+        .market_value_lower_bound(Decimal::from(repay_amount))?
+        .try_mul(withdraw_reserve.loan_to_value_ratio())?;
+    let borrowed_value_decrease = repay_reserve
+        .market_value_upper_bound(settle_amount)?
+        .try_mul(repay_reserve.borrow_weight())?;
+    if allowed_borrow_value_decrease > borrowed_value_decrease {
+        msg!("Withdrawing the collateral would lower the borrow limit more than the repay lowers the borrowed value");
+        return Err(LendingError::WithdrawTooLarge.into());
+    }
+
+    repay_reserve.liquidity.repay(repay_amount, settle_amount)?;
+    repay_reserve.last_update.mark_stale();
+    Reserve::pack(*repay_reserve, &mut repay_reserve_info.data.borrow_mut())?;
+
+    // unpack again so the repay is accounted for when both reserves are the same account
+    let mut withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+    withdraw_reserve.collateral.burn(collateral_amount)?;
+    withdraw_reserve.liquidity.withdraw(repay_amount)?;
+    if collateral_amount == collateral.deposited_amount {
+        withdraw_reserve.attributed_borrow_value = withdraw_reserve
+            .attributed_borrow_value
+            .saturating_sub(collateral.attributed_borrow_value);
+    }
+    withdraw_reserve.last_update.mark_stale();
+    Reserve::pack(
+        *withdraw_reserve,
+        &mut withdraw_reserve_info.data.borrow_mut(),
+    )?;
+
+    obligation.repay(settle_amount, liquidity_index)?;
+    obligation.withdraw(collateral_amount, collateral_index)?;
+    obligation.last_update.mark_stale();
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    spl_token_burn(TokenBurnParams {
+        mint: withdraw_reserve_collateral_mint_info.clone(),
+        source: withdraw_reserve_collateral_supply_info.clone(),
+        amount: collateral_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    if repay_reserve_info.key != withdraw_reserve_info.key {
+        spl_token_transfer(TokenTransferParams {
+            source: withdraw_reserve_liquidity_supply_info.clone(),
+            destination: repay_reserve_liquidity_supply_info.clone(),
+            amount: repay_amount,
+            authority: lending_market_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_id.clone(),
+        })?;
+    }
+    record_reserve_activity(
+        reserve_activity_infos,
+        repay_reserve_info,
+        ReserveAction::Repay,
+        repay_amount,
+    )?;
+
+    Ok(())
+}
+
+pub(super) fn process_buy_rate_cap(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let insurance_fund_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let borrow_reserve = Box::new(Reserve::unpack(&borrow_reserve_info.data.borrow())?);
+    validate_reserve_account(
+        program_id,
+        borrow_reserve_info,
+        &borrow_reserve,
+        lending_market_info,
+    )?;
+    if borrow_reserve.config.borrow_rate_cap == 0 {
+        msg!("Borrow reserve doesn't offer rate caps");
+        return Err(LendingError::RateCapNotOffered.into());
+    }
+    validate_insurance_fund(
+        program_id,
+        insurance_fund_info,
+        lending_market_info,
+        &borrow_reserve,
+    )?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+    if obligation.last_update.is_stale(clock.slot)? {
+        msg!("Obligation is stale and must be refreshed in the current slot");
+        return Err(LendingError::ObligationStale.into());
+    }
+
+    let (liquidity, _liquidity_index) =
+        obligation.find_liquidity_in_borrows_mut(*borrow_reserve_info.key)?;
+    if liquidity.borrowed_amount_wads == Decimal::zero() {
+        msg!("Liquidity borrowed amount is zero");
+        return Err(LendingError::ObligationLiquidityEmpty.into());
+    }
+    if liquidity.rate_cap > 0 {
+        msg!("Borrow already has a rate cap");
+        return Err(LendingError::BorrowRateCapped.into());
+    }
+
+    let premium = liquidity
+        .borrowed_amount_wads
+        .try_mul(Decimal::from_bps(
+            borrow_reserve.config.rate_cap_premium_bps as u64,
+        ))?
+        .try_ceil_u64()?;
+    liquidity.rate_cap = borrow_reserve.config.borrow_rate_cap;
+    liquidity.rate_cap_slot = clock.slot;
+    msg!(
+        "Rate cap of {}% bought for a premium of {}",
+        liquidity.rate_cap,
+        premium
+    );
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    if premium > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: source_liquidity_info.clone(),
+            destination: insurance_fund_info.clone(),
+            amount: premium,
+            authority: user_transfer_authority_info.clone(),
+            authority_signer_seeds: &[],
+            token_program: token_program_id.clone(),
+        })?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn process_claim_rate_cap_credit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let insurance_fund_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.supply_pubkey != reserve_liquidity_supply_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    validate_insurance_fund(
+        program_id,
+        insurance_fund_info,
+        lending_market_info,
+        &reserve,
+    )?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+
+    let (liquidity, liquidity_index) =
+        obligation.find_liquidity_in_borrows_mut(*reserve_info.key)?;
+    liquidity.settle_rewards(&reserve)?;
+    liquidity
+        .accrue_interest_with_rate_cap(reserve.liquidity.cumulative_borrow_rate_wads, clock.slot)?;
+
+    // the reserve still counts the interest above the cap as borrowed, so the insurance fund
+    // repays it to the reserve on the borrow's behalf
+    let insurance_fund_amount = unpack_token_account(&insurance_fund_info.data.borrow())?.amount;
+    let CalculateRepayResult {
+        settle_amount,
+        repay_amount,
+    } = reserve.calculate_repay(insurance_fund_amount, liquidity.rate_cap_credit_wads)?;
+    if repay_amount == 0 {
+        msg!("No rate cap credit the insurance fund can repay");
+        return Err(LendingError::InvalidAmount.into());
+    }
+    liquidity.rate_cap_credit_wads = liquidity.rate_cap_credit_wads.try_sub(settle_amount)?;
+    if liquidity.is_settled() {
+        obligation.borrows.remove(liquidity_index);
+    }
+
+    reserve.liquidity.repay(repay_amount, settle_amount)?;
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    obligation.last_update.mark_stale();
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: insurance_fund_info.clone(),
+        destination: reserve_liquidity_supply_info.clone(),
+        amount: repay_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+
+    Ok(())
+}
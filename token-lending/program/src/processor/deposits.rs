@@ -0,0 +1,1255 @@
+//! Depositing and redeeming reserve liquidity and obligation collateral
+
+use super::*;
+
+pub(super) fn process_deposit_reserve_liquidity(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    min_collateral_out: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        user_transfer_authority_info,
+        market_member_info,
+    )?;
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let CTokenAmount(collateral_amount) = _deposit_reserve_liquidity(
+        program_id,
+        TokenAmount(liquidity_amount),
+        source_liquidity_info,
+        destination_collateral_info,
+        reserve_info,
+        reserve_liquidity_supply_info,
+        reserve_collateral_mint_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    if collateral_amount < min_collateral_out {
+        msg!(
+            "Deposit would mint {} collateral, less than the minimum of {}",
+            collateral_amount,
+            min_collateral_out
+        );
+        return Err(LendingError::ExchangeRateSlippageExceeded.into());
+    }
+    record_reserve_activity(
+        reserve_activity_infos,
+        reserve_info,
+        ReserveAction::Deposit,
+        liquidity_amount,
+    )?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _deposit_reserve_liquidity<'a>(
+    program_id: &Pubkey,
+    TokenAmount(liquidity_amount): TokenAmount,
+    source_liquidity_info: &AccountInfo<'a>,
+    destination_collateral_info: &AccountInfo<'a>,
+    reserve_info: &AccountInfo<'a>,
+    reserve_liquidity_supply_info: &AccountInfo<'a>,
+    reserve_collateral_mint_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    lending_market_authority_info: &AccountInfo<'a>,
+    user_transfer_authority_info: &AccountInfo<'a>,
+    clock: &Clock,
+    token_program_id: &AccountInfo<'a>,
+) -> Result<CTokenAmount, ProgramError> {
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.supply_pubkey != reserve_liquidity_supply_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.collateral.mint_pubkey != reserve_collateral_mint_info.key {
+        msg!("Reserve collateral mint does not match the reserve collateral mint provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey == source_liquidity_info.key {
+        msg!("Reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.collateral.supply_pubkey == destination_collateral_info.key {
+        msg!("Reserve collateral supply cannot be used as the destination collateral provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if reserve.last_update.is_stale(clock.slot)? {
+        msg!("Reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+    if reserve.config.deposits_paused {
+        msg!("Deposits are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    let lending_market_authority_pubkey =
+        lending_market.authority(program_id, lending_market_info.key)?;
+    if &lending_market_authority_pubkey != lending_market_authority_info.key {
+        msg!(
+            "Derived lending market authority {} does not match the lending market authority provided {}",
+            &lending_market_authority_pubkey.to_string(),
+            &lending_market_authority_info.key.to_string(),
+        );
+        return Err(LendingError::InvalidMarketAuthority.into());
+    }
+
+    let remaining_deposit_capacity = reserve.remaining_deposit_capacity()?;
+    if liquidity_amount > remaining_deposit_capacity {
+        msg!(
+            "Cannot deposit liquidity above the reserve deposit limit, the reserve can take {} more",
+            remaining_deposit_capacity
+        );
+        return Err(LendingError::DepositLimitExceeded.into());
+    }
+
+    // lamports sent to a wSOL account only become tokens once it's synced, which wallets often
+    // leave out of the transaction
+    if reserve.liquidity.mint_pubkey == spl_token::native_mint::id()
+        && unpack_token_account(&source_liquidity_info.data.borrow())?.is_native()
+    {
+        spl_token_sync_native(source_liquidity_info.clone(), token_program_id.clone())?;
+    }
+
+    let collateral_amount = reserve.deposit_liquidity(liquidity_amount)?;
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: reserve_liquidity_supply_info.clone(),
+        amount: liquidity_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_mint_to(TokenMintToParams {
+        mint: reserve_collateral_mint_info.clone(),
+        destination: destination_collateral_info.clone(),
+        amount: collateral_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    DepositEvent {
+        kind: DepositKind::Deposit,
+        reserve: *reserve_info.key,
+        authority: *user_transfer_authority_info.key,
+        liquidity_amount,
+        collateral_amount,
+    }
+    .log();
+
+    Ok(CTokenAmount(collateral_amount))
+}
+
+pub(super) fn process_redeem_reserve_collateral(
+    program_id: &Pubkey,
+    collateral_amount: u64,
+    min_liquidity_out: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if collateral_amount == 0 {
+        msg!("Collateral amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_collateral_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let liquidity_amount = _redeem_reserve_collateral(
+        program_id,
+        CTokenAmount(collateral_amount),
+        None,
+        source_collateral_info,
+        destination_liquidity_info,
+        reserve_info,
+        reserve_collateral_mint_info,
+        reserve_liquidity_supply_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+        true,
+    )?;
+    if liquidity_amount.0 < min_liquidity_out {
+        msg!(
+            "Redeem would receive {} liquidity, less than the minimum of {}",
+            liquidity_amount.0,
+            min_liquidity_out
+        );
+        return Err(LendingError::ExchangeRateSlippageExceeded.into());
+    }
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    record_reserve_activity(
+        reserve_activity_infos,
+        reserve_info,
+        ReserveAction::Withdraw,
+        liquidity_amount.0,
+    )?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn _redeem_reserve_collateral<'a>(
+    program_id: &Pubkey,
+    CTokenAmount(collateral_amount): CTokenAmount,
+    exact_liquidity_amount: Option<u64>,
+    source_collateral_info: &AccountInfo<'a>,
+    destination_liquidity_info: &AccountInfo<'a>,
+    reserve_info: &AccountInfo<'a>,
+    reserve_collateral_mint_info: &AccountInfo<'a>,
+    reserve_liquidity_supply_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    lending_market_authority_info: &AccountInfo<'a>,
+    user_transfer_authority_info: &AccountInfo<'a>,
+    clock: &Clock,
+    token_program_id: &AccountInfo<'a>,
+    check_rate_limits: bool,
+) -> Result<TokenAmount, ProgramError> {
+    let mut lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.collateral.mint_pubkey != reserve_collateral_mint_info.key {
+        msg!("Reserve collateral mint does not match the reserve collateral mint provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.collateral.supply_pubkey == source_collateral_info.key {
+        msg!("Reserve collateral supply cannot be used as the source collateral provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey != reserve_liquidity_supply_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey == destination_liquidity_info.key {
+        msg!("Reserve liquidity supply cannot be used as the destination liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if reserve.last_update.is_stale(clock.slot)? {
+        msg!("Reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+    // liquidations redeem without rate limits and have to keep working while withdrawals are
+    // paused. Compounding redeems the same way since the liquidity never leaves the reserve.
+    if check_rate_limits && reserve.config.withdrawals_paused {
+        msg!("Withdrawals are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    let liquidity_amount = match exact_liquidity_amount {
+        Some(liquidity_amount) => {
+            reserve.redeem_collateral_exact(collateral_amount, liquidity_amount)?
+        }
+        None => reserve.redeem_collateral(collateral_amount)?,
+    };
+
+    if check_rate_limits {
+        lending_market
+            .rate_limiter
+            .update(
+                clock.slot,
+                reserve.market_value_upper_bound(Decimal::from(liquidity_amount))?,
+            )
+            .inspect_err(|_| {
+                msg!("Market outflow limit exceeded! Please try again later.");
+            })?;
+
+        reserve
+            .rate_limiter
+            .update(clock.slot, Decimal::from(liquidity_amount))
+            .inspect_err(|_| {
+                msg!("Reserve outflow limit exceeded! Please try again later.");
+            })?;
+    }
+
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    LendingMarket::pack(lending_market, &mut lending_market_info.data.borrow_mut())?;
+
+    spl_token_burn(TokenBurnParams {
+        mint: reserve_collateral_mint_info.clone(),
+        source: source_collateral_info.clone(),
+        amount: collateral_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: reserve_liquidity_supply_info.clone(),
+        destination: destination_liquidity_info.clone(),
+        amount: liquidity_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    DepositEvent {
+        kind: DepositKind::Redeem,
+        reserve: *reserve_info.key,
+        authority: *user_transfer_authority_info.key,
+        liquidity_amount,
+        collateral_amount,
+    }
+    .log();
+
+    Ok(TokenAmount(liquidity_amount))
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_deposit_obligation_collateral(
+    program_id: &Pubkey,
+    collateral_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if collateral_amount == 0 {
+        msg!("Collateral amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_collateral_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let deposit_reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        obligation_owner_info,
+        market_member_info,
+    )?;
+
+    _refresh_reserve_interest(program_id, deposit_reserve_info, clock)?;
+    _deposit_obligation_collateral(
+        program_id,
+        CTokenAmount(collateral_amount),
+        source_collateral_info,
+        destination_collateral_info,
+        deposit_reserve_info,
+        obligation_info,
+        lending_market_info,
+        obligation_owner_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    let mut reserve = Box::new(Reserve::unpack(&deposit_reserve_info.data.borrow())?);
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut deposit_reserve_info.data.borrow_mut())?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _deposit_obligation_collateral<'a>(
+    program_id: &Pubkey,
+    CTokenAmount(collateral_amount): CTokenAmount,
+    source_collateral_info: &AccountInfo<'a>,
+    destination_collateral_info: &AccountInfo<'a>,
+    deposit_reserve_info: &AccountInfo<'a>,
+    obligation_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    obligation_owner_info: &AccountInfo<'a>,
+    user_transfer_authority_info: &AccountInfo<'a>,
+    clock: &Clock,
+    token_program_id: &AccountInfo<'a>,
+) -> ProgramResult {
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let deposit_reserve = Box::new(Reserve::unpack(&deposit_reserve_info.data.borrow())?);
+    if deposit_reserve_info.owner != program_id {
+        msg!("Deposit reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &deposit_reserve.lending_market != lending_market_info.key {
+        msg!("Deposit reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &deposit_reserve.collateral.supply_pubkey == source_collateral_info.key {
+        msg!("Deposit reserve collateral supply cannot be used as the source collateral provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &deposit_reserve.collateral.supply_pubkey != destination_collateral_info.key {
+        msg!(
+            "Deposit reserve collateral supply must be used as the destination collateral provided"
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if deposit_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Deposit reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+    if deposit_reserve.config.deposits_paused {
+        msg!("Deposits are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+    if deposit_reserve.config.reserve_type == ReserveType::Isolated {
+        msg!("Isolated tier assets can't be deposited as obligation collateral");
+        return Err(LendingError::IsolatedTierAssetViolation.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+
+    let collateral = obligation.find_or_add_collateral_to_deposits(*deposit_reserve_info.key)?;
+    collateral.settle_rewards(&deposit_reserve)?;
+    collateral.deposit(collateral_amount)?;
+    let deposited_amount = collateral.deposited_amount;
+    obligation.last_update.mark_stale();
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+    spl_token_transfer(TokenTransferParams {
+        source: source_collateral_info.clone(),
+        destination: destination_collateral_info.clone(),
+        amount: collateral_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+    log_obligation_collateral(
+        ObligationCollateralKind::Deposit,
+        obligation_info.key,
+        deposit_reserve_info.key,
+        deposit_reserve.collateral_exchange_rate()?,
+        collateral_amount,
+        deposited_amount,
+    )?;
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_deposit_reserve_liquidity_and_obligation_collateral(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let user_collateral_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let _pyth_price_info = next_account_info(account_info_iter)?;
+    let _switchboard_feed_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        obligation_owner_info,
+        market_member_info,
+    )?;
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let collateral_amount = _deposit_reserve_liquidity(
+        program_id,
+        TokenAmount(liquidity_amount),
+        source_liquidity_info,
+        user_collateral_info,
+        reserve_info,
+        reserve_liquidity_supply_info,
+        reserve_collateral_mint_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    _deposit_obligation_collateral(
+        program_id,
+        collateral_amount,
+        user_collateral_info,
+        destination_collateral_info,
+        reserve_info,
+        obligation_info,
+        lending_market_info,
+        obligation_owner_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    // mark the reserve as stale to make sure no weird bugs happen
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    record_reserve_activity(
+        reserve_activity_infos,
+        reserve_info,
+        ReserveAction::Deposit,
+        liquidity_amount,
+    )?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+    program_id: &Pubkey,
+    deposit_liquidity_amount: u64,
+    borrow_liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if deposit_liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let user_collateral_info = next_account_info(account_info_iter)?;
+    let deposit_reserve_info = next_account_info(account_info_iter)?;
+    let deposit_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let deposit_reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let borrow_reserve_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        obligation_owner_info,
+        market_member_info,
+    )?;
+
+    _refresh_reserve_interest(program_id, deposit_reserve_info, clock)?;
+    let collateral_amount = _deposit_reserve_liquidity(
+        program_id,
+        TokenAmount(deposit_liquidity_amount),
+        source_liquidity_info,
+        user_collateral_info,
+        deposit_reserve_info,
+        deposit_reserve_liquidity_supply_info,
+        deposit_reserve_collateral_mint_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    _refresh_reserve_interest(program_id, deposit_reserve_info, clock)?;
+    _deposit_obligation_collateral(
+        program_id,
+        collateral_amount,
+        user_collateral_info,
+        destination_collateral_info,
+        deposit_reserve_info,
+        obligation_info,
+        lending_market_info,
+        obligation_owner_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    // the deposit reserve was refreshed earlier in this slot, so this only clears the stale flag
+    // the deposit set and lets the obligation be refreshed against it
+    _refresh_reserve_interest(program_id, deposit_reserve_info, clock)?;
+
+    let obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    let obligation_reserves_len = obligation.deposits.len() + obligation.borrows.len();
+    let remaining_infos = account_info_iter.as_slice();
+    if remaining_infos.len() < obligation_reserves_len {
+        msg!("Every deposit and borrow reserve of the obligation must be provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    let (obligation_reserve_infos, host_fee_infos) =
+        remaining_infos.split_at(obligation_reserves_len);
+
+    let mut refresh_obligation_infos = vec![obligation_info.clone()];
+    refresh_obligation_infos.extend_from_slice(obligation_reserve_infos);
+    process_refresh_obligation(program_id, &refresh_obligation_infos)?;
+
+    // refreshing drops empty deposits, so the borrow gets as many deposit reserves as remain
+    let obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    let mut borrow_infos = vec![
+        borrow_reserve_liquidity_supply_info.clone(),
+        destination_liquidity_info.clone(),
+        borrow_reserve_info.clone(),
+        borrow_reserve_liquidity_fee_receiver_info.clone(),
+        obligation_info.clone(),
+        lending_market_info.clone(),
+        lending_market_authority_info.clone(),
+        obligation_owner_info.clone(),
+        token_program_id.clone(),
+    ];
+    borrow_infos.extend_from_slice(&obligation_reserve_infos[..obligation.deposits.len()]);
+    borrow_infos.extend_from_slice(host_fee_infos);
+    borrow_infos.extend_from_slice(reserve_activity_infos);
+    borrow_infos.extend(market_member_info.cloned());
+    process_borrow_obligation_liquidity(program_id, borrow_liquidity_amount, 0, &borrow_infos)?;
+
+    // mark the reserve as stale to make sure no weird bugs happen
+    let mut deposit_reserve = Box::new(Reserve::unpack(&deposit_reserve_info.data.borrow())?);
+    deposit_reserve.last_update.mark_stale();
+    Reserve::pack(
+        *deposit_reserve,
+        &mut deposit_reserve_info.data.borrow_mut(),
+    )?;
+    record_reserve_activity(
+        reserve_activity_infos,
+        deposit_reserve_info,
+        ReserveAction::Deposit,
+        deposit_liquidity_amount,
+    )?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_borrow_obligation_liquidity_with_wallet_collateral(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let user_collateral_info = next_account_info(account_info_iter)?;
+    let deposit_reserve_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let borrow_reserve_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let borrow_reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        obligation_owner_info,
+        market_member_info,
+    )?;
+
+    // cTokens left in the wallet don't count towards the borrow limit, so collateralize them first
+    let user_collateral = unpack_token_account(&user_collateral_info.data.borrow())?;
+    if user_collateral.amount > 0 {
+        _deposit_obligation_collateral(
+            program_id,
+            CTokenAmount(user_collateral.amount),
+            user_collateral_info,
+            destination_collateral_info,
+            deposit_reserve_info,
+            obligation_info,
+            lending_market_info,
+            obligation_owner_info,
+            user_transfer_authority_info,
+            clock,
+            token_program_id,
+        )?;
+    }
+
+    let obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    let obligation_reserves_len = obligation.deposits.len() + obligation.borrows.len();
+    let remaining_infos = account_info_iter.as_slice();
+    if remaining_infos.len() < obligation_reserves_len {
+        msg!("Every deposit and borrow reserve of the obligation must be provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    let (obligation_reserve_infos, host_fee_infos) =
+        remaining_infos.split_at(obligation_reserves_len);
+
+    let mut refresh_obligation_infos = vec![obligation_info.clone()];
+    refresh_obligation_infos.extend_from_slice(obligation_reserve_infos);
+    process_refresh_obligation(program_id, &refresh_obligation_infos)?;
+
+    // the refresh may have dropped empty deposits
+    let obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    let mut borrow_infos = vec![
+        borrow_reserve_liquidity_supply_info.clone(),
+        destination_liquidity_info.clone(),
+        borrow_reserve_info.clone(),
+        borrow_reserve_liquidity_fee_receiver_info.clone(),
+        obligation_info.clone(),
+        lending_market_info.clone(),
+        lending_market_authority_info.clone(),
+        obligation_owner_info.clone(),
+        token_program_id.clone(),
+    ];
+    borrow_infos.extend_from_slice(&obligation_reserve_infos[..obligation.deposits.len()]);
+    borrow_infos.extend_from_slice(host_fee_infos);
+    borrow_infos.extend_from_slice(reserve_activity_infos);
+    borrow_infos.extend(market_member_info.cloned());
+    process_borrow_obligation_liquidity(program_id, liquidity_amount, 0, &borrow_infos)
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_compound_obligation_collateral(
+    program_id: &Pubkey,
+    collateral_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if collateral_amount == 0 {
+        msg!("Collateral amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let user_collateral_info = next_account_info(account_info_iter)?;
+    let user_liquidity_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let collateral_amount = if collateral_amount == u64::MAX {
+        let user_collateral = unpack_token_account(&user_collateral_info.data.borrow())?;
+        if user_collateral.amount == 0 {
+            msg!("User collateral token account has no collateral to compound");
+            return Err(LendingError::InvalidAmount.into());
+        }
+        user_collateral.amount
+    } else {
+        collateral_amount
+    };
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let liquidity_amount = _redeem_reserve_collateral(
+        program_id,
+        CTokenAmount(collateral_amount),
+        None,
+        user_collateral_info,
+        user_liquidity_info,
+        reserve_info,
+        reserve_collateral_mint_info,
+        reserve_liquidity_supply_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+        false,
+    )?;
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let collateral_amount = _deposit_reserve_liquidity(
+        program_id,
+        liquidity_amount,
+        user_liquidity_info,
+        user_collateral_info,
+        reserve_info,
+        reserve_liquidity_supply_info,
+        reserve_collateral_mint_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    _deposit_obligation_collateral(
+        program_id,
+        collateral_amount,
+        user_collateral_info,
+        destination_collateral_info,
+        reserve_info,
+        obligation_info,
+        lending_market_info,
+        obligation_owner_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+    )?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_withdraw_obligation_collateral(
+    program_id: &Pubkey,
+    collateral_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if collateral_amount == 0 {
+        msg!("Collateral amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let source_collateral_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    // withdrawing can't make an obligation without borrows unhealthy, so a stale withdraw reserve
+    // only needs its interest accrued. this lets users exit without a working oracle.
+    let accrue_only = Obligation::unpack_account(&obligation_info.data.borrow())?
+        .borrows
+        .is_empty()
+        && Reserve::unpack(&withdraw_reserve_info.data.borrow())?
+            .last_update
+            .is_stale(clock.slot)?;
+    if accrue_only {
+        _refresh_reserve_interest(program_id, withdraw_reserve_info, clock)?;
+    }
+
+    _withdraw_obligation_collateral(
+        program_id,
+        CTokenAmount(collateral_amount),
+        source_collateral_info,
+        destination_collateral_info,
+        withdraw_reserve_info,
+        obligation_info,
+        lending_market_info,
+        lending_market_authority_info,
+        obligation_owner_info,
+        clock,
+        token_program_id,
+        false,
+        &accounts[8..],
+    )?;
+
+    if accrue_only {
+        let mut withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+        withdraw_reserve.last_update.mark_stale();
+        Reserve::pack(
+            *withdraw_reserve,
+            &mut withdraw_reserve_info.data.borrow_mut(),
+        )?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _withdraw_obligation_collateral<'a>(
+    program_id: &Pubkey,
+    CTokenAmount(collateral_amount): CTokenAmount,
+    source_collateral_info: &AccountInfo<'a>,
+    destination_collateral_info: &AccountInfo<'a>,
+    withdraw_reserve_info: &AccountInfo<'a>,
+    obligation_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    lending_market_authority_info: &AccountInfo<'a>,
+    obligation_owner_info: &AccountInfo<'a>,
+    clock: &Clock,
+    token_program_id: &AccountInfo<'a>,
+    account_for_rate_limiter: bool,
+    deposit_reserve_infos: &[AccountInfo],
+) -> Result<CTokenAmount, ProgramError> {
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+    if withdraw_reserve_info.owner != program_id {
+        msg!("Withdraw reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &withdraw_reserve.lending_market != lending_market_info.key {
+        msg!("Withdraw reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.collateral.supply_pubkey != source_collateral_info.key {
+        msg!("Withdraw reserve collateral supply must be used as the source collateral provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.collateral.supply_pubkey == destination_collateral_info.key {
+        msg!("Withdraw reserve collateral supply cannot be used as the destination collateral provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if withdraw_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Withdraw reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+    if withdraw_reserve.config.withdrawals_paused {
+        msg!("Withdrawals are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+    if obligation.borrows.is_empty() {
+        // nothing to attribute, and the obligation may not have been refreshed since the last repay
+        obligation.borrowed_value = Decimal::zero();
+        obligation.borrowed_value_upper_bound = Decimal::zero();
+        obligation.unweighted_borrowed_value = Decimal::zero();
+    } else if obligation.last_update.is_stale(clock.slot)? {
+        msg!("Obligation is stale and must be refreshed in the current slot");
+        return Err(LendingError::ObligationStale.into());
+    }
+    if withdraw_reserve.emergency_price_active && !obligation.borrows.is_empty() {
+        msg!("Withdraw reserve is priced from its emergency price and the obligation has borrows");
+        return Err(LendingError::ReserveEmergencyMode.into());
+    }
+    let obligation_rate_limited =
+        sync_obligation_rate_limiter(&lending_market, &mut obligation, clock);
+    let obligation_max_outflow_usd = if obligation_rate_limited {
+        Some(obligation.rate_limiter.remaining_outflow(clock.slot)?)
+    } else {
+        None
+    };
+
+    let (collateral, collateral_index) =
+        obligation.find_collateral_in_deposits(*withdraw_reserve_info.key)?;
+    if collateral.deposited_amount == 0 {
+        msg!("Collateral deposited amount is zero");
+        return Err(LendingError::ObligationCollateralEmpty.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    // account for lending market and reserve rate limiter when withdrawing. this is needed to
+    // support max withdraws.
+    let max_outflow_collateral_amount = if account_for_rate_limiter {
+        let mut max_outflow_usd = lending_market
+            .rate_limiter
+            .max_outflow_remaining(clock.slot)?;
+        if let Some(obligation_max_outflow_usd) = obligation_max_outflow_usd {
+            max_outflow_usd = min(max_outflow_usd, obligation_max_outflow_usd);
+        }
+
+        let max_lending_market_outflow_liquidity_amount = withdraw_reserve
+            .usd_to_liquidity_amount_lower_bound(min(
+                max_outflow_usd,
+                // min here bc this function can overflow if max_outflow_usd is u64::MAX
+                // the actual value doesn't matter too much as long as its sensible
+                obligation.deposited_value.try_mul(2)?,
+            ))?;
+
+        let max_reserve_outflow_liquidity_amount = withdraw_reserve
+            .rate_limiter
+            .max_outflow_remaining(clock.slot)?;
+
+        let max_outflow_liquidity_amount = min(
+            max_lending_market_outflow_liquidity_amount,
+            max_reserve_outflow_liquidity_amount,
+        );
+
+        withdraw_reserve
+            .collateral_exchange_rate()?
+            .decimal_liquidity_to_collateral(max_outflow_liquidity_amount)?
+            .try_floor_u64()?
+    } else if let Some(obligation_max_outflow_usd) = obligation_max_outflow_usd {
+        // collateral withdrawn to the wallet can be redeemed right away, so it counts against the
+        // obligation's outflow as well
+        let max_outflow_liquidity_amount =
+            withdraw_reserve.usd_to_liquidity_amount_lower_bound(min(
+                obligation_max_outflow_usd,
+                obligation.deposited_value.try_mul(2)?,
+            ))?;
+
+        withdraw_reserve
+            .collateral_exchange_rate()?
+            .decimal_liquidity_to_collateral(max_outflow_liquidity_amount)?
+            .try_floor_u64()?
+    } else {
+        u64::MAX
+    };
+
+    let max_withdraw_amount = obligation.max_withdraw_amount(collateral, &withdraw_reserve)?;
+    let withdraw_amount = min(
+        collateral_amount,
+        min(max_withdraw_amount, max_outflow_collateral_amount),
+    );
+
+    if withdraw_amount == 0 {
+        msg!("Maximum withdraw value is zero");
+        return Err(LendingError::WithdrawTooLarge.into());
+    }
+
+    let withdraw_liquidity_amount = withdraw_reserve
+        .collateral_exchange_rate()?
+        .decimal_collateral_to_liquidity(Decimal::from(withdraw_amount))?;
+    let withdraw_value = withdraw_reserve.market_value(withdraw_liquidity_amount)?;
+
+    update_obligation_rate_limiter(
+        &mut obligation,
+        obligation_rate_limited,
+        clock,
+        withdraw_reserve.market_value_upper_bound(withdraw_liquidity_amount)?,
+    )?;
+
+    // update relevant values before updating borrow attribution values
+    obligation.deposited_value = obligation.deposited_value.saturating_sub(withdraw_value);
+
+    obligation.deposits[collateral_index].market_value = obligation.deposits[collateral_index]
+        .market_value
+        .saturating_sub(withdraw_value);
+
+    let (open_exceeded, _) =
+        update_borrow_attribution_values(&mut obligation, deposit_reserve_infos)?;
+    if let Some(reserve_pubkey) = open_exceeded {
+        msg!(
+            "Open borrow attribution limit exceeded for reserve {:?}",
+            reserve_pubkey
+        );
+        return Err(LendingError::BorrowAttributionLimitExceeded.into());
+    }
+
+    // obligation.withdraw must be called after updating borrow attribution values, since we can
+    // lose information if an entire deposit is removed, making the former calculation incorrect
+    let deposited_amount = obligation.deposits[collateral_index]
+        .deposited_amount
+        .saturating_sub(withdraw_amount);
+    obligation.deposits[collateral_index].settle_rewards(&withdraw_reserve)?;
+    obligation.withdraw(withdraw_amount, collateral_index)?;
+    obligation.last_update.mark_stale();
+
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_collateral_info.clone(),
+        destination: destination_collateral_info.clone(),
+        amount: withdraw_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    log_obligation_collateral(
+        ObligationCollateralKind::Withdraw,
+        obligation_info.key,
+        withdraw_reserve_info.key,
+        withdraw_reserve.collateral_exchange_rate()?,
+        withdraw_amount,
+        deposited_amount,
+    )?;
+
+    Ok(CTokenAmount(withdraw_amount))
+}
+
+pub(super) fn process_withdraw_obligation_collateral_and_redeem_reserve_liquidity(
+    program_id: &Pubkey,
+    collateral_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    _withdraw_obligation_collateral_and_redeem_reserve_liquidity(
+        program_id,
+        collateral_amount,
+        None,
+        accounts,
+    )
+}
+
+#[inline(never)] // avoid stack frame limit
+fn _withdraw_obligation_collateral_and_redeem_reserve_liquidity(
+    program_id: &Pubkey,
+    collateral_amount: u64,
+    exact_liquidity_amount: Option<u64>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let reserve_collateral_info = next_account_info(account_info_iter)?;
+    let user_collateral_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let user_liquidity_info = next_account_info(account_info_iter)?;
+    let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    let withdrawn_collateral_amount = _withdraw_obligation_collateral(
+        program_id,
+        CTokenAmount(collateral_amount),
+        reserve_collateral_info,
+        user_collateral_info,
+        reserve_info,
+        obligation_info,
+        lending_market_info,
+        lending_market_authority_info,
+        obligation_owner_info,
+        clock,
+        token_program_id,
+        true,
+        &accounts[12..],
+    )?;
+
+    let liquidity_amount = _redeem_reserve_collateral(
+        program_id,
+        withdrawn_collateral_amount,
+        exact_liquidity_amount,
+        user_collateral_info,
+        user_liquidity_info,
+        reserve_info,
+        reserve_collateral_mint_info,
+        reserve_liquidity_supply_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        clock,
+        token_program_id,
+        true,
+    )?;
+    record_reserve_activity(
+        reserve_activity_infos,
+        reserve_info,
+        ReserveAction::Withdraw,
+        liquidity_amount.0,
+    )?;
+
+    Ok(())
+}
+
+pub(super) fn process_withdraw_obligation_liquidity_exact(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == u64::MAX {
+        return process_withdraw_obligation_collateral_and_redeem_reserve_liquidity(
+            program_id,
+            u64::MAX,
+            accounts,
+        );
+    }
+
+    // the withdraw fails unless the reserve was refreshed this slot, so its exchange rate is the
+    // current one
+    let reserve_info = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    let collateral_amount = reserve
+        .collateral_exchange_rate()?
+        .liquidity_to_collateral_ceil(liquidity_amount)?;
+
+    // rounding the collateral up can make it worth slightly more than liquidity_amount. Exactly
+    // liquidity_amount is paid out and the excess stays in the reserve.
+    _withdraw_obligation_collateral_and_redeem_reserve_liquidity(
+        program_id,
+        collateral_amount,
+        Some(liquidity_amount),
+        accounts,
+    )
+}
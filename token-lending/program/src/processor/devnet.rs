@@ -0,0 +1,116 @@
+//! Test token faucet and settable prices for devnet and localnet markets. Only compiled with
+//! the devnet-utils feature, which mainnet builds must never enable.
+
+use super::*;
+use crate::state::{DevnetPrice, DEVNET_FAUCET_SEED, DEVNET_PRICE_SEED};
+
+/// Mints test tokens of a mint whose authority is the devnet faucet. Permissionless.
+pub(super) fn process_devnet_mint_test_tokens(
+    program_id: &Pubkey,
+    amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if amount == 0 {
+        msg!("Amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+    let mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let devnet_faucet_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    if token_program_id.key != &spl_token::id() {
+        msg!("Token program provided is not the spl token program");
+        return Err(LendingError::InvalidTokenProgram.into());
+    }
+    let (devnet_faucet_authority_key, bump_seed) =
+        Pubkey::find_program_address(&[DEVNET_FAUCET_SEED], program_id);
+    if devnet_faucet_authority_key != *devnet_faucet_authority_info.key {
+        msg!("Provided devnet faucet authority does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    spl_token_mint_to(TokenMintToParams {
+        mint: mint_info.clone(),
+        destination: destination_info.clone(),
+        amount,
+        authority: devnet_faucet_authority_info.clone(),
+        authority_signer_seeds: &[DEVNET_FAUCET_SEED, &[bump_seed]],
+        token_program: token_program_id.clone(),
+    })
+}
+
+/// Creates or updates the devnet price of a mint. Permissionless.
+pub(super) fn process_devnet_set_price(
+    program_id: &Pubkey,
+    price: Decimal,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let devnet_price_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    if price == Decimal::zero() {
+        msg!("Devnet price must be non-zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let devnet_price_seeds = &[mint_info.key.as_ref(), DEVNET_PRICE_SEED];
+    let (devnet_price_key, bump_seed) =
+        Pubkey::find_program_address(devnet_price_seeds, program_id);
+    if devnet_price_key != *devnet_price_info.key {
+        msg!("Provided devnet price account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if devnet_price_info.data_is_empty() {
+        invoke_signed(
+            &create_account(
+                payer_info.key,
+                devnet_price_info.key,
+                Rent::get()?.minimum_balance(DevnetPrice::LEN),
+                DevnetPrice::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), devnet_price_info.clone()],
+            &[&[mint_info.key.as_ref(), DEVNET_PRICE_SEED, &[bump_seed]]],
+        )?;
+    }
+
+    if devnet_price_info.owner != program_id {
+        msg!("Devnet price account provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    msg!("Devnet price for mint {} set to {}", mint_info.key, price);
+    DevnetPrice::pack(
+        DevnetPrice {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            mint: *mint_info.key,
+            price,
+            set_slot: clock.slot,
+        },
+        &mut devnet_price_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// Whether the account is a devnet price account, which reserves can use as their pyth oracle
+pub(super) fn is_devnet_price_account(price_info: &AccountInfo) -> bool {
+    price_info.owner == &solend_program::id() && price_info.data_len() == DevnetPrice::LEN
+}
+
+/// Reads the price of a devnet price account, None for any other account
+pub(super) fn get_devnet_price(price_info: &AccountInfo) -> Result<Option<Decimal>, ProgramError> {
+    if !is_devnet_price_account(price_info) {
+        return Ok(None);
+    }
+    let devnet_price = DevnetPrice::unpack(&price_info.data.borrow())?;
+    Ok(Some(devnet_price.price))
+}
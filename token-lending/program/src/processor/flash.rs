@@ -0,0 +1,446 @@
+//! Flash loans
+
+use super::*;
+
+pub(super) fn process_flash_borrow_reserve_liquidity(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let sysvar_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    _refresh_reserve_interest(program_id, reserve_info, &clock)?;
+    _flash_borrow_reserve_liquidity(
+        program_id,
+        TokenAmount(liquidity_amount),
+        source_liquidity_info,
+        destination_liquidity_info,
+        reserve_info,
+        lending_market_info,
+        lending_market_authority_info,
+        sysvar_info,
+        token_program_id,
+    )?;
+    Ok(())
+}
+
+/// Checks the flash borrows of the transaction from the reserve against its max flash loan
+/// amount, if it has one
+fn validate_flash_loan_cap(
+    program_id: &Pubkey,
+    reserve: &Reserve,
+    reserve_info: &AccountInfo,
+    sysvar_info: &AccountInfo,
+) -> ProgramResult {
+    if reserve.config.max_flash_loan_amount == 0 {
+        return Ok(());
+    }
+
+    let mut total_flash_loan_amount: u64 = 0;
+    let mut i = 0;
+    loop {
+        let ixn = match load_instruction_at_checked(i, sysvar_info) {
+            Ok(ix) => ix,
+            Err(ProgramError::InvalidArgument) => break, // out of bounds
+            Err(e) => {
+                return Err(e);
+            }
+        };
+        i += 1;
+
+        if ixn.program_id != *program_id {
+            continue;
+        }
+        if let LendingInstruction::FlashBorrowReserveLiquidity { liquidity_amount } =
+            LendingInstruction::unpack(ixn.data.as_slice())?
+        {
+            if ixn.accounts[2].pubkey == *reserve_info.key {
+                total_flash_loan_amount = total_flash_loan_amount
+                    .checked_add(liquidity_amount)
+                    .ok_or(LendingError::MathOverflow)?;
+            }
+        }
+    }
+
+    if total_flash_loan_amount > reserve.config.max_flash_loan_amount {
+        msg!(
+            "Flash borrows of {} in this transaction exceed the reserve's max flash loan amount of {}",
+            total_flash_loan_amount,
+            reserve.config.max_flash_loan_amount
+        );
+        return Err(LendingError::FlashLoanTooLarge.into());
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _flash_borrow_reserve_liquidity<'a>(
+    program_id: &Pubkey,
+    TokenAmount(liquidity_amount): TokenAmount,
+    source_liquidity_info: &AccountInfo<'a>,
+    destination_liquidity_info: &AccountInfo<'a>,
+    reserve_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    lending_market_authority_info: &AccountInfo<'a>,
+    sysvar_info: &AccountInfo<'a>,
+    token_program_id: &AccountInfo<'a>,
+) -> ProgramResult {
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.supply_pubkey != source_liquidity_info.key {
+        msg!("Borrow reserve liquidity supply must be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey == destination_liquidity_info.key {
+        msg!(
+            "Borrow reserve liquidity supply cannot be used as the destination liquidity provided"
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    let lending_market_authority_pubkey =
+        lending_market.authority(program_id, lending_market_info.key)?;
+    if &lending_market_authority_pubkey != lending_market_authority_info.key {
+        msg!(
+            "Derived lending market authority {} does not match the lending market authority provided {}",
+            &lending_market_authority_pubkey.to_string(),
+            &lending_market_authority_info.key.to_string(),
+        );
+        return Err(LendingError::InvalidMarketAuthority.into());
+    }
+
+    if reserve.config.fees.flash_loan_fee_wad == u64::MAX {
+        msg!("Flash loans are disabled for this reserve");
+        return Err(LendingError::FlashLoansDisabled.into());
+    }
+    if reserve.emergency_mode {
+        msg!("Reserve is in emergency mode, borrows are blocked");
+        return Err(LendingError::ReserveEmergencyMode.into());
+    }
+    if reserve.config.borrows_paused {
+        msg!("Borrows are paused for this reserve");
+        return Err(LendingError::ReserveOperationPaused.into());
+    }
+
+    // Make sure this isnt a cpi call
+    let current_index = load_current_index_checked(sysvar_info)? as usize;
+    if is_cpi_call(program_id, current_index, sysvar_info)? {
+        msg!("Flash Borrow was called via CPI!");
+        return Err(LendingError::FlashBorrowCpi.into());
+    }
+    validate_flash_loan_cap(program_id, &reserve, reserve_info, sysvar_info)?;
+
+    // Find and validate the flash repay instruction.
+    //
+    // 1. Ensure the instruction is for this program
+    // 2. Ensure the instruction can be unpacked into a LendingInstruction
+    // 3. Ensure that there is exactly one flash repay that points back to this borrow
+    // 4. Ensure that the reserve for the repay matches the borrow
+    // 5. Ensure that the repay amount matches the borrow amount
+    //
+    // Flash borrows and repays belonging to other pairs are allowed anywhere in the transaction,
+    // since every flash repay is tied to its borrow by borrow_instruction_index.
+    //
+    // If all of these conditions are not met, the flash borrow fails.
+    let mut i = current_index;
+    let mut found_repay_ix = false;
+
+    loop {
+        i += 1;
+
+        let ixn = match load_instruction_at_checked(i, sysvar_info) {
+            Ok(ix) => ix,
+            Err(ProgramError::InvalidArgument) => break, // out of bounds
+            Err(e) => {
+                return Err(e);
+            }
+        };
+
+        if ixn.program_id != *program_id {
+            continue;
+        }
+
+        let unpacked = LendingInstruction::unpack(ixn.data.as_slice())?;
+        if let LendingInstruction::FlashRepayReserveLiquidity {
+            liquidity_amount: repay_liquidity_amount,
+            borrow_instruction_index,
+        } = unpacked
+        {
+            if (borrow_instruction_index as usize) != current_index {
+                continue;
+            }
+            if found_repay_ix {
+                msg!("Multiple flash repays not allowed for the same flash borrow");
+                return Err(LendingError::MultipleFlashBorrows.into());
+            }
+            if ixn.accounts[4].pubkey != *reserve_info.key {
+                msg!("Invalid reserve account on flash repay");
+                return Err(LendingError::InvalidFlashRepay.into());
+            }
+            if repay_liquidity_amount != liquidity_amount {
+                msg!("Liquidity amount for flash repay doesn't match borrow");
+                return Err(LendingError::InvalidFlashRepay.into());
+            }
+
+            found_repay_ix = true;
+        }
+    }
+
+    if !found_repay_ix {
+        msg!("No flash repay found");
+        return Err(LendingError::NoFlashRepayFound.into());
+    }
+
+    reserve.liquidity.borrow(Decimal::from(liquidity_amount))?;
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: destination_liquidity_info.clone(),
+        amount: liquidity_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    FlashLoanEvent {
+        kind: FlashLoanKind::Borrow,
+        reserve: *reserve_info.key,
+        token_account: *destination_liquidity_info.key,
+        liquidity_amount,
+    }
+    .log();
+
+    Ok(())
+}
+
+pub(super) fn process_flash_repay_reserve_liquidity(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    borrow_instruction_index: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let host_fee_receiver_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let sysvar_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    _flash_repay_reserve_liquidity(
+        program_id,
+        TokenAmount(liquidity_amount),
+        borrow_instruction_index,
+        source_liquidity_info,
+        destination_liquidity_info,
+        reserve_liquidity_fee_receiver_info,
+        host_fee_receiver_info,
+        reserve_info,
+        lending_market_info,
+        user_transfer_authority_info,
+        sysvar_info,
+        token_program_id,
+    )?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _flash_repay_reserve_liquidity<'a>(
+    program_id: &Pubkey,
+    TokenAmount(liquidity_amount): TokenAmount,
+    borrow_instruction_index: u8,
+    source_liquidity_info: &AccountInfo<'a>,
+    destination_liquidity_info: &AccountInfo<'a>,
+    reserve_liquidity_fee_receiver_info: &AccountInfo<'a>,
+    host_fee_receiver_info: &AccountInfo<'a>,
+    reserve_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    user_transfer_authority_info: &AccountInfo<'a>,
+    sysvar_info: &AccountInfo<'a>,
+    token_program_id: &AccountInfo<'a>,
+) -> ProgramResult {
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+    if &reserve.liquidity.supply_pubkey != destination_liquidity_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.liquidity.supply_pubkey == source_liquidity_info.key {
+        msg!("Reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reserve.config.fee_receiver != reserve_liquidity_fee_receiver_info.key {
+        msg!("Reserve liquidity fee receiver does not match the reserve liquidity fee receiver provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let flash_loan_amount = liquidity_amount;
+
+    let flash_loan_amount_decimal = Decimal::from(flash_loan_amount);
+    let (origination_fee, host_fee) = reserve
+        .config
+        .fees
+        .calculate_flash_loan_fees(flash_loan_amount_decimal)?;
+
+    // Make sure this isnt a cpi call
+    let current_index = load_current_index_checked(sysvar_info)? as usize;
+    if is_cpi_call(program_id, current_index, sysvar_info)? {
+        msg!("Flash Repay was called via CPI!");
+        return Err(LendingError::FlashRepayCpi.into());
+    }
+
+    // validate flash borrow
+    if (borrow_instruction_index as usize) > current_index {
+        msg!(
+            "Flash repay: borrow instruction index {} has to be less than current index {}",
+            borrow_instruction_index,
+            current_index
+        );
+        return Err(LendingError::InvalidFlashRepay.into());
+    }
+
+    let ixn = load_instruction_at_checked(borrow_instruction_index as usize, sysvar_info)?;
+    if ixn.program_id != *program_id {
+        msg!(
+            "Flash repay: supplied instruction index {} doesn't belong to program id {}",
+            borrow_instruction_index,
+            *program_id
+        );
+        return Err(LendingError::InvalidFlashRepay.into());
+    }
+
+    let unpacked = LendingInstruction::unpack(ixn.data.as_slice())?;
+    match unpacked {
+        LendingInstruction::FlashBorrowReserveLiquidity {
+            liquidity_amount: borrow_liquidity_amount,
+        } => {
+            // re-check everything here out of paranoia
+            if ixn.accounts[2].pubkey != *reserve_info.key {
+                msg!("Invalid reserve account on flash repay");
+                return Err(LendingError::InvalidFlashRepay.into());
+            }
+
+            if liquidity_amount != borrow_liquidity_amount {
+                msg!("Liquidity amount for flash repay doesn't match borrow");
+                return Err(LendingError::InvalidFlashRepay.into());
+            }
+        }
+        _ => {
+            msg!("Flash repay: Supplied borrow instruction index is not a flash borrow");
+            return Err(LendingError::InvalidFlashRepay.into());
+        }
+    };
+
+    reserve
+        .liquidity
+        .repay(flash_loan_amount, flash_loan_amount_decimal)?;
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: destination_liquidity_info.clone(),
+        amount: flash_loan_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+    FlashLoanEvent {
+        kind: FlashLoanKind::Repay,
+        reserve: *reserve_info.key,
+        token_account: *source_liquidity_info.key,
+        liquidity_amount: flash_loan_amount,
+    }
+    .log();
+
+    if host_fee > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: source_liquidity_info.clone(),
+            destination: host_fee_receiver_info.clone(),
+            amount: host_fee,
+            authority: user_transfer_authority_info.clone(),
+            authority_signer_seeds: &[],
+            token_program: token_program_id.clone(),
+        })?;
+        log_fee(
+            FeeKind::Host,
+            reserve_info.key,
+            user_transfer_authority_info.key,
+            host_fee_receiver_info.key,
+            host_fee,
+            None,
+        );
+    }
+
+    if origination_fee > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: source_liquidity_info.clone(),
+            destination: reserve_liquidity_fee_receiver_info.clone(),
+            amount: origination_fee,
+            authority: user_transfer_authority_info.clone(),
+            authority_signer_seeds: &[],
+            token_program: token_program_id.clone(),
+        })?;
+        log_fee(
+            FeeKind::FlashLoan,
+            reserve_info.key,
+            user_transfer_authority_info.key,
+            reserve_liquidity_fee_receiver_info.key,
+            origination_fee,
+            None,
+        );
+    }
+
+    Ok(())
+}
+
+fn is_cpi_call(
+    program_id: &Pubkey,
+    current_index: usize,
+    sysvar_info: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    // say the tx looks like:
+    // ix 0
+    //   - ix a
+    //   - ix b
+    //   - ix c
+    // ix 1
+    // and we call "load_current_index_checked" from b, we will get 0. And when we
+    // load_instruction_at_checked(0), we will get ix 0.
+    // tldr; instructions sysvar only stores top-level instructions, never CPI instructions.
+    let current_ixn = load_instruction_at_checked(current_index, sysvar_info)?;
+
+    // the current ixn must match the flash_* ix. otherwise, it's a CPI. Comparing program_ids is a
+    // cheaper way of verifying this property, bc token-lending doesn't allow re-entrancy anywhere.
+    if *program_id != current_ixn.program_id {
+        return Ok(true);
+    }
+
+    if get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Ok(true);
+    }
+
+    Ok(false)
+}
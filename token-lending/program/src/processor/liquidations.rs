@@ -0,0 +1,427 @@
+//! Liquidating unhealthy obligations
+
+use super::*;
+
+#[allow(clippy::too_many_arguments)]
+fn _liquidate_obligation<'a>(
+    program_id: &Pubkey,
+    TokenAmount(liquidity_amount): TokenAmount,
+    source_liquidity_info: &AccountInfo<'a>,
+    destination_collateral_info: &AccountInfo<'a>,
+    repay_reserve_info: &AccountInfo<'a>,
+    repay_reserve_liquidity_supply_info: &AccountInfo<'a>,
+    withdraw_reserve_info: &AccountInfo<'a>,
+    withdraw_reserve_collateral_supply_info: &AccountInfo<'a>,
+    obligation_info: &AccountInfo<'a>,
+    lending_market_info: &AccountInfo<'a>,
+    lending_market_authority_info: &AccountInfo<'a>,
+    user_transfer_authority_info: &AccountInfo<'a>,
+    repay_reserve_liquidity_fee_receiver_info: Option<&AccountInfo<'a>>,
+    liquidator_whitelist_info: Option<&AccountInfo<'a>>,
+    clock: &Clock,
+    token_program_id: &AccountInfo<'a>,
+) -> Result<(TokenAmount, CTokenAmount, Bonus), ProgramError> {
+    let lending_market = Box::new(LendingMarket::unpack(&lending_market_info.data.borrow())?);
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    let mut repay_reserve = Box::new(Reserve::unpack(&repay_reserve_info.data.borrow())?);
+    if repay_reserve_info.owner != program_id {
+        msg!("Repay reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &repay_reserve.lending_market != lending_market_info.key {
+        msg!("Repay reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &repay_reserve.liquidity.supply_pubkey != repay_reserve_liquidity_supply_info.key {
+        msg!("Repay reserve liquidity supply does not match the repay reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &repay_reserve.liquidity.supply_pubkey == source_liquidity_info.key {
+        msg!("Repay reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &repay_reserve.collateral.supply_pubkey == destination_collateral_info.key {
+        msg!(
+            "Repay reserve collateral supply cannot be used as the destination collateral provided"
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if repay_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Repay reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let mut withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+    if withdraw_reserve_info.owner != program_id {
+        msg!("Withdraw reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &withdraw_reserve.lending_market != lending_market_info.key {
+        msg!("Withdraw reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.collateral.supply_pubkey != withdraw_reserve_collateral_supply_info.key {
+        msg!("Withdraw reserve collateral supply does not match the withdraw reserve collateral supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.liquidity.supply_pubkey == source_liquidity_info.key {
+        msg!("Withdraw reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &withdraw_reserve.collateral.supply_pubkey == destination_collateral_info.key {
+        msg!("Withdraw reserve collateral supply cannot be used as the destination collateral provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if withdraw_reserve.last_update.is_stale(clock.slot)? {
+        msg!("Withdraw reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    if obligation.last_update.is_stale(clock.slot)? {
+        msg!("Obligation is stale and must be refreshed in the current slot");
+        return Err(LendingError::ObligationStale.into());
+    }
+    if obligation.deposited_value == Decimal::zero() {
+        msg!("Obligation deposited value is zero");
+        return Err(LendingError::ObligationDepositsZero.into());
+    }
+    if obligation.borrowed_value == Decimal::zero() {
+        msg!("Obligation borrowed value is zero");
+        return Err(LendingError::ObligationBorrowsZero.into());
+    }
+
+    if obligation.borrowed_value < obligation.unhealthy_borrow_value && !obligation.closeable {
+        msg!("Obligation must be unhealthy or marked as closeable to be liquidated");
+        return Err(LendingError::ObligationHealthy.into());
+    }
+    if !obligation.closeable && withdraw_reserve.is_price_outside_liquidation_band()? {
+        msg!("Withdraw reserve spot price is outside its liquidation price band");
+        return Err(LendingError::PriceOutsideLiquidationBand.into());
+    }
+
+    if let Some(liquidator) = lending_market.whitelisted_liquidator {
+        if liquidator != *user_transfer_authority_info.key {
+            msg!("Liquidator is not whitelisted");
+            return Err(LendingError::NotWhitelistedLiquidator.into());
+        }
+    }
+    validate_liquidator_whitelist(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        &obligation,
+        user_transfer_authority_info,
+        liquidator_whitelist_info,
+        clock,
+    )?;
+
+    obligation.settle_rewards(repay_reserve_info.key, &repay_reserve)?;
+    obligation.settle_rewards(withdraw_reserve_info.key, &withdraw_reserve)?;
+
+    let (liquidity, liquidity_index) =
+        obligation.find_liquidity_in_borrows(*repay_reserve_info.key)?;
+    if liquidity.market_value == Decimal::zero() {
+        msg!("Obligation borrow value is zero");
+        return Err(LendingError::ObligationLiquidityEmpty.into());
+    }
+    if liquidity_index != 0 {
+        msg!("Obligation borrow is not the first liquidity in the borrows list");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let (collateral, collateral_index) =
+        obligation.find_collateral_in_deposits(*withdraw_reserve_info.key)?;
+    if collateral.market_value == Decimal::zero() {
+        msg!("Obligation deposit value is zero");
+        return Err(LendingError::ObligationCollateralEmpty.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    let bonus = withdraw_reserve.calculate_bonus(&obligation, clock.slot)?;
+    let CalculateLiquidationResult {
+        settle_amount,
+        repay_amount,
+        withdraw_amount,
+    } = withdraw_reserve.calculate_liquidation(
+        liquidity_amount,
+        &obligation,
+        liquidity,
+        collateral,
+        &bonus,
+        lending_market.dust_borrow_value(),
+    )?;
+
+    if repay_amount == 0 {
+        msg!("Liquidation is too small to transfer liquidity");
+        return Err(LendingError::LiquidationTooSmall.into());
+    }
+    if withdraw_amount == 0 {
+        msg!("Liquidation is too small to receive collateral");
+        return Err(LendingError::LiquidationTooSmall.into());
+    }
+
+    let protocol_liquidity_fee = withdraw_reserve.calculate_protocol_liquidity_fee(repay_amount)?;
+    let protocol_liquidity_fee_receiver_info = if protocol_liquidity_fee > 0 {
+        match repay_reserve_liquidity_fee_receiver_info {
+            Some(fee_receiver_info)
+                if fee_receiver_info.key == &repay_reserve.config.fee_receiver =>
+            {
+                Some(fee_receiver_info)
+            }
+            _ => {
+                msg!("Repay reserve liquidity fee receiver must be provided to pay the protocol liquidation fee in liquidity");
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+        }
+    } else {
+        None
+    };
+
+    let collateral_exchange_rate = withdraw_reserve.collateral_exchange_rate()?;
+    repay_reserve.liquidity.repay(repay_amount, settle_amount)?;
+    repay_reserve.last_update.mark_stale();
+    Reserve::pack(*repay_reserve, &mut repay_reserve_info.data.borrow_mut())?;
+
+    // if there is a full withdraw here (which can happen on a full liquidation), then the borrow
+    // attribution value needs to be updated on the reserve. note that we can't depend on
+    // refresh_obligation to update this correctly because the ObligationCollateral object will be
+    // deleted after this call.
+    if withdraw_amount == collateral.deposited_amount {
+        withdraw_reserve.attributed_borrow_value = withdraw_reserve
+            .attributed_borrow_value
+            .saturating_sub(collateral.market_value);
+
+        Reserve::pack(
+            *withdraw_reserve,
+            &mut withdraw_reserve_info.data.borrow_mut(),
+        )?;
+    }
+
+    let deposited_amount = collateral.deposited_amount.saturating_sub(withdraw_amount);
+    obligation.repay(settle_amount, liquidity_index)?;
+    obligation.withdraw(withdraw_amount, collateral_index)?;
+    obligation.last_update.mark_stale();
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: repay_reserve_liquidity_supply_info.clone(),
+        amount: repay_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+    if let Some(fee_receiver_info) = protocol_liquidity_fee_receiver_info {
+        spl_token_transfer(TokenTransferParams {
+            source: source_liquidity_info.clone(),
+            destination: fee_receiver_info.clone(),
+            amount: protocol_liquidity_fee,
+            authority: user_transfer_authority_info.clone(),
+            authority_signer_seeds: &[],
+            token_program: token_program_id.clone(),
+        })?;
+        log_fee(
+            FeeKind::LiquidationProtocol,
+            repay_reserve_info.key,
+            user_transfer_authority_info.key,
+            fee_receiver_info.key,
+            protocol_liquidity_fee,
+            None,
+        );
+    }
+
+    spl_token_transfer(TokenTransferParams {
+        source: withdraw_reserve_collateral_supply_info.clone(),
+        destination: destination_collateral_info.clone(),
+        amount: withdraw_amount,
+        authority: lending_market_authority_info.clone(),
+        authority_signer_seeds,
+        token_program: token_program_id.clone(),
+    })?;
+    log_obligation_collateral(
+        ObligationCollateralKind::Liquidation,
+        obligation_info.key,
+        withdraw_reserve_info.key,
+        collateral_exchange_rate,
+        withdraw_amount,
+        deposited_amount,
+    )?;
+    LiquidationEvent {
+        obligation: *obligation_info.key,
+        liquidator: *user_transfer_authority_info.key,
+        repay_reserve: *repay_reserve_info.key,
+        withdraw_reserve: *withdraw_reserve_info.key,
+        repay_amount,
+        withdraw_amount,
+        bonus: bonus.total_bonus,
+    }
+    .log();
+
+    Ok((
+        TokenAmount(repay_amount),
+        CTokenAmount(withdraw_amount),
+        bonus,
+    ))
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_liquidate_obligation_and_redeem_reserve_collateral(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+
+    let (accounts, liquidator_whitelist_info) =
+        split_liquidator_whitelist_info(program_id, accounts);
+    let (accounts, reserve_activity_infos) = split_reserve_activity_infos(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_collateral_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let repay_reserve_info = next_account_info(account_info_iter)?;
+    let repay_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_collateral_mint_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_collateral_supply_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_liquidity_supply_info = next_account_info(account_info_iter)?;
+    let withdraw_reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let repay_reserve_liquidity_fee_receiver_info = account_info_iter.next();
+    let clock = &Clock::get()?;
+
+    let (repay_amount, withdrawn_collateral_amount, bonus) = _liquidate_obligation(
+        program_id,
+        TokenAmount(liquidity_amount),
+        source_liquidity_info,
+        destination_collateral_info,
+        repay_reserve_info,
+        repay_reserve_liquidity_supply_info,
+        withdraw_reserve_info,
+        withdraw_reserve_collateral_supply_info,
+        obligation_info,
+        lending_market_info,
+        lending_market_authority_info,
+        user_transfer_authority_info,
+        repay_reserve_liquidity_fee_receiver_info,
+        liquidator_whitelist_info,
+        clock,
+        token_program_id,
+    )?;
+
+    _refresh_reserve_interest(program_id, withdraw_reserve_info, clock)?;
+    let withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+    let collateral_exchange_rate = withdraw_reserve.collateral_exchange_rate()?;
+    let max_redeemable_collateral = CTokenAmount(
+        collateral_exchange_rate
+            .liquidity_to_collateral(withdraw_reserve.liquidity.available_amount)?,
+    );
+    let withdraw_collateral_amount = min(withdrawn_collateral_amount, max_redeemable_collateral);
+    let mut received_liquidity_amount = TokenAmount::zero();
+    // if there is liquidity redeem it, the rest is left to the liquidator as collateral
+    if withdraw_collateral_amount != CTokenAmount::zero() {
+        let withdraw_liquidity_amount = _redeem_reserve_collateral(
+            program_id,
+            withdraw_collateral_amount,
+            None,
+            destination_collateral_info,
+            destination_liquidity_info,
+            withdraw_reserve_info,
+            withdraw_reserve_collateral_mint_info,
+            withdraw_reserve_liquidity_supply_info,
+            lending_market_info,
+            lending_market_authority_info,
+            user_transfer_authority_info,
+            clock,
+            token_program_id,
+            false,
+        )?;
+        let withdraw_reserve = Box::new(Reserve::unpack(&withdraw_reserve_info.data.borrow())?);
+        if &withdraw_reserve.config.fee_receiver != withdraw_reserve_liquidity_fee_receiver_info.key
+        {
+            msg!("Withdraw reserve liquidity fee receiver does not match the reserve liquidity fee receiver provided");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        // already paid in the repaid liquidity
+        let protocol_fee = if withdraw_reserve
+            .config
+            .protocol_liquidation_fee_in_liquidity
+        {
+            0
+        } else {
+            withdraw_reserve
+                .calculate_protocol_liquidation_fee(withdraw_liquidity_amount.0, &bonus)?
+        };
+
+        if protocol_fee > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: destination_liquidity_info.clone(),
+                destination: withdraw_reserve_liquidity_fee_receiver_info.clone(),
+                amount: protocol_fee,
+                authority: user_transfer_authority_info.clone(),
+                authority_signer_seeds: &[],
+                token_program: token_program_id.clone(),
+            })?;
+            log_fee(
+                FeeKind::LiquidationProtocol,
+                withdraw_reserve_info.key,
+                user_transfer_authority_info.key,
+                withdraw_reserve_liquidity_fee_receiver_info.key,
+                protocol_fee,
+                None,
+            );
+        }
+
+        received_liquidity_amount = withdraw_liquidity_amount.try_sub(TokenAmount(protocol_fee))?;
+    }
+
+    let unredeemed_collateral_amount =
+        withdrawn_collateral_amount.try_sub(withdraw_collateral_amount)?;
+    if unredeemed_collateral_amount != CTokenAmount::zero() {
+        msg!(
+            "Not enough liquidity to redeem all collateral, {} collateral left unredeemed",
+            unredeemed_collateral_amount
+        );
+    }
+
+    let mut return_data = [0u8; 24];
+    return_data[..8].copy_from_slice(&withdraw_collateral_amount.0.to_le_bytes());
+    return_data[8..16].copy_from_slice(&received_liquidity_amount.0.to_le_bytes());
+    return_data[16..].copy_from_slice(&unredeemed_collateral_amount.0.to_le_bytes());
+    set_return_data(&return_data);
+
+    record_reserve_activity(
+        reserve_activity_infos,
+        repay_reserve_info,
+        ReserveAction::Liquidation,
+        repay_amount.0,
+    )?;
+
+    Ok(())
+}
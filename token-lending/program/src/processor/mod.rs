@@ -0,0 +1,994 @@
+//! Program state processor
+
+mod admin;
+mod borrows;
+mod deposits;
+#[cfg(feature = "devnet-utils")]
+mod devnet;
+mod flash;
+mod liquidations;
+mod obligations;
+mod reserves;
+mod rewards;
+mod validation;
+
+use admin::*;
+use borrows::*;
+use deposits::*;
+#[cfg(feature = "devnet-utils")]
+use devnet::*;
+use flash::*;
+use liquidations::*;
+use obligations::*;
+use reserves::*;
+use rewards::*;
+use validation::*;
+
+use crate::state::Bonus;
+use crate::{
+    self as solend_program,
+    error::LendingError,
+    events::{
+        BorrowEvent, BorrowKind, ConfigUpdateEvent, DepositEvent, DepositKind, FeeEvent,
+        FlashLoanEvent, FlashLoanKind, LiquidationEvent, ObligationCollateralEvent,
+        ObligationCollateralKind,
+    },
+    instruction::LendingInstruction,
+    math::{CTokenAmount, Decimal, Rate, TokenAmount, TryAdd, TryDiv, TryMul, TrySub},
+    state::{
+        is_newer_version_account, is_v1_layout_account, shared_asset_category,
+        validate_reserve_config, AssetCategories, AssetCategory, CalculateBorrowResult,
+        CalculateLiquidationResult, CalculateRepayResult, CollateralExchangeRate, EmergencyPrice,
+        FeeKind, InitLendingMarketParams, InitObligationParams, InitReserveParams, LendingMarket,
+        LiquidatorWhitelist, MarketMember, MaxPriceAge, NewReserveCollateralParams,
+        NewReserveLiquidityParams, Obligation, ObligationCounter, Reserve, ReserveAction,
+        ReserveActivity, ReserveCTokenPrice, ReserveCollateral, ReserveConfig, ReserveLiquidity,
+        ReservePairBorrowWeights, ReserveRateStats, ReserveSpreadFeeReceiver, RewardRule,
+        RewardSide, ASSET_CATEGORIES_SEED, EMERGENCY_PRICE_SEED,
+        EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS, EMPTY_OBLIGATION_CRANK_BOUNTY_PERCENT,
+        GLOBAL_ALLOWED_BORROW_VALUE, GLOBAL_UNHEALTHY_BORROW_VALUE, INSURANCE_FUND_SEED,
+        LENDING_MARKET_LEN_V1, LIQUIDATOR_WHITELIST_SEED, MARKET_MEMBER_SEED,
+        MAX_COLLATERAL_DECIMALS_OFFSET, MAX_EMERGENCY_PRICE_DURATION_SLOTS, MAX_INSURANCE_FEE_BPS,
+        MAX_PRICE_AGE_SEED, MAX_PRICE_AGE_SLOTS, MAX_WHITELISTED_LIQUIDATORS,
+        OBLIGATION_COUNTER_SEED, PROGRAM_VERSION, RESERVE_ACTIVITY_SEED, RESERVE_CTOKEN_PRICE_SEED,
+        RESERVE_PAIR_BORROW_WEIGHTS_SEED, RESERVE_RATE_STATS_SEED,
+        RESERVE_SPREAD_FEE_RECEIVER_SEED, REWARD_RULE_SEED,
+    },
+};
+use bytemuck::bytes_of;
+use oracles::get_single_price;
+use oracles::get_single_price_publish_time;
+use oracles::get_single_price_unchecked;
+use oracles::pyth::validate_pyth_keys;
+use oracles::switchboard::validate_sb_on_demand_keys;
+use oracles::switchboard::validate_switchboard_keys;
+use oracles::OracleLimits;
+use oracles::{get_oracle_type, pyth::validate_pyth_price_account_info, OracleType};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::{Slot, UnixTimestamp},
+    entrypoint::ProgramResult,
+    instruction::{get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
+    keccak,
+    log::sol_log_data,
+    msg,
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    system_instruction::{create_account, transfer},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use solend_sdk::{
+    math::SaturatingSub,
+    spl_account_compression, spl_noop,
+    state::{LendingMarketMetadata, RateLimiter, RateLimiterConfig, ReserveType},
+};
+
+use spl_token::state::{Account as Token, Mint};
+use std::{cmp::min, result::Result};
+
+/// solend market owner
+pub mod solend_market_owner {
+    solana_program::declare_id!("5pHk2TmnqQzRF9L6egy5FfiyBgS7G9cMZ5RFaJAvghzw");
+}
+
+/// Processes an instruction
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let instruction = LendingInstruction::unpack(input)?;
+    // a binary rolled back or not yet upgraded must not rewrite accounts migrated by a newer one
+    for account_info in accounts {
+        if account_info.owner == program_id && is_newer_version_account(&account_info.data.borrow())
+        {
+            msg!(
+                "Account {} was written by a newer version of the lending program",
+                account_info.key
+            );
+            return Err(LendingError::AccountVersionNotSupported.into());
+        }
+    }
+
+    match instruction {
+        LendingInstruction::InitLendingMarket {
+            owner,
+            quote_currency,
+        } => {
+            msg!("Instruction: Init Lending Market");
+            process_init_lending_market(program_id, owner, quote_currency, accounts)
+        }
+        LendingInstruction::SetLendingMarketOwnerAndConfig {
+            new_owner,
+            rate_limiter_config,
+            whitelisted_liquidator,
+            risk_authority,
+        } => {
+            msg!("Instruction: Set Lending Market Owner");
+            process_set_lending_market_owner_and_config(
+                program_id,
+                new_owner,
+                rate_limiter_config,
+                whitelisted_liquidator,
+                risk_authority,
+                accounts,
+            )
+        }
+        LendingInstruction::InitReserve {
+            liquidity_amount,
+            config,
+            collateral_decimals,
+        } => {
+            msg!("Instruction: Init Reserve");
+            process_init_reserve(
+                program_id,
+                liquidity_amount,
+                config,
+                collateral_decimals,
+                accounts,
+            )
+        }
+        LendingInstruction::RefreshReserve => {
+            msg!("Instruction: Refresh Reserve");
+            process_refresh_reserve(program_id, accounts)
+        }
+        LendingInstruction::DepositReserveLiquidity {
+            liquidity_amount,
+            min_collateral_out,
+        } => {
+            msg!("Instruction: Deposit Reserve Liquidity");
+            process_deposit_reserve_liquidity(
+                program_id,
+                liquidity_amount,
+                min_collateral_out,
+                accounts,
+            )
+        }
+        LendingInstruction::RedeemReserveCollateral {
+            collateral_amount,
+            min_liquidity_out,
+        } => {
+            msg!("Instruction: Redeem Reserve Collateral");
+            process_redeem_reserve_collateral(
+                program_id,
+                collateral_amount,
+                min_liquidity_out,
+                accounts,
+            )
+        }
+        LendingInstruction::InitObligation { referrer } => {
+            msg!("Instruction: Init Obligation");
+            process_init_obligation(program_id, referrer, accounts)
+        }
+        LendingInstruction::RefreshObligation => {
+            msg!("Instruction: Refresh Obligation");
+            process_refresh_obligation(program_id, accounts)
+        }
+        LendingInstruction::DepositObligationCollateral { collateral_amount } => {
+            msg!("Instruction: Deposit Obligation Collateral");
+            process_deposit_obligation_collateral(program_id, collateral_amount, accounts)
+        }
+        LendingInstruction::WithdrawObligationCollateral { collateral_amount } => {
+            msg!("Instruction: Withdraw Obligation Collateral");
+            process_withdraw_obligation_collateral(program_id, collateral_amount, accounts)
+        }
+        LendingInstruction::BorrowObligationLiquidity {
+            liquidity_amount,
+            min_liquidity_received,
+        } => {
+            msg!("Instruction: Borrow Obligation Liquidity");
+            process_borrow_obligation_liquidity(
+                program_id,
+                liquidity_amount,
+                min_liquidity_received,
+                accounts,
+            )
+        }
+        LendingInstruction::RepayObligationLiquidity { liquidity_amount } => {
+            msg!("Instruction: Repay Obligation Liquidity");
+            process_repay_obligation_liquidity(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::LiquidateObligation { .. } => {
+            msg!("Instruction: Liquidate Obligation");
+            msg!("method deprecated, please migrate to Liquidate Obligation and Redeem Reserve Collateral");
+            Err(LendingError::DeprecatedInstruction.into())
+        }
+        LendingInstruction::FlashLoan { .. } => {
+            msg!("Instruction: Flash Loan");
+            msg!("This instruction has been deprecated. Use FlashBorrowReserveLiquidity instead");
+            Err(LendingError::DeprecatedInstruction.into())
+        }
+        LendingInstruction::DepositReserveLiquidityAndObligationCollateral { liquidity_amount } => {
+            msg!("Instruction: Deposit Reserve Liquidity and Obligation Collateral");
+            process_deposit_reserve_liquidity_and_obligation_collateral(
+                program_id,
+                liquidity_amount,
+                accounts,
+            )
+        }
+        LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
+            collateral_amount,
+        } => {
+            msg!("Instruction: Withdraw Obligation Collateral and Redeem Reserve Collateral");
+            process_withdraw_obligation_collateral_and_redeem_reserve_liquidity(
+                program_id,
+                collateral_amount,
+                accounts,
+            )
+        }
+        LendingInstruction::UpdateReserveConfig {
+            config,
+            rate_limiter_config,
+        } => {
+            msg!("Instruction: UpdateReserveConfig");
+            process_update_reserve_config(program_id, config, rate_limiter_config, accounts)
+        }
+        LendingInstruction::LiquidateObligationAndRedeemReserveCollateral { liquidity_amount } => {
+            msg!("Instruction: Liquidate Obligation and Redeem Reserve Collateral");
+            process_liquidate_obligation_and_redeem_reserve_collateral(
+                program_id,
+                liquidity_amount,
+                accounts,
+            )
+        }
+        LendingInstruction::RedeemFees => {
+            msg!("Instruction: RedeemFees");
+            process_redeem_fees(program_id, accounts)
+        }
+        LendingInstruction::RedeemFeesIfAbove { min_amount } => {
+            msg!("Instruction: Redeem Fees If Above");
+            process_redeem_fees_if_above(program_id, min_amount, accounts)
+        }
+        LendingInstruction::SetObligationMaxOutflow {
+            obligation_max_outflow,
+        } => {
+            msg!("Instruction: Set Obligation Max Outflow");
+            process_set_obligation_max_outflow(program_id, obligation_max_outflow, accounts)
+        }
+        LendingInstruction::ResetRateLimiter => {
+            msg!("Instruction: Reset Rate Limiter");
+            process_reset_rate_limiter(program_id, accounts)
+        }
+        LendingInstruction::FlashBorrowReserveLiquidity { liquidity_amount } => {
+            msg!("Instruction: Flash Borrow Reserve Liquidity");
+            process_flash_borrow_reserve_liquidity(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::FlashRepayReserveLiquidity {
+            liquidity_amount,
+            borrow_instruction_index,
+        } => {
+            msg!("Instruction: Flash Repay Reserve Liquidity");
+            process_flash_repay_reserve_liquidity(
+                program_id,
+                liquidity_amount,
+                borrow_instruction_index,
+                accounts,
+            )
+        }
+        LendingInstruction::ForgiveDebt { liquidity_amount } => {
+            msg!("Instruction: Forgive Debt");
+            process_forgive_debt(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::UpdateMarketMetadata => {
+            msg!("Instruction: Update Metadata");
+            let metadata = LendingMarketMetadata::new_from_bytes(input)?;
+            process_update_market_metadata(program_id, metadata, accounts)
+        }
+        LendingInstruction::SetObligationCloseabilityStatus { closeable } => {
+            msg!("Instruction: Mark Obligation As Closable");
+            process_set_obligation_closeability_status(program_id, closeable, accounts)
+        }
+        LendingInstruction::DonateToReserve { liquidity_amount } => {
+            msg!("Instruction: Donate To Reserve");
+            process_donate_to_reserve(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::InitObligationArchive {
+            max_depth,
+            max_buffer_size,
+        } => {
+            msg!("Instruction: Init Obligation Archive");
+            process_init_obligation_archive(program_id, max_depth, max_buffer_size, accounts)
+        }
+        LendingInstruction::ArchiveObligation => {
+            msg!("Instruction: Archive Obligation");
+            process_archive_obligation(program_id, accounts)
+        }
+        LendingInstruction::RotateReserveCollateralSupply => {
+            msg!("Instruction: Rotate Reserve Collateral Supply");
+            process_rotate_reserve_collateral_supply(program_id, accounts)
+        }
+        LendingInstruction::RotateReserveFeeReceiver => {
+            msg!("Instruction: Rotate Reserve Fee Receiver");
+            process_rotate_reserve_fee_receiver(program_id, accounts)
+        }
+        LendingInstruction::SetDefaultHostFeeReceiver => {
+            msg!("Instruction: Set Default Host Fee Receiver");
+            process_set_default_host_fee_receiver(program_id, accounts)
+        }
+        LendingInstruction::RepayObligationLiquidityMany { liquidity_amounts } => {
+            msg!("Instruction: Repay Obligation Liquidity Many");
+            process_repay_obligation_liquidity_many(program_id, &liquidity_amounts, accounts)
+        }
+        LendingInstruction::SetReserveUtilizationKillSwitch {
+            emergency_utilization_threshold,
+            emergency_utilization_slots,
+        } => {
+            msg!("Instruction: Set Reserve Utilization Kill Switch");
+            process_set_reserve_utilization_kill_switch(
+                program_id,
+                emergency_utilization_threshold,
+                emergency_utilization_slots,
+                accounts,
+            )
+        }
+        LendingInstruction::CrankCloseEmptyObligation => {
+            msg!("Instruction: Crank Close Empty Obligation");
+            process_crank_close_empty_obligation(program_id, accounts)
+        }
+        LendingInstruction::SetObligationBorrowLock { borrows_locked } => {
+            msg!("Instruction: Set Obligation Borrow Lock");
+            process_set_obligation_borrow_lock(program_id, borrows_locked, accounts)
+        }
+        LendingInstruction::SetObligationBorrowCap { borrow_cap } => {
+            msg!("Instruction: Set Obligation Borrow Cap");
+            process_set_obligation_borrow_cap(program_id, borrow_cap, accounts)
+        }
+        LendingInstruction::DelegateBorrowAuthority {
+            approved_borrower,
+            delegated_value,
+        } => {
+            msg!("Instruction: Delegate Borrow Authority");
+            process_delegate_borrow_authority(
+                program_id,
+                approved_borrower,
+                delegated_value,
+                accounts,
+            )
+        }
+        LendingInstruction::SetEmergencyPrice {
+            price,
+            max_duration_slots,
+        } => {
+            msg!("Instruction: Set Emergency Price");
+            process_set_emergency_price(program_id, price, max_duration_slots, accounts)
+        }
+        LendingInstruction::SetRewardRule {
+            side,
+            emission_per_slot,
+            start_slot,
+            end_slot,
+        } => {
+            msg!("Instruction: Set Reward Rule");
+            process_set_reward_rule(
+                program_id,
+                side,
+                emission_per_slot,
+                start_slot,
+                end_slot,
+                accounts,
+            )
+        }
+        LendingInstruction::UpdateRewards => {
+            msg!("Instruction: Update Rewards");
+            process_update_rewards(program_id, accounts)
+        }
+        LendingInstruction::ClaimRewards => {
+            msg!("Instruction: Claim Rewards");
+            process_claim_rewards(program_id, accounts)
+        }
+        LendingInstruction::SetAssetCategory {
+            asset_category,
+            loan_to_value_ratio,
+            liquidation_threshold,
+            max_liquidation_threshold,
+        } => {
+            msg!("Instruction: Set Asset Category");
+            process_set_asset_category(
+                program_id,
+                asset_category,
+                AssetCategory {
+                    loan_to_value_ratio,
+                    liquidation_threshold,
+                    max_liquidation_threshold,
+                },
+                accounts,
+            )
+        }
+        LendingInstruction::SetRiskAuthority { risk_authority } => {
+            msg!("Instruction: Set Risk Authority");
+            process_set_risk_authority(program_id, risk_authority, accounts)
+        }
+        LendingInstruction::SetMaxObligationsPerOwner {
+            max_obligations_per_owner,
+        } => {
+            msg!("Instruction: Set Max Obligations Per Owner");
+            process_set_max_obligations_per_owner(program_id, max_obligations_per_owner, accounts)
+        }
+        LendingInstruction::SetProtocolFeeConfig {
+            fee_authority,
+            treasury,
+            rate_limiter_config,
+        } => {
+            msg!("Instruction: Set Protocol Fee Config");
+            process_set_protocol_fee_config(
+                program_id,
+                fee_authority,
+                treasury,
+                rate_limiter_config,
+                accounts,
+            )
+        }
+        LendingInstruction::WithdrawProtocolFees { liquidity_amount } => {
+            msg!("Instruction: Withdraw Protocol Fees");
+            process_withdraw_protocol_fees(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::SetObligationReferrer { referrer } => {
+            msg!("Instruction: Set Obligation Referrer");
+            process_set_obligation_referrer(program_id, referrer, accounts)
+        }
+        LendingInstruction::CompoundObligationCollateral { collateral_amount } => {
+            msg!("Instruction: Compound Obligation Collateral");
+            process_compound_obligation_collateral(program_id, collateral_amount, accounts)
+        }
+        LendingInstruction::RepayObligationLiquidityWithCollateral { liquidity_amount } => {
+            msg!("Instruction: Repay Obligation Liquidity With Collateral");
+            process_repay_obligation_liquidity_with_collateral(
+                program_id,
+                liquidity_amount,
+                accounts,
+            )
+        }
+        LendingInstruction::DepositReserveLiquidityAndObligationCollateralAndBorrow {
+            deposit_liquidity_amount,
+            borrow_liquidity_amount,
+        } => {
+            msg!("Instruction: Deposit Reserve Liquidity and Obligation Collateral and Borrow");
+            process_deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+                program_id,
+                deposit_liquidity_amount,
+                borrow_liquidity_amount,
+                accounts,
+            )
+        }
+        LendingInstruction::InitReserveRateStats => {
+            msg!("Instruction: Init Reserve Rate Stats");
+            process_init_reserve_rate_stats(program_id, accounts)
+        }
+        LendingInstruction::SetDustBorrowValue { dust_borrow_value } => {
+            msg!("Instruction: Set Dust Borrow Value");
+            process_set_dust_borrow_value(program_id, dust_borrow_value, accounts)
+        }
+        LendingInstruction::InitReserveActivity => {
+            msg!("Instruction: Init Reserve Activity");
+            process_init_reserve_activity(program_id, accounts)
+        }
+        #[cfg(feature = "devnet-utils")]
+        LendingInstruction::DevnetMintTestTokens { amount } => {
+            msg!("Instruction: Devnet Mint Test Tokens");
+            process_devnet_mint_test_tokens(program_id, amount, accounts)
+        }
+        #[cfg(feature = "devnet-utils")]
+        LendingInstruction::DevnetSetPrice { price } => {
+            msg!("Instruction: Devnet Set Price");
+            process_devnet_set_price(program_id, price, accounts)
+        }
+        LendingInstruction::BorrowObligationLiquidityWithWalletCollateral { liquidity_amount } => {
+            msg!("Instruction: Borrow Obligation Liquidity With Wallet Collateral");
+            process_borrow_obligation_liquidity_with_wallet_collateral(
+                program_id,
+                liquidity_amount,
+                accounts,
+            )
+        }
+        LendingInstruction::CloseReserve => {
+            msg!("Instruction: Close Reserve");
+            process_close_reserve(program_id, accounts)
+        }
+        LendingInstruction::SetReserveTertiaryOracle => {
+            msg!("Instruction: Set Reserve Tertiary Oracle");
+            process_set_reserve_tertiary_oracle(program_id, accounts)
+        }
+        LendingInstruction::SetReservePairBorrowWeight { borrow_weight_bps } => {
+            msg!("Instruction: Set Reserve Pair Borrow Weight");
+            process_set_reserve_pair_borrow_weight(program_id, borrow_weight_bps, accounts)
+        }
+        LendingInstruction::SetWhitelistRequired { whitelist_required } => {
+            msg!("Instruction: Set Whitelist Required");
+            process_set_whitelist_required(program_id, whitelist_required, accounts)
+        }
+        LendingInstruction::UpdateMarketMember { is_member } => {
+            msg!("Instruction: Update Market Member");
+            process_update_market_member(program_id, is_member, accounts)
+        }
+        LendingInstruction::SetLiquidatorWhitelist {
+            protected_window_slots,
+            liquidators,
+        } => {
+            msg!("Instruction: Set Liquidator Whitelist");
+            process_set_liquidator_whitelist(
+                program_id,
+                protected_window_slots,
+                liquidators,
+                accounts,
+            )
+        }
+        LendingInstruction::SetInsuranceFee { insurance_fee_bps } => {
+            msg!("Instruction: Set Insurance Fee");
+            process_set_insurance_fee(program_id, insurance_fee_bps, accounts)
+        }
+        LendingInstruction::InitInsuranceFund => {
+            msg!("Instruction: Init Insurance Fund");
+            process_init_insurance_fund(program_id, accounts)
+        }
+        LendingInstruction::CoverBadDebt { liquidity_amount } => {
+            msg!("Instruction: Cover Bad Debt");
+            process_cover_bad_debt(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::WithdrawObligationLiquidityExact { liquidity_amount } => {
+            msg!("Instruction: Withdraw Obligation Liquidity Exact");
+            process_withdraw_obligation_liquidity_exact(program_id, liquidity_amount, accounts)
+        }
+        LendingInstruction::SetMaxPriceAge {
+            max_price_age_slots,
+        } => {
+            msg!("Instruction: Set Max Price Age");
+            process_set_max_price_age(program_id, max_price_age_slots, accounts)
+        }
+        LendingInstruction::BuyRateCap => {
+            msg!("Instruction: Buy Rate Cap");
+            process_buy_rate_cap(program_id, accounts)
+        }
+        LendingInstruction::ClaimRateCapCredit => {
+            msg!("Instruction: Claim Rate Cap Credit");
+            process_claim_rate_cap_credit(program_id, accounts)
+        }
+        LendingInstruction::SetReserveSpreadFeeReceiver => {
+            msg!("Instruction: Set Reserve Spread Fee Receiver");
+            process_set_reserve_spread_fee_receiver(program_id, accounts)
+        }
+        LendingInstruction::RefreshCTokenPrice => {
+            msg!("Instruction: Refresh CToken Price");
+            process_refresh_ctoken_price(program_id, accounts)
+        }
+        LendingInstruction::MigrateAccount => {
+            msg!("Instruction: Migrate Account");
+            process_migrate_account(program_id, accounts)
+        }
+    }
+}
+
+/// Issue a spl_token `InitializeAccount` instruction.
+#[inline(always)]
+fn spl_token_init_account(params: TokenInitializeAccountParams<'_>) -> ProgramResult {
+    let TokenInitializeAccountParams {
+        account,
+        mint,
+        owner,
+        rent,
+        token_program,
+    } = params;
+    let ix = spl_token::instruction::initialize_account(
+        token_program.key,
+        account.key,
+        mint.key,
+        owner.key,
+    )?;
+    let result = invoke(&ix, &[account, mint, owner, rent, token_program]);
+    result.map_err(|_| LendingError::TokenInitializeAccountFailed.into())
+}
+
+/// Issue a spl_token `InitializeMint` instruction.
+#[inline(always)]
+fn spl_token_init_mint(params: TokenInitializeMintParams<'_, '_>) -> ProgramResult {
+    let TokenInitializeMintParams {
+        mint,
+        rent,
+        authority,
+        token_program,
+        decimals,
+    } = params;
+    let ix = spl_token::instruction::initialize_mint(
+        token_program.key,
+        mint.key,
+        authority,
+        None,
+        decimals,
+    )?;
+    let result = invoke(&ix, &[mint, rent, token_program]);
+    result.map_err(|_| LendingError::TokenInitializeMintFailed.into())
+}
+
+/// Invoke signed unless signers seeds are empty
+#[inline(always)]
+fn invoke_optionally_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    authority_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if authority_signer_seeds.is_empty() {
+        invoke(instruction, account_infos)
+    } else {
+        invoke_signed(instruction, account_infos, &[authority_signer_seeds])
+    }
+}
+
+/// Issue a spl_token `Transfer` instruction.
+#[inline(always)]
+fn spl_token_transfer(params: TokenTransferParams<'_, '_>) -> ProgramResult {
+    let TokenTransferParams {
+        source,
+        destination,
+        authority,
+        token_program,
+        amount,
+        authority_signer_seeds,
+    } = params;
+    let result = invoke_optionally_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            source.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[source, destination, authority, token_program],
+        authority_signer_seeds,
+    );
+
+    result.map_err(|_| LendingError::TokenTransferFailed.into())
+}
+
+/// Issue a spl_token `SyncNative` instruction.
+fn spl_token_sync_native<'a>(
+    account: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+) -> ProgramResult {
+    invoke(
+        &spl_token::instruction::sync_native(token_program.key, account.key)?,
+        &[account, token_program],
+    )
+}
+
+/// Issue a spl_token `MintTo` instruction.
+fn spl_token_mint_to(params: TokenMintToParams<'_, '_>) -> ProgramResult {
+    let TokenMintToParams {
+        mint,
+        destination,
+        authority,
+        token_program,
+        amount,
+        authority_signer_seeds,
+    } = params;
+    let result = invoke_optionally_signed(
+        &spl_token::instruction::mint_to(
+            token_program.key,
+            mint.key,
+            destination.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[mint, destination, authority, token_program],
+        authority_signer_seeds,
+    );
+    result.map_err(|_| LendingError::TokenMintToFailed.into())
+}
+
+/// Issue a spl_token `Burn` instruction.
+#[inline(always)]
+fn spl_token_burn(params: TokenBurnParams<'_, '_>) -> ProgramResult {
+    let TokenBurnParams {
+        mint,
+        source,
+        authority,
+        token_program,
+        amount,
+        authority_signer_seeds,
+    } = params;
+    let result = invoke_optionally_signed(
+        &spl_token::instruction::burn(
+            token_program.key,
+            source.key,
+            mint.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[source, mint, authority, token_program],
+        authority_signer_seeds,
+    );
+    result.map_err(|_| LendingError::TokenBurnFailed.into())
+}
+
+/// Issue a spl_token `CloseAccount` instruction.
+#[inline(always)]
+fn spl_token_close_account(params: TokenCloseAccountParams<'_, '_>) -> ProgramResult {
+    let TokenCloseAccountParams {
+        account,
+        destination,
+        authority,
+        token_program,
+        authority_signer_seeds,
+    } = params;
+    let result = invoke_optionally_signed(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?,
+        &[account, destination, authority, token_program],
+        authority_signer_seeds,
+    );
+    result.map_err(|_| LendingError::TokenCloseAccountFailed.into())
+}
+
+/// Closes an account derived from another account's address when that account is closed, so it
+/// isn't left behind for an account later created at the same address. Accounts that were never
+/// created are skipped.
+fn close_derived_account(
+    program_id: &Pubkey,
+    name: &str,
+    seeds: &[&[u8]],
+    account_info: &AccountInfo,
+    destination_info: &AccountInfo,
+) -> ProgramResult {
+    let (account_key, _bump_seed) = Pubkey::find_program_address(seeds, program_id);
+    if account_key != *account_info.key {
+        msg!(
+            "Provided {} account does not match the expected derived address",
+            name
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if account_info.data_is_empty() {
+        return Ok(());
+    }
+    if account_info.owner != program_id {
+        msg!(
+            "Provided {} account is not owned by the lending program",
+            name
+        );
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let destination_lamports = destination_info.lamports();
+    **destination_info.lamports.borrow_mut() = destination_lamports
+        .checked_add(account_info.lamports())
+        .ok_or(LendingError::MathOverflow)?;
+    **account_info.lamports.borrow_mut() = 0;
+    account_info.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// Logs a "Fee" event for integrators reconciling fee revenue, see `FeeKind`
+fn log_fee(
+    kind: FeeKind,
+    reserve: &Pubkey,
+    payer: &Pubkey,
+    receiver: &Pubkey,
+    amount: u64,
+    referrer: Option<Pubkey>,
+) {
+    FeeEvent {
+        kind,
+        reserve: *reserve,
+        payer: *payer,
+        receiver: *receiver,
+        amount,
+        referrer,
+    }
+    .log();
+}
+
+/// Logs an "ObligationCollateral" event with the collateral moved, what it was worth at the
+/// reserve's exchange rate and the amount left in the obligation
+fn log_obligation_collateral(
+    kind: ObligationCollateralKind,
+    obligation: &Pubkey,
+    reserve: &Pubkey,
+    collateral_exchange_rate: CollateralExchangeRate,
+    collateral_amount: u64,
+    deposited_amount: u64,
+) -> ProgramResult {
+    ObligationCollateralEvent {
+        kind,
+        obligation: *obligation,
+        reserve: *reserve,
+        collateral_amount,
+        liquidity_amount: collateral_exchange_rate.collateral_to_liquidity(collateral_amount)?,
+        collateral_exchange_rate: Rate::from(collateral_exchange_rate).into(),
+        deposited_amount,
+    }
+    .log();
+    Ok(())
+}
+
+/// Splits off the reserve activity accounts the accounts of an instruction may end with
+fn split_reserve_activity_infos<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], &'b [AccountInfo<'a>]) {
+    let reserve_activity_len = accounts
+        .iter()
+        .rev()
+        .take_while(|account_info| {
+            account_info.owner == program_id && account_info.data_len() == ReserveActivity::LEN
+        })
+        .count();
+    accounts.split_at(accounts.len() - reserve_activity_len)
+}
+
+/// Counts an action in the reserve activity account of the reserve, if one was passed
+fn record_reserve_activity(
+    reserve_activity_infos: &[AccountInfo],
+    reserve_info: &AccountInfo,
+    action: ReserveAction,
+    amount: u64,
+) -> ProgramResult {
+    for reserve_activity_info in reserve_activity_infos {
+        let mut reserve_activity = ReserveActivity::unpack(&reserve_activity_info.data.borrow())?;
+        if reserve_activity.reserve == *reserve_info.key {
+            reserve_activity.record(action, amount);
+            ReserveActivity::pack(
+                reserve_activity,
+                &mut reserve_activity_info.data.borrow_mut(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits the market member account off the end of the accounts, where it's passed when the
+/// lending market requires a whitelist
+fn split_market_member_info<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+    match accounts.split_last() {
+        Some((last, rest)) if last.owner == program_id && last.data_len() == MarketMember::LEN => {
+            (rest, Some(last))
+        }
+        _ => (accounts, None),
+    }
+}
+
+/// Splits the liquidator whitelist account off the end of the accounts, where it's passed when
+/// the lending market has a liquidator whitelist
+fn split_liquidator_whitelist_info<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+    match accounts.split_last() {
+        Some((last, rest))
+            if last.owner == program_id && last.data_len() == LiquidatorWhitelist::LEN =>
+        {
+            (rest, Some(last))
+        }
+        _ => (accounts, None),
+    }
+}
+
+/// Splits the max price age account off the end of the accounts, where RefreshObligation takes it
+/// to accept reserves refreshed within the market's max price age
+fn split_max_price_age_info<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+    match accounts.split_last() {
+        Some((last, rest)) if last.owner == program_id && last.data_len() == MaxPriceAge::LEN => {
+            (rest, Some(last))
+        }
+        _ => (accounts, None),
+    }
+}
+
+/// Matches the obligation's rate limiter to the lending market's config if the market limits the
+/// outflow of obligations. Returns whether it does.
+fn sync_obligation_rate_limiter(
+    lending_market: &LendingMarket,
+    obligation: &mut Obligation,
+    clock: &Clock,
+) -> bool {
+    if !lending_market.is_obligation_rate_limited() {
+        return false;
+    }
+    obligation.sync_rate_limiter_config(lending_market, clock.slot);
+    true
+}
+
+/// Counts an outflow, in USD, against the obligation's rate limiter if the market limits the
+/// outflow of obligations
+fn update_obligation_rate_limiter(
+    obligation: &mut Obligation,
+    obligation_rate_limited: bool,
+    clock: &Clock,
+    outflow: Decimal,
+) -> ProgramResult {
+    if obligation_rate_limited {
+        obligation
+            .rate_limiter
+            .update(clock.slot, outflow)
+            .inspect_err(|_| {
+                msg!("Obligation outflow limit exceeded! Please try again later.");
+            })?;
+    }
+    Ok(())
+}
+
+struct TokenInitializeMintParams<'a: 'b, 'b> {
+    mint: AccountInfo<'a>,
+    rent: AccountInfo<'a>,
+    authority: &'b Pubkey,
+    decimals: u8,
+    token_program: AccountInfo<'a>,
+}
+
+struct TokenInitializeAccountParams<'a> {
+    account: AccountInfo<'a>,
+    mint: AccountInfo<'a>,
+    owner: AccountInfo<'a>,
+    rent: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+}
+
+struct TokenTransferParams<'a: 'b, 'b> {
+    source: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    amount: u64,
+    authority: AccountInfo<'a>,
+    authority_signer_seeds: &'b [&'b [u8]],
+    token_program: AccountInfo<'a>,
+}
+
+struct TokenMintToParams<'a: 'b, 'b> {
+    mint: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    amount: u64,
+    authority: AccountInfo<'a>,
+    authority_signer_seeds: &'b [&'b [u8]],
+    token_program: AccountInfo<'a>,
+}
+
+struct TokenBurnParams<'a: 'b, 'b> {
+    mint: AccountInfo<'a>,
+    source: AccountInfo<'a>,
+    amount: u64,
+    authority: AccountInfo<'a>,
+    authority_signer_seeds: &'b [&'b [u8]],
+    token_program: AccountInfo<'a>,
+}
+
+struct TokenCloseAccountParams<'a: 'b, 'b> {
+    account: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    authority_signer_seeds: &'b [&'b [u8]],
+    token_program: AccountInfo<'a>,
+}
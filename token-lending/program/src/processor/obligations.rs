@@ -0,0 +1,1010 @@
+//! Obligation lifecycle: init, refresh, locking, archiving and closing
+
+use super::*;
+
+/// anchor instruction discriminator for account compression `init_empty_merkle_tree`
+const ACCOUNT_COMPRESSION_INIT_EMPTY_MERKLE_TREE: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+/// anchor instruction discriminator for account compression `append`
+const ACCOUNT_COMPRESSION_APPEND: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_init_obligation(
+    program_id: &Pubkey,
+    referrer: Option<Pubkey>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let (accounts, market_member_info) = split_market_member_info(program_id, accounts);
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+
+    assert_rent_exempt(rent, obligation_info)?;
+    let mut obligation = Obligation::unpack_account_unchecked(&obligation_info.data.borrow())?;
+    if obligation.is_initialized() {
+        return Err(LendingError::AlreadyInitialized.into());
+    }
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    if !obligation_owner_info.is_signer {
+        msg!("Obligation owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+    validate_market_member(
+        program_id,
+        lending_market_info,
+        obligation_owner_info,
+        market_member_info,
+    )?;
+
+    if lending_market.max_obligations_per_owner > 0 {
+        let obligation_counter_info = next_account_info(account_info_iter)?;
+        _count_obligation(
+            program_id,
+            lending_market.max_obligations_per_owner,
+            lending_market_info,
+            obligation_owner_info,
+            obligation_counter_info,
+        )?;
+    }
+
+    obligation.init(InitObligationParams {
+        current_slot: clock.slot,
+        lending_market: *lending_market_info.key,
+        owner: *obligation_owner_info.key,
+        deposits: vec![],
+        borrows: vec![],
+    });
+    if let Some(referrer) = referrer {
+        obligation.referrer = Some(referrer);
+        msg!("Obligation referrer set to {}", referrer);
+    }
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Counts a new obligation against the owner's cap, creating the owner's counter account on first
+/// use
+fn _count_obligation<'a>(
+    program_id: &Pubkey,
+    max_obligations_per_owner: u16,
+    lending_market_info: &AccountInfo<'a>,
+    obligation_owner_info: &AccountInfo<'a>,
+    obligation_counter_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let obligation_counter_seeds = &[
+        lending_market_info.key.as_ref(),
+        obligation_owner_info.key.as_ref(),
+        OBLIGATION_COUNTER_SEED,
+    ];
+    let (obligation_counter_key, bump_seed) =
+        Pubkey::find_program_address(obligation_counter_seeds, program_id);
+    if obligation_counter_key != *obligation_counter_info.key {
+        msg!("Provided obligation counter account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let mut obligation_counter = if obligation_counter_info.data_is_empty() {
+        msg!("Creating obligation counter account");
+
+        invoke_signed(
+            &create_account(
+                obligation_owner_info.key,
+                obligation_counter_info.key,
+                Rent::get()?.minimum_balance(ObligationCounter::LEN),
+                ObligationCounter::LEN as u64,
+                program_id,
+            ),
+            &[
+                obligation_owner_info.clone(),
+                obligation_counter_info.clone(),
+            ],
+            &[&[
+                lending_market_info.key.as_ref(),
+                obligation_owner_info.key.as_ref(),
+                OBLIGATION_COUNTER_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+
+        ObligationCounter {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: *lending_market_info.key,
+            owner: *obligation_owner_info.key,
+            count: 0,
+        }
+    } else {
+        if obligation_counter_info.owner != program_id {
+            msg!("Obligation counter provided is not owned by the lending program");
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+        ObligationCounter::unpack(&obligation_counter_info.data.borrow())?
+    };
+
+    if obligation_counter.count >= max_obligations_per_owner as u64 {
+        msg!(
+            "Obligation owner has already initialized {} obligations in this lending market",
+            obligation_counter.count
+        );
+        return Err(LendingError::ObligationLimitReached.into());
+    }
+    obligation_counter.count += 1;
+    ObligationCounter::pack(
+        obligation_counter,
+        &mut obligation_counter_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// Releases a closed obligation from its owner's cap. Owners that never hit a capped market have
+/// no counter account, and obligations initialized before the cap was enforced were never
+/// counted, so the count is only lowered while there is one to lower.
+fn _uncount_obligation(
+    program_id: &Pubkey,
+    lending_market_info: &AccountInfo,
+    obligation_owner: &Pubkey,
+    obligation_counter_info: &AccountInfo,
+) -> ProgramResult {
+    let (obligation_counter_key, _bump_seed) = Pubkey::find_program_address(
+        &[
+            lending_market_info.key.as_ref(),
+            obligation_owner.as_ref(),
+            OBLIGATION_COUNTER_SEED,
+        ],
+        program_id,
+    );
+    if obligation_counter_key != *obligation_counter_info.key {
+        msg!("Provided obligation counter account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if obligation_counter_info.data_is_empty() {
+        return Ok(());
+    }
+    if obligation_counter_info.owner != program_id {
+        msg!("Obligation counter provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let mut obligation_counter = ObligationCounter::unpack(&obligation_counter_info.data.borrow())?;
+    obligation_counter.count = obligation_counter.count.saturating_sub(1);
+    ObligationCounter::pack(
+        obligation_counter,
+        &mut obligation_counter_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// Slot the reserve's price was read in, if RefreshObligation can value an obligation with it.
+/// Reserves refreshed in this slot always can. With a max price age, so can reserves whose price
+/// was read at most that many slots ago and is still within their oracle staleness limit, even if
+/// a deposit or another change marked them stale since.
+fn reserve_price_slot(
+    reserve: &Reserve,
+    clock: &Clock,
+    max_price_age_slots: u64,
+) -> Result<Option<Slot>, ProgramError> {
+    if !reserve.last_update.is_stale(clock.slot)? {
+        return Ok(Some(clock.slot));
+    }
+    if max_price_age_slots == 0 {
+        return Ok(None);
+    }
+
+    let price_age_slots = clock
+        .slot
+        .checked_sub(reserve.liquidity.price_slot)
+        .ok_or(LendingError::MathOverflow)?;
+    let price_age_secs = clock
+        .unix_timestamp
+        .saturating_sub(reserve.liquidity.price_publish_time);
+    let max_staleness_secs = OracleLimits::from_reserve_config(&reserve.config).max_staleness_secs;
+    if price_age_slots > max_price_age_slots || price_age_secs > max_staleness_secs as i64 {
+        return Ok(None);
+    }
+    Ok(Some(reserve.liquidity.price_slot))
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_refresh_obligation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let (accounts, max_price_age_info) = split_max_price_age_info(program_id, accounts);
+    let max_price_age_slots = match max_price_age_info {
+        Some(max_price_age_info) => {
+            let max_price_age = MaxPriceAge::unpack(&max_price_age_info.data.borrow())?;
+            if max_price_age.lending_market != obligation.lending_market {
+                msg!("Max price age provided is not for the obligation's lending market");
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+            max_price_age.max_price_age_slots as u64
+        }
+        None => 0,
+    };
+
+    let (accounts, asset_categories_info) = split_asset_categories_info(program_id, accounts);
+    let asset_categories = match asset_categories_info {
+        Some(asset_categories_info) => {
+            let asset_categories = AssetCategories::unpack(&asset_categories_info.data.borrow())?;
+            if asset_categories.lending_market != obligation.lending_market {
+                msg!("Asset categories provided do not belong to the obligation lending market");
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+            Some(asset_categories)
+        }
+        None => None,
+    };
+
+    // pair borrow weights accounts follow the deposit and borrow reserves
+    let reserve_pair_borrow_weights_iter = &mut accounts
+        .iter()
+        .skip(1 + obligation.deposits.len() + obligation.borrows.len());
+    let mut collateral_values = Vec::with_capacity(obligation.deposits.len());
+
+    let mut deposited_value = Decimal::zero();
+    let mut borrowed_value = Decimal::zero(); // weighted borrow value wrt borrow weights
+    let mut unweighted_borrowed_value = Decimal::zero();
+    let mut borrowed_value_upper_bound = Decimal::zero();
+    let mut allowed_borrow_value = Decimal::zero();
+    let mut unhealthy_borrow_value = Decimal::zero();
+    let mut super_unhealthy_borrow_value = Decimal::zero();
+
+    // values at the limits of the deposit reserves' asset categories, which apply instead if the
+    // obligation's deposits and borrows are all in the same category
+    let mut reserve_asset_categories =
+        Vec::with_capacity(obligation.deposits.len() + obligation.borrows.len());
+    let mut category_allowed_borrow_value = Decimal::zero();
+    let mut category_unhealthy_borrow_value = Decimal::zero();
+    let mut category_super_unhealthy_borrow_value = Decimal::zero();
+
+    let mut oldest_price_slot = clock.slot;
+    for (index, collateral) in obligation.deposits.iter_mut().enumerate() {
+        let deposit_reserve_info = next_account_info(account_info_iter)?;
+        if deposit_reserve_info.owner != program_id {
+            msg!(
+                "Deposit reserve provided for collateral {} is not owned by the lending program",
+                index
+            );
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+        if collateral.deposit_reserve != *deposit_reserve_info.key {
+            msg!(
+                "Deposit reserve of collateral {} does not match the deposit reserve provided",
+                index
+            );
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+
+        let deposit_reserve = Box::new(Reserve::unpack(&deposit_reserve_info.data.borrow())?);
+        match reserve_price_slot(&deposit_reserve, clock, max_price_age_slots)? {
+            Some(price_slot) => oldest_price_slot = min(oldest_price_slot, price_slot),
+            None => {
+                msg!(
+                    "Deposit reserve provided for collateral {} is stale and must be refreshed",
+                    index
+                );
+                return Err(LendingError::ReserveStale.into());
+            }
+        }
+
+        let liquidity_amount = deposit_reserve
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(collateral.deposited_amount.into())?;
+
+        let market_value = deposit_reserve.market_value(liquidity_amount)?;
+        let market_value_lower_bound =
+            deposit_reserve.market_value_lower_bound(liquidity_amount)?;
+
+        // isolated tier collateral, eg deposited before the reserve became isolated, can't back
+        // borrows whatever its max liquidation threshold is
+        let (loan_to_value_rate, liquidation_threshold_rate, max_liquidation_threshold_rate) =
+            if deposit_reserve.config.reserve_type == ReserveType::Isolated {
+                (Rate::zero(), Rate::zero(), Rate::zero())
+            } else {
+                (
+                    Rate::from_percent(deposit_reserve.config.loan_to_value_ratio),
+                    Rate::from_percent(deposit_reserve.config.liquidation_threshold),
+                    Rate::from_percent(deposit_reserve.config.max_liquidation_threshold),
+                )
+            };
+
+        let (
+            category_loan_to_value_rate,
+            category_liquidation_threshold_rate,
+            category_max_liquidation_threshold_rate,
+        ) = match asset_categories.as_ref().and_then(|asset_categories| {
+            asset_categories.get(deposit_reserve.config.asset_category)
+        }) {
+            Some(category) if deposit_reserve.config.reserve_type != ReserveType::Isolated => {
+                category.collateral_rates(&deposit_reserve.config)
+            }
+            _ => (
+                loan_to_value_rate,
+                liquidation_threshold_rate,
+                max_liquidation_threshold_rate,
+            ),
+        };
+
+        // a single asset can only back so much of an obligation's borrows, however much of it
+        // is deposited
+        let borrowing_power = |loan_to_value_rate| -> Result<Decimal, ProgramError> {
+            let borrowing_power = market_value_lower_bound.try_mul(loan_to_value_rate)?;
+            if deposit_reserve.config.max_collateral_value_contribution > 0 {
+                return Ok(min(
+                    borrowing_power,
+                    Decimal::from(deposit_reserve.config.max_collateral_value_contribution),
+                ));
+            }
+            Ok(borrowing_power)
+        };
+
+        collateral.market_value = market_value;
+        collateral_values.push((collateral.deposit_reserve, market_value));
+        reserve_asset_categories.push(deposit_reserve.config.asset_category);
+        deposited_value = deposited_value.try_add(market_value)?;
+        allowed_borrow_value =
+            allowed_borrow_value.try_add(borrowing_power(loan_to_value_rate)?)?;
+        unhealthy_borrow_value =
+            unhealthy_borrow_value.try_add(market_value.try_mul(liquidation_threshold_rate)?)?;
+        super_unhealthy_borrow_value = super_unhealthy_borrow_value
+            .try_add(market_value.try_mul(max_liquidation_threshold_rate)?)?;
+        category_allowed_borrow_value =
+            category_allowed_borrow_value.try_add(borrowing_power(category_loan_to_value_rate)?)?;
+        category_unhealthy_borrow_value = category_unhealthy_borrow_value
+            .try_add(market_value.try_mul(category_liquidation_threshold_rate)?)?;
+        category_super_unhealthy_borrow_value = category_super_unhealthy_borrow_value
+            .try_add(market_value.try_mul(category_max_liquidation_threshold_rate)?)?;
+    }
+
+    let mut borrowing_isolated_asset = false;
+    let mut max_borrow_weight = None;
+    for (index, liquidity) in obligation.borrows.iter_mut().enumerate() {
+        let borrow_reserve_info = next_account_info(account_info_iter)?;
+        if borrow_reserve_info.owner != program_id {
+            msg!(
+                "Borrow reserve provided for liquidity {} is not owned by the lending program",
+                index
+            );
+            return Err(LendingError::InvalidAccountOwner.into());
+        }
+        if liquidity.borrow_reserve != *borrow_reserve_info.key {
+            msg!(
+                "Borrow reserve of liquidity {} does not match the borrow reserve provided",
+                index
+            );
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+
+        let borrow_reserve = Box::new(Reserve::unpack(&borrow_reserve_info.data.borrow())?);
+        match reserve_price_slot(&borrow_reserve, clock, max_price_age_slots)? {
+            Some(price_slot) => oldest_price_slot = min(oldest_price_slot, price_slot),
+            None => {
+                msg!(
+                    "Borrow reserve provided for liquidity {} is stale and must be refreshed",
+                    index
+                );
+                return Err(LendingError::ReserveStale.into());
+            }
+        }
+
+        if borrow_reserve.config.reserve_type == ReserveType::Isolated
+            && liquidity.borrowed_amount_wads > Decimal::zero()
+        {
+            borrowing_isolated_asset = true;
+        }
+        reserve_asset_categories.push(borrow_reserve.config.asset_category);
+
+        liquidity.settle_rewards(&borrow_reserve)?;
+        liquidity.accrue_interest_with_rate_cap(
+            borrow_reserve.liquidity.cumulative_borrow_rate_wads,
+            clock.slot,
+        )?;
+
+        let borrow_weight_and_pubkey = (
+            borrow_reserve.config.added_borrow_weight_bps,
+            borrow_reserve_info.key,
+        );
+        max_borrow_weight = match max_borrow_weight {
+            None => {
+                if liquidity.borrowed_amount_wads > Decimal::zero() {
+                    Some((borrow_weight_and_pubkey, index))
+                } else {
+                    None
+                }
+            }
+            Some((max_borrow_weight_and_pubkey, _)) => {
+                if liquidity.borrowed_amount_wads > Decimal::zero()
+                    && borrow_weight_and_pubkey > max_borrow_weight_and_pubkey
+                {
+                    Some((borrow_weight_and_pubkey, index))
+                } else {
+                    max_borrow_weight
+                }
+            }
+        };
+
+        let market_value = borrow_reserve.borrow_market_value(liquidity.borrowed_amount_wads)?;
+        let market_value_upper_bound =
+            borrow_reserve.market_value_upper_bound(liquidity.borrowed_amount_wads)?;
+        liquidity.market_value = market_value;
+
+        let mut borrow_weight = borrow_reserve.borrow_weight();
+        if borrow_reserve.has_pair_borrow_weights {
+            let reserve_pair_borrow_weights = validate_reserve_pair_borrow_weights(
+                program_id,
+                borrow_reserve_info.key,
+                next_account_info(reserve_pair_borrow_weights_iter)?,
+            )?;
+            borrow_weight = borrow_weight.try_mul(
+                reserve_pair_borrow_weights.borrow_weight_multiplier(&collateral_values)?,
+            )?;
+        }
+
+        borrowed_value = borrowed_value.try_add(market_value.try_mul(borrow_weight)?)?;
+        borrowed_value_upper_bound =
+            borrowed_value_upper_bound.try_add(market_value_upper_bound.try_mul(borrow_weight)?)?;
+        unweighted_borrowed_value = unweighted_borrowed_value.try_add(market_value)?;
+    }
+
+    if reserve_pair_borrow_weights_iter.next().is_some() {
+        msg!("Too many obligation deposit or borrow reserves or pair borrow weights provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    // the category only ever raises limits, so they apply if they changed any value. Without the
+    // asset categories account the obligation is valued at its reserves' limits, unless the
+    // category's applied on its last refresh, so a refresh can't drop it back to them.
+    let (allowed_borrow_value, unhealthy_borrow_value, super_unhealthy_borrow_value) =
+        match shared_asset_category(&reserve_asset_categories) {
+            Some(_) if asset_categories.is_some() => {
+                obligation.uses_asset_category_limits = category_allowed_borrow_value
+                    != allowed_borrow_value
+                    || category_unhealthy_borrow_value != unhealthy_borrow_value
+                    || category_super_unhealthy_borrow_value != super_unhealthy_borrow_value;
+                (
+                    category_allowed_borrow_value,
+                    category_unhealthy_borrow_value,
+                    category_super_unhealthy_borrow_value,
+                )
+            }
+            Some(asset_category) if obligation.uses_asset_category_limits => {
+                msg!(
+                    "Obligation is valued at asset category {}'s limits and must be refreshed with the asset categories account",
+                    asset_category
+                );
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+            _ => {
+                obligation.uses_asset_category_limits = false;
+                (
+                    allowed_borrow_value,
+                    unhealthy_borrow_value,
+                    super_unhealthy_borrow_value,
+                )
+            }
+        };
+
+    obligation.deposited_value = deposited_value;
+    obligation.borrowed_value = borrowed_value;
+    obligation.unweighted_borrowed_value = unweighted_borrowed_value;
+    obligation.borrowed_value_upper_bound = borrowed_value_upper_bound;
+    obligation.borrowing_isolated_asset = borrowing_isolated_asset;
+
+    let global_unhealthy_borrow_value = Decimal::from(GLOBAL_UNHEALTHY_BORROW_VALUE);
+    let global_allowed_borrow_value = Decimal::from(GLOBAL_ALLOWED_BORROW_VALUE);
+
+    obligation.allowed_borrow_value = min(allowed_borrow_value, global_allowed_borrow_value);
+    obligation.unhealthy_borrow_value = min(unhealthy_borrow_value, global_unhealthy_borrow_value);
+    obligation.super_unhealthy_borrow_value =
+        min(super_unhealthy_borrow_value, global_unhealthy_borrow_value);
+
+    obligation.last_update.update_slot(clock.slot);
+    obligation.oldest_price_slot = oldest_price_slot;
+    obligation.update_unhealthy_since_slot(clock.slot);
+
+    let (_, close_exceeded) = update_borrow_attribution_values(&mut obligation, &accounts[1..])?;
+    if close_exceeded.is_none() {
+        obligation.closeable = false;
+    }
+
+    // move the ObligationLiquidity with the max borrow weight to the front
+    if let Some((_, max_borrow_weight_index)) = max_borrow_weight {
+        obligation.borrows.swap(0, max_borrow_weight_index);
+    }
+
+    // filter out ObligationCollaterals and ObligationLiquiditys with an amount of zero
+    obligation
+        .deposits
+        .retain(|collateral| collateral.deposited_amount > 0);
+    obligation
+        .borrows
+        .retain(|liquidity| liquidity.borrowed_amount_wads > Decimal::zero());
+
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Splits the asset categories account off the end of the accounts, where RefreshObligation takes
+/// it to value obligations whose deposits and borrows are all in the same asset category at the
+/// category's limits
+fn split_asset_categories_info<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b [AccountInfo<'a>],
+) -> (&'b [AccountInfo<'a>], Option<&'b AccountInfo<'a>>) {
+    match accounts.split_last() {
+        Some((last, rest))
+            if last.owner == program_id && last.data_len() == AssetCategories::LEN =>
+        {
+            (rest, Some(last))
+        }
+        _ => (accounts, None),
+    }
+}
+
+/// This function updates the borrow attribution value on the ObligationCollateral and
+/// the reserve.
+///
+/// Prerequisites:
+/// - the collateral's market value must be refreshed
+/// - the obligation's deposited_value must be refreshed
+/// - the obligation's true_borrowed_value must be refreshed
+///
+/// Note that this function packs and unpacks deposit reserves.
+pub(super) fn update_borrow_attribution_values(
+    obligation: &mut Obligation,
+    deposit_reserve_infos: &[AccountInfo],
+) -> Result<(Option<Pubkey>, Option<Pubkey>), ProgramError> {
+    let deposit_infos = &mut deposit_reserve_infos.iter();
+
+    let mut open_exceeded = None;
+    let mut close_exceeded = None;
+
+    for collateral in obligation.deposits.iter_mut() {
+        let deposit_reserve_info = next_account_info(deposit_infos)?;
+        let mut deposit_reserve = Reserve::unpack(&deposit_reserve_info.data.borrow())?;
+
+        // sanity check
+        if collateral.deposit_reserve != *deposit_reserve_info.key {
+            msg!("Something went wrong, deposit reserve account mismatch");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+
+        deposit_reserve.attributed_borrow_value = deposit_reserve
+            .attributed_borrow_value
+            .saturating_sub(collateral.attributed_borrow_value);
+
+        if obligation.deposited_value > Decimal::zero() {
+            collateral.attributed_borrow_value = collateral
+                .market_value
+                .try_mul(obligation.unweighted_borrowed_value)?
+                .try_div(obligation.deposited_value)?
+        } else {
+            collateral.attributed_borrow_value = Decimal::zero();
+        }
+
+        deposit_reserve.attributed_borrow_value = deposit_reserve
+            .attributed_borrow_value
+            .try_add(collateral.attributed_borrow_value)?;
+
+        if deposit_reserve.attributed_borrow_value
+            > Decimal::from(deposit_reserve.config.attributed_borrow_limit_open)
+        {
+            open_exceeded = Some(*deposit_reserve_info.key);
+        }
+        if deposit_reserve.attributed_borrow_value
+            > Decimal::from(deposit_reserve.config.attributed_borrow_limit_close)
+        {
+            close_exceeded = Some(*deposit_reserve_info.key);
+        }
+
+        Reserve::pack(deposit_reserve, &mut deposit_reserve_info.data.borrow_mut())?;
+    }
+
+    Ok((open_exceeded, close_exceeded))
+}
+
+/// process init obligation archive
+pub(super) fn process_init_obligation_archive(
+    program_id: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let merkle_tree_info = next_account_info(account_info_iter)?;
+    let account_compression_program_info = next_account_info(account_info_iter)?;
+    let noop_program_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_lending_market_owner(&lending_market, lending_market_owner_info)?;
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    if account_compression_program_info.key != &spl_account_compression::id() {
+        msg!("Account compression program provided does not match the expected program id");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if noop_program_info.key != &spl_noop::id() {
+        msg!("Noop program provided does not match the expected program id");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if merkle_tree_info.owner != account_compression_program_info.key {
+        msg!("Merkle tree provided is not owned by the account compression program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let mut data = ACCOUNT_COMPRESSION_INIT_EMPTY_MERKLE_TREE.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+
+    invoke_signed(
+        &Instruction {
+            program_id: *account_compression_program_info.key,
+            accounts: vec![
+                AccountMeta::new(*merkle_tree_info.key, false),
+                AccountMeta::new_readonly(*lending_market_authority_info.key, true),
+                AccountMeta::new_readonly(*noop_program_info.key, false),
+            ],
+            data,
+        },
+        &[
+            merkle_tree_info.clone(),
+            lending_market_authority_info.clone(),
+            noop_program_info.clone(),
+            account_compression_program_info.clone(),
+        ],
+        &[authority_signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+/// process archive obligation
+pub(super) fn process_archive_obligation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let merkle_tree_info = next_account_info(account_info_iter)?;
+    let account_compression_program_info = next_account_info(account_info_iter)?;
+    let noop_program_info = next_account_info(account_info_iter)?;
+    let obligation_counter_info = next_account_info(account_info_iter)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+    if !obligation.deposits.is_empty() || !obligation.borrows.is_empty() {
+        msg!("Obligation must have no deposits and no borrows to be archived");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if destination_info.key == obligation_info.key {
+        msg!("Obligation cannot be used as the destination account");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    if account_compression_program_info.key != &spl_account_compression::id() {
+        msg!("Account compression program provided does not match the expected program id");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if noop_program_info.key != &spl_noop::id() {
+        msg!("Noop program provided does not match the expected program id");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    // the leaf commits to the obligation address and its final packed state. the preimage is
+    // logged so indexers can reconstruct the archived obligation.
+    let leaf = {
+        let obligation_data = obligation_info.data.borrow();
+        sol_log_data(&[obligation_info.key.as_ref(), &obligation_data]);
+        keccak::hashv(&[obligation_info.key.as_ref(), &obligation_data]).to_bytes()
+    };
+
+    let mut data = ACCOUNT_COMPRESSION_APPEND.to_vec();
+    data.extend_from_slice(&leaf);
+
+    invoke_signed(
+        &Instruction {
+            program_id: *account_compression_program_info.key,
+            accounts: vec![
+                AccountMeta::new(*merkle_tree_info.key, false),
+                AccountMeta::new_readonly(*lending_market_authority_info.key, true),
+                AccountMeta::new_readonly(*noop_program_info.key, false),
+            ],
+            data,
+        },
+        &[
+            merkle_tree_info.clone(),
+            lending_market_authority_info.clone(),
+            noop_program_info.clone(),
+            account_compression_program_info.clone(),
+        ],
+        &[authority_signer_seeds],
+    )?;
+
+    _uncount_obligation(
+        program_id,
+        lending_market_info,
+        &obligation.owner,
+        obligation_counter_info,
+    )?;
+
+    // close the obligation account
+    let destination_lamports = destination_info.lamports();
+    **destination_info.lamports.borrow_mut() = destination_lamports
+        .checked_add(obligation_info.lamports())
+        .ok_or(LendingError::MathOverflow)?;
+    **obligation_info.lamports.borrow_mut() = 0;
+    obligation_info.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// process set obligation referrer
+pub(super) fn process_set_obligation_referrer(
+    program_id: &Pubkey,
+    referrer: Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+    if obligation.referrer.is_some() {
+        msg!("Obligation referrer can only be set once");
+        return Err(LendingError::AlreadyInitialized.into());
+    }
+
+    obligation.referrer = Some(referrer);
+    msg!("Obligation referrer set to {}", referrer);
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process crank close empty obligation
+pub(super) fn process_crank_close_empty_obligation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let bounty_destination_info = next_account_info(account_info_iter)?;
+    let obligation_counter_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    if &obligation.owner != obligation_owner_info.key {
+        msg!("Obligation owner does not match the obligation owner provided");
+        return Err(LendingError::InvalidObligationOwner.into());
+    }
+    if !obligation.is_empty_and_idle(clock.slot)? {
+        msg!(
+            "Obligation must have no deposits and no borrows and be idle for more than {} slots to be closed",
+            EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if bounty_destination_info.key == obligation_info.key
+        || obligation_owner_info.key == obligation_info.key
+    {
+        msg!("Obligation cannot be used as the destination account");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let obligation_lamports = obligation_info.lamports();
+    let bounty = obligation_lamports
+        .checked_mul(EMPTY_OBLIGATION_CRANK_BOUNTY_PERCENT as u64)
+        .ok_or(LendingError::MathOverflow)?
+        / 100;
+    let refund = obligation_lamports
+        .checked_sub(bounty)
+        .ok_or(LendingError::MathOverflow)?;
+
+    _uncount_obligation(
+        program_id,
+        lending_market_info,
+        &obligation.owner,
+        obligation_counter_info,
+    )?;
+
+    // close the obligation account
+    let bounty_destination_lamports = bounty_destination_info.lamports();
+    **bounty_destination_info.lamports.borrow_mut() = bounty_destination_lamports
+        .checked_add(bounty)
+        .ok_or(LendingError::MathOverflow)?;
+    let obligation_owner_lamports = obligation_owner_info.lamports();
+    **obligation_owner_info.lamports.borrow_mut() = obligation_owner_lamports
+        .checked_add(refund)
+        .ok_or(LendingError::MathOverflow)?;
+    **obligation_info.lamports.borrow_mut() = 0;
+    obligation_info.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// process set obligation borrow lock
+pub(super) fn process_set_obligation_borrow_lock(
+    program_id: &Pubkey,
+    borrows_locked: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+
+    obligation.set_borrow_lock(borrows_locked, clock.slot)?;
+    if obligation.borrow_unlock_slot != 0 {
+        msg!("Borrows unlock at slot {}", obligation.borrow_unlock_slot);
+    }
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process set obligation borrow cap
+pub(super) fn process_set_obligation_borrow_cap(
+    program_id: &Pubkey,
+    borrow_cap: u32,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+
+    msg!(
+        "Borrow cap changed from {} to {}",
+        obligation.borrow_cap,
+        borrow_cap
+    );
+    obligation.set_borrow_cap(borrow_cap, clock.slot)?;
+    if obligation.borrow_unlock_slot != 0 {
+        msg!("Borrows unlock at slot {}", obligation.borrow_unlock_slot);
+    }
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process delegate borrow authority
+pub(super) fn process_delegate_borrow_authority(
+    program_id: &Pubkey,
+    approved_borrower: Pubkey,
+    delegated_value: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let obligation_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+
+    validate_lending_market_account(program_id, lending_market_info)?;
+
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    validate_obligation_account(
+        program_id,
+        obligation_info,
+        &obligation,
+        lending_market_info,
+    )?;
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+
+    obligation.approved_borrower = Some(approved_borrower);
+    obligation.delegated_value = Decimal::from(delegated_value);
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    msg!(
+        "Delegated ${} of borrow authority to {}",
+        delegated_value,
+        approved_borrower
+    );
+    Ok(())
+}
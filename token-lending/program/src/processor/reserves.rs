@@ -0,0 +1,554 @@
+//! Refreshing reserves and pricing their liquidity
+
+use super::*;
+
+pub(super) fn process_refresh_reserve(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    // an optional reserve rate stats account always comes last
+    let (accounts, reserve_rate_stats_info) = match accounts.split_last() {
+        Some((last, rest))
+            if rest.len() >= 3
+                && last.owner == program_id
+                && last.data_len() == ReserveRateStats::LEN =>
+        {
+            (rest, Some(last))
+        }
+        _ => (accounts, None),
+    };
+
+    let account_info_iter = &mut accounts.iter().peekable();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let pyth_price_info = next_account_info(account_info_iter)?;
+    let switchboard_feed_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    let tertiary_oracle_info = match reserve.tertiary_oracle_pubkey {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
+
+    // sample the rates that were in effect up to this refresh
+    let rate_sample = match reserve_rate_stats_info {
+        Some(_) => Some((
+            reserve.liquidity.utilization_rate()?,
+            reserve.current_borrow_rate()?,
+        )),
+        None => None,
+    };
+
+    let extra_oracle_account_info = next_account_info(account_info_iter).ok();
+    let emergency_price_info = next_account_info(account_info_iter).ok();
+    _refresh_reserve(
+        program_id,
+        reserve_info,
+        pyth_price_info,
+        Some(switchboard_feed_info),
+        tertiary_oracle_info,
+        clock,
+        extra_oracle_account_info,
+        emergency_price_info,
+    )?;
+
+    if let (Some(reserve_rate_stats_info), Some((utilization_rate, borrow_rate))) =
+        (reserve_rate_stats_info, rate_sample)
+    {
+        let mut reserve_rate_stats =
+            ReserveRateStats::unpack(&reserve_rate_stats_info.data.borrow())?;
+        if reserve_rate_stats.reserve != *reserve_info.key {
+            msg!("Reserve rate stats account provided is not for the reserve provided");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+        reserve_rate_stats.update(utilization_rate, borrow_rate, clock.slot)?;
+        ReserveRateStats::pack(
+            reserve_rate_stats,
+            &mut reserve_rate_stats_info.data.borrow_mut(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates the account tracking the moving averages of a reserve's rates. Permissionless.
+pub(super) fn process_init_reserve_rate_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_rate_stats_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let reserve_rate_stats_seeds = &[reserve_info.key.as_ref(), RESERVE_RATE_STATS_SEED];
+    let (reserve_rate_stats_key, bump_seed) =
+        Pubkey::find_program_address(reserve_rate_stats_seeds, program_id);
+    if reserve_rate_stats_key != *reserve_rate_stats_info.key {
+        msg!("Provided reserve rate stats account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reserve_rate_stats_info.data_is_empty() {
+        invoke_signed(
+            &create_account(
+                payer_info.key,
+                reserve_rate_stats_info.key,
+                Rent::get()?.minimum_balance(ReserveRateStats::LEN),
+                ReserveRateStats::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), reserve_rate_stats_info.clone()],
+            &[&[
+                reserve_info.key.as_ref(),
+                RESERVE_RATE_STATS_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if reserve_rate_stats_info.owner != program_id {
+        msg!("Reserve rate stats account provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if ReserveRateStats::unpack_unchecked(&reserve_rate_stats_info.data.borrow())?.is_initialized()
+    {
+        msg!("Reserve rate stats account is already initialized");
+        return Err(LendingError::AlreadyInitialized.into());
+    }
+
+    ReserveRateStats::pack(
+        ReserveRateStats {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            reserve: *reserve_info.key,
+            last_update_slot: clock.slot,
+            average_utilization_rate: reserve.liquidity.utilization_rate()?.into(),
+            average_borrow_rate: reserve.current_borrow_rate()?.into(),
+        },
+        &mut reserve_rate_stats_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// Creates the account counting a reserve's activity. Permissionless.
+pub(super) fn process_init_reserve_activity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_activity_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+
+    Reserve::unpack(&reserve_info.data.borrow())?;
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let reserve_activity_seeds = &[reserve_info.key.as_ref(), RESERVE_ACTIVITY_SEED];
+    let (reserve_activity_key, bump_seed) =
+        Pubkey::find_program_address(reserve_activity_seeds, program_id);
+    if reserve_activity_key != *reserve_activity_info.key {
+        msg!("Provided reserve activity account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reserve_activity_info.data_is_empty() {
+        invoke_signed(
+            &create_account(
+                payer_info.key,
+                reserve_activity_info.key,
+                Rent::get()?.minimum_balance(ReserveActivity::LEN),
+                ReserveActivity::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), reserve_activity_info.clone()],
+            &[&[
+                reserve_info.key.as_ref(),
+                RESERVE_ACTIVITY_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if reserve_activity_info.owner != program_id {
+        msg!("Reserve activity account provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if ReserveActivity::unpack_unchecked(&reserve_activity_info.data.borrow())?.is_initialized() {
+        msg!("Reserve activity account is already initialized");
+        return Err(LendingError::AlreadyInitialized.into());
+    }
+
+    ReserveActivity::pack(
+        ReserveActivity {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            reserve: *reserve_info.key,
+            ..ReserveActivity::default()
+        },
+        &mut reserve_activity_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+/// Writes the price of a refreshed reserve's collateral token into its ctoken price account,
+/// creating the account on first use. Permissionless.
+pub(super) fn process_refresh_ctoken_price(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_ctoken_price_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if reserve.last_update.is_stale(clock.slot)? {
+        msg!("Reserve is stale and must be refreshed in the current slot");
+        return Err(LendingError::ReserveStale.into());
+    }
+
+    let reserve_ctoken_price_seeds = &[reserve_info.key.as_ref(), RESERVE_CTOKEN_PRICE_SEED];
+    let (reserve_ctoken_price_key, bump_seed) =
+        Pubkey::find_program_address(reserve_ctoken_price_seeds, program_id);
+    if reserve_ctoken_price_key != *reserve_ctoken_price_info.key {
+        msg!("Provided reserve ctoken price account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reserve_ctoken_price_info.data_is_empty() {
+        invoke_signed(
+            &create_account(
+                payer_info.key,
+                reserve_ctoken_price_info.key,
+                Rent::get()?.minimum_balance(ReserveCTokenPrice::LEN),
+                ReserveCTokenPrice::LEN as u64,
+                program_id,
+            ),
+            &[payer_info.clone(), reserve_ctoken_price_info.clone()],
+            &[&[
+                reserve_info.key.as_ref(),
+                RESERVE_CTOKEN_PRICE_SEED,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if reserve_ctoken_price_info.owner != program_id {
+        msg!("Reserve ctoken price account provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let mut reserve_ctoken_price =
+        ReserveCTokenPrice::unpack_unchecked(&reserve_ctoken_price_info.data.borrow())?;
+    if !reserve_ctoken_price.is_initialized() {
+        reserve_ctoken_price = ReserveCTokenPrice {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            reserve: *reserve_info.key,
+            ..ReserveCTokenPrice::default()
+        };
+    }
+    reserve_ctoken_price.update(&reserve, clock.slot)?;
+    ReserveCTokenPrice::pack(
+        reserve_ctoken_price,
+        &mut reserve_ctoken_price_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn _refresh_reserve<'a>(
+    program_id: &Pubkey,
+    reserve_info: &AccountInfo<'a>,
+    pyth_price_info: &AccountInfo<'a>,
+    switchboard_feed_info: Option<&AccountInfo<'a>>,
+    tertiary_oracle_info: Option<&AccountInfo<'a>>,
+    clock: &Clock,
+    extra_oracle_account_info: Option<&AccountInfo<'a>>,
+    emergency_price_info: Option<&AccountInfo<'a>>,
+) -> ProgramResult {
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &reserve.liquidity.pyth_oracle_pubkey != pyth_price_info.key {
+        msg!("Reserve liquidity pyth oracle does not match the reserve liquidity pyth oracle provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    // the first check is to allow for the only passing in pyth case
+    // TODO maybe change this to is_some_and later
+    if switchboard_feed_info.is_some()
+        && &reserve.liquidity.switchboard_oracle_pubkey != switchboard_feed_info.unwrap().key
+    {
+        msg!("Reserve liquidity switchboard oracle does not match the reserve liquidity switchboard oracle provided");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+    if let Some(tertiary_oracle_info) = tertiary_oracle_info {
+        if Some(*tertiary_oracle_info.key) != reserve.tertiary_oracle_pubkey {
+            msg!("Tertiary oracle provided does not match the reserve's tertiary oracle");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+    }
+
+    // oracles in the order they're tried
+    let mut oracle_infos = vec![pyth_price_info];
+    oracle_infos.extend(switchboard_feed_info);
+    oracle_infos.extend(tertiary_oracle_info);
+
+    // the emergency price account takes the extra oracle's place if the reserve doesn't have one
+    let (extra_oracle_account_info, emergency_price_info) = match reserve.config.extra_oracle_pubkey
+    {
+        Some(_) => (extra_oracle_account_info, emergency_price_info),
+        None => (None, extra_oracle_account_info),
+    };
+
+    let (market_price, smoothed_market_price, price_publish_time) = match get_price(
+        &oracle_infos,
+        clock,
+        &OracleLimits::from_reserve_config(&reserve.config),
+    ) {
+        Ok(prices) => {
+            reserve.emergency_price_active = false;
+            prices
+        }
+        Err(e) => {
+            let emergency_price = match emergency_price_info {
+                Some(emergency_price_info) => {
+                    get_emergency_price(program_id, reserve_info.key, emergency_price_info, clock)?
+                }
+                None => return Err(e),
+            };
+            reserve.emergency_price_active = true;
+            (emergency_price, Some(emergency_price), clock.unix_timestamp)
+        }
+    };
+    reserve.liquidity.price_slot = clock.slot;
+    reserve.liquidity.price_publish_time = price_publish_time;
+
+    reserve.liquidity.market_price =
+        reserve.cap_price(market_price.try_mul(reserve.price_scale())?);
+
+    if let Some(smoothed_market_price) = smoothed_market_price {
+        reserve.liquidity.smoothed_market_price =
+            reserve.cap_price(smoothed_market_price.try_mul(reserve.price_scale())?);
+    }
+
+    reserve.liquidity.extra_market_price = match reserve.config.extra_oracle_pubkey {
+        None => None,
+
+        Some(extra_oracle_pubkey) => match extra_oracle_account_info {
+            Some(extra_oracle_account_info) => {
+                if extra_oracle_account_info.key != &extra_oracle_pubkey {
+                    msg!("Reserve extra oracle does not match the reserve extra oracle provided");
+                    return Err(LendingError::InvalidAccountInput.into());
+                }
+
+                Some(reserve.cap_price(get_single_price_unchecked(
+                    extra_oracle_account_info,
+                    clock,
+                )?))
+            }
+            None => {
+                msg!("Reserve extra oracle account info missing");
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+        },
+    };
+
+    // currently there's no way to support two prices without a pyth oracle. So if a reserve
+    // only supports switchboard, reserve.smoothed_market_price == reserve.market_price
+    if reserve.liquidity.pyth_oracle_pubkey == solend_program::NULL_PUBKEY {
+        reserve.liquidity.smoothed_market_price = reserve.liquidity.market_price;
+    }
+
+    if reserve.update_utilization_kill_switch(clock.slot)? {
+        msg!("Reserve utilization stayed above the emergency threshold, borrows are now blocked");
+        sol_log_data(&[b"ReserveEmergencyMode", reserve_info.key.as_ref()]);
+    }
+
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)
+}
+
+/// Lite version of refresh_reserve that should be used when the oracle price doesn't need to be updated
+/// BE CAREFUL WHEN USING THIS
+pub(super) fn _refresh_reserve_interest(
+    program_id: &Pubkey,
+    reserve_info: &AccountInfo<'_>,
+    clock: &Clock,
+) -> ProgramResult {
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    reserve.accrue_rewards(clock.slot)?;
+    reserve.accrue_interest(clock.slot)?;
+    reserve.last_update.update_slot(clock.slot);
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// process donate to reserve
+pub(super) fn process_donate_to_reserve(
+    program_id: &Pubkey,
+    liquidity_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if liquidity_amount == 0 {
+        msg!("Liquidity amount provided cannot be zero");
+        return Err(LendingError::InvalidAmount.into());
+    }
+    let account_info_iter = &mut accounts.iter();
+    let source_liquidity_info = next_account_info(account_info_iter)?;
+    let destination_liquidity_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+
+    _refresh_reserve_interest(program_id, reserve_info, clock)?;
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    if &reserve.liquidity.supply_pubkey != destination_liquidity_info.key {
+        msg!("Reserve liquidity supply does not match the reserve liquidity supply provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if &reserve.liquidity.supply_pubkey == source_liquidity_info.key {
+        msg!("Reserve liquidity supply cannot be used as the source liquidity provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    // with no collateral outstanding, the next depositor would get the whole donation
+    if reserve.collateral.mint_total_supply == 0 {
+        msg!("Reserve has no collateral outstanding to donate to");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    reserve.liquidity.donate(liquidity_amount)?;
+    spl_token_transfer(TokenTransferParams {
+        source: source_liquidity_info.clone(),
+        destination: destination_liquidity_info.clone(),
+        amount: liquidity_amount,
+        authority: user_transfer_authority_info.clone(),
+        authority_signer_seeds: &[],
+        token_program: token_program_id.clone(),
+    })?;
+
+    reserve.last_update.mark_stale();
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    DepositEvent {
+        kind: DepositKind::Donate,
+        reserve: *reserve_info.key,
+        authority: *user_transfer_authority_info.key,
+        liquidity_amount,
+        collateral_amount: 0,
+    }
+    .log();
+
+    Ok(())
+}
+
+/// Loads the emergency price for a reserve whose oracles have failed
+fn get_emergency_price(
+    program_id: &Pubkey,
+    reserve_key: &Pubkey,
+    emergency_price_info: &AccountInfo,
+    clock: &Clock,
+) -> Result<Decimal, ProgramError> {
+    if emergency_price_info.owner != program_id {
+        msg!("Emergency price provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let emergency_price = EmergencyPrice::unpack(&emergency_price_info.data.borrow())?;
+    if &emergency_price.reserve != reserve_key {
+        msg!("Emergency price reserve does not match the reserve provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if !emergency_price.is_active(clock.slot) {
+        msg!(
+            "Oracles are unavailable and the emergency price expired at slot {}",
+            emergency_price.expiry_slot
+        );
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    msg!(
+        "Oracles are unavailable, using emergency price {} set at slot {}",
+        emergency_price.price,
+        emergency_price.set_slot
+    );
+    sol_log_data(&[
+        b"EmergencyPriceUsed",
+        reserve_key.as_ref(),
+        &emergency_price.price.to_scaled_val()?.to_le_bytes(),
+    ]);
+
+    Ok(emergency_price.price)
+}
+
+/// get_price walks the oracles in order, usually pyth, then switchboard, then the tertiary
+/// oracle, and returns the price of the first one with a fresh price.
+/// The first element in the returned tuple is the market price, the second is the optional
+/// smoothed price (eg ema, twap), and the third is the publish time of the market price.
+pub(super) fn get_price(
+    price_account_infos: &[&AccountInfo],
+    clock: &Clock,
+    limits: &OracleLimits,
+) -> Result<(Decimal, Option<Decimal>, UnixTimestamp), ProgramError> {
+    #[cfg(feature = "devnet-utils")]
+    if let Some(main_price_account_info) = price_account_infos.first() {
+        if let Some(price) = get_devnet_price(main_price_account_info)? {
+            return Ok((price, Some(price), clock.unix_timestamp));
+        }
+    }
+
+    for (index, price_account_info) in price_account_infos.iter().enumerate() {
+        // TODO: add support for fallback smoothed prices. Probably need to add a new
+        // secondary account per reserve.
+        if let Ok(prices) = get_single_price(price_account_info, clock, limits) {
+            if index > 0 {
+                msg!(
+                    "Using price from fallback oracle {}: {}",
+                    index,
+                    price_account_info.key
+                );
+            }
+            let publish_time = get_single_price_publish_time(price_account_info, clock, limits)?;
+            return Ok((prices.0, prices.1, publish_time));
+        }
+    }
+
+    Err(LendingError::InvalidOracleConfig.into())
+}
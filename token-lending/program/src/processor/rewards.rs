@@ -0,0 +1,257 @@
+//! Liquidity mining rewards
+
+use super::*;
+
+/// process_set_reward_rule creates or reconfigures a reserve's reward rule
+pub(super) fn process_set_reward_rule(
+    program_id: &Pubkey,
+    side: RewardSide,
+    emission_per_slot: u64,
+    start_slot: u64,
+    end_slot: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reward_rule_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let reward_supply_info = next_account_info(account_info_iter)?;
+    let signer_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    if &lending_market.owner != signer_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !signer_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+    validate_reserve_account(program_id, reserve_info, &reserve, lending_market_info)?;
+
+    if end_slot < start_slot {
+        msg!("Reward rule end slot must not be before its start slot");
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    if reward_supply_info.owner != &lending_market.token_program_id {
+        msg!("Reward supply provided is not owned by the lending market token program");
+        return Err(LendingError::InvalidTokenOwner.into());
+    }
+    let reward_supply = unpack_token_account(&reward_supply_info.data.borrow())?;
+    let lending_market_authority_pubkey =
+        lending_market.authority(program_id, lending_market_info.key)?;
+    if reward_supply.owner != lending_market_authority_pubkey {
+        msg!("Reward supply must be owned by the lending market authority");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let side_seed = [side as u8];
+    let reward_rule_seeds = &[reserve_info.key.as_ref(), REWARD_RULE_SEED, &side_seed];
+    let (reward_rule_key, bump_seed) = Pubkey::find_program_address(reward_rule_seeds, program_id);
+    if reward_rule_key != *reward_rule_info.key {
+        msg!("Provided reward rule account does not match the expected derived address");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reward_rule_info.data_is_empty() {
+        msg!("Creating reward rule account");
+
+        invoke_signed(
+            &create_account(
+                signer_info.key,
+                reward_rule_info.key,
+                Rent::get()?.minimum_balance(RewardRule::LEN),
+                RewardRule::LEN as u64,
+                program_id,
+            ),
+            &[signer_info.clone(), reward_rule_info.clone()],
+            &[&[
+                reserve_info.key.as_ref(),
+                REWARD_RULE_SEED,
+                &side_seed,
+                &[bump_seed],
+            ]],
+        )?;
+    }
+
+    if reward_rule_info.owner != program_id {
+        msg!("Reward rule provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+
+    let mut reward_rule = RewardRule::unpack_unchecked(&reward_rule_info.data.borrow())?;
+    if reward_rule.is_initialized() {
+        if &reward_rule.reward_supply != reward_supply_info.key {
+            msg!("Reward supply does not match the reward rule's reward supply");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+    } else {
+        reward_rule = RewardRule {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: *lending_market_info.key,
+            reserve: *reserve_info.key,
+            side,
+            reward_mint: reward_supply.mint,
+            reward_supply: *reward_supply_info.key,
+        };
+    }
+
+    reserve.accrue_rewards(clock.slot)?;
+    let rewards = reserve.rewards_mut(side);
+    // a new schedule doesn't pay out for slots before it was set
+    rewards.last_update_slot = rewards.last_update_slot.max(clock.slot);
+    rewards.emission_per_slot = emission_per_slot;
+    rewards.start_slot = start_slot;
+    rewards.end_slot = end_slot;
+    msg!(
+        "Reward rule for reserve {} emits {} per slot from slot {} to {}",
+        reserve_info.key,
+        emission_per_slot,
+        start_slot,
+        end_slot
+    );
+    sol_log_data(&[
+        b"SetRewardRule",
+        reward_rule_info.key.as_ref(),
+        &emission_per_slot.to_le_bytes(),
+        &start_slot.to_le_bytes(),
+        &end_slot.to_le_bytes(),
+    ]);
+    RewardRule::pack(reward_rule, &mut reward_rule_info.data.borrow_mut())?;
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_update_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    if obligation.lending_market != reserve.lending_market {
+        msg!("Obligation lending market does not match the reserve lending market");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    reserve.accrue_rewards(clock.slot)?;
+    obligation.settle_rewards(reserve_info.key, &reserve)?;
+
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+pub(super) fn process_claim_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let reward_rule_info = next_account_info(account_info_iter)?;
+    let reserve_info = next_account_info(account_info_iter)?;
+    let obligation_info = next_account_info(account_info_iter)?;
+    let reward_supply_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_authority_info = next_account_info(account_info_iter)?;
+    let obligation_owner_info = next_account_info(account_info_iter)?;
+    let token_program_id = next_account_info(account_info_iter)?;
+    let clock = Clock::get()?;
+
+    if reward_rule_info.owner != program_id {
+        msg!("Reward rule provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let reward_rule = RewardRule::unpack(&reward_rule_info.data.borrow())?;
+    if &reward_rule.reserve != reserve_info.key {
+        msg!("Reward rule reserve does not match the reserve provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reward_rule.reward_supply != reward_supply_info.key {
+        msg!("Reward supply does not match the reward rule's reward supply");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if &reward_rule.lending_market != lending_market_info.key {
+        msg!("Reward rule lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let mut reserve = Box::new(Reserve::unpack(&reserve_info.data.borrow())?);
+
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let mut obligation = Obligation::unpack_account(&obligation_info.data.borrow())?;
+    if obligation.lending_market != reward_rule.lending_market {
+        msg!("Obligation lending market does not match the reward rule lending market");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    validate_obligation_owner(&obligation, obligation_owner_info)?;
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    validate_lending_market_account(program_id, lending_market_info)?;
+    validate_token_program(&lending_market, token_program_id)?;
+    let authority_signer_seeds = &[
+        lending_market_info.key.as_ref(),
+        &[lending_market.bump_seed],
+    ];
+    validate_lending_market_authority(
+        program_id,
+        &lending_market,
+        lending_market_info,
+        lending_market_authority_info,
+    )?;
+
+    reserve.accrue_rewards(clock.slot)?;
+    let claim_amount = obligation.claim_rewards(reserve_info.key, &reserve, reward_rule.side)?;
+
+    Reserve::pack(*reserve, &mut reserve_info.data.borrow_mut())?;
+    Obligation::pack_account(obligation, &mut obligation_info.data.borrow_mut())?;
+
+    if claim_amount > 0 {
+        sol_log_data(&[
+            b"ClaimRewards",
+            obligation_info.key.as_ref(),
+            reward_rule_info.key.as_ref(),
+            &claim_amount.to_le_bytes(),
+        ]);
+        spl_token_transfer(TokenTransferParams {
+            source: reward_supply_info.clone(),
+            destination: destination_info.clone(),
+            amount: claim_amount,
+            authority: lending_market_authority_info.clone(),
+            authority_signer_seeds,
+            token_program: token_program_id.clone(),
+        })?;
+    }
+
+    Ok(())
+}
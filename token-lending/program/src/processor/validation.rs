@@ -0,0 +1,395 @@
+//! Account checks shared by the instruction processors
+
+use super::*;
+
+/// Checks that the lending market account is owned by the lending program
+pub(super) fn validate_lending_market_account(
+    program_id: &Pubkey,
+    lending_market_info: &AccountInfo,
+) -> ProgramResult {
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    Ok(())
+}
+
+/// Checks that the token program provided is the lending market's
+pub(super) fn validate_token_program(
+    lending_market: &LendingMarket,
+    token_program_id: &AccountInfo,
+) -> ProgramResult {
+    if &lending_market.token_program_id != token_program_id.key {
+        msg!("Lending market token program does not match the token program provided");
+        return Err(LendingError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+/// Checks that the lending market owner provided is the market's owner and signed
+pub(super) fn validate_lending_market_owner(
+    lending_market: &LendingMarket,
+    lending_market_owner_info: &AccountInfo,
+) -> ProgramResult {
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+    Ok(())
+}
+
+/// Checks that the lending market authority provided is the lending market's authority
+pub(super) fn validate_lending_market_authority(
+    program_id: &Pubkey,
+    lending_market: &LendingMarket,
+    lending_market_info: &AccountInfo,
+    lending_market_authority_info: &AccountInfo,
+) -> ProgramResult {
+    let lending_market_authority_pubkey =
+        lending_market.authority(program_id, lending_market_info.key)?;
+    if &lending_market_authority_pubkey != lending_market_authority_info.key {
+        msg!(
+            "Derived lending market authority does not match the lending market authority provided"
+        );
+        return Err(LendingError::InvalidMarketAuthority.into());
+    }
+    Ok(())
+}
+
+/// Checks that the reserve is owned by the lending program and belongs to the lending market
+pub(super) fn validate_reserve_account(
+    program_id: &Pubkey,
+    reserve_info: &AccountInfo,
+    reserve: &Reserve,
+    lending_market_info: &AccountInfo,
+) -> ProgramResult {
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &reserve.lending_market != lending_market_info.key {
+        msg!("Reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(())
+}
+
+/// Checks that the obligation is owned by the lending program and belongs to the lending market
+pub(super) fn validate_obligation_account(
+    program_id: &Pubkey,
+    obligation_info: &AccountInfo,
+    obligation: &Obligation,
+    lending_market_info: &AccountInfo,
+) -> ProgramResult {
+    if obligation_info.owner != program_id {
+        msg!("Obligation provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &obligation.lending_market != lending_market_info.key {
+        msg!("Obligation lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(())
+}
+
+/// Checks that the obligation owner provided is the obligation's owner and signed
+pub(super) fn validate_obligation_owner(
+    obligation: &Obligation,
+    obligation_owner_info: &AccountInfo,
+) -> ProgramResult {
+    if &obligation.owner != obligation_owner_info.key {
+        msg!("Obligation owner does not match the obligation owner provided");
+        return Err(LendingError::InvalidObligationOwner.into());
+    }
+    if !obligation_owner_info.is_signer {
+        msg!("Obligation owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+    Ok(())
+}
+
+/// Checks that the signer provided is the obligation's approved borrower
+pub(super) fn validate_approved_borrower(
+    obligation: &Obligation,
+    approved_borrower_info: &AccountInfo,
+) -> ProgramResult {
+    if obligation.approved_borrower != Some(*approved_borrower_info.key) {
+        msg!("Signer is neither the obligation owner nor its approved borrower");
+        return Err(LendingError::InvalidObligationOwner.into());
+    }
+    if !approved_borrower_info.is_signer {
+        msg!("Approved borrower provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+    Ok(())
+}
+
+/// Checks that a wallet is a member of the lending market, if the market requires a whitelist
+pub(super) fn validate_market_member(
+    program_id: &Pubkey,
+    lending_market_info: &AccountInfo,
+    member_info: &AccountInfo,
+    market_member_info: Option<&AccountInfo>,
+) -> ProgramResult {
+    validate_lending_market_account(program_id, lending_market_info)?;
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if !lending_market.whitelist_required {
+        return Ok(());
+    }
+
+    let market_member_info = match market_member_info {
+        Some(market_member_info) => market_member_info,
+        None => {
+            msg!(
+                "Lending market requires a whitelist, the market member account of {} must be provided",
+                member_info.key
+            );
+            return Err(LendingError::NotMarketMember.into());
+        }
+    };
+    if market_member_info.owner != program_id {
+        msg!("Market member provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let market_member = MarketMember::unpack(&market_member_info.data.borrow())?;
+    if &market_member.lending_market != lending_market_info.key
+        || &market_member.member != member_info.key
+    {
+        msg!(
+            "Market member provided is not for {} in the lending market",
+            member_info.key
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if !market_member.is_member {
+        msg!(
+            "Membership of {} in the lending market was revoked",
+            member_info.key
+        );
+        return Err(LendingError::NotMarketMember.into());
+    }
+    Ok(())
+}
+
+/// Checks that a liquidator can liquidate an obligation, if the lending market protects newly
+/// unhealthy obligations for its liquidator whitelist
+pub(super) fn validate_liquidator_whitelist(
+    program_id: &Pubkey,
+    lending_market: &LendingMarket,
+    lending_market_info: &AccountInfo,
+    obligation: &Obligation,
+    liquidator_info: &AccountInfo,
+    liquidator_whitelist_info: Option<&AccountInfo>,
+    clock: &Clock,
+) -> ProgramResult {
+    if !lending_market.has_liquidator_whitelist {
+        return Ok(());
+    }
+
+    let liquidator_whitelist_info = match liquidator_whitelist_info {
+        Some(liquidator_whitelist_info) => liquidator_whitelist_info,
+        None => {
+            msg!("Lending market has a liquidator whitelist, the liquidator whitelist account must be provided");
+            return Err(LendingError::InvalidAccountInput.into());
+        }
+    };
+    if liquidator_whitelist_info.owner != program_id {
+        msg!("Liquidator whitelist provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let liquidator_whitelist =
+        LiquidatorWhitelist::unpack(&liquidator_whitelist_info.data.borrow())?;
+    if &liquidator_whitelist.lending_market != lending_market_info.key {
+        msg!("Liquidator whitelist provided is not for the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    if !liquidator_whitelist.can_liquidate(
+        liquidator_info.key,
+        obligation.unhealthy_since_slot,
+        clock.slot,
+    ) {
+        msg!(
+            "Obligation is in its protected liquidation window until slot {}, only whitelisted liquidators can liquidate it",
+            obligation
+                .unhealthy_since_slot
+                .saturating_add(liquidator_whitelist.protected_window_slots)
+        );
+        return Err(LendingError::NotWhitelistedLiquidator.into());
+    }
+    Ok(())
+}
+
+/// Checks that a token account is the lending market's insurance fund for a reserve's liquidity
+/// mint. The fund can only be created by InitInsuranceFund, so its address pins down its mint and
+/// owner. Reserves that pay into the fund store its bump seed, the others share it with a reserve
+/// of the same mint and have the address derived.
+pub(super) fn validate_insurance_fund(
+    program_id: &Pubkey,
+    insurance_fund_info: &AccountInfo,
+    lending_market_info: &AccountInfo,
+    reserve: &Reserve,
+) -> ProgramResult {
+    let insurance_fund_key = if reserve.has_insurance_fund {
+        Pubkey::create_program_address(
+            &[
+                lending_market_info.key.as_ref(),
+                reserve.liquidity.mint_pubkey.as_ref(),
+                INSURANCE_FUND_SEED,
+                &[reserve.insurance_fund_bump_seed],
+            ],
+            program_id,
+        )?
+    } else {
+        Pubkey::find_program_address(
+            &[
+                lending_market_info.key.as_ref(),
+                reserve.liquidity.mint_pubkey.as_ref(),
+                INSURANCE_FUND_SEED,
+            ],
+            program_id,
+        )
+        .0
+    };
+    if insurance_fund_key != *insurance_fund_info.key {
+        msg!("Insurance fund provided is not the lending market's insurance fund for the reserve liquidity mint");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(())
+}
+
+/// Unpacks the pair borrow weights account of a borrow reserve
+pub(super) fn validate_reserve_pair_borrow_weights(
+    program_id: &Pubkey,
+    borrow_reserve_pubkey: &Pubkey,
+    reserve_pair_borrow_weights_info: &AccountInfo,
+) -> Result<ReservePairBorrowWeights, ProgramError> {
+    if reserve_pair_borrow_weights_info.owner != program_id {
+        msg!("Reserve pair borrow weights provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    let reserve_pair_borrow_weights =
+        ReservePairBorrowWeights::unpack(&reserve_pair_borrow_weights_info.data.borrow())?;
+    if &reserve_pair_borrow_weights.reserve != borrow_reserve_pubkey {
+        msg!("Reserve pair borrow weights provided do not belong to the borrow reserve");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(reserve_pair_borrow_weights)
+}
+
+/// Validates the pyth oracle of a reserve, which can be a devnet price account in builds with
+/// the devnet-utils feature
+pub(super) fn validate_pyth_oracle(pyth_price_info: &AccountInfo) -> ProgramResult {
+    #[cfg(feature = "devnet-utils")]
+    if is_devnet_price_account(pyth_price_info) {
+        return Ok(());
+    }
+    validate_pyth_keys(pyth_price_info)
+}
+
+pub(super) fn validate_extra_oracle(
+    extra_oracle_pubkey: Pubkey,
+    extra_oracle_info: &AccountInfo<'_>,
+) -> Result<(), ProgramError> {
+    if extra_oracle_pubkey == solend_program::NULL_PUBKEY {
+        msg!("Extra oracle cannot equal the null pubkey");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    if extra_oracle_info.key != &extra_oracle_pubkey {
+        msg!("Extra oracle provided does not match the extra oracle pubkey in the config");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    match get_oracle_type(extra_oracle_info)? {
+        OracleType::Pyth => {
+            validate_pyth_price_account_info(extra_oracle_info)?;
+        }
+        OracleType::PythPull => {
+            validate_pyth_price_account_info(extra_oracle_info)?;
+        }
+        OracleType::Switchboard => {
+            validate_switchboard_keys(extra_oracle_info)?;
+        }
+        OracleType::SbOnDemand => {
+            validate_sb_on_demand_keys(extra_oracle_info)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
+    if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
+        msg!(
+            "Rent exempt balance insufficient got {} expected {}",
+            &account_info.lamports().to_string(),
+            &rent.minimum_balance(account_info.data_len()).to_string(),
+        );
+        Err(LendingError::NotRentExempt.into())
+    } else {
+        Ok(())
+    }
+}
+
+pub(super) fn assert_uninitialized<T: Pack + IsInitialized>(
+    account_info: &AccountInfo,
+) -> Result<T, ProgramError> {
+    let account: T = T::unpack_unchecked(&account_info.data.borrow())?;
+    if account.is_initialized() {
+        Err(LendingError::AlreadyInitialized.into())
+    } else {
+        Ok(account)
+    }
+}
+
+/// Checks that a host fee receiver is owned by the referrer of the obligation
+pub(super) fn check_referrer_host_fee_receiver(
+    referrer: &Pubkey,
+    host_fee_receiver_info: &AccountInfo,
+) -> ProgramResult {
+    let host_fee_receiver = unpack_token_account(&host_fee_receiver_info.data.borrow())?;
+    if &host_fee_receiver.owner != referrer {
+        msg!(
+            "Host fee receiver must be a token account owned by the obligation referrer {}",
+            referrer
+        );
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(())
+}
+
+/// Checks that a token account is the lending market's default host fee receiver for a liquidity
+/// mint, if the lending market has one
+pub(super) fn check_default_host_fee_receiver(
+    lending_market: &LendingMarket,
+    host_fee_receiver_info: &AccountInfo,
+    token_program_id: &Pubkey,
+    liquidity_mint: &Pubkey,
+) -> ProgramResult {
+    match lending_market.default_host_fee_receiver_token_address(token_program_id, liquidity_mint) {
+        Some(default_host_fee_receiver)
+            if &default_host_fee_receiver != host_fee_receiver_info.key =>
+        {
+            msg!(
+                "Host fee receiver must be the default host fee receiver's associated token account {}",
+                default_host_fee_receiver
+            );
+            Err(LendingError::InvalidAccountInput.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Unpacks a spl_token `Mint`.
+pub(super) fn unpack_mint(data: &[u8]) -> Result<Mint, LendingError> {
+    Mint::unpack(data).map_err(|_| LendingError::InvalidTokenMint)
+}
+
+/// Unpacks a spl_token `Account`.
+pub(super) fn unpack_token_account(data: &[u8]) -> Result<Token, LendingError> {
+    Token::unpack(data).map_err(|_| LendingError::InvalidTokenAccount)
+}
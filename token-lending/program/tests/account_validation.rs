@@ -0,0 +1,248 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::User;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::system_program;
+use solana_program_test::*;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::{
+    deposit_reserve_liquidity, set_max_obligations_per_owner, set_obligation_borrow_lock,
+};
+use solend_program::state::PROGRAM_VERSION;
+
+#[tokio::test]
+async fn test_invalid_lending_market_authority() {
+    let (mut test, lending_market, usdc_reserve, _, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let mut instruction = deposit_reserve_liquidity(
+        solend_program::id(),
+        1_000_000,
+        user.get_account(&usdc_mint::id()).unwrap(),
+        user.get_account(&usdc_reserve.account.collateral.mint_pubkey)
+            .unwrap(),
+        usdc_reserve.pubkey,
+        usdc_reserve.account.liquidity.supply_pubkey,
+        usdc_reserve.account.collateral.mint_pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+    );
+    instruction.accounts[6].pubkey = Keypair::new().pubkey();
+
+    let res = test
+        .process_transaction(&[instruction], Some(&[&user.keypair]))
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_token_program() {
+    let (mut test, lending_market, usdc_reserve, _, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let mut instruction = deposit_reserve_liquidity(
+        solend_program::id(),
+        1_000_000,
+        user.get_account(&usdc_mint::id()).unwrap(),
+        user.get_account(&usdc_reserve.account.collateral.mint_pubkey)
+            .unwrap(),
+        usdc_reserve.pubkey,
+        usdc_reserve.account.liquidity.supply_pubkey,
+        usdc_reserve.account.collateral.mint_pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+    );
+    instruction.accounts[8].pubkey = system_program::id();
+
+    let res = test
+        .process_transaction(&[instruction], Some(&[&user.keypair]))
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidTokenProgram as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_obligation_accounts() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // the reserve is owned by the lending program but isn't the obligation's lending market
+    let res = test
+        .process_transaction(
+            &[set_obligation_borrow_lock(
+                solend_program::id(),
+                true,
+                obligation.pubkey,
+                wsol_reserve.pubkey,
+                user.keypair.pubkey(),
+            )],
+            Some(&[&user.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+
+    let not_owner = User::new_with_balances(&mut test, &[]).await;
+    let res = test
+        .process_transaction(
+            &[set_obligation_borrow_lock(
+                solend_program::id(),
+                true,
+                obligation.pubkey,
+                lending_market.pubkey,
+                not_owner.keypair.pubkey(),
+            )],
+            Some(&[&not_owner.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidObligationOwner as u32)
+        )
+    );
+
+    let mut instruction = set_obligation_borrow_lock(
+        solend_program::id(),
+        true,
+        obligation.pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+    );
+    instruction.accounts[2].is_signer = false;
+    let res = test
+        .process_transaction(&[instruction], None)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidSigner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_lending_market_owner() {
+    let (mut test, lending_market, _, _, user, _, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let res = test
+        .process_transaction(
+            &[set_max_obligations_per_owner(
+                solend_program::id(),
+                1,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            )],
+            Some(&[&user.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    let mut instruction = set_max_obligations_per_owner(
+        solend_program::id(),
+        1,
+        lending_market.pubkey,
+        lending_market_owner.keypair.pubkey(),
+    );
+    instruction.accounts[1].is_signer = false;
+    let res = test
+        .process_transaction(&[instruction], None)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidSigner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_newer_version_account() {
+    let (mut test, lending_market, usdc_reserve, _, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // as if a newer program binary had migrated the reserve
+    let mut reserve_account = test
+        .context
+        .banks_client
+        .get_account(usdc_reserve.pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    reserve_account.data[0] = PROGRAM_VERSION + 1;
+    test.context
+        .set_account(&usdc_reserve.pubkey, &reserve_account.into());
+
+    let instruction = deposit_reserve_liquidity(
+        solend_program::id(),
+        1_000_000,
+        user.get_account(&usdc_mint::id()).unwrap(),
+        user.get_account(&usdc_reserve.account.collateral.mint_pubkey)
+            .unwrap(),
+        usdc_reserve.pubkey,
+        usdc_reserve.account.liquidity.supply_pubkey,
+        usdc_reserve.account.collateral.mint_pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+    );
+
+    let res = test
+        .process_transaction(&[instruction], Some(&[&user.keypair]))
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::AccountVersionNotSupported as u32)
+        )
+    );
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "test-bpf")]
+
+use crate::solend_program_test::custom_scenario;
+use crate::solend_program_test::ObligationArgs;
+use crate::solend_program_test::PriceArgs;
+use crate::solend_program_test::ReserveArgs;
+use crate::solend_program_test::User;
+
+use solana_program::keccak;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_sdk::spl_account_compression;
+use std::convert::TryInto;
+
+mod helpers;
+
+use helpers::mock_account_compression::MOCK_MERKLE_TREE_LEN;
+use helpers::*;
+use solana_program_test::*;
+
+#[tokio::test]
+async fn test_archive_obligation() {
+    let (mut test, lending_market, _reserves, obligations, users, _lending_market_owner) =
+        custom_scenario(
+            &[ReserveArgs {
+                mint: usdc_mint::id(),
+                config: reserve_config_no_fees(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: -1,
+                    ema_price: 10,
+                    ema_conf: 1,
+                },
+            }],
+            &[ObligationArgs {
+                deposits: vec![],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    let merkle_tree = test
+        .create_account(MOCK_MERKLE_TREE_LEN, &spl_account_compression::id(), None)
+        .await;
+    let obligation_account = test
+        .context
+        .banks_client
+        .get_account(obligations[0].pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let owner_lamports = test
+        .context
+        .banks_client
+        .get_balance(users[0].keypair.pubkey())
+        .await
+        .unwrap();
+
+    lending_market
+        .archive_obligation(&mut test, &obligations[0], &users[0], merkle_tree)
+        .await
+        .unwrap();
+
+    // the obligation is closed and its rent goes to the owner
+    assert!(test
+        .context
+        .banks_client
+        .get_account(obligations[0].pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        test.context
+            .banks_client
+            .get_balance(users[0].keypair.pubkey())
+            .await
+            .unwrap(),
+        owner_lamports + obligation_account.lamports
+    );
+
+    // the leaf commits to the obligation address and its final state
+    let merkle_tree_data = test
+        .context
+        .banks_client
+        .get_account(merkle_tree)
+        .await
+        .unwrap()
+        .unwrap()
+        .data;
+    assert_eq!(
+        u64::from_le_bytes(merkle_tree_data[..8].try_into().unwrap()),
+        1
+    );
+    assert_eq!(
+        merkle_tree_data[8..],
+        keccak::hashv(&[obligations[0].pubkey.as_ref(), &obligation_account.data]).to_bytes()
+    );
+}
+
+#[tokio::test]
+async fn test_archive_obligation_with_deposits() {
+    let (mut test, lending_market, _reserves, obligations, users, _lending_market_owner) =
+        custom_scenario(
+            &[ReserveArgs {
+                mint: usdc_mint::id(),
+                config: reserve_config_no_fees(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: -1,
+                    ema_price: 10,
+                    ema_conf: 1,
+                },
+            }],
+            &[ObligationArgs {
+                deposits: vec![(usdc_mint::id(), 20 * FRACTIONAL_TO_USDC)],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    let err = lending_market
+        .archive_obligation(&mut test, &obligations[0], &users[0], Pubkey::new_unique())
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_archive_obligation_invalid_owner() {
+    let (mut test, lending_market, _reserves, obligations, _users, _lending_market_owner) =
+        custom_scenario(
+            &[ReserveArgs {
+                mint: usdc_mint::id(),
+                config: reserve_config_no_fees(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: -1,
+                    ema_price: 10,
+                    ema_conf: 1,
+                },
+            }],
+            &[ObligationArgs {
+                deposits: vec![],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    let rando = User::new_with_keypair(Keypair::new());
+    let err = lending_market
+        .archive_obligation(&mut test, &obligations[0], &rando, Pubkey::new_unique())
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidObligationOwner as u32)
+        )
+    );
+}
@@ -367,6 +367,7 @@ async fn test_calculations() {
                     .borrowed_amount_wads
                     .try_add(Decimal::from(10 * FRACTIONAL_TO_USDC))
                     .unwrap(),
+                price_slot: 1001,
                 ..usdc_reserve.liquidity
             },
             rate_limiter: {
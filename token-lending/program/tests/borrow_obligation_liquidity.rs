@@ -16,6 +16,7 @@ use solana_program_test::*;
 use solana_sdk::{
     instruction::InstructionError, signature::Keypair, transaction::TransactionError,
 };
+use solend_program::instruction::borrow_obligation_liquidity;
 use solend_program::state::*;
 use solend_program::{error::LendingError, math::Decimal};
 
@@ -264,7 +265,8 @@ async fn test_success() {
                     .liquidity
                     .cumulative_borrow_rate_wads,
                 market_value: Decimal::zero(), // we only update this retroactively on a
-                                               // refresh_obligation
+                // refresh_obligation
+                ..ObligationLiquidity::default()
             }],
             deposited_value: Decimal::from(100u64),
             borrowed_value: borrow_value,
@@ -279,6 +281,194 @@ async fn test_success() {
 }
 
 // FIXME this should really be a unit test
+#[tokio::test]
+async fn test_success_default_host_fee_receiver() {
+    let (
+        mut test,
+        lending_market,
+        _usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+        default_host_fee_receiver,
+        lending_market_owner,
+    ) = setup(&ReserveConfig {
+        fees: ReserveFees {
+            borrow_fee_wad: 100_000_000_000,
+            flash_loan_fee_wad: 0,
+            host_fee_percentage: 20,
+        },
+        ..test_reserve_config()
+    })
+    .await;
+
+    lending_market
+        .set_default_host_fee_receiver(
+            &mut test,
+            &lending_market_owner,
+            Some(default_host_fee_receiver.keypair.pubkey()),
+        )
+        .await
+        .unwrap();
+    let default_host_fee_receiver_token_pubkey = test
+        .create_associated_token_account(
+            &default_host_fee_receiver.keypair.pubkey(),
+            &wsol_mint::id(),
+        )
+        .await;
+
+    let balance_checker = BalanceChecker::start(&mut test, &[&wsol_reserve]).await;
+
+    // no host fee receiver is supplied, so the host fee goes to the market's default
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            4 * LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert!(balance_changes.contains(&TokenBalanceChange {
+        token_account: wsol_reserve.account.config.fee_receiver,
+        mint: wsol_mint::id(),
+        diff: 320,
+    }));
+    let default_host_fee_receiver_token = test
+        .load_account::<spl_token::state::Account>(default_host_fee_receiver_token_pubkey)
+        .await;
+    assert_eq!(default_host_fee_receiver_token.account.amount, 80);
+}
+
+#[tokio::test]
+async fn test_success_default_host_fee_receiver_omitted() {
+    let (
+        mut test,
+        lending_market,
+        _usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+        default_host_fee_receiver,
+        lending_market_owner,
+    ) = setup(&ReserveConfig {
+        fees: ReserveFees {
+            borrow_fee_wad: 100_000_000_000,
+            flash_loan_fee_wad: 0,
+            host_fee_percentage: 20,
+        },
+        ..test_reserve_config()
+    })
+    .await;
+
+    lending_market
+        .set_default_host_fee_receiver(
+            &mut test,
+            &lending_market_owner,
+            Some(default_host_fee_receiver.keypair.pubkey()),
+        )
+        .await
+        .unwrap();
+    let default_host_fee_receiver_token_pubkey = test
+        .create_associated_token_account(
+            &default_host_fee_receiver.keypair.pubkey(),
+            &wsol_mint::id(),
+        )
+        .await;
+
+    let balance_checker = BalanceChecker::start(&mut test, &[&wsol_reserve]).await;
+
+    // the default host fee receiver accounts are left out, so the whole fee goes to the reserve
+    let mut instructions = lending_market
+        .build_refresh_instructions(&mut test, &obligation, Some(&wsol_reserve))
+        .await;
+    instructions.push(borrow_obligation_liquidity(
+        solend_program::id(),
+        4 * LAMPORTS_PER_SOL,
+        wsol_reserve.account.liquidity.supply_pubkey,
+        user.get_account(&wsol_mint::id()).unwrap(),
+        wsol_reserve.pubkey,
+        wsol_reserve.account.config.fee_receiver,
+        obligation.pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+        obligation
+            .account
+            .deposits
+            .iter()
+            .map(|d| d.deposit_reserve)
+            .collect(),
+        None,
+    ));
+    test.process_transaction(&instructions, Some(&[&user.keypair]))
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert!(balance_changes.contains(&TokenBalanceChange {
+        token_account: wsol_reserve.account.config.fee_receiver,
+        mint: wsol_mint::id(),
+        diff: 400,
+    }));
+    let default_host_fee_receiver_token = test
+        .load_account::<spl_token::state::Account>(default_host_fee_receiver_token_pubkey)
+        .await;
+    assert_eq!(default_host_fee_receiver_token.account.amount, 0);
+}
+
+#[tokio::test]
+async fn test_fail_host_fee_receiver_not_default() {
+    let (
+        mut test,
+        lending_market,
+        _usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+        host_fee_receiver,
+        lending_market_owner,
+    ) = setup(&ReserveConfig {
+        fees: ReserveFees {
+            borrow_fee_wad: 100_000_000_000,
+            flash_loan_fee_wad: 0,
+            host_fee_percentage: 20,
+        },
+        ..test_reserve_config()
+    })
+    .await;
+
+    lending_market
+        .set_default_host_fee_receiver(&mut test, &lending_market_owner, Some(Pubkey::new_unique()))
+        .await
+        .unwrap();
+
+    // borrows without a referrer can only pay the host fee to the market's default
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            4 * LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
+
 #[tokio::test]
 async fn test_borrow_max() {
     let (
@@ -300,68 +490,389 @@ async fn test_borrow_max() {
     })
     .await;
 
-    let balance_checker = BalanceChecker::start(
-        &mut test,
-        &[&usdc_reserve, &user, &wsol_reserve, &host_fee_receiver],
-    )
-    .await;
+    let balance_checker = BalanceChecker::start(
+        &mut test,
+        &[&usdc_reserve, &user, &wsol_reserve, &host_fee_receiver],
+    )
+    .await;
+
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    // check token balances
+    let (balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+
+    let expected_balance_changes = HashSet::from([
+        TokenBalanceChange {
+            token_account: wsol_reserve.account.liquidity.supply_pubkey,
+            mint: wsol_mint::id(),
+            diff: -((5 * LAMPORTS_PER_SOL) as i128),
+        },
+        TokenBalanceChange {
+            token_account: user.get_account(&wsol_mint::id()).unwrap(),
+            mint: wsol_mint::id(),
+            diff: (5 * LAMPORTS_PER_SOL as i128) - 500,
+        },
+        TokenBalanceChange {
+            token_account: wsol_reserve.account.config.fee_receiver,
+            mint: wsol_mint::id(),
+            diff: 400,
+        },
+        TokenBalanceChange {
+            token_account: host_fee_receiver.get_account(&wsol_mint::id()).unwrap(),
+            mint: wsol_mint::id(),
+            diff: 100,
+        },
+    ]);
+
+    assert_eq!(
+        balance_changes, expected_balance_changes,
+        "{:#?} \n {:#?}",
+        balance_changes, expected_balance_changes
+    );
+    assert_eq!(mint_supply_changes, HashSet::new());
+}
+
+#[tokio::test]
+async fn test_fail_borrow_over_reserve_borrow_limit() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&ReserveConfig {
+            borrow_limit: LAMPORTS_PER_SOL,
+            ..test_reserve_config()
+        })
+        .await;
+
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL + 1,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAmount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_borrow_max_with_min_received() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&ReserveConfig {
+            borrow_limit: LAMPORTS_PER_SOL,
+            ..test_reserve_config()
+        })
+        .await;
+
+    // the borrow limit clamps the borrow to 1 SOL
+    let res = lending_market
+        .borrow_obligation_liquidity_with_min_received(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            u64::MAX,
+            LAMPORTS_PER_SOL + 1,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::BorrowSlippageExceeded as u32)
+        )
+    );
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .borrow_obligation_liquidity_with_min_received(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            u64::MAX,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(
+        obligation_post.account.borrows[0].borrowed_amount_wads,
+        Decimal::from(LAMPORTS_PER_SOL)
+    );
+}
+
+#[tokio::test]
+async fn test_fail_borrow_over_single_borrower_share() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&ReserveConfig {
+            // 6 SOL supplied with the reserve's initial liquidity, so one obligation may borrow at
+            // most 1.2 SOL
+            max_single_borrower_share_bps: 2_000,
+            ..test_reserve_config()
+        })
+        .await;
+
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            6 * LAMPORTS_PER_SOL / 5 + 1,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::BorrowerConcentrationLimitExceeded as u32)
+        )
+    );
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            6 * LAMPORTS_PER_SOL / 5,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_fail_borrow_below_min_borrow_value() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&ReserveConfig {
+            // SOL is $10, so a borrow has to be at least 0.5 SOL
+            min_borrow_value: 5,
+            ..test_reserve_config()
+        })
+        .await;
+
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL / 10,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::BorrowBelowMinValue as u32)
+        )
+    );
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL / 2,
+        )
+        .await
+        .unwrap();
+
+    // the minimum applies to the whole borrow, so topping it up by a little is fine
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL / 10,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_fail_borrows_paused() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&ReserveConfig {
+            borrows_paused: true,
+            ..test_reserve_config()
+        })
+        .await;
+
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ReserveOperationPaused as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fail_borrows_locked() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&test_reserve_config()).await;
+
+    lending_market
+        .set_obligation_borrow_lock(&mut test, &obligation, &user, true)
+        .await
+        .unwrap();
 
+    // an unlock only takes effect after the delay
     lending_market
+        .set_obligation_borrow_lock(&mut test, &obligation, &user, false)
+        .await
+        .unwrap();
+
+    let res = lending_market
         .borrow_obligation_liquidity(
             &mut test,
             &wsol_reserve,
             &obligation,
             &user,
             host_fee_receiver.get_account(&wsol_mint::id()),
-            u64::MAX,
+            LAMPORTS_PER_SOL,
         )
         .await
+        .err()
+        .unwrap()
         .unwrap();
 
-    // check token balances
-    let (balance_changes, mint_supply_changes) =
-        balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ObligationBorrowsLocked as u32)
+        )
+    );
 
-    let expected_balance_changes = HashSet::from([
-        TokenBalanceChange {
-            token_account: wsol_reserve.account.liquidity.supply_pubkey,
-            mint: wsol_mint::id(),
-            diff: -((5 * LAMPORTS_PER_SOL) as i128),
-        },
-        TokenBalanceChange {
-            token_account: user.get_account(&wsol_mint::id()).unwrap(),
-            mint: wsol_mint::id(),
-            diff: (5 * LAMPORTS_PER_SOL as i128) - 500,
-        },
-        TokenBalanceChange {
-            token_account: wsol_reserve.account.config.fee_receiver,
-            mint: wsol_mint::id(),
-            diff: 400,
+    test.advance_clock_by_slots(OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS)
+        .await;
+
+    // refresh the oracles after the warp
+    test.set_price(
+        &usdc_mint::id(),
+        &PriceArgs {
+            price: 1,
+            conf: 0,
+            expo: 0,
+            ema_price: 1,
+            ema_conf: 0,
         },
-        TokenBalanceChange {
-            token_account: host_fee_receiver.get_account(&wsol_mint::id()).unwrap(),
-            mint: wsol_mint::id(),
-            diff: 100,
+    )
+    .await;
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: 0,
+            ema_price: 10,
+            ema_conf: 0,
         },
-    ]);
+    )
+    .await;
 
-    assert_eq!(
-        balance_changes, expected_balance_changes,
-        "{:#?} \n {:#?}",
-        balance_changes, expected_balance_changes
-    );
-    assert_eq!(mint_supply_changes, HashSet::new());
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
 }
 
 #[tokio::test]
-async fn test_fail_borrow_over_reserve_borrow_limit() {
+async fn test_fail_borrow_cap_exceeded() {
     let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
-        setup(&ReserveConfig {
-            borrow_limit: LAMPORTS_PER_SOL,
-            ..test_reserve_config()
-        })
-        .await;
+        setup(&test_reserve_config()).await;
+
+    // $15, enough for one SOL but not two
+    lending_market
+        .set_obligation_borrow_cap(&mut test, &obligation, &user, 15)
+        .await
+        .unwrap();
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.borrow_cap, 15);
+    assert!(!obligation_post.account.borrows_locked);
+
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
 
+    test.advance_clock_by_slots(1).await;
     let res = lending_market
         .borrow_obligation_liquidity(
             &mut test,
@@ -369,7 +880,7 @@ async fn test_fail_borrow_over_reserve_borrow_limit() {
             &obligation,
             &user,
             host_fee_receiver.get_account(&wsol_mint::id()),
-            LAMPORTS_PER_SOL + 1,
+            LAMPORTS_PER_SOL,
         )
         .await
         .err()
@@ -380,7 +891,131 @@ async fn test_fail_borrow_over_reserve_borrow_limit() {
         res,
         TransactionError::InstructionError(
             1,
-            InstructionError::Custom(LendingError::InvalidAmount as u32)
+            InstructionError::Custom(LendingError::ObligationBorrowCapExceeded as u32)
+        )
+    );
+
+    // raising the cap locks borrows until the unlock delay has passed
+    lending_market
+        .set_obligation_borrow_cap(&mut test, &obligation, &user, 25)
+        .await
+        .unwrap();
+    test.advance_clock_by_slots(1).await;
+
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ObligationBorrowsLocked as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_borrow_as_approved_borrower() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, _, _) =
+        setup(&test_reserve_config()).await;
+
+    let approved_borrower = User::new_with_balances(&mut test, &[(&wsol_mint::id(), 0)]).await;
+
+    // can't borrow before the owner delegates
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &approved_borrower,
+            None,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidObligationOwner as u32)
+        )
+    );
+
+    // $15, enough for one SOL but not two
+    lending_market
+        .delegate_borrow_authority(
+            &mut test,
+            &obligation,
+            &user,
+            approved_borrower.keypair.pubkey(),
+            15,
+        )
+        .await
+        .unwrap();
+    test.advance_clock_by_slots(1).await;
+
+    let balance_checker = BalanceChecker::start(&mut test, &[&approved_borrower]).await;
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &approved_borrower,
+            None,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        balance_changes,
+        HashSet::from([TokenBalanceChange {
+            token_account: approved_borrower.get_account(&wsol_mint::id()).unwrap(),
+            mint: wsol_mint::id(),
+            diff: LAMPORTS_PER_SOL as i128,
+        }])
+    );
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.borrows.len(), 1);
+    assert_eq!(
+        obligation_post.account.approved_borrower,
+        Some(approved_borrower.keypair.pubkey())
+    );
+    assert_eq!(obligation_post.account.delegated_value, Decimal::from(5u64));
+
+    test.advance_clock_by_slots(1).await;
+
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &approved_borrower,
+            None,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::BorrowTooLarge as u32)
         )
     );
 }
@@ -625,3 +1260,86 @@ async fn test_borrow_max_rate_limiter() {
 
     assert_eq!(balance_changes, expected_balance_changes);
 }
+
+#[tokio::test]
+async fn test_borrow_with_referrer() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, host_fee_receiver, _) =
+        setup(&ReserveConfig {
+            fees: ReserveFees {
+                borrow_fee_wad: 100_000_000_000,
+                flash_loan_fee_wad: 0,
+                host_fee_percentage: 20,
+            },
+            ..test_reserve_config()
+        })
+        .await;
+
+    let referrer = User::new_with_balances(&mut test, &[(&wsol_mint::id(), 0)]).await;
+    lending_market
+        .set_obligation_referrer(&mut test, &obligation, &user, referrer.keypair.pubkey())
+        .await
+        .unwrap();
+
+    let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation.account.referrer, Some(referrer.keypair.pubkey()));
+
+    // the referrer can't be changed
+    let res = lending_market
+        .set_obligation_referrer(&mut test, &obligation, &user, user.keypair.pubkey())
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::AlreadyInitialized as u32)
+        )
+    );
+
+    // host fees can only go to the referrer
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+    test.advance_clock_by_slots(1).await;
+
+    let balance_checker = BalanceChecker::start(&mut test, &[&referrer]).await;
+
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            referrer.get_account(&wsol_mint::id()),
+            4 * LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        balance_changes,
+        HashSet::from([TokenBalanceChange {
+            token_account: referrer.get_account(&wsol_mint::id()).unwrap(),
+            mint: wsol_mint::id(),
+            diff: 80,
+        }])
+    );
+}
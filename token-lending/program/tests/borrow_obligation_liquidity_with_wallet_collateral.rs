@@ -0,0 +1,170 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use std::collections::HashSet;
+
+use helpers::solend_program_test::{
+    setup_world, BalanceChecker, Info, SolendProgramTest, TokenBalanceChange, User,
+};
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, transaction::TransactionError};
+use solend_program::error::LendingError;
+use solend_program::state::{LendingMarket, Obligation, Reserve};
+
+async fn setup() -> (
+    SolendProgramTest,
+    Info<LendingMarket>,
+    Info<Reserve>,
+    Info<Reserve>,
+    User,
+    Info<Obligation>,
+) {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    // 100 cUSDC sitting in the user's wallet
+    lending_market
+        .deposit(&mut test, &usdc_reserve, &user, 100_000_000)
+        .await
+        .expect("This should succeed");
+
+    let obligation = lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .expect("This should succeed");
+
+    let usdc_reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+
+    (
+        test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+    )
+}
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation) = setup().await;
+
+    let balance_checker =
+        BalanceChecker::start(&mut test, &[&usdc_reserve, &wsol_reserve, &user]).await;
+
+    lending_market
+        .borrow_obligation_liquidity_with_wallet_collateral(
+            &mut test,
+            &usdc_reserve,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            LAMPORTS_TO_SOL / 10,
+        )
+        .await
+        .expect("this should succeed");
+
+    let (token_balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        token_balance_changes,
+        HashSet::from([
+            TokenBalanceChange {
+                token_account: user
+                    .get_account(&usdc_reserve.account.collateral.mint_pubkey)
+                    .unwrap(),
+                mint: usdc_reserve.account.collateral.mint_pubkey,
+                diff: -100_000_000,
+            },
+            TokenBalanceChange {
+                token_account: usdc_reserve.account.collateral.supply_pubkey,
+                mint: usdc_reserve.account.collateral.mint_pubkey,
+                diff: 100_000_000,
+            },
+            TokenBalanceChange {
+                token_account: wsol_reserve.account.liquidity.supply_pubkey,
+                mint: wsol_mint::id(),
+                diff: -((LAMPORTS_TO_SOL / 10) as i128),
+            },
+            TokenBalanceChange {
+                token_account: user.get_account(&wsol_mint::id()).unwrap(),
+                mint: wsol_mint::id(),
+                diff: (LAMPORTS_TO_SOL / 10) as i128,
+            },
+        ]),
+        "{:#?}",
+        token_balance_changes
+    );
+    assert!(mint_supply_changes.is_empty());
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.deposits.len(), 1);
+    assert_eq!(
+        obligation_post.account.deposits[0].deposit_reserve,
+        usdc_reserve.pubkey
+    );
+    assert_eq!(
+        obligation_post.account.deposits[0].deposited_amount,
+        100_000_000
+    );
+    assert_eq!(obligation_post.account.borrows.len(), 1);
+    assert_eq!(
+        obligation_post.account.borrows[0].borrow_reserve,
+        wsol_reserve.pubkey
+    );
+
+    // the wallet is empty now, so a second borrow only uses the deposited collateral
+    lending_market
+        .borrow_obligation_liquidity_with_wallet_collateral(
+            &mut test,
+            &usdc_reserve,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            LAMPORTS_TO_SOL / 10,
+        )
+        .await
+        .expect("this should succeed");
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(
+        obligation_post.account.deposits[0].deposited_amount,
+        100_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_fail_borrow_too_much() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation) = setup().await;
+
+    // 100 USDC at 50% LTV can't back 10 SOL
+    let res = lending_market
+        .borrow_obligation_liquidity_with_wallet_collateral(
+            &mut test,
+            &usdc_reserve,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            10 * LAMPORTS_TO_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::BorrowTooLarge as u32)
+        )
+    );
+
+    // the deposit is rolled back with the borrow
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert!(obligation_post.account.deposits.is_empty());
+}
@@ -19,6 +19,7 @@ use solana_program_test::*;
 use solana_sdk::signature::Keypair;
 use solend_program::math::Decimal;
 use solend_program::state::Obligation;
+use solend_program::state::Reserve;
 use std::collections::HashSet;
 
 #[tokio::test]
@@ -50,6 +51,72 @@ async fn test_refresh_obligation() {
     );
 }
 
+#[tokio::test]
+async fn test_refresh_obligation_with_pair_borrow_weights() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _, obligation, lending_market_owner) =
+        scenario_1(
+            &test_reserve_config(),
+            &ReserveConfig {
+                added_borrow_weight_bps: 10_000,
+                ..test_reserve_config()
+            },
+        )
+        .await;
+
+    // halve the sol borrow weight against usdc collateral
+    lending_market
+        .set_reserve_pair_borrow_weight(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &lending_market_owner,
+            5_000,
+        )
+        .await
+        .unwrap();
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(wsol_reserve.account.has_pair_borrow_weights);
+
+    // refreshing without the pair borrow weights account fails
+    {
+        test.advance_clock_by_slots(1).await;
+        let mut instructions = lending_market
+            .build_refresh_instructions(&mut test, &obligation, None)
+            .await;
+        instructions.last_mut().unwrap().accounts.pop();
+
+        let res = test
+            .process_transaction(&instructions, None)
+            .await
+            .err()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            res,
+            TransactionError::InstructionError(
+                instructions.len() as u8 - 1,
+                InstructionError::NotEnoughAccountKeys
+            )
+        );
+    }
+
+    lending_market
+        .refresh_obligation(&mut test, &obligation)
+        .await
+        .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+
+    // all of the collateral is usdc, so the sol borrow weight of 2 is halved and the borrow,
+    // 10 sol at $10 plus the interest since the slot advanced, counts at its market value
+    assert_eq!(
+        obligation_post.account.borrowed_value,
+        obligation_post.account.borrows[0].market_value
+    );
+    assert!(obligation_post.account.borrowed_value > Decimal::from(100u64));
+}
+
 #[tokio::test]
 async fn test_borrow() {
     let (mut test, lending_market, usdc_reserve, wsol_reserve, _, _) = setup_world(
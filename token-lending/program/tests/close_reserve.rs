@@ -0,0 +1,252 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use std::str::FromStr;
+
+use helpers::solend_program_test::{setup_world, Info, SolendProgramTest, User};
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::program_pack::Pack;
+use solana_program_test::*;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use solend_program::error::LendingError;
+use solend_program::instruction::{init_reserve, redeem_reserve_collateral, refresh_reserve};
+use solend_program::state::{
+    find_reserve_rate_stats_address, find_reserve_spread_fee_receiver_address, LendingMarket,
+    Reserve, ReserveConfig,
+};
+use spl_token::state::{Account as Token, Mint};
+
+async fn setup() -> (
+    SolendProgramTest,
+    Info<LendingMarket>,
+    Info<Reserve>,
+    User,
+    User,
+) {
+    let (test, lending_market, usdc_reserve, _, lending_market_owner, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    (
+        test,
+        lending_market,
+        usdc_reserve,
+        lending_market_owner,
+        user,
+    )
+}
+
+/// Inits a wSOL reserve and redeems the collateral of its initial deposit, leaving it empty
+async fn init_empty_reserve(
+    test: &mut SolendProgramTest,
+    lending_market: &Info<LendingMarket>,
+    lending_market_owner: &User,
+) -> Info<Reserve> {
+    let reserve_keypair = Keypair::new();
+    let destination_collateral_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_liquidity_supply_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_pubkey = test
+        .create_account(Reserve::LEN, &solend_program::id(), Some(&reserve_keypair))
+        .await;
+    let reserve_liquidity_fee_receiver = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_collateral_mint_pubkey =
+        test.create_account(Mint::LEN, &spl_token::id(), None).await;
+    let reserve_collateral_supply_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+
+    let oracle = test.mints.get(&wsol_mint::id()).unwrap().unwrap();
+    test.process_transaction(
+        &[init_reserve(
+            solend_program::id(),
+            1000,
+            ReserveConfig {
+                fee_receiver: reserve_liquidity_fee_receiver,
+                ..test_reserve_config()
+            },
+            lending_market_owner.get_account(&wsol_mint::id()).unwrap(),
+            destination_collateral_pubkey,
+            reserve_pubkey,
+            wsol_mint::id(),
+            reserve_liquidity_supply_pubkey,
+            reserve_collateral_mint_pubkey,
+            reserve_collateral_supply_pubkey,
+            oracle.pyth_product_pubkey,
+            oracle.pyth_price_pubkey,
+            Pubkey::from_str("nu11111111111111111111111111111111111111111").unwrap(),
+            lending_market.pubkey,
+            lending_market_owner.keypair.pubkey(),
+            lending_market_owner.keypair.pubkey(),
+        )],
+        Some(&[&lending_market_owner.keypair]),
+    )
+    .await
+    .unwrap();
+
+    let reserve = test.load_account::<Reserve>(reserve_pubkey).await;
+    test.process_transaction(
+        &[
+            refresh_reserve(
+                solend_program::id(),
+                reserve.pubkey,
+                reserve.account.liquidity.pyth_oracle_pubkey,
+                reserve.account.liquidity.switchboard_oracle_pubkey,
+                reserve.account.config.extra_oracle_pubkey,
+            ),
+            redeem_reserve_collateral(
+                solend_program::id(),
+                1000,
+                destination_collateral_pubkey,
+                lending_market_owner.get_account(&wsol_mint::id()).unwrap(),
+                reserve.pubkey,
+                reserve.account.collateral.mint_pubkey,
+                reserve.account.liquidity.supply_pubkey,
+                lending_market.pubkey,
+                lending_market_owner.keypair.pubkey(),
+            ),
+        ],
+        Some(&[&lending_market_owner.keypair]),
+    )
+    .await
+    .unwrap();
+
+    test.load_account::<Reserve>(reserve_pubkey).await
+}
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, _, lending_market_owner, _) = setup().await;
+    let reserve = init_empty_reserve(&mut test, &lending_market, &lending_market_owner).await;
+
+    // accounts derived from the reserve address are closed with it
+    lending_market
+        .set_reserve_spread_fee_receiver(
+            &mut test,
+            &reserve,
+            &lending_market_owner,
+            lending_market_owner.get_account(&wsol_mint::id()).unwrap(),
+        )
+        .await
+        .unwrap();
+    lending_market
+        .init_reserve_rate_stats(&mut test, &reserve, &lending_market_owner)
+        .await
+        .unwrap();
+    let derived_pubkeys = [
+        find_reserve_spread_fee_receiver_address(&solend_program::id(), &reserve.pubkey).0,
+        find_reserve_rate_stats_address(&solend_program::id(), &reserve.pubkey).0,
+    ];
+
+    let owner_lamports = test
+        .context
+        .banks_client
+        .get_balance(lending_market_owner.keypair.pubkey())
+        .await
+        .unwrap();
+    let mut rent = 0;
+    for pubkey in [
+        reserve.pubkey,
+        reserve.account.liquidity.supply_pubkey,
+        reserve.account.collateral.supply_pubkey,
+        reserve.account.config.fee_receiver,
+        derived_pubkeys[0],
+        derived_pubkeys[1],
+    ] {
+        rent += test.context.banks_client.get_balance(pubkey).await.unwrap();
+    }
+
+    lending_market
+        .close_reserve(
+            &mut test,
+            &reserve,
+            &lending_market_owner,
+            lending_market_owner.get_account(&wsol_mint::id()).unwrap(),
+        )
+        .await
+        .unwrap();
+
+    for pubkey in [
+        reserve.pubkey,
+        reserve.account.liquidity.supply_pubkey,
+        reserve.account.collateral.supply_pubkey,
+        reserve.account.config.fee_receiver,
+        derived_pubkeys[0],
+        derived_pubkeys[1],
+    ] {
+        assert!(test
+            .context
+            .banks_client
+            .get_account(pubkey)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    // the rent of every closed account goes to the owner
+    let owner_lamports_post = test
+        .context
+        .banks_client
+        .get_balance(lending_market_owner.keypair.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(owner_lamports_post, owner_lamports + rent);
+}
+
+#[tokio::test]
+async fn test_fail_reserve_not_empty() {
+    let (mut test, lending_market, usdc_reserve, lending_market_owner, _) = setup().await;
+
+    // the initial deposit's collateral is still in circulation
+    let res = lending_market
+        .close_reserve(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            lending_market_owner.get_account(&usdc_mint::id()).unwrap(),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ReserveNotEmpty as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fail_invalid_owner() {
+    let (mut test, lending_market, _, lending_market_owner, user) = setup().await;
+    let reserve = init_empty_reserve(&mut test, &lending_market, &lending_market_owner).await;
+
+    let res = lending_market
+        .close_reserve(
+            &mut test,
+            &reserve,
+            &user,
+            user.get_account(&wsol_mint::id()).unwrap(),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use std::collections::HashSet;
+
+use helpers::solend_program_test::{
+    setup_world, BalanceChecker, Info, SolendProgramTest, TokenBalanceChange, User,
+};
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, transaction::TransactionError};
+use solend_program::error::LendingError;
+use solend_program::state::{LendingMarket, Obligation, Reserve};
+
+async fn setup() -> (
+    SolendProgramTest,
+    Info<LendingMarket>,
+    Info<Reserve>,
+    User,
+    Info<Obligation>,
+) {
+    let (mut test, lending_market, usdc_reserve, _, _, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    let obligation = lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .expect("This should succeed");
+
+    lending_market
+        .deposit(&mut test, &usdc_reserve, &user, 1_000_000)
+        .await
+        .expect("This should succeed");
+
+    let usdc_reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    (test, lending_market, usdc_reserve, user, obligation)
+}
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, usdc_reserve, user, obligation) = setup().await;
+
+    test.advance_clock_by_slots(1).await;
+
+    let balance_checker = BalanceChecker::start(&mut test, &[&usdc_reserve, &user]).await;
+
+    lending_market
+        .compound_obligation_collateral(&mut test, &usdc_reserve, &obligation, &user, u64::MAX)
+        .await
+        .expect("this should succeed");
+
+    // the liquidity is redeemed and deposited right back, only the collateral moves
+    let (token_balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        token_balance_changes,
+        HashSet::from([
+            TokenBalanceChange {
+                token_account: user
+                    .get_account(&usdc_reserve.account.collateral.mint_pubkey)
+                    .unwrap(),
+                mint: usdc_reserve.account.collateral.mint_pubkey,
+                diff: -1_000_000,
+            },
+            TokenBalanceChange {
+                token_account: usdc_reserve.account.collateral.supply_pubkey,
+                mint: usdc_reserve.account.collateral.mint_pubkey,
+                diff: 1_000_000,
+            },
+        ]),
+        "{:#?}",
+        token_balance_changes
+    );
+    assert!(mint_supply_changes.is_empty(), "{:#?}", mint_supply_changes);
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.deposits.len(), 1);
+    assert_eq!(
+        obligation_post.account.deposits[0].deposit_reserve,
+        usdc_reserve.pubkey
+    );
+    assert_eq!(
+        obligation_post.account.deposits[0].deposited_amount,
+        1_000_000
+    );
+
+    // nothing left to compound
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .compound_obligation_collateral(&mut test, &usdc_reserve, &obligation, &user, u64::MAX)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAmount as u32)
+        )
+    );
+}
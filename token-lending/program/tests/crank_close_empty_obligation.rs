@@ -0,0 +1,155 @@
+#![cfg(feature = "test-bpf")]
+
+use crate::solend_program_test::custom_scenario;
+use crate::solend_program_test::ObligationArgs;
+use crate::solend_program_test::PriceArgs;
+use crate::solend_program_test::ReserveArgs;
+use crate::solend_program_test::User;
+
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::{
+    EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS, EMPTY_OBLIGATION_CRANK_BOUNTY_PERCENT,
+};
+
+mod helpers;
+
+use helpers::*;
+use solana_program_test::*;
+
+fn usdc_reserve_args() -> ReserveArgs {
+    ReserveArgs {
+        mint: usdc_mint::id(),
+        config: reserve_config_no_fees(),
+        liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+        price: PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: -1,
+            ema_price: 10,
+            ema_conf: 1,
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_crank_close_empty_obligation() {
+    let (mut test, lending_market, _reserves, obligations, users, _lending_market_owner) =
+        custom_scenario(
+            &[usdc_reserve_args()],
+            &[ObligationArgs {
+                deposits: vec![],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    test.advance_clock_by_slots(EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS + 1)
+        .await;
+
+    let cranker = User::new_with_keypair(Keypair::new());
+    let obligation_lamports = test
+        .context
+        .banks_client
+        .get_balance(obligations[0].pubkey)
+        .await
+        .unwrap();
+    let owner_lamports = test
+        .context
+        .banks_client
+        .get_balance(users[0].keypair.pubkey())
+        .await
+        .unwrap();
+
+    lending_market
+        .crank_close_empty_obligation(&mut test, &obligations[0], &cranker)
+        .await
+        .unwrap();
+
+    let bounty = obligation_lamports * EMPTY_OBLIGATION_CRANK_BOUNTY_PERCENT as u64 / 100;
+    assert_eq!(
+        test.context
+            .banks_client
+            .get_balance(cranker.keypair.pubkey())
+            .await
+            .unwrap(),
+        bounty
+    );
+    assert_eq!(
+        test.context
+            .banks_client
+            .get_balance(users[0].keypair.pubkey())
+            .await
+            .unwrap(),
+        owner_lamports + obligation_lamports - bounty
+    );
+    assert!(test
+        .context
+        .banks_client
+        .get_account(obligations[0].pubkey)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_crank_close_empty_obligation_too_early() {
+    let (mut test, lending_market, _reserves, obligations, _users, _lending_market_owner) =
+        custom_scenario(
+            &[usdc_reserve_args()],
+            &[ObligationArgs {
+                deposits: vec![],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    let cranker = User::new_with_keypair(Keypair::new());
+    let err = lending_market
+        .crank_close_empty_obligation(&mut test, &obligations[0], &cranker)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_crank_close_obligation_with_deposits() {
+    let (mut test, lending_market, _reserves, obligations, _users, _lending_market_owner) =
+        custom_scenario(
+            &[usdc_reserve_args()],
+            &[ObligationArgs {
+                deposits: vec![(usdc_mint::id(), 20 * FRACTIONAL_TO_USDC)],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    test.advance_clock_by_slots(EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS + 1)
+        .await;
+
+    let cranker = User::new_with_keypair(Keypair::new());
+    let err = lending_market
+        .crank_close_empty_obligation(&mut test, &obligations[0], &cranker)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
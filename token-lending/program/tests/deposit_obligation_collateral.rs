@@ -14,7 +14,9 @@ use solana_program_test::*;
 use solana_sdk::signature::Keypair;
 use solana_sdk::transaction::TransactionError;
 use solend_program::math::Decimal;
-use solend_program::state::{LastUpdate, LendingMarket, Obligation, ObligationCollateral, Reserve};
+use solend_program::state::{
+    LastUpdate, LendingMarket, Obligation, ObligationCollateral, PositionRewards, Reserve,
+};
 
 async fn setup() -> (
     SolendProgramTest,
@@ -92,7 +94,8 @@ async fn test_success() {
                 deposit_reserve: usdc_reserve.pubkey,
                 deposited_amount: 1_000_000,
                 market_value: Decimal::zero(), // this field only gets updated on a refresh
-                attributed_borrow_value: Decimal::zero()
+                attributed_borrow_value: Decimal::zero(),
+                rewards: PositionRewards::default(),
             }],
             ..obligation.account
         }
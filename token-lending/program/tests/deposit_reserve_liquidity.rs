@@ -2,7 +2,8 @@
 
 mod helpers;
 
-use crate::solend_program_test::MintSupplyChange;
+use crate::solend_program_test::{MintSupplyChange, PriceArgs};
+use solend_program::instruction::deposit_reserve_liquidity;
 use solend_program::state::ReserveConfig;
 use std::collections::HashSet;
 
@@ -11,12 +12,17 @@ use helpers::solend_program_test::{
 };
 use helpers::*;
 use solana_program::instruction::InstructionError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::system_instruction;
 use solana_program_test::*;
+use solana_sdk::signature::{Keypair, Signer};
 use solana_sdk::transaction::TransactionError;
 use solend_program::error::LendingError;
 use solend_program::state::{
     LastUpdate, LendingMarket, Reserve, ReserveCollateral, ReserveLiquidity,
 };
+use spl_token::native_mint;
+use spl_token::state::Account as Token;
 
 async fn setup() -> (SolendProgramTest, Info<LendingMarket>, Info<Reserve>, User) {
     let (test, lending_market, usdc_reserve, _, _, user) = setup_world(
@@ -126,7 +132,28 @@ async fn test_fail_exceed_deposit_limit() {
         res,
         TransactionError::InstructionError(
             1,
-            InstructionError::Custom(LendingError::InvalidAmount as u32)
+            InstructionError::Custom(LendingError::DepositLimitExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fail_below_min_collateral_out() {
+    let (mut test, lending_market, usdc_reserve, user) = setup().await;
+
+    // the exchange rate is 1, so 1_000_000 collateral tokens would be minted
+    let res = lending_market
+        .deposit_with_min_out(&mut test, &usdc_reserve, &user, 1_000_000, 1_000_001)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ExchangeRateSlippageExceeded as u32)
         )
     );
 }
@@ -163,3 +190,103 @@ async fn test_fail_deposit_too_much() {
         e => panic!("unexpected error: {:#?}", e),
     };
 }
+
+#[tokio::test]
+async fn test_deposit_wsol_without_sync_native() {
+    let (mut test, lending_market, _, _, mut lending_market_owner, _) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    // a reserve for the real native mint, the wsol reserve of the test world uses a fake mint
+    test.init_pyth_feed(&native_mint::id()).await;
+    test.set_price(
+        &native_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    let owner_wsol_pubkey = test
+        .create_native_token_account(&lending_market_owner.keypair.pubkey(), LAMPORTS_PER_SOL)
+        .await;
+    lending_market_owner
+        .token_accounts
+        .push(test.load_account::<Token>(owner_wsol_pubkey).await);
+    let native_reserve = test
+        .init_reserve(
+            &lending_market,
+            &lending_market_owner,
+            &native_mint::id(),
+            &test_reserve_config(),
+            &Keypair::new(),
+            LAMPORTS_PER_SOL,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let mut user = User {
+        keypair: Keypair::new(),
+        token_accounts: Vec::new(),
+    };
+    let user_wsol_pubkey = test
+        .create_native_token_account(&user.keypair.pubkey(), 0)
+        .await;
+    user.token_accounts
+        .push(test.load_account::<Token>(user_wsol_pubkey).await);
+    let user_collateral = user
+        .create_token_account(&native_reserve.account.collateral.mint_pubkey, &mut test)
+        .await;
+
+    // the wallet sends the lamports but drops the SyncNative instruction
+    let payer_pubkey = test.context.payer.pubkey();
+    test.process_transaction(
+        &[system_instruction::transfer(
+            &payer_pubkey,
+            &user_wsol_pubkey,
+            LAMPORTS_PER_SOL,
+        )],
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        user.get_balance(&mut test, &native_mint::id()).await,
+        Some(0)
+    );
+
+    test.process_transaction(
+        &[deposit_reserve_liquidity(
+            solend_program::id(),
+            LAMPORTS_PER_SOL,
+            user_wsol_pubkey,
+            user_collateral.pubkey,
+            native_reserve.pubkey,
+            native_reserve.account.liquidity.supply_pubkey,
+            native_reserve.account.collateral.mint_pubkey,
+            lending_market.pubkey,
+            user.keypair.pubkey(),
+        )],
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        user.get_balance(&mut test, &native_mint::id()).await,
+        Some(0)
+    );
+    assert_eq!(
+        user.get_balance(&mut test, &native_reserve.account.collateral.mint_pubkey)
+            .await,
+        Some(LAMPORTS_PER_SOL)
+    );
+    let native_reserve_post = test.load_account::<Reserve>(native_reserve.pubkey).await;
+    assert_eq!(
+        native_reserve_post.account.liquidity.available_amount,
+        2 * LAMPORTS_PER_SOL
+    );
+}
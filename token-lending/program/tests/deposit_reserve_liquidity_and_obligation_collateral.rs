@@ -14,8 +14,8 @@ use solana_sdk::signature::Keypair;
 
 use solend_program::math::Decimal;
 use solend_program::state::{
-    LastUpdate, LendingMarket, Obligation, ObligationCollateral, Reserve, ReserveCollateral,
-    ReserveLiquidity,
+    LastUpdate, LendingMarket, Obligation, ObligationCollateral, PositionRewards, Reserve,
+    ReserveCollateral, ReserveLiquidity,
 };
 
 async fn setup() -> (
@@ -131,7 +131,8 @@ async fn test_success() {
                 deposit_reserve: usdc_reserve.pubkey,
                 deposited_amount: 1_000_000,
                 market_value: Decimal::zero(),
-                attributed_borrow_value: Decimal::zero()
+                attributed_borrow_value: Decimal::zero(),
+                rewards: PositionRewards::default(),
             }]
             .to_vec(),
             ..obligation.account
@@ -0,0 +1,164 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use std::collections::HashSet;
+
+use helpers::solend_program_test::{
+    setup_world, BalanceChecker, Info, MintSupplyChange, SolendProgramTest, TokenBalanceChange,
+    User,
+};
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Keypair, transaction::TransactionError};
+use solend_program::error::LendingError;
+use solend_program::math::Decimal;
+use solend_program::state::{LendingMarket, Obligation, Reserve};
+
+async fn setup() -> (
+    SolendProgramTest,
+    Info<LendingMarket>,
+    Info<Reserve>,
+    Info<Reserve>,
+    User,
+    Info<Obligation>,
+) {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    let obligation = lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .expect("This should succeed");
+
+    (
+        test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+    )
+}
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation) = setup().await;
+
+    let balance_checker =
+        BalanceChecker::start(&mut test, &[&usdc_reserve, &wsol_reserve, &user]).await;
+
+    // deposit 100 USDC and borrow 0.1 SOL against it
+    lending_market
+        .deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+            &mut test,
+            &usdc_reserve,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            100_000_000,
+            LAMPORTS_TO_SOL / 10,
+        )
+        .await
+        .expect("this should succeed");
+
+    let (token_balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        token_balance_changes,
+        HashSet::from([
+            TokenBalanceChange {
+                token_account: user.get_account(&usdc_mint::id()).unwrap(),
+                mint: usdc_mint::id(),
+                diff: -100_000_000,
+            },
+            TokenBalanceChange {
+                token_account: usdc_reserve.account.liquidity.supply_pubkey,
+                mint: usdc_mint::id(),
+                diff: 100_000_000,
+            },
+            TokenBalanceChange {
+                token_account: usdc_reserve.account.collateral.supply_pubkey,
+                mint: usdc_reserve.account.collateral.mint_pubkey,
+                diff: 100_000_000,
+            },
+            TokenBalanceChange {
+                token_account: wsol_reserve.account.liquidity.supply_pubkey,
+                mint: wsol_mint::id(),
+                diff: -((LAMPORTS_TO_SOL / 10) as i128),
+            },
+            TokenBalanceChange {
+                token_account: user.get_account(&wsol_mint::id()).unwrap(),
+                mint: wsol_mint::id(),
+                diff: (LAMPORTS_TO_SOL / 10) as i128,
+            },
+        ]),
+        "{:#?}",
+        token_balance_changes
+    );
+    assert_eq!(
+        mint_supply_changes,
+        HashSet::from([MintSupplyChange {
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: 100_000_000,
+        }]),
+        "{:#?}",
+        mint_supply_changes
+    );
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.deposits.len(), 1);
+    assert_eq!(
+        obligation_post.account.deposits[0].deposit_reserve,
+        usdc_reserve.pubkey
+    );
+    assert_eq!(
+        obligation_post.account.deposits[0].deposited_amount,
+        100_000_000
+    );
+    assert_eq!(obligation_post.account.borrows.len(), 1);
+    assert_eq!(
+        obligation_post.account.borrows[0].borrow_reserve,
+        wsol_reserve.pubkey
+    );
+    assert_eq!(
+        obligation_post.account.borrows[0].borrowed_amount_wads,
+        Decimal::from(LAMPORTS_TO_SOL / 10)
+    );
+
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert!(usdc_reserve_post.account.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_fail_borrow_too_large() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation) = setup().await;
+
+    // 1 USDC can't back 1 SOL, and the deposit is rolled back with the borrow
+    let res = lending_market
+        .deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+            &mut test,
+            &usdc_reserve,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            1_000_000,
+            LAMPORTS_TO_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::BorrowTooLarge as u32)
+        )
+    );
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert!(obligation_post.account.deposits.is_empty());
+}
@@ -0,0 +1,68 @@
+#![cfg(all(feature = "test-bpf", feature = "devnet-utils"))]
+
+mod helpers;
+
+use helpers::solend_program_test::{SolendProgramTest, User};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+use solend_program::instruction::{devnet_mint_test_tokens, devnet_set_price};
+use solend_program::math::Decimal;
+use solend_program::state::{
+    find_devnet_faucet_authority_address, find_devnet_price_address, DevnetPrice,
+};
+use spl_token::state::Account as Token;
+
+#[tokio::test]
+async fn test_devnet_mint_test_tokens() {
+    let mut test = SolendProgramTest::start_new().await;
+    let (devnet_faucet_authority, _) = find_devnet_faucet_authority_address(&solend_program::id());
+    let mint = test.create_mint(&devnet_faucet_authority).await;
+    let user = User::new_with_balances(&mut test, &[]).await;
+    let token_account = test
+        .create_token_account(&user.keypair.pubkey(), &mint)
+        .await;
+
+    test.process_transaction(
+        &[devnet_mint_test_tokens(
+            solend_program::id(),
+            1_000,
+            mint,
+            token_account,
+        )],
+        None,
+    )
+    .await
+    .unwrap();
+
+    let token_account = test.load_account::<Token>(token_account).await;
+    assert_eq!(token_account.account.amount, 1_000);
+}
+
+#[tokio::test]
+async fn test_devnet_set_price() {
+    let mut test = SolendProgramTest::start_new().await;
+    let mint = test.create_mint(&Pubkey::new_unique()).await;
+    let user = User::new_with_balances(&mut test, &[]).await;
+
+    // the first price creates the account, later ones overwrite it
+    for price in [Decimal::from(5u64), Decimal::from_percent(50)] {
+        test.process_transaction(
+            &[devnet_set_price(
+                solend_program::id(),
+                price,
+                mint,
+                user.keypair.pubkey(),
+            )],
+            Some(&[&user.keypair]),
+        )
+        .await
+        .unwrap();
+        test.advance_clock_by_slots(1).await;
+
+        let (devnet_price_pubkey, _) = find_devnet_price_address(&solend_program::id(), &mint);
+        let devnet_price = test.load_account::<DevnetPrice>(devnet_price_pubkey).await;
+        assert_eq!(devnet_price.account.mint, mint);
+        assert_eq!(devnet_price.account.price, price);
+    }
+}
@@ -12,7 +12,10 @@ use crate::solend_program_test::TokenBalanceChange;
 mod helpers;
 
 use helpers::*;
+use solana_program::instruction::InstructionError;
 use solana_program_test::*;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
 use solend_sdk::state::Reserve;
 
 use std::collections::HashSet;
@@ -77,3 +80,43 @@ async fn test_donate_to_reserve() {
 
     assert_eq!(balance_changes, expected_balance_changes);
 }
+
+#[tokio::test]
+async fn test_donate_zero_amount() {
+    let (mut test, lending_market, reserves, _obligations, _users, _) = custom_scenario(
+        &[ReserveArgs {
+            mint: usdc_mint::id(),
+            config: test_reserve_config(),
+            liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+            price: PriceArgs {
+                price: 10,
+                conf: 0,
+                expo: -1,
+                ema_price: 10,
+                ema_conf: 1,
+            },
+        }],
+        &[],
+    )
+    .await;
+
+    let whale = User::new_with_balances(
+        &mut test,
+        &[(&usdc_mint::id(), 100_000 * FRACTIONAL_TO_USDC)],
+    )
+    .await;
+
+    let res = lending_market
+        .donate_to_reserve(&mut test, &reserves[0], &whale, 0)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAmount as u32)
+        )
+    );
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use helpers::solend_program_test::{scenario_1, PriceArgs};
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, signature::Signer, transaction::TransactionError,
+};
+use solend_program::error::LendingError;
+use solend_program::instruction::{
+    borrow_obligation_liquidity, flash_borrow_reserve_liquidity, flash_repay_reserve_liquidity,
+};
+use solend_program::state::{Obligation, Reserve};
+
+// well past the ~150 slots a recent blockhash stays valid for
+const SLOTS_BEFORE_SUBMISSION: u64 = 300;
+
+#[tokio::test]
+async fn test_borrow_with_refreshes() {
+    let (mut test, lending_market, usdc_reserve, _, user, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+    let nonce_pubkey = test.create_nonce_account().await;
+    test.advance_clock_by_slots(1).await;
+
+    // refreshes signed together with the borrow run in the slot the transaction lands in
+    let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_000_000)];
+    instructions.extend(
+        lending_market
+            .build_refresh_instructions(&mut test, &obligation, Some(&usdc_reserve))
+            .await,
+    );
+    instructions.push(borrow_obligation_liquidity(
+        solend_program::id(),
+        10 * FRACTIONAL_TO_USDC,
+        usdc_reserve.account.liquidity.supply_pubkey,
+        user.get_account(&usdc_mint::id()).unwrap(),
+        usdc_reserve.pubkey,
+        usdc_reserve.account.config.fee_receiver,
+        obligation.pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+        obligation
+            .account
+            .deposits
+            .iter()
+            .map(|d| d.deposit_reserve)
+            .collect(),
+        None,
+    ));
+    let transaction = test
+        .sign_with_nonce(&instructions, Some(&[&user.keypair]), nonce_pubkey)
+        .await;
+
+    test.advance_clock_by_slots(SLOTS_BEFORE_SUBMISSION).await;
+    // the oracles keep publishing while the transaction waits
+    test.set_price(
+        &usdc_mint::id(),
+        &PriceArgs {
+            price: 1,
+            conf: 0,
+            expo: 0,
+            ema_price: 1,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    test.process_nonce_transaction(transaction).await.unwrap();
+
+    let slot = test.get_clock().await.slot;
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert_eq!(usdc_reserve_post.account.last_update.slot, slot);
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(
+        obligation_post
+            .account
+            .find_liquidity_in_borrows(usdc_reserve.pubkey)
+            .unwrap()
+            .0
+            .borrowed_amount_wads
+            .try_floor_u64()
+            .unwrap(),
+        10 * FRACTIONAL_TO_USDC
+    );
+}
+
+#[tokio::test]
+async fn test_fail_borrow_refreshed_before_signing() {
+    let (mut test, lending_market, usdc_reserve, _, user, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+    let nonce_pubkey = test.create_nonce_account().await;
+    test.advance_clock_by_slots(1).await;
+
+    // refreshing in an earlier transaction doesn't carry over to the slot the borrow lands in
+    let refresh_instructions = lending_market
+        .build_refresh_instructions(&mut test, &obligation, Some(&usdc_reserve))
+        .await;
+    test.process_transaction(&refresh_instructions, None)
+        .await
+        .unwrap();
+
+    let transaction = test
+        .sign_with_nonce(
+            &[borrow_obligation_liquidity(
+                solend_program::id(),
+                10 * FRACTIONAL_TO_USDC,
+                usdc_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                usdc_reserve.account.config.fee_receiver,
+                obligation.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+                obligation
+                    .account
+                    .deposits
+                    .iter()
+                    .map(|d| d.deposit_reserve)
+                    .collect(),
+                None,
+            )],
+            Some(&[&user.keypair]),
+            nonce_pubkey,
+        )
+        .await;
+
+    test.advance_clock_by_slots(SLOTS_BEFORE_SUBMISSION).await;
+    let res = test
+        .process_nonce_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ReserveStale as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_flash_loan() {
+    let (mut test, lending_market, usdc_reserve, _, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+    let nonce_pubkey = test.create_nonce_account().await;
+    test.advance_clock_by_slots(1).await;
+
+    // the nonce advance is instruction 0, so the flash borrow is instruction 1
+    let transaction = test
+        .sign_with_nonce(
+            &[
+                flash_borrow_reserve_liquidity(
+                    solend_program::id(),
+                    FRACTIONAL_TO_USDC,
+                    usdc_reserve.account.liquidity.supply_pubkey,
+                    user.get_account(&usdc_mint::id()).unwrap(),
+                    usdc_reserve.pubkey,
+                    lending_market.pubkey,
+                ),
+                flash_repay_reserve_liquidity(
+                    solend_program::id(),
+                    FRACTIONAL_TO_USDC,
+                    1,
+                    user.get_account(&usdc_mint::id()).unwrap(),
+                    usdc_reserve.account.liquidity.supply_pubkey,
+                    usdc_reserve.account.config.fee_receiver,
+                    usdc_reserve.account.config.fee_receiver,
+                    usdc_reserve.pubkey,
+                    lending_market.pubkey,
+                    user.keypair.pubkey(),
+                ),
+            ],
+            Some(&[&user.keypair]),
+            nonce_pubkey,
+        )
+        .await;
+
+    test.advance_clock_by_slots(SLOTS_BEFORE_SUBMISSION).await;
+    test.process_nonce_transaction(transaction).await.unwrap();
+}
@@ -206,6 +206,117 @@ async fn test_fail_disable_flash_loans() {
     );
 }
 
+#[tokio::test]
+async fn test_flash_loan_cap() {
+    const MAX_FLASH_LOAN_AMOUNT: u64 = 1_000 * FRACTIONAL_TO_USDC;
+    let (mut test, lending_market, usdc_reserve, user, host_fee_receiver, lending_market_owner) =
+        setup(&ReserveConfig {
+            deposit_limit: u64::MAX,
+            max_flash_loan_amount: MAX_FLASH_LOAN_AMOUNT,
+            ..test_reserve_config()
+        })
+        .await;
+
+    // flash borrow and repay pairs of the given amounts, one after the other
+    let flash_loans = |liquidity_amounts: &[u64]| {
+        let mut instructions = Vec::new();
+        for &liquidity_amount in liquidity_amounts {
+            let borrow_instruction_index = instructions.len() as u8;
+            instructions.push(flash_borrow_reserve_liquidity(
+                solend_program::id(),
+                liquidity_amount,
+                usdc_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+            ));
+            instructions.push(flash_repay_reserve_liquidity(
+                solend_program::id(),
+                liquidity_amount,
+                borrow_instruction_index,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.account.liquidity.supply_pubkey,
+                usdc_reserve.account.config.fee_receiver,
+                host_fee_receiver.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            ));
+        }
+        instructions
+    };
+
+    test.process_transaction(
+        &flash_loans(&[MAX_FLASH_LOAN_AMOUNT]),
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
+
+    let res = test
+        .process_transaction(
+            &flash_loans(&[MAX_FLASH_LOAN_AMOUNT + 1]),
+            Some(&[&user.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::FlashLoanTooLarge as u32)
+        )
+    );
+
+    // the cap applies to all the flash borrows of the transaction combined
+    test.process_transaction(
+        &flash_loans(&[MAX_FLASH_LOAN_AMOUNT / 2, MAX_FLASH_LOAN_AMOUNT / 2]),
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
+
+    let res = test
+        .process_transaction(
+            &flash_loans(&[MAX_FLASH_LOAN_AMOUNT / 2, MAX_FLASH_LOAN_AMOUNT / 2 + 1]),
+            Some(&[&user.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::FlashLoanTooLarge as u32)
+        )
+    );
+
+    // 0 removes the cap
+    lending_market
+        .update_reserve_config(
+            &mut test,
+            &lending_market_owner,
+            &usdc_reserve,
+            ReserveConfig {
+                max_flash_loan_amount: 0,
+                ..usdc_reserve.account.config
+            },
+            usdc_reserve.account.rate_limiter.config,
+            None,
+        )
+        .await
+        .unwrap();
+
+    test.process_transaction(
+        &flash_loans(&[2 * MAX_FLASH_LOAN_AMOUNT]),
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
+}
+
 #[tokio::test]
 async fn test_fail_double_borrow() {
     let (mut test, lending_market, usdc_reserve, user, host_fee_receiver, _) =
@@ -263,8 +374,8 @@ async fn test_fail_double_borrow() {
     assert_eq!(
         res,
         TransactionError::InstructionError(
-            0,
-            InstructionError::Custom(LendingError::MultipleFlashBorrows as u32)
+            1,
+            InstructionError::Custom(LendingError::NoFlashRepayFound as u32)
         )
     );
 }
@@ -337,7 +448,7 @@ async fn test_fail_double_repay() {
 }
 
 #[tokio::test]
-async fn test_fail_only_one_flash_ix_pair_per_tx() {
+async fn test_success_multiple_flash_ix_pairs_per_tx() {
     let (mut test, lending_market, usdc_reserve, user, host_fee_receiver, _) =
         setup(&ReserveConfig {
             deposit_limit: u64::MAX,
@@ -351,66 +462,147 @@ async fn test_fail_only_one_flash_ix_pair_per_tx() {
         })
         .await;
 
+    let balance_checker = BalanceChecker::start(&mut test, &[&usdc_reserve, &user]).await;
+
     const FLASH_LOAN_AMOUNT: u64 = 3_000_000;
-    let res = test
-        .process_transaction(
-            &[
-                flash_borrow_reserve_liquidity(
-                    solend_program::id(),
-                    FLASH_LOAN_AMOUNT,
-                    usdc_reserve.account.liquidity.supply_pubkey,
-                    user.get_account(&usdc_mint::id()).unwrap(),
-                    usdc_reserve.pubkey,
-                    lending_market.pubkey,
-                ),
-                flash_repay_reserve_liquidity(
-                    solend_program::id(),
-                    FLASH_LOAN_AMOUNT,
-                    0,
-                    user.get_account(&usdc_mint::id()).unwrap(),
-                    usdc_reserve.account.liquidity.supply_pubkey,
-                    usdc_reserve.account.config.fee_receiver,
-                    host_fee_receiver.get_account(&usdc_mint::id()).unwrap(),
-                    usdc_reserve.pubkey,
-                    lending_market.pubkey,
-                    user.keypair.pubkey(),
-                ),
-                flash_borrow_reserve_liquidity(
-                    solend_program::id(),
-                    FLASH_LOAN_AMOUNT,
-                    usdc_reserve.account.liquidity.supply_pubkey,
-                    user.get_account(&usdc_mint::id()).unwrap(),
-                    usdc_reserve.pubkey,
-                    lending_market.pubkey,
-                ),
-                flash_repay_reserve_liquidity(
-                    solend_program::id(),
-                    FLASH_LOAN_AMOUNT,
-                    2,
-                    user.get_account(&usdc_mint::id()).unwrap(),
-                    usdc_reserve.account.liquidity.supply_pubkey,
-                    usdc_reserve.account.config.fee_receiver,
-                    host_fee_receiver.get_account(&usdc_mint::id()).unwrap(),
-                    usdc_reserve.pubkey,
-                    lending_market.pubkey,
-                    user.keypair.pubkey(),
-                ),
-            ],
-            Some(&[&user.keypair]),
-        )
-        .await
-        .unwrap_err()
-        .unwrap();
+    const FEE_AMOUNT: u64 = 9_000;
+    const HOST_FEE_AMOUNT: u64 = 1_800;
+    test.process_transaction(
+        &[
+            flash_borrow_reserve_liquidity(
+                solend_program::id(),
+                FLASH_LOAN_AMOUNT,
+                usdc_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+            ),
+            flash_repay_reserve_liquidity(
+                solend_program::id(),
+                FLASH_LOAN_AMOUNT,
+                0,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.account.liquidity.supply_pubkey,
+                usdc_reserve.account.config.fee_receiver,
+                host_fee_receiver.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            ),
+            flash_borrow_reserve_liquidity(
+                solend_program::id(),
+                FLASH_LOAN_AMOUNT,
+                usdc_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+            ),
+            flash_repay_reserve_liquidity(
+                solend_program::id(),
+                FLASH_LOAN_AMOUNT,
+                2,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.account.liquidity.supply_pubkey,
+                usdc_reserve.account.config.fee_receiver,
+                host_fee_receiver.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            ),
+        ],
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
 
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
     assert_eq!(
-        res,
-        TransactionError::InstructionError(
-            0,
-            InstructionError::Custom(LendingError::MultipleFlashBorrows as u32)
-        )
+        balance_changes,
+        HashSet::from([
+            TokenBalanceChange {
+                token_account: user.get_account(&usdc_mint::id()).unwrap(),
+                mint: usdc_mint::id(),
+                diff: -2 * FEE_AMOUNT as i128,
+            },
+            TokenBalanceChange {
+                token_account: usdc_reserve.account.config.fee_receiver,
+                mint: usdc_mint::id(),
+                diff: 2 * (FEE_AMOUNT - HOST_FEE_AMOUNT) as i128,
+            },
+        ])
     );
 }
 
+#[tokio::test]
+async fn test_success_nested_flash_ix_pairs_on_different_reserves() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    lending_market
+        .deposit(&mut test, &usdc_reserve, &user, 100_000_000_000)
+        .await
+        .unwrap();
+
+    let usdc_reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    let balance_checker =
+        BalanceChecker::start(&mut test, &[&usdc_reserve, &wsol_reserve, &user]).await;
+
+    const USDC_FLASH_LOAN_AMOUNT: u64 = 1_000 * FRACTIONAL_TO_USDC;
+    const WSOL_FLASH_LOAN_AMOUNT: u64 = LAMPORTS_TO_SOL / 2;
+    test.process_transaction(
+        &[
+            flash_borrow_reserve_liquidity(
+                solend_program::id(),
+                USDC_FLASH_LOAN_AMOUNT,
+                usdc_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+            ),
+            flash_borrow_reserve_liquidity(
+                solend_program::id(),
+                WSOL_FLASH_LOAN_AMOUNT,
+                wsol_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&wsol_mint::id()).unwrap(),
+                wsol_reserve.pubkey,
+                lending_market.pubkey,
+            ),
+            flash_repay_reserve_liquidity(
+                solend_program::id(),
+                WSOL_FLASH_LOAN_AMOUNT,
+                1,
+                user.get_account(&wsol_mint::id()).unwrap(),
+                wsol_reserve.account.liquidity.supply_pubkey,
+                wsol_reserve.account.config.fee_receiver,
+                user.get_account(&wsol_mint::id()).unwrap(),
+                wsol_reserve.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            ),
+            flash_repay_reserve_liquidity(
+                solend_program::id(),
+                USDC_FLASH_LOAN_AMOUNT,
+                0,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.account.liquidity.supply_pubkey,
+                usdc_reserve.account.config.fee_receiver,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            ),
+        ],
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
+
+    let (balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(balance_changes, HashSet::new());
+    assert_eq!(mint_supply_changes, HashSet::new());
+}
+
 #[tokio::test]
 async fn test_fail_invalid_repay_ix() {
     let (mut test, lending_market, usdc_reserve, user, host_fee_receiver, _) =
@@ -655,7 +847,7 @@ async fn test_fail_invalid_repay_ix() {
         );
     }
 
-    // case 7: Incorrect borrow instruction index -- points to itself
+    // case 7: Incorrect borrow instruction index -- points to itself, so the borrow has no repay
     {
         let res = test
             .process_transaction(
@@ -691,12 +883,13 @@ async fn test_fail_invalid_repay_ix() {
             res,
             TransactionError::InstructionError(
                 0,
-                InstructionError::Custom(LendingError::InvalidFlashRepay as u32)
+                InstructionError::Custom(LendingError::NoFlashRepayFound as u32)
             )
         );
     }
 
-    // case 8: Incorrect borrow instruction index -- points to some other program
+    // case 8: Incorrect borrow instruction index -- points to some other program, so the borrow
+    // has no repay
     {
         let user_transfer_authority = Keypair::new();
         let res = test
@@ -742,7 +935,7 @@ async fn test_fail_invalid_repay_ix() {
             res,
             TransactionError::InstructionError(
                 1,
-                InstructionError::Custom(LendingError::InvalidFlashRepay as u32)
+                InstructionError::Custom(LendingError::NoFlashRepayFound as u32)
             )
         );
     }
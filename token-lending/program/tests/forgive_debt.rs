@@ -173,6 +173,7 @@ async fn test_forgive_debt_success_easy() {
                 slot: 1002,
                 stale: true,
             },
+            oldest_price_slot: 1002,
             deposits: vec![],
             borrows: vec![],
             deposited_value: Decimal::zero(),
@@ -195,6 +196,7 @@ async fn test_forgive_debt_success_easy() {
                 stale: true,
             },
             liquidity: ReserveLiquidity {
+                price_slot: 1002,
                 borrowed_amount_wads: Decimal::zero(),
                 // 0.2 SOL is repaid on liquidation
                 available_amount: LAMPORTS_PER_SOL / 5
@@ -418,3 +420,148 @@ async fn test_forgive_debt_fail_no_signer() {
         )
     );
 }
+
+#[tokio::test]
+async fn test_forgive_debt_partial() {
+    let (mut test, lending_market, reserves, obligations, _users, lending_market_owner) =
+        custom_scenario(
+            &[
+                ReserveArgs {
+                    mint: usdc_mint::id(),
+                    config: ReserveConfig {
+                        liquidation_bonus: 0,
+                        max_liquidation_bonus: 0,
+                        protocol_liquidation_fee: 0,
+                        ..test_reserve_config()
+                    },
+                    liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                    price: PriceArgs {
+                        price: 10,
+                        conf: 0,
+                        expo: -1,
+                        ema_price: 10,
+                        ema_conf: 1,
+                    },
+                },
+                ReserveArgs {
+                    mint: wsol_mint::id(),
+                    config: ReserveConfig {
+                        loan_to_value_ratio: 50,
+                        liquidation_threshold: 55,
+                        fees: ReserveFees::default(),
+                        optimal_borrow_rate: 0,
+                        max_borrow_rate: 0,
+                        ..test_reserve_config()
+                    },
+                    liquidity_amount: 2 * LAMPORTS_PER_SOL,
+                    price: PriceArgs {
+                        price: 10,
+                        conf: 0,
+                        expo: 0,
+                        ema_price: 10,
+                        ema_conf: 0,
+                    },
+                },
+            ],
+            &[ObligationArgs {
+                deposits: vec![(usdc_mint::id(), 20 * FRACTIONAL_TO_USDC)],
+                borrows: vec![(wsol_mint::id(), LAMPORTS_PER_SOL)],
+            }],
+        )
+        .await;
+
+    // USDC depegs to 0.1
+    test.set_price(
+        &usdc_mint::id(),
+        &PriceArgs {
+            price: 1,
+            conf: 0,
+            expo: -1,
+            ema_price: 0,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    test.advance_clock_by_slots(1).await;
+
+    let wsol_reserve = find_reserve(&reserves, &wsol_mint::id()).unwrap();
+    let usdc_reserve = find_reserve(&reserves, &usdc_mint::id()).unwrap();
+
+    // liquidate everything first. 0.2 SOL is repaid, 0.8 SOL of debt is left
+    {
+        let liquidator = User::new_with_balances(
+            &mut test,
+            &[
+                (&usdc_mint::id(), 100_000 * FRACTIONAL_TO_USDC),
+                (&usdc_reserve.account.collateral.mint_pubkey, 0),
+                (&wsol_mint::id(), 100_000 * LAMPORTS_PER_SOL),
+                (&wsol_reserve.account.collateral.mint_pubkey, 0),
+            ],
+        )
+        .await;
+
+        lending_market
+            .liquidate_obligation_and_redeem_reserve_collateral(
+                &mut test,
+                &wsol_reserve,
+                &usdc_reserve,
+                &obligations[0],
+                &liquidator,
+                u64::MAX,
+            )
+            .await
+            .unwrap();
+
+        test.advance_clock_by_slots(1).await;
+    }
+
+    // only forgive 0.1 SOL, the rest of the borrow stays on the obligation
+    lending_market
+        .forgive_debt(
+            &mut test,
+            &obligations[0],
+            &lending_market_owner,
+            &wsol_reserve,
+            LAMPORTS_PER_SOL / 10,
+        )
+        .await
+        .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligations[0].pubkey).await;
+    assert!(obligation_post.account.deposits.is_empty());
+    assert_eq!(obligation_post.account.borrows.len(), 1);
+    assert_eq!(
+        obligation_post.account.borrows[0].borrowed_amount_wads,
+        Decimal::from(LAMPORTS_PER_SOL * 7 / 10)
+    );
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.borrowed_amount_wads,
+        Decimal::from(LAMPORTS_PER_SOL * 7 / 10)
+    );
+
+    test.advance_clock_by_slots(1).await;
+
+    // forgiving the remainder removes the borrow entirely
+    lending_market
+        .forgive_debt(
+            &mut test,
+            &obligations[0],
+            &lending_market_owner,
+            &wsol_reserve,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligations[0].pubkey).await;
+    assert!(obligation_post.account.borrows.is_empty());
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.borrowed_amount_wads,
+        Decimal::zero()
+    );
+}
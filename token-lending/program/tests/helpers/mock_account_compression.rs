@@ -0,0 +1,41 @@
+/// mock the account compression program's append in tests with this program. the mock tree
+/// account holds the number of leaves appended followed by the last leaf.
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use std::convert::TryInto;
+
+/// Anchor discriminator of the account compression program's `append` instruction
+const APPEND: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+/// Size of a mock merkle tree account
+pub const MOCK_MERKLE_TREE_LEN: usize = 8 + 32;
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    if instruction_data.len() != 8 + 32 || instruction_data[..8] != APPEND {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    msg!("Mock Account Compression: Append");
+    let account_info_iter = &mut accounts.iter();
+    let merkle_tree = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut data = merkle_tree.try_borrow_mut_data()?;
+    let leaf_count = u64::from_le_bytes(data[..8].try_into().unwrap()) + 1;
+    data[..8].copy_from_slice(&leaf_count.to_le_bytes());
+    data[8..MOCK_MERKLE_TREE_LEN].copy_from_slice(&instruction_data[8..]);
+
+    Ok(())
+}
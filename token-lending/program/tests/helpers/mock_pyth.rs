@@ -134,6 +134,7 @@ impl Processor {
 
                 price_account.last_slot = Clock::get()?.slot;
                 price_account.agg.pub_slot = Clock::get()?.slot;
+                price_account.timestamp = Clock::get()?.unix_timestamp;
                 price_account.agg.status = PriceStatus::Trading;
 
                 Ok(())
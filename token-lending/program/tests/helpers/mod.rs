@@ -1,8 +1,10 @@
 #![allow(dead_code)]
 
 pub mod flash_loan_proxy;
+#[allow(unexpected_cfgs)] // from the entrypoint! macro
 pub mod flash_loan_receiver;
 pub mod genesis;
+pub mod mock_account_compression;
 pub mod mock_pyth;
 pub mod mock_pyth_pull;
 pub mod mock_switchboard;
@@ -17,7 +19,7 @@ use solana_sdk::{
     account::Account,
     signature::{Keypair, Signer},
 };
-use solend_program::state::{ReserveConfig, ReserveFees, ReserveType};
+use solend_program::state::{ReserveConfig, ReserveFees, ReserveType, LIQUIDATION_CLOSE_FACTOR};
 
 use spl_token::state::Mint;
 
@@ -43,6 +45,8 @@ pub fn reserve_config_no_fees() -> ReserveConfig {
         optimal_borrow_rate: 0,
         max_borrow_rate: 0,
         super_max_borrow_rate: 0,
+        super_optimal_utilization_rate: 0,
+        super_optimal_borrow_rate: 0,
         fees: ReserveFees {
             borrow_fee_wad: 0,
             flash_loan_fee_wad: 0,
@@ -59,6 +63,24 @@ pub fn reserve_config_no_fees() -> ReserveConfig {
         extra_oracle_pubkey: None,
         attributed_borrow_limit_open: u64::MAX,
         attributed_borrow_limit_close: u64::MAX,
+        max_close_factor_bps: LIQUIDATION_CLOSE_FACTOR as u16 * 100,
+        liquidation_price_band_bps: 0,
+        max_oracle_staleness_secs: 0,
+        max_oracle_confidence_bps: 0,
+        max_single_borrower_share_bps: 0,
+        deposits_paused: false,
+        borrows_paused: false,
+        withdrawals_paused: false,
+        min_borrow_value: 0,
+        max_collateral_value_contribution: 0,
+        min_price: 0,
+        max_price: 0,
+        liquidation_auction: false,
+        protocol_liquidation_fee_in_liquidity: false,
+        asset_category: 0,
+        borrow_rate_cap: 0,
+        rate_cap_premium_bps: 0,
+        max_flash_loan_amount: 0,
     }
 }
 
@@ -75,6 +97,8 @@ pub fn test_reserve_config() -> ReserveConfig {
         optimal_borrow_rate: 4,
         max_borrow_rate: 30,
         super_max_borrow_rate: 30,
+        super_optimal_utilization_rate: 0,
+        super_optimal_borrow_rate: 0,
         fees: ReserveFees {
             borrow_fee_wad: 0,
             flash_loan_fee_wad: 0,
@@ -91,6 +115,24 @@ pub fn test_reserve_config() -> ReserveConfig {
         extra_oracle_pubkey: None,
         attributed_borrow_limit_open: u64::MAX,
         attributed_borrow_limit_close: u64::MAX,
+        max_close_factor_bps: LIQUIDATION_CLOSE_FACTOR as u16 * 100,
+        liquidation_price_band_bps: 0,
+        max_oracle_staleness_secs: 0,
+        max_oracle_confidence_bps: 0,
+        max_single_borrower_share_bps: 0,
+        deposits_paused: false,
+        borrows_paused: false,
+        withdrawals_paused: false,
+        min_borrow_value: 0,
+        max_collateral_value_contribution: 0,
+        min_price: 0,
+        max_price: 0,
+        liquidation_auction: false,
+        protocol_liquidation_fee_in_liquidity: false,
+        asset_category: 0,
+        borrow_rate_cap: 0,
+        rate_cap_premium_bps: 0,
+        max_flash_loan_amount: 0,
     }
 }
 
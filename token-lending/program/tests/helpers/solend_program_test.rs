@@ -20,30 +20,36 @@ use super::{
 use crate::helpers::*;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 use solend_program::state::RateLimiterConfig;
-use solend_sdk::{instruction::update_reserve_config, NULL_PUBKEY};
+use solend_sdk::{
+    instruction::update_reserve_config, spl_account_compression, spl_associated_token_account,
+    NULL_PUBKEY,
+};
 
 use pyth_sdk_solana::state::PROD_ACCT_SIZE;
 use solana_program::{
     clock::Clock,
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction},
+    nonce,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
     rent::Rent,
-    system_instruction, sysvar,
+    system_instruction, system_program, sysvar,
 };
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
     signature::{Keypair, Signer},
     system_instruction::create_account,
     transaction::Transaction,
+    transaction_context::TransactionReturnData,
 };
 use solend_program::{
     instruction::{
-        deposit_obligation_collateral, deposit_reserve_liquidity, forgive_debt,
-        init_lending_market, init_reserve, liquidate_obligation_and_redeem_reserve_collateral,
-        redeem_fees, redeem_reserve_collateral, repay_obligation_liquidity,
-        set_lending_market_owner_and_config, withdraw_obligation_collateral,
+        deposit_obligation_collateral, forgive_debt, init_lending_market, init_reserve,
+        liquidate_obligation_and_redeem_reserve_collateral, redeem_fees, redeem_fees_if_above,
+        repay_obligation_liquidity, set_lending_market_owner_and_config,
+        withdraw_obligation_collateral,
     },
+    math::Decimal,
     processor::process_instruction,
     state::{LendingMarket, Reserve, ReserveConfig},
 };
@@ -51,6 +57,7 @@ use solend_program::{
 use spl_token::state::{Account as Token, Mint};
 use std::{
     collections::{HashMap, HashSet},
+    convert::TryInto,
     str::FromStr,
 };
 
@@ -109,6 +116,11 @@ impl SolendProgramTest {
             proxy_program::id(),
             processor!(flash_loan_proxy::process_instruction),
         );
+        test.add_program(
+            "mock_account_compression",
+            spl_account_compression::id(),
+            processor!(mock_account_compression::process_instruction),
+        );
 
         let authority = Keypair::new();
 
@@ -169,6 +181,11 @@ impl SolendProgramTest {
             proxy_program::id(),
             processor!(flash_loan_proxy::process_instruction),
         );
+        test.add_program(
+            "mock_account_compression",
+            spl_account_compression::id(),
+            processor!(mock_account_compression::process_instruction),
+        );
 
         let authority = Keypair::new();
 
@@ -223,6 +240,106 @@ impl SolendProgramTest {
             .await
     }
 
+    /// Processes a transaction like process_transaction, returning the return data its last
+    /// instruction to set any left behind
+    pub async fn process_transaction_with_return_data(
+        &mut self,
+        instructions: &[Instruction],
+        signers: Option<&[&Keypair]>,
+    ) -> Result<Option<TransactionReturnData>, BanksClientError> {
+        let mut transaction =
+            Transaction::new_with_payer(instructions, Some(&self.context.payer.pubkey()));
+
+        let mut all_signers = vec![&self.context.payer];
+
+        if let Some(signers) = signers {
+            all_signers.extend_from_slice(signers);
+        }
+
+        transaction.sign(&all_signers, self.context.last_blockhash);
+
+        let result = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+        result.result?;
+
+        Ok(result.metadata.and_then(|metadata| metadata.return_data))
+    }
+
+    /// Creates a nonce account the payer is the authority of
+    pub async fn create_nonce_account(&mut self) -> Pubkey {
+        let nonce_keypair = Keypair::new();
+        let instructions = system_instruction::create_nonce_account(
+            &self.context.payer.pubkey(),
+            &nonce_keypair.pubkey(),
+            &self.context.payer.pubkey(),
+            self.rent.minimum_balance(nonce::State::size()),
+        );
+
+        self.process_transaction(&instructions, Some(&[&nonce_keypair]))
+            .await
+            .unwrap();
+
+        nonce_keypair.pubkey()
+    }
+
+    /// Signs a durable nonce transaction that advances the nonce account before running the
+    /// instructions. It stays valid until the nonce is advanced, however many slots pass.
+    pub async fn sign_with_nonce(
+        &mut self,
+        instructions: &[Instruction],
+        signers: Option<&[&Keypair]>,
+        nonce_pubkey: Pubkey,
+    ) -> Transaction {
+        let nonce_account = self
+            .context
+            .banks_client
+            .get_account(nonce_pubkey)
+            .await
+            .unwrap()
+            .unwrap();
+        let nonce_blockhash =
+            match bincode::deserialize::<nonce::state::Versions>(&nonce_account.data)
+                .unwrap()
+                .state()
+            {
+                nonce::State::Initialized(data) => data.blockhash(),
+                nonce::State::Uninitialized => panic!("nonce account is not initialized"),
+            };
+
+        let mut nonce_instructions = vec![system_instruction::advance_nonce_account(
+            &nonce_pubkey,
+            &self.context.payer.pubkey(),
+        )];
+        nonce_instructions.extend_from_slice(instructions);
+        let mut transaction =
+            Transaction::new_with_payer(&nonce_instructions, Some(&self.context.payer.pubkey()));
+
+        let mut all_signers = vec![&self.context.payer];
+        if let Some(signers) = signers {
+            all_signers.extend_from_slice(signers);
+        }
+        transaction.sign(&all_signers, nonce_blockhash);
+
+        transaction
+    }
+
+    /// Processes a transaction signed by sign_with_nonce. The banks client's process_transaction
+    /// looks its blockhash up in the recent blockhash queue, which a nonce blockhash needn't be in.
+    pub async fn process_nonce_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<(), BanksClientError> {
+        self.context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?
+            .result
+            .map_err(BanksClientError::from)
+    }
+
     pub async fn load_optional_account<T: Pack + IsInitialized>(
         &mut self,
         acc_pk: Pubkey,
@@ -380,6 +497,63 @@ impl SolendProgramTest {
         keypair.pubkey()
     }
 
+    /// Creates a wSOL token account holding `amount` lamports on top of its rent, synced so they
+    /// count as its token balance.
+    pub async fn create_native_token_account(&mut self, owner: &Pubkey, amount: u64) -> Pubkey {
+        let keypair = Keypair::new();
+        let instructions = [
+            system_instruction::create_account(
+                &self.context.payer.pubkey(),
+                &keypair.pubkey(),
+                self.rent.minimum_balance(Token::LEN) + amount,
+                Token::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &keypair.pubkey(),
+                &spl_token::native_mint::id(),
+                owner,
+            )
+            .unwrap(),
+        ];
+
+        self.process_transaction(&instructions, Some(&[&keypair]))
+            .await
+            .unwrap();
+
+        keypair.pubkey()
+    }
+
+    /// Creates the associated token account of a wallet. The spl crate isn't a dependency, so the
+    /// instruction is built by hand.
+    pub async fn create_associated_token_account(
+        &mut self,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+    ) -> Pubkey {
+        let (associated_token_pubkey, _bump_seed) = Pubkey::find_program_address(
+            &[wallet.as_ref(), spl_token::id().as_ref(), mint.as_ref()],
+            &spl_associated_token_account::id(),
+        );
+        let instructions = [Instruction {
+            program_id: spl_associated_token_account::id(),
+            accounts: vec![
+                AccountMeta::new(self.context.payer.pubkey(), true),
+                AccountMeta::new(associated_token_pubkey, false),
+                AccountMeta::new_readonly(*wallet, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: vec![],
+        }];
+
+        self.process_transaction(&instructions, None).await.unwrap();
+
+        associated_token_pubkey
+    }
+
     pub async fn mint_to(&mut self, mint: &Pubkey, dst: &Pubkey, amount: u64) {
         assert!(self.mints.contains_key(mint));
 
@@ -707,6 +881,7 @@ impl User {
 
     /// Creates a user with specified token accounts and balances. This function only works if the
     /// SolendProgramTest object owns the mint authorities. eg this won't work for native SOL.
+    /// The user is also funded with 1 SOL so it can pay rent for the PDAs it creates.
     pub async fn new_with_balances(
         test: &mut SolendProgramTest,
         mints_and_balances: &[(&Pubkey, u64)],
@@ -716,6 +891,18 @@ impl User {
             token_accounts: Vec::new(),
         };
 
+        let payer = test.context.payer.pubkey();
+        test.process_transaction(
+            &[system_instruction::transfer(
+                &payer,
+                &user.keypair.pubkey(),
+                LAMPORTS_PER_SOL,
+            )],
+            None,
+        )
+        .await
+        .unwrap();
+
         for (mint, balance) in mints_and_balances {
             let token_account = user.create_token_account(mint, test).await;
             if *balance > 0 {
@@ -808,6 +995,35 @@ pub struct SwitchboardPriceArgs {
 }
 
 impl Info<LendingMarket> {
+    /// Token account of the market's default host fee receiver, passed as the host fee receiver of
+    /// borrows without one, if the market has one
+    async fn default_host_fee_receiver(
+        &self,
+        test: &mut SolendProgramTest,
+        liquidity_mint: &Pubkey,
+    ) -> Option<Pubkey> {
+        let lending_market = test.load_account::<LendingMarket>(self.pubkey).await;
+        lending_market
+            .account
+            .default_host_fee_receiver_token_address(&spl_token::id(), liquidity_mint)
+    }
+
+    /// Market member account of a wallet, passed last when the lending market requires a
+    /// whitelist
+    async fn market_member_account(
+        &self,
+        test: &mut SolendProgramTest,
+        member: Pubkey,
+    ) -> Option<AccountMeta> {
+        let lending_market = test.load_account::<LendingMarket>(self.pubkey).await;
+        lending_market.account.whitelist_required.then(|| {
+            AccountMeta::new_readonly(
+                find_market_member_address(&solend_program::id(), &self.pubkey, &member).0,
+                false,
+            )
+        })
+    }
+
     pub async fn set_obligation_closeability_status(
         &self,
         test: &mut SolendProgramTest,
@@ -841,21 +1057,39 @@ impl Info<LendingMarket> {
         user: &User,
         liquidity_amount: u64,
     ) -> Result<(), BanksClientError> {
+        self.deposit_with_min_out(test, reserve, user, liquidity_amount, 0)
+            .await
+    }
+
+    pub async fn deposit_with_min_out(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        user: &User,
+        liquidity_amount: u64,
+        min_collateral_out: u64,
+    ) -> Result<(), BanksClientError> {
+        let mut deposit_ix = deposit_reserve_liquidity_with_min_out(
+            solend_program::id(),
+            liquidity_amount,
+            min_collateral_out,
+            user.get_account(&reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            user.get_account(&reserve.account.collateral.mint_pubkey)
+                .unwrap(),
+            reserve.pubkey,
+            reserve.account.liquidity.supply_pubkey,
+            reserve.account.collateral.mint_pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+        );
+        deposit_ix.accounts.extend(
+            self.market_member_account(test, user.keypair.pubkey())
+                .await,
+        );
         let instructions = [
             ComputeBudgetInstruction::set_compute_unit_limit(50_000),
-            deposit_reserve_liquidity(
-                solend_program::id(),
-                liquidity_amount,
-                user.get_account(&reserve.account.liquidity.mint_pubkey)
-                    .unwrap(),
-                user.get_account(&reserve.account.collateral.mint_pubkey)
-                    .unwrap(),
-                reserve.pubkey,
-                reserve.account.liquidity.supply_pubkey,
-                reserve.account.collateral.mint_pubkey,
-                self.pubkey,
-                user.keypair.pubkey(),
-            ),
+            deposit_ix,
         ];
 
         test.process_transaction(&instructions, Some(&[&user.keypair]))
@@ -956,12 +1190,56 @@ impl Info<LendingMarket> {
             .await
     }
 
+    pub async fn compound_obligation_collateral(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        collateral_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+            compound_obligation_collateral(
+                solend_program::id(),
+                collateral_amount,
+                user.get_account(&reserve.account.collateral.mint_pubkey)
+                    .unwrap(),
+                user.get_account(&reserve.account.liquidity.mint_pubkey)
+                    .unwrap(),
+                reserve.pubkey,
+                reserve.account.collateral.mint_pubkey,
+                reserve.account.liquidity.supply_pubkey,
+                reserve.account.collateral.supply_pubkey,
+                obligation.pubkey,
+                self.pubkey,
+                user.keypair.pubkey(),
+                user.keypair.pubkey(),
+            ),
+        ];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
     pub async fn redeem(
         &self,
         test: &mut SolendProgramTest,
         reserve: &Info<Reserve>,
         user: &User,
         collateral_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        self.redeem_with_min_out(test, reserve, user, collateral_amount, 0)
+            .await
+    }
+
+    pub async fn redeem_with_min_out(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        user: &User,
+        collateral_amount: u64,
+        min_liquidity_out: u64,
     ) -> Result<(), BanksClientError> {
         let instructions = [
             ComputeBudgetInstruction::set_compute_unit_limit(58_000),
@@ -972,9 +1250,10 @@ impl Info<LendingMarket> {
                 reserve.account.liquidity.switchboard_oracle_pubkey,
                 reserve.account.config.extra_oracle_pubkey,
             ),
-            redeem_reserve_collateral(
+            redeem_reserve_collateral_with_min_out(
                 solend_program::id(),
                 collateral_amount,
+                min_liquidity_out,
                 user.get_account(&reserve.account.collateral.mint_pubkey)
                     .unwrap(),
                 user.get_account(&reserve.account.liquidity.mint_pubkey)
@@ -997,6 +1276,16 @@ impl Info<LendingMarket> {
         obligation_keypair: Keypair,
         user: &User,
     ) -> Result<Info<Obligation>, BanksClientError> {
+        let mut init_obligation_ix = init_obligation(
+            solend_program::id(),
+            obligation_keypair.pubkey(),
+            self.pubkey,
+            user.keypair.pubkey(),
+        );
+        init_obligation_ix.accounts.extend(
+            self.market_member_account(test, user.keypair.pubkey())
+                .await,
+        );
         let instructions = [
             ComputeBudgetInstruction::set_compute_unit_limit(10_000),
             system_instruction::create_account(
@@ -1006,12 +1295,7 @@ impl Info<LendingMarket> {
                 Obligation::LEN as u64,
                 &solend_program::id(),
             ),
-            init_obligation(
-                solend_program::id(),
-                obligation_keypair.pubkey(),
-                self.pubkey,
-                user.keypair.pubkey(),
-            ),
+            init_obligation_ix,
         ];
 
         match test
@@ -1104,6 +1388,18 @@ impl Info<LendingMarket> {
             reserves.push(test.load_account::<Reserve>(pubkey).await);
         }
 
+        let pair_borrow_weights_reserve_pubkeys: Vec<Pubkey> = obligation
+            .account
+            .borrows
+            .iter()
+            .map(|b| b.borrow_reserve)
+            .filter(|pubkey| {
+                reserves
+                    .iter()
+                    .any(|r| r.pubkey == *pubkey && r.account.has_pair_borrow_weights)
+            })
+            .collect();
+
         let mut instructions: Vec<Instruction> = reserves
             .into_iter()
             .map(|reserve| {
@@ -1130,10 +1426,11 @@ impl Info<LendingMarket> {
             r
         };
 
-        instructions.push(refresh_obligation(
+        instructions.push(refresh_obligation_with_pair_borrow_weights(
             solend_program::id(),
             obligation.pubkey,
             reserve_pubkeys,
+            pair_borrow_weights_reserve_pubkeys,
         ));
 
         instructions
@@ -1173,6 +1470,29 @@ impl Info<LendingMarket> {
         user: &User,
         host_fee_receiver_pubkey: Option<Pubkey>,
         liquidity_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        self.borrow_obligation_liquidity_with_min_received(
+            test,
+            borrow_reserve,
+            obligation,
+            user,
+            host_fee_receiver_pubkey,
+            liquidity_amount,
+            0,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn borrow_obligation_liquidity_with_min_received(
+        &self,
+        test: &mut SolendProgramTest,
+        borrow_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        host_fee_receiver_pubkey: Option<Pubkey>,
+        liquidity_amount: u64,
+        min_liquidity_received: u64,
     ) -> Result<(), BanksClientError> {
         let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
 
@@ -1181,10 +1501,17 @@ impl Info<LendingMarket> {
             .await;
         test.process_transaction(&refresh_ixs, None).await.unwrap();
 
-        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(100_000)];
-        instructions.push(borrow_obligation_liquidity(
+        let host_fee_receiver_pubkey = match host_fee_receiver_pubkey {
+            None if obligation.account.referrer.is_none() => {
+                self.default_host_fee_receiver(test, &borrow_reserve.account.liquidity.mint_pubkey)
+                    .await
+            }
+            host_fee_receiver_pubkey => host_fee_receiver_pubkey,
+        };
+        let mut borrow_ix = borrow_obligation_liquidity_with_min_received(
             solend_program::id(),
             liquidity_amount,
+            min_liquidity_received,
             borrow_reserve.account.liquidity.supply_pubkey,
             user.get_account(&borrow_reserve.account.liquidity.mint_pubkey)
                 .unwrap(),
@@ -1200,66 +1527,173 @@ impl Info<LendingMarket> {
                 .map(|d| d.deposit_reserve)
                 .collect(),
             host_fee_receiver_pubkey,
-        ));
+        );
+        borrow_ix.accounts.extend(
+            self.market_member_account(test, user.keypair.pubkey())
+                .await,
+        );
+
+        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(100_000)];
+        instructions.push(borrow_ix);
 
         test.process_transaction(&instructions, Some(&[&user.keypair]))
             .await
     }
 
-    pub async fn repay_obligation_liquidity(
+    #[allow(clippy::too_many_arguments)]
+    pub async fn deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
         &self,
         test: &mut SolendProgramTest,
-        repay_reserve: &Info<Reserve>,
+        deposit_reserve: &Info<Reserve>,
+        borrow_reserve: &Info<Reserve>,
         obligation: &Info<Obligation>,
         user: &User,
-        liquidity_amount: u64,
+        host_fee_receiver_pubkey: Option<Pubkey>,
+        deposit_liquidity_amount: u64,
+        borrow_liquidity_amount: u64,
     ) -> Result<(), BanksClientError> {
-        let instructions = [
-            ComputeBudgetInstruction::set_compute_unit_limit(35_000),
-            repay_obligation_liquidity(
-                solend_program::id(),
-                liquidity_amount,
-                user.get_account(&repay_reserve.account.liquidity.mint_pubkey)
-                    .unwrap(),
-                repay_reserve.account.liquidity.supply_pubkey,
-                repay_reserve.pubkey,
-                obligation.pubkey,
-                self.pubkey,
-                user.keypair.pubkey(),
-            ),
-        ];
+        let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
 
-        test.process_transaction(&instructions, Some(&[&user.keypair]))
-            .await
-    }
+        self.refresh_reserve(test, deposit_reserve).await.unwrap();
+        let refresh_ixs = self
+            .build_refresh_instructions(test, &obligation, Some(borrow_reserve))
+            .await;
+        test.process_transaction(&refresh_ixs, None).await.unwrap();
 
-    pub async fn redeem_fees(
-        &self,
-        test: &mut SolendProgramTest,
-        reserve: &Info<Reserve>,
-    ) -> Result<(), BanksClientError> {
-        let instructions = [
-            ComputeBudgetInstruction::set_compute_unit_limit(50_000),
-            refresh_reserve(
-                solend_program::id(),
-                reserve.pubkey,
-                reserve.account.liquidity.pyth_oracle_pubkey,
-                reserve.account.liquidity.switchboard_oracle_pubkey,
-                reserve.account.config.extra_oracle_pubkey,
-            ),
-            redeem_fees(
+        let mut obligation_reserves: Vec<Pubkey> = obligation
+            .account
+            .deposits
+            .iter()
+            .map(|d| d.deposit_reserve)
+            .collect();
+        if !obligation_reserves.contains(&deposit_reserve.pubkey) {
+            obligation_reserves.push(deposit_reserve.pubkey);
+        }
+        obligation_reserves.extend(obligation.account.borrows.iter().map(|b| b.borrow_reserve));
+
+        let instruction = deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+            solend_program::id(),
+            deposit_liquidity_amount,
+            borrow_liquidity_amount,
+            user.get_account(&deposit_reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            user.get_account(&deposit_reserve.account.collateral.mint_pubkey)
+                .unwrap(),
+            deposit_reserve.pubkey,
+            deposit_reserve.account.liquidity.supply_pubkey,
+            deposit_reserve.account.collateral.mint_pubkey,
+            self.pubkey,
+            deposit_reserve.account.collateral.supply_pubkey,
+            obligation.pubkey,
+            user.keypair.pubkey(),
+            user.keypair.pubkey(),
+            borrow_reserve.pubkey,
+            borrow_reserve.account.liquidity.supply_pubkey,
+            borrow_reserve.account.config.fee_receiver,
+            user.get_account(&borrow_reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            obligation_reserves,
+            host_fee_receiver_pubkey,
+        );
+
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(250_000),
+                instruction,
+            ],
+            Some(&[&user.keypair]),
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn borrow_obligation_liquidity_with_wallet_collateral(
+        &self,
+        test: &mut SolendProgramTest,
+        deposit_reserve: &Info<Reserve>,
+        borrow_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        host_fee_receiver_pubkey: Option<Pubkey>,
+        liquidity_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+
+        self.refresh_reserve(test, deposit_reserve).await.unwrap();
+        let refresh_ixs = self
+            .build_refresh_instructions(test, &obligation, Some(borrow_reserve))
+            .await;
+        test.process_transaction(&refresh_ixs, None).await.unwrap();
+
+        let mut obligation_reserves: Vec<Pubkey> = obligation
+            .account
+            .deposits
+            .iter()
+            .map(|d| d.deposit_reserve)
+            .collect();
+        if !obligation_reserves.contains(&deposit_reserve.pubkey) {
+            obligation_reserves.push(deposit_reserve.pubkey);
+        }
+        obligation_reserves.extend(obligation.account.borrows.iter().map(|b| b.borrow_reserve));
+
+        let instruction = borrow_obligation_liquidity_with_wallet_collateral(
+            solend_program::id(),
+            liquidity_amount,
+            user.get_account(&deposit_reserve.account.collateral.mint_pubkey)
+                .unwrap(),
+            deposit_reserve.pubkey,
+            deposit_reserve.account.collateral.supply_pubkey,
+            self.pubkey,
+            obligation.pubkey,
+            user.keypair.pubkey(),
+            user.keypair.pubkey(),
+            borrow_reserve.pubkey,
+            borrow_reserve.account.liquidity.supply_pubkey,
+            borrow_reserve.account.config.fee_receiver,
+            user.get_account(&borrow_reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            obligation_reserves,
+            host_fee_receiver_pubkey,
+        );
+
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(250_000),
+                instruction,
+            ],
+            Some(&[&user.keypair]),
+        )
+        .await
+    }
+
+    pub async fn repay_obligation_liquidity(
+        &self,
+        test: &mut SolendProgramTest,
+        repay_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        liquidity_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(35_000),
+            repay_obligation_liquidity(
                 solend_program::id(),
-                reserve.pubkey,
-                reserve.account.config.fee_receiver,
-                reserve.account.liquidity.supply_pubkey,
+                liquidity_amount,
+                user.get_account(&repay_reserve.account.liquidity.mint_pubkey)
+                    .unwrap(),
+                repay_reserve.account.liquidity.supply_pubkey,
+                repay_reserve.pubkey,
+                obligation.pubkey,
                 self.pubkey,
+                user.keypair.pubkey(),
             ),
         ];
 
-        test.process_transaction(&instructions, None).await
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
     }
 
-    pub async fn liquidate_obligation_and_redeem_reserve_collateral(
+    pub async fn repay_obligation_liquidity_with_collateral(
         &self,
         test: &mut SolendProgramTest,
         repay_reserve: &Info<Reserve>,
@@ -1275,23 +1709,16 @@ impl Info<LendingMarket> {
 
         test.process_transaction(
             &[
-                ComputeBudgetInstruction::set_compute_unit_limit(110_000),
-                liquidate_obligation_and_redeem_reserve_collateral(
+                ComputeBudgetInstruction::set_compute_unit_limit(80_000),
+                repay_obligation_liquidity_with_collateral(
                     solend_program::id(),
                     liquidity_amount,
-                    user.get_account(&repay_reserve.account.liquidity.mint_pubkey)
-                        .unwrap(),
-                    user.get_account(&withdraw_reserve.account.collateral.mint_pubkey)
-                        .unwrap(),
-                    user.get_account(&withdraw_reserve.account.liquidity.mint_pubkey)
-                        .unwrap(),
                     repay_reserve.pubkey,
                     repay_reserve.account.liquidity.supply_pubkey,
                     withdraw_reserve.pubkey,
                     withdraw_reserve.account.collateral.mint_pubkey,
                     withdraw_reserve.account.collateral.supply_pubkey,
                     withdraw_reserve.account.liquidity.supply_pubkey,
-                    withdraw_reserve.account.config.fee_receiver,
                     obligation.pubkey,
                     self.pubkey,
                     user.keypair.pubkey(),
@@ -1302,6 +1729,245 @@ impl Info<LendingMarket> {
         .await
     }
 
+    pub async fn repay_obligation_liquidity_many(
+        &self,
+        test: &mut SolendProgramTest,
+        repays: &[(&Info<Reserve>, u64)],
+        obligation: &Info<Obligation>,
+        user: &User,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+            repay_obligation_liquidity_many(
+                solend_program::id(),
+                repays
+                    .iter()
+                    .map(|(repay_reserve, liquidity_amount)| {
+                        (
+                            *liquidity_amount,
+                            user.get_account(&repay_reserve.account.liquidity.mint_pubkey)
+                                .unwrap(),
+                            repay_reserve.account.liquidity.supply_pubkey,
+                            repay_reserve.pubkey,
+                        )
+                    })
+                    .collect(),
+                obligation.pubkey,
+                self.pubkey,
+                user.keypair.pubkey(),
+            ),
+        ];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    /// Accounts redeeming fees of a reserve with a spread fee receiver must be passed last
+    async fn spread_fee_receiver_accounts(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+    ) -> Vec<AccountMeta> {
+        if !reserve.account.has_spread_fee_receiver {
+            return vec![];
+        }
+
+        let reserve_spread_fee_receiver_pubkey =
+            find_reserve_spread_fee_receiver_address(&solend_program::id(), &reserve.pubkey).0;
+        let reserve_spread_fee_receiver = test
+            .load_account::<ReserveSpreadFeeReceiver>(reserve_spread_fee_receiver_pubkey)
+            .await;
+        vec![
+            AccountMeta::new_readonly(reserve_spread_fee_receiver_pubkey, false),
+            AccountMeta::new(
+                reserve_spread_fee_receiver.account.spread_fee_receiver,
+                false,
+            ),
+        ]
+    }
+
+    pub async fn redeem_fees(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+    ) -> Result<(), BanksClientError> {
+        let mut redeem_fees_ix = if reserve.account.has_insurance_fund {
+            redeem_fees_with_insurance_fund(
+                solend_program::id(),
+                reserve.pubkey,
+                reserve.account.config.fee_receiver,
+                reserve.account.liquidity.supply_pubkey,
+                reserve.account.liquidity.mint_pubkey,
+                self.pubkey,
+            )
+        } else {
+            redeem_fees(
+                solend_program::id(),
+                reserve.pubkey,
+                reserve.account.config.fee_receiver,
+                reserve.account.liquidity.supply_pubkey,
+                self.pubkey,
+            )
+        };
+        redeem_fees_ix
+            .accounts
+            .extend(self.spread_fee_receiver_accounts(test, reserve).await);
+
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+            refresh_reserve(
+                solend_program::id(),
+                reserve.pubkey,
+                reserve.account.liquidity.pyth_oracle_pubkey,
+                reserve.account.liquidity.switchboard_oracle_pubkey,
+                reserve.account.config.extra_oracle_pubkey,
+            ),
+            redeem_fees_ix,
+        ];
+
+        test.process_transaction(&instructions, None).await
+    }
+
+    pub async fn redeem_fees_if_above(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        min_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let mut redeem_fees_ix = if reserve.account.has_insurance_fund {
+            redeem_fees_if_above_with_insurance_fund(
+                solend_program::id(),
+                min_amount,
+                reserve.pubkey,
+                reserve.account.config.fee_receiver,
+                reserve.account.liquidity.supply_pubkey,
+                reserve.account.liquidity.mint_pubkey,
+                self.pubkey,
+            )
+        } else {
+            redeem_fees_if_above(
+                solend_program::id(),
+                min_amount,
+                reserve.pubkey,
+                reserve.account.config.fee_receiver,
+                reserve.account.liquidity.supply_pubkey,
+                self.pubkey,
+            )
+        };
+        redeem_fees_ix
+            .accounts
+            .extend(self.spread_fee_receiver_accounts(test, reserve).await);
+
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+            refresh_reserve(
+                solend_program::id(),
+                reserve.pubkey,
+                reserve.account.liquidity.pyth_oracle_pubkey,
+                reserve.account.liquidity.switchboard_oracle_pubkey,
+                reserve.account.config.extra_oracle_pubkey,
+            ),
+            redeem_fees_ix,
+        ];
+
+        test.process_transaction(&instructions, None).await
+    }
+
+    pub async fn liquidate_obligation_and_redeem_reserve_collateral(
+        &self,
+        test: &mut SolendProgramTest,
+        repay_reserve: &Info<Reserve>,
+        withdraw_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        liquidity_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        self.liquidate_obligation_and_redeem_reserve_collateral_with_return_data(
+            test,
+            repay_reserve,
+            withdraw_reserve,
+            obligation,
+            user,
+            liquidity_amount,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Liquidates and redeems like liquidate_obligation_and_redeem_reserve_collateral, returning
+    /// the collateral redeemed, liquidity received and collateral left unredeemed that the
+    /// instruction sets as return data
+    pub async fn liquidate_obligation_and_redeem_reserve_collateral_with_return_data(
+        &self,
+        test: &mut SolendProgramTest,
+        repay_reserve: &Info<Reserve>,
+        withdraw_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        liquidity_amount: u64,
+    ) -> Result<(u64, u64, u64), BanksClientError> {
+        let refresh_ixs = self
+            .build_refresh_instructions(test, obligation, None)
+            .await;
+        test.process_transaction(&refresh_ixs, None).await.unwrap();
+
+        let mut liquidate_ix = liquidate_obligation_and_redeem_reserve_collateral(
+            solend_program::id(),
+            liquidity_amount,
+            user.get_account(&repay_reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            user.get_account(&withdraw_reserve.account.collateral.mint_pubkey)
+                .unwrap(),
+            user.get_account(&withdraw_reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            repay_reserve.pubkey,
+            repay_reserve.account.liquidity.supply_pubkey,
+            withdraw_reserve.pubkey,
+            withdraw_reserve.account.collateral.mint_pubkey,
+            withdraw_reserve.account.collateral.supply_pubkey,
+            withdraw_reserve.account.liquidity.supply_pubkey,
+            withdraw_reserve.account.config.fee_receiver,
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+        );
+        if withdraw_reserve
+            .account
+            .config
+            .protocol_liquidation_fee_in_liquidity
+        {
+            liquidate_ix.accounts.push(AccountMeta::new(
+                repay_reserve.account.config.fee_receiver,
+                false,
+            ));
+        }
+        let lending_market = test.load_account::<LendingMarket>(self.pubkey).await;
+        if lending_market.account.has_liquidator_whitelist {
+            liquidate_ix.accounts.push(AccountMeta::new_readonly(
+                find_liquidator_whitelist_address(&solend_program::id(), &self.pubkey).0,
+                false,
+            ));
+        }
+
+        let return_data = test
+            .process_transaction_with_return_data(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(110_000),
+                    liquidate_ix,
+                ],
+                Some(&[&user.keypair]),
+            )
+            .await?
+            .unwrap();
+        assert_eq!(return_data.program_id, solend_program::id());
+
+        // trailing zeros are trimmed from the return data
+        let mut data = [0u8; 24];
+        data[..return_data.data.len()].copy_from_slice(&return_data.data);
+        let amount = |i: usize| u64::from_le_bytes(data[i..i + 8].try_into().unwrap());
+        Ok((amount(0), amount(8), amount(16)))
+    }
+
     pub async fn liquidate_obligation(
         &self,
         test: &mut SolendProgramTest,
@@ -1381,16 +2047,62 @@ impl Info<LendingMarket> {
         .await
     }
 
-    pub async fn withdraw_obligation_collateral(
+    pub async fn withdraw_obligation_liquidity_exact(
         &self,
         test: &mut SolendProgramTest,
         withdraw_reserve: &Info<Reserve>,
         obligation: &Info<Obligation>,
         user: &User,
-        collateral_amount: u64,
+        liquidity_amount: u64,
     ) -> Result<(), BanksClientError> {
+        let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+
         let refresh_ixs = self
-            .build_refresh_instructions(test, obligation, None)
+            .build_refresh_instructions(test, &obligation, None)
+            .await;
+        test.process_transaction(&refresh_ixs, None).await.unwrap();
+
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(110_000),
+                withdraw_obligation_liquidity_exact(
+                    solend_program::id(),
+                    liquidity_amount,
+                    withdraw_reserve.account.collateral.supply_pubkey,
+                    user.get_account(&withdraw_reserve.account.collateral.mint_pubkey)
+                        .unwrap(),
+                    withdraw_reserve.pubkey,
+                    obligation.pubkey,
+                    self.pubkey,
+                    user.get_account(&withdraw_reserve.account.liquidity.mint_pubkey)
+                        .unwrap(),
+                    withdraw_reserve.account.collateral.mint_pubkey,
+                    withdraw_reserve.account.liquidity.supply_pubkey,
+                    user.keypair.pubkey(),
+                    user.keypair.pubkey(),
+                    obligation
+                        .account
+                        .deposits
+                        .iter()
+                        .map(|d| d.deposit_reserve)
+                        .collect(),
+                ),
+            ],
+            Some(&[&user.keypair]),
+        )
+        .await
+    }
+
+    pub async fn withdraw_obligation_collateral(
+        &self,
+        test: &mut SolendProgramTest,
+        withdraw_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+        collateral_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let refresh_ixs = self
+            .build_refresh_instructions(test, obligation, None)
             .await;
         test.process_transaction(&refresh_ixs, None).await.unwrap();
 
@@ -1443,28 +2155,844 @@ impl Info<LendingMarket> {
             .await
     }
 
-    pub async fn forgive_debt(
+    pub async fn set_risk_authority(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        risk_authority: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_risk_authority(
+            solend_program::id(),
+            risk_authority,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_max_obligations_per_owner(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        max_obligations_per_owner: u16,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_max_obligations_per_owner(
+            solend_program::id(),
+            max_obligations_per_owner,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_whitelist_required(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        whitelist_required: bool,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_whitelist_required(
+            solend_program::id(),
+            whitelist_required,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn update_market_member(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        member: Pubkey,
+        is_member: bool,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [update_market_member(
+            solend_program::id(),
+            is_member,
+            member,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_liquidator_whitelist(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        protected_window_slots: u64,
+        liquidators: Vec<Pubkey>,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_liquidator_whitelist(
+            solend_program::id(),
+            protected_window_slots,
+            liquidators,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_dust_borrow_value(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        dust_borrow_value: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_dust_borrow_value(
+            solend_program::id(),
+            dust_borrow_value,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_max_price_age(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        max_price_age_slots: u8,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_max_price_age(
+            solend_program::id(),
+            max_price_age_slots,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_obligation_max_outflow(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        obligation_max_outflow: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_obligation_max_outflow(
+            solend_program::id(),
+            obligation_max_outflow,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn reset_rate_limiter(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        reserves: &[&Info<Reserve>],
+    ) -> Result<(), BanksClientError> {
+        let instructions = [reset_rate_limiter(
+            solend_program::id(),
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+            reserves.iter().map(|reserve| reserve.pubkey).collect(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_protocol_fee_config(
         &self,
         test: &mut SolendProgramTest,
-        obligation: &Info<Obligation>,
         lending_market_owner: &User,
+        fee_authority: Pubkey,
+        treasury: Pubkey,
+        rate_limiter_config: RateLimiterConfig,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_protocol_fee_config(
+            solend_program::id(),
+            fee_authority,
+            treasury,
+            rate_limiter_config,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn withdraw_protocol_fees(
+        &self,
+        test: &mut SolendProgramTest,
         reserve: &Info<Reserve>,
+        fee_authority: &User,
+        treasury_token_pubkey: Pubkey,
         liquidity_amount: u64,
     ) -> Result<(), BanksClientError> {
-        let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+        let instructions = [
+            refresh_reserve(
+                solend_program::id(),
+                reserve.pubkey,
+                reserve.account.liquidity.pyth_oracle_pubkey,
+                reserve.account.liquidity.switchboard_oracle_pubkey,
+                reserve.account.config.extra_oracle_pubkey,
+            ),
+            withdraw_protocol_fees(
+                solend_program::id(),
+                liquidity_amount,
+                reserve.pubkey,
+                reserve.account.config.fee_receiver,
+                treasury_token_pubkey,
+                self.pubkey,
+                fee_authority.keypair.pubkey(),
+            ),
+        ];
 
-        let mut instructions = self
-            .build_refresh_instructions(test, &obligation, None)
-            .await;
+        test.process_transaction(&instructions, Some(&[&fee_authority.keypair]))
+            .await
+    }
 
-        instructions.push(forgive_debt(
+    pub async fn set_insurance_fee(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        insurance_fee_bps: u16,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_insurance_fee(
+            solend_program::id(),
+            insurance_fee_bps,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn init_insurance_fund(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        reserve: &Info<Reserve>,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [init_insurance_fund(
+            solend_program::id(),
+            reserve.pubkey,
+            reserve.account.liquidity.mint_pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn cover_bad_debt(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        reserve: &Info<Reserve>,
+        liquidity_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [cover_bad_debt(
             solend_program::id(),
             liquidity_amount,
             reserve.pubkey,
-            obligation.pubkey,
+            reserve.account.liquidity.mint_pubkey,
+            reserve.account.liquidity.supply_pubkey,
             self.pubkey,
             lending_market_owner.keypair.pubkey(),
-        ));
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn buy_rate_cap(
+        &self,
+        test: &mut SolendProgramTest,
+        borrow_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+        user: &User,
+    ) -> Result<(), BanksClientError> {
+        let mut instructions = self
+            .build_refresh_instructions(test, obligation, None)
+            .await;
+        instructions.push(buy_rate_cap(
+            solend_program::id(),
+            user.get_account(&borrow_reserve.account.liquidity.mint_pubkey)
+                .unwrap(),
+            borrow_reserve.pubkey,
+            borrow_reserve.account.liquidity.mint_pubkey,
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+            user.keypair.pubkey(),
+        ));
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn claim_rate_cap_credit(
+        &self,
+        test: &mut SolendProgramTest,
+        borrow_reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [claim_rate_cap_credit(
+            solend_program::id(),
+            borrow_reserve.pubkey,
+            borrow_reserve.account.liquidity.mint_pubkey,
+            borrow_reserve.account.liquidity.supply_pubkey,
+            obligation.pubkey,
+            self.pubkey,
+        )];
+
+        test.process_transaction(&instructions, None).await
+    }
+
+    pub async fn forgive_debt(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        lending_market_owner: &User,
+        reserve: &Info<Reserve>,
+        liquidity_amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+
+        let mut instructions = self
+            .build_refresh_instructions(test, &obligation, None)
+            .await;
+
+        instructions.push(forgive_debt(
+            solend_program::id(),
+            liquidity_amount,
+            reserve.pubkey,
+            obligation.pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        ));
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn archive_obligation(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        user: &User,
+        merkle_tree: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [archive_obligation(
+            solend_program::id(),
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+            user.keypair.pubkey(),
+            merkle_tree,
+        )];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn crank_close_empty_obligation(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        cranker: &User,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [crank_close_empty_obligation(
+            solend_program::id(),
+            obligation.pubkey,
+            self.pubkey,
+            obligation.account.owner,
+            cranker.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, None).await
+    }
+
+    pub async fn set_obligation_borrow_lock(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        user: &User,
+        borrows_locked: bool,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_obligation_borrow_lock(
+            solend_program::id(),
+            borrows_locked,
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn set_obligation_borrow_cap(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        user: &User,
+        borrow_cap: u32,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_obligation_borrow_cap(
+            solend_program::id(),
+            borrow_cap,
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn delegate_borrow_authority(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        user: &User,
+        approved_borrower: Pubkey,
+        delegated_value: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [delegate_borrow_authority(
+            solend_program::id(),
+            approved_borrower,
+            delegated_value,
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn set_obligation_referrer(
+        &self,
+        test: &mut SolendProgramTest,
+        obligation: &Info<Obligation>,
+        user: &User,
+        referrer: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_obligation_referrer(
+            solend_program::id(),
+            referrer,
+            obligation.pubkey,
+            self.pubkey,
+            user.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn set_emergency_price(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        signer: &User, // lending market owner or risk authority
+        price: Decimal,
+        max_duration_slots: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_emergency_price(
+            solend_program::id(),
+            price,
+            max_duration_slots,
+            reserve.pubkey,
+            self.pubkey,
+            signer.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&signer.keypair]))
+            .await
+    }
+
+    pub async fn set_reserve_spread_fee_receiver(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        spread_fee_receiver: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_reserve_spread_fee_receiver(
+            solend_program::id(),
+            reserve.pubkey,
+            spread_fee_receiver,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_reserve_tertiary_oracle(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        oracle: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_reserve_tertiary_oracle(
+            solend_program::id(),
+            oracle,
+            reserve.pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_reserve_pair_borrow_weight(
+        &self,
+        test: &mut SolendProgramTest,
+        borrow_reserve: &Info<Reserve>,
+        collateral_reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        borrow_weight_bps: u16,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_reserve_pair_borrow_weight(
+            solend_program::id(),
+            borrow_weight_bps,
+            borrow_reserve.pubkey,
+            collateral_reserve.pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn init_reserve_rate_stats(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        payer: &User,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [init_reserve_rate_stats(
+            solend_program::id(),
+            reserve.pubkey,
+            payer.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&payer.keypair]))
+            .await
+    }
+
+    pub async fn init_reserve_activity(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        payer: &User,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [init_reserve_activity(
+            solend_program::id(),
+            reserve.pubkey,
+            payer.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&payer.keypair]))
+            .await
+    }
+
+    pub async fn refresh_ctoken_price(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        payer: &User,
+    ) -> Result<(), BanksClientError> {
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(2_000_000),
+                refresh_reserve(
+                    solend_program::id(),
+                    reserve.pubkey,
+                    reserve.account.liquidity.pyth_oracle_pubkey,
+                    reserve.account.liquidity.switchboard_oracle_pubkey,
+                    reserve.account.config.extra_oracle_pubkey,
+                ),
+                refresh_ctoken_price(solend_program::id(), reserve.pubkey, payer.keypair.pubkey()),
+            ],
+            Some(&[&payer.keypair]),
+        )
+        .await
+    }
+
+    pub async fn refresh_reserve_with_rate_stats(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+    ) -> Result<(), BanksClientError> {
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(2_000_000),
+                refresh_reserve_with_rate_stats(
+                    solend_program::id(),
+                    reserve.pubkey,
+                    reserve.account.liquidity.pyth_oracle_pubkey,
+                    reserve.account.liquidity.switchboard_oracle_pubkey,
+                    reserve.account.config.extra_oracle_pubkey,
+                ),
+            ],
+            None,
+        )
+        .await
+    }
+
+    pub async fn set_asset_category(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        asset_category: u8,
+        category: AssetCategory,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_asset_category(
+            solend_program::id(),
+            asset_category,
+            category.loan_to_value_ratio,
+            category.liquidation_threshold,
+            category.max_liquidation_threshold,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn refresh_reserve_with_emergency_price(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+    ) -> Result<(), BanksClientError> {
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(2_000_000),
+                refresh_reserve_with_emergency_price(
+                    solend_program::id(),
+                    reserve.pubkey,
+                    reserve.account.liquidity.pyth_oracle_pubkey,
+                    reserve.account.liquidity.switchboard_oracle_pubkey,
+                    reserve.account.config.extra_oracle_pubkey,
+                ),
+            ],
+            None,
+        )
+        .await
+    }
+
+    pub async fn refresh_reserve_with_tertiary_oracle(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        tertiary_oracle: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        test.process_transaction(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(2_000_000),
+                refresh_reserve_with_tertiary_oracle(
+                    solend_program::id(),
+                    reserve.pubkey,
+                    reserve.account.liquidity.pyth_oracle_pubkey,
+                    reserve.account.liquidity.switchboard_oracle_pubkey,
+                    tertiary_oracle,
+                    reserve.account.config.extra_oracle_pubkey,
+                ),
+            ],
+            None,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn set_reward_rule(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        side: RewardSide,
+        emission_per_slot: u64,
+        start_slot: u64,
+        end_slot: u64,
+        reward_supply: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_reward_rule(
+            solend_program::id(),
+            side,
+            emission_per_slot,
+            start_slot,
+            end_slot,
+            reserve.pubkey,
+            self.pubkey,
+            reward_supply,
+            lending_market_owner.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn update_rewards(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        obligation: &Info<Obligation>,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [update_rewards(
+            solend_program::id(),
+            reserve.pubkey,
+            obligation.pubkey,
+        )];
+
+        test.process_transaction(&instructions, None).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn claim_rewards(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        side: RewardSide,
+        obligation: &Info<Obligation>,
+        user: &User,
+        reward_supply: Pubkey,
+        destination: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let (reward_rule, _) =
+            find_reward_rule_address(&solend_program::id(), &reserve.pubkey, side);
+        let instructions = [claim_rewards(
+            solend_program::id(),
+            reward_rule,
+            reserve.pubkey,
+            obligation.pubkey,
+            reward_supply,
+            destination,
+            self.pubkey,
+            user.keypair.pubkey(),
+        )];
+
+        test.process_transaction(&instructions, Some(&[&user.keypair]))
+            .await
+    }
+
+    pub async fn rotate_reserve_collateral_supply(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        new_collateral_supply: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [rotate_reserve_collateral_supply(
+            solend_program::id(),
+            reserve.pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+            reserve.account.collateral.supply_pubkey,
+            new_collateral_supply,
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn rotate_reserve_fee_receiver(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        signer: &User,
+        new_fee_receiver: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [rotate_reserve_fee_receiver(
+            solend_program::id(),
+            reserve.pubkey,
+            self.pubkey,
+            signer.keypair.pubkey(),
+            reserve.account.config.fee_receiver,
+            new_fee_receiver,
+        )];
+
+        test.process_transaction(&instructions, Some(&[&signer.keypair]))
+            .await
+    }
+
+    pub async fn close_reserve(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        destination_liquidity_pubkey: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [close_reserve(
+            solend_program::id(),
+            reserve.pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+            reserve.account.liquidity.supply_pubkey,
+            reserve.account.collateral.supply_pubkey,
+            reserve.account.config.fee_receiver,
+            destination_liquidity_pubkey,
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_default_host_fee_receiver(
+        &self,
+        test: &mut SolendProgramTest,
+        lending_market_owner: &User,
+        default_host_fee_receiver: Option<Pubkey>,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_default_host_fee_receiver(
+            solend_program::id(),
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+            default_host_fee_receiver,
+        )];
+
+        test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
+            .await
+    }
+
+    pub async fn set_reserve_utilization_kill_switch(
+        &self,
+        test: &mut SolendProgramTest,
+        reserve: &Info<Reserve>,
+        lending_market_owner: &User,
+        emergency_utilization_threshold: u8,
+        emergency_utilization_slots: u64,
+    ) -> Result<(), BanksClientError> {
+        let instructions = [set_reserve_utilization_kill_switch(
+            solend_program::id(),
+            emergency_utilization_threshold,
+            emergency_utilization_slots,
+            reserve.pubkey,
+            self.pubkey,
+            lending_market_owner.keypair.pubkey(),
+        )];
 
         test.process_transaction(&instructions, Some(&[&lending_market_owner.keypair]))
             .await
@@ -1745,13 +3273,16 @@ pub async fn setup_world(
 /// LendingMarket
 /// - USDC Reserve
 /// - WSOL Reserve
+///
 /// Obligation
 /// - 100k USDC deposit
 /// - 10 SOL borrowed
+///
 /// no interest has accrued on anything yet, ie:
 /// - cUSDC/USDC = 1
 /// - cSOL/SOL = 1
 /// - Obligation owes _exactly_ 10 SOL
+///
 /// slot is 999, so the next tx that runs will be at slot 1000
 pub async fn scenario_1(
     usdc_reserve_config: &ReserveConfig,
@@ -6,6 +6,8 @@ use helpers::solend_program_test::{SolendProgramTest, User};
 use helpers::*;
 use oracles::{pyth_mainnet, switchboard_v2_mainnet};
 use solana_program::instruction::InstructionError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
 use solana_program_test::*;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
@@ -38,6 +40,16 @@ async fn test_success() {
             rate_limiter: RateLimiter::default(),
             whitelisted_liquidator: None,
             risk_authority: lending_market_owner.keypair.pubkey(),
+            max_obligations_per_owner: 0,
+            dust_borrow_value: 0,
+            obligation_max_outflow: 0,
+            whitelist_required: false,
+            has_liquidator_whitelist: false,
+            fee_authority: Pubkey::default(),
+            treasury: Pubkey::default(),
+            protocol_fee_rate_limiter: RateLimiter::default(),
+            insurance_fee_bps: 0,
+            default_host_fee_receiver: None,
         }
     );
 }
@@ -80,3 +92,42 @@ async fn test_already_initialized() {
         )
     );
 }
+
+#[tokio::test]
+async fn test_invalid_token_program() {
+    let mut test = SolendProgramTest::start_new().await;
+    test.advance_clock_by_slots(1000).await;
+
+    let lending_market_owner = User::new_with_balances(&mut test, &[]).await;
+
+    let keypair = Keypair::new();
+    test.create_account(LendingMarket::LEN, &solend_program::id(), Some(&keypair))
+        .await;
+
+    // neither an account that isn't a program nor a program that isn't the token program
+    for token_program_id in [Keypair::new().pubkey(), pyth_mainnet::id()] {
+        let mut instruction = init_lending_market(
+            solend_program::id(),
+            lending_market_owner.keypair.pubkey(),
+            QUOTE_CURRENCY,
+            keypair.pubkey(),
+            pyth_mainnet::id(),
+            switchboard_v2_mainnet::id(),
+        );
+        instruction.accounts[2].pubkey = token_program_id;
+
+        let res = test
+            .process_transaction(&[instruction], None)
+            .await
+            .unwrap_err()
+            .unwrap();
+
+        assert_eq!(
+            res,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(LendingError::InvalidTokenProgram as u32)
+            )
+        );
+    }
+}
@@ -5,15 +5,24 @@ mod helpers;
 use helpers::solend_program_test::{setup_world, Info, SolendProgramTest, User};
 use helpers::*;
 use solana_program::instruction::InstructionError;
+use solana_program::program_pack::Pack;
+use solana_program::rent::Rent;
 use solana_program_test::*;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::signature::Keypair;
+use solana_sdk::system_instruction::{self, transfer};
 
 use solana_sdk::signer::Signer;
 use solana_sdk::transaction::TransactionError;
 use solend_program::error::LendingError;
-use solend_program::instruction::init_obligation;
+use solend_program::instruction::{
+    init_obligation, init_obligation_with_counter, init_obligation_with_referrer,
+};
 use solend_program::math::Decimal;
-use solend_program::state::{LastUpdate, LendingMarket, Obligation, PROGRAM_VERSION};
+use solend_program::state::{
+    find_obligation_counter_address, LastUpdate, LendingMarket, Obligation, ObligationCounter,
+    RateLimiter, EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS, PROGRAM_VERSION,
+};
 
 async fn setup() -> (SolendProgramTest, Info<LendingMarket>, User) {
     let (test, lending_market, _, _, _, user) =
@@ -52,6 +61,16 @@ async fn test_success() {
             super_unhealthy_borrow_value: Decimal::zero(),
             borrowing_isolated_asset: false,
             closeable: false,
+            borrows_locked: false,
+            borrow_unlock_slot: 0,
+            referrer: None,
+            borrow_cap: 0,
+            unhealthy_since_slot: 0,
+            uses_asset_category_limits: false,
+            oldest_price_slot: 0,
+            rate_limiter: RateLimiter::default(),
+            approved_borrower: None,
+            delegated_value: Decimal::zero(),
         }
     );
 }
@@ -92,3 +111,157 @@ async fn test_already_initialized() {
         )
     );
 }
+
+#[tokio::test]
+async fn test_max_obligations_per_owner() {
+    let (mut test, lending_market, _, _, lending_market_owner, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    lending_market
+        .set_max_obligations_per_owner(&mut test, &lending_market_owner, 1)
+        .await
+        .unwrap();
+
+    // the obligation owner pays for its counter account
+    let instructions = [transfer(
+        &test.context.payer.pubkey(),
+        &user.keypair.pubkey(),
+        LAMPORTS_PER_SOL,
+    )];
+    test.process_transaction(&instructions, None).await.unwrap();
+
+    let mut obligation_pubkeys = Vec::new();
+    for i in 0..2 {
+        let obligation_keypair = Keypair::new();
+        obligation_pubkeys.push(obligation_keypair.pubkey());
+        let instructions = [
+            system_instruction::create_account(
+                &test.context.payer.pubkey(),
+                &obligation_keypair.pubkey(),
+                Rent::default().minimum_balance(Obligation::LEN),
+                Obligation::LEN as u64,
+                &solend_program::id(),
+            ),
+            init_obligation_with_counter(
+                solend_program::id(),
+                obligation_keypair.pubkey(),
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+            ),
+        ];
+        let res = test
+            .process_transaction(&instructions, Some(&[&obligation_keypair, &user.keypair]))
+            .await;
+
+        if i == 0 {
+            res.unwrap();
+        } else {
+            assert_eq!(
+                res.unwrap_err().unwrap(),
+                TransactionError::InstructionError(
+                    1,
+                    InstructionError::Custom(LendingError::ObligationLimitReached as u32)
+                )
+            );
+        }
+    }
+
+    let (obligation_counter_pubkey, _) = find_obligation_counter_address(
+        &solend_program::id(),
+        &lending_market.pubkey,
+        &user.keypair.pubkey(),
+    );
+    let obligation_counter = test
+        .load_account::<ObligationCounter>(obligation_counter_pubkey)
+        .await;
+    assert_eq!(obligation_counter.account.count, 1);
+
+    // closing an obligation frees its slot under the cap
+    test.advance_clock_by_slots(EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS + 1)
+        .await;
+    let obligation = test.load_account::<Obligation>(obligation_pubkeys[0]).await;
+    lending_market
+        .crank_close_empty_obligation(
+            &mut test,
+            &obligation,
+            &User::new_with_keypair(Keypair::new()),
+        )
+        .await
+        .unwrap();
+    let obligation_counter = test
+        .load_account::<ObligationCounter>(obligation_counter_pubkey)
+        .await;
+    assert_eq!(obligation_counter.account.count, 0);
+
+    let obligation_keypair = Keypair::new();
+    let instructions = [
+        system_instruction::create_account(
+            &test.context.payer.pubkey(),
+            &obligation_keypair.pubkey(),
+            Rent::default().minimum_balance(Obligation::LEN),
+            Obligation::LEN as u64,
+            &solend_program::id(),
+        ),
+        init_obligation_with_counter(
+            solend_program::id(),
+            obligation_keypair.pubkey(),
+            lending_market.pubkey,
+            user.keypair.pubkey(),
+        ),
+    ];
+    test.process_transaction(&instructions, Some(&[&obligation_keypair, &user.keypair]))
+        .await
+        .unwrap();
+
+    // without the counter account the instruction can't be checked against the cap
+    let err = lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(2, InstructionError::NotEnoughAccountKeys)
+    );
+
+    lending_market
+        .set_max_obligations_per_owner(&mut test, &lending_market_owner, 0)
+        .await
+        .unwrap();
+    lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_success_with_referrer() {
+    let (mut test, lending_market, user) = setup().await;
+
+    let referrer = Keypair::new().pubkey();
+    let obligation_keypair = Keypair::new();
+    let instructions = [
+        system_instruction::create_account(
+            &test.context.payer.pubkey(),
+            &obligation_keypair.pubkey(),
+            Rent::default().minimum_balance(Obligation::LEN),
+            Obligation::LEN as u64,
+            &solend_program::id(),
+        ),
+        init_obligation_with_referrer(
+            solend_program::id(),
+            referrer,
+            obligation_keypair.pubkey(),
+            lending_market.pubkey,
+            user.keypair.pubkey(),
+        ),
+    ];
+    test.process_transaction(&instructions, Some(&[&obligation_keypair, &user.keypair]))
+        .await
+        .unwrap();
+
+    let obligation = test
+        .load_account::<Obligation>(obligation_keypair.pubkey())
+        .await;
+    assert_eq!(obligation.account.referrer, Some(referrer));
+}
@@ -31,12 +31,13 @@ use solend_program::state::RateLimiter;
 use solend_program::state::Reserve;
 use solend_program::state::ReserveCollateral;
 use solend_program::state::ReserveLiquidity;
+use solend_program::state::ReserveRewards;
 use solend_program::state::PROGRAM_VERSION;
 use solend_program::NULL_PUBKEY;
 
 use solend_program::{
     error::LendingError,
-    instruction::init_reserve,
+    instruction::{init_reserve, init_reserve_with_collateral_decimals},
     math::Decimal,
     state::{RateLimiterConfig, ReserveConfig, ReserveFees},
 };
@@ -151,6 +152,16 @@ async fn test_success() {
 
     // check program state
     let wsol_reserve = test.load_account::<Reserve>(reserve_pubkey).await;
+    let pyth_price_account = test
+        .context
+        .banks_client
+        .get_account(oracle.pyth_price_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let pyth_publish_time = pyth_sdk_solana::state::load_price_account(&pyth_price_account.data)
+        .unwrap()
+        .timestamp;
     assert_eq!(
         wsol_reserve.account,
         Reserve {
@@ -172,20 +183,104 @@ async fn test_success() {
                 accumulated_protocol_fees_wads: Decimal::zero(),
                 market_price: Decimal::from(10u64),
                 smoothed_market_price: Decimal::from(10u64),
+                price_slot: 1001,
+                price_publish_time: pyth_publish_time,
                 extra_market_price: None
             },
             collateral: ReserveCollateral {
                 mint_pubkey: reserve_collateral_mint_pubkey,
                 mint_total_supply: 1000,
                 supply_pubkey: reserve_collateral_supply_pubkey,
+                decimals_offset: 0,
             },
             config: reserve_config,
             rate_limiter: RateLimiter::new(RateLimiterConfig::default(), 1001),
             attributed_borrow_value: Decimal::zero(),
+            emergency_utilization_threshold: 0,
+            emergency_utilization_slots: 0,
+            high_utilization_slots: 0,
+            emergency_mode: false,
+            emergency_price_active: false,
+            tertiary_oracle_pubkey: None,
+            has_pair_borrow_weights: false,
+            has_insurance_fund: false,
+            has_spread_fee_receiver: false,
+            deposit_rewards: ReserveRewards::default(),
+            borrow_rewards: ReserveRewards::default(),
+            insurance_fund_bump_seed: 0,
         }
     );
 }
 
+#[tokio::test]
+async fn test_success_collateral_decimals() {
+    let (mut test, lending_market, lending_market_owner) = setup().await;
+
+    let reserve_keypair = Keypair::new();
+    let destination_collateral_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_liquidity_supply_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_pubkey = test
+        .create_account(Reserve::LEN, &solend_program::id(), Some(&reserve_keypair))
+        .await;
+    let reserve_liquidity_fee_receiver = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_collateral_mint_pubkey =
+        test.create_account(Mint::LEN, &spl_token::id(), None).await;
+    let reserve_collateral_supply_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+
+    let oracle = test.mints.get(&wsol_mint::id()).unwrap().unwrap();
+    let reserve_config = ReserveConfig {
+        fee_receiver: reserve_liquidity_fee_receiver,
+        ..test_reserve_config()
+    };
+
+    test.process_transaction(
+        &[init_reserve_with_collateral_decimals(
+            solend_program::id(),
+            LAMPORTS_TO_SOL,
+            reserve_config,
+            lending_market_owner.get_account(&wsol_mint::id()).unwrap(),
+            destination_collateral_pubkey,
+            reserve_pubkey,
+            wsol_mint::id(),
+            reserve_liquidity_supply_pubkey,
+            reserve_collateral_mint_pubkey,
+            reserve_collateral_supply_pubkey,
+            oracle.pyth_product_pubkey,
+            oracle.pyth_price_pubkey,
+            Pubkey::from_str("nu11111111111111111111111111111111111111111").unwrap(),
+            lending_market.pubkey,
+            lending_market_owner.keypair.pubkey(),
+            lending_market_owner.keypair.pubkey(),
+            Some(6),
+        )],
+        Some(&[&lending_market_owner.keypair]),
+    )
+    .await
+    .unwrap();
+
+    // one whole SOL still mints one whole cToken
+    let collateral_mint = test
+        .load_account::<Mint>(reserve_collateral_mint_pubkey)
+        .await;
+    assert_eq!(collateral_mint.account.decimals, 6);
+    let destination_collateral = test
+        .load_account::<Token>(destination_collateral_pubkey)
+        .await;
+    assert_eq!(destination_collateral.account.amount, 1_000_000);
+
+    let reserve = test.load_account::<Reserve>(reserve_pubkey).await;
+    assert_eq!(reserve.account.collateral.decimals_offset, -3);
+    assert_eq!(reserve.account.collateral.mint_total_supply, 1_000_000);
+}
+
 #[tokio::test]
 async fn test_init_reserve_null_oracles() {
     let (mut test, lending_market, lending_market_owner) = setup().await;
@@ -217,6 +312,69 @@ async fn test_init_reserve_null_oracles() {
     );
 }
 
+#[tokio::test]
+async fn test_invalid_token_account_owner() {
+    let (mut test, lending_market, lending_market_owner) = setup().await;
+
+    let destination_collateral_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    // not owned by the token program
+    let reserve_liquidity_supply_pubkey = test
+        .create_account(Token::LEN, &solana_program::system_program::id(), None)
+        .await;
+    let reserve_pubkey = test
+        .create_account(Reserve::LEN, &solend_program::id(), None)
+        .await;
+    let reserve_liquidity_fee_receiver = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+    let reserve_collateral_mint_pubkey =
+        test.create_account(Mint::LEN, &spl_token::id(), None).await;
+    let reserve_collateral_supply_pubkey = test
+        .create_account(Token::LEN, &spl_token::id(), None)
+        .await;
+
+    let oracle = test.mints.get(&wsol_mint::id()).unwrap().unwrap();
+
+    let res = test
+        .process_transaction(
+            &[init_reserve(
+                solend_program::id(),
+                1000,
+                ReserveConfig {
+                    fee_receiver: reserve_liquidity_fee_receiver,
+                    ..test_reserve_config()
+                },
+                lending_market_owner.get_account(&wsol_mint::id()).unwrap(),
+                destination_collateral_pubkey,
+                reserve_pubkey,
+                wsol_mint::id(),
+                reserve_liquidity_supply_pubkey,
+                reserve_collateral_mint_pubkey,
+                reserve_collateral_supply_pubkey,
+                oracle.pyth_product_pubkey,
+                oracle.pyth_price_pubkey,
+                NULL_PUBKEY,
+                lending_market.pubkey,
+                lending_market_owner.keypair.pubkey(),
+                lending_market_owner.keypair.pubkey(),
+            )],
+            Some(&[&lending_market_owner.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidTokenOwner as u32)
+        )
+    );
+}
+
 #[tokio::test]
 async fn test_already_initialized() {
     let (mut test, lending_market, lending_market_owner) = setup().await;
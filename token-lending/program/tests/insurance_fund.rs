@@ -0,0 +1,201 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::PriceArgs;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::{redeem_fees, refresh_reserve};
+use solend_program::state::{
+    find_insurance_fund_address, LendingMarket, Reserve, ReserveConfig, SLOTS_PER_YEAR,
+};
+use spl_token::state::Account as Token;
+
+#[tokio::test]
+async fn test_insurance_fund() {
+    let (mut test, lending_market, _, wsol_reserve, user, _, lending_market_owner) = scenario_1(
+        &test_reserve_config(),
+        &ReserveConfig {
+            protocol_take_rate: 10,
+            ..test_reserve_config()
+        },
+    )
+    .await;
+
+    test.advance_clock_by_slots(SLOTS_PER_YEAR).await;
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    // deposit some liquidity so the fees can actually be redeemed
+    lending_market
+        .deposit(&mut test, &wsol_reserve, &user, LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+
+    let res = lending_market
+        .set_insurance_fee(&mut test, &lending_market_owner, 10_001)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidConfig as u32)
+        )
+    );
+
+    lending_market
+        .set_insurance_fee(&mut test, &lending_market_owner, 2_000)
+        .await
+        .unwrap();
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert_eq!(lending_market_post.account.insurance_fee_bps, 2_000);
+
+    lending_market
+        .init_insurance_fund(&mut test, &lending_market_owner, &wsol_reserve)
+        .await
+        .unwrap();
+    let (insurance_fund_pubkey, insurance_fund_bump_seed) = find_insurance_fund_address(
+        &solend_program::id(),
+        &lending_market.pubkey,
+        &wsol_mint::id(),
+    );
+    let insurance_fund = test.load_account::<Token>(insurance_fund_pubkey).await;
+    assert_eq!(insurance_fund.account.mint, wsol_mint::id());
+    let (lending_market_authority, _) =
+        Pubkey::find_program_address(&[lending_market.pubkey.as_ref()], &solend_program::id());
+    assert_eq!(insurance_fund.account.owner, lending_market_authority);
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(wsol_reserve.account.has_insurance_fund);
+    assert_eq!(
+        wsol_reserve.account.insurance_fund_bump_seed,
+        insurance_fund_bump_seed
+    );
+
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .init_insurance_fund(&mut test, &lending_market_owner, &wsol_reserve)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::AlreadyInitialized as u32)
+        )
+    );
+
+    // the insurance fund can't be skipped when redeeming the reserve's fees
+    let res = test
+        .process_transaction(
+            &[
+                refresh_reserve(
+                    solend_program::id(),
+                    wsol_reserve.pubkey,
+                    wsol_reserve.account.liquidity.pyth_oracle_pubkey,
+                    wsol_reserve.account.liquidity.switchboard_oracle_pubkey,
+                    wsol_reserve.account.config.extra_oracle_pubkey,
+                ),
+                redeem_fees(
+                    solend_program::id(),
+                    wsol_reserve.pubkey,
+                    wsol_reserve.account.config.fee_receiver,
+                    wsol_reserve.account.liquidity.supply_pubkey,
+                    lending_market.pubkey,
+                ),
+            ],
+            None,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(1, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // the insurance share is taken out of the redeemed spread
+    let fee_receiver_balance = test
+        .load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+        .await
+        .account
+        .amount;
+    let spread = wsol_reserve.account.calculate_redeem_fees().unwrap();
+    assert!(spread > 0);
+    lending_market
+        .redeem_fees(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let insurance_amount = test
+        .load_account::<Token>(insurance_fund_pubkey)
+        .await
+        .account
+        .amount;
+    let fee_receiver_amount = test
+        .load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+        .await
+        .account
+        .amount
+        - fee_receiver_balance;
+    let redeemed = insurance_amount + fee_receiver_amount;
+    assert!(redeemed >= spread);
+    assert_eq!(insurance_amount, redeemed * 2_000 / 10_000);
+
+    // only the lending market owner can spend the fund
+    let res = lending_market
+        .cover_bad_debt(&mut test, &user, &wsol_reserve, insurance_amount)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    lending_market
+        .cover_bad_debt(
+            &mut test,
+            &lending_market_owner,
+            &wsol_reserve,
+            insurance_amount,
+        )
+        .await
+        .unwrap();
+
+    let insurance_fund = test.load_account::<Token>(insurance_fund_pubkey).await;
+    assert_eq!(insurance_fund.account.amount, 0);
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.available_amount,
+        wsol_reserve.account.liquidity.available_amount + insurance_amount
+    );
+}
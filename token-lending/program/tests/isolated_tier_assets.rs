@@ -113,6 +113,7 @@ async fn test_refresh_obligation() {
                 slot: 1001,
                 stale: false
             },
+            oldest_price_slot: 1001,
             deposits: vec![ObligationCollateral {
                 attributed_borrow_value: Decimal::from(10u64),
                 ..obligations[0].account.deposits[0]
@@ -122,6 +123,7 @@ async fn test_refresh_obligation() {
                 cumulative_borrow_rate_wads: Decimal::one(),
                 borrowed_amount_wads: Decimal::from(LAMPORTS_PER_SOL),
                 market_value: Decimal::from(10u64),
+                ..ObligationLiquidity::default()
             }],
             borrowed_value: Decimal::from(10u64),
             unweighted_borrowed_value: Decimal::from(10u64),
@@ -490,3 +492,173 @@ async fn invalid_borrow_due_to_reserve_config_change() {
         )
     );
 }
+
+#[tokio::test]
+async fn deposit_isolated_asset_as_collateral_invalid() {
+    let (mut test, lending_market, reserves, obligations, mut users, _) = custom_scenario(
+        &[
+            ReserveArgs {
+                mint: usdc_mint::id(),
+                config: test_reserve_config(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 1,
+                    conf: 0,
+                    expo: 0,
+                    ema_price: 1,
+                    ema_conf: 0,
+                },
+            },
+            ReserveArgs {
+                mint: bonk_mint::id(),
+                config: ReserveConfig {
+                    loan_to_value_ratio: 0,
+                    liquidation_threshold: 0,
+                    fees: ReserveFees::default(),
+                    optimal_borrow_rate: 0,
+                    max_borrow_rate: 0,
+                    protocol_liquidation_fee: 0,
+                    reserve_type: ReserveType::Isolated,
+                    ..test_reserve_config()
+                },
+                liquidity_amount: 1_000_000,
+                price: PriceArgs {
+                    price: 1,
+                    conf: 0,
+                    expo: -6,
+                    ema_price: 1,
+                    ema_conf: 0,
+                },
+            },
+        ],
+        &[ObligationArgs {
+            deposits: vec![(usdc_mint::id(), 100 * FRACTIONAL_TO_USDC)],
+            borrows: vec![],
+        }],
+    )
+    .await;
+
+    let bonk_reserve = reserves
+        .iter()
+        .find(|r| r.account.liquidity.mint_pubkey == bonk_mint::id())
+        .unwrap();
+    users[0]
+        .create_token_account(&bonk_reserve.account.collateral.mint_pubkey, &mut test)
+        .await;
+    test.mint_to(
+        &bonk_mint::id(),
+        &users[0].get_account(&bonk_mint::id()).unwrap(),
+        1_000,
+    )
+    .await;
+
+    let err = lending_market
+        .deposit_reserve_liquidity_and_obligation_collateral(
+            &mut test,
+            bonk_reserve,
+            &obligations[0],
+            &users[0],
+            1_000,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::IsolatedTierAssetViolation as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn refresh_obligation_with_isolated_collateral() {
+    let (mut test, lending_market, reserves, obligations, _, lending_market_owner) =
+        custom_scenario(
+            &[
+                ReserveArgs {
+                    mint: usdc_mint::id(),
+                    config: test_reserve_config(),
+                    liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                    price: PriceArgs {
+                        price: 1,
+                        conf: 0,
+                        expo: 0,
+                        ema_price: 1,
+                        ema_conf: 0,
+                    },
+                },
+                ReserveArgs {
+                    mint: wsol_mint::id(),
+                    config: ReserveConfig {
+                        loan_to_value_ratio: 0,
+                        liquidation_threshold: 0,
+                        fees: ReserveFees::default(),
+                        protocol_liquidation_fee: 0,
+                        reserve_type: ReserveType::Regular, // regular for now
+                        ..test_reserve_config()
+                    },
+                    liquidity_amount: 100 * LAMPORTS_PER_SOL,
+                    price: PriceArgs {
+                        price: 10,
+                        conf: 0,
+                        expo: 0,
+                        ema_price: 10,
+                        ema_conf: 0,
+                    },
+                },
+            ],
+            &[ObligationArgs {
+                deposits: vec![
+                    (usdc_mint::id(), 100 * FRACTIONAL_TO_USDC),
+                    (wsol_mint::id(), LAMPORTS_PER_SOL),
+                ],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    let wsol_reserve = reserves
+        .iter()
+        .find(|r| r.account.liquidity.mint_pubkey == wsol_mint::id())
+        .unwrap();
+
+    lending_market
+        .update_reserve_config(
+            &mut test,
+            &lending_market_owner,
+            wsol_reserve,
+            ReserveConfig {
+                reserve_type: ReserveType::Isolated,
+                ..wsol_reserve.account.config
+            },
+            wsol_reserve.account.rate_limiter.config,
+            None,
+        )
+        .await
+        .unwrap();
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .refresh_obligation(&mut test, &obligations[0])
+        .await
+        .unwrap();
+
+    // the wsol collateral still counts towards the deposited value, but only the usdc collateral
+    // counts towards the liquidation thresholds
+    let obligation = test.load_account::<Obligation>(obligations[0].pubkey).await;
+    assert_eq!(obligation.account.deposited_value, Decimal::from(110u64));
+    assert_eq!(
+        obligation.account.allowed_borrow_value,
+        Decimal::from(50u64)
+    );
+    assert_eq!(
+        obligation.account.unhealthy_borrow_value,
+        Decimal::from(55u64)
+    );
+    assert_eq!(
+        obligation.account.super_unhealthy_borrow_value,
+        Decimal::from(65u64)
+    );
+}
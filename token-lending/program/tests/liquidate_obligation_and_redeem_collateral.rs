@@ -13,10 +13,12 @@ use solend_program::math::TrySub;
 use solend_program::state::LastUpdate;
 use solend_program::state::ObligationCollateral;
 use solend_program::state::ObligationLiquidity;
+use solend_program::state::PositionRewards;
 use solend_program::state::ReserveConfig;
 use solend_program::state::ReserveFees;
 use solend_sdk::state::Bonus;
 use solend_sdk::NULL_PUBKEY;
+use spl_token::state::Account as Token;
 mod helpers;
 
 use crate::solend_program_test::scenario_1;
@@ -218,7 +220,8 @@ async fn test_success_new() {
                 deposited_amount: (100_000 - expected_usdc_withdrawn) * FRACTIONAL_TO_USDC,
                 market_value: Decimal::from(100_000u64), // old value
                 attributed_borrow_value: obligation_post.account.deposits[0]
-                    .attributed_borrow_value, // don't care about verifying this here
+                    .attributed_borrow_value, // don't care about verifying this here,
+                rewards: PositionRewards::default(),
             }]
             .to_vec(),
             borrows: [ObligationLiquidity {
@@ -228,6 +231,7 @@ async fn test_success_new() {
                     .try_sub(Decimal::from(expected_borrow_repaid * LAMPORTS_TO_SOL))
                     .unwrap(),
                 market_value: Decimal::from(55_000u64),
+                ..ObligationLiquidity::default()
             }]
             .to_vec(),
             deposited_value: Decimal::from(100_000u64),
@@ -236,6 +240,7 @@ async fn test_success_new() {
             borrowed_value_upper_bound: Decimal::from(55_000u64),
             allowed_borrow_value: Decimal::from(50_000u64),
             unhealthy_borrow_value: Decimal::from(55_000u64),
+            unhealthy_since_slot: 1000,
             ..obligation.account
         }
     );
@@ -340,6 +345,367 @@ async fn test_whitelisting_liquidator() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn test_protected_liquidation_window() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        _user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let rando_liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_TO_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+
+    lending_market
+        .set_liquidator_whitelist(
+            &mut test,
+            &lending_market_owner,
+            100,
+            vec![Keypair::new().pubkey()],
+        )
+        .await
+        .unwrap();
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert!(lending_market_post.account.has_liquidator_whitelist);
+
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 5500,
+            conf: 0,
+            expo: 0,
+            ema_price: 5500,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    // only whitelisted liquidators can liquidate while the obligation is newly unhealthy
+    let err = lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &rando_liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::NotWhitelistedLiquidator as u32)
+        )
+    );
+
+    test.advance_clock_by_slots(100).await;
+
+    lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &rando_liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_liquidation_auction() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _user, obligation, _) = scenario_1(
+        &ReserveConfig {
+            protocol_liquidation_fee: 0,
+            liquidation_auction: true,
+            ..test_reserve_config()
+        },
+        &test_reserve_config(),
+    )
+    .await;
+
+    let liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_TO_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 5500,
+            conf: 0,
+            expo: 0,
+            ema_price: 5500,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    // liquidated in the slot it became unhealthy, so the auction hasn't raised the bonus yet
+    lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    // no bonus, so the collateral is worth the repaid liquidity less what's lost to rounding down
+    let expected_borrow_repaid = 10 * (LIQUIDATION_CLOSE_FACTOR as u64) / 100;
+    assert_eq!(
+        liquidator.get_balance(&mut test, &usdc_mint::id()).await,
+        Some(expected_borrow_repaid * 5500 * FRACTIONAL_TO_USDC - 1)
+    );
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.unhealthy_since_slot, 1000);
+}
+
+#[tokio::test]
+async fn test_protocol_liquidation_fee_in_liquidity() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _user, obligation, _) = scenario_1(
+        &ReserveConfig {
+            protocol_liquidation_fee: 10,
+            protocol_liquidation_fee_in_liquidity: true,
+            liquidation_auction: true,
+            ..test_reserve_config()
+        },
+        &test_reserve_config(),
+    )
+    .await;
+
+    let liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_TO_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 5500,
+            conf: 0,
+            expo: 0,
+            ema_price: 5500,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    let usdc_fee_receiver = usdc_reserve.account.config.fee_receiver;
+    let wsol_fee_receiver = wsol_reserve.account.config.fee_receiver;
+    let usdc_fees_pre = test.load_account::<Token>(usdc_fee_receiver).await;
+    let wsol_fees_pre = test.load_account::<Token>(wsol_fee_receiver).await;
+
+    // the auction hasn't raised the bonus yet, so the protocol only earns through the fee paid in
+    // the repaid liquidity
+    lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    let usdc_fees_post = test.load_account::<Token>(usdc_fee_receiver).await;
+    let wsol_fees_post = test.load_account::<Token>(wsol_fee_receiver).await;
+    assert_eq!(usdc_fees_post.account.amount, usdc_fees_pre.account.amount);
+
+    let protocol_fee = wsol_fees_post.account.amount - wsol_fees_pre.account.amount;
+    let wsol_spent = 100 * LAMPORTS_TO_SOL
+        - liquidator
+            .get_balance(&mut test, &wsol_mint::id())
+            .await
+            .unwrap();
+    let repay_amount = wsol_spent - protocol_fee;
+    assert!(repay_amount > 0);
+    assert_eq!(protocol_fee, repay_amount.div_ceil(100));
+}
+
+#[tokio::test]
+async fn test_liquidation_price_band() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _user, obligation, _) = scenario_1(
+        &ReserveConfig {
+            liquidation_price_band_bps: 500,
+            ..test_reserve_config()
+        },
+        &test_reserve_config(),
+    )
+    .await;
+
+    let liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_TO_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 5500,
+            conf: 0,
+            expo: 0,
+            ema_price: 5500,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    // usdc spot price is 10% below its ema, outside of the 5% band
+    test.set_price(
+        &usdc_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: -1,
+            ema_price: 11,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    let err = lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::PriceOutsideLiquidationBand as u32)
+        )
+    );
+
+    test.advance_clock_by_slots(1).await;
+    test.set_price(
+        &usdc_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: -1,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_liquidate_dust_borrow_in_full() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        _user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // the 10 SOL borrow is worth $55k at this price, under the dust threshold
+    lending_market
+        .set_dust_borrow_value(&mut test, &lending_market_owner, 60_000)
+        .await
+        .unwrap();
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 5500,
+            conf: 0,
+            expo: 0,
+            ema_price: 5500,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    let liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_TO_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+
+    lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    // the close factor would have left most of the borrow in place
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert!(obligation_post.account.borrows.is_empty());
+}
+
 #[tokio::test]
 async fn test_success_insufficient_liquidity() {
     let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation, _) = scenario_1(
@@ -434,8 +800,8 @@ async fn test_success_insufficient_liquidity() {
 
     let available_amount = usdc_reserve.account.liquidity.available_amount / FRACTIONAL_TO_USDC;
 
-    lending_market
-        .liquidate_obligation_and_redeem_reserve_collateral(
+    let (redeemed_collateral, received_liquidity, unredeemed_collateral) = lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral_with_return_data(
             &mut test,
             &wsol_reserve,
             &usdc_reserve,
@@ -524,6 +890,17 @@ async fn test_success_insufficient_liquidity() {
             diff: -((available_amount * FRACTIONAL_TO_USDC) as i128)
         }])
     );
+
+    // the return data matches the redeemed and unredeemed split
+    assert_eq!(redeemed_collateral, available_amount * FRACTIONAL_TO_USDC);
+    assert_eq!(
+        received_liquidity,
+        available_amount * FRACTIONAL_TO_USDC - expected_protocol_liquidation_fee
+    );
+    assert_eq!(
+        unredeemed_collateral,
+        expected_cusdc_withdrawn * FRACTIONAL_TO_USDC
+    );
 }
 
 #[tokio::test]
@@ -137,6 +137,7 @@ async fn test_mark_obligation_as_closeable_success() {
                 slot: 1002,
                 stale: false
             },
+            oldest_price_slot: 1002,
             closeable: true,
             ..obligations[0].account.clone()
         }
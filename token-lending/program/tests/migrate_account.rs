@@ -0,0 +1,272 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::PriceArgs;
+use crate::solend_program_test::SolendProgramTest;
+use crate::solend_program_test::User;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::{migrate_account, migrate_obligation};
+use solend_program::math::Decimal;
+use solend_program::state::{
+    LendingMarket, Obligation, RateLimiter, Reserve, ReserveLiquidity, LENDING_MARKET_LEN_V1,
+    OBLIGATION_LEN_V1, PROGRAM_VERSION, RATE_LIMITER_LEN, RESERVE_LEN_V1,
+};
+
+// rewrites the account as if it had been created by the version 1 program
+async fn downgrade(test: &mut SolendProgramTest, pubkey: Pubkey, len: usize) {
+    let mut account = test
+        .context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    account.data.truncate(len);
+    account.data[0] = 1;
+    test.context.set_account(&pubkey, &account.into());
+}
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, usdc_reserve, _, _, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    downgrade(&mut test, lending_market.pubkey, LENDING_MARKET_LEN_V1).await;
+    downgrade(&mut test, usdc_reserve.pubkey, RESERVE_LEN_V1).await;
+    test.advance_clock_by_slots(1).await;
+
+    // version 1 accounts keep working in their old layout until they're migrated
+    lending_market
+        .refresh_reserve(&mut test, &usdc_reserve)
+        .await
+        .unwrap();
+    let v1_reserve = test
+        .context
+        .banks_client
+        .get_account(usdc_reserve.pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(v1_reserve.data.len(), RESERVE_LEN_V1);
+    test.advance_clock_by_slots(1).await;
+
+    let payer = test.context.payer.pubkey();
+    test.process_transaction(
+        &[
+            migrate_account(solend_program::id(), lending_market.pubkey, payer),
+            migrate_account(solend_program::id(), usdc_reserve.pubkey, payer),
+        ],
+        None,
+    )
+    .await
+    .unwrap();
+
+    let migrated_lending_market = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert_eq!(
+        migrated_lending_market.account,
+        LendingMarket {
+            version: PROGRAM_VERSION,
+            max_obligations_per_owner: 0,
+            dust_borrow_value: 0,
+            obligation_max_outflow: 0,
+            whitelist_required: false,
+            has_liquidator_whitelist: false,
+            protocol_fee_rate_limiter: RateLimiter::unpack_from_slice(&[0; RATE_LIMITER_LEN])
+                .unwrap(),
+            ..lending_market.account
+        }
+    );
+
+    let migrated_reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert_eq!(migrated_reserve.account.version, PROGRAM_VERSION);
+    assert_eq!(
+        migrated_reserve.account.liquidity,
+        ReserveLiquidity {
+            price_slot: 0,
+            price_publish_time: 0,
+            ..usdc_reserve.account.liquidity
+        }
+    );
+    assert_eq!(
+        migrated_reserve.account.collateral,
+        usdc_reserve.account.collateral
+    );
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .refresh_reserve(&mut test, &usdc_reserve)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_success_obligation() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // rewards accrued on the reserves before the obligation is migrated aren't owed to it
+    let mut reserve = usdc_reserve.account.clone();
+    reserve.deposit_rewards.reward_index = Decimal::from(3u64);
+    let mut account = test
+        .context
+        .banks_client
+        .get_account(usdc_reserve.pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    Reserve::pack(reserve, &mut account.data).unwrap();
+    test.context
+        .set_account(&usdc_reserve.pubkey, &account.into());
+
+    downgrade(&mut test, obligation.pubkey, OBLIGATION_LEN_V1).await;
+    test.advance_clock_by_slots(1).await;
+
+    let payer = test.context.payer.pubkey();
+    test.process_transaction(
+        &[migrate_obligation(
+            solend_program::id(),
+            obligation.pubkey,
+            payer,
+            vec![usdc_reserve.pubkey, wsol_reserve.pubkey],
+        )],
+        None,
+    )
+    .await
+    .unwrap();
+
+    let migrated_obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(migrated_obligation.account.version, PROGRAM_VERSION);
+    assert_eq!(
+        migrated_obligation.account.deposits[0].rewards.reward_index,
+        Decimal::from(3u64)
+    );
+    assert_eq!(
+        migrated_obligation.account.borrows[0].rewards.reward_index,
+        Decimal::zero()
+    );
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .refresh_obligation(&mut test, &obligation)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_success_liquidate_v1_obligation() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, _, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    downgrade(&mut test, lending_market.pubkey, LENDING_MARKET_LEN_V1).await;
+    downgrade(&mut test, usdc_reserve.pubkey, RESERVE_LEN_V1).await;
+    downgrade(&mut test, wsol_reserve.pubkey, RESERVE_LEN_V1).await;
+    downgrade(&mut test, obligation.pubkey, OBLIGATION_LEN_V1).await;
+
+    let liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_TO_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+
+    // close LTV is 0.55, we've deposited 100k USDC and borrowed 10 SOL
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 5500,
+            conf: 0,
+            expo: 0,
+            ema_price: 5500,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    lending_market
+        .liquidate_obligation_and_redeem_reserve_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &liquidator,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    let liquidated_obligation = test
+        .context
+        .banks_client
+        .get_account(obligation.pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(liquidated_obligation.data.len(), OBLIGATION_LEN_V1);
+    let liquidated_obligation = Obligation::unpack_account(&liquidated_obligation.data).unwrap();
+    assert!(
+        liquidated_obligation.borrows[0].borrowed_amount_wads
+            < obligation.account.borrows[0].borrowed_amount_wads
+    );
+}
+
+#[tokio::test]
+async fn test_fail_v1_account_needs_migration_for_new_fields() {
+    let (mut test, lending_market, _, _, _, _, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    downgrade(&mut test, lending_market.pubkey, LENDING_MARKET_LEN_V1).await;
+
+    let res = lending_market
+        .set_dust_borrow_value(&mut test, &lending_market_owner, 5)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::AccountNotMigrated as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fail_already_migrated() {
+    let (mut test, _, usdc_reserve, _, _, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let payer = test.context.payer.pubkey();
+    let res = test
+        .process_transaction(
+            &[migrate_account(
+                solend_program::id(),
+                usdc_reserve.pubkey,
+                payer,
+            )],
+            None,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use helpers::solend_program_test::scenario_1;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::{LendingMarket, Obligation, RateLimiterConfig};
+
+#[tokio::test]
+async fn test_obligation_rate_limiter() {
+    let (mut test, lending_market, usdc_reserve, _, user, obligation, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    lending_market
+        .set_lending_market_owner_and_config(
+            &mut test,
+            &lending_market_owner,
+            &lending_market_owner.keypair.pubkey(),
+            RateLimiterConfig {
+                window_duration: 20,
+                max_outflow: u64::MAX,
+            },
+            None,
+            lending_market_owner.keypair.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    // $10 per window
+    lending_market
+        .set_obligation_max_outflow(&mut test, &lending_market_owner, 10)
+        .await
+        .unwrap();
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert_eq!(lending_market_post.account.obligation_max_outflow, 10);
+
+    // a max withdraw is capped at the obligation's remaining outflow
+    let balance_before = user.get_balance(&mut test, &usdc_mint::id()).await.unwrap();
+    lending_market
+        .withdraw_obligation_collateral_and_redeem_reserve_collateral(
+            &mut test,
+            &usdc_reserve,
+            &obligation,
+            &user,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+    let balance_after = user.get_balance(&mut test, &usdc_mint::id()).await.unwrap();
+    assert_eq!(balance_after - balance_before, 10_000_000);
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(
+        obligation_post.account.rate_limiter.config,
+        RateLimiterConfig {
+            window_duration: 20,
+            max_outflow: 10,
+        }
+    );
+
+    // nothing left in this window
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .withdraw_obligation_collateral_and_redeem_reserve_collateral(
+            &mut test,
+            &usdc_reserve,
+            &obligation,
+            &user,
+            1_000_000,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::WithdrawTooLarge as u32)
+        )
+    );
+
+    // the outflow frees up once the window has passed
+    test.advance_clock_by_slots(40).await;
+    lending_market
+        .withdraw_obligation_collateral_and_redeem_reserve_collateral(
+            &mut test,
+            &usdc_reserve,
+            &obligation,
+            &user,
+            1_000_000,
+        )
+        .await
+        .unwrap();
+}
@@ -11,6 +11,7 @@ mod helpers;
 use helpers::solend_program_test::{setup_world, Info, SolendProgramTest, User};
 use solend_sdk::error::LendingError;
 
+use solend_sdk::math::Decimal;
 use solend_sdk::state::{LendingMarket, RateLimiterConfig, Reserve, ReserveConfig};
 
 use helpers::*;
@@ -213,3 +214,100 @@ async fn test_outflow_reserve() {
         test.advance_clock_by_slots(1).await;
     }
 }
+
+#[tokio::test]
+async fn test_reset_rate_limiter() {
+    let (
+        mut test,
+        lending_market,
+        _usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+        host_fee_receiver,
+        lending_market_owner,
+        _wsol_depositor,
+    ) = setup(&ReserveConfig {
+        ..test_reserve_config()
+    })
+    .await;
+
+    lending_market
+        .set_lending_market_owner_and_config(
+            &mut test,
+            &lending_market_owner,
+            &lending_market_owner.keypair.pubkey(),
+            RateLimiterConfig {
+                window_duration: 10,
+                max_outflow: 10,
+            },
+            None,
+            lending_market.account.risk_authority,
+        )
+        .await
+        .unwrap();
+
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    // only the owner can reset
+    let res = lending_market
+        .reset_rate_limiter(&mut test, &user, &[&wsol_reserve])
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    lending_market
+        .reset_rate_limiter(&mut test, &lending_market_owner, &[&wsol_reserve])
+        .await
+        .unwrap();
+
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    let cur_slot = test.get_clock().await.slot;
+    assert_eq!(
+        lending_market_post
+            .account
+            .rate_limiter
+            .max_outflow_remaining(cur_slot),
+        Ok(Decimal::from(10u64))
+    );
+    assert_eq!(
+        lending_market_post.account.rate_limiter.config,
+        RateLimiterConfig {
+            window_duration: 10,
+            max_outflow: 10,
+        }
+    );
+
+    // the outflow frees up within the same window
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            host_fee_receiver.get_account(&wsol_mint::id()),
+            1,
+        )
+        .await
+        .unwrap();
+}
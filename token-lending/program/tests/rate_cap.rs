@@ -0,0 +1,201 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::PriceArgs;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::*;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::math::{Decimal, TryAdd, TrySub};
+use solend_program::state::{
+    find_insurance_fund_address, Obligation, Reserve, ReserveConfig, SLOTS_PER_YEAR,
+};
+use spl_token::state::Account as Token;
+
+#[tokio::test]
+async fn test_rate_cap() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(
+        &test_reserve_config(),
+        &ReserveConfig {
+            borrow_rate_cap: 10,
+            rate_cap_premium_bps: 100,
+            ..test_reserve_config()
+        },
+    )
+    .await;
+
+    lending_market
+        .init_insurance_fund(&mut test, &lending_market_owner, &wsol_reserve)
+        .await
+        .unwrap();
+    let insurance_fund_pubkey = find_insurance_fund_address(
+        &solend_program::id(),
+        &lending_market.pubkey,
+        &wsol_mint::id(),
+    )
+    .0;
+
+    // the usdc reserve doesn't offer rate caps
+    let res = lending_market
+        .buy_rate_cap(&mut test, &usdc_reserve, &obligation, &user)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert!(matches!(
+        res,
+        TransactionError::InstructionError(_, InstructionError::Custom(code))
+            if code == LendingError::RateCapNotOffered as u32
+    ));
+
+    // the premium is 1% of the 10 SOL borrowed
+    lending_market
+        .buy_rate_cap(&mut test, &wsol_reserve, &obligation, &user)
+        .await
+        .unwrap();
+    let insurance_fund = test.load_account::<Token>(insurance_fund_pubkey).await;
+    assert_eq!(insurance_fund.account.amount, LAMPORTS_PER_SOL / 10);
+    let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation.account.borrows[0].rate_cap, 10);
+    assert_eq!(
+        obligation.account.borrows[0].rate_cap_credit_wads,
+        Decimal::zero()
+    );
+
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .buy_rate_cap(&mut test, &wsol_reserve, &obligation, &user)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert!(matches!(
+        res,
+        TransactionError::InstructionError(_, InstructionError::Custom(code))
+            if code == LendingError::BorrowRateCapped as u32
+    ));
+
+    // a year at the 30% max borrow rate, well above the 10% cap
+    test.advance_clock_by_slots(SLOTS_PER_YEAR).await;
+    for (mint, price) in [(usdc_mint::id(), 1), (wsol_mint::id(), 10)] {
+        test.set_price(
+            &mint,
+            &PriceArgs {
+                price,
+                conf: 0,
+                expo: 0,
+                ema_price: price,
+                ema_conf: 0,
+            },
+        )
+        .await;
+    }
+    lending_market
+        .refresh_obligation(&mut test, &obligation)
+        .await
+        .unwrap();
+
+    // the borrow is only charged the capped 10%, and the rest of the reserve's interest is owed
+    // by the insurance fund
+    let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    let liquidity = &obligation.account.borrows[0];
+    let borrowed = liquidity.borrowed_amount_wads.try_floor_u64().unwrap();
+    assert!(
+        (11 * LAMPORTS_PER_SOL..111 * LAMPORTS_PER_SOL / 10).contains(&borrowed),
+        "{}",
+        borrowed
+    );
+    assert!(liquidity.rate_cap_credit_wads > Decimal::from(LAMPORTS_PER_SOL));
+    assert_eq!(
+        liquidity
+            .borrowed_amount_wads
+            .try_add(liquidity.rate_cap_credit_wads)
+            .unwrap()
+            .try_round_u64()
+            .unwrap(),
+        wsol_reserve
+            .account
+            .liquidity
+            .borrowed_amount_wads
+            .try_round_u64()
+            .unwrap()
+    );
+
+    // the fund only holds the premium, so that's all that can be claimed
+    lending_market
+        .claim_rate_cap_credit(&mut test, &wsol_reserve, &obligation)
+        .await
+        .unwrap();
+
+    let insurance_fund = test.load_account::<Token>(insurance_fund_pubkey).await;
+    assert_eq!(insurance_fund.account.amount, 0);
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    let claimed = Decimal::from(LAMPORTS_PER_SOL / 10);
+    assert_eq!(
+        obligation_post.account.borrows[0].borrowed_amount_wads,
+        liquidity.borrowed_amount_wads
+    );
+    assert_eq!(
+        obligation_post.account.borrows[0].rate_cap_credit_wads,
+        liquidity.rate_cap_credit_wads.try_sub(claimed).unwrap()
+    );
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.available_amount,
+        wsol_reserve.account.liquidity.available_amount + LAMPORTS_PER_SOL / 10
+    );
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.borrowed_amount_wads,
+        wsol_reserve
+            .account
+            .liquidity
+            .borrowed_amount_wads
+            .try_sub(claimed)
+            .unwrap()
+    );
+
+    // nothing left in the fund to claim
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .claim_rate_cap_credit(&mut test, &wsol_reserve, &obligation)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAmount as u32)
+        )
+    );
+
+    // a capped borrow can't be increased
+    let res = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            LAMPORTS_PER_SOL / 100,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert!(matches!(
+        res,
+        TransactionError::InstructionError(_, InstructionError::Custom(code))
+            if code == LendingError::BorrowRateCapped as u32
+    ));
+}
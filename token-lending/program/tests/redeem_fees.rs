@@ -108,3 +108,82 @@ async fn test_success() {
         }
     );
 }
+
+#[tokio::test]
+async fn test_redeem_fees_if_above() {
+    let (mut test, lending_market, _, wsol_reserve, user, _, _) = scenario_1(
+        &test_reserve_config(),
+        &ReserveConfig {
+            protocol_take_rate: 10,
+            ..test_reserve_config()
+        },
+    )
+    .await;
+
+    test.advance_clock_by_slots(SLOTS_PER_YEAR).await;
+
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            expo: 0,
+            conf: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    // deposit some liquidity so we can actually redeem the fees later
+    lending_market
+        .deposit(&mut test, &wsol_reserve, &user, LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    let expected_fees = wsol_reserve.account.calculate_redeem_fees().unwrap();
+    assert!(expected_fees > 0);
+
+    // fees at the threshold are left in the reserve without failing
+    let balance_checker = BalanceChecker::start(&mut test, &[&wsol_reserve]).await;
+    lending_market
+        .redeem_fees_if_above(&mut test, &wsol_reserve, expected_fees)
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(balance_changes, HashSet::new());
+
+    // fees above it are redeemed
+    test.advance_clock_by_slots(1).await;
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    let balance_checker = BalanceChecker::start(&mut test, &[&wsol_reserve]).await;
+    lending_market
+        .redeem_fees_if_above(&mut test, &wsol_reserve, expected_fees - 1)
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    let fee_receiver_change = balance_changes
+        .iter()
+        .find(|change| change.token_account == wsol_reserve.account.config.fee_receiver)
+        .unwrap();
+    assert!(fee_receiver_change.diff >= expected_fees as i128);
+
+    // and once they are, running it again does nothing
+    test.advance_clock_by_slots(1).await;
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    let balance_checker = BalanceChecker::start(&mut test, &[&wsol_reserve]).await;
+    lending_market
+        .redeem_fees_if_above(&mut test, &wsol_reserve, expected_fees - 1)
+        .await
+        .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(balance_changes, HashSet::new());
+}
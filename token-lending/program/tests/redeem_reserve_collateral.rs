@@ -13,6 +13,7 @@ use helpers::*;
 use solana_program::instruction::InstructionError;
 use solana_program_test::*;
 use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
 use solend_program::state::{
     LastUpdate, LendingMarket, Reserve, ReserveCollateral, ReserveLiquidity,
 };
@@ -148,3 +149,23 @@ async fn test_fail_redeem_too_much() {
         _ => panic!("Unexpected error: {:#?}", res),
     };
 }
+
+#[tokio::test]
+async fn test_fail_below_min_liquidity_out() {
+    let (mut test, lending_market, usdc_reserve, user) = setup().await;
+
+    let res = lending_market
+        .redeem_with_min_out(&mut test, &usdc_reserve, &user, 1_000_000, 1_000_001)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(LendingError::ExchangeRateSlippageExceeded as u32)
+        )
+    );
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use helpers::solend_program_test::scenario_1;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{signature::Signer, transaction::TransactionError};
+use solend_program::error::LendingError;
+use solend_program::instruction::refresh_ctoken_price;
+use solend_program::math::{Decimal, TryMul};
+use solend_program::state::{find_reserve_ctoken_price_address, Reserve, ReserveCTokenPrice};
+
+#[tokio::test]
+async fn test_refresh_ctoken_price() {
+    let (mut test, lending_market, _, wsol_reserve, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // created on first use
+    lending_market
+        .refresh_ctoken_price(&mut test, &wsol_reserve, &user)
+        .await
+        .unwrap();
+
+    let (reserve_ctoken_price_pubkey, bump_seed) =
+        find_reserve_ctoken_price_address(&solend_program::id(), &wsol_reserve.pubkey);
+    let reserve_ctoken_price = test
+        .load_account::<ReserveCTokenPrice>(reserve_ctoken_price_pubkey)
+        .await;
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(reserve_ctoken_price.account.bump_seed, bump_seed);
+    assert_eq!(reserve_ctoken_price.account.reserve, wsol_reserve.pubkey);
+    assert_eq!(
+        reserve_ctoken_price.account.collateral_mint,
+        wsol_reserve.account.collateral.mint_pubkey
+    );
+    assert_eq!(
+        reserve_ctoken_price.account.last_update_slot,
+        wsol_reserve_post.account.last_update.slot
+    );
+    assert_eq!(
+        reserve_ctoken_price.account.liquidity_market_price,
+        wsol_reserve_post.account.liquidity.market_price
+    );
+
+    // interest only ever adds to the liquidity a ctoken redeems for
+    let collateral_exchange_rate = reserve_ctoken_price.account.collateral_exchange_rate;
+    assert!(collateral_exchange_rate >= Decimal::one());
+    assert_eq!(
+        reserve_ctoken_price.account.ctoken_market_price,
+        wsol_reserve_post
+            .account
+            .liquidity
+            .market_price
+            .try_mul(collateral_exchange_rate)
+            .unwrap()
+    );
+
+    // later refreshes update the existing account
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .refresh_ctoken_price(&mut test, &wsol_reserve_post, &user)
+        .await
+        .unwrap();
+    let reserve_ctoken_price_post = test
+        .load_account::<ReserveCTokenPrice>(reserve_ctoken_price_pubkey)
+        .await;
+    assert_eq!(
+        reserve_ctoken_price_post.account.last_update_slot,
+        reserve_ctoken_price.account.last_update_slot + 1
+    );
+}
+
+#[tokio::test]
+async fn test_fail_reserve_stale() {
+    let (mut test, _, _, wsol_reserve, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    test.advance_clock_by_slots(1).await;
+    let res = test
+        .process_transaction(
+            &[refresh_ctoken_price(
+                solend_program::id(),
+                wsol_reserve.pubkey,
+                user.keypair.pubkey(),
+            )],
+            Some(&[&user.keypair]),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ReserveStale as u32)
+        )
+    );
+}
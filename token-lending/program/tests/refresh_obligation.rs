@@ -3,23 +3,38 @@
 mod helpers;
 
 use crate::solend_program_test::custom_scenario;
+use crate::solend_program_test::scenario_1;
 use crate::solend_program_test::ObligationArgs;
 use crate::solend_program_test::PriceArgs;
 use crate::solend_program_test::ReserveArgs;
 use helpers::AddPacked;
+use solana_program::instruction::InstructionError;
+use solana_program::system_instruction::transfer;
 use solana_sdk::pubkey::Pubkey;
-use solend_program::instruction::refresh_obligation;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::{
+    borrow_obligation_liquidity, liquidate_obligation_and_redeem_reserve_collateral,
+    refresh_obligation, refresh_obligation_with_asset_categories,
+    refresh_obligation_with_max_price_age, refresh_reserve,
+};
 use solend_program::processor::process_instruction;
 
 use solend_program::state::ObligationCollateral;
-use solend_sdk::state::PROGRAM_VERSION;
+use solend_sdk::state::{
+    find_asset_categories_address, find_max_price_age_address, AssetCategories, AssetCategory,
+    MaxPriceAge, MAX_PRICE_AGE_SLOTS, PROGRAM_VERSION,
+};
 use std::collections::HashSet;
 
-use helpers::solend_program_test::{setup_world, BalanceChecker, Info, SolendProgramTest, User};
+use helpers::solend_program_test::{
+    setup_world, BalanceChecker, Info, SolendProgramTest, TokenBalanceChange, User,
+};
 use helpers::*;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 use solana_program_test::*;
 use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
 use solend_program::state::SLOTS_PER_YEAR;
 use solend_program::state::{LastUpdate, ObligationLiquidity, ReserveFees, ReserveLiquidity};
 
@@ -218,6 +233,7 @@ async fn test_success() {
                 stale: false
             },
             liquidity: ReserveLiquidity {
+                price_slot: 1001,
                 smoothed_market_price: Decimal::from_percent(90),
                 ..usdc_reserve.account.liquidity
             },
@@ -239,6 +255,7 @@ async fn test_success() {
                 available_amount: 0,
                 borrowed_amount_wads: new_borrowed_amount_wads,
                 cumulative_borrow_rate_wads: new_cumulative_borrow_rate,
+                price_slot: 1001,
                 smoothed_market_price: Decimal::from(11u64),
                 ..wsol_reserve.account.liquidity
             },
@@ -264,7 +281,8 @@ async fn test_success() {
                 borrow_reserve: wsol_reserve.pubkey,
                 cumulative_borrow_rate_wads: new_cumulative_borrow_rate,
                 borrowed_amount_wads: new_borrowed_amount_wads,
-                market_value: new_borrow_value
+                market_value: new_borrow_value,
+                ..ObligationLiquidity::default()
             }]
             .to_vec(),
 
@@ -295,6 +313,7 @@ async fn test_success() {
                 .unwrap()
                 .try_mul(Decimal::from_percent(90))
                 .unwrap(),
+            oldest_price_slot: 1001,
 
             ..obligation.account
         }
@@ -582,3 +601,510 @@ async fn test_normalize_obligation() {
         }]
     );
 }
+
+#[tokio::test]
+async fn test_max_collateral_value_contribution() {
+    let (mut test, lending_market, reserves, obligations, _users, lending_market_owner) =
+        custom_scenario(
+            &[ReserveArgs {
+                mint: usdc_mint::id(),
+                config: test_reserve_config(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: -1,
+                    ema_price: 10,
+                    ema_conf: 0,
+                },
+            }],
+            &[ObligationArgs {
+                deposits: vec![(usdc_mint::id(), 100 * FRACTIONAL_TO_USDC)],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    lending_market
+        .update_reserve_config(
+            &mut test,
+            &lending_market_owner,
+            &reserves[0],
+            ReserveConfig {
+                max_collateral_value_contribution: 10,
+                ..reserves[0].account.config
+            },
+            reserves[0].account.rate_limiter.config,
+            None,
+        )
+        .await
+        .unwrap();
+
+    test.advance_clock_by_slots(1).await;
+
+    lending_market
+        .refresh_obligation(&mut test, &obligations[0])
+        .await
+        .unwrap();
+
+    // the $100 deposit only backs $10 of borrows, its liquidation threshold is unaffected
+    let obligation = test.load_account::<Obligation>(obligations[0].pubkey).await;
+    assert_eq!(obligation.account.deposited_value, Decimal::from(100u64));
+    assert_eq!(
+        obligation.account.allowed_borrow_value,
+        Decimal::from(10u64)
+    );
+    assert_eq!(
+        obligation.account.unhealthy_borrow_value,
+        Decimal::from(55u64)
+    );
+}
+
+#[tokio::test]
+async fn test_max_price_age() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        _user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let res = lending_market
+        .set_max_price_age(&mut test, &lending_market_owner, MAX_PRICE_AGE_SLOTS + 1)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidConfig as u32)
+        )
+    );
+
+    lending_market
+        .set_max_price_age(&mut test, &lending_market_owner, 10)
+        .await
+        .unwrap();
+    let max_price_age = test
+        .load_account::<MaxPriceAge>(
+            find_max_price_age_address(&solend_program::id(), &lending_market.pubkey).0,
+        )
+        .await;
+    assert_eq!(max_price_age.account.lending_market, lending_market.pubkey);
+    assert_eq!(max_price_age.account.max_price_age_slots, 10);
+
+    let depositor = User::new_with_balances(
+        &mut test,
+        &[
+            (&usdc_mint::id(), 1_000_000),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+        ],
+    )
+    .await;
+    lending_market
+        .refresh_reserve(&mut test, &usdc_reserve)
+        .await
+        .unwrap();
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+    let price_slot = test.get_clock().await.slot;
+
+    // deposits mark the reserve stale without changing its price
+    lending_market
+        .deposit(&mut test, &usdc_reserve, &depositor, 1_000_000)
+        .await
+        .unwrap();
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert!(usdc_reserve_post.account.last_update.stale);
+    assert_eq!(usdc_reserve_post.account.liquidity.price_slot, price_slot);
+
+    test.advance_clock_by_slots(5).await;
+
+    let reserve_pubkeys = vec![usdc_reserve.pubkey, wsol_reserve.pubkey];
+
+    // without the max price age account, the reserves must be refreshed in the same slot
+    let res = test
+        .process_transaction(
+            &[refresh_obligation(
+                solend_program::id(),
+                obligation.pubkey,
+                reserve_pubkeys.clone(),
+            )],
+            None,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ReserveStale as u32)
+        )
+    );
+
+    test.process_transaction(
+        &[refresh_obligation_with_max_price_age(
+            solend_program::id(),
+            obligation.pubkey,
+            reserve_pubkeys.clone(),
+            vec![],
+            lending_market.pubkey,
+        )],
+        None,
+    )
+    .await
+    .unwrap();
+
+    // the obligation is refreshed as of the current slot, and records how old its prices are
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(
+        obligation_post.account.last_update,
+        LastUpdate {
+            slot: test.get_clock().await.slot,
+            stale: false
+        }
+    );
+    assert_eq!(obligation_post.account.oldest_price_slot, price_slot);
+
+    // past the max price age the reserves are stale again
+    test.advance_clock_by_slots(6).await;
+
+    let res = test
+        .process_transaction(
+            &[refresh_obligation_with_max_price_age(
+                solend_program::id(),
+                obligation.pubkey,
+                reserve_pubkeys,
+                vec![],
+                lending_market.pubkey,
+            )],
+            None,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::ReserveStale as u32)
+        )
+    );
+
+    // the max price age only applies to the reserves, so an obligation refreshed in an earlier
+    // slot still can't be liquidated
+    let liquidator = User::new_with_balances(
+        &mut test,
+        &[
+            (&wsol_mint::id(), 100 * LAMPORTS_PER_SOL),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+            (&usdc_mint::id(), 0),
+        ],
+    )
+    .await;
+    let res = test
+        .process_transaction(
+            &[
+                refresh_reserve(
+                    solend_program::id(),
+                    usdc_reserve.pubkey,
+                    usdc_reserve.account.liquidity.pyth_oracle_pubkey,
+                    usdc_reserve.account.liquidity.switchboard_oracle_pubkey,
+                    usdc_reserve.account.config.extra_oracle_pubkey,
+                ),
+                refresh_reserve(
+                    solend_program::id(),
+                    wsol_reserve.pubkey,
+                    wsol_reserve.account.liquidity.pyth_oracle_pubkey,
+                    wsol_reserve.account.liquidity.switchboard_oracle_pubkey,
+                    wsol_reserve.account.config.extra_oracle_pubkey,
+                ),
+                liquidate_obligation_and_redeem_reserve_collateral(
+                    solend_program::id(),
+                    LAMPORTS_PER_SOL,
+                    liquidator.get_account(&wsol_mint::id()).unwrap(),
+                    liquidator
+                        .get_account(&usdc_reserve.account.collateral.mint_pubkey)
+                        .unwrap(),
+                    liquidator.get_account(&usdc_mint::id()).unwrap(),
+                    wsol_reserve.pubkey,
+                    wsol_reserve.account.liquidity.supply_pubkey,
+                    usdc_reserve.pubkey,
+                    usdc_reserve.account.collateral.mint_pubkey,
+                    usdc_reserve.account.collateral.supply_pubkey,
+                    usdc_reserve.account.liquidity.supply_pubkey,
+                    usdc_reserve.account.config.fee_receiver,
+                    obligation.pubkey,
+                    lending_market.pubkey,
+                    liquidator.keypair.pubkey(),
+                ),
+            ],
+            Some(&[&liquidator.keypair]),
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(LendingError::ObligationStale as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_borrow_with_max_price_age() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    lending_market
+        .set_max_price_age(&mut test, &lending_market_owner, 10)
+        .await
+        .unwrap();
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+    let price_slot = test.get_clock().await.slot;
+
+    test.advance_clock_by_slots(5).await;
+
+    // only the borrow reserve is refreshed, the wSOL price is 5 slots old
+    let balance_checker = BalanceChecker::start(&mut test, &[&user]).await;
+    test.process_transaction(
+        &[
+            refresh_reserve(
+                solend_program::id(),
+                usdc_reserve.pubkey,
+                usdc_reserve.account.liquidity.pyth_oracle_pubkey,
+                usdc_reserve.account.liquidity.switchboard_oracle_pubkey,
+                usdc_reserve.account.config.extra_oracle_pubkey,
+            ),
+            refresh_obligation_with_max_price_age(
+                solend_program::id(),
+                obligation.pubkey,
+                vec![usdc_reserve.pubkey, wsol_reserve.pubkey],
+                vec![],
+                lending_market.pubkey,
+            ),
+            borrow_obligation_liquidity(
+                solend_program::id(),
+                FRACTIONAL_TO_USDC,
+                usdc_reserve.account.liquidity.supply_pubkey,
+                user.get_account(&usdc_mint::id()).unwrap(),
+                usdc_reserve.pubkey,
+                usdc_reserve.account.config.fee_receiver,
+                obligation.pubkey,
+                lending_market.pubkey,
+                user.keypair.pubkey(),
+                vec![usdc_reserve.pubkey],
+                None,
+            ),
+        ],
+        Some(&[&user.keypair]),
+    )
+    .await
+    .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    assert!(balance_changes.contains(&TokenBalanceChange {
+        token_account: user.get_account(&usdc_mint::id()).unwrap(),
+        mint: usdc_mint::id(),
+        diff: FRACTIONAL_TO_USDC as i128,
+    }));
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(obligation_post.account.oldest_price_slot, price_slot);
+    assert_eq!(obligation_post.account.borrows.len(), 2);
+}
+
+#[tokio::test]
+async fn test_asset_category() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        wsol_reserve,
+        _user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // the lending market owner pays for the asset categories account
+    let instructions = [transfer(
+        &test.context.payer.pubkey(),
+        &lending_market_owner.keypair.pubkey(),
+        LAMPORTS_PER_SOL,
+    )];
+    test.process_transaction(&instructions, None).await.unwrap();
+
+    let category = AssetCategory {
+        loan_to_value_ratio: 90,
+        liquidation_threshold: 95,
+        max_liquidation_threshold: 97,
+    };
+    lending_market
+        .set_asset_category(&mut test, &lending_market_owner, 1, category)
+        .await
+        .unwrap();
+    let asset_categories = test
+        .load_account::<AssetCategories>(
+            find_asset_categories_address(&solend_program::id(), &lending_market.pubkey).0,
+        )
+        .await;
+    assert_eq!(asset_categories.account.get(1), Some(&category));
+    assert_eq!(
+        asset_categories.account.lending_market,
+        lending_market.pubkey
+    );
+
+    // only the lending market owner can set categories
+    let not_owner = User::new_with_balances(&mut test, &[]).await;
+    let res = lending_market
+        .set_asset_category(&mut test, &not_owner, 1, category)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    for reserve in [&usdc_reserve, &wsol_reserve] {
+        lending_market
+            .update_reserve_config(
+                &mut test,
+                &lending_market_owner,
+                reserve,
+                ReserveConfig {
+                    asset_category: 1,
+                    ..reserve.account.config
+                },
+                reserve.account.rate_limiter.config,
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .refresh_reserve(&mut test, &usdc_reserve)
+        .await
+        .unwrap();
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let reserve_pubkeys = vec![usdc_reserve.pubkey, wsol_reserve.pubkey];
+
+    // without the categories, the obligation is valued at its reserves' own limits
+    test.process_transaction(
+        &[refresh_obligation(
+            solend_program::id(),
+            obligation.pubkey,
+            reserve_pubkeys.clone(),
+        )],
+        None,
+    )
+    .await
+    .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert!(!obligation_post.account.uses_asset_category_limits);
+    assert_eq!(
+        obligation_post.account.allowed_borrow_value,
+        obligation_post
+            .account
+            .deposited_value
+            .try_mul(Decimal::from_percent(
+                usdc_reserve.account.config.loan_to_value_ratio
+            ))
+            .unwrap()
+    );
+
+    test.process_transaction(
+        &[refresh_obligation_with_asset_categories(
+            solend_program::id(),
+            obligation.pubkey,
+            reserve_pubkeys.clone(),
+            vec![],
+            lending_market.pubkey,
+        )],
+        None,
+    )
+    .await
+    .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert!(obligation_post.account.uses_asset_category_limits);
+    let deposited_value = obligation_post.account.deposited_value;
+    assert_eq!(
+        obligation_post.account.allowed_borrow_value,
+        deposited_value.try_mul(Decimal::from_percent(90)).unwrap()
+    );
+    assert_eq!(
+        obligation_post.account.unhealthy_borrow_value,
+        deposited_value.try_mul(Decimal::from_percent(95)).unwrap()
+    );
+    assert_eq!(
+        obligation_post.account.super_unhealthy_borrow_value,
+        deposited_value.try_mul(Decimal::from_percent(97)).unwrap()
+    );
+
+    // once the category's limits apply, a refresh can't drop the obligation back to the
+    // reserves' own
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .refresh_reserve(&mut test, &usdc_reserve)
+        .await
+        .unwrap();
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let res = test
+        .process_transaction(
+            &[refresh_obligation(
+                solend_program::id(),
+                obligation.pubkey,
+                reserve_pubkeys,
+            )],
+            None,
+        )
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
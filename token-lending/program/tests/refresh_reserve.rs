@@ -16,12 +16,15 @@ use helpers::*;
 use solana_program::instruction::InstructionError;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 use solana_program_test::*;
-use solana_sdk::{signature::Keypair, transaction::TransactionError};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, transaction::TransactionError};
+use solend_program::state::Change;
 use solend_program::state::LastUpdate;
 use solend_program::state::LendingMarket;
 use solend_program::state::Obligation;
 use solend_program::state::Reserve;
+use solend_program::state::ReserveChange;
 use solend_program::state::ReserveConfig;
+use solend_program::state::ReserveDiff;
 use solend_program::state::ReserveFees;
 use solend_program::state::ReserveLiquidity;
 use solend_program::NULL_PUBKEY;
@@ -204,6 +207,7 @@ async fn test_success() {
                 borrowed_amount_wads: compound_borrow,
                 cumulative_borrow_rate_wads: compound_rate.into(),
                 accumulated_protocol_fees_wads: delta_accumulated_protocol_fees,
+                price_slot: 1001,
                 market_price: Decimal::from(200u64),
                 smoothed_market_price: Decimal::from(150u64),
                 ..wsol_reserve.account.liquidity
@@ -570,6 +574,7 @@ async fn test_use_extra_oracle() {
                 stale: false
             },
             liquidity: ReserveLiquidity {
+                price_slot: 1001,
                 market_price: Decimal::from(12u64),
                 smoothed_market_price: Decimal::from(12u64),
                 extra_market_price: Some(Decimal::from(5u64)),
@@ -610,18 +615,21 @@ async fn test_use_extra_oracle() {
 
     let msol_reserve_post = test.load_account::<Reserve>(reserves[0].pubkey).await;
     assert_eq!(
-        msol_reserve_post.account,
-        Reserve {
-            last_update: LastUpdate {
-                slot: 1002,
-                stale: false
-            },
-            liquidity: ReserveLiquidity {
-                extra_market_price: Some(Decimal::from(2u64)),
-                ..msol_reserve.account.liquidity
-            },
-            ..msol_reserve.account
-        }
+        ReserveDiff::between(&msol_reserve.account, &msol_reserve_post.account).changes,
+        vec![
+            ReserveChange::LastUpdateSlot(Change {
+                before: 1001,
+                after: 1002
+            }),
+            ReserveChange::LastUpdateStale(Change {
+                before: true,
+                after: false
+            }),
+            ReserveChange::ExtraMarketPrice(Change {
+                before: Some(Decimal::from(5u64)),
+                after: Some(Decimal::from(2u64))
+            }),
+        ]
     );
 
     test.advance_clock_by_slots(1).await;
@@ -651,18 +659,21 @@ async fn test_use_extra_oracle() {
 
     let msol_reserve_post = test.load_account::<Reserve>(reserves[0].pubkey).await;
     assert_eq!(
-        msol_reserve_post.account,
-        Reserve {
-            last_update: LastUpdate {
-                slot: 1003,
-                stale: false
-            },
-            liquidity: ReserveLiquidity {
-                extra_market_price: None,
-                ..msol_reserve.account.liquidity
-            },
-            ..msol_reserve.account
-        }
+        ReserveDiff::between(&msol_reserve.account, &msol_reserve_post.account).changes,
+        vec![
+            ReserveChange::LastUpdateSlot(Change {
+                before: 1002,
+                after: 1003
+            }),
+            ReserveChange::LastUpdateStale(Change {
+                before: true,
+                after: false
+            }),
+            ReserveChange::ExtraMarketPrice(Change {
+                before: Some(Decimal::from(2u64)),
+                after: None
+            }),
+        ]
     );
 }
 
@@ -955,3 +966,164 @@ async fn test_switchboard_pull_oracle() {
         ),
     );
 }
+
+#[tokio::test]
+async fn test_tertiary_oracle() {
+    let (mut test, lending_market, _, wsol_reserve, lending_market_owner, _) = setup().await;
+
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 9,
+            conf: 0,
+            expo: 0,
+            ema_price: 11,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    test.advance_clock_by_slots(1).await;
+
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    // the reserve only has a pyth oracle, the switchboard feed backs it up as its tertiary oracle
+    let feed = test.init_switchboard_feed(&wsol_mint::id()).await;
+    lending_market
+        .set_reserve_tertiary_oracle(&mut test, &wsol_reserve, &lending_market_owner, feed)
+        .await
+        .unwrap();
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(wsol_reserve.account.tertiary_oracle_pubkey, Some(feed));
+    assert_eq!(
+        wsol_reserve.account.liquidity.switchboard_oracle_pubkey,
+        NULL_PUBKEY
+    );
+
+    // refreshes must be passed the tertiary oracle
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(1, InstructionError::NotEnoughAccountKeys)
+    );
+
+    test.advance_clock_by_slots(240).await;
+    test.set_switchboard_price(&wsol_mint::id(), SwitchboardPriceArgs { price: 8, expo: 0 })
+        .await;
+
+    let res = lending_market
+        .refresh_reserve_with_tertiary_oracle(&mut test, &wsol_reserve, Pubkey::new_unique())
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+
+    // pyth is stale, so the price comes from the tertiary oracle
+    lending_market
+        .refresh_reserve_with_tertiary_oracle(&mut test, &wsol_reserve, feed)
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.market_price,
+        Decimal::from(8u64)
+    );
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.smoothed_market_price,
+        Decimal::from(11u64)
+    );
+
+    lending_market
+        .set_reserve_tertiary_oracle(&mut test, &wsol_reserve, &lending_market_owner, NULL_PUBKEY)
+        .await
+        .unwrap();
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(wsol_reserve.account.tertiary_oracle_pubkey, None);
+}
+
+#[tokio::test]
+async fn test_price_bounds() {
+    let (mut test, lending_market, reserves, _obligations, _users, lending_market_owner) =
+        custom_scenario(
+            &[ReserveArgs {
+                mint: usdc_mint::id(),
+                config: test_reserve_config(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 105,
+                    conf: 0,
+                    expo: -2,
+                    ema_price: 95,
+                    ema_conf: 0,
+                },
+            }],
+            &[],
+        )
+        .await;
+
+    lending_market
+        .update_reserve_config(
+            &mut test,
+            &lending_market_owner,
+            &reserves[0],
+            ReserveConfig {
+                min_price: 9_900,
+                max_price: 10_010,
+                ..reserves[0].account.config
+            },
+            reserves[0].account.rate_limiter.config,
+            None,
+        )
+        .await
+        .unwrap();
+
+    test.advance_clock_by_slots(1).await;
+
+    let reserve = test.load_account::<Reserve>(reserves[0].pubkey).await;
+    lending_market
+        .refresh_reserve(&mut test, &reserve)
+        .await
+        .unwrap();
+
+    // the $1.05 spot price is capped at the max price, the $0.95 ema is stored as is
+    let reserve = test.load_account::<Reserve>(reserves[0].pubkey).await;
+    assert_eq!(
+        reserve.account.liquidity.market_price,
+        Decimal::from_bps(10_010)
+    );
+    assert_eq!(
+        reserve.account.liquidity.smoothed_market_price,
+        Decimal::from_bps(9_500)
+    );
+
+    // collateral is valued down to the ema, borrows never below the min price
+    assert_eq!(
+        reserve.account.price_lower_bound(),
+        Decimal::from_bps(9_500)
+    );
+    let mut depegged = reserve.account.clone();
+    depegged.liquidity.market_price = Decimal::from_bps(9_000);
+    assert_eq!(depegged.price_upper_bound(), Decimal::from_bps(9_900));
+    assert_eq!(
+        depegged
+            .borrow_market_value(Decimal::from(FRACTIONAL_TO_USDC))
+            .unwrap(),
+        Decimal::from_bps(9_900)
+    );
+}
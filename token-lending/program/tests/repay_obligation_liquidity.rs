@@ -2,16 +2,23 @@
 
 mod helpers;
 
-use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::{
+    custom_scenario, scenario_1, ObligationArgs, PriceArgs, ReserveArgs,
+};
 use std::collections::HashSet;
 
 use helpers::solend_program_test::{BalanceChecker, TokenBalanceChange};
 use helpers::*;
 use solana_program::native_token::LAMPORTS_PER_SOL;
 use solana_program_test::*;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
 
+use solend_program::error::LendingError;
 use solend_program::math::TryDiv;
-use solend_program::state::{LastUpdate, ObligationLiquidity, ReserveLiquidity, SLOTS_PER_YEAR};
+use solend_program::state::{
+    LastUpdate, ObligationLiquidity, ReserveConfig, ReserveFees, ReserveLiquidity, SLOTS_PER_YEAR,
+};
 use solend_program::{
     math::{Decimal, TryAdd, TryMul, TrySub},
     state::{Obligation, Reserve},
@@ -110,3 +117,152 @@ async fn test_success() {
         }
     );
 }
+
+#[tokio::test]
+async fn test_repay_many() {
+    let reserve_config = ReserveConfig {
+        fees: ReserveFees {
+            host_fee_percentage: 0,
+            ..ReserveFees::default()
+        },
+        optimal_borrow_rate: 0,
+        max_borrow_rate: 0,
+        ..test_reserve_config()
+    };
+    let (mut test, lending_market, reserves, obligations, users, _) = custom_scenario(
+        &[
+            ReserveArgs {
+                mint: usdc_mint::id(),
+                config: reserve_config,
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: -1,
+                    ema_price: 10,
+                    ema_conf: 1,
+                },
+            },
+            ReserveArgs {
+                mint: wsol_mint::id(),
+                config: reserve_config,
+                liquidity_amount: 100 * LAMPORTS_PER_SOL,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: 0,
+                    ema_price: 10,
+                    ema_conf: 0,
+                },
+            },
+        ],
+        &[ObligationArgs {
+            deposits: vec![(usdc_mint::id(), 100 * FRACTIONAL_TO_USDC)],
+            borrows: vec![
+                (usdc_mint::id(), 10 * FRACTIONAL_TO_USDC),
+                (wsol_mint::id(), LAMPORTS_PER_SOL),
+            ],
+        }],
+    )
+    .await;
+
+    let balance_checker =
+        BalanceChecker::start(&mut test, &[&reserves[0], &reserves[1], &users[0]]).await;
+
+    lending_market
+        .repay_obligation_liquidity_many(
+            &mut test,
+            &[
+                (&reserves[0], 4 * FRACTIONAL_TO_USDC),
+                (&reserves[1], u64::MAX),
+            ],
+            &obligations[0],
+            &users[0],
+        )
+        .await
+        .unwrap();
+
+    let (balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    let expected_balance_changes = HashSet::from([
+        TokenBalanceChange {
+            token_account: users[0].get_account(&usdc_mint::id()).unwrap(),
+            mint: usdc_mint::id(),
+            diff: -(4 * FRACTIONAL_TO_USDC as i128),
+        },
+        TokenBalanceChange {
+            token_account: reserves[0].account.liquidity.supply_pubkey,
+            mint: usdc_mint::id(),
+            diff: (4 * FRACTIONAL_TO_USDC as i128),
+        },
+        TokenBalanceChange {
+            token_account: users[0].get_account(&wsol_mint::id()).unwrap(),
+            mint: wsol_mint::id(),
+            diff: -(LAMPORTS_PER_SOL as i128),
+        },
+        TokenBalanceChange {
+            token_account: reserves[1].account.liquidity.supply_pubkey,
+            mint: wsol_mint::id(),
+            diff: LAMPORTS_PER_SOL as i128,
+        },
+    ]);
+    assert_eq!(balance_changes, expected_balance_changes);
+    assert_eq!(mint_supply_changes, HashSet::new());
+
+    let obligation_post = test.load_account::<Obligation>(obligations[0].pubkey).await;
+    assert_eq!(obligation_post.account.borrows.len(), 1);
+    assert_eq!(
+        obligation_post.account.borrows[0].borrow_reserve,
+        reserves[0].pubkey
+    );
+    assert_eq!(
+        obligation_post.account.borrows[0].borrowed_amount_wads,
+        Decimal::from(6 * FRACTIONAL_TO_USDC)
+    );
+    assert!(obligation_post.account.last_update.stale);
+}
+
+#[tokio::test]
+async fn test_fail_repay_many_duplicate_reserve() {
+    let (mut test, lending_market, reserves, obligations, users, _) = custom_scenario(
+        &[ReserveArgs {
+            mint: usdc_mint::id(),
+            config: test_reserve_config(),
+            liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+            price: PriceArgs {
+                price: 10,
+                conf: 0,
+                expo: -1,
+                ema_price: 10,
+                ema_conf: 1,
+            },
+        }],
+        &[ObligationArgs {
+            deposits: vec![(usdc_mint::id(), 100 * FRACTIONAL_TO_USDC)],
+            borrows: vec![(usdc_mint::id(), 10 * FRACTIONAL_TO_USDC)],
+        }],
+    )
+    .await;
+
+    let err = lending_market
+        .repay_obligation_liquidity_many(
+            &mut test,
+            &[
+                (&reserves[0], FRACTIONAL_TO_USDC),
+                (&reserves[0], FRACTIONAL_TO_USDC),
+            ],
+            &obligations[0],
+            &users[0],
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use std::collections::HashSet;
+
+use helpers::solend_program_test::{
+    scenario_1, BalanceChecker, MintSupplyChange, TokenBalanceChange,
+};
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::*;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::math::{Decimal, TrySub};
+use solend_program::state::{Obligation, Reserve, ReserveConfig};
+
+#[tokio::test]
+async fn test_repay_with_collateral() {
+    // no interest, so cSOL/SOL stays at 1
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation, _) = scenario_1(
+        &test_reserve_config(),
+        &ReserveConfig {
+            optimal_borrow_rate: 0,
+            max_borrow_rate: 0,
+            super_max_borrow_rate: 0,
+            ..test_reserve_config()
+        },
+    )
+    .await;
+
+    lending_market
+        .deposit_reserve_liquidity_and_obligation_collateral(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            5 * LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    test.advance_clock_by_slots(1).await;
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    let obligation = test.load_account::<Obligation>(obligation.pubkey).await;
+
+    // the collateral has to be in the same token as the borrow
+    let res = lending_market
+        .repay_obligation_liquidity_with_collateral(
+            &mut test,
+            &wsol_reserve,
+            &usdc_reserve,
+            &obligation,
+            &user,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+
+    let balance_checker = BalanceChecker::start(&mut test, &[&wsol_reserve, &user]).await;
+
+    lending_market
+        .repay_obligation_liquidity_with_collateral(
+            &mut test,
+            &wsol_reserve,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            2 * LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    // the user's wallet isn't touched, the collateral is burned and the liquidity stays put
+    let (token_balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    assert_eq!(
+        token_balance_changes,
+        HashSet::from([TokenBalanceChange {
+            token_account: wsol_reserve.account.collateral.supply_pubkey,
+            mint: wsol_reserve.account.collateral.mint_pubkey,
+            diff: -2 * LAMPORTS_PER_SOL as i128,
+        }]),
+        "{:#?}",
+        token_balance_changes
+    );
+    assert_eq!(
+        mint_supply_changes,
+        HashSet::from([MintSupplyChange {
+            mint: wsol_reserve.account.collateral.mint_pubkey,
+            diff: -2 * LAMPORTS_PER_SOL as i128,
+        }]),
+        "{:#?}",
+        mint_supply_changes
+    );
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    let (collateral, _) = obligation_post
+        .account
+        .find_collateral_in_deposits(wsol_reserve.pubkey)
+        .unwrap();
+    assert_eq!(collateral.deposited_amount, 3 * LAMPORTS_PER_SOL);
+    let (liquidity, _) = obligation_post
+        .account
+        .find_liquidity_in_borrows(wsol_reserve.pubkey)
+        .unwrap();
+    assert_eq!(
+        liquidity.borrowed_amount_wads,
+        Decimal::from(8 * LAMPORTS_PER_SOL)
+    );
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.available_amount,
+        wsol_reserve.account.liquidity.available_amount
+    );
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.borrowed_amount_wads,
+        wsol_reserve
+            .account
+            .liquidity
+            .borrowed_amount_wads
+            .try_sub(Decimal::from(2 * LAMPORTS_PER_SOL))
+            .unwrap()
+    );
+
+    // a max repay is capped by the deposited collateral
+    test.advance_clock_by_slots(1).await;
+    lending_market
+        .repay_obligation_liquidity_with_collateral(
+            &mut test,
+            &wsol_reserve_post,
+            &wsol_reserve_post,
+            &obligation_post,
+            &user,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert!(obligation_post
+        .account
+        .find_collateral_in_deposits(wsol_reserve.pubkey)
+        .is_err());
+    let (liquidity, _) = obligation_post
+        .account
+        .find_liquidity_in_borrows(wsol_reserve.pubkey)
+        .unwrap();
+    assert_eq!(
+        liquidity.borrowed_amount_wads,
+        Decimal::from(5 * LAMPORTS_PER_SOL)
+    );
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use helpers::solend_program_test::{scenario_1, User};
+use helpers::*;
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::{deposit_reserve_liquidity, repay_obligation_liquidity};
+use solend_program::state::{find_reserve_activity_address, ReserveActivity, PROGRAM_VERSION};
+
+#[tokio::test]
+async fn test_reserve_activity() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    for reserve in [&usdc_reserve, &wsol_reserve] {
+        lending_market
+            .init_reserve_activity(&mut test, reserve, &user)
+            .await
+            .unwrap();
+    }
+    let (usdc_reserve_activity_pubkey, _) =
+        find_reserve_activity_address(&solend_program::id(), &usdc_reserve.pubkey);
+    let (wsol_reserve_activity_pubkey, bump_seed) =
+        find_reserve_activity_address(&solend_program::id(), &wsol_reserve.pubkey);
+
+    let wsol_reserve_activity = test
+        .load_account::<ReserveActivity>(wsol_reserve_activity_pubkey)
+        .await;
+    assert_eq!(
+        wsol_reserve_activity.account,
+        ReserveActivity {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            reserve: wsol_reserve.pubkey,
+            ..ReserveActivity::default()
+        }
+    );
+
+    // only once
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .init_reserve_activity(&mut test, &wsol_reserve, &user)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::AlreadyInitialized as u32)
+        )
+    );
+
+    // a repay only counts in the activity account of the repay reserve
+    let mut instruction = repay_obligation_liquidity(
+        solend_program::id(),
+        LAMPORTS_PER_SOL,
+        user.get_account(&wsol_mint::id()).unwrap(),
+        wsol_reserve.account.liquidity.supply_pubkey,
+        wsol_reserve.pubkey,
+        obligation.pubkey,
+        lending_market.pubkey,
+        user.keypair.pubkey(),
+    );
+    instruction.accounts.extend([
+        AccountMeta::new(usdc_reserve_activity_pubkey, false),
+        AccountMeta::new(wsol_reserve_activity_pubkey, false),
+    ]);
+    test.process_transaction(&[instruction], Some(&[&user.keypair]))
+        .await
+        .unwrap();
+
+    let wsol_reserve_activity = test
+        .load_account::<ReserveActivity>(wsol_reserve_activity_pubkey)
+        .await;
+    assert_eq!(wsol_reserve_activity.account.repay_count, 1);
+    assert_eq!(wsol_reserve_activity.account.repay_volume, LAMPORTS_PER_SOL);
+    let usdc_reserve_activity = test
+        .load_account::<ReserveActivity>(usdc_reserve_activity_pubkey)
+        .await;
+    assert_eq!(usdc_reserve_activity.account.repay_count, 0);
+
+    // deposits are counted too
+    let depositor = User::new_with_balances(
+        &mut test,
+        &[
+            (&usdc_mint::id(), 1_000_000),
+            (&usdc_reserve.account.collateral.mint_pubkey, 0),
+        ],
+    )
+    .await;
+    let mut instruction = deposit_reserve_liquidity(
+        solend_program::id(),
+        1_000_000,
+        depositor.get_account(&usdc_mint::id()).unwrap(),
+        depositor
+            .get_account(&usdc_reserve.account.collateral.mint_pubkey)
+            .unwrap(),
+        usdc_reserve.pubkey,
+        usdc_reserve.account.liquidity.supply_pubkey,
+        usdc_reserve.account.collateral.mint_pubkey,
+        lending_market.pubkey,
+        depositor.keypair.pubkey(),
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new(usdc_reserve_activity_pubkey, false));
+    test.process_transaction(&[instruction], Some(&[&depositor.keypair]))
+        .await
+        .unwrap();
+
+    let usdc_reserve_activity = test
+        .load_account::<ReserveActivity>(usdc_reserve_activity_pubkey)
+        .await;
+    assert_eq!(usdc_reserve_activity.account.deposit_count, 1);
+    assert_eq!(usdc_reserve_activity.account.deposit_volume, 1_000_000);
+}
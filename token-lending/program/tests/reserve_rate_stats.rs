@@ -0,0 +1,135 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use helpers::solend_program_test::{scenario_1, PriceArgs};
+use helpers::*;
+use solana_program::instruction::{AccountMeta, InstructionError};
+use solana_program_test::*;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::refresh_reserve;
+use solend_program::math::Decimal;
+use solend_program::state::{
+    find_reserve_rate_stats_address, Reserve, ReserveRateStats, RESERVE_RATE_STATS_WINDOW_SLOTS,
+};
+
+#[tokio::test]
+async fn test_reserve_rate_stats() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    lending_market
+        .init_reserve_rate_stats(&mut test, &wsol_reserve, &user)
+        .await
+        .unwrap();
+
+    // the averages start from the current rates
+    let (reserve_rate_stats_pubkey, bump_seed) =
+        find_reserve_rate_stats_address(&solend_program::id(), &wsol_reserve.pubkey);
+    let reserve_rate_stats = test
+        .load_account::<ReserveRateStats>(reserve_rate_stats_pubkey)
+        .await;
+    let utilization_rate: Decimal = wsol_reserve
+        .account
+        .liquidity
+        .utilization_rate()
+        .unwrap()
+        .into();
+    let borrow_rate: Decimal = wsol_reserve.account.current_borrow_rate().unwrap().into();
+    assert_eq!(reserve_rate_stats.account.bump_seed, bump_seed);
+    assert_eq!(reserve_rate_stats.account.reserve, wsol_reserve.pubkey);
+    assert_eq!(
+        reserve_rate_stats.account.average_utilization_rate,
+        utilization_rate
+    );
+    assert_eq!(reserve_rate_stats.account.average_borrow_rate, borrow_rate);
+
+    // only once
+    test.advance_clock_by_slots(1).await;
+    let res = lending_market
+        .init_reserve_rate_stats(&mut test, &wsol_reserve, &user)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::AlreadyInitialized as u32)
+        )
+    );
+
+    // a refresh after a full window replaces the averages with the rates that were in effect
+    test.advance_clock_by_slots(RESERVE_RATE_STATS_WINDOW_SLOTS)
+        .await;
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    lending_market
+        .refresh_reserve_with_rate_stats(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let reserve_rate_stats_post = test
+        .load_account::<ReserveRateStats>(reserve_rate_stats_pubkey)
+        .await;
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        reserve_rate_stats_post.account.last_update_slot,
+        wsol_reserve_post.account.last_update.slot
+    );
+    assert_eq!(
+        reserve_rate_stats_post.account.average_utilization_rate,
+        wsol_reserve
+            .account
+            .liquidity
+            .utilization_rate()
+            .unwrap()
+            .into()
+    );
+    assert_eq!(
+        reserve_rate_stats_post.account.average_borrow_rate,
+        wsol_reserve.account.current_borrow_rate().unwrap().into()
+    );
+
+    // the stats account has to belong to the refreshed reserve
+    lending_market
+        .init_reserve_rate_stats(&mut test, &usdc_reserve, &user)
+        .await
+        .unwrap();
+    let (usdc_reserve_rate_stats_pubkey, _) =
+        find_reserve_rate_stats_address(&solend_program::id(), &usdc_reserve.pubkey);
+    let mut instruction = refresh_reserve(
+        solend_program::id(),
+        wsol_reserve.pubkey,
+        wsol_reserve.account.liquidity.pyth_oracle_pubkey,
+        wsol_reserve.account.liquidity.switchboard_oracle_pubkey,
+        wsol_reserve.account.config.extra_oracle_pubkey,
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new(usdc_reserve_rate_stats_pubkey, false));
+
+    let res = test
+        .process_transaction(&[instruction], None)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
@@ -0,0 +1,324 @@
+#![cfg(feature = "test-bpf")]
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::User;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::transfer;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::math::{Decimal, TryAdd, TryMul, TrySub};
+use solend_program::state::{Obligation, PositionRewards, Reserve, RewardSide};
+use spl_token::state::Account as Token;
+
+mod helpers;
+
+use helpers::*;
+use solana_program_test::*;
+
+#[tokio::test]
+async fn test_success() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        _wsol_reserve,
+        mut user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // the lending market owner pays for the reward rule account
+    let instructions = [transfer(
+        &test.context.payer.pubkey(),
+        &lending_market_owner.keypair.pubkey(),
+        LAMPORTS_PER_SOL,
+    )];
+    test.process_transaction(&instructions, None).await.unwrap();
+
+    let (lending_market_authority, _) =
+        Pubkey::find_program_address(&[lending_market.pubkey.as_ref()], &solend_program::id());
+    let reward_supply = test
+        .create_token_account(&lending_market_authority, &bonk_mint::id())
+        .await;
+    test.mint_to(&bonk_mint::id(), &reward_supply, 1_000_000_000)
+        .await;
+    let destination = user.create_token_account(&bonk_mint::id(), &mut test).await;
+
+    let slot = test.get_clock().await.slot;
+    lending_market
+        .set_reward_rule(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            RewardSide::Deposit,
+            1_000,
+            slot,
+            slot + 1_000,
+            reward_supply,
+        )
+        .await
+        .unwrap();
+
+    let reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert_eq!(reserve.account.deposit_rewards.emission_per_slot, 1_000);
+    assert_eq!(reserve.account.deposit_rewards.start_slot, slot);
+    assert_eq!(reserve.account.deposit_rewards.end_slot, slot + 1_000);
+
+    test.advance_clock_by_slots(100).await;
+
+    lending_market
+        .update_rewards(&mut test, &usdc_reserve, &obligation)
+        .await
+        .unwrap();
+
+    let reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    let deposited_amount = obligation.account.deposits[0].deposited_amount;
+    let rewards = obligation_post.account.deposits[0].rewards;
+    assert!(reserve.account.deposit_rewards.reward_index > Decimal::zero());
+    assert_eq!(
+        rewards,
+        PositionRewards {
+            reward_index: reserve.account.deposit_rewards.reward_index,
+            unclaimed_rewards: reserve
+                .account
+                .deposit_rewards
+                .reward_index
+                .try_mul(Decimal::from(deposited_amount))
+                .unwrap(),
+        }
+    );
+
+    test.advance_clock_by_slots(100).await;
+
+    lending_market
+        .claim_rewards(
+            &mut test,
+            &usdc_reserve,
+            RewardSide::Deposit,
+            &obligation,
+            &user,
+            reward_supply,
+            destination.pubkey,
+        )
+        .await
+        .unwrap();
+
+    let reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+
+    let earned = reserve_post
+        .account
+        .deposit_rewards
+        .reward_index
+        .try_sub(reserve.account.deposit_rewards.reward_index)
+        .unwrap()
+        .try_mul(Decimal::from(deposited_amount))
+        .unwrap()
+        .try_add(rewards.unclaimed_rewards)
+        .unwrap();
+    let claimed = earned.try_floor_u64().unwrap();
+    assert!(claimed > 0);
+    assert_eq!(
+        obligation_post.account.deposits[0].rewards,
+        PositionRewards {
+            reward_index: reserve_post.account.deposit_rewards.reward_index,
+            unclaimed_rewards: earned.try_sub(Decimal::from(claimed)).unwrap(),
+        }
+    );
+
+    let destination_post = test.load_account::<Token>(destination.pubkey).await;
+    assert_eq!(destination_post.account.amount, claimed);
+    let reward_supply_post = test.load_account::<Token>(reward_supply).await;
+    assert_eq!(reward_supply_post.account.amount, 1_000_000_000 - claimed);
+}
+
+#[tokio::test]
+async fn test_withdraw_settles_rewards() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        _wsol_reserve,
+        user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let instructions = [transfer(
+        &test.context.payer.pubkey(),
+        &lending_market_owner.keypair.pubkey(),
+        LAMPORTS_PER_SOL,
+    )];
+    test.process_transaction(&instructions, None).await.unwrap();
+
+    let (lending_market_authority, _) =
+        Pubkey::find_program_address(&[lending_market.pubkey.as_ref()], &solend_program::id());
+    let reward_supply = test
+        .create_token_account(&lending_market_authority, &bonk_mint::id())
+        .await;
+
+    let slot = test.get_clock().await.slot;
+    lending_market
+        .set_reward_rule(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            RewardSide::Deposit,
+            1_000,
+            slot,
+            slot + 1_000,
+            reward_supply,
+        )
+        .await
+        .unwrap();
+
+    test.advance_clock_by_slots(100).await;
+
+    // the rewards earned on the full deposit are settled before the balance shrinks
+    lending_market
+        .withdraw_obligation_collateral(&mut test, &usdc_reserve, &obligation, &user, 1_000_000)
+        .await
+        .unwrap();
+
+    let reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    let obligation_post = test.load_account::<Obligation>(obligation.pubkey).await;
+    assert_eq!(
+        obligation_post.account.deposits[0].deposited_amount,
+        obligation.account.deposits[0].deposited_amount - 1_000_000
+    );
+    assert_eq!(
+        obligation_post.account.deposits[0].rewards,
+        PositionRewards {
+            reward_index: reserve.account.deposit_rewards.reward_index,
+            unclaimed_rewards: reserve
+                .account
+                .deposit_rewards
+                .reward_index
+                .try_mul(Decimal::from(
+                    obligation.account.deposits[0].deposited_amount
+                ))
+                .unwrap(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_fail_set_reward_rule_not_market_owner() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        _wsol_reserve,
+        user,
+        _obligation,
+        _lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let (lending_market_authority, _) =
+        Pubkey::find_program_address(&[lending_market.pubkey.as_ref()], &solend_program::id());
+    let reward_supply = test
+        .create_token_account(&lending_market_authority, &bonk_mint::id())
+        .await;
+
+    let err = lending_market
+        .set_reward_rule(
+            &mut test,
+            &usdc_reserve,
+            &user,
+            RewardSide::Deposit,
+            1_000,
+            0,
+            1_000,
+            reward_supply,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fail_claim_not_obligation_owner() {
+    let (
+        mut test,
+        lending_market,
+        usdc_reserve,
+        _wsol_reserve,
+        _user,
+        obligation,
+        lending_market_owner,
+    ) = scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let instructions = [transfer(
+        &test.context.payer.pubkey(),
+        &lending_market_owner.keypair.pubkey(),
+        LAMPORTS_PER_SOL,
+    )];
+    test.process_transaction(&instructions, None).await.unwrap();
+
+    let (lending_market_authority, _) =
+        Pubkey::find_program_address(&[lending_market.pubkey.as_ref()], &solend_program::id());
+    let reward_supply = test
+        .create_token_account(&lending_market_authority, &bonk_mint::id())
+        .await;
+    test.mint_to(&bonk_mint::id(), &reward_supply, 1_000_000_000)
+        .await;
+
+    let slot = test.get_clock().await.slot;
+    lending_market
+        .set_reward_rule(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            RewardSide::Deposit,
+            1_000,
+            slot,
+            slot + 1_000,
+            reward_supply,
+        )
+        .await
+        .unwrap();
+    lending_market
+        .update_rewards(&mut test, &usdc_reserve, &obligation)
+        .await
+        .unwrap();
+
+    test.advance_clock_by_slots(100).await;
+
+    let mut thief = User::new_with_balances(&mut test, &[(&bonk_mint::id(), 0)]).await;
+    let destination = thief
+        .create_token_account(&bonk_mint::id(), &mut test)
+        .await;
+    let err = lending_market
+        .claim_rewards(
+            &mut test,
+            &usdc_reserve,
+            RewardSide::Deposit,
+            &obligation,
+            &thief,
+            reward_supply,
+            destination.pubkey,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidObligationOwner as u32)
+        )
+    );
+}
@@ -0,0 +1,157 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use helpers::*;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::{Reserve, ReserveCollateral};
+use spl_token::state::Account as Token;
+
+#[tokio::test]
+async fn test_rotate_collateral_supply_success() {
+    let (mut test, lending_market, usdc_reserve, _, _user, _, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let lending_market_authority =
+        Pubkey::find_program_address(&[lending_market.pubkey.as_ref()], &solend_program::id()).0;
+
+    let old_collateral_supply = test
+        .load_account::<Token>(usdc_reserve.account.collateral.supply_pubkey)
+        .await;
+    assert!(old_collateral_supply.account.amount > 0);
+    let old_collateral_supply_account = test
+        .context
+        .banks_client
+        .get_account(old_collateral_supply.pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let new_collateral_supply = test
+        .create_token_account(
+            &lending_market_authority,
+            &usdc_reserve.account.collateral.mint_pubkey,
+        )
+        .await;
+    let owner_lamports = test
+        .context
+        .banks_client
+        .get_balance(lending_market_owner.keypair.pubkey())
+        .await
+        .unwrap();
+
+    lending_market
+        .rotate_reserve_collateral_supply(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            new_collateral_supply,
+        )
+        .await
+        .unwrap();
+
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert_eq!(
+        usdc_reserve_post.account,
+        Reserve {
+            collateral: ReserveCollateral {
+                supply_pubkey: new_collateral_supply,
+                ..usdc_reserve.account.collateral
+            },
+            ..usdc_reserve.account
+        }
+    );
+
+    let new_collateral_supply = test.load_account::<Token>(new_collateral_supply).await;
+    assert_eq!(
+        new_collateral_supply.account.amount,
+        old_collateral_supply.account.amount
+    );
+
+    // the old collateral supply is closed and its rent goes to the lending market owner
+    assert!(test
+        .context
+        .banks_client
+        .get_account(old_collateral_supply.pubkey)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        test.context
+            .banks_client
+            .get_balance(lending_market_owner.keypair.pubkey())
+            .await
+            .unwrap(),
+        owner_lamports + old_collateral_supply_account.lamports
+    );
+}
+
+#[tokio::test]
+async fn test_rotate_collateral_supply_not_owned_by_authority() {
+    let (mut test, lending_market, usdc_reserve, _, _user, _, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let new_collateral_supply = test
+        .create_token_account(
+            &lending_market_owner.keypair.pubkey(),
+            &usdc_reserve.account.collateral.mint_pubkey,
+        )
+        .await;
+
+    let err = lending_market
+        .rotate_reserve_collateral_supply(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            new_collateral_supply,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidTokenAccount as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_rotate_fee_receiver_permissionless_market() {
+    let (mut test, lending_market, usdc_reserve, _, _user, _, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let new_fee_receiver = test
+        .create_token_account(
+            &lending_market_owner.keypair.pubkey(),
+            &usdc_reserve.account.liquidity.mint_pubkey,
+        )
+        .await;
+
+    let err = lending_market
+        .rotate_reserve_fee_receiver(
+            &mut test,
+            &usdc_reserve,
+            &lending_market_owner,
+            new_fee_receiver,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidSigner as u32)
+        )
+    );
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use helpers::*;
+use solana_program::pubkey::Pubkey;
+use solana_program_test::*;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::LendingMarket;
+
+#[tokio::test]
+async fn test_set_default_host_fee_receiver() {
+    let (mut test, lending_market, _, _, _user, _, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let default_host_fee_receiver = Pubkey::new_unique();
+    lending_market
+        .set_default_host_fee_receiver(
+            &mut test,
+            &lending_market_owner,
+            Some(default_host_fee_receiver),
+        )
+        .await
+        .unwrap();
+
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert_eq!(
+        lending_market_post.account,
+        LendingMarket {
+            default_host_fee_receiver: Some(default_host_fee_receiver),
+            ..lending_market.account.clone()
+        }
+    );
+
+    // omitting the wallet clears it
+    lending_market
+        .set_default_host_fee_receiver(&mut test, &lending_market_owner, None)
+        .await
+        .unwrap();
+
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert_eq!(lending_market_post.account, lending_market.account);
+}
+
+#[tokio::test]
+async fn test_set_default_host_fee_receiver_not_owner() {
+    let (mut test, lending_market, _, _, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let err = lending_market
+        .set_default_host_fee_receiver(&mut test, &user, Some(Pubkey::new_unique()))
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
@@ -0,0 +1,162 @@
+#![cfg(feature = "test-bpf")]
+
+use crate::solend_program_test::setup_world;
+use crate::solend_program_test::PriceArgs;
+use solana_program::system_instruction::transfer;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::math::Decimal;
+use solend_program::state::{Reserve, MAX_EMERGENCY_PRICE_DURATION_SLOTS};
+
+mod helpers;
+
+use helpers::*;
+use solana_program_test::*;
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, _usdc_reserve, wsol_reserve, lending_market_owner, _user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    // the lending market owner pays for the emergency price account
+    let instructions = [transfer(
+        &test.context.payer.pubkey(),
+        &lending_market_owner.keypair.pubkey(),
+        LAMPORTS_PER_SOL,
+    )];
+    test.process_transaction(&instructions, None).await.unwrap();
+
+    // both oracles go stale
+    test.advance_clock_by_slots(300).await;
+
+    let err = lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+
+    lending_market
+        .set_emergency_price(
+            &mut test,
+            &wsol_reserve,
+            &lending_market_owner,
+            Decimal::from(8u64),
+            100,
+        )
+        .await
+        .unwrap();
+
+    lending_market
+        .refresh_reserve_with_emergency_price(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(wsol_reserve_post.account.emergency_price_active);
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.market_price,
+        Decimal::from(8u64)
+    );
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.smoothed_market_price,
+        Decimal::from(8u64)
+    );
+
+    // the emergency price expires
+    test.advance_clock_by_slots(100).await;
+
+    let err = lending_market
+        .refresh_reserve_with_emergency_price(&mut test, &wsol_reserve)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidOracleConfig as u32)
+        )
+    );
+
+    // once the oracles recover the emergency price is no longer used
+    test.advance_clock_by_slots(1).await;
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            conf: 0,
+            expo: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+
+    lending_market
+        .refresh_reserve_with_emergency_price(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(!wsol_reserve_post.account.emergency_price_active);
+    assert_eq!(
+        wsol_reserve_post.account.liquidity.market_price,
+        Decimal::from(10u64)
+    );
+}
+
+#[tokio::test]
+async fn test_fail_invalid_signer() {
+    let (mut test, lending_market, _usdc_reserve, wsol_reserve, _lending_market_owner, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    let err = lending_market
+        .set_emergency_price(&mut test, &wsol_reserve, &user, Decimal::from(8u64), 100)
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_fail_duration_too_long() {
+    let (mut test, lending_market, _usdc_reserve, wsol_reserve, lending_market_owner, _user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    let err = lending_market
+        .set_emergency_price(
+            &mut test,
+            &wsol_reserve,
+            &lending_market_owner,
+            Decimal::from(8u64),
+            MAX_EMERGENCY_PRICE_DURATION_SLOTS + 1,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidConfig as u32)
+        )
+    );
+}
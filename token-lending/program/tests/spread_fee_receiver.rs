@@ -0,0 +1,224 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::PriceArgs;
+use crate::solend_program_test::User;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::*;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::instruction::{redeem_fees, refresh_reserve};
+use solend_program::state::{
+    find_reserve_spread_fee_receiver_address, Reserve, ReserveConfig, ReserveFees,
+    ReserveSpreadFeeReceiver, SLOTS_PER_YEAR,
+};
+use spl_token::state::Account as Token;
+
+#[tokio::test]
+async fn test_spread_fee_receiver() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, lending_market_owner) =
+        scenario_1(
+            &test_reserve_config(),
+            &ReserveConfig {
+                protocol_take_rate: 10,
+                fees: ReserveFees {
+                    // 1%
+                    borrow_fee_wad: 10_000_000_000_000_000,
+                    ..test_reserve_config().fees
+                },
+                ..test_reserve_config()
+            },
+        )
+        .await;
+
+    let spread_fee_receiver =
+        User::new_with_balances(&mut test, &[(&wsol_mint::id(), 0), (&usdc_mint::id(), 0)]).await;
+    let spread_fee_receiver_pubkey = spread_fee_receiver.get_account(&wsol_mint::id()).unwrap();
+
+    // only the lending market owner can redirect the spread
+    let res = lending_market
+        .set_reserve_spread_fee_receiver(
+            &mut test,
+            &wsol_reserve,
+            &user,
+            spread_fee_receiver_pubkey,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    let res = lending_market
+        .set_reserve_spread_fee_receiver(
+            &mut test,
+            &wsol_reserve,
+            &lending_market_owner,
+            spread_fee_receiver.get_account(&usdc_mint::id()).unwrap(),
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidTokenMint as u32)
+        )
+    );
+
+    lending_market
+        .set_reserve_spread_fee_receiver(
+            &mut test,
+            &wsol_reserve,
+            &lending_market_owner,
+            spread_fee_receiver_pubkey,
+        )
+        .await
+        .unwrap();
+    let reserve_spread_fee_receiver = test
+        .load_account::<ReserveSpreadFeeReceiver>(
+            find_reserve_spread_fee_receiver_address(&solend_program::id(), &wsol_reserve.pubkey).0,
+        )
+        .await;
+    assert_eq!(
+        reserve_spread_fee_receiver.account.reserve,
+        wsol_reserve.pubkey
+    );
+    assert_eq!(
+        reserve_spread_fee_receiver.account.spread_fee_receiver,
+        spread_fee_receiver_pubkey
+    );
+
+    test.advance_clock_by_slots(SLOTS_PER_YEAR).await;
+    for (mint, price) in [(usdc_mint::id(), 1), (wsol_mint::id(), 10)] {
+        test.set_price(
+            &mint,
+            &PriceArgs {
+                price,
+                conf: 0,
+                expo: 0,
+                ema_price: price,
+                ema_conf: 0,
+            },
+        )
+        .await;
+    }
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    // deposit some liquidity so the fees can actually be redeemed
+    lending_market
+        .deposit(&mut test, &wsol_reserve, &user, LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+
+    // the spread fee receiver can't be skipped when redeeming the reserve's fees
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(wsol_reserve.account.has_spread_fee_receiver);
+    let res = test
+        .process_transaction(
+            &[
+                refresh_reserve(
+                    solend_program::id(),
+                    wsol_reserve.pubkey,
+                    wsol_reserve.account.liquidity.pyth_oracle_pubkey,
+                    wsol_reserve.account.liquidity.switchboard_oracle_pubkey,
+                    wsol_reserve.account.config.extra_oracle_pubkey,
+                ),
+                redeem_fees(
+                    solend_program::id(),
+                    wsol_reserve.pubkey,
+                    wsol_reserve.account.config.fee_receiver,
+                    wsol_reserve.account.liquidity.supply_pubkey,
+                    lending_market.pubkey,
+                ),
+            ],
+            None,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(1, InstructionError::NotEnoughAccountKeys)
+    );
+
+    // the spread goes to the spread fee receiver
+    let fee_receiver_balance = test
+        .load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+        .await
+        .account
+        .amount;
+    let spread = wsol_reserve.account.calculate_redeem_fees().unwrap();
+    assert!(spread > 0);
+    lending_market
+        .redeem_fees(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let spread_fee_receiver_amount = test
+        .load_account::<Token>(spread_fee_receiver_pubkey)
+        .await
+        .account
+        .amount;
+    assert!(spread_fee_receiver_amount >= spread);
+    assert_eq!(
+        test.load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+            .await
+            .account
+            .amount,
+        fee_receiver_balance
+    );
+
+    // origination fees still go to the fee receiver
+    lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve,
+            &obligation,
+            &user,
+            None,
+            LAMPORTS_PER_SOL / 10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        test.load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+            .await
+            .account
+            .amount,
+        fee_receiver_balance + LAMPORTS_PER_SOL / 1000
+    );
+    assert_eq!(
+        test.load_account::<Token>(spread_fee_receiver_pubkey)
+            .await
+            .account
+            .amount,
+        spread_fee_receiver_amount
+    );
+
+    // passing the fee receiver sends the spread back to it
+    lending_market
+        .set_reserve_spread_fee_receiver(
+            &mut test,
+            &wsol_reserve,
+            &lending_market_owner,
+            wsol_reserve.account.config.fee_receiver,
+        )
+        .await
+        .unwrap();
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(!wsol_reserve.account.has_spread_fee_receiver);
+}
@@ -161,6 +161,75 @@ async fn test_update_reserve_config_risk_authority() {
     );
 }
 
+#[tokio::test]
+async fn test_risk_authority_tightens_ltv_and_borrower_share() {
+    let (mut test, lending_market, lending_market_owner) = setup().await;
+
+    let wsol_reserve = test
+        .init_reserve(
+            &lending_market,
+            &lending_market_owner,
+            &wsol_mint::id(),
+            &test_reserve_config(),
+            &Keypair::new(),
+            1000,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let risk_authority = User::new_with_keypair(Keypair::new());
+    let err = lending_market
+        .set_risk_authority(&mut test, &risk_authority, risk_authority.keypair.pubkey())
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+
+    lending_market
+        .set_risk_authority(
+            &mut test,
+            &lending_market_owner,
+            risk_authority.keypair.pubkey(),
+        )
+        .await
+        .unwrap();
+
+    let new_reserve_config = ReserveConfig {
+        loan_to_value_ratio: wsol_reserve.account.config.loan_to_value_ratio - 10,
+        max_single_borrower_share_bps: 1_000,
+        ..wsol_reserve.account.config
+    };
+
+    lending_market
+        .update_reserve_config(
+            &mut test,
+            &risk_authority,
+            &wsol_reserve,
+            new_reserve_config,
+            wsol_reserve.account.rate_limiter.config,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(
+        wsol_reserve_post.account.config,
+        ReserveConfig {
+            loan_to_value_ratio: wsol_reserve.account.config.loan_to_value_ratio - 10,
+            max_single_borrower_share_bps: 1_000,
+            ..wsol_reserve.account.config
+        }
+    );
+}
+
 #[tokio::test]
 async fn test_update_invalid_oracle_config() {
     let (mut test, lending_market, lending_market_owner) = setup().await;
@@ -0,0 +1,123 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use helpers::*;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::*;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::Reserve;
+
+#[tokio::test]
+async fn test_kill_switch() {
+    let (mut test, lending_market, _, wsol_reserve, user, obligation, lending_market_owner) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // the wsol reserve is fully borrowed
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+    lending_market
+        .set_reserve_utilization_kill_switch(
+            &mut test,
+            &wsol_reserve,
+            &lending_market_owner,
+            90,
+            10,
+        )
+        .await
+        .unwrap();
+
+    // the slots since the last refresh are counted, however few refreshes there are
+    test.advance_clock_by_slots(4).await;
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(wsol_reserve_post.account.high_utilization_slots, 4);
+    assert!(!wsol_reserve_post.account.emergency_mode);
+
+    test.advance_clock_by_slots(6).await;
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert_eq!(wsol_reserve_post.account.high_utilization_slots, 10);
+    assert!(wsol_reserve_post.account.emergency_mode);
+
+    // deposits still work so utilization can come back down
+    lending_market
+        .deposit(&mut test, &wsol_reserve_post, &user, LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+
+    // borrows are blocked
+    let err = lending_market
+        .borrow_obligation_liquidity(
+            &mut test,
+            &wsol_reserve_post,
+            &obligation,
+            &user,
+            None,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::ReserveEmergencyMode as u32)
+        )
+    );
+
+    // repays still work
+    lending_market
+        .repay_obligation_liquidity(
+            &mut test,
+            &wsol_reserve_post,
+            &obligation,
+            &user,
+            LAMPORTS_PER_SOL,
+        )
+        .await
+        .unwrap();
+
+    // the owner can take the reserve out of emergency mode
+    lending_market
+        .set_reserve_utilization_kill_switch(&mut test, &wsol_reserve, &lending_market_owner, 0, 0)
+        .await
+        .unwrap();
+
+    let wsol_reserve_post = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    assert!(!wsol_reserve_post.account.emergency_mode);
+    assert_eq!(wsol_reserve_post.account.high_utilization_slots, 0);
+}
+
+#[tokio::test]
+async fn test_kill_switch_invalid_owner() {
+    let (mut test, lending_market, _, wsol_reserve, user, _, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let err = lending_market
+        .set_reserve_utilization_kill_switch(&mut test, &wsol_reserve, &user, 90, 2)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
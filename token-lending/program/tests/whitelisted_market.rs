@@ -0,0 +1,144 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use helpers::solend_program_test::setup_world;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::{
+    find_market_member_address, LendingMarket, MarketMember, PROGRAM_VERSION,
+};
+
+#[tokio::test]
+async fn test_whitelisted_market() {
+    let (mut test, lending_market, usdc_reserve, _, lending_market_owner, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    lending_market
+        .set_whitelist_required(&mut test, &lending_market_owner, true)
+        .await
+        .unwrap();
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert!(lending_market_post.account.whitelist_required);
+
+    // wallets without a market member account can't open obligations or deposit
+    let res = lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(LendingError::NotMarketMember as u32)
+        )
+    );
+
+    let res = lending_market
+        .deposit(&mut test, &usdc_reserve, &user, FRACTIONAL_TO_USDC)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::NotMarketMember as u32)
+        )
+    );
+
+    lending_market
+        .update_market_member(
+            &mut test,
+            &lending_market_owner,
+            user.keypair.pubkey(),
+            true,
+        )
+        .await
+        .unwrap();
+    test.advance_clock_by_slots(1).await;
+
+    let (market_member_pubkey, bump_seed) = find_market_member_address(
+        &solend_program::id(),
+        &lending_market.pubkey,
+        &user.keypair.pubkey(),
+    );
+    let market_member = test
+        .load_account::<MarketMember>(market_member_pubkey)
+        .await;
+    assert_eq!(
+        market_member.account,
+        MarketMember {
+            version: PROGRAM_VERSION,
+            bump_seed,
+            lending_market: lending_market.pubkey,
+            member: user.keypair.pubkey(),
+            is_member: true,
+        }
+    );
+
+    lending_market
+        .init_obligation(&mut test, Keypair::new(), &user)
+        .await
+        .unwrap();
+    lending_market
+        .deposit(&mut test, &usdc_reserve, &user, FRACTIONAL_TO_USDC)
+        .await
+        .unwrap();
+
+    // revoked members are turned away again
+    lending_market
+        .update_market_member(
+            &mut test,
+            &lending_market_owner,
+            user.keypair.pubkey(),
+            false,
+        )
+        .await
+        .unwrap();
+    test.advance_clock_by_slots(1).await;
+
+    let res = lending_market
+        .deposit(&mut test, &usdc_reserve, &user, FRACTIONAL_TO_USDC)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::NotMarketMember as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_update_market_member_not_owner() {
+    let (mut test, lending_market, _, _, _, user) =
+        setup_world(&test_reserve_config(), &test_reserve_config()).await;
+
+    let res = lending_market
+        .update_market_member(&mut test, &user, user.keypair.pubkey(), true)
+        .await
+        .err()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(LendingError::InvalidMarketOwner as u32)
+        )
+    );
+}
@@ -2,16 +2,21 @@
 
 mod helpers;
 
+use crate::solend_program_test::custom_scenario;
 use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::ObligationArgs;
+use crate::solend_program_test::PriceArgs;
+use crate::solend_program_test::ReserveArgs;
 use helpers::solend_program_test::{BalanceChecker, TokenBalanceChange};
 use helpers::*;
+use solana_sdk::signer::Signer;
+use solend_program::instruction::withdraw_obligation_collateral;
 use solend_sdk::math::Decimal;
 
 use solana_program_test::*;
 
 use solend_program::state::{LastUpdate, Obligation, ObligationCollateral, Reserve};
 use std::collections::HashSet;
-use std::u64;
 
 #[tokio::test]
 async fn test_success_withdraw_fixed_amount() {
@@ -133,3 +138,78 @@ async fn test_success_withdraw_max() {
         }
     );
 }
+
+#[tokio::test]
+async fn test_success_withdraw_without_borrows_without_refresh() {
+    let (mut test, lending_market, reserves, obligations, users, _lending_market_owner) =
+        custom_scenario(
+            &[ReserveArgs {
+                mint: usdc_mint::id(),
+                config: reserve_config_no_fees(),
+                liquidity_amount: 100_000 * FRACTIONAL_TO_USDC,
+                price: PriceArgs {
+                    price: 10,
+                    conf: 0,
+                    expo: -1,
+                    ema_price: 10,
+                    ema_conf: 1,
+                },
+            }],
+            &[ObligationArgs {
+                deposits: vec![(usdc_mint::id(), 20 * FRACTIONAL_TO_USDC)],
+                borrows: vec![],
+            }],
+        )
+        .await;
+
+    // neither the reserve nor the obligation is refreshed in this slot
+    test.advance_clock_by_slots(1).await;
+
+    let usdc_reserve = &reserves[0];
+    let balance_checker = BalanceChecker::start(&mut test, &[usdc_reserve, &users[0]]).await;
+
+    test.process_transaction(
+        &[withdraw_obligation_collateral(
+            solend_program::id(),
+            u64::MAX,
+            usdc_reserve.account.collateral.supply_pubkey,
+            users[0]
+                .get_account(&usdc_reserve.account.collateral.mint_pubkey)
+                .unwrap(),
+            usdc_reserve.pubkey,
+            obligations[0].pubkey,
+            lending_market.pubkey,
+            users[0].keypair.pubkey(),
+            vec![usdc_reserve.pubkey],
+        )],
+        Some(&[&users[0].keypair]),
+    )
+    .await
+    .unwrap();
+
+    let (balance_changes, _) = balance_checker.find_balance_changes(&mut test).await;
+    let expected_balance_changes = HashSet::from([
+        TokenBalanceChange {
+            token_account: users[0]
+                .get_account(&usdc_reserve.account.collateral.mint_pubkey)
+                .unwrap(),
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: (20 * FRACTIONAL_TO_USDC) as i128,
+        },
+        TokenBalanceChange {
+            token_account: usdc_reserve.account.collateral.supply_pubkey,
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: -((20 * FRACTIONAL_TO_USDC) as i128),
+        },
+    ]);
+    assert_eq!(balance_changes, expected_balance_changes);
+
+    // interest was accrued, but the reserve still needs a price refresh before it's used
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    let slot = test.get_clock().await.slot;
+    assert_eq!(usdc_reserve_post.account.last_update.slot, slot);
+    assert!(usdc_reserve_post.account.last_update.stale);
+
+    let obligation_post = test.load_account::<Obligation>(obligations[0].pubkey).await;
+    assert!(obligation_post.account.deposits.is_empty());
+}
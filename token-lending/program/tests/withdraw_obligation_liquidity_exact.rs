@@ -0,0 +1,141 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::*;
+use helpers::*;
+use solana_program_test::*;
+use solend_sdk::state::Reserve;
+use std::collections::HashSet;
+
+#[tokio::test]
+async fn test_success() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    let balance_checker =
+        BalanceChecker::start(&mut test, &[&usdc_reserve, &user, &wsol_reserve]).await;
+
+    lending_market
+        .withdraw_obligation_liquidity_exact(
+            &mut test,
+            &usdc_reserve,
+            &obligation,
+            &user,
+            1_000 * FRACTIONAL_TO_USDC,
+        )
+        .await
+        .unwrap();
+
+    // the exchange rate is still 1, so as much collateral as liquidity is burned
+    let withdraw_amount = (1_000 * FRACTIONAL_TO_USDC) as i128;
+    let (balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    let expected_balance_changes = HashSet::from([
+        TokenBalanceChange {
+            token_account: user.get_account(&usdc_mint::id()).unwrap(),
+            mint: usdc_mint::id(),
+            diff: withdraw_amount,
+        },
+        TokenBalanceChange {
+            token_account: usdc_reserve.account.liquidity.supply_pubkey,
+            mint: usdc_mint::id(),
+            diff: -withdraw_amount,
+        },
+        TokenBalanceChange {
+            token_account: usdc_reserve.account.collateral.supply_pubkey,
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: -withdraw_amount,
+        },
+    ]);
+    assert_eq!(balance_changes, expected_balance_changes);
+    assert_eq!(
+        mint_supply_changes,
+        HashSet::from([MintSupplyChange {
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: -withdraw_amount
+        }])
+    );
+
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert_eq!(
+        usdc_reserve_post.account.liquidity.available_amount,
+        usdc_reserve.account.liquidity.available_amount - withdraw_amount as u64
+    );
+}
+
+#[tokio::test]
+async fn test_success_pays_out_exact_amount() {
+    let (mut test, lending_market, usdc_reserve, wsol_reserve, user, obligation, _) =
+        scenario_1(&test_reserve_config(), &test_reserve_config()).await;
+
+    // donate until a cToken redeems for 1.9 USDC
+    assert_eq!(
+        usdc_reserve.account.liquidity.available_amount,
+        usdc_reserve.account.collateral.mint_total_supply
+    );
+    let donation = usdc_reserve.account.collateral.mint_total_supply / 10 * 9;
+    let whale = User::new_with_balances(&mut test, &[(&usdc_mint::id(), donation)]).await;
+    lending_market
+        .donate_to_reserve(&mut test, &usdc_reserve, &whale, donation)
+        .await
+        .unwrap();
+    let usdc_reserve = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+
+    // 54 cTokens are needed for 101 USDC, but they redeem for 102
+    let collateral_exchange_rate = usdc_reserve.account.collateral_exchange_rate().unwrap();
+    assert_eq!(
+        collateral_exchange_rate
+            .liquidity_to_collateral_ceil(101)
+            .unwrap(),
+        54
+    );
+    assert_eq!(
+        collateral_exchange_rate
+            .collateral_to_liquidity(54)
+            .unwrap(),
+        102
+    );
+
+    let balance_checker =
+        BalanceChecker::start(&mut test, &[&usdc_reserve, &user, &wsol_reserve]).await;
+
+    lending_market
+        .withdraw_obligation_liquidity_exact(&mut test, &usdc_reserve, &obligation, &user, 101)
+        .await
+        .unwrap();
+
+    let (balance_changes, mint_supply_changes) =
+        balance_checker.find_balance_changes(&mut test).await;
+    let expected_balance_changes = HashSet::from([
+        TokenBalanceChange {
+            token_account: user.get_account(&usdc_mint::id()).unwrap(),
+            mint: usdc_mint::id(),
+            diff: 101,
+        },
+        TokenBalanceChange {
+            token_account: usdc_reserve.account.liquidity.supply_pubkey,
+            mint: usdc_mint::id(),
+            diff: -101,
+        },
+        TokenBalanceChange {
+            token_account: usdc_reserve.account.collateral.supply_pubkey,
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: -54,
+        },
+    ]);
+    assert_eq!(balance_changes, expected_balance_changes);
+    assert_eq!(
+        mint_supply_changes,
+        HashSet::from([MintSupplyChange {
+            mint: usdc_reserve.account.collateral.mint_pubkey,
+            diff: -54
+        }])
+    );
+
+    let usdc_reserve_post = test.load_account::<Reserve>(usdc_reserve.pubkey).await;
+    assert_eq!(
+        usdc_reserve_post.account.liquidity.available_amount,
+        usdc_reserve.account.liquidity.available_amount - 101
+    );
+}
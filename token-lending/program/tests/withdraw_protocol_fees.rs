@@ -0,0 +1,181 @@
+#![cfg(feature = "test-bpf")]
+
+mod helpers;
+
+use crate::solend_program_test::scenario_1;
+use crate::solend_program_test::PriceArgs;
+use crate::solend_program_test::User;
+use helpers::*;
+use solana_program::instruction::InstructionError;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_program_test::*;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::TransactionError;
+use solend_program::error::LendingError;
+use solend_program::state::{
+    LendingMarket, RateLimiterConfig, Reserve, ReserveConfig, SLOTS_PER_YEAR,
+};
+use spl_token::state::Account as Token;
+
+#[tokio::test]
+async fn test_withdraw_protocol_fees() {
+    let (mut test, lending_market, _, wsol_reserve, user, _, lending_market_owner) = scenario_1(
+        &test_reserve_config(),
+        &ReserveConfig {
+            protocol_take_rate: 10,
+            ..test_reserve_config()
+        },
+    )
+    .await;
+
+    test.advance_clock_by_slots(SLOTS_PER_YEAR).await;
+    test.set_price(
+        &wsol_mint::id(),
+        &PriceArgs {
+            price: 10,
+            expo: 0,
+            conf: 0,
+            ema_price: 10,
+            ema_conf: 0,
+        },
+    )
+    .await;
+    lending_market
+        .refresh_reserve(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+    lending_market
+        .deposit(&mut test, &wsol_reserve, &user, LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+
+    let wsol_reserve = test.load_account::<Reserve>(wsol_reserve.pubkey).await;
+    lending_market
+        .redeem_fees(&mut test, &wsol_reserve)
+        .await
+        .unwrap();
+    let fees = test
+        .load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+        .await
+        .account
+        .amount;
+    assert!(fees > 0);
+
+    let fee_authority = User::new_with_balances(&mut test, &[]).await;
+    let treasury = User::new_with_balances(&mut test, &[(&wsol_mint::id(), 0)]).await;
+    let treasury_token_pubkey = test
+        .create_associated_token_account(&treasury.keypair.pubkey(), &wsol_mint::id())
+        .await;
+
+    // nothing can be withdrawn while the rate limiter allows no outflow
+    lending_market
+        .set_protocol_fee_config(
+            &mut test,
+            &lending_market_owner,
+            fee_authority.keypair.pubkey(),
+            treasury.keypair.pubkey(),
+            RateLimiterConfig {
+                window_duration: 10,
+                max_outflow: 0,
+            },
+        )
+        .await
+        .unwrap();
+
+    let lending_market_post = test
+        .load_account::<LendingMarket>(lending_market.pubkey)
+        .await;
+    assert_eq!(
+        lending_market_post.account.fee_authority,
+        fee_authority.keypair.pubkey()
+    );
+    assert_eq!(
+        lending_market_post.account.treasury,
+        treasury.keypair.pubkey()
+    );
+
+    let res = lending_market
+        .withdraw_protocol_fees(
+            &mut test,
+            &wsol_reserve,
+            &fee_authority,
+            treasury_token_pubkey,
+            fees,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::OutflowRateLimitExceeded as u32)
+        )
+    );
+
+    lending_market
+        .set_protocol_fee_config(
+            &mut test,
+            &lending_market_owner,
+            fee_authority.keypair.pubkey(),
+            treasury.keypair.pubkey(),
+            RateLimiterConfig {
+                window_duration: 10,
+                max_outflow: u64::MAX,
+            },
+        )
+        .await
+        .unwrap();
+
+    // fees can only go to the treasury's associated token account
+    let res = lending_market
+        .withdraw_protocol_fees(
+            &mut test,
+            &wsol_reserve,
+            &fee_authority,
+            treasury.get_account(&wsol_mint::id()).unwrap(),
+            fees,
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+
+    // only the fee authority can withdraw
+    let res = lending_market
+        .withdraw_protocol_fees(&mut test, &wsol_reserve, &user, treasury_token_pubkey, fees)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        res,
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(LendingError::InvalidAccountInput as u32)
+        )
+    );
+
+    lending_market
+        .withdraw_protocol_fees(
+            &mut test,
+            &wsol_reserve,
+            &fee_authority,
+            treasury_token_pubkey,
+            u64::MAX,
+        )
+        .await
+        .unwrap();
+
+    let fee_receiver = test
+        .load_account::<Token>(wsol_reserve.account.config.fee_receiver)
+        .await;
+    assert_eq!(fee_receiver.account.amount, 0);
+    let treasury_token = test.load_account::<Token>(treasury_token_pubkey).await;
+    assert_eq!(treasury_token.account.amount, fees);
+}
@@ -4,6 +4,8 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use solana_program::{decode_error::DecodeError, program_error::ProgramError};
 use solana_program::{msg, program_error::PrintProgramError};
+#[cfg(feature = "client")]
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
 use thiserror::Error;
 
 /// Errors that may be returned by the TokenLending program.
@@ -183,8 +185,8 @@ pub enum LendingError {
     /// No cpi flash repays allowed
     #[error("No cpi flash repays allowed")]
     FlashRepayCpi,
-    /// Multiple flash borrows not allowed in the same transaction
-    #[error("Multiple flash borrows not allowed in the same transaction")]
+    /// Multiple flash repays found for the same flash borrow
+    #[error("Multiple flash repays found for the same flash borrow")]
     MultipleFlashBorrows,
     /// Flash loans are disabled for this reserve
     #[error("Flash loans are disabled for this reserve")]
@@ -209,6 +211,333 @@ pub enum LendingError {
     /// Borrow Attribution Limit Not Exceeded
     #[error("Borrow Attribution Limit Not Exceeded")]
     BorrowAttributionLimitNotExceeded,
+    /// Token close account failed
+    #[error("Token close account failed")]
+    TokenCloseAccountFailed,
+    /// Reserve is in emergency mode
+    #[error("Reserve is in emergency mode")]
+    ReserveEmergencyMode,
+
+    // 60
+    /// Spot price is outside the liquidation price band
+    #[error("Spot price deviates too far from the smoothed price to liquidate")]
+    PriceOutsideLiquidationBand,
+    /// Obligation owner has locked new borrows
+    #[error("Obligation owner has locked new borrows")]
+    ObligationBorrowsLocked,
+    /// Borrow would exceed the reserve's single borrower share limit
+    #[error("Borrow would exceed the reserve's single borrower share limit")]
+    BorrowerConcentrationLimitExceeded,
+    /// This operation is paused on the reserve
+    #[error("This operation is paused on the reserve")]
+    ReserveOperationPaused,
+    /// Obligation owner has reached the lending market's obligation limit
+    #[error("Obligation owner has reached the lending market's obligation limit")]
+    ObligationLimitReached,
+    /// Borrow would leave the obligation's borrow from the reserve below the reserve's minimum
+    #[error("Borrow would leave a borrow worth less than the reserve's min borrow value")]
+    BorrowBelowMinValue,
+    /// Reserve still has borrows, collateral or fees and cannot be closed
+    #[error("Reserve still has borrows, collateral or fees and cannot be closed")]
+    ReserveNotEmpty,
+    /// Flash borrows exceed the reserve's max flash loan amount
+    #[error("Flash borrows exceed the reserve's max flash loan amount")]
+    FlashLoanTooLarge,
+    /// Deposit exceeds the reserve's deposit limit
+    #[error("Deposit exceeds the reserve's deposit limit")]
+    DepositLimitExceeded,
+    /// Account was written by a newer version of the lending program
+    #[error("Account was written by a newer version of the lending program")]
+    AccountVersionNotSupported,
+    /// Borrow would take the obligation over its borrow cap
+    #[error("Borrow would take the obligation over its borrow cap")]
+    ObligationBorrowCapExceeded,
+    /// Wallet is not a member of a lending market that requires a whitelist
+    #[error("Wallet is not a member of the whitelisted lending market")]
+    NotMarketMember,
+    /// Borrow would receive less liquidity than the borrower's minimum
+    #[error("Borrow would receive less liquidity than the minimum")]
+    BorrowSlippageExceeded,
+    /// Deposit or redeem would receive less than the user's minimum at the exchange rate
+    #[error("Deposit or redeem would receive less than the minimum at the exchange rate")]
+    ExchangeRateSlippageExceeded,
+    /// Reserve doesn't offer borrow rate caps
+    #[error("Reserve doesn't offer borrow rate caps")]
+    RateCapNotOffered,
+    /// Borrow has a rate cap, which fixes the amount it covers
+    #[error("Borrow has a rate cap")]
+    BorrowRateCapped,
+    /// Account still has the version 1 layout, which has no room for the change
+    #[error("Account must be migrated to the current layout")]
+    AccountNotMigrated,
+}
+
+impl LendingError {
+    /// Returns the lending error a failed transaction was rejected with, if any. A `Custom` error
+    /// code raised by a CPI into another program (eg the token program) will also be mapped, so
+    /// callers should only use this on transactions whose failing instruction targets the lending
+    /// program.
+    #[cfg(feature = "client")]
+    pub fn from_instruction_error(error: &TransactionError) -> Option<Self> {
+        match error {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+                Self::from_u32(*code)
+            }
+            _ => None,
+        }
+    }
+
+    /// Human readable description of the error, for display to end users
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::InstructionUnpackError => "The instruction data could not be decoded.",
+            Self::AlreadyInitialized => "The account has already been initialized.",
+            Self::NotRentExempt => "The account does not hold enough lamports to be rent exempt.",
+            Self::InvalidMarketAuthority => {
+                "The lending market authority does not match the one derived from the market."
+            }
+            Self::InvalidMarketOwner => "The signer is not the owner of the lending market.",
+            Self::InvalidAccountOwner => "An account is not owned by the lending program.",
+            Self::InvalidTokenOwner => "A token account is not owned by the token program.",
+            Self::InvalidTokenAccount => "A token account is not valid for this instruction.",
+            Self::InvalidTokenMint => "A token account has the wrong mint.",
+            Self::InvalidTokenProgram => "The token program provided is not the SPL token program.",
+            Self::InvalidAmount => "The amount must be greater than zero.",
+            Self::InvalidConfig => "A config value is out of range.",
+            Self::InvalidSigner => "A required signature is missing or from the wrong account.",
+            Self::InvalidAccountInput => {
+                "One of the accounts provided is not the expected account."
+            }
+            Self::MathOverflow => "A calculation overflowed.",
+            Self::TokenInitializeMintFailed => "Initializing a token mint failed.",
+            Self::TokenInitializeAccountFailed => "Initializing a token account failed.",
+            Self::TokenTransferFailed => "A token transfer failed.",
+            Self::TokenMintToFailed => "Minting tokens failed.",
+            Self::TokenBurnFailed => "Burning tokens failed.",
+            Self::TokenCloseAccountFailed => "Closing a token account failed.",
+            Self::InsufficientLiquidity => "The reserve does not have enough available liquidity.",
+            Self::ReserveCollateralDisabled => "This reserve cannot be used as collateral.",
+            Self::ReserveStale => "The reserve was not refreshed in this slot.",
+            Self::WithdrawTooSmall => "The withdraw amount rounds down to zero.",
+            Self::WithdrawTooLarge => {
+                "The withdraw would leave the obligation undercollateralized."
+            }
+            Self::BorrowTooSmall => "The borrow amount is too small to cover the borrow fees.",
+            Self::BorrowTooLarge => "The borrow exceeds the obligation's remaining borrow limit.",
+            Self::RepayTooSmall => "The repay amount rounds down to zero.",
+            Self::LiquidationTooSmall => {
+                "The liquidation amount is too small to receive collateral."
+            }
+            Self::ObligationHealthy => "The obligation is healthy and cannot be liquidated.",
+            Self::ObligationStale => "The obligation was not refreshed in this slot.",
+            Self::ObligationReserveLimit => {
+                "The obligation has reached its maximum number of positions."
+            }
+            Self::InvalidObligationOwner => "The signer is not the owner of the obligation.",
+            Self::ObligationDepositsEmpty => "The obligation has no deposits.",
+            Self::ObligationBorrowsEmpty => "The obligation has no borrows.",
+            Self::ObligationDepositsZero => "The obligation's deposits have no market value.",
+            Self::ObligationBorrowsZero => "The obligation's borrows have no market value.",
+            Self::InvalidObligationCollateral => "The obligation has no deposit in this reserve.",
+            Self::InvalidObligationLiquidity => "The obligation has no borrow from this reserve.",
+            Self::ObligationCollateralEmpty => "The obligation's deposit in this reserve is empty.",
+            Self::ObligationLiquidityEmpty => "The obligation's borrow from this reserve is empty.",
+            Self::NegativeInterestRate => "The reserve's interest rate became negative.",
+            Self::InvalidOracleConfig => "The reserve's price oracle is invalid or unavailable.",
+            Self::InvalidFlashLoanReceiverProgram => "The flash loan receiver program is invalid.",
+            Self::NotEnoughLiquidityAfterFlashLoan => "The flash loan was not fully repaid.",
+            Self::NullOracleConfig => "The reserve has no price oracle configured.",
+            Self::InsufficientProtocolFeesToRedeem => {
+                "There are no protocol fees, or no liquidity, available to redeem."
+            }
+            Self::FlashBorrowCpi => "Flash borrows cannot be made through a CPI.",
+            Self::NoFlashRepayFound => "The flash borrow has no matching flash repay.",
+            Self::InvalidFlashRepay => "The flash repay does not match the flash borrow.",
+            Self::FlashRepayCpi => "Flash repays cannot be made through a CPI.",
+            Self::MultipleFlashBorrows => "A flash borrow has more than one matching flash repay.",
+            Self::FlashLoansDisabled => "Flash loans are disabled for this reserve.",
+            Self::DeprecatedInstruction => "This instruction is no longer supported.",
+            Self::OutflowRateLimitExceeded => "The market's outflow rate limit has been reached.",
+            Self::NotWhitelistedLiquidator => {
+                "Only whitelisted liquidators can liquidate this obligation."
+            }
+            Self::IsolatedTierAssetViolation => {
+                "Isolated tier assets can only be borrowed on their own and can't be used as collateral."
+            }
+            Self::BorrowAttributionLimitExceeded => {
+                "The borrow would exceed the collateral's attributed borrow limit."
+            }
+            Self::BorrowAttributionLimitNotExceeded => {
+                "The collateral's attributed borrow limit has not been exceeded."
+            }
+            Self::ReserveEmergencyMode => "The reserve is in emergency mode and doesn't allow this right now.",
+            Self::PriceOutsideLiquidationBand => {
+                "The collateral's spot price is too far from its average price to liquidate."
+            }
+            Self::ObligationBorrowsLocked => "The obligation's owner has locked new borrows.",
+            Self::BorrowerConcentrationLimitExceeded => {
+                "A single obligation may not borrow more than this share of the reserve."
+            }
+            Self::ReserveOperationPaused => {
+                "The reserve's owner has paused this operation on the reserve."
+            }
+            Self::ObligationLimitReached => {
+                "The lending market caps how many obligations a wallet can create."
+            }
+            Self::BorrowBelowMinValue => {
+                "The reserve doesn't allow borrows worth less than its minimum borrow value."
+            }
+            Self::ReserveNotEmpty => {
+                "The reserve still has borrows, collateral or protocol fees outstanding."
+            }
+            Self::FlashLoanTooLarge => {
+                "The transaction's flash borrows from the reserve exceed the most it lends in flash loans at once."
+            }
+            Self::DepositLimitExceeded => {
+                "The deposit would take the reserve's total supply above its deposit limit."
+            }
+            Self::AccountVersionNotSupported => {
+                "An account was migrated by a newer program version than the one that ran."
+            }
+            Self::ObligationBorrowCapExceeded => {
+                "The obligation owner capped its borrowed value below what the borrow needs."
+            }
+            Self::NotMarketMember => {
+                "This lending market only accepts wallets whitelisted by its owner."
+            }
+            Self::BorrowSlippageExceeded => {
+                "Fees or borrow limits changed, and the borrow would receive less than the minimum."
+            }
+            Self::ExchangeRateSlippageExceeded => {
+                "The collateral exchange rate moved, and the user would receive less than the minimum."
+            }
+            Self::RateCapNotOffered => "This reserve doesn't sell borrow rate caps.",
+            Self::BorrowRateCapped => {
+                "The borrow already has a rate cap, which only covers the amount it was bought for."
+            }
+            Self::AccountNotMigrated => {
+                "An account still has the layout of an older program version, which can't store this change."
+            }
+        }
+    }
+
+    /// Suggested action for the end user, for display alongside the description
+    pub fn suggested_action(&self) -> &'static str {
+        match self {
+            Self::ReserveStale | Self::ObligationStale => {
+                "Refresh the reserves and the obligation in the same transaction and retry."
+            }
+            Self::InvalidAmount
+            | Self::WithdrawTooSmall
+            | Self::RepayTooSmall
+            | Self::BorrowTooSmall
+            | Self::BorrowBelowMinValue
+            | Self::LiquidationTooSmall => "Increase the amount and retry.",
+            Self::FlashLoanTooLarge => "Reduce the flash borrowed amounts and retry.",
+            Self::DepositLimitExceeded => {
+                "Reduce the amount to the reserve's remaining deposit capacity and retry."
+            }
+            Self::AccountVersionNotSupported => {
+                "Wait for the program upgrade to finish rolling out and retry."
+            }
+            Self::ObligationBorrowCapExceeded => {
+                "Reduce the amount, or have the owner raise the borrow cap and retry."
+            }
+            Self::NotMarketMember => "Ask the lending market owner to whitelist your wallet.",
+            Self::RateCapNotOffered => "Borrow without a rate cap or use a different reserve.",
+            Self::BorrowRateCapped => {
+                "Claim the rate cap credit and repay the capped borrow, or use a new obligation."
+            }
+            Self::AccountNotMigrated => "Migrate the account with MigrateAccount and retry.",
+            Self::BorrowSlippageExceeded | Self::ExchangeRateSlippageExceeded => {
+                "Lower the minimum or the amount and retry."
+            }
+            Self::WithdrawTooLarge | Self::BorrowTooLarge => {
+                "Reduce the amount, or deposit more collateral and retry."
+            }
+            Self::InsufficientLiquidity | Self::OutflowRateLimitExceeded => {
+                "Reduce the amount or wait for more liquidity to become available."
+            }
+            Self::ReserveCollateralDisabled => "Deposit a different asset as collateral.",
+            Self::ObligationHealthy => "No action needed - the obligation is not liquidatable.",
+            Self::ObligationReserveLimit => {
+                "Close an existing position or use a new obligation."
+            }
+            Self::InvalidObligationOwner | Self::InvalidSigner | Self::InvalidMarketOwner => {
+                "Sign the transaction with the correct wallet."
+            }
+            Self::ObligationDepositsEmpty | Self::ObligationDepositsZero => {
+                "Deposit collateral before borrowing."
+            }
+            Self::ObligationBorrowsEmpty
+            | Self::ObligationBorrowsZero
+            | Self::InvalidObligationLiquidity
+            | Self::ObligationLiquidityEmpty => "No action needed - there is nothing to repay.",
+            Self::InvalidObligationCollateral | Self::ObligationCollateralEmpty => {
+                "Choose a reserve the obligation has deposited into."
+            }
+            Self::InvalidOracleConfig | Self::NullOracleConfig => {
+                "Wait for the price feed to update and retry."
+            }
+            Self::NotEnoughLiquidityAfterFlashLoan
+            | Self::FlashBorrowCpi
+            | Self::NoFlashRepayFound
+            | Self::InvalidFlashRepay
+            | Self::FlashRepayCpi
+            | Self::MultipleFlashBorrows => {
+                "Pair each flash borrow with a flash repay of the same reserve at the top level of the transaction."
+            }
+            Self::FlashLoansDisabled => "Use a reserve with flash loans enabled.",
+            Self::IsolatedTierAssetViolation => {
+                "Repay other borrows before borrowing an isolated asset, or use a new obligation. Isolated assets can be deposited but not as collateral."
+            }
+            Self::BorrowAttributionLimitExceeded => {
+                "Reduce the amount or deposit a different asset as collateral."
+            }
+            Self::ReserveEmergencyMode => "Use another reserve, or wait for the reserve to be reopened.",
+            Self::InsufficientProtocolFeesToRedeem => "Wait for more fees to accrue and retry.",
+            Self::PriceOutsideLiquidationBand => "Wait for the price to settle and retry.",
+            Self::ObligationBorrowsLocked => {
+                "Request a borrow unlock and wait for it to take effect."
+            }
+            Self::BorrowerConcentrationLimitExceeded => {
+                "Reduce the amount or wait for the reserve's supply to grow."
+            }
+            Self::ReserveOperationPaused => "Wait for the reserve's owner to unpause it.",
+            Self::ObligationLimitReached => "Reuse one of your existing obligations.",
+            Self::ReserveNotEmpty => {
+                "Wait for borrows to be repaid and collateral redeemed, and withdraw the protocol fees."
+            }
+            Self::DeprecatedInstruction => "Update your client to a newer version.",
+            Self::NotWhitelistedLiquidator | Self::BorrowAttributionLimitNotExceeded => {
+                "No action needed."
+            }
+            Self::InstructionUnpackError
+            | Self::AlreadyInitialized
+            | Self::NotRentExempt
+            | Self::InvalidMarketAuthority
+            | Self::InvalidAccountOwner
+            | Self::InvalidTokenOwner
+            | Self::InvalidTokenAccount
+            | Self::InvalidTokenMint
+            | Self::InvalidTokenProgram
+            | Self::InvalidConfig
+            | Self::InvalidAccountInput
+            | Self::InvalidFlashLoanReceiverProgram => {
+                "Check the accounts and data passed to the instruction."
+            }
+            Self::MathOverflow
+            | Self::TokenInitializeMintFailed
+            | Self::TokenInitializeAccountFailed
+            | Self::TokenTransferFailed
+            | Self::TokenMintToFailed
+            | Self::TokenBurnFailed
+            | Self::TokenCloseAccountFailed
+            | Self::NegativeInterestRate => {
+                "Check your token balances and retry. If this persists, contact support."
+            }
+        }
+    }
 }
 
 impl From<LendingError> for ProgramError {
@@ -231,3 +560,42 @@ impl PrintProgramError for LendingError {
         msg!(&self.to_string());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn every_error_has_a_description_and_action() {
+        let mut code = 0;
+        while let Some(error) = LendingError::from_u32(code) {
+            assert!(!error.description().is_empty());
+            assert!(!error.suggested_action().is_empty());
+            code += 1;
+        }
+        assert_eq!(code, LendingError::AccountNotMigrated as u32 + 1);
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn from_instruction_error() {
+        assert_eq!(
+            LendingError::from_instruction_error(&TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(LendingError::ObligationStale as u32)
+            )),
+            Some(LendingError::ObligationStale)
+        );
+        assert_eq!(
+            LendingError::from_instruction_error(&TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(u32::MAX)
+            )),
+            None
+        );
+        assert_eq!(
+            LendingError::from_instruction_error(&TransactionError::AccountNotFound),
+            None
+        );
+    }
+}
@@ -0,0 +1,679 @@
+//! Structured events the lending program logs with `sol_log_data`, for indexers and off-chain
+//! accounting.
+//!
+//! An event is logged as its name followed by its fields, and shows up in the transaction logs as
+//! "Program data: " and the base64 encoding of each field, space separated. Decode the fields and
+//! pass them to `LendingEvent::decode`.
+
+use crate::{math::Decimal, state::FeeKind};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+use std::convert::TryInto;
+
+/// Name of the event logged whenever a fee is collected
+pub const FEE_EVENT: &[u8] = b"Fee";
+
+/// Name of the event logged whenever collateral moves in or out of an obligation
+pub const OBLIGATION_COLLATERAL_EVENT: &[u8] = b"ObligationCollateral";
+
+/// Name of the event logged whenever liquidity is deposited to or redeemed from a reserve
+pub const DEPOSIT_EVENT: &[u8] = b"Deposit";
+
+/// Name of the event logged whenever an obligation borrows or repays liquidity
+pub const BORROW_EVENT: &[u8] = b"Borrow";
+
+/// Name of the event logged whenever an obligation is liquidated
+pub const LIQUIDATION_EVENT: &[u8] = b"Liquidation";
+
+/// Name of the event logged whenever a flash loan is borrowed or repaid
+pub const FLASH_LOAN_EVENT: &[u8] = b"FlashLoan";
+
+/// Name of the event logged whenever a reserve config is updated
+pub const CONFIG_UPDATE_EVENT: &[u8] = b"ConfigUpdate";
+
+/// An event logged by the lending program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LendingEvent {
+    /// A fee was collected
+    Fee(FeeEvent),
+    /// Collateral was deposited to or removed from an obligation
+    ObligationCollateral(ObligationCollateralEvent),
+    /// Liquidity was deposited to or redeemed from a reserve
+    Deposit(DepositEvent),
+    /// An obligation borrowed or repaid liquidity
+    Borrow(BorrowEvent),
+    /// An obligation was liquidated
+    Liquidation(LiquidationEvent),
+    /// A flash loan was borrowed or repaid
+    FlashLoan(FlashLoanEvent),
+    /// A reserve config was updated
+    ConfigUpdate(ConfigUpdateEvent),
+}
+
+impl LendingEvent {
+    /// Decode the fields of a "Program data" log. Returns None for data that isn't one of these
+    /// events, such as events of other programs.
+    pub fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields.first() {
+            Some(&name) if name == FEE_EVENT => FeeEvent::decode(fields).map(Self::Fee),
+            Some(&name) if name == OBLIGATION_COLLATERAL_EVENT => {
+                ObligationCollateralEvent::decode(fields).map(Self::ObligationCollateral)
+            }
+            Some(&name) if name == DEPOSIT_EVENT => DepositEvent::decode(fields).map(Self::Deposit),
+            Some(&name) if name == BORROW_EVENT => BorrowEvent::decode(fields).map(Self::Borrow),
+            Some(&name) if name == LIQUIDATION_EVENT => {
+                LiquidationEvent::decode(fields).map(Self::Liquidation)
+            }
+            Some(&name) if name == FLASH_LOAN_EVENT => {
+                FlashLoanEvent::decode(fields).map(Self::FlashLoan)
+            }
+            Some(&name) if name == CONFIG_UPDATE_EVENT => {
+                ConfigUpdateEvent::decode(fields).map(Self::ConfigUpdate)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A fee was collected. Logged as
+/// `[b"Fee", [kind], reserve, payer, receiver, amount (u64 le)]`, followed by the referrer when
+/// the fee was paid on a borrow from a referred obligation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeEvent {
+    /// Kind of fee
+    pub kind: FeeKind,
+    /// Reserve the fee was paid in
+    pub reserve: Pubkey,
+    /// Account that paid the fee
+    pub payer: Pubkey,
+    /// Token account that received the fee
+    pub receiver: Pubkey,
+    /// Fee amount, in the reserve's liquidity token
+    pub amount: u64,
+    /// Referrer of the obligation the fee was paid by, for borrow fees
+    pub referrer: Option<Pubkey>,
+}
+
+impl FeeEvent {
+    /// Log the event
+    pub fn log(&self) {
+        let amount = self.amount.to_le_bytes();
+        let fields: &[&[u8]] = &[
+            FEE_EVENT,
+            &[self.kind as u8],
+            self.reserve.as_ref(),
+            self.payer.as_ref(),
+            self.receiver.as_ref(),
+            &amount,
+        ];
+        match &self.referrer {
+            Some(referrer) => sol_log_data(&[fields, &[referrer.as_ref()]].concat()),
+            None => sol_log_data(fields),
+        }
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        let (fields, referrer) = match fields {
+            [fields @ .., referrer] if fields.len() == 6 => {
+                (fields, Some(decode_pubkey(referrer)?))
+            }
+            fields => (fields, None),
+        };
+        match fields {
+            [_, kind, reserve, payer, receiver, amount] => Some(Self {
+                kind: FeeKind::from_u8(*kind.first()?)?,
+                reserve: decode_pubkey(reserve)?,
+                payer: decode_pubkey(payer)?,
+                receiver: decode_pubkey(receiver)?,
+                amount: u64::from_le_bytes((*amount).try_into().ok()?),
+                referrer,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// How collateral moved in or out of an obligation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum ObligationCollateralKind {
+    /// Collateral deposited by the obligation owner
+    Deposit = 0,
+    /// Collateral withdrawn by the obligation owner
+    Withdraw = 1,
+    /// Collateral seized by a liquidator
+    Liquidation = 2,
+}
+
+/// Collateral was deposited to or removed from an obligation, with the exchange rate the reserve
+/// had at the time, so the liquidity a deposit started from can be told apart from the interest it
+/// earned. Logged as `[b"ObligationCollateral", [kind], obligation, reserve, collateral_amount
+/// (u64 le), liquidity_amount (u64 le), collateral_exchange_rate (u128 le, scaled by WAD),
+/// deposited_amount (u64 le)]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObligationCollateralEvent {
+    /// How the collateral moved
+    pub kind: ObligationCollateralKind,
+    /// Obligation the collateral moved in or out of
+    pub obligation: Pubkey,
+    /// Reserve of the collateral
+    pub reserve: Pubkey,
+    /// Collateral tokens moved, the obligation's shares of the reserve
+    pub collateral_amount: u64,
+    /// Liquidity the collateral tokens were worth at the exchange rate, rounded down
+    pub liquidity_amount: u64,
+    /// Collateral tokens per liquidity token at the time, exact
+    pub collateral_exchange_rate: Decimal,
+    /// Collateral tokens of the reserve left in the obligation afterwards
+    pub deposited_amount: u64,
+}
+
+impl ObligationCollateralEvent {
+    /// Log the event
+    pub fn log(&self) {
+        sol_log_data(&[
+            OBLIGATION_COLLATERAL_EVENT,
+            &[self.kind as u8],
+            self.obligation.as_ref(),
+            self.reserve.as_ref(),
+            &self.collateral_amount.to_le_bytes(),
+            &self.liquidity_amount.to_le_bytes(),
+            &self
+                .collateral_exchange_rate
+                .to_scaled_val()
+                .unwrap_or(u128::MAX)
+                .to_le_bytes(),
+            &self.deposited_amount.to_le_bytes(),
+        ]);
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [_, kind, obligation, reserve, collateral_amount, liquidity_amount, collateral_exchange_rate, deposited_amount] => {
+                Some(Self {
+                    kind: ObligationCollateralKind::from_u8(*kind.first()?)?,
+                    obligation: decode_pubkey(obligation)?,
+                    reserve: decode_pubkey(reserve)?,
+                    collateral_amount: u64::from_le_bytes((*collateral_amount).try_into().ok()?),
+                    liquidity_amount: u64::from_le_bytes((*liquidity_amount).try_into().ok()?),
+                    collateral_exchange_rate: Decimal::from_scaled_val(u128::from_le_bytes(
+                        (*collateral_exchange_rate).try_into().ok()?,
+                    )),
+                    deposited_amount: u64::from_le_bytes((*deposited_amount).try_into().ok()?),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether liquidity went into or out of a reserve
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum DepositKind {
+    /// Liquidity deposited for newly minted collateral tokens
+    Deposit = 0,
+    /// Collateral tokens burned for liquidity
+    Redeem = 1,
+    /// Liquidity donated to the reserve, no collateral tokens are minted
+    Donate = 2,
+}
+
+/// Liquidity was deposited to or redeemed from a reserve. Logged as `[b"Deposit", [kind],
+/// reserve, authority, liquidity_amount (u64 le), collateral_amount (u64 le)]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DepositEvent {
+    /// Which way the liquidity went
+    pub kind: DepositKind,
+    /// Reserve of the liquidity
+    pub reserve: Pubkey,
+    /// Transfer authority of the tokens given to the reserve
+    pub authority: Pubkey,
+    /// Liquidity tokens deposited or redeemed
+    pub liquidity_amount: u64,
+    /// Collateral tokens minted or burned
+    pub collateral_amount: u64,
+}
+
+impl DepositEvent {
+    /// Log the event
+    pub fn log(&self) {
+        sol_log_data(&[
+            DEPOSIT_EVENT,
+            &[self.kind as u8],
+            self.reserve.as_ref(),
+            self.authority.as_ref(),
+            &self.liquidity_amount.to_le_bytes(),
+            &self.collateral_amount.to_le_bytes(),
+        ]);
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [_, kind, reserve, authority, liquidity_amount, collateral_amount] => Some(Self {
+                kind: DepositKind::from_u8(*kind.first()?)?,
+                reserve: decode_pubkey(reserve)?,
+                authority: decode_pubkey(authority)?,
+                liquidity_amount: u64::from_le_bytes((*liquidity_amount).try_into().ok()?),
+                collateral_amount: u64::from_le_bytes((*collateral_amount).try_into().ok()?),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an obligation took on or paid down debt
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum BorrowKind {
+    /// Liquidity borrowed by the obligation owner
+    Borrow = 0,
+    /// Liquidity repaid by anyone on the obligation's behalf
+    Repay = 1,
+}
+
+/// An obligation borrowed or repaid liquidity. Logged as `[b"Borrow", [kind], obligation,
+/// reserve, liquidity_amount (u64 le), borrowed_amount (u128 le, scaled by WAD)]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowEvent {
+    /// Which way the debt moved
+    pub kind: BorrowKind,
+    /// Obligation that borrowed or repaid
+    pub obligation: Pubkey,
+    /// Reserve of the liquidity
+    pub reserve: Pubkey,
+    /// Liquidity borrowed, including the borrow fee, or repaid
+    pub liquidity_amount: u64,
+    /// Liquidity of the reserve the obligation owes afterwards
+    pub borrowed_amount: Decimal,
+}
+
+impl BorrowEvent {
+    /// Log the event
+    pub fn log(&self) {
+        sol_log_data(&[
+            BORROW_EVENT,
+            &[self.kind as u8],
+            self.obligation.as_ref(),
+            self.reserve.as_ref(),
+            &self.liquidity_amount.to_le_bytes(),
+            &self
+                .borrowed_amount
+                .to_scaled_val()
+                .unwrap_or(u128::MAX)
+                .to_le_bytes(),
+        ]);
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [_, kind, obligation, reserve, liquidity_amount, borrowed_amount] => Some(Self {
+                kind: BorrowKind::from_u8(*kind.first()?)?,
+                obligation: decode_pubkey(obligation)?,
+                reserve: decode_pubkey(reserve)?,
+                liquidity_amount: u64::from_le_bytes((*liquidity_amount).try_into().ok()?),
+                borrowed_amount: Decimal::from_scaled_val(u128::from_le_bytes(
+                    (*borrowed_amount).try_into().ok()?,
+                )),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// An obligation was liquidated. Logged as `[b"Liquidation", obligation, liquidator,
+/// repay_reserve, withdraw_reserve, repay_amount (u64 le), withdraw_amount (u64 le), bonus (u128
+/// le, scaled by WAD)]`. The collateral seized is also logged as an "ObligationCollateral" event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiquidationEvent {
+    /// Obligation that was liquidated
+    pub obligation: Pubkey,
+    /// Transfer authority of the liquidator
+    pub liquidator: Pubkey,
+    /// Reserve of the debt repaid
+    pub repay_reserve: Pubkey,
+    /// Reserve of the collateral seized
+    pub withdraw_reserve: Pubkey,
+    /// Liquidity repaid by the liquidator
+    pub repay_amount: u64,
+    /// Collateral tokens seized by the liquidator
+    pub withdraw_amount: u64,
+    /// Total bonus the liquidation paid out, including the protocol liquidation fee
+    pub bonus: Decimal,
+}
+
+impl LiquidationEvent {
+    /// Log the event
+    pub fn log(&self) {
+        sol_log_data(&[
+            LIQUIDATION_EVENT,
+            self.obligation.as_ref(),
+            self.liquidator.as_ref(),
+            self.repay_reserve.as_ref(),
+            self.withdraw_reserve.as_ref(),
+            &self.repay_amount.to_le_bytes(),
+            &self.withdraw_amount.to_le_bytes(),
+            &self
+                .bonus
+                .to_scaled_val()
+                .unwrap_or(u128::MAX)
+                .to_le_bytes(),
+        ]);
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [_, obligation, liquidator, repay_reserve, withdraw_reserve, repay_amount, withdraw_amount, bonus] => {
+                Some(Self {
+                    obligation: decode_pubkey(obligation)?,
+                    liquidator: decode_pubkey(liquidator)?,
+                    repay_reserve: decode_pubkey(repay_reserve)?,
+                    withdraw_reserve: decode_pubkey(withdraw_reserve)?,
+                    repay_amount: u64::from_le_bytes((*repay_amount).try_into().ok()?),
+                    withdraw_amount: u64::from_le_bytes((*withdraw_amount).try_into().ok()?),
+                    bonus: Decimal::from_scaled_val(u128::from_le_bytes((*bonus).try_into().ok()?)),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Which half of a flash loan happened
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum FlashLoanKind {
+    /// Liquidity lent out at the start of the transaction
+    Borrow = 0,
+    /// Liquidity returned later in the transaction, fees are logged separately
+    Repay = 1,
+}
+
+/// A flash loan was borrowed or repaid. Logged as `[b"FlashLoan", [kind], reserve, token_account,
+/// liquidity_amount (u64 le)]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlashLoanEvent {
+    /// Which half of the flash loan happened
+    pub kind: FlashLoanKind,
+    /// Reserve of the liquidity
+    pub reserve: Pubkey,
+    /// Token account the liquidity was sent to or repaid from
+    pub token_account: Pubkey,
+    /// Liquidity borrowed or repaid, without fees
+    pub liquidity_amount: u64,
+}
+
+impl FlashLoanEvent {
+    /// Log the event
+    pub fn log(&self) {
+        sol_log_data(&[
+            FLASH_LOAN_EVENT,
+            &[self.kind as u8],
+            self.reserve.as_ref(),
+            self.token_account.as_ref(),
+            &self.liquidity_amount.to_le_bytes(),
+        ]);
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [_, kind, reserve, token_account, liquidity_amount] => Some(Self {
+                kind: FlashLoanKind::from_u8(*kind.first()?)?,
+                reserve: decode_pubkey(reserve)?,
+                token_account: decode_pubkey(token_account)?,
+                liquidity_amount: u64::from_le_bytes((*liquidity_amount).try_into().ok()?),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A reserve config was updated. Logged as `[b"ConfigUpdate", reserve, authority]`, the new
+/// config is read from the reserve account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigUpdateEvent {
+    /// Reserve whose config was updated
+    pub reserve: Pubkey,
+    /// Lending market owner or risk authority that signed the update
+    pub authority: Pubkey,
+}
+
+impl ConfigUpdateEvent {
+    /// Log the event
+    pub fn log(&self) {
+        sol_log_data(&[
+            CONFIG_UPDATE_EVENT,
+            self.reserve.as_ref(),
+            self.authority.as_ref(),
+        ]);
+    }
+
+    fn decode(fields: &[&[u8]]) -> Option<Self> {
+        match fields {
+            [_, reserve, authority] => Some(Self {
+                reserve: decode_pubkey(reserve)?,
+                authority: decode_pubkey(authority)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn decode_pubkey(field: &[u8]) -> Option<Pubkey> {
+    Some(Pubkey::new_from_array(field.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::math::Rate;
+
+    #[test]
+    fn decode_events() {
+        let fee = FeeEvent {
+            kind: FeeKind::FlashLoan,
+            reserve: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            receiver: Pubkey::new_unique(),
+            amount: 3_000,
+            referrer: None,
+        };
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Fee",
+                &[1],
+                fee.reserve.as_ref(),
+                fee.payer.as_ref(),
+                fee.receiver.as_ref(),
+                &3_000u64.to_le_bytes(),
+            ]),
+            Some(LendingEvent::Fee(fee.clone()))
+        );
+
+        let referrer = Pubkey::new_unique();
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Fee",
+                &[1],
+                fee.reserve.as_ref(),
+                fee.payer.as_ref(),
+                fee.receiver.as_ref(),
+                &3_000u64.to_le_bytes(),
+                referrer.as_ref(),
+            ]),
+            Some(LendingEvent::Fee(FeeEvent {
+                referrer: Some(referrer),
+                ..fee
+            }))
+        );
+
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Fee",
+                &[4],
+                fee.reserve.as_ref(),
+                fee.payer.as_ref(),
+                fee.receiver.as_ref(),
+                &3_000u64.to_le_bytes(),
+            ]),
+            Some(LendingEvent::Fee(FeeEvent {
+                kind: FeeKind::Spread,
+                ..fee
+            }))
+        );
+
+        let obligation = Pubkey::new_unique();
+        let reserve = Pubkey::new_unique();
+        let rate = Decimal::from(Rate::from_percent(80));
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"ObligationCollateral",
+                &[1],
+                obligation.as_ref(),
+                reserve.as_ref(),
+                &400u64.to_le_bytes(),
+                &500u64.to_le_bytes(),
+                &rate.to_scaled_val().unwrap().to_le_bytes(),
+                &1_600u64.to_le_bytes(),
+            ]),
+            Some(LendingEvent::ObligationCollateral(
+                ObligationCollateralEvent {
+                    kind: ObligationCollateralKind::Withdraw,
+                    obligation,
+                    reserve,
+                    collateral_amount: 400,
+                    liquidity_amount: 500,
+                    collateral_exchange_rate: rate,
+                    deposited_amount: 1_600,
+                }
+            ))
+        );
+
+        let deposit = DepositEvent {
+            kind: DepositKind::Redeem,
+            reserve: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            liquidity_amount: 500,
+            collateral_amount: 400,
+        };
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Deposit",
+                &[1],
+                deposit.reserve.as_ref(),
+                deposit.authority.as_ref(),
+                &500u64.to_le_bytes(),
+                &400u64.to_le_bytes(),
+            ]),
+            Some(LendingEvent::Deposit(deposit))
+        );
+
+        let borrow = BorrowEvent {
+            kind: BorrowKind::Borrow,
+            obligation: Pubkey::new_unique(),
+            reserve: Pubkey::new_unique(),
+            liquidity_amount: 1_000,
+            borrowed_amount: Decimal::from(1_500u64),
+        };
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Borrow",
+                &[0],
+                borrow.obligation.as_ref(),
+                borrow.reserve.as_ref(),
+                &1_000u64.to_le_bytes(),
+                &Decimal::from(1_500u64)
+                    .to_scaled_val()
+                    .unwrap()
+                    .to_le_bytes(),
+            ]),
+            Some(LendingEvent::Borrow(borrow))
+        );
+
+        let liquidation = LiquidationEvent {
+            obligation: Pubkey::new_unique(),
+            liquidator: Pubkey::new_unique(),
+            repay_reserve: Pubkey::new_unique(),
+            withdraw_reserve: Pubkey::new_unique(),
+            repay_amount: 100,
+            withdraw_amount: 105,
+            bonus: Decimal::from_percent(5),
+        };
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Liquidation",
+                liquidation.obligation.as_ref(),
+                liquidation.liquidator.as_ref(),
+                liquidation.repay_reserve.as_ref(),
+                liquidation.withdraw_reserve.as_ref(),
+                &100u64.to_le_bytes(),
+                &105u64.to_le_bytes(),
+                &Decimal::from_percent(5)
+                    .to_scaled_val()
+                    .unwrap()
+                    .to_le_bytes(),
+            ]),
+            Some(LendingEvent::Liquidation(liquidation))
+        );
+
+        let flash_loan = FlashLoanEvent {
+            kind: FlashLoanKind::Repay,
+            reserve: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            liquidity_amount: 7_000,
+        };
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"FlashLoan",
+                &[1],
+                flash_loan.reserve.as_ref(),
+                flash_loan.token_account.as_ref(),
+                &7_000u64.to_le_bytes(),
+            ]),
+            Some(LendingEvent::FlashLoan(flash_loan))
+        );
+
+        let config_update = ConfigUpdateEvent {
+            reserve: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"ConfigUpdate",
+                config_update.reserve.as_ref(),
+                config_update.authority.as_ref(),
+            ]),
+            Some(LendingEvent::ConfigUpdate(config_update))
+        );
+
+        // other events, unknown kinds and truncated fields
+        let reserve = Pubkey::new_unique();
+        assert_eq!(
+            LendingEvent::decode(&[b"CloseReserve", reserve.as_ref()]),
+            None
+        );
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Fee",
+                &[9],
+                reserve.as_ref(),
+                reserve.as_ref(),
+                reserve.as_ref(),
+                &3_000u64.to_le_bytes(),
+            ]),
+            None
+        );
+        assert_eq!(
+            LendingEvent::decode(&[
+                b"Fee",
+                &[1],
+                reserve.as_ref(),
+                reserve.as_ref(),
+                reserve.as_ref(),
+                &[0; 4],
+            ]),
+            None
+        );
+    }
+}
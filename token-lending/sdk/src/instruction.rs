@@ -1,8 +1,17 @@
 //! Instruction types
 
-use crate::state::{LendingMarketMetadata, ReserveType};
+use crate::state::{
+    find_asset_categories_address, find_emergency_price_address, find_insurance_fund_address,
+    find_liquidator_whitelist_address, find_market_member_address, find_max_price_age_address,
+    find_obligation_counter_address, find_reserve_activity_address,
+    find_reserve_ctoken_price_address, find_reserve_pair_borrow_weights_address,
+    find_reserve_rate_stats_address, find_reserve_spread_fee_receiver_address,
+    find_reward_rule_address, LendingMarketMetadata, Obligation, Reserve, ReserveType, RewardSide,
+};
 use crate::{
     error::LendingError,
+    math::Decimal,
+    spl_account_compression, spl_noop,
     state::{RateLimiterConfig, ReserveConfig, ReserveFees},
 };
 use bytemuck::bytes_of;
@@ -65,30 +74,35 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      $authority can transfer $liquidity_amount.
     ///   1. `[writable]` Destination collateral token account - uninitialized.
     ///   2. `[writable]` Reserve account - uninitialized.
     ///   3. `[]` Reserve liquidity SPL Token mint.
     ///   4. `[writable]` Reserve liquidity supply SPL Token account - uninitialized.
     ///   5. `[writable]` Reserve liquidity fee receiver - uninitialized.
     ///   6. `[writable]` Reserve collateral SPL Token mint - uninitialized.
-    ///   7 `[writable]` Reserve collateral token supply - uninitialized.
+    ///   7. `[writable]` Reserve collateral token supply - uninitialized.
     ///   8. `[]` Pyth product account.
     ///   9. `[]` Pyth price account.
-    ///             This will be used as the reserve liquidity oracle account.
+    ///      This will be used as the reserve liquidity oracle account.
     ///   10. `[]` Switchboard price feed account. used as a backup oracle
-    ///   11 `[]` Lending market account.
-    ///   12 `[]` Derived lending market authority.
-    ///   13 `[signer]` Lending market owner.
-    ///   14 `[signer]` User transfer authority ($authority).
-    ///   15 `[]` Clock sysvar (optional, will be removed soon).
-    ///   16 `[]` Rent sysvar.
-    ///   17 `[]` Token program id.
+    ///   11. `[]` Lending market account.
+    ///   12. `[]` Derived lending market authority.
+    ///   13. `[signer]` Lending market owner.
+    ///   14. `[signer]` User transfer authority ($authority).
+    ///   15. `[]` Clock sysvar (optional, will be removed soon).
+    ///   16. `[]` Rent sysvar.
+    ///   17. `[]` Token program id.
     InitReserve {
         /// Initial amount of liquidity to deposit into the new reserve
         liquidity_amount: u64,
-        /// Reserve configuration values
+        /// Reserve configuration values. The fields after attributed_borrow_limit_close are
+        /// optional in the instruction data and default to 0.
         config: ReserveConfig,
+        /// Collateral mint decimals, the liquidity mint's decimals if not set. Must be within
+        /// MAX_COLLATERAL_DECIMALS_OFFSET of the liquidity mint's decimals. Optional in the
+        /// instruction data.
+        collateral_decimals: Option<u8>,
     },
 
     // 3
@@ -98,10 +112,14 @@ pub enum LendingInstruction {
     ///
     ///   0. `[writable]` Reserve account.
     ///   1. `[]` Pyth Reserve liquidity oracle account.
-    ///             Must be the Pyth price account specified at InitReserve.
+    ///      Must be the Pyth price account specified at InitReserve.
     ///   2. `[]` Switchboard Reserve liquidity oracle account.
-    ///             Must be the Switchboard price feed account specified at InitReserve.
-    ///   3. `[]` Clock sysvar (optional, will be removed soon).
+    ///      Must be the Switchboard price feed account specified at InitReserve.
+    ///   3. `[]` Extra oracle account (optional). Required if the reserve has an extra oracle.
+    ///   4. `[]` Emergency price account (optional). Takes the place of account 3 if the reserve has no extra oracle.
+    ///
+    ///   .. `[]` Tertiary oracle account. Required if the reserve has a tertiary oracle, right after account 2.
+    ///   .. `[writable]` Reserve rate stats account (optional). Sampled before the refresh when passed as the last account.
     RefreshReserve,
 
     // 4
@@ -111,7 +129,9 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      $authority can transfer $liquidity_amount.
+    ///      A wSOL account is synced before the transfer, so lamports sent to it
+    ///      without a SyncNative instruction are deposited too.
     ///   1. `[writable]` Destination collateral token account.
     ///   2. `[writable]` Reserve account.
     ///   3. `[writable]` Reserve liquidity supply SPL Token account.
@@ -121,9 +141,15 @@ pub enum LendingInstruction {
     ///   7. `[signer]` User transfer authority ($authority).
     ///   8. `[]` Clock sysvar (optional, will be removed soon).
     ///   9. `[]` Token program id.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed before any market member account, counts the action for the reserves they belong to.
+    ///   .. `[]` Market member account - of the user transfer authority, required if the lending market requires a whitelist. Passed last.
     DepositReserveLiquidity {
         /// Amount of liquidity to deposit in exchange for collateral tokens
         liquidity_amount: u64,
+        /// Minimum amount of collateral tokens to receive, or the deposit fails - 0 for no
+        /// minimum. Optional in the instruction data.
+        min_collateral_out: u64,
     },
 
     // 5
@@ -132,7 +158,7 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source collateral token account.
-    ///                     $authority can transfer $collateral_amount.
+    ///      $authority can transfer $collateral_amount.
     ///   1. `[writable]` Destination liquidity token account.
     ///   2. `[writable]` Reserve account.
     ///   3. `[writable]` Reserve collateral SPL Token mint.
@@ -142,9 +168,14 @@ pub enum LendingInstruction {
     ///   7. `[signer]` User transfer authority ($authority).
     ///   8. `[]` Clock sysvar (optional, will be removed soon).
     ///   9. `[]` Token program id.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed last, counts the action for the reserves they belong to.
     RedeemReserveCollateral {
         /// Amount of collateral tokens to redeem in exchange for liquidity
         collateral_amount: u64,
+        /// Minimum amount of liquidity to receive, or the redeem fails - 0 for no minimum.
+        /// Optional in the instruction data.
+        min_liquidity_out: u64,
     },
 
     // 6
@@ -152,13 +183,26 @@ pub enum LendingInstruction {
     ///
     /// Accounts expected by this instruction:
     ///
-    ///   0. `[writable]` Obligation account - uninitialized.
+    ///   0. `[writable]` Obligation account - uninitialized, Obligation::LEN or OBLIGATION_EXTENDED_LEN bytes.
     ///   1. `[]` Lending market account.
-    ///   2. `[signer]` Obligation owner.
+    ///   2. `[signer]` Obligation owner. Writable if it pays for the obligation counter.
     ///   3. `[]` Clock sysvar (optional, will be removed soon).
     ///   4. `[]` Rent sysvar.
     ///   5. `[]` Token program id.
-    InitObligation,
+    ///
+    /// Required when the lending market caps obligations per owner:
+    ///
+    ///   6. `[writable]` Obligation counter account, created on first use.
+    ///   7. `[]` System program.
+    ///
+    /// Required when the lending market requires a whitelist, passed last:
+    ///
+    ///   .. `[]` Market member account of the obligation owner.
+    InitObligation {
+        /// Referrer to attribute the obligation to, as SetObligationReferrer. Omitted from the
+        /// instruction data when None.
+        referrer: Option<Pubkey>,
+    },
 
     // 7
     /// Refresh an obligation's accrued interest and collateral and liquidity prices. Requires
@@ -169,8 +213,22 @@ pub enum LendingInstruction {
     ///
     ///   0. `[writable]` Obligation account.
     ///   1. `[]` Clock sysvar (optional, will be removed soon).
-    ///   .. `[writable]` Collateral deposit reserve accounts - refreshed, all, in order.
-    ///   .. `[]` Liquidity borrow reserve accounts - refreshed, all, in order.
+    ///      .. `[writable]` Collateral deposit reserve accounts - refreshed, all, in order.
+    ///      .. `[]` Liquidity borrow reserve accounts - refreshed, all, in order.
+    ///      .. `[]` Reserve pair borrow weights accounts - of the borrow reserves with pair borrow weights, in order.
+    ///      .. `[]` Asset categories account - optional, see below.
+    ///
+    /// The asset categories account goes after the pair borrow weights accounts, or after the
+    /// reserves if there are none, and before the max price age account. It values an obligation
+    /// whose deposits and borrows are all in the same asset category at the category's limits,
+    /// and is required once the obligation's last refresh did so.
+    ///
+    /// Optional, passed last to accept reserves whose price was read within the market's max
+    /// price age instead of only in the current slot, even if they were marked stale since. The
+    /// obligation is refreshed as of the current slot either way, and records the slot of the
+    /// oldest price it was valued at:
+    ///
+    ///   .. `[]` Max price age account - PDA of the lending market address and MAX_PRICE_AGE_SEED.
     RefreshObligation,
 
     // 8
@@ -179,8 +237,8 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source collateral token account.
-    ///                     Minted by deposit reserve collateral mint.
-    ///                     $authority can transfer $collateral_amount.
+    ///      Minted by deposit reserve collateral mint.
+    ///      $authority can transfer $collateral_amount.
     ///   1. `[writable]` Destination deposit reserve collateral supply SPL Token account.
     ///   2. `[writable]` Deposit reserve account.
     ///   3. `[writable]` Obligation account.
@@ -189,21 +247,24 @@ pub enum LendingInstruction {
     ///   6. `[signer]` User transfer authority ($authority).
     ///   7. `[]` Clock sysvar (optional, will be removed soon).
     ///   8. `[]` Token program id.
+    ///
+    ///   .. `[]` Market member account - of the obligation owner, required if the lending market requires a whitelist. Passed last.
     DepositObligationCollateral {
         /// Amount of collateral tokens to deposit
         collateral_amount: u64,
     },
 
     // 9
-    /// Withdraw collateral from an obligation. Requires a refreshed obligation and reserve.
+    /// Withdraw collateral from an obligation. Requires a refreshed obligation and reserve, unless
+    /// the obligation has no borrows.
     ///
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source withdraw reserve collateral supply SPL Token account.
     ///   1. `[writable]` Destination collateral token account.
-    ///                     Minted by withdraw reserve collateral mint.
-    ///   2. `[]` Withdraw reserve account - refreshed.
-    ///   3. `[writable]` Obligation account - refreshed.
+    ///      Minted by withdraw reserve collateral mint.
+    ///   2. `[writable]` Withdraw reserve account - refreshed if the obligation has borrows.
+    ///   3. `[writable]` Obligation account - refreshed if it has borrows.
     ///   4. `[]` Lending market account.
     ///   5. `[]` Derived lending market authority.
     ///   6. `[signer]` Obligation owner.
@@ -222,21 +283,26 @@ pub enum LendingInstruction {
     ///
     ///   0. `[writable]` Source borrow reserve liquidity supply SPL Token account.
     ///   1. `[writable]` Destination liquidity token account.
-    ///                     Minted by borrow reserve liquidity mint.
+    ///      Minted by borrow reserve liquidity mint.
     ///   2. `[writable]` Borrow reserve account - refreshed.
     ///   3. `[writable]` Borrow reserve liquidity fee receiver account.
-    ///                     Must be the fee account specified at InitReserve.
+    ///      Must be the fee account specified at InitReserve.
     ///   4. `[writable]` Obligation account - refreshed.
     ///   5. `[]` Lending market account.
     ///   6. `[]` Derived lending market authority.
-    ///   7. `[signer]` Obligation owner.
+    ///   7. `[signer]` Obligation owner, or its approved borrower.
     ///   8. `[]` Clock sysvar (optional, will be removed soon).
     ///   9. `[]` Token program id.
-    ///   10 `[optional, writable]` Host fee receiver account.
+    ///   10. `[optional, writable]` Host fee receiver account - required if the obligation has a referrer, whose token account it must then be. Otherwise it must be the associated token account of the lending market's default host fee receiver, if it has one. Without it the whole borrow fee goes to the fee receiver.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed before any market member account, counts the action for the reserves they belong to.
+    ///   .. `[]` Market member account - of the signer, required if the lending market requires a whitelist. Passed last.
     BorrowObligationLiquidity {
         /// Amount of liquidity to borrow - u64::MAX for 100% of borrowing power
         liquidity_amount: u64,
-        // @TODO: slippage constraint - https://git.io/JmV67
+        /// Minimum amount of liquidity to receive after fees, or the borrow fails - 0 for no
+        /// minimum. Optional in the instruction data.
+        min_liquidity_received: u64,
     },
 
     // 11
@@ -245,8 +311,8 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     Minted by repay reserve liquidity mint.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      Minted by repay reserve liquidity mint.
+    ///      $authority can transfer $liquidity_amount.
     ///   1. `[writable]` Destination repay reserve liquidity supply SPL Token account.
     ///   2. `[writable]` Repay reserve account - refreshed.
     ///   3. `[writable]` Obligation account - refreshed.
@@ -254,6 +320,8 @@ pub enum LendingInstruction {
     ///   5. `[signer]` User transfer authority ($authority).
     ///   6. `[]` Clock sysvar (optional, will be removed soon).
     ///   7. `[]` Token program id.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed last, counts the action for the reserves they belong to.
     RepayObligationLiquidity {
         /// Amount of liquidity to repay - u64::MAX for 100% of borrowed amount
         liquidity_amount: u64,
@@ -266,10 +334,10 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     Minted by repay reserve liquidity mint.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      Minted by repay reserve liquidity mint.
+    ///      $authority can transfer $liquidity_amount.
     ///   1. `[writable]` Destination collateral token account.
-    ///                     Minted by withdraw reserve collateral mint.
+    ///      Minted by withdraw reserve collateral mint.
     ///   2. `[writable]` Repay reserve account - refreshed.
     ///   3. `[writable]` Repay reserve liquidity supply SPL Token account.
     ///   4. `[]` Withdraw reserve account - refreshed.
@@ -278,8 +346,8 @@ pub enum LendingInstruction {
     ///   7. `[]` Lending market account.
     ///   8. `[]` Derived lending market authority.
     ///   9. `[signer]` User transfer authority ($authority).
-    ///   10 `[]` Clock sysvar (optional, will be removed soon).
-    ///   11 `[]` Token program id.
+    ///   10. `[]` Clock sysvar (optional, will be removed soon).
+    ///   11. `[]` Token program id.
     LiquidateObligation {
         /// Amount of liquidity to repay - u64::MAX for up to 100% of borrowed amount
         liquidity_amount: u64,
@@ -292,21 +360,21 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     Minted by reserve liquidity mint.
-    ///                     Must match the reserve liquidity supply.
+    ///      Minted by reserve liquidity mint.
+    ///      Must match the reserve liquidity supply.
     ///   1. `[writable]` Destination liquidity token account.
-    ///                     Minted by reserve liquidity mint.
+    ///      Minted by reserve liquidity mint.
     ///   2. `[writable]` Reserve account.
     ///   3. `[writable]` Flash loan fee receiver account.
-    ///                     Must match the reserve liquidity fee receiver.
+    ///      Must match the reserve liquidity fee receiver.
     ///   4. `[writable]` Host fee receiver.
     ///   5. `[]` Lending market account.
     ///   6. `[]` Derived lending market authority.
     ///   7. `[]` Token program id.
     ///   8. `[]` Flash loan receiver program id.
-    ///             Must implement an instruction that has tag of 0 and a signature of `(amount: u64)`
-    ///             This instruction must return the amount to the source liquidity account.
-    ///   .. `[any]` Additional accounts expected by the receiving program's `ReceiveFlashLoan` instruction.
+    ///      Must implement an instruction that has tag of 0 and a signature of `(amount: u64)`
+    ///      This instruction must return the amount to the source liquidity account.
+    ///      .. `[any]` Additional accounts expected by the receiving program's `ReceiveFlashLoan` instruction.
     ///
     ///   The flash loan receiver program that is to be invoked should contain an instruction with
     ///   tag `0` and accept the total amount (including fee) that needs to be returned back after
@@ -317,11 +385,14 @@ pub enum LendingInstruction {
     ///   0. `[writable]` Source liquidity (matching the destination from above).
     ///   1. `[writable]` Destination liquidity (matching the source from above).
     ///   2. `[]` Token program id
-    ///   .. `[any]` Additional accounts provided to the lending program's `FlashLoan` instruction above.
+    ///      .. `[any]` Additional accounts provided to the lending program's `FlashLoan` instruction above.
+    ///
+    ///   ```ignore
     ///   ReceiveFlashLoan {
     ///       // Amount that must be repaid by the receiver program
     ///       amount: u64
     ///   }
+    ///   ```
     FlashLoan {
         /// The amount that is to be borrowed - u64::MAX for up to 100% of available liquidity
         amount: u64,
@@ -333,7 +404,8 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      $authority can transfer $liquidity_amount.
+    ///      Synced first if it's a wSOL account.
     ///   1. `[writable]` Destination collateral token account.
     ///   2. `[writable]` Reserve account.
     ///   3. `[writable]` Reserve liquidity supply SPL Token account.
@@ -343,11 +415,14 @@ pub enum LendingInstruction {
     ///   7. `[writable]` Destination deposit reserve collateral supply SPL Token account.
     ///   8. `[writable]` Obligation account.
     ///   9. `[signer]` Obligation owner.
-    ///   10 `[]` Pyth price oracle account.
-    ///   11 `[]` Switchboard price feed oracle account.
-    ///   12 `[signer]` User transfer authority ($authority).
-    ///   13 `[]` Clock sysvar (optional, will be removed soon).
-    ///   14 `[]` Token program id.
+    ///   10. `[]` Pyth price oracle account.
+    ///   11. `[]` Switchboard price feed oracle account.
+    ///   12. `[signer]` User transfer authority ($authority).
+    ///   13. `[]` Clock sysvar (optional, will be removed soon).
+    ///   14. `[]` Token program id.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed before any market member account, counts the action for the reserves they belong to.
+    ///   .. `[]` Market member account - of the obligation owner, required if the lending market requires a whitelist. Passed last.
     DepositReserveLiquidityAndObligationCollateral {
         /// Amount of liquidity to deposit in exchange
         liquidity_amount: u64,
@@ -360,7 +435,7 @@ pub enum LendingInstruction {
     ///
     ///   0. `[writable]` Source withdraw reserve collateral supply SPL Token account.
     ///   1. `[writable]` Destination collateral token account.
-    ///                     Minted by withdraw reserve collateral mint.
+    ///      Minted by withdraw reserve collateral mint.
     ///   2. `[writable]` Withdraw reserve account - refreshed.
     ///   3. `[writable]` Obligation account - refreshed.
     ///   4. `[]` Lending market account.
@@ -369,9 +444,11 @@ pub enum LendingInstruction {
     ///   7. `[writable]` Reserve collateral SPL Token mint.
     ///   8. `[writable]` Reserve liquidity supply SPL Token account.
     ///   9. `[signer]` Obligation owner
-    ///   10 `[signer]` User transfer authority ($authority).
+    ///   10. `[signer]` User transfer authority ($authority).
     ///   11. `[]` Clock sysvar (optional, will be removed soon).
     ///   12. `[]` Token program id.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed last, counts the action for the reserves they belong to.
     WithdrawObligationCollateralAndRedeemReserveCollateral {
         /// liquidity_amount is the amount of collateral tokens to withdraw
         collateral_amount: u64,
@@ -383,14 +460,15 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   1. `[writable]` Reserve account - refreshed
-    ///   2 `[]` Lending market account.
-    ///   3 `[]` Derived lending market authority.
-    ///   4 `[signer]` Lending market owner.
-    ///   5 `[]` Pyth product key.
-    ///   6 `[]` Pyth price key.
-    ///   7 `[]` Switchboard key.
+    ///   2. `[]` Lending market account.
+    ///   3. `[]` Derived lending market authority.
+    ///   4. `[signer]` Lending market owner.
+    ///   5. `[]` Pyth product key.
+    ///   6. `[]` Pyth price key.
+    ///   7. `[]` Switchboard key.
     UpdateReserveConfig {
-        /// Reserve config to update to
+        /// Reserve config to update to. The fields after attributed_borrow_limit_close follow the
+        /// rate limiter config in the instruction data, and are optional and default to 0.
         config: ReserveConfig,
         /// Rate limiter config
         rate_limiter_config: RateLimiterConfig,
@@ -400,13 +478,18 @@ pub enum LendingInstruction {
     /// Repay borrowed liquidity to a reserve to receive collateral at a discount from an unhealthy
     /// obligation. Requires a refreshed obligation and reserves.
     ///
+    /// As much of the seized collateral as the withdraw reserve's available liquidity allows is
+    /// redeemed. The rest stays in the destination collateral account. The split is set as return
+    /// data: three little-endian u64s holding the collateral redeemed, the liquidity received
+    /// after the protocol fee and the collateral left unredeemed.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     Minted by repay reserve liquidity mint.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      Minted by repay reserve liquidity mint.
+    ///      $authority can transfer $liquidity_amount.
     ///   1. `[writable]` Destination collateral token account.
-    ///                     Minted by withdraw reserve collateral mint.
+    ///      Minted by withdraw reserve collateral mint.
     ///   2. `[writable]` Destination liquidity token account.
     ///   3. `[writable]` Repay reserve account - refreshed.
     ///   4. `[writable]` Repay reserve liquidity supply SPL Token account.
@@ -415,28 +498,45 @@ pub enum LendingInstruction {
     ///   7. `[writable]` Withdraw reserve collateral supply SPL Token account.
     ///   8. `[writable]` Withdraw reserve liquidity supply SPL Token account.
     ///   9. `[writable]` Withdraw reserve liquidity fee receiver account.
-    ///   10 `[writable]` Obligation account - refreshed.
-    ///   11 `[]` Lending market account.
-    ///   12 `[]` Derived lending market authority.
-    ///   13 `[signer]` User transfer authority ($authority).
-    ///   14 `[]` Token program id.
+    ///   10. `[writable]` Obligation account - refreshed.
+    ///   11. `[]` Lending market account.
+    ///   12. `[]` Derived lending market authority.
+    ///   13. `[signer]` User transfer authority ($authority).
+    ///   14. `[]` Token program id.
+    ///   15. `[writable]` Repay reserve liquidity fee receiver account - required if the withdraw reserve takes its protocol liquidation fee in the repaid liquidity, paid by $authority on top of the repay amount.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed before any liquidator whitelist account, counts the action for the reserves they belong to.
+    ///   .. `[]` Liquidator whitelist account - required if the lending market has a liquidator whitelist. Passed last.
     LiquidateObligationAndRedeemReserveCollateral {
         /// Amount of liquidity to repay - u64::MAX for up to 100% of borrowed amount
         liquidity_amount: u64,
     },
 
     // 18
+    /// Moves the protocol's accumulated share of borrow interest from the reserve supply to the
+    /// fee receiver, logged as a Spread fee. Origination fees are paid to the fee receiver when
+    /// they're charged and never accumulate on the reserve. For reserves with an insurance fund,
+    /// the lending market's insurance fee share of it goes to the insurance fund instead. For
+    /// reserves with a spread fee receiver, the rest goes to that instead of the fee receiver.
+    ///
     ///   0. `[writable]` Reserve account.
     ///   1. `[writable]` Borrow reserve liquidity fee receiver account.
-    ///                     Must be the fee account specified at InitReserve.
+    ///      Must be the fee account specified at InitReserve.
     ///   2. `[writable]` Reserve liquidity supply SPL Token account.
     ///   3. `[]` Lending market account.
     ///   4. `[]` Derived lending market authority.
     ///   5. `[]` Token program id.
+    ///   6. `[writable]` Insurance fund account - required if the reserve has an insurance fund.
+    ///      .. `[]` Reserve spread fee receiver account - required if the reserve has a spread fee receiver.
+    ///      .. `[writable]` Spread fee receiver token account - required if the reserve has a spread fee receiver.
     RedeemFees,
 
     // 19
     /// Flash borrow reserve liquidity
+    ///
+    /// Must be followed by exactly one FlashRepayReserveLiquidity whose borrow_instruction_index
+    /// points back to it. A transaction can hold several such pairs, on the same or different
+    /// reserves.
     //
     /// Accounts expected by this instruction:
     ///
@@ -448,6 +548,9 @@ pub enum LendingInstruction {
     ///   5. `[]` Instructions sysvar.
     ///   6. `[]` Token program id.
     ///   7. `[]` Clock sysvar (optional, will be removed soon).
+    ///
+    /// The flash borrows of a transaction from a reserve with a max flash loan amount can't take
+    /// more than that amount combined.
     FlashBorrowReserveLiquidity {
         /// Amount of liquidity to flash borrow
         liquidity_amount: u64,
@@ -459,10 +562,10 @@ pub enum LendingInstruction {
     /// Accounts expected by this instruction:
     ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      $authority can transfer $liquidity_amount.
     ///   1. `[writable]` Destination liquidity token account.
     ///   2. `[writable]` Flash loan fee receiver account.
-    ///                     Must match the reserve liquidity fee receiver.
+    ///      Must match the reserve liquidity fee receiver.
     ///   3. `[writable]` Host fee receiver.
     ///   4. `[writable]` Reserve account.
     ///   5. `[]` Lending market account.
@@ -479,6 +582,11 @@ pub enum LendingInstruction {
     // 21
     /// Forgive Debt
     ///
+    /// Socializes the bad debt of an obligation whose collateral has been fully liquidated.
+    /// Removing the debt from the reserve's borrows lowers the collateral exchange rate, so the
+    /// loss is spread across the reserve's cToken holders. The exchange rate can't be pushed below
+    /// 1% of the cToken supply.
+    ///
     /// Accounts expected by this instruction:
     ///  0. `[writable]` Obligation account - refreshed.
     ///  1. `[writable]` Reserve account - refreshed.
@@ -496,7 +604,7 @@ pub enum LendingInstruction {
     /// 0. `[]` Lending market account.
     /// 1. `[signer]` Lending market owner.
     /// 2. `[writable]` Lending market metadata account.
-    /// Must be a pda with seeds [lending_market, "MetaData"]
+    ///    Must be a pda with seeds [lending_market, "MetaData"]
     /// 3. `[]` System program
     UpdateMarketMetadata,
 
@@ -516,11 +624,14 @@ pub enum LendingInstruction {
     // 24
     /// DonateToReserve
     ///
+    /// Gives liquidity to a reserve without minting collateral tokens, raising the collateral
+    /// exchange rate for every depositor. The reserve must have collateral tokens outstanding.
+    ///
     ///   0. `[writable]` Source liquidity token account.
-    ///                     Minted by repay reserve liquidity mint.
-    ///                     $authority can transfer $liquidity_amount.
+    ///      Minted by reserve liquidity mint.
+    ///      $authority can transfer $liquidity_amount.
     ///   1. `[writable]` Destination reserve liquidity supply SPL Token account.
-    ///   2. `[writable]` Repay reserve account - refreshed.
+    ///   2. `[writable]` Reserve account.
     ///   3. `[]` Lending market account.
     ///   4. `[signer]` User transfer authority ($authority).
     ///   5. `[]` Token program id.
@@ -528,1389 +639,5569 @@ pub enum LendingInstruction {
         /// amount to donate
         liquidity_amount: u64,
     },
-}
 
-impl LendingInstruction {
-    /// Unpacks a byte buffer into a [LendingInstruction](enum.LendingInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (&tag, rest) = input
-            .split_first()
-            .ok_or(LendingError::InstructionUnpackError)?;
-        Ok(match tag {
-            0 => {
-                let (owner, rest) = Self::unpack_pubkey(rest)?;
-                let (quote_currency, _rest) = Self::unpack_bytes32(rest)?;
-                Self::InitLendingMarket {
-                    owner,
-                    quote_currency: *quote_currency,
-                }
-            }
-            1 => {
-                let (new_owner, rest) = Self::unpack_pubkey(rest)?;
-                let (window_duration, rest) = Self::unpack_u64(rest)?;
-                let (max_outflow, rest) = Self::unpack_u64(rest)?;
-                let (whitelisted_liquidator, rest) = match Self::unpack_u8(rest)? {
-                    (0, rest) => (None, rest),
-                    (1, rest) => {
-                        let (pubkey, rest) = Self::unpack_pubkey(rest)?;
-                        (Some(pubkey), rest)
-                    }
-                    _ => return Err(LendingError::InstructionUnpackError.into()),
-                };
+    // 25
+    /// InitObligationArchive
+    ///
+    /// Initializes a concurrent merkle tree owned by the account compression program, with the
+    /// lending market authority as the tree authority. Closed obligations are appended to it by
+    /// ArchiveObligation.
+    ///
+    ///   0. `[]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    ///   2. `[]` Derived lending market authority.
+    ///   3. `[writable]` Merkle tree account - owned by the account compression program.
+    ///   4. `[]` Account compression program id.
+    ///   5. `[]` Noop program id.
+    InitObligationArchive {
+        /// Max depth of the merkle tree
+        max_depth: u32,
+        /// Max buffer size of the merkle tree
+        max_buffer_size: u32,
+    },
 
-                let (risk_authority, _rest) = Self::unpack_pubkey(rest)?;
-                Self::SetLendingMarketOwnerAndConfig {
-                    new_owner,
-                    rate_limiter_config: RateLimiterConfig {
-                        window_duration,
-                        max_outflow,
-                    },
-                    whitelisted_liquidator,
-                    risk_authority,
-                }
-            }
-            2 => {
-                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
-                let (optimal_utilization_rate, rest) = Self::unpack_u8(rest)?;
-                let (max_utilization_rate, rest) = Self::unpack_u8(rest)?;
-                let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
-                let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
-                let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
-                let (min_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (optimal_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (super_max_borrow_rate, rest) = Self::unpack_u64(rest)?;
-                let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
-                let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
-                let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
-                let (deposit_limit, rest) = Self::unpack_u64(rest)?;
-                let (borrow_limit, rest) = Self::unpack_u64(rest)?;
-                let (fee_receiver, rest) = Self::unpack_pubkey(rest)?;
-                let (protocol_liquidation_fee, rest) = Self::unpack_u8(rest)?;
-                let (protocol_take_rate, rest) = Self::unpack_u8(rest)?;
-                let (added_borrow_weight_bps, rest) = Self::unpack_u64(rest)?;
-                let (asset_type, rest) = Self::unpack_u8(rest)?;
-                let (max_liquidation_bonus, rest) = Self::unpack_u8(rest)?;
-                let (max_liquidation_threshold, rest) = Self::unpack_u8(rest)?;
-                let (scaled_price_offset_bps, rest) = Self::unpack_i64(rest)?;
-                let (extra_oracle_pubkey, rest) = match Self::unpack_u8(rest)? {
-                    (0, rest) => (None, rest),
-                    (1, rest) => {
-                        let (pubkey, rest) = Self::unpack_pubkey(rest)?;
-                        (Some(pubkey), rest)
-                    }
-                    _ => return Err(LendingError::InstructionUnpackError.into()),
-                };
-                let (attributed_borrow_limit_open, rest) = Self::unpack_u64(rest)?;
-                let (attributed_borrow_limit_close, _rest) = Self::unpack_u64(rest)?;
-                Self::InitReserve {
-                    liquidity_amount,
-                    config: ReserveConfig {
-                        optimal_utilization_rate,
-                        max_utilization_rate,
-                        loan_to_value_ratio,
-                        liquidation_bonus,
-                        max_liquidation_bonus,
-                        liquidation_threshold,
-                        max_liquidation_threshold,
-                        min_borrow_rate,
-                        optimal_borrow_rate,
-                        max_borrow_rate,
-                        super_max_borrow_rate,
-                        fees: ReserveFees {
-                            borrow_fee_wad,
-                            flash_loan_fee_wad,
-                            host_fee_percentage,
-                        },
-                        deposit_limit,
-                        borrow_limit,
-                        fee_receiver,
-                        protocol_liquidation_fee,
-                        protocol_take_rate,
-                        added_borrow_weight_bps,
-                        reserve_type: ReserveType::from_u8(asset_type).unwrap(),
-                        scaled_price_offset_bps,
-                        extra_oracle_pubkey,
-                        attributed_borrow_limit_open,
-                        attributed_borrow_limit_close,
-                    },
-                }
-            }
-            3 => Self::RefreshReserve,
-            4 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::DepositReserveLiquidity { liquidity_amount }
-            }
-            5 => {
-                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::RedeemReserveCollateral { collateral_amount }
-            }
-            6 => Self::InitObligation,
-            7 => Self::RefreshObligation,
-            8 => {
-                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::DepositObligationCollateral { collateral_amount }
-            }
-            9 => {
-                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::WithdrawObligationCollateral { collateral_amount }
-            }
-            10 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::BorrowObligationLiquidity { liquidity_amount }
-            }
-            11 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::RepayObligationLiquidity { liquidity_amount }
-            }
-            12 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::LiquidateObligation { liquidity_amount }
-            }
-            13 => {
-                let (amount, _rest) = Self::unpack_u64(rest)?;
-                Self::FlashLoan { amount }
-            }
-            14 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::DepositReserveLiquidityAndObligationCollateral { liquidity_amount }
-            }
-            15 => {
-                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::WithdrawObligationCollateralAndRedeemReserveCollateral { collateral_amount }
-            }
-            16 => {
-                let (optimal_utilization_rate, rest) = Self::unpack_u8(rest)?;
-                let (max_utilization_rate, rest) = Self::unpack_u8(rest)?;
-                let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
-                let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
-                let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
-                let (min_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (optimal_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (super_max_borrow_rate, rest) = Self::unpack_u64(rest)?;
-                let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
-                let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
-                let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
-                let (deposit_limit, rest) = Self::unpack_u64(rest)?;
-                let (borrow_limit, rest) = Self::unpack_u64(rest)?;
-                let (fee_receiver, rest) = Self::unpack_pubkey(rest)?;
-                let (protocol_liquidation_fee, rest) = Self::unpack_u8(rest)?;
-                let (protocol_take_rate, rest) = Self::unpack_u8(rest)?;
-                let (added_borrow_weight_bps, rest) = Self::unpack_u64(rest)?;
-                let (asset_type, rest) = Self::unpack_u8(rest)?;
-                let (max_liquidation_bonus, rest) = Self::unpack_u8(rest)?;
-                let (max_liquidation_threshold, rest) = Self::unpack_u8(rest)?;
-                let (scaled_price_offset_bps, rest) = Self::unpack_i64(rest)?;
-                let (extra_oracle_pubkey, rest) = match Self::unpack_u8(rest)? {
-                    (0, rest) => (None, rest),
-                    (1, rest) => {
-                        let (pubkey, rest) = Self::unpack_pubkey(rest)?;
-                        (Some(pubkey), rest)
-                    }
-                    _ => return Err(LendingError::InstructionUnpackError.into()),
-                };
-                let (attributed_borrow_limit_open, rest) = Self::unpack_u64(rest)?;
-                let (attributed_borrow_limit_close, rest) = Self::unpack_u64(rest)?;
-                let (window_duration, rest) = Self::unpack_u64(rest)?;
-                let (max_outflow, _rest) = Self::unpack_u64(rest)?;
+    // 26
+    /// ArchiveObligation
+    ///
+    /// Appends the final state of an obligation with no deposits and no borrows to the market's
+    /// obligation archive and closes the obligation account, along with the accounts derived from
+    /// its address. Positions with unclaimed rewards are kept on the obligation, so its rewards
+    /// must be claimed first.
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[]` Derived lending market authority.
+    ///   3. `[signer]` Obligation owner.
+    ///   4. `[writable]` Destination account for the lamports of the closed accounts.
+    ///   5. `[writable]` Merkle tree account - initialized by InitObligationArchive.
+    ///   6. `[]` Account compression program id.
+    ///   7. `[]` Noop program id.
+    ///   8. `[writable]` Obligation counter account of the owner - uninitialized if the owner's
+    ///      obligations were never counted.
+    ArchiveObligation,
 
-                Self::UpdateReserveConfig {
-                    config: ReserveConfig {
-                        optimal_utilization_rate,
-                        max_utilization_rate,
-                        loan_to_value_ratio,
-                        liquidation_bonus,
-                        max_liquidation_bonus,
-                        liquidation_threshold,
-                        max_liquidation_threshold,
-                        min_borrow_rate,
-                        optimal_borrow_rate,
-                        max_borrow_rate,
-                        super_max_borrow_rate,
-                        fees: ReserveFees {
-                            borrow_fee_wad,
-                            flash_loan_fee_wad,
-                            host_fee_percentage,
-                        },
-                        deposit_limit,
-                        borrow_limit,
-                        fee_receiver,
-                        protocol_liquidation_fee,
-                        protocol_take_rate,
-                        added_borrow_weight_bps,
-                        reserve_type: ReserveType::from_u8(asset_type).unwrap(),
-                        scaled_price_offset_bps,
-                        extra_oracle_pubkey,
-                        attributed_borrow_limit_open,
-                        attributed_borrow_limit_close,
-                    },
-                    rate_limiter_config: RateLimiterConfig {
-                        window_duration,
-                        max_outflow,
-                    },
-                }
-            }
-            17 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::LiquidateObligationAndRedeemReserveCollateral { liquidity_amount }
-            }
-            18 => Self::RedeemFees,
-            19 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::FlashBorrowReserveLiquidity { liquidity_amount }
-            }
-            20 => {
-                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
-                let (borrow_instruction_index, _rest) = Self::unpack_u8(rest)?;
-                Self::FlashRepayReserveLiquidity {
-                    liquidity_amount,
-                    borrow_instruction_index,
-                }
-            }
-            21 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::ForgiveDebt { liquidity_amount }
-            }
-            22 => Self::UpdateMarketMetadata,
-            23 => {
-                let (closeable, _rest) = match Self::unpack_u8(rest)? {
-                    (0, rest) => (false, rest),
-                    (1, rest) => (true, rest),
-                    _ => return Err(LendingError::InstructionUnpackError.into()),
-                };
+    // 27
+    /// RotateReserveCollateralSupply
+    ///
+    /// Moves the reserve's collateral supply to a new token account. The full balance of the
+    /// current collateral supply is transferred to the new account and the old account is closed,
+    /// returning its rent to the lending market owner.
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[]` Derived lending market authority.
+    ///   3. `[writable, signer]` Lending market owner.
+    ///   4. `[writable]` Current reserve collateral supply SPL Token account.
+    ///   5. `[writable]` New reserve collateral supply SPL Token account - owned by the authority.
+    ///   6. `[]` Token program id.
+    RotateReserveCollateralSupply,
 
-                Self::SetObligationCloseabilityStatus { closeable }
-            }
-            24 => {
-                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
-                Self::DonateToReserve { liquidity_amount }
-            }
-            _ => {
-                msg!("Instruction cannot be unpacked");
-                return Err(LendingError::InstructionUnpackError.into());
-            }
-        })
-    }
+    // 28
+    /// RotateReserveFeeReceiver
+    ///
+    /// Moves the reserve's liquidity fee receiver to a new token account. If the current fee
+    /// receiver is owned by the lending market authority, its full balance is transferred to the
+    /// new account and the old account is closed, returning its rent to the solend market owner.
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[]` Derived lending market authority.
+    ///   3. `[writable, signer]` Solend market owner.
+    ///   4. `[writable]` Current reserve liquidity fee receiver SPL Token account.
+    ///   5. `[writable]` New reserve liquidity fee receiver SPL Token account.
+    ///   6. `[]` Token program id.
+    RotateReserveFeeReceiver,
 
-    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
-        if input.len() < 8 {
-            msg!("u64 cannot be unpacked");
+    // 29
+    /// SetDefaultHostFeeReceiver
+    ///
+    /// Sets the wallet whose associated token accounts are the only host fee receivers borrows
+    /// without a referrer can pay the host fee to. Omitting the wallet clears it.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    ///   2. `[optional]` Default host fee receiver wallet.
+    SetDefaultHostFeeReceiver,
+
+    // 30
+    /// Repay borrowed liquidity to several reserves at once. Interest is accrued on each repay
+    /// reserve by the instruction, but their prices aren't refreshed, and the obligation is only
+    /// written once. Each reserve can only be provided once.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` User transfer authority ($authority).
+    ///   3. `[]` Token program id.
+    ///
+    /// Followed by one group of accounts per liquidity amount:
+    ///
+    ///   0. `[writable]` Source liquidity token account - $authority can transfer the amount.
+    ///   1. `[writable]` Destination repay reserve liquidity supply SPL Token account.
+    ///   2. `[writable]` Repay reserve account.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed last, counts the action for the reserves they belong to.
+    RepayObligationLiquidityMany {
+        /// Amounts of liquidity to repay, in the same order as the account groups - u64::MAX for
+        /// 100% of borrowed amount
+        liquidity_amounts: Vec<u64>,
+    },
+
+    // 31
+    /// SetReserveUtilizationKillSwitch
+    ///
+    /// Configures the switch that puts the reserve in emergency mode, blocking borrows, once its
+    /// utilization stays above a threshold for a number of slots. Also takes the reserve out of
+    /// that mode.
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Lending market owner.
+    SetReserveUtilizationKillSwitch {
+        /// Utilization percent above which the switch counts a refresh - 0 to disable
+        emergency_utilization_threshold: u8,
+        /// Slots utilization must stay above the threshold to trigger the switch
+        emergency_utilization_slots: u64,
+    },
+
+    // 32
+    /// CrankCloseEmptyObligation
+    ///
+    /// Permissionless. Closes an obligation with no deposits and no borrows that hasn't been
+    /// updated for more than EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS. The account's lamports are split
+    /// between the cranker and the obligation owner. The accounts derived from the obligation
+    /// address are closed too, returning their lamports to the owner. An obligation that still
+    /// has unclaimed rewards keeps the positions that earned them and can't be closed.
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[writable]` Obligation owner - receives the lamports not paid as the bounty.
+    ///   3. `[writable]` Bounty destination account.
+    ///   4. `[writable]` Obligation counter account of the owner - uninitialized if the owner's
+    ///      obligations were never counted.
+    CrankCloseEmptyObligation,
+
+    // 33
+    /// SetObligationBorrowLock
+    ///
+    /// Locks new borrows from the obligation immediately, or requests an unlock that takes effect
+    /// after OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS.
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Obligation owner.
+    SetObligationBorrowLock {
+        /// True to lock borrows, false to request an unlock
+        borrows_locked: bool,
+    },
+
+    // 34
+    /// SetEmergencyPrice
+    ///
+    /// Sets the price RefreshReserve falls back to when both of the reserve's oracles fail, so
+    /// repays and liquidations can continue through an oracle outage. Borrows, and collateral
+    /// withdrawals from obligations with borrows, are blocked while the reserve is priced this way. The price expires after max_duration_slots,
+    /// which is capped at MAX_EMERGENCY_PRICE_DURATION_SLOTS. A duration of 0 clears the price.
+    ///
+    ///   0. `[writable]` Emergency price account - PDA of the reserve address and EMERGENCY_PRICE_SEED.
+    ///   1. `[]` Reserve account.
+    ///   2. `[]` Lending market account.
+    ///   3. `[signer, writable]` Risk authority or lending market owner - pays for the account.
+    ///   4. `[]` System program.
+    SetEmergencyPrice {
+        /// Price of one whole liquidity token in the quote currency
+        price: Decimal,
+        /// Number of slots the price can be used for
+        max_duration_slots: u64,
+    },
+
+    // 35
+    /// SetRewardRule
+    ///
+    /// Creates or reconfigures the rule streaming a reward token to one side of a reserve. The
+    /// emission schedule is stored on the reserve, whose rewards up to the current slot are
+    /// accrued at the old rate first.
+    ///
+    ///   0. `[writable]` Reward rule account - PDA of the reserve address, REWARD_RULE_SEED and the side.
+    ///   1. `[writable]` Reserve account.
+    ///   2. `[]` Lending market account.
+    ///   3. `[]` Reward supply token account - owned by the lending market authority.
+    ///   4. `[signer, writable]` Lending market owner - pays for the account.
+    ///   5. `[]` System program.
+    SetRewardRule {
+        /// Whether deposits or borrows are rewarded
+        side: RewardSide,
+        /// Reward tokens emitted per slot
+        emission_per_slot: u64,
+        /// First slot rewards are emitted in
+        start_slot: u64,
+        /// Slot from which rewards are no longer emitted
+        end_slot: u64,
+    },
+
+    // 36
+    /// UpdateRewards
+    ///
+    /// Accrues a reserve's rewards and credits an obligation's deposit and borrow in the reserve
+    /// with the rewards they earned since they were last settled. Positions are also settled by
+    /// every instruction that changes them and by RefreshObligation, so this is only needed to
+    /// bring the unclaimed rewards up to date without either.
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[writable]` Obligation account.
+    UpdateRewards,
+
+    // 37
+    /// ClaimRewards
+    ///
+    /// Settles the rewards of an obligation's position on the reward rule's side of the reserve
+    /// and transfers the whole tokens earned to the destination. A position that was closed
+    /// with rewards left to claim is removed once they are claimed.
+    ///
+    ///   0. `[]` Reward rule account.
+    ///   1. `[writable]` Reserve account.
+    ///   2. `[writable]` Obligation account.
+    ///   3. `[writable]` Reward supply token account.
+    ///   4. `[writable]` Destination token account for the reward token.
+    ///   5. `[]` Lending market account.
+    ///   6. `[]` Derived lending market authority.
+    ///   7. `[signer]` Obligation owner.
+    ///   8. `[]` Token program id.
+    ClaimRewards,
+
+    // 38
+    /// SetAssetCategory
+    ///
+    /// Sets the limits of one of the lending market's asset categories, eg stablecoins or SOL and
+    /// its LSTs, which obligations whose deposits and borrows are all in the category use where
+    /// they are higher than the reserves' own.
+    ///
+    ///   0. `[writable]` Asset categories account - PDA of the lending market address and ASSET_CATEGORIES_SEED.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer, writable]` Lending market owner - pays for the asset categories account.
+    ///   3. `[]` System program.
+    SetAssetCategory {
+        /// Category to set, from 1 to MAX_ASSET_CATEGORIES
+        asset_category: u8,
+        /// Loan to value ratio of the category's collateral, as a percentage
+        loan_to_value_ratio: u8,
+        /// Liquidation threshold of the category's collateral, as a percentage
+        liquidation_threshold: u8,
+        /// Max liquidation threshold of the category's collateral, as a percentage
+        max_liquidation_threshold: u8,
+    },
+
+    // 39
+    /// SetRiskAuthority
+    ///
+    /// Sets the lending market's risk authority, which can only tighten reserve parameters and
+    /// can't change fees or fee receivers.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetRiskAuthority {
+        /// The new risk authority
+        risk_authority: Pubkey,
+    },
+
+    // 40
+    /// SetMaxObligationsPerOwner
+    ///
+    /// Caps how many obligations an owner can initialize in the lending market. Obligations
+    /// initialized while there was no cap aren't counted.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetMaxObligationsPerOwner {
+        /// Max obligations per owner, 0 to remove the cap
+        max_obligations_per_owner: u16,
+    },
+
+    // 41
+    /// SetProtocolFeeConfig
+    ///
+    /// Sets who can withdraw the lending market's protocol fees, the treasury they are sent to and
+    /// the cap on how much value can be withdrawn per window. Changing the rate limiter config
+    /// resets the rate limiter.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetProtocolFeeConfig {
+        /// Authority allowed to withdraw protocol fees
+        fee_authority: Pubkey,
+        /// Wallet whose associated token accounts receive the fees
+        treasury: Pubkey,
+        /// Rate limiter config, denominated in the quote currency
+        rate_limiter_config: RateLimiterConfig,
+    },
+
+    // 42
+    /// WithdrawProtocolFees
+    ///
+    /// Transfers protocol fees from a reserve's fee receiver to the treasury's associated token
+    /// account for the reserve liquidity mint. The reserve must be refreshed so the amount can be
+    /// valued for the rate limiter.
+    ///
+    ///   0. `[]` Reserve account - refreshed.
+    ///   1. `[writable]` Reserve liquidity fee receiver account.
+    ///   2. `[writable]` Treasury token account - associated token account of the treasury.
+    ///   3. `[writable]` Lending market account.
+    ///   4. `[]` Derived lending market authority.
+    ///   5. `[signer]` Fee authority.
+    ///   6. `[]` Token program id.
+    WithdrawProtocolFees {
+        /// Amount of liquidity to withdraw, u64::MAX for the fee receiver's whole balance
+        liquidity_amount: u64,
+    },
+
+    // 43
+    /// SetObligationReferrer
+    ///
+    /// Attributes an obligation to a referrer. Once set, the referrer can't be changed and every
+    /// borrow from the obligation pays its host fee to a token account owned by the referrer.
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Obligation owner.
+    SetObligationReferrer {
+        /// Wallet credited with the obligation's host fees
+        referrer: Pubkey,
+    },
+
+    // 44
+    /// CompoundObligationCollateral
+    ///
+    /// Combines RedeemReserveCollateral, DepositReserveLiquidity and DepositObligationCollateral
+    /// to move collateral tokens held in a wallet into an obligation. The redeemed liquidity goes
+    /// straight back into the reserve, so outflow rate limits don't apply.
+    ///
+    ///   0. `[writable]` User collateral token account - $authority can transfer $collateral_amount.
+    ///   1. `[writable]` User liquidity token account - receives the redeemed liquidity in between.
+    ///   2. `[writable]` Reserve account.
+    ///   3. `[writable]` Reserve collateral SPL Token mint.
+    ///   4. `[writable]` Reserve liquidity supply SPL Token account.
+    ///   5. `[writable]` Destination deposit reserve collateral supply SPL Token account.
+    ///   6. `[writable]` Obligation account.
+    ///   7. `[writable]` Lending market account.
+    ///   8. `[]` Derived lending market authority.
+    ///   9. `[signer]` Obligation owner.
+    ///   10. `[signer]` User transfer authority ($authority).
+    ///   11. `[]` Token program id.
+    CompoundObligationCollateral {
+        /// Amount of collateral tokens to compound - u64::MAX for the whole user collateral balance
+        collateral_amount: u64,
+    },
+
+    // 45
+    /// RepayObligationLiquidityWithCollateral
+    ///
+    /// Redeems collateral deposited in an obligation and repays the obligation's borrow with the
+    /// redeemed liquidity, without a liquidation bonus. Both reserves must have the same
+    /// liquidity mint and can be the same reserve. The collateral amount is rounded down, so the
+    /// repaid amount can be slightly below the requested one.
+    ///
+    ///   0. `[writable]` Repay reserve account - refreshed.
+    ///   1. `[writable]` Repay reserve liquidity supply SPL Token account.
+    ///   2. `[writable]` Withdraw reserve account - refreshed.
+    ///   3. `[writable]` Withdraw reserve collateral SPL Token mint.
+    ///   4. `[writable]` Withdraw reserve collateral supply SPL Token account.
+    ///   5. `[writable]` Withdraw reserve liquidity supply SPL Token account.
+    ///   6. `[writable]` Obligation account - refreshed.
+    ///   7. `[]` Lending market account.
+    ///   8. `[]` Derived lending market authority.
+    ///   9. `[signer]` Obligation owner.
+    ///   10. `[]` Token program id.
+    ///
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed last, counts the action for the reserves they belong to.
+    RepayObligationLiquidityWithCollateral {
+        /// Amount of liquidity to repay - u64::MAX for up to 100% of borrowed amount
+        liquidity_amount: u64,
+    },
+
+    // 46
+    /// DepositReserveLiquidityAndObligationCollateralAndBorrow
+    ///
+    /// Combines DepositReserveLiquidityAndObligationCollateral, RefreshObligation and
+    /// BorrowObligationLiquidity. Every reserve of the obligation must be refreshed, but the
+    /// obligation itself is refreshed after the deposit.
+    ///
+    ///   0. `[writable]` Source liquidity token account - $authority can transfer $deposit_liquidity_amount. Synced first if it's a wSOL account.
+    ///   1. `[writable]` User collateral token account - receives the minted collateral in between.
+    ///   2. `[writable]` Deposit reserve account - refreshed.
+    ///   3. `[writable]` Deposit reserve liquidity supply SPL Token account.
+    ///   4. `[writable]` Deposit reserve collateral SPL Token mint.
+    ///   5. `[writable]` Lending market account.
+    ///   6. `[]` Derived lending market authority.
+    ///   7. `[writable]` Destination deposit reserve collateral supply SPL Token account.
+    ///   8. `[writable]` Obligation account.
+    ///   9. `[signer]` Obligation owner.
+    ///   10. `[signer]` User transfer authority ($authority).
+    ///   11. `[]` Token program id.
+    ///   12. `[writable]` Borrow reserve account - refreshed.
+    ///   13. `[writable]` Borrow reserve liquidity supply SPL Token account.
+    ///   14. `[writable]` Borrow reserve liquidity fee receiver account.
+    ///   15. `[writable]` Destination liquidity token account.
+    ///
+    ///   .. `[writable]` Obligation deposit reserves, including the deposit reserve, then obligation borrow reserves - refreshed, in the obligation's order after the deposit.
+    ///   .. `[optional, writable]` Host fee receiver account - as for BorrowObligationLiquidity.
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed before any market member account, counts the action for the reserves they belong to.
+    ///   .. `[]` Market member account - of the obligation owner, required if the lending market requires a whitelist. Passed last.
+    DepositReserveLiquidityAndObligationCollateralAndBorrow {
+        /// Amount of liquidity to deposit in exchange for collateral tokens
+        deposit_liquidity_amount: u64,
+        /// Amount of liquidity to borrow - u64::MAX for 100% of borrowing power
+        borrow_liquidity_amount: u64,
+    },
+
+    // 47
+    /// InitReserveRateStats
+    ///
+    /// Creates the account tracking the moving averages of a reserve's utilization and borrow
+    /// rate, starting from the current ones. Anyone can create it.
+    ///
+    ///   0. `[writable]` Reserve rate stats account - PDA of the reserve address and RESERVE_RATE_STATS_SEED.
+    ///   1. `[]` Reserve account.
+    ///   2. `[signer, writable]` Payer.
+    ///   3. `[]` System program.
+    InitReserveRateStats,
+
+    // 48
+    /// SetDustBorrowValue
+    ///
+    /// Sets the borrow value at or below which liquidations repay the whole borrow, ignoring the
+    /// close factor, so dust borrows don't linger on obligations that can't be closed.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetDustBorrowValue {
+        /// Borrow value in whole USD, 0 to use DEFAULT_DUST_BORROW_VALUE
+        dust_borrow_value: u64,
+    },
+
+    // 49
+    /// InitReserveActivity
+    ///
+    /// Creates the account counting the deposits, withdrawals, borrows, repays and liquidations
+    /// of a reserve, along with their volumes. Anyone can create it, and it only counts the
+    /// instructions it's passed to from then on.
+    ///
+    ///   0. `[writable]` Reserve activity account - PDA of the reserve address and RESERVE_ACTIVITY_SEED.
+    ///   1. `[]` Reserve account.
+    ///   2. `[signer, writable]` Payer.
+    ///   3. `[]` System program.
+    InitReserveActivity,
+
+    // 50
+    /// DevnetMintTestTokens
+    ///
+    /// Mints test tokens to anyone. Only in builds with the devnet-utils feature.
+    ///
+    ///   0. `[writable]` Test token mint - its mint authority must be the devnet faucet authority.
+    ///   1. `[writable]` Destination token account.
+    ///   2. `[]` Devnet faucet authority - PDA of DEVNET_FAUCET_SEED.
+    ///   3. `[]` Token program id.
+    #[cfg(feature = "devnet-utils")]
+    DevnetMintTestTokens {
+        /// Amount of tokens to mint
+        amount: u64,
+    },
+
+    // 51
+    /// DevnetSetPrice
+    ///
+    /// Sets the price of a test token. Reserves whose pyth oracle is the token's devnet price
+    /// account are priced from it. Anyone can set it. Only in builds with the devnet-utils feature.
+    ///
+    ///   0. `[writable]` Devnet price account - PDA of the mint address and DEVNET_PRICE_SEED.
+    ///   1. `[]` Test token mint.
+    ///   2. `[signer, writable]` Payer.
+    ///   3. `[]` System program.
+    #[cfg(feature = "devnet-utils")]
+    DevnetSetPrice {
+        /// Price of one whole token in the quote currency
+        price: Decimal,
+    },
+
+    // 52
+    /// BorrowObligationLiquidityWithWalletCollateral
+    ///
+    /// Combines DepositObligationCollateral of the owner's whole wallet balance of the deposit
+    /// reserve's collateral, RefreshObligation and BorrowObligationLiquidity, so cTokens that were
+    /// never deposited to the obligation still back the borrow. Every reserve of the obligation
+    /// must be refreshed, but the obligation itself is refreshed after the deposit.
+    ///
+    ///   0. `[writable]` User collateral token account - $authority can transfer its balance, which can be zero.
+    ///   1. `[writable]` Deposit reserve account - refreshed.
+    ///   2. `[writable]` Destination deposit reserve collateral supply SPL Token account.
+    ///   3. `[writable]` Lending market account.
+    ///   4. `[]` Derived lending market authority.
+    ///   5. `[writable]` Obligation account.
+    ///   6. `[signer]` Obligation owner.
+    ///   7. `[signer]` User transfer authority ($authority).
+    ///   8. `[]` Token program id.
+    ///   9. `[writable]` Borrow reserve account - refreshed.
+    ///   10. `[writable]` Borrow reserve liquidity supply SPL Token account.
+    ///   11. `[writable]` Borrow reserve liquidity fee receiver account.
+    ///   12. `[writable]` Destination liquidity token account.
+    ///
+    ///   .. `[writable]` Obligation deposit reserves, then obligation borrow reserves - refreshed, in the obligation's order after the deposit.
+    ///   .. `[optional, writable]` Host fee receiver account - as for BorrowObligationLiquidity.
+    ///   .. `[writable]` Reserve activity accounts (optional). Passed before any market member account, counts the action for the reserves they belong to.
+    ///   .. `[]` Market member account - of the obligation owner, required if the lending market requires a whitelist. Passed last.
+    BorrowObligationLiquidityWithWalletCollateral {
+        /// Amount of liquidity to borrow - u64::MAX for 100% of borrowing power
+        liquidity_amount: u64,
+    },
+
+    // 53
+    /// CloseReserve
+    ///
+    /// Closes a reserve with no borrows, no collateral in circulation and no unredeemed protocol
+    /// fees, along with its supply token accounts, its fee receiver if the lending market
+    /// authority owns it, and the accounts derived from its address. Rent goes to the lending
+    /// market owner. Rewards left unclaimed in the reserve can't be claimed once its reward rules
+    /// are closed.
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[]` Derived lending market authority.
+    ///   3. `[writable, signer]` Lending market owner - receives the rent.
+    ///   4. `[writable]` Reserve liquidity supply SPL Token account.
+    ///   5. `[writable]` Reserve collateral supply SPL Token account.
+    ///   6. `[writable]` Reserve liquidity fee receiver account - must be empty.
+    ///   7. `[writable]` Destination liquidity token account - receives any liquidity left in the supply.
+    ///   8. `[]` Token program id.
+    ///   9. `[writable]` Reserve pair borrow weights account - closed if it exists.
+    ///   10. `[writable]` Reserve rate stats account - closed if it exists.
+    ///   11. `[writable]` Reserve activity account - closed if it exists.
+    ///   12. `[writable]` Reserve cToken price account - closed if it exists.
+    ///   13. `[writable]` Reserve spread fee receiver account - closed if it exists.
+    ///   14. `[writable]` Reserve emergency price account - closed if it exists.
+    ///   15. `[writable]` Reserve deposit reward rule account - closed if it exists.
+    ///   16. `[writable]` Reserve borrow reward rule account - closed if it exists.
+    CloseReserve,
+
+    // 54 was SetReserveFlashLoanCap, replaced by the reserve config's max_flash_loan_amount
+
+    // 55
+    /// SetReserveTertiaryOracle
+    ///
+    /// Sets the oracle a reserve falls back to when neither its pyth nor its switchboard oracle
+    /// has a usable price. Refreshes of the reserve must then be passed the oracle.
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Lending market owner.
+    ///   3. `[]` Oracle price account, or the null pubkey to remove the tertiary oracle.
+    SetReserveTertiaryOracle,
+
+    // 56
+    /// RefreshCTokenPrice
+    ///
+    /// Writes the price of a reserve's collateral token, its liquidity price and the collateral
+    /// exchange rate into the reserve's ctoken price account, creating it on first use, so other
+    /// programs can price the collateral token like they would with an oracle. Anyone can call it,
+    /// and the reserve must be refreshed in the same slot.
+    ///
+    ///   0. `[writable]` Reserve ctoken price account - PDA of the reserve address and RESERVE_CTOKEN_PRICE_SEED.
+    ///   1. `[]` Reserve account - refreshed.
+    ///   2. `[signer, writable]` Payer - only charged when the account is created.
+    ///   3. `[]` System program.
+    RefreshCTokenPrice,
+
+    // 57
+    /// RedeemFeesIfAbove
+    ///
+    /// RedeemFees, but only when the reserve's unredeemed protocol fees are above `min_amount`.
+    /// Does nothing otherwise instead of failing, so fee cranks can run it on a schedule.
+    ///
+    ///   0. `[writable]` Reserve account - refreshed.
+    ///   1. `[writable]` Reserve liquidity fee receiver account.
+    ///   2. `[writable]` Reserve liquidity supply SPL Token account.
+    ///   3. `[]` Lending market account.
+    ///   4. `[]` Derived lending market authority.
+    ///   5. `[]` Token program id.
+    ///   6. `[writable]` Insurance fund account - required if the reserve has an insurance fund.
+    ///      .. `[]` Reserve spread fee receiver account - required if the reserve has a spread fee receiver.
+    ///      .. `[writable]` Spread fee receiver token account - required if the reserve has a spread fee receiver.
+    RedeemFeesIfAbove {
+        /// Amount of liquidity the unredeemed fees must exceed
+        min_amount: u64,
+    },
+
+    // 58
+    /// SetObligationMaxOutflow
+    ///
+    /// Sets the max value a single obligation can withdraw and borrow per window of the market
+    /// rate limiter. While it's set, withdraws and borrows count against the obligation's rate
+    /// limiter, so obligations must be migrated to the current layout to make them.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetObligationMaxOutflow {
+        /// Max outflow in whole USD, 0 for no limit
+        obligation_max_outflow: u64,
+    },
+
+    // 59 was InitObligationRateLimiter, whose rate limiter is now on the obligation
+
+    // 60
+    /// ResetRateLimiter
+    ///
+    /// Clears the outflow counted by the lending market's rate limiter, and by the rate limiters
+    /// of any reserves passed, without waiting for the window to pass. The configs are kept.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    ///
+    ///   .. `[writable]` Reserve accounts of the lending market (optional).
+    ResetRateLimiter,
+
+    // 61
+    /// SetObligationBorrowCap
+    ///
+    /// Caps the value the obligation can borrow. Lowering the cap takes effect immediately, while
+    /// raising or removing it also locks borrows for OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS.
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Obligation owner.
+    SetObligationBorrowCap {
+        /// Max borrowed value in whole USD, 0 for no cap
+        borrow_cap: u32,
+    },
+
+    // 62
+    /// DelegateBorrowAuthority
+    ///
+    /// Lets another wallet borrow against the obligation's collateral, up to a delegated value.
+    /// Replaces any previous delegation, a delegated value of 0 revokes it.
+    ///
+    ///   0. `[writable]` Obligation account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Obligation owner.
+    DelegateBorrowAuthority {
+        /// Wallet allowed to borrow on the owner's behalf
+        approved_borrower: Pubkey,
+        /// Value the approved borrower can borrow in total, in whole USD
+        delegated_value: u64,
+    },
+
+    // 63
+    /// SetReservePairBorrowWeight
+    ///
+    /// Scales the borrow weight of a reserve for obligations backed by a given collateral reserve,
+    /// eg to make borrowing against correlated collateral cheaper. Refreshes of obligations
+    /// borrowing from the reserve must then pass its pair borrow weights account.
+    ///
+    ///   0. `[writable]` Reserve pair borrow weights account - PDA of the borrow reserve address and RESERVE_PAIR_BORROW_WEIGHTS_SEED.
+    ///   1. `[writable]` Borrow reserve account.
+    ///   2. `[]` Collateral reserve account.
+    ///   3. `[]` Lending market account.
+    ///   4. `[signer, writable]` Lending market owner - pays for the reserve pair borrow weights account.
+    ///   5. `[]` System program.
+    SetReservePairBorrowWeight {
+        /// Multiplier of the borrow weight in bps, 0 removes the collateral reserve's multiplier
+        borrow_weight_bps: u16,
+    },
+
+    // 64
+    /// SetWhitelistRequired
+    ///
+    /// Restricts deposits, borrows and new obligations in the lending market to wallets the owner
+    /// made members with UpdateMarketMember. Those instructions then take the market member
+    /// account of the depositor, borrower or obligation owner as their last account.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetWhitelistRequired {
+        /// Whether a market member account is required
+        whitelist_required: bool,
+    },
+
+    // 65
+    /// UpdateMarketMember
+    ///
+    /// Adds a wallet to the members of the lending market, or revokes its membership.
+    ///
+    ///   0. `[writable]` Market member account - PDA of the lending market, member and MARKET_MEMBER_SEED.
+    ///   1. `[]` Member wallet.
+    ///   2. `[]` Lending market account.
+    ///   3. `[signer, writable]` Lending market owner - pays for the market member account.
+    ///   4. `[]` System program.
+    UpdateMarketMember {
+        /// Whether the wallet is a member
+        is_member: bool,
+    },
+
+    // 66
+    /// SetLiquidatorWhitelist
+    ///
+    /// Sets the liquidators that get a protected window on newly unhealthy obligations of the
+    /// lending market. During the window only they can liquidate, afterwards anyone can. A window
+    /// of 0 makes liquidations permissionless again.
+    ///
+    ///   0. `[writable]` Liquidator whitelist account - PDA of the lending market and LIQUIDATOR_WHITELIST_SEED.
+    ///   1. `[writable]` Lending market account.
+    ///   2. `[signer, writable]` Lending market owner - pays for the liquidator whitelist account.
+    ///   3. `[]` System program.
+    SetLiquidatorWhitelist {
+        /// Slots after an obligation becomes unhealthy during which only the liquidators can
+        /// liquidate it
+        protected_window_slots: u64,
+        /// Whitelisted liquidators, at most MAX_WHITELISTED_LIQUIDATORS
+        liquidators: Vec<Pubkey>,
+    },
+
+    // 67
+    /// SetInsuranceFee
+    ///
+    /// Sets the share of redeemed interest spread that RedeemFees sends to the lending market's
+    /// insurance funds instead of the reserve fee receivers. Only reserves initialized with
+    /// InitInsuranceFund pay it.
+    ///
+    ///   0. `[writable]` Lending market account.
+    ///   1. `[signer]` Lending market owner.
+    SetInsuranceFee {
+        /// Insurance fund share in basis points, at most MAX_INSURANCE_FEE_BPS
+        insurance_fee_bps: u16,
+    },
+
+    // 68
+    /// InitInsuranceFund
+    ///
+    /// Makes a reserve pay the insurance fee share of its redeemed interest spread into the
+    /// lending market's insurance fund for its liquidity mint, creating the fund's token account
+    /// if no other reserve with that mint has.
+    ///
+    ///   0. `[writable]` Insurance fund account - PDA of the lending market, liquidity mint and INSURANCE_FUND_SEED.
+    ///   1. `[writable]` Reserve account.
+    ///   2. `[]` Reserve liquidity mint account.
+    ///   3. `[]` Lending market account.
+    ///   4. `[]` Derived lending market authority.
+    ///   5. `[signer, writable]` Lending market owner - pays for the insurance fund account.
+    ///   6. `[]` System program.
+    ///   7. `[]` Rent sysvar.
+    ///   8. `[]` Token program id.
+    InitInsuranceFund,
+
+    // 69
+    /// CoverBadDebt
+    ///
+    /// Repays a reserve's socialized losses, eg debt forgiven with ForgiveDebt, from the lending
+    /// market's insurance fund by moving liquidity into the reserve supply.
+    ///
+    ///   0. `[writable]` Insurance fund account.
+    ///   1. `[writable]` Reserve account.
+    ///   2. `[writable]` Reserve liquidity supply SPL Token account.
+    ///   3. `[]` Lending market account.
+    ///   4. `[]` Derived lending market authority.
+    ///   5. `[signer]` Lending market owner.
+    ///   6. `[]` Token program id.
+    CoverBadDebt {
+        /// Amount of liquidity to move from the insurance fund to the reserve
+        liquidity_amount: u64,
+    },
+
+    // 70
+    /// WithdrawObligationLiquidityExact
+    ///
+    /// WithdrawObligationCollateralAndRedeemReserveCollateral for a liquidity amount. The
+    /// collateral to withdraw is converted at the reserve's current exchange rate, rounded up.
+    /// Exactly liquidity_amount is received; whatever the rounded up collateral redeems beyond
+    /// that stays in the reserve.
+    ///
+    /// Accounts expected by this instruction are the same as for
+    /// WithdrawObligationCollateralAndRedeemReserveCollateral.
+    WithdrawObligationLiquidityExact {
+        /// Amount of liquidity to receive, u64::MAX to withdraw as much collateral as possible
+        liquidity_amount: u64,
+    },
+
+    // 71
+    /// SetMaxPriceAge
+    ///
+    /// Sets how many slots after a reserve refresh RefreshObligation still accepts the reserve's
+    /// price, so obligations with many reserves don't need them all refreshed in one slot to be
+    /// valued.
+    ///
+    ///   0. `[writable]` Max price age account - PDA of the lending market address and MAX_PRICE_AGE_SEED.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer, writable]` Lending market owner - pays for the account.
+    ///   3. `[]` System program.
+    SetMaxPriceAge {
+        /// Max price age in slots, 0 to require reserves refreshed in the same slot, at most
+        /// MAX_PRICE_AGE_SLOTS
+        max_price_age_slots: u8,
+    },
+
+    // 72
+    /// BuyRateCap
+    ///
+    /// Caps the borrow APR of an obligation's borrow at the reserve's borrow_rate_cap for an
+    /// upfront premium of rate_cap_premium_bps of the borrowed amount, paid into the lending
+    /// market's insurance fund. The borrow is only charged interest up to the cap. The reserve
+    /// still accrues its own rate, and the part above the cap is kept as a credit that
+    /// ClaimRateCapCredit pays the reserve from the insurance fund. A capped borrow can't be
+    /// increased.
+    ///
+    ///   0. `[writable]` Source liquidity token account.
+    ///      $authority can transfer $premium.
+    ///   1. `[writable]` Insurance fund account.
+    ///   2. `[]` Borrow reserve account.
+    ///   3. `[writable]` Obligation account - refreshed.
+    ///   4. `[]` Lending market account.
+    ///   5. `[signer]` Obligation owner.
+    ///   6. `[signer]` User transfer authority ($authority).
+    ///   7. `[]` Token program id.
+    BuyRateCap,
+
+    // 73
+    /// ClaimRateCapCredit
+    ///
+    /// Pays a capped borrow's rate cap credit, the reserve's interest above the borrow's rate cap,
+    /// from the lending market's insurance fund to the reserve, up to the fund's balance. Anyone
+    /// can claim for any obligation. A repaid borrow stays on the obligation until its credit is
+    /// paid.
+    ///
+    ///   0. `[writable]` Insurance fund account.
+    ///   1. `[writable]` Borrow reserve account.
+    ///   2. `[writable]` Borrow reserve liquidity supply SPL Token account.
+    ///   3. `[writable]` Obligation account.
+    ///   4. `[]` Lending market account.
+    ///   5. `[]` Derived lending market authority.
+    ///   6. `[]` Token program id.
+    ClaimRateCapCredit,
+
+    // 74
+    /// SetReserveSpreadFeeReceiver
+    ///
+    /// Sends a reserve's redeemed interest spread to a token account other than its fee receiver,
+    /// which keeps receiving origination fees. Passing the fee receiver sends the spread back to it.
+    ///
+    ///   0. `[writable]` Reserve spread fee receiver account - PDA of the reserve address and RESERVE_SPREAD_FEE_RECEIVER_SEED.
+    ///   1. `[writable]` Reserve account.
+    ///   2. `[]` Spread fee receiver token account - of the reserve liquidity mint.
+    ///   3. `[]` Lending market account.
+    ///   4. `[signer, writable]` Lending market owner - pays for the account.
+    ///   5. `[]` Token program id.
+    ///   6. `[]` System program.
+    SetReserveSpreadFeeReceiver,
+
+    // 75
+    /// MigrateAccount
+    ///
+    /// Reallocates a lending market, reserve or obligation with the version 1 layout to the
+    /// current layout and bumps its version. The fields added since start out zeroed, which leaves
+    /// them disabled. An obligation's positions start at their reserve's current reward index.
+    /// Unmigrated accounts keep working, but can't use anything stored in those fields, and their
+    /// obligations earn no rewards. Anyone can migrate any account, and the payer funds the
+    /// extra rent.
+    ///
+    ///   0. `[writable]` Lending market, reserve or obligation account.
+    ///   1. `[signer, writable]` Payer.
+    ///   2. `[]` System program.
+    ///      .. `[]` Obligation only: its deposit reserve accounts, then its borrow reserve accounts, in the obligation's order.
+    MigrateAccount,
+}
+
+impl LendingInstruction {
+    /// Unpacks a byte buffer into a [LendingInstruction](enum.LendingInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok(match tag {
+            0 => {
+                let (owner, rest) = Self::unpack_pubkey(rest)?;
+                let (quote_currency, _rest) = Self::unpack_bytes32(rest)?;
+                Self::InitLendingMarket {
+                    owner,
+                    quote_currency: *quote_currency,
+                }
+            }
+            1 => {
+                let (new_owner, rest) = Self::unpack_pubkey(rest)?;
+                let (window_duration, rest) = Self::unpack_u64(rest)?;
+                let (max_outflow, rest) = Self::unpack_u64(rest)?;
+                let (whitelisted_liquidator, rest) = match Self::unpack_u8(rest)? {
+                    (0, rest) => (None, rest),
+                    (1, rest) => {
+                        let (pubkey, rest) = Self::unpack_pubkey(rest)?;
+                        (Some(pubkey), rest)
+                    }
+                    _ => return Err(LendingError::InstructionUnpackError.into()),
+                };
+
+                let (risk_authority, _rest) = Self::unpack_pubkey(rest)?;
+                Self::SetLendingMarketOwnerAndConfig {
+                    new_owner,
+                    rate_limiter_config: RateLimiterConfig {
+                        window_duration,
+                        max_outflow,
+                    },
+                    whitelisted_liquidator,
+                    risk_authority,
+                }
+            }
+            2 => {
+                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (optimal_utilization_rate, rest) = Self::unpack_u8(rest)?;
+                let (max_utilization_rate, rest) = Self::unpack_u8(rest)?;
+                let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
+                let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
+                let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+                let (min_borrow_rate, rest) = Self::unpack_u8(rest)?;
+                let (optimal_borrow_rate, rest) = Self::unpack_u8(rest)?;
+                let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
+                let (super_max_borrow_rate, rest) = Self::unpack_u64(rest)?;
+                let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
+                let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
+                let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
+                let (deposit_limit, rest) = Self::unpack_u64(rest)?;
+                let (borrow_limit, rest) = Self::unpack_u64(rest)?;
+                let (fee_receiver, rest) = Self::unpack_pubkey(rest)?;
+                let (protocol_liquidation_fee, rest) = Self::unpack_u8(rest)?;
+                let (protocol_take_rate, rest) = Self::unpack_u8(rest)?;
+                let (added_borrow_weight_bps, rest) = Self::unpack_u64(rest)?;
+                let (asset_type, rest) = Self::unpack_u8(rest)?;
+                let (max_liquidation_bonus, rest) = Self::unpack_u8(rest)?;
+                let (max_liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+                let (scaled_price_offset_bps, rest) = Self::unpack_i64(rest)?;
+                let (extra_oracle_pubkey, rest) = match Self::unpack_u8(rest)? {
+                    (0, rest) => (None, rest),
+                    (1, rest) => {
+                        let (pubkey, rest) = Self::unpack_pubkey(rest)?;
+                        (Some(pubkey), rest)
+                    }
+                    _ => return Err(LendingError::InstructionUnpackError.into()),
+                };
+                let (attributed_borrow_limit_open, rest) = Self::unpack_u64(rest)?;
+                let (attributed_borrow_limit_close, rest) = Self::unpack_u64(rest)?;
+                let mut config = ReserveConfig {
+                    optimal_utilization_rate,
+                    max_utilization_rate,
+                    loan_to_value_ratio,
+                    liquidation_bonus,
+                    max_liquidation_bonus,
+                    liquidation_threshold,
+                    max_liquidation_threshold,
+                    min_borrow_rate,
+                    optimal_borrow_rate,
+                    max_borrow_rate,
+                    super_max_borrow_rate,
+                    fees: ReserveFees {
+                        borrow_fee_wad,
+                        flash_loan_fee_wad,
+                        host_fee_percentage,
+                    },
+                    deposit_limit,
+                    borrow_limit,
+                    fee_receiver,
+                    protocol_liquidation_fee,
+                    protocol_take_rate,
+                    added_borrow_weight_bps,
+                    reserve_type: ReserveType::from_u8(asset_type).unwrap(),
+                    scaled_price_offset_bps,
+                    extra_oracle_pubkey,
+                    attributed_borrow_limit_open,
+                    attributed_borrow_limit_close,
+                    ..ReserveConfig::default()
+                };
+                let rest = Self::unpack_reserve_config_extension(rest, &mut config)?;
+                let (collateral_decimals, _rest) = if rest.is_empty() {
+                    (None, rest)
+                } else {
+                    match Self::unpack_u8(rest)? {
+                        (0, rest) => (None, rest),
+                        (1, rest) => {
+                            let (decimals, rest) = Self::unpack_u8(rest)?;
+                            (Some(decimals), rest)
+                        }
+                        _ => return Err(LendingError::InstructionUnpackError.into()),
+                    }
+                };
+                Self::InitReserve {
+                    liquidity_amount,
+                    config,
+                    collateral_decimals,
+                }
+            }
+            3 => Self::RefreshReserve,
+            4 => {
+                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (min_collateral_out, _rest) = Self::unpack_optional_u64(rest)?;
+                Self::DepositReserveLiquidity {
+                    liquidity_amount,
+                    min_collateral_out,
+                }
+            }
+            5 => {
+                let (collateral_amount, rest) = Self::unpack_u64(rest)?;
+                let (min_liquidity_out, _rest) = Self::unpack_optional_u64(rest)?;
+                Self::RedeemReserveCollateral {
+                    collateral_amount,
+                    min_liquidity_out,
+                }
+            }
+            6 => {
+                let referrer = match rest {
+                    [] => None,
+                    rest => Some(Self::unpack_pubkey(rest)?.0),
+                };
+                Self::InitObligation { referrer }
+            }
+            7 => Self::RefreshObligation,
+            8 => {
+                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DepositObligationCollateral { collateral_amount }
+            }
+            9 => {
+                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WithdrawObligationCollateral { collateral_amount }
+            }
+            10 => {
+                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (min_liquidity_received, _rest) = Self::unpack_optional_u64(rest)?;
+                Self::BorrowObligationLiquidity {
+                    liquidity_amount,
+                    min_liquidity_received,
+                }
+            }
+            11 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::RepayObligationLiquidity { liquidity_amount }
+            }
+            12 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::LiquidateObligation { liquidity_amount }
+            }
+            13 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::FlashLoan { amount }
+            }
+            14 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DepositReserveLiquidityAndObligationCollateral { liquidity_amount }
+            }
+            15 => {
+                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WithdrawObligationCollateralAndRedeemReserveCollateral { collateral_amount }
+            }
+            16 => {
+                let (optimal_utilization_rate, rest) = Self::unpack_u8(rest)?;
+                let (max_utilization_rate, rest) = Self::unpack_u8(rest)?;
+                let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
+                let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
+                let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+                let (min_borrow_rate, rest) = Self::unpack_u8(rest)?;
+                let (optimal_borrow_rate, rest) = Self::unpack_u8(rest)?;
+                let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
+                let (super_max_borrow_rate, rest) = Self::unpack_u64(rest)?;
+                let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
+                let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
+                let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
+                let (deposit_limit, rest) = Self::unpack_u64(rest)?;
+                let (borrow_limit, rest) = Self::unpack_u64(rest)?;
+                let (fee_receiver, rest) = Self::unpack_pubkey(rest)?;
+                let (protocol_liquidation_fee, rest) = Self::unpack_u8(rest)?;
+                let (protocol_take_rate, rest) = Self::unpack_u8(rest)?;
+                let (added_borrow_weight_bps, rest) = Self::unpack_u64(rest)?;
+                let (asset_type, rest) = Self::unpack_u8(rest)?;
+                let (max_liquidation_bonus, rest) = Self::unpack_u8(rest)?;
+                let (max_liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+                let (scaled_price_offset_bps, rest) = Self::unpack_i64(rest)?;
+                let (extra_oracle_pubkey, rest) = match Self::unpack_u8(rest)? {
+                    (0, rest) => (None, rest),
+                    (1, rest) => {
+                        let (pubkey, rest) = Self::unpack_pubkey(rest)?;
+                        (Some(pubkey), rest)
+                    }
+                    _ => return Err(LendingError::InstructionUnpackError.into()),
+                };
+                let (attributed_borrow_limit_open, rest) = Self::unpack_u64(rest)?;
+                let (attributed_borrow_limit_close, rest) = Self::unpack_u64(rest)?;
+                let (window_duration, rest) = Self::unpack_u64(rest)?;
+                let (max_outflow, rest) = Self::unpack_u64(rest)?;
+
+                let mut config = ReserveConfig {
+                    optimal_utilization_rate,
+                    max_utilization_rate,
+                    loan_to_value_ratio,
+                    liquidation_bonus,
+                    max_liquidation_bonus,
+                    liquidation_threshold,
+                    max_liquidation_threshold,
+                    min_borrow_rate,
+                    optimal_borrow_rate,
+                    max_borrow_rate,
+                    super_max_borrow_rate,
+                    fees: ReserveFees {
+                        borrow_fee_wad,
+                        flash_loan_fee_wad,
+                        host_fee_percentage,
+                    },
+                    deposit_limit,
+                    borrow_limit,
+                    fee_receiver,
+                    protocol_liquidation_fee,
+                    protocol_take_rate,
+                    added_borrow_weight_bps,
+                    reserve_type: ReserveType::from_u8(asset_type).unwrap(),
+                    scaled_price_offset_bps,
+                    extra_oracle_pubkey,
+                    attributed_borrow_limit_open,
+                    attributed_borrow_limit_close,
+                    ..ReserveConfig::default()
+                };
+                let _rest = Self::unpack_reserve_config_extension(rest, &mut config)?;
+
+                Self::UpdateReserveConfig {
+                    config,
+                    rate_limiter_config: RateLimiterConfig {
+                        window_duration,
+                        max_outflow,
+                    },
+                }
+            }
+            17 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::LiquidateObligationAndRedeemReserveCollateral { liquidity_amount }
+            }
+            18 => Self::RedeemFees,
+            19 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::FlashBorrowReserveLiquidity { liquidity_amount }
+            }
+            20 => {
+                let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (borrow_instruction_index, _rest) = Self::unpack_u8(rest)?;
+                Self::FlashRepayReserveLiquidity {
+                    liquidity_amount,
+                    borrow_instruction_index,
+                }
+            }
+            21 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::ForgiveDebt { liquidity_amount }
+            }
+            22 => Self::UpdateMarketMetadata,
+            23 => {
+                let (closeable, _rest) = match Self::unpack_u8(rest)? {
+                    (0, rest) => (false, rest),
+                    (1, rest) => (true, rest),
+                    _ => return Err(LendingError::InstructionUnpackError.into()),
+                };
+
+                Self::SetObligationCloseabilityStatus { closeable }
+            }
+            24 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DonateToReserve { liquidity_amount }
+            }
+            25 => {
+                let (max_depth, rest) = Self::unpack_u32(rest)?;
+                let (max_buffer_size, _rest) = Self::unpack_u32(rest)?;
+                Self::InitObligationArchive {
+                    max_depth,
+                    max_buffer_size,
+                }
+            }
+            26 => Self::ArchiveObligation,
+            27 => Self::RotateReserveCollateralSupply,
+            28 => Self::RotateReserveFeeReceiver,
+            29 => Self::SetDefaultHostFeeReceiver,
+            30 => {
+                let (len, mut rest) = Self::unpack_u8(rest)?;
+                let mut liquidity_amounts = Vec::with_capacity(len as usize);
+                for _ in 0..len {
+                    let (liquidity_amount, new_rest) = Self::unpack_u64(rest)?;
+                    liquidity_amounts.push(liquidity_amount);
+                    rest = new_rest;
+                }
+                Self::RepayObligationLiquidityMany { liquidity_amounts }
+            }
+            31 => {
+                let (emergency_utilization_threshold, rest) = Self::unpack_u8(rest)?;
+                let (emergency_utilization_slots, _rest) = Self::unpack_u64(rest)?;
+                Self::SetReserveUtilizationKillSwitch {
+                    emergency_utilization_threshold,
+                    emergency_utilization_slots,
+                }
+            }
+            32 => Self::CrankCloseEmptyObligation,
+            33 => {
+                let (borrows_locked, _rest) = match Self::unpack_u8(rest)? {
+                    (0, rest) => (false, rest),
+                    (1, rest) => (true, rest),
+                    _ => return Err(LendingError::InstructionUnpackError.into()),
+                };
+
+                Self::SetObligationBorrowLock { borrows_locked }
+            }
+            34 => {
+                let (price, rest) = Self::unpack_u128(rest)?;
+                let (max_duration_slots, _rest) = Self::unpack_u64(rest)?;
+                Self::SetEmergencyPrice {
+                    price: Decimal::from_scaled_val(price),
+                    max_duration_slots,
+                }
+            }
+            35 => {
+                let (side, rest) = Self::unpack_u8(rest)?;
+                let (emission_per_slot, rest) = Self::unpack_u64(rest)?;
+                let (start_slot, rest) = Self::unpack_u64(rest)?;
+                let (end_slot, _rest) = Self::unpack_u64(rest)?;
+                Self::SetRewardRule {
+                    side: RewardSide::from_u8(side).ok_or(LendingError::InstructionUnpackError)?,
+                    emission_per_slot,
+                    start_slot,
+                    end_slot,
+                }
+            }
+            36 => Self::UpdateRewards,
+            37 => Self::ClaimRewards,
+            38 => {
+                let (asset_category, rest) = Self::unpack_u8(rest)?;
+                let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
+                let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+                let (max_liquidation_threshold, _rest) = Self::unpack_u8(rest)?;
+                Self::SetAssetCategory {
+                    asset_category,
+                    loan_to_value_ratio,
+                    liquidation_threshold,
+                    max_liquidation_threshold,
+                }
+            }
+            39 => {
+                let (risk_authority, _rest) = Self::unpack_pubkey(rest)?;
+                Self::SetRiskAuthority { risk_authority }
+            }
+            40 => {
+                let (max_obligations_per_owner, _rest) = Self::unpack_u16(rest)?;
+                Self::SetMaxObligationsPerOwner {
+                    max_obligations_per_owner,
+                }
+            }
+            41 => {
+                let (fee_authority, rest) = Self::unpack_pubkey(rest)?;
+                let (treasury, rest) = Self::unpack_pubkey(rest)?;
+                let (window_duration, rest) = Self::unpack_u64(rest)?;
+                let (max_outflow, _rest) = Self::unpack_u64(rest)?;
+                Self::SetProtocolFeeConfig {
+                    fee_authority,
+                    treasury,
+                    rate_limiter_config: RateLimiterConfig {
+                        window_duration,
+                        max_outflow,
+                    },
+                }
+            }
+            42 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WithdrawProtocolFees { liquidity_amount }
+            }
+            43 => {
+                let (referrer, _rest) = Self::unpack_pubkey(rest)?;
+                Self::SetObligationReferrer { referrer }
+            }
+            44 => {
+                let (collateral_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::CompoundObligationCollateral { collateral_amount }
+            }
+            45 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::RepayObligationLiquidityWithCollateral { liquidity_amount }
+            }
+            46 => {
+                let (deposit_liquidity_amount, rest) = Self::unpack_u64(rest)?;
+                let (borrow_liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DepositReserveLiquidityAndObligationCollateralAndBorrow {
+                    deposit_liquidity_amount,
+                    borrow_liquidity_amount,
+                }
+            }
+            47 => Self::InitReserveRateStats,
+            48 => {
+                let (dust_borrow_value, _rest) = Self::unpack_u64(rest)?;
+                Self::SetDustBorrowValue { dust_borrow_value }
+            }
+            49 => Self::InitReserveActivity,
+            #[cfg(feature = "devnet-utils")]
+            50 => {
+                let (amount, _rest) = Self::unpack_u64(rest)?;
+                Self::DevnetMintTestTokens { amount }
+            }
+            #[cfg(feature = "devnet-utils")]
+            51 => {
+                let (price, _rest) = Self::unpack_u128(rest)?;
+                Self::DevnetSetPrice {
+                    price: Decimal::from_scaled_val(price),
+                }
+            }
+            52 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::BorrowObligationLiquidityWithWalletCollateral { liquidity_amount }
+            }
+            53 => Self::CloseReserve,
+            55 => Self::SetReserveTertiaryOracle,
+            56 => Self::RefreshCTokenPrice,
+            57 => {
+                let (min_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::RedeemFeesIfAbove { min_amount }
+            }
+            58 => {
+                let (obligation_max_outflow, _rest) = Self::unpack_u64(rest)?;
+                Self::SetObligationMaxOutflow {
+                    obligation_max_outflow,
+                }
+            }
+            60 => Self::ResetRateLimiter,
+            61 => {
+                let (borrow_cap, _rest) = Self::unpack_u32(rest)?;
+                Self::SetObligationBorrowCap { borrow_cap }
+            }
+            62 => {
+                let (approved_borrower, rest) = Self::unpack_pubkey(rest)?;
+                let (delegated_value, _rest) = Self::unpack_u64(rest)?;
+                Self::DelegateBorrowAuthority {
+                    approved_borrower,
+                    delegated_value,
+                }
+            }
+            63 => {
+                let (borrow_weight_bps, _rest) = Self::unpack_u16(rest)?;
+                Self::SetReservePairBorrowWeight { borrow_weight_bps }
+            }
+            64 => {
+                let (whitelist_required, _rest) = Self::unpack_bool(rest)?;
+                Self::SetWhitelistRequired { whitelist_required }
+            }
+            65 => {
+                let (is_member, _rest) = Self::unpack_bool(rest)?;
+                Self::UpdateMarketMember { is_member }
+            }
+            66 => {
+                let (protected_window_slots, rest) = Self::unpack_u64(rest)?;
+                let (len, mut rest) = Self::unpack_u8(rest)?;
+                let mut liquidators = Vec::with_capacity(len as usize);
+                for _ in 0..len {
+                    let (liquidator, new_rest) = Self::unpack_pubkey(rest)?;
+                    liquidators.push(liquidator);
+                    rest = new_rest;
+                }
+                Self::SetLiquidatorWhitelist {
+                    protected_window_slots,
+                    liquidators,
+                }
+            }
+            67 => {
+                let (insurance_fee_bps, _rest) = Self::unpack_u16(rest)?;
+                Self::SetInsuranceFee { insurance_fee_bps }
+            }
+            68 => Self::InitInsuranceFund,
+            69 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::CoverBadDebt { liquidity_amount }
+            }
+            70 => {
+                let (liquidity_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WithdrawObligationLiquidityExact { liquidity_amount }
+            }
+            71 => {
+                let (max_price_age_slots, _rest) = Self::unpack_u8(rest)?;
+                Self::SetMaxPriceAge {
+                    max_price_age_slots,
+                }
+            }
+            72 => Self::BuyRateCap,
+            73 => Self::ClaimRateCapCredit,
+            74 => Self::SetReserveSpreadFeeReceiver,
+            75 => Self::MigrateAccount,
+            _ => {
+                msg!("Instruction cannot be unpacked");
+                return Err(LendingError::InstructionUnpackError.into());
+            }
+        })
+    }
+
+    fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
+        if input.len() < 16 {
+            msg!("u128 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(16);
+        let value = bytes
+            .get(..16)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u128::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.len() < 8 {
+            msg!("u64 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(8);
+        let value = bytes
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    /// Unpacks a u64 that older clients leave out of the instruction data, as 0
+    fn unpack_optional_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+        if input.is_empty() {
+            return Ok((0, input));
+        }
+        Self::unpack_u64(input)
+    }
+
+    fn unpack_optional_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        if input.is_empty() {
+            return Ok((0, input));
+        }
+        Self::unpack_u16(input)
+    }
+
+    fn unpack_optional_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        if input.is_empty() {
+            return Ok((0, input));
+        }
+        Self::unpack_u8(input)
+    }
+
+    fn unpack_optional_bool(input: &[u8]) -> Result<(bool, &[u8]), ProgramError> {
+        if input.is_empty() {
+            return Ok((false, input));
+        }
+        Self::unpack_bool(input)
+    }
+
+    /// Unpacks the reserve config fields appended after the InitReserve and UpdateReserveConfig
+    /// payloads. Each field is optional, so older instruction data still unpacks with the fields
+    /// it ends before left at 0, which disables them.
+    fn unpack_reserve_config_extension<'a>(
+        input: &'a [u8],
+        config: &mut ReserveConfig,
+    ) -> Result<&'a [u8], ProgramError> {
+        let (max_close_factor_bps, rest) = Self::unpack_optional_u16(input)?;
+        let (liquidation_price_band_bps, rest) = Self::unpack_optional_u16(rest)?;
+        let (max_oracle_staleness_secs, rest) = Self::unpack_optional_u64(rest)?;
+        let (max_oracle_confidence_bps, rest) = Self::unpack_optional_u16(rest)?;
+        let (max_single_borrower_share_bps, rest) = Self::unpack_optional_u16(rest)?;
+        let (deposits_paused, rest) = Self::unpack_optional_bool(rest)?;
+        let (borrows_paused, rest) = Self::unpack_optional_bool(rest)?;
+        let (withdrawals_paused, rest) = Self::unpack_optional_bool(rest)?;
+        let (super_optimal_utilization_rate, rest) = Self::unpack_optional_u8(rest)?;
+        let (super_optimal_borrow_rate, rest) = Self::unpack_optional_u8(rest)?;
+        let (min_borrow_value, rest) = Self::unpack_optional_u64(rest)?;
+        let (max_collateral_value_contribution, rest) = Self::unpack_optional_u64(rest)?;
+        let (min_price, rest) = Self::unpack_optional_u64(rest)?;
+        let (max_price, rest) = Self::unpack_optional_u64(rest)?;
+        let (liquidation_auction, rest) = Self::unpack_optional_bool(rest)?;
+        let (protocol_liquidation_fee_in_liquidity, rest) = Self::unpack_optional_bool(rest)?;
+        let (asset_category, rest) = Self::unpack_optional_u8(rest)?;
+        let (borrow_rate_cap, rest) = Self::unpack_optional_u8(rest)?;
+        let (rate_cap_premium_bps, rest) = Self::unpack_optional_u16(rest)?;
+        let (max_flash_loan_amount, rest) = Self::unpack_optional_u64(rest)?;
+
+        *config = ReserveConfig {
+            max_close_factor_bps,
+            liquidation_price_band_bps,
+            max_oracle_staleness_secs,
+            max_oracle_confidence_bps,
+            max_single_borrower_share_bps,
+            deposits_paused,
+            borrows_paused,
+            withdrawals_paused,
+            super_optimal_utilization_rate,
+            super_optimal_borrow_rate,
+            min_borrow_value,
+            max_collateral_value_contribution,
+            min_price,
+            max_price,
+            liquidation_auction,
+            protocol_liquidation_fee_in_liquidity,
+            asset_category,
+            borrow_rate_cap,
+            rate_cap_premium_bps,
+            max_flash_loan_amount,
+            ..*config
+        };
+        Ok(rest)
+    }
+
+    /// Packs the reserve config fields appended after the InitReserve and UpdateReserveConfig
+    /// payloads, in the order unpack_reserve_config_extension reads them.
+    fn pack_reserve_config_extension(buf: &mut Vec<u8>, config: &ReserveConfig) {
+        buf.extend_from_slice(&config.max_close_factor_bps.to_le_bytes());
+        buf.extend_from_slice(&config.liquidation_price_band_bps.to_le_bytes());
+        buf.extend_from_slice(&config.max_oracle_staleness_secs.to_le_bytes());
+        buf.extend_from_slice(&config.max_oracle_confidence_bps.to_le_bytes());
+        buf.extend_from_slice(&config.max_single_borrower_share_bps.to_le_bytes());
+        buf.push(config.deposits_paused as u8);
+        buf.push(config.borrows_paused as u8);
+        buf.push(config.withdrawals_paused as u8);
+        buf.push(config.super_optimal_utilization_rate);
+        buf.push(config.super_optimal_borrow_rate);
+        buf.extend_from_slice(&config.min_borrow_value.to_le_bytes());
+        buf.extend_from_slice(&config.max_collateral_value_contribution.to_le_bytes());
+        buf.extend_from_slice(&config.min_price.to_le_bytes());
+        buf.extend_from_slice(&config.max_price.to_le_bytes());
+        buf.push(config.liquidation_auction as u8);
+        buf.push(config.protocol_liquidation_fee_in_liquidity as u8);
+        buf.push(config.asset_category);
+        buf.push(config.borrow_rate_cap);
+        buf.extend_from_slice(&config.rate_cap_premium_bps.to_le_bytes());
+        buf.extend_from_slice(&config.max_flash_loan_amount.to_le_bytes());
+    }
+
+    fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+        if input.len() < 8 {
+            msg!("i64 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(8);
+        let value = bytes
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    fn unpack_u32(input: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+        if input.len() < 4 {
+            msg!("u32 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(4);
+        let value = bytes
+            .get(..4)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+        if input.len() < 2 {
+            msg!("u16 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(2);
+        let value = bytes
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    fn unpack_bool(input: &[u8]) -> Result<(bool, &[u8]), ProgramError> {
+        match Self::unpack_u8(input)? {
+            (0, rest) => Ok((false, rest)),
+            (1, rest) => Ok((true, rest)),
+            _ => {
+                msg!("bool cannot be unpacked");
+                Err(LendingError::InstructionUnpackError.into())
+            }
+        }
+    }
+
+    fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+        if input.is_empty() {
+            msg!("u8 cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(1);
+        let value = bytes
+            .get(..1)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u8::from_le_bytes)
+            .ok_or(LendingError::InstructionUnpackError)?;
+        Ok((value, rest))
+    }
+
+    fn unpack_bytes32(input: &[u8]) -> Result<(&[u8; 32], &[u8]), ProgramError> {
+        if input.len() < 32 {
+            msg!("32 bytes cannot be unpacked");
+            return Err(LendingError::InstructionUnpackError.into());
+        }
+        let (bytes, rest) = input.split_at(32);
+        Ok((
+            bytes
+                .try_into()
+                .map_err(|_| LendingError::InstructionUnpackError)?,
+            rest,
+        ))
+    }
+
+    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+        if input.len() < PUBKEY_BYTES {
+            msg!("Pubkey cannot be unpacked");
             return Err(LendingError::InstructionUnpackError.into());
         }
-        let (bytes, rest) = input.split_at(8);
-        let value = bytes
-            .get(..8)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u64::from_le_bytes)
-            .ok_or(LendingError::InstructionUnpackError)?;
-        Ok((value, rest))
+        let (key, rest) = input.split_at(PUBKEY_BYTES);
+        let pk = Pubkey::try_from(key).map_err(|_| LendingError::InstructionUnpackError)?;
+        Ok((pk, rest))
+    }
+
+    /// Packs a [LendingInstruction](enum.LendingInstruction.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match *self {
+            Self::InitLendingMarket {
+                owner,
+                quote_currency,
+            } => {
+                buf.push(0);
+                buf.extend_from_slice(owner.as_ref());
+                buf.extend_from_slice(quote_currency.as_ref());
+            }
+            Self::SetLendingMarketOwnerAndConfig {
+                new_owner,
+                rate_limiter_config: config,
+                whitelisted_liquidator,
+                risk_authority,
+            } => {
+                buf.push(1);
+                buf.extend_from_slice(new_owner.as_ref());
+                buf.extend_from_slice(&config.window_duration.to_le_bytes());
+                buf.extend_from_slice(&config.max_outflow.to_le_bytes());
+                match whitelisted_liquidator {
+                    Some(liquidator) => {
+                        buf.push(1);
+                        buf.extend_from_slice(liquidator.as_ref());
+                    }
+                    None => {
+                        buf.push(0);
+                    }
+                };
+                buf.extend_from_slice(risk_authority.as_ref());
+            }
+            Self::InitReserve {
+                liquidity_amount,
+                config,
+                collateral_decimals,
+            } => {
+                buf.push(2);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&config.optimal_utilization_rate.to_le_bytes());
+                buf.extend_from_slice(&config.max_utilization_rate.to_le_bytes());
+                buf.extend_from_slice(&config.loan_to_value_ratio.to_le_bytes());
+                buf.extend_from_slice(&config.liquidation_bonus.to_le_bytes());
+                buf.extend_from_slice(&config.liquidation_threshold.to_le_bytes());
+                buf.extend_from_slice(&config.min_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.optimal_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.max_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.super_max_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.fees.borrow_fee_wad.to_le_bytes());
+                buf.extend_from_slice(&config.fees.flash_loan_fee_wad.to_le_bytes());
+                buf.extend_from_slice(&config.fees.host_fee_percentage.to_le_bytes());
+                buf.extend_from_slice(&config.deposit_limit.to_le_bytes());
+                buf.extend_from_slice(&config.borrow_limit.to_le_bytes());
+                buf.extend_from_slice(&config.fee_receiver.to_bytes());
+                buf.extend_from_slice(&config.protocol_liquidation_fee.to_le_bytes());
+                buf.extend_from_slice(&config.protocol_take_rate.to_le_bytes());
+                buf.extend_from_slice(&config.added_borrow_weight_bps.to_le_bytes());
+                buf.extend_from_slice(&(config.reserve_type as u8).to_le_bytes());
+                buf.extend_from_slice(&config.max_liquidation_bonus.to_le_bytes());
+                buf.extend_from_slice(&config.max_liquidation_threshold.to_le_bytes());
+                buf.extend_from_slice(&config.scaled_price_offset_bps.to_le_bytes());
+                match config.extra_oracle_pubkey {
+                    Some(pubkey) => {
+                        buf.push(1);
+                        buf.extend_from_slice(pubkey.as_ref());
+                    }
+                    None => {
+                        buf.push(0);
+                    }
+                };
+                buf.extend_from_slice(&config.attributed_borrow_limit_open.to_le_bytes());
+                buf.extend_from_slice(&config.attributed_borrow_limit_close.to_le_bytes());
+                Self::pack_reserve_config_extension(&mut buf, &config);
+                match collateral_decimals {
+                    Some(decimals) => {
+                        buf.push(1);
+                        buf.push(decimals);
+                    }
+                    None => {
+                        buf.push(0);
+                    }
+                };
+            }
+            Self::RefreshReserve => {
+                buf.push(3);
+            }
+            Self::DepositReserveLiquidity {
+                liquidity_amount,
+                min_collateral_out,
+            } => {
+                buf.push(4);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&min_collateral_out.to_le_bytes());
+            }
+            Self::RedeemReserveCollateral {
+                collateral_amount,
+                min_liquidity_out,
+            } => {
+                buf.push(5);
+                buf.extend_from_slice(&collateral_amount.to_le_bytes());
+                buf.extend_from_slice(&min_liquidity_out.to_le_bytes());
+            }
+            Self::InitObligation { referrer } => {
+                buf.push(6);
+                if let Some(referrer) = referrer {
+                    buf.extend_from_slice(referrer.as_ref());
+                }
+            }
+            Self::RefreshObligation => {
+                buf.push(7);
+            }
+            Self::DepositObligationCollateral { collateral_amount } => {
+                buf.push(8);
+                buf.extend_from_slice(&collateral_amount.to_le_bytes());
+            }
+            Self::WithdrawObligationCollateral { collateral_amount } => {
+                buf.push(9);
+                buf.extend_from_slice(&collateral_amount.to_le_bytes());
+            }
+            Self::BorrowObligationLiquidity {
+                liquidity_amount,
+                min_liquidity_received,
+            } => {
+                buf.push(10);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&min_liquidity_received.to_le_bytes());
+            }
+            Self::RepayObligationLiquidity { liquidity_amount } => {
+                buf.push(11);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::LiquidateObligation { liquidity_amount } => {
+                buf.push(12);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::FlashLoan { amount } => {
+                buf.push(13);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::DepositReserveLiquidityAndObligationCollateral { liquidity_amount } => {
+                buf.push(14);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::WithdrawObligationCollateralAndRedeemReserveCollateral { collateral_amount } => {
+                buf.push(15);
+                buf.extend_from_slice(&collateral_amount.to_le_bytes());
+            }
+            Self::UpdateReserveConfig {
+                config,
+                rate_limiter_config,
+            } => {
+                buf.push(16);
+                buf.extend_from_slice(&config.optimal_utilization_rate.to_le_bytes());
+                buf.extend_from_slice(&config.max_utilization_rate.to_le_bytes());
+                buf.extend_from_slice(&config.loan_to_value_ratio.to_le_bytes());
+                buf.extend_from_slice(&config.liquidation_bonus.to_le_bytes());
+                buf.extend_from_slice(&config.liquidation_threshold.to_le_bytes());
+                buf.extend_from_slice(&config.min_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.optimal_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.max_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.super_max_borrow_rate.to_le_bytes());
+                buf.extend_from_slice(&config.fees.borrow_fee_wad.to_le_bytes());
+                buf.extend_from_slice(&config.fees.flash_loan_fee_wad.to_le_bytes());
+                buf.extend_from_slice(&config.fees.host_fee_percentage.to_le_bytes());
+                buf.extend_from_slice(&config.deposit_limit.to_le_bytes());
+                buf.extend_from_slice(&config.borrow_limit.to_le_bytes());
+                buf.extend_from_slice(&config.fee_receiver.to_bytes());
+                buf.extend_from_slice(&config.protocol_liquidation_fee.to_le_bytes());
+                buf.extend_from_slice(&config.protocol_take_rate.to_le_bytes());
+                buf.extend_from_slice(&config.added_borrow_weight_bps.to_le_bytes());
+                buf.extend_from_slice(&(config.reserve_type as u8).to_le_bytes());
+                buf.extend_from_slice(&config.max_liquidation_bonus.to_le_bytes());
+                buf.extend_from_slice(&config.max_liquidation_threshold.to_le_bytes());
+                buf.extend_from_slice(&config.scaled_price_offset_bps.to_le_bytes());
+                match config.extra_oracle_pubkey {
+                    Some(pubkey) => {
+                        buf.push(1);
+                        buf.extend_from_slice(pubkey.as_ref());
+                    }
+                    None => {
+                        buf.push(0);
+                    }
+                };
+                buf.extend_from_slice(&config.attributed_borrow_limit_open.to_le_bytes());
+                buf.extend_from_slice(&config.attributed_borrow_limit_close.to_le_bytes());
+                buf.extend_from_slice(&rate_limiter_config.window_duration.to_le_bytes());
+                buf.extend_from_slice(&rate_limiter_config.max_outflow.to_le_bytes());
+                Self::pack_reserve_config_extension(&mut buf, &config);
+            }
+            Self::LiquidateObligationAndRedeemReserveCollateral { liquidity_amount } => {
+                buf.push(17);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::RedeemFees {} => {
+                buf.push(18);
+            }
+            Self::FlashBorrowReserveLiquidity { liquidity_amount } => {
+                buf.push(19);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::FlashRepayReserveLiquidity {
+                liquidity_amount,
+                borrow_instruction_index,
+            } => {
+                buf.push(20);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&borrow_instruction_index.to_le_bytes());
+            }
+            Self::ForgiveDebt { liquidity_amount } => {
+                buf.push(21);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            // special handling for this instruction, bc the instruction is too big to deserialize
+            Self::UpdateMarketMetadata => {}
+            Self::SetObligationCloseabilityStatus { closeable } => {
+                buf.push(23);
+                buf.extend_from_slice(&(closeable as u8).to_le_bytes());
+            }
+            Self::DonateToReserve { liquidity_amount } => {
+                buf.push(24);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::InitObligationArchive {
+                max_depth,
+                max_buffer_size,
+            } => {
+                buf.push(25);
+                buf.extend_from_slice(&max_depth.to_le_bytes());
+                buf.extend_from_slice(&max_buffer_size.to_le_bytes());
+            }
+            Self::ArchiveObligation => {
+                buf.push(26);
+            }
+            Self::RotateReserveCollateralSupply => {
+                buf.push(27);
+            }
+            Self::RotateReserveFeeReceiver => {
+                buf.push(28);
+            }
+            Self::SetDefaultHostFeeReceiver => {
+                buf.push(29);
+            }
+            Self::RepayObligationLiquidityMany {
+                ref liquidity_amounts,
+            } => {
+                buf.push(30);
+                buf.extend_from_slice(&(liquidity_amounts.len() as u8).to_le_bytes());
+                for liquidity_amount in liquidity_amounts {
+                    buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                }
+            }
+            Self::SetReserveUtilizationKillSwitch {
+                emergency_utilization_threshold,
+                emergency_utilization_slots,
+            } => {
+                buf.push(31);
+                buf.extend_from_slice(&emergency_utilization_threshold.to_le_bytes());
+                buf.extend_from_slice(&emergency_utilization_slots.to_le_bytes());
+            }
+            Self::CrankCloseEmptyObligation => {
+                buf.push(32);
+            }
+            Self::SetObligationBorrowLock { borrows_locked } => {
+                buf.push(33);
+                buf.extend_from_slice(&(borrows_locked as u8).to_le_bytes());
+            }
+            Self::SetEmergencyPrice {
+                price,
+                max_duration_slots,
+            } => {
+                buf.push(34);
+                buf.extend_from_slice(
+                    &price
+                        .to_scaled_val()
+                        .expect("Decimal cannot be packed")
+                        .to_le_bytes(),
+                );
+                buf.extend_from_slice(&max_duration_slots.to_le_bytes());
+            }
+            Self::SetRewardRule {
+                side,
+                emission_per_slot,
+                start_slot,
+                end_slot,
+            } => {
+                buf.push(35);
+                buf.extend_from_slice(&(side as u8).to_le_bytes());
+                buf.extend_from_slice(&emission_per_slot.to_le_bytes());
+                buf.extend_from_slice(&start_slot.to_le_bytes());
+                buf.extend_from_slice(&end_slot.to_le_bytes());
+            }
+            Self::UpdateRewards => {
+                buf.push(36);
+            }
+            Self::ClaimRewards => {
+                buf.push(37);
+            }
+            Self::SetAssetCategory {
+                asset_category,
+                loan_to_value_ratio,
+                liquidation_threshold,
+                max_liquidation_threshold,
+            } => {
+                buf.push(38);
+                buf.extend_from_slice(&asset_category.to_le_bytes());
+                buf.extend_from_slice(&loan_to_value_ratio.to_le_bytes());
+                buf.extend_from_slice(&liquidation_threshold.to_le_bytes());
+                buf.extend_from_slice(&max_liquidation_threshold.to_le_bytes());
+            }
+            Self::SetRiskAuthority { risk_authority } => {
+                buf.push(39);
+                buf.extend_from_slice(risk_authority.as_ref());
+            }
+            Self::SetMaxObligationsPerOwner {
+                max_obligations_per_owner,
+            } => {
+                buf.push(40);
+                buf.extend_from_slice(&max_obligations_per_owner.to_le_bytes());
+            }
+            Self::SetProtocolFeeConfig {
+                fee_authority,
+                treasury,
+                rate_limiter_config,
+            } => {
+                buf.push(41);
+                buf.extend_from_slice(fee_authority.as_ref());
+                buf.extend_from_slice(treasury.as_ref());
+                buf.extend_from_slice(&rate_limiter_config.window_duration.to_le_bytes());
+                buf.extend_from_slice(&rate_limiter_config.max_outflow.to_le_bytes());
+            }
+            Self::WithdrawProtocolFees { liquidity_amount } => {
+                buf.push(42);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::SetObligationReferrer { referrer } => {
+                buf.push(43);
+                buf.extend_from_slice(referrer.as_ref());
+            }
+            Self::CompoundObligationCollateral { collateral_amount } => {
+                buf.push(44);
+                buf.extend_from_slice(&collateral_amount.to_le_bytes());
+            }
+            Self::RepayObligationLiquidityWithCollateral { liquidity_amount } => {
+                buf.push(45);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::DepositReserveLiquidityAndObligationCollateralAndBorrow {
+                deposit_liquidity_amount,
+                borrow_liquidity_amount,
+            } => {
+                buf.push(46);
+                buf.extend_from_slice(&deposit_liquidity_amount.to_le_bytes());
+                buf.extend_from_slice(&borrow_liquidity_amount.to_le_bytes());
+            }
+            Self::InitReserveRateStats => {
+                buf.push(47);
+            }
+            Self::SetDustBorrowValue { dust_borrow_value } => {
+                buf.push(48);
+                buf.extend_from_slice(&dust_borrow_value.to_le_bytes());
+            }
+            Self::InitReserveActivity => {
+                buf.push(49);
+            }
+            #[cfg(feature = "devnet-utils")]
+            Self::DevnetMintTestTokens { amount } => {
+                buf.push(50);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            #[cfg(feature = "devnet-utils")]
+            Self::DevnetSetPrice { price } => {
+                buf.push(51);
+                buf.extend_from_slice(
+                    &price
+                        .to_scaled_val()
+                        .expect("Decimal cannot be packed")
+                        .to_le_bytes(),
+                );
+            }
+            Self::BorrowObligationLiquidityWithWalletCollateral { liquidity_amount } => {
+                buf.push(52);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::CloseReserve => {
+                buf.push(53);
+            }
+            Self::SetReserveTertiaryOracle => {
+                buf.push(55);
+            }
+            Self::RefreshCTokenPrice => {
+                buf.push(56);
+            }
+            Self::RedeemFeesIfAbove { min_amount } => {
+                buf.push(57);
+                buf.extend_from_slice(&min_amount.to_le_bytes());
+            }
+            Self::SetObligationMaxOutflow {
+                obligation_max_outflow,
+            } => {
+                buf.push(58);
+                buf.extend_from_slice(&obligation_max_outflow.to_le_bytes());
+            }
+            Self::ResetRateLimiter => {
+                buf.push(60);
+            }
+            Self::SetObligationBorrowCap { borrow_cap } => {
+                buf.push(61);
+                buf.extend_from_slice(&borrow_cap.to_le_bytes());
+            }
+            Self::DelegateBorrowAuthority {
+                approved_borrower,
+                delegated_value,
+            } => {
+                buf.push(62);
+                buf.extend_from_slice(approved_borrower.as_ref());
+                buf.extend_from_slice(&delegated_value.to_le_bytes());
+            }
+            Self::SetReservePairBorrowWeight { borrow_weight_bps } => {
+                buf.push(63);
+                buf.extend_from_slice(&borrow_weight_bps.to_le_bytes());
+            }
+            Self::SetWhitelistRequired { whitelist_required } => {
+                buf.push(64);
+                buf.push(whitelist_required as u8);
+            }
+            Self::UpdateMarketMember { is_member } => {
+                buf.push(65);
+                buf.push(is_member as u8);
+            }
+            Self::SetLiquidatorWhitelist {
+                protected_window_slots,
+                ref liquidators,
+            } => {
+                buf.push(66);
+                buf.extend_from_slice(&protected_window_slots.to_le_bytes());
+                buf.extend_from_slice(&(liquidators.len() as u8).to_le_bytes());
+                for liquidator in liquidators {
+                    buf.extend_from_slice(liquidator.as_ref());
+                }
+            }
+            Self::SetInsuranceFee { insurance_fee_bps } => {
+                buf.push(67);
+                buf.extend_from_slice(&insurance_fee_bps.to_le_bytes());
+            }
+            Self::InitInsuranceFund => {
+                buf.push(68);
+            }
+            Self::CoverBadDebt { liquidity_amount } => {
+                buf.push(69);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::WithdrawObligationLiquidityExact { liquidity_amount } => {
+                buf.push(70);
+                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+            }
+            Self::SetMaxPriceAge {
+                max_price_age_slots,
+            } => {
+                buf.push(71);
+                buf.extend_from_slice(&max_price_age_slots.to_le_bytes());
+            }
+            Self::BuyRateCap => {
+                buf.push(72);
+            }
+            Self::ClaimRateCapCredit => {
+                buf.push(73);
+            }
+            Self::SetReserveSpreadFeeReceiver => {
+                buf.push(74);
+            }
+            Self::MigrateAccount => {
+                buf.push(75);
+            }
+        }
+        buf
+    }
+}
+
+/// Creates an 'InitLendingMarket' instruction.
+pub fn init_lending_market(
+    program_id: Pubkey,
+    owner: Pubkey,
+    quote_currency: [u8; 32],
+    lending_market_pubkey: Pubkey,
+    oracle_program_id: Pubkey,
+    switchboard_oracle_program_id: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(oracle_program_id, false),
+            AccountMeta::new_readonly(switchboard_oracle_program_id, false),
+        ],
+        data: LendingInstruction::InitLendingMarket {
+            owner,
+            quote_currency,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'SetLendingMarketOwner' instruction.
+pub fn set_lending_market_owner_and_config(
+    program_id: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+    new_owner: Pubkey,
+    rate_limiter_config: RateLimiterConfig,
+    whitelisted_liquidator: Option<Pubkey>,
+    risk_authority: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner, true),
+        ],
+        data: LendingInstruction::SetLendingMarketOwnerAndConfig {
+            new_owner,
+            rate_limiter_config,
+            whitelisted_liquidator,
+            risk_authority,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an 'InitReserve' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_reserve(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    config: ReserveConfig,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_collateral_supply_pubkey: Pubkey,
+    pyth_product_pubkey: Pubkey,
+    pyth_price_pubkey: Pubkey,
+    switchboard_feed_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    init_reserve_with_collateral_decimals(
+        program_id,
+        liquidity_amount,
+        config,
+        source_liquidity_pubkey,
+        destination_collateral_pubkey,
+        reserve_pubkey,
+        reserve_liquidity_mint_pubkey,
+        reserve_liquidity_supply_pubkey,
+        reserve_collateral_mint_pubkey,
+        reserve_collateral_supply_pubkey,
+        pyth_product_pubkey,
+        pyth_price_pubkey,
+        switchboard_feed_pubkey,
+        lending_market_pubkey,
+        lending_market_owner_pubkey,
+        user_transfer_authority_pubkey,
+        None,
+    )
+}
+
+/// Creates an 'InitReserve' instruction whose collateral mint has `collateral_decimals` decimals,
+/// if set, instead of the liquidity mint's
+#[allow(clippy::too_many_arguments)]
+pub fn init_reserve_with_collateral_decimals(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    config: ReserveConfig,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_collateral_supply_pubkey: Pubkey,
+    pyth_product_pubkey: Pubkey,
+    pyth_price_pubkey: Pubkey,
+    switchboard_feed_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    collateral_decimals: Option<u8>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(source_liquidity_pubkey, false),
+        AccountMeta::new(destination_collateral_pubkey, false),
+        AccountMeta::new(reserve_pubkey, false),
+        AccountMeta::new_readonly(reserve_liquidity_mint_pubkey, false),
+        AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+        AccountMeta::new(config.fee_receiver, false),
+        AccountMeta::new(reserve_collateral_mint_pubkey, false),
+        AccountMeta::new(reserve_collateral_supply_pubkey, false),
+        AccountMeta::new_readonly(pyth_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_price_pubkey, false),
+        AccountMeta::new_readonly(switchboard_feed_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(extra_oracle_pubkey) = config.extra_oracle_pubkey {
+        accounts.push(AccountMeta::new_readonly(extra_oracle_pubkey, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::InitReserve {
+            liquidity_amount,
+            config,
+            collateral_decimals,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `RefreshReserve` instruction
+pub fn refresh_reserve(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
+    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
+    extra_oracle_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(reserve_pubkey, false),
+        AccountMeta::new_readonly(reserve_liquidity_pyth_oracle_pubkey, false),
+        AccountMeta::new_readonly(reserve_liquidity_switchboard_oracle_pubkey, false),
+    ];
+
+    if let Some(extra_oracle_pubkey) = extra_oracle_pubkey {
+        accounts.push(AccountMeta::new_readonly(extra_oracle_pubkey, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::RefreshReserve.pack(),
+    }
+}
+
+/// Creates a 'RefreshReserve' instruction that falls back to the reserve's emergency price if
+/// both oracles fail
+pub fn refresh_reserve_with_emergency_price(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
+    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
+    extra_oracle_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let mut instruction = refresh_reserve(
+        program_id,
+        reserve_pubkey,
+        reserve_liquidity_pyth_oracle_pubkey,
+        reserve_liquidity_switchboard_oracle_pubkey,
+        extra_oracle_pubkey,
+    );
+    let (emergency_price_pubkey, _bump_seed) =
+        find_emergency_price_address(&program_id, &reserve_pubkey);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(emergency_price_pubkey, false));
+
+    instruction
+}
+
+/// Creates a 'RefreshReserve' instruction for a reserve with a tertiary oracle
+pub fn refresh_reserve_with_tertiary_oracle(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
+    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
+    tertiary_oracle_pubkey: Pubkey,
+    extra_oracle_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let mut instruction = refresh_reserve(
+        program_id,
+        reserve_pubkey,
+        reserve_liquidity_pyth_oracle_pubkey,
+        reserve_liquidity_switchboard_oracle_pubkey,
+        extra_oracle_pubkey,
+    );
+    instruction
+        .accounts
+        .insert(3, AccountMeta::new_readonly(tertiary_oracle_pubkey, false));
+
+    instruction
+}
+
+/// Creates a 'RefreshReserve' instruction that also samples the reserve's rates into its rate
+/// stats account
+pub fn refresh_reserve_with_rate_stats(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
+    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
+    extra_oracle_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let mut instruction = refresh_reserve(
+        program_id,
+        reserve_pubkey,
+        reserve_liquidity_pyth_oracle_pubkey,
+        reserve_liquidity_switchboard_oracle_pubkey,
+        extra_oracle_pubkey,
+    );
+    let (reserve_rate_stats_pubkey, _bump_seed) =
+        find_reserve_rate_stats_address(&program_id, &reserve_pubkey);
+    instruction
+        .accounts
+        .push(AccountMeta::new(reserve_rate_stats_pubkey, false));
+
+    instruction
+}
+
+/// Creates a 'DepositReserveLiquidity' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_reserve_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    deposit_reserve_liquidity_with_min_out(
+        program_id,
+        liquidity_amount,
+        0,
+        source_liquidity_pubkey,
+        destination_collateral_pubkey,
+        reserve_pubkey,
+        reserve_liquidity_supply_pubkey,
+        reserve_collateral_mint_pubkey,
+        lending_market_pubkey,
+        user_transfer_authority_pubkey,
+    )
+}
+
+/// Creates a 'DepositReserveLiquidity' instruction that fails unless at least
+/// `min_collateral_out` collateral tokens are minted.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_reserve_liquidity_with_min_out(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    min_collateral_out: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_collateral_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(reserve_collateral_mint_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::DepositReserveLiquidity {
+            liquidity_amount,
+            min_collateral_out,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'RedeemReserveCollateral' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn redeem_reserve_collateral(
+    program_id: Pubkey,
+    collateral_amount: u64,
+    source_collateral_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    redeem_reserve_collateral_with_min_out(
+        program_id,
+        collateral_amount,
+        0,
+        source_collateral_pubkey,
+        destination_liquidity_pubkey,
+        reserve_pubkey,
+        reserve_collateral_mint_pubkey,
+        reserve_liquidity_supply_pubkey,
+        lending_market_pubkey,
+        user_transfer_authority_pubkey,
+    )
+}
+
+/// Creates a 'RedeemReserveCollateral' instruction that fails unless at least
+/// `min_liquidity_out` liquidity is redeemed.
+#[allow(clippy::too_many_arguments)]
+pub fn redeem_reserve_collateral_with_min_out(
+    program_id: Pubkey,
+    collateral_amount: u64,
+    min_liquidity_out: u64,
+    source_collateral_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_collateral_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(reserve_collateral_mint_pubkey, false),
+            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::RedeemReserveCollateral {
+            collateral_amount,
+            min_liquidity_out,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an 'InitObligation' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_obligation(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::InitObligation { referrer: None }.pack(),
+    }
+}
+
+/// Creates an 'InitObligation' instruction for a lending market that caps obligations per owner.
+/// The obligation owner pays for its obligation counter account if it doesn't exist yet.
+pub fn init_obligation_with_counter(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (obligation_counter_pubkey, _bump_seed) = find_obligation_counter_address(
+        &program_id,
+        &lending_market_pubkey,
+        &obligation_owner_pubkey,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(obligation_counter_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::InitObligation { referrer: None }.pack(),
+    }
+}
+
+/// Creates an 'InitObligation' instruction that attributes the new obligation to a referrer, for
+/// a lending market that doesn't cap obligations per owner
+pub fn init_obligation_with_referrer(
+    program_id: Pubkey,
+    referrer: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::InitObligation {
+            referrer: Some(referrer),
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'RefreshObligation' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn refresh_obligation(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(obligation_pubkey, false)];
+    accounts.extend(
+        reserve_pubkeys
+            .into_iter()
+            .map(|pubkey| AccountMeta::new(pubkey, false)),
+    );
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::RefreshObligation.pack(),
+    }
+}
+
+/// Creates a 'RefreshObligation' instruction for an obligation borrowing from reserves with pair
+/// borrow weights, which are passed in the order the obligation borrows from them.
+pub fn refresh_obligation_with_pair_borrow_weights(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+    pair_borrow_weights_reserve_pubkeys: Vec<Pubkey>,
+) -> Instruction {
+    let mut instruction = refresh_obligation(program_id, obligation_pubkey, reserve_pubkeys);
+    instruction
+        .accounts
+        .extend(pair_borrow_weights_reserve_pubkeys.iter().map(|pubkey| {
+            AccountMeta::new_readonly(
+                find_reserve_pair_borrow_weights_address(&program_id, pubkey).0,
+                false,
+            )
+        }));
+    instruction
+}
+
+/// Creates a 'RefreshObligation' instruction that accepts reserves refreshed within the lending
+/// market's max price age, rather than only in the current slot.
+pub fn refresh_obligation_with_max_price_age(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+    pair_borrow_weights_reserve_pubkeys: Vec<Pubkey>,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    let mut instruction = refresh_obligation_with_pair_borrow_weights(
+        program_id,
+        obligation_pubkey,
+        reserve_pubkeys,
+        pair_borrow_weights_reserve_pubkeys,
+    );
+    instruction.accounts.push(AccountMeta::new_readonly(
+        find_max_price_age_address(&program_id, &lending_market_pubkey).0,
+        false,
+    ));
+    instruction
+}
+
+/// Creates a 'RefreshObligation' instruction for an obligation whose deposits and borrows are all
+/// in the same asset category, passing the lending market's asset categories account.
+pub fn refresh_obligation_with_asset_categories(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+    pair_borrow_weights_reserve_pubkeys: Vec<Pubkey>,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    let mut instruction = refresh_obligation_with_pair_borrow_weights(
+        program_id,
+        obligation_pubkey,
+        reserve_pubkeys,
+        pair_borrow_weights_reserve_pubkeys,
+    );
+    instruction.accounts.push(AccountMeta::new_readonly(
+        find_asset_categories_address(&program_id, &lending_market_pubkey).0,
+        false,
+    ));
+    instruction
+}
+
+/// Creates a 'DepositObligationCollateral' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_obligation_collateral(
+    program_id: Pubkey,
+    collateral_amount: u64,
+    source_collateral_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    deposit_reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_collateral_pubkey, false),
+            AccountMeta::new(destination_collateral_pubkey, false),
+            AccountMeta::new(deposit_reserve_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::DepositObligationCollateral { collateral_amount }.pack(),
+    }
+}
+
+/// Creates a 'DepositReserveLiquidityAndObligationCollateral' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_reserve_liquidity_and_obligation_collateral(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    user_collateral_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    destination_deposit_collateral_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
+    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(user_collateral_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(reserve_collateral_mint_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new(destination_deposit_collateral_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(reserve_liquidity_pyth_oracle_pubkey, false),
+            AccountMeta::new_readonly(reserve_liquidity_switchboard_oracle_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::DepositReserveLiquidityAndObligationCollateral {
+            liquidity_amount,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'WithdrawObligationCollateralAndRedeemReserveCollateral' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral(
+    program_id: Pubkey,
+    collateral_amount: u64,
+    source_collateral_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    collateral_reserves: Vec<Pubkey>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(source_collateral_pubkey, false),
+        AccountMeta::new(destination_collateral_pubkey, false),
+        AccountMeta::new(withdraw_reserve_pubkey, false),
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new(destination_liquidity_pubkey, false),
+        AccountMeta::new(reserve_collateral_mint_pubkey, false),
+        AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+        AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    accounts.extend(
+        collateral_reserves
+            .into_iter()
+            .map(|pubkey| AccountMeta::new(pubkey, false)),
+    );
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
+            collateral_amount,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a 'WithdrawObligationCollateral' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_obligation_collateral(
+    program_id: Pubkey,
+    collateral_amount: u64,
+    source_collateral_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    collateral_reserves: Vec<Pubkey>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(source_collateral_pubkey, false),
+        AccountMeta::new(destination_collateral_pubkey, false),
+        AccountMeta::new(withdraw_reserve_pubkey, false),
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    accounts.extend(
+        collateral_reserves
+            .into_iter()
+            .map(|pubkey| AccountMeta::new(pubkey, false)),
+    );
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::WithdrawObligationCollateral { collateral_amount }.pack(),
+    }
+}
+
+/// Creates a 'BorrowObligationLiquidity' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    collateral_reserves: Vec<Pubkey>,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+) -> Instruction {
+    borrow_obligation_liquidity_with_min_received(
+        program_id,
+        liquidity_amount,
+        0,
+        source_liquidity_pubkey,
+        destination_liquidity_pubkey,
+        borrow_reserve_pubkey,
+        borrow_reserve_liquidity_fee_receiver_pubkey,
+        obligation_pubkey,
+        lending_market_pubkey,
+        obligation_owner_pubkey,
+        collateral_reserves,
+        host_fee_receiver_pubkey,
+    )
+}
+
+/// Creates a 'BorrowObligationLiquidity' instruction that fails unless at least
+/// `min_liquidity_received` is received after fees, eg when borrowing u64::MAX.
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_with_min_received(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    min_liquidity_received: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    collateral_reserves: Vec<Pubkey>,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(source_liquidity_pubkey, false),
+        AccountMeta::new(destination_liquidity_pubkey, false),
+        AccountMeta::new(borrow_reserve_pubkey, false),
+        AccountMeta::new(borrow_reserve_liquidity_fee_receiver_pubkey, false),
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    for collateral_reserve in collateral_reserves {
+        accounts.push(AccountMeta::new(collateral_reserve, false));
+    }
+
+    if let Some(host_fee_receiver_pubkey) = host_fee_receiver_pubkey {
+        accounts.push(AccountMeta::new(host_fee_receiver_pubkey, false));
+    }
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::BorrowObligationLiquidity {
+            liquidity_amount,
+            min_liquidity_received,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `RepayObligationLiquidity` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn repay_obligation_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    repay_reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new(repay_reserve_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::RepayObligationLiquidity { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates a `RepayObligationLiquidityMany` instruction. Each repay is a tuple of
+/// (liquidity amount, source liquidity, repay reserve liquidity supply, repay reserve).
+pub fn repay_obligation_liquidity_many(
+    program_id: Pubkey,
+    repays: Vec<(u64, Pubkey, Pubkey, Pubkey)>,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    let mut liquidity_amounts = Vec::with_capacity(repays.len());
+    for (
+        liquidity_amount,
+        source_liquidity_pubkey,
+        destination_liquidity_pubkey,
+        repay_reserve_pubkey,
+    ) in repays
+    {
+        liquidity_amounts.push(liquidity_amount);
+        accounts.push(AccountMeta::new(source_liquidity_pubkey, false));
+        accounts.push(AccountMeta::new(destination_liquidity_pubkey, false));
+        accounts.push(AccountMeta::new(repay_reserve_pubkey, false));
+    }
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::RepayObligationLiquidityMany { liquidity_amounts }.pack(),
+    }
+}
+
+/// Creates a `LiquidateObligation` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    repay_reserve_pubkey: Pubkey,
+    repay_reserve_liquidity_supply_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve_collateral_supply_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_collateral_pubkey, false),
+            AccountMeta::new(repay_reserve_pubkey, false),
+            AccountMeta::new(repay_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new_readonly(withdraw_reserve_pubkey, false),
+            AccountMeta::new(withdraw_reserve_collateral_supply_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::LiquidateObligation { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates an 'UpdateReserveConfig' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn update_reserve_config(
+    program_id: Pubkey,
+    config: ReserveConfig,
+    rate_limiter_config: RateLimiterConfig,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    pyth_product_pubkey: Pubkey,
+    pyth_price_pubkey: Pubkey,
+    switchboard_feed_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(reserve_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        AccountMeta::new_readonly(pyth_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_price_pubkey, false),
+        AccountMeta::new_readonly(switchboard_feed_pubkey, false),
+    ];
+
+    if let Some(extra_oracle_pubkey) = config.extra_oracle_pubkey {
+        accounts.push(AccountMeta::new_readonly(extra_oracle_pubkey, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::UpdateReserveConfig {
+            config,
+            rate_limiter_config,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `LiquidateObligationAndRedeemReserveCollateral` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation_and_redeem_reserve_collateral(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    repay_reserve_pubkey: Pubkey,
+    repay_reserve_liquidity_supply_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve_collateral_mint_pubkey: Pubkey,
+    withdraw_reserve_collateral_supply_pubkey: Pubkey,
+    withdraw_reserve_liquidity_supply_pubkey: Pubkey,
+    withdraw_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_collateral_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new(repay_reserve_pubkey, false),
+            AccountMeta::new(repay_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(withdraw_reserve_pubkey, false),
+            AccountMeta::new(withdraw_reserve_collateral_mint_pubkey, false),
+            AccountMeta::new(withdraw_reserve_collateral_supply_pubkey, false),
+            AccountMeta::new(withdraw_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(withdraw_reserve_liquidity_fee_receiver_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::LiquidateObligationAndRedeemReserveCollateral {
+            liquidity_amount,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `LiquidateObligationAndRedeemReserveCollateral` instruction for a withdraw reserve
+/// that takes its protocol liquidation fee in the repaid liquidity
+#[allow(clippy::too_many_arguments)]
+pub fn liquidate_obligation_and_redeem_reserve_collateral_with_liquidity_fee(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    repay_reserve_pubkey: Pubkey,
+    repay_reserve_liquidity_supply_pubkey: Pubkey,
+    repay_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve_collateral_mint_pubkey: Pubkey,
+    withdraw_reserve_collateral_supply_pubkey: Pubkey,
+    withdraw_reserve_liquidity_supply_pubkey: Pubkey,
+    withdraw_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let mut instruction = liquidate_obligation_and_redeem_reserve_collateral(
+        program_id,
+        liquidity_amount,
+        source_liquidity_pubkey,
+        destination_collateral_pubkey,
+        destination_liquidity_pubkey,
+        repay_reserve_pubkey,
+        repay_reserve_liquidity_supply_pubkey,
+        withdraw_reserve_pubkey,
+        withdraw_reserve_collateral_mint_pubkey,
+        withdraw_reserve_collateral_supply_pubkey,
+        withdraw_reserve_liquidity_supply_pubkey,
+        withdraw_reserve_liquidity_fee_receiver_pubkey,
+        obligation_pubkey,
+        lending_market_pubkey,
+        user_transfer_authority_pubkey,
+    );
+    instruction.accounts.push(AccountMeta::new(
+        repay_reserve_liquidity_fee_receiver_pubkey,
+        false,
+    ));
+    instruction
+}
+
+/// Creates a `RedeemFees` instruction
+pub fn redeem_fees(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    reserve_supply_liquidity_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let accounts = vec![
+        AccountMeta::new(reserve_pubkey, false),
+        AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
+        AccountMeta::new(reserve_supply_liquidity_pubkey, false),
+        AccountMeta::new_readonly(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::RedeemFees.pack(),
+    }
+}
+
+/// Creates a `RedeemFees` instruction for a reserve with an insurance fund
+pub fn redeem_fees_with_insurance_fund(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    reserve_supply_liquidity_pubkey: Pubkey,
+    reserve_liquidity_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    let mut instruction = redeem_fees(
+        program_id,
+        reserve_pubkey,
+        reserve_liquidity_fee_receiver_pubkey,
+        reserve_supply_liquidity_pubkey,
+        lending_market_pubkey,
+    );
+    let (insurance_fund_pubkey, _bump_seed) = find_insurance_fund_address(
+        &program_id,
+        &lending_market_pubkey,
+        &reserve_liquidity_mint_pubkey,
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new(insurance_fund_pubkey, false));
+
+    instruction
+}
+
+/// Creates a `RedeemFeesIfAbove` instruction
+pub fn redeem_fees_if_above(
+    program_id: Pubkey,
+    min_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    reserve_supply_liquidity_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        data: LendingInstruction::RedeemFeesIfAbove { min_amount }.pack(),
+        ..redeem_fees(
+            program_id,
+            reserve_pubkey,
+            reserve_liquidity_fee_receiver_pubkey,
+            reserve_supply_liquidity_pubkey,
+            lending_market_pubkey,
+        )
+    }
+}
+
+/// Creates a `RedeemFeesIfAbove` instruction for a reserve with an insurance fund
+pub fn redeem_fees_if_above_with_insurance_fund(
+    program_id: Pubkey,
+    min_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    reserve_supply_liquidity_pubkey: Pubkey,
+    reserve_liquidity_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        data: LendingInstruction::RedeemFeesIfAbove { min_amount }.pack(),
+        ..redeem_fees_with_insurance_fund(
+            program_id,
+            reserve_pubkey,
+            reserve_liquidity_fee_receiver_pubkey,
+            reserve_supply_liquidity_pubkey,
+            reserve_liquidity_mint_pubkey,
+            lending_market_pubkey,
+        )
+    }
+}
+
+/// Creates a 'FlashBorrowReserveLiquidity' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn flash_borrow_reserve_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::FlashBorrowReserveLiquidity { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates a 'FlashRepayReserveLiquidity' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn flash_repay_reserve_liquidity(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    borrow_instruction_index: u8,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    host_fee_receiver_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
+            AccountMeta::new(host_fee_receiver_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::FlashRepayReserveLiquidity {
+            liquidity_amount,
+            borrow_instruction_index,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `ForgiveDebt` instruction
+pub fn forgive_debt(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner, true),
+        ],
+        data: LendingInstruction::ForgiveDebt { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates a `UpdateMarketMetadata` instruction
+pub fn update_market_metadata(
+    program_id: Pubkey,
+    mut metadata: LendingMarketMetadata,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+) -> Instruction {
+    let (lending_market_metadata_pubkey, bump_seed) = Pubkey::find_program_address(
+        &[
+            &lending_market_pubkey.to_bytes()[..PUBKEY_BYTES],
+            b"MetaData",
+        ],
+        &program_id,
+    );
+
+    metadata.bump_seed = bump_seed;
+
+    let mut data = [0u8; 1 + std::mem::size_of::<LendingMarketMetadata>()];
+    data[0] = 22;
+    data[1..].copy_from_slice(bytes_of(&metadata));
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner, true),
+            AccountMeta::new(lending_market_metadata_pubkey, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: data.to_vec(),
+    }
+}
+
+/// Creates a `MarkObligationAsClosable` instruction
+pub fn set_obligation_closeability_status(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    risk_authority: Pubkey,
+    closeable: bool,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(reserve_pubkey, false),
+            AccountMeta::new_readonly(risk_authority, true),
+        ],
+        data: LendingInstruction::SetObligationCloseabilityStatus { closeable }.pack(),
+    }
+}
+
+/// Creates a `DonateToReserve` instruction
+pub fn donate_to_reserve(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::DonateToReserve { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates an `InitObligationArchive` instruction
+pub fn init_obligation_archive(
+    program_id: Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner: Pubkey,
+    merkle_tree_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner, true),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new(merkle_tree_pubkey, false),
+            AccountMeta::new_readonly(spl_account_compression::id(), false),
+            AccountMeta::new_readonly(spl_noop::id(), false),
+        ],
+        data: LendingInstruction::InitObligationArchive {
+            max_depth,
+            max_buffer_size,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `ArchiveObligation` instruction
+pub fn archive_obligation(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    merkle_tree_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let (obligation_counter_pubkey, _bump_seed) = find_obligation_counter_address(
+        &program_id,
+        &lending_market_pubkey,
+        &obligation_owner_pubkey,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new(merkle_tree_pubkey, false),
+            AccountMeta::new_readonly(spl_account_compression::id(), false),
+            AccountMeta::new_readonly(spl_noop::id(), false),
+            AccountMeta::new(obligation_counter_pubkey, false),
+        ],
+        data: LendingInstruction::ArchiveObligation.pack(),
+    }
+}
+
+/// Creates a `RotateReserveCollateralSupply` instruction
+pub fn rotate_reserve_collateral_supply(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    current_collateral_supply_pubkey: Pubkey,
+    new_collateral_supply_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new(current_collateral_supply_pubkey, false),
+            AccountMeta::new(new_collateral_supply_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::RotateReserveCollateralSupply.pack(),
+    }
+}
+
+/// Creates a `RotateReserveFeeReceiver` instruction
+pub fn rotate_reserve_fee_receiver(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    signer_pubkey: Pubkey,
+    current_fee_receiver_pubkey: Pubkey,
+    new_fee_receiver_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new(signer_pubkey, true),
+            AccountMeta::new(current_fee_receiver_pubkey, false),
+            AccountMeta::new(new_fee_receiver_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::RotateReserveFeeReceiver.pack(),
+    }
+}
+
+/// Creates a `SetDefaultHostFeeReceiver` instruction
+pub fn set_default_host_fee_receiver(
+    program_id: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    default_host_fee_receiver: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+    ];
+    if let Some(default_host_fee_receiver) = default_host_fee_receiver {
+        accounts.push(AccountMeta::new_readonly(default_host_fee_receiver, false));
+    }
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::SetDefaultHostFeeReceiver.pack(),
+    }
+}
+
+/// Creates a `SetReserveUtilizationKillSwitch` instruction
+pub fn set_reserve_utilization_kill_switch(
+    program_id: Pubkey,
+    emergency_utilization_threshold: u8,
+    emergency_utilization_slots: u64,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetReserveUtilizationKillSwitch {
+            emergency_utilization_threshold,
+            emergency_utilization_slots,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CrankCloseEmptyObligation` instruction
+pub fn crank_close_empty_obligation(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    bounty_destination_pubkey: Pubkey,
+) -> Instruction {
+    let (obligation_counter_pubkey, _bump_seed) = find_obligation_counter_address(
+        &program_id,
+        &lending_market_pubkey,
+        &obligation_owner_pubkey,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(obligation_owner_pubkey, false),
+            AccountMeta::new(bounty_destination_pubkey, false),
+            AccountMeta::new(obligation_counter_pubkey, false),
+        ],
+        data: LendingInstruction::CrankCloseEmptyObligation.pack(),
+    }
+}
+
+/// Creates a `SetObligationBorrowLock` instruction
+pub fn set_obligation_borrow_lock(
+    program_id: Pubkey,
+    borrows_locked: bool,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetObligationBorrowLock { borrows_locked }.pack(),
+    }
+}
+
+/// Creates a `SetObligationBorrowCap` instruction
+pub fn set_obligation_borrow_cap(
+    program_id: Pubkey,
+    borrow_cap: u32,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetObligationBorrowCap { borrow_cap }.pack(),
+    }
+}
+
+/// Creates a `DelegateBorrowAuthority` instruction
+pub fn delegate_borrow_authority(
+    program_id: Pubkey,
+    approved_borrower: Pubkey,
+    delegated_value: u64,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        ],
+        data: LendingInstruction::DelegateBorrowAuthority {
+            approved_borrower,
+            delegated_value,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetReservePairBorrowWeight` instruction
+pub fn set_reserve_pair_borrow_weight(
+    program_id: Pubkey,
+    borrow_weight_bps: u16,
+    borrow_reserve_pubkey: Pubkey,
+    collateral_reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (reserve_pair_borrow_weights_pubkey, _bump_seed) =
+        find_reserve_pair_borrow_weights_address(&program_id, &borrow_reserve_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pair_borrow_weights_pubkey, false),
+            AccountMeta::new(borrow_reserve_pubkey, false),
+            AccountMeta::new_readonly(collateral_reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetReservePairBorrowWeight { borrow_weight_bps }.pack(),
+    }
+}
+
+/// Creates a `SetWhitelistRequired` instruction
+pub fn set_whitelist_required(
+    program_id: Pubkey,
+    whitelist_required: bool,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetWhitelistRequired { whitelist_required }.pack(),
+    }
+}
+
+/// Creates an `UpdateMarketMember` instruction
+pub fn update_market_member(
+    program_id: Pubkey,
+    is_member: bool,
+    member_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (market_member_pubkey, _bump_seed) =
+        find_market_member_address(&program_id, &lending_market_pubkey, &member_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(market_member_pubkey, false),
+            AccountMeta::new_readonly(member_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::UpdateMarketMember { is_member }.pack(),
+    }
+}
+
+/// Creates a `SetLiquidatorWhitelist` instruction
+pub fn set_liquidator_whitelist(
+    program_id: Pubkey,
+    protected_window_slots: u64,
+    liquidators: Vec<Pubkey>,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (liquidator_whitelist_pubkey, _bump_seed) =
+        find_liquidator_whitelist_address(&program_id, &lending_market_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(liquidator_whitelist_pubkey, false),
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetLiquidatorWhitelist {
+            protected_window_slots,
+            liquidators,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetEmergencyPrice` instruction
+pub fn set_emergency_price(
+    program_id: Pubkey,
+    price: Decimal,
+    max_duration_slots: u64,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    signer_pubkey: Pubkey,
+) -> Instruction {
+    let (emergency_price_pubkey, _bump_seed) =
+        find_emergency_price_address(&program_id, &reserve_pubkey);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(emergency_price_pubkey, false),
+            AccountMeta::new_readonly(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(signer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetEmergencyPrice {
+            price,
+            max_duration_slots,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetRewardRule` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn set_reward_rule(
+    program_id: Pubkey,
+    side: RewardSide,
+    emission_per_slot: u64,
+    start_slot: u64,
+    end_slot: u64,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    reward_supply_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (reward_rule_pubkey, _bump_seed) =
+        find_reward_rule_address(&program_id, &reserve_pubkey, side);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reward_rule_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(reward_supply_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetRewardRule {
+            side,
+            emission_per_slot,
+            start_slot,
+            end_slot,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `UpdateRewards` instruction
+pub fn update_rewards(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+        ],
+        data: LendingInstruction::UpdateRewards.pack(),
+    }
+}
+
+/// Creates a `ClaimRewards` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn claim_rewards(
+    program_id: Pubkey,
+    reward_rule_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    reward_supply_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(reward_rule_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new(reward_supply_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::ClaimRewards.pack(),
+    }
+}
+
+/// Creates a `SetAssetCategory` instruction
+pub fn set_asset_category(
+    program_id: Pubkey,
+    asset_category: u8,
+    loan_to_value_ratio: u8,
+    liquidation_threshold: u8,
+    max_liquidation_threshold: u8,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (asset_categories_pubkey, _bump_seed) =
+        find_asset_categories_address(&program_id, &lending_market_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(asset_categories_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetAssetCategory {
+            asset_category,
+            loan_to_value_ratio,
+            liquidation_threshold,
+            max_liquidation_threshold,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetRiskAuthority` instruction
+pub fn set_risk_authority(
+    program_id: Pubkey,
+    risk_authority: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetRiskAuthority { risk_authority }.pack(),
+    }
+}
+
+/// Creates a `SetMaxObligationsPerOwner` instruction
+pub fn set_max_obligations_per_owner(
+    program_id: Pubkey,
+    max_obligations_per_owner: u16,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetMaxObligationsPerOwner {
+            max_obligations_per_owner,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetDustBorrowValue` instruction
+pub fn set_dust_borrow_value(
+    program_id: Pubkey,
+    dust_borrow_value: u64,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetDustBorrowValue { dust_borrow_value }.pack(),
+    }
+}
+
+/// Creates a `SetMaxPriceAge` instruction
+pub fn set_max_price_age(
+    program_id: Pubkey,
+    max_price_age_slots: u8,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (max_price_age_pubkey, _bump_seed) =
+        find_max_price_age_address(&program_id, &lending_market_pubkey);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(max_price_age_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetMaxPriceAge {
+            max_price_age_slots,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetObligationMaxOutflow` instruction
+pub fn set_obligation_max_outflow(
+    program_id: Pubkey,
+    obligation_max_outflow: u64,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetObligationMaxOutflow {
+            obligation_max_outflow,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `ResetRateLimiter` instruction
+pub fn reset_rate_limiter(
+    program_id: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+    ];
+    accounts.extend(
+        reserve_pubkeys
+            .into_iter()
+            .map(|reserve_pubkey| AccountMeta::new(reserve_pubkey, false)),
+    );
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::ResetRateLimiter.pack(),
+    }
+}
+
+/// Creates a `SetProtocolFeeConfig` instruction
+pub fn set_protocol_fee_config(
+    program_id: Pubkey,
+    fee_authority: Pubkey,
+    treasury: Pubkey,
+    rate_limiter_config: RateLimiterConfig,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetProtocolFeeConfig {
+            fee_authority,
+            treasury,
+            rate_limiter_config,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `WithdrawProtocolFees` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_protocol_fees(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    treasury_token_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    fee_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(reserve_pubkey, false),
+            AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
+            AccountMeta::new(treasury_token_pubkey, false),
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(fee_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::WithdrawProtocolFees { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates a `SetInsuranceFee` instruction
+pub fn set_insurance_fee(
+    program_id: Pubkey,
+    insurance_fee_bps: u16,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetInsuranceFee { insurance_fee_bps }.pack(),
+    }
+}
+
+/// Creates an `InitInsuranceFund` instruction
+pub fn init_insurance_fund(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (insurance_fund_pubkey, _bump_seed) = find_insurance_fund_address(
+        &program_id,
+        &lending_market_pubkey,
+        &reserve_liquidity_mint_pubkey,
+    );
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(insurance_fund_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(reserve_liquidity_mint_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::InitInsuranceFund.pack(),
+    }
+}
+
+/// Creates a `CoverBadDebt` instruction
+pub fn cover_bad_debt(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    reserve_pubkey: Pubkey,
+    reserve_liquidity_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (insurance_fund_pubkey, _bump_seed) = find_insurance_fund_address(
+        &program_id,
+        &lending_market_pubkey,
+        &reserve_liquidity_mint_pubkey,
+    );
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(insurance_fund_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::CoverBadDebt { liquidity_amount }.pack(),
+    }
+}
+
+/// Creates a `BuyRateCap` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn buy_rate_cap(
+    program_id: Pubkey,
+    source_liquidity_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_mint_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (insurance_fund_pubkey, _bump_seed) = find_insurance_fund_address(
+        &program_id,
+        &lending_market_pubkey,
+        &borrow_reserve_liquidity_mint_pubkey,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(source_liquidity_pubkey, false),
+            AccountMeta::new(insurance_fund_pubkey, false),
+            AccountMeta::new_readonly(borrow_reserve_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::BuyRateCap.pack(),
+    }
+}
+
+/// Creates a `ClaimRateCapCredit` instruction
+pub fn claim_rate_cap_credit(
+    program_id: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_mint_pubkey: Pubkey,
+    borrow_reserve_liquidity_supply_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+) -> Instruction {
+    let (insurance_fund_pubkey, _bump_seed) = find_insurance_fund_address(
+        &program_id,
+        &lending_market_pubkey,
+        &borrow_reserve_liquidity_mint_pubkey,
+    );
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(insurance_fund_pubkey, false),
+            AccountMeta::new(borrow_reserve_pubkey, false),
+            AccountMeta::new(borrow_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::ClaimRateCapCredit.pack(),
+    }
+}
+
+/// Creates a `SetReserveSpreadFeeReceiver` instruction
+pub fn set_reserve_spread_fee_receiver(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    spread_fee_receiver_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (reserve_spread_fee_receiver_pubkey, _bump_seed) =
+        find_reserve_spread_fee_receiver_address(&program_id, &reserve_pubkey);
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_spread_fee_receiver_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(spread_fee_receiver_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::SetReserveSpreadFeeReceiver.pack(),
+    }
+}
+
+/// Creates a `MigrateAccount` instruction
+pub fn migrate_account(program_id: Pubkey, account_pubkey: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(account_pubkey, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::MigrateAccount.pack(),
+    }
+}
+
+/// Creates a `MigrateAccount` instruction for an obligation
+pub fn migrate_obligation(
+    program_id: Pubkey,
+    obligation_pubkey: Pubkey,
+    payer: Pubkey,
+    reserve_pubkeys: Vec<Pubkey>,
+) -> Instruction {
+    let mut instruction = migrate_account(program_id, obligation_pubkey, payer);
+    instruction.accounts.extend(
+        reserve_pubkeys
+            .into_iter()
+            .map(|pubkey| AccountMeta::new_readonly(pubkey, false)),
+    );
+    instruction
+}
+
+/// Creates a 'WithdrawObligationLiquidityExact' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_obligation_liquidity_exact(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    source_collateral_pubkey: Pubkey,
+    destination_collateral_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    collateral_reserves: Vec<Pubkey>,
+) -> Instruction {
+    let mut instruction = withdraw_obligation_collateral_and_redeem_reserve_collateral(
+        program_id,
+        liquidity_amount,
+        source_collateral_pubkey,
+        destination_collateral_pubkey,
+        withdraw_reserve_pubkey,
+        obligation_pubkey,
+        lending_market_pubkey,
+        destination_liquidity_pubkey,
+        reserve_collateral_mint_pubkey,
+        reserve_liquidity_supply_pubkey,
+        obligation_owner_pubkey,
+        user_transfer_authority_pubkey,
+        collateral_reserves,
+    );
+    instruction.data =
+        LendingInstruction::WithdrawObligationLiquidityExact { liquidity_amount }.pack();
+    instruction
+}
+
+/// Creates a `SetObligationReferrer` instruction
+pub fn set_obligation_referrer(
+    program_id: Pubkey,
+    referrer: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        ],
+        data: LendingInstruction::SetObligationReferrer { referrer }.pack(),
+    }
+}
+
+/// Creates a `CompoundObligationCollateral` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn compound_obligation_collateral(
+    program_id: Pubkey,
+    collateral_amount: u64,
+    user_collateral_pubkey: Pubkey,
+    user_liquidity_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    reserve_collateral_mint_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    destination_deposit_collateral_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(user_collateral_pubkey, false),
+            AccountMeta::new(user_liquidity_pubkey, false),
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new(reserve_collateral_mint_pubkey, false),
+            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(destination_deposit_collateral_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::CompoundObligationCollateral { collateral_amount }.pack(),
+    }
+}
+
+/// Creates a `RepayObligationLiquidityWithCollateral` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn repay_obligation_liquidity_with_collateral(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    repay_reserve_pubkey: Pubkey,
+    repay_reserve_liquidity_supply_pubkey: Pubkey,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve_collateral_mint_pubkey: Pubkey,
+    withdraw_reserve_collateral_supply_pubkey: Pubkey,
+    withdraw_reserve_liquidity_supply_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(repay_reserve_pubkey, false),
+            AccountMeta::new(repay_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(withdraw_reserve_pubkey, false),
+            AccountMeta::new(withdraw_reserve_collateral_mint_pubkey, false),
+            AccountMeta::new(withdraw_reserve_collateral_supply_pubkey, false),
+            AccountMeta::new(withdraw_reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(obligation_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new_readonly(obligation_owner_pubkey, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::RepayObligationLiquidityWithCollateral { liquidity_amount }
+            .pack(),
+    }
+}
+
+/// Creates a `DepositReserveLiquidityAndObligationCollateralAndBorrow` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+    program_id: Pubkey,
+    deposit_liquidity_amount: u64,
+    borrow_liquidity_amount: u64,
+    source_liquidity_pubkey: Pubkey,
+    user_collateral_pubkey: Pubkey,
+    deposit_reserve_pubkey: Pubkey,
+    deposit_reserve_liquidity_supply_pubkey: Pubkey,
+    deposit_reserve_collateral_mint_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    destination_deposit_collateral_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_supply_pubkey: Pubkey,
+    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    obligation_reserves: Vec<Pubkey>,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(source_liquidity_pubkey, false),
+        AccountMeta::new(user_collateral_pubkey, false),
+        AccountMeta::new(deposit_reserve_pubkey, false),
+        AccountMeta::new(deposit_reserve_liquidity_supply_pubkey, false),
+        AccountMeta::new(deposit_reserve_collateral_mint_pubkey, false),
+        AccountMeta::new(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new(destination_deposit_collateral_pubkey, false),
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(borrow_reserve_pubkey, false),
+        AccountMeta::new(borrow_reserve_liquidity_supply_pubkey, false),
+        AccountMeta::new(borrow_reserve_liquidity_fee_receiver_pubkey, false),
+        AccountMeta::new(destination_liquidity_pubkey, false),
+    ];
+    for obligation_reserve in obligation_reserves {
+        accounts.push(AccountMeta::new(obligation_reserve, false));
+    }
+
+    if let Some(host_fee_receiver_pubkey) = host_fee_receiver_pubkey {
+        accounts.push(AccountMeta::new(host_fee_receiver_pubkey, false));
+    }
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::DepositReserveLiquidityAndObligationCollateralAndBorrow {
+            deposit_liquidity_amount,
+            borrow_liquidity_amount,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `InitReserveRateStats` instruction
+pub fn init_reserve_rate_stats(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+) -> Instruction {
+    let (reserve_rate_stats_pubkey, _bump_seed) =
+        find_reserve_rate_stats_address(&program_id, &reserve_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_rate_stats_pubkey, false),
+            AccountMeta::new_readonly(reserve_pubkey, false),
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::InitReserveRateStats.pack(),
     }
+}
 
-    fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
-        if input.len() < 8 {
-            msg!("i64 cannot be unpacked");
-            return Err(LendingError::InstructionUnpackError.into());
-        }
-        let (bytes, rest) = input.split_at(8);
-        let value = bytes
-            .get(..8)
-            .and_then(|slice| slice.try_into().ok())
-            .map(i64::from_le_bytes)
-            .ok_or(LendingError::InstructionUnpackError)?;
-        Ok((value, rest))
+/// Creates an `InitReserveActivity` instruction
+pub fn init_reserve_activity(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+) -> Instruction {
+    let (reserve_activity_pubkey, _bump_seed) =
+        find_reserve_activity_address(&program_id, &reserve_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_activity_pubkey, false),
+            AccountMeta::new_readonly(reserve_pubkey, false),
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::InitReserveActivity.pack(),
     }
+}
 
-    fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
-        if input.is_empty() {
-            msg!("u8 cannot be unpacked");
-            return Err(LendingError::InstructionUnpackError.into());
-        }
-        let (bytes, rest) = input.split_at(1);
-        let value = bytes
-            .get(..1)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u8::from_le_bytes)
-            .ok_or(LendingError::InstructionUnpackError)?;
-        Ok((value, rest))
+/// Creates a `DevnetMintTestTokens` instruction
+#[cfg(feature = "devnet-utils")]
+pub fn devnet_mint_test_tokens(
+    program_id: Pubkey,
+    amount: u64,
+    mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+) -> Instruction {
+    let (devnet_faucet_authority_pubkey, _bump_seed) =
+        crate::state::find_devnet_faucet_authority_address(&program_id);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(mint_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new_readonly(devnet_faucet_authority_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: LendingInstruction::DevnetMintTestTokens { amount }.pack(),
+    }
+}
+
+/// Creates a `DevnetSetPrice` instruction
+#[cfg(feature = "devnet-utils")]
+pub fn devnet_set_price(
+    program_id: Pubkey,
+    price: Decimal,
+    mint_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+) -> Instruction {
+    let (devnet_price_pubkey, _bump_seed) =
+        crate::state::find_devnet_price_address(&program_id, &mint_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(devnet_price_pubkey, false),
+            AccountMeta::new_readonly(mint_pubkey, false),
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::DevnetSetPrice { price }.pack(),
     }
+}
 
-    fn unpack_bytes32(input: &[u8]) -> Result<(&[u8; 32], &[u8]), ProgramError> {
-        if input.len() < 32 {
-            msg!("32 bytes cannot be unpacked");
-            return Err(LendingError::InstructionUnpackError.into());
+/// Creates a `BorrowObligationLiquidityWithWalletCollateral` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_obligation_liquidity_with_wallet_collateral(
+    program_id: Pubkey,
+    liquidity_amount: u64,
+    user_collateral_pubkey: Pubkey,
+    deposit_reserve_pubkey: Pubkey,
+    destination_deposit_collateral_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    obligation_pubkey: Pubkey,
+    obligation_owner_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve_liquidity_supply_pubkey: Pubkey,
+    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+    obligation_reserves: Vec<Pubkey>,
+    host_fee_receiver_pubkey: Option<Pubkey>,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    let mut accounts = vec![
+        AccountMeta::new(user_collateral_pubkey, false),
+        AccountMeta::new(deposit_reserve_pubkey, false),
+        AccountMeta::new(destination_deposit_collateral_pubkey, false),
+        AccountMeta::new(lending_market_pubkey, false),
+        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+        AccountMeta::new(obligation_pubkey, false),
+        AccountMeta::new_readonly(obligation_owner_pubkey, true),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(borrow_reserve_pubkey, false),
+        AccountMeta::new(borrow_reserve_liquidity_supply_pubkey, false),
+        AccountMeta::new(borrow_reserve_liquidity_fee_receiver_pubkey, false),
+        AccountMeta::new(destination_liquidity_pubkey, false),
+    ];
+    for obligation_reserve in obligation_reserves {
+        accounts.push(AccountMeta::new(obligation_reserve, false));
+    }
+    if let Some(host_fee_receiver_pubkey) = host_fee_receiver_pubkey {
+        accounts.push(AccountMeta::new(host_fee_receiver_pubkey, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: LendingInstruction::BorrowObligationLiquidityWithWalletCollateral {
+            liquidity_amount,
         }
-        let (bytes, rest) = input.split_at(32);
-        Ok((
-            bytes
-                .try_into()
-                .map_err(|_| LendingError::InstructionUnpackError)?,
-            rest,
-        ))
+        .pack(),
+    }
+}
+
+/// Creates a `CloseReserve` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn close_reserve(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+    reserve_liquidity_supply_pubkey: Pubkey,
+    reserve_collateral_supply_pubkey: Pubkey,
+    reserve_liquidity_fee_receiver_pubkey: Pubkey,
+    destination_liquidity_pubkey: Pubkey,
+) -> Instruction {
+    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
+        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+        &program_id,
+    );
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+            AccountMeta::new(lending_market_owner_pubkey, true),
+            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
+            AccountMeta::new(reserve_collateral_supply_pubkey, false),
+            AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(
+                find_reserve_pair_borrow_weights_address(&program_id, &reserve_pubkey).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_reserve_rate_stats_address(&program_id, &reserve_pubkey).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_reserve_activity_address(&program_id, &reserve_pubkey).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_reserve_ctoken_price_address(&program_id, &reserve_pubkey).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_reserve_spread_fee_receiver_address(&program_id, &reserve_pubkey).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_emergency_price_address(&program_id, &reserve_pubkey).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_reward_rule_address(&program_id, &reserve_pubkey, RewardSide::Deposit).0,
+                false,
+            ),
+            AccountMeta::new(
+                find_reward_rule_address(&program_id, &reserve_pubkey, RewardSide::Borrow).0,
+                false,
+            ),
+        ],
+        data: LendingInstruction::CloseReserve.pack(),
+    }
+}
+
+/// Creates a `SetReserveTertiaryOracle` instruction. Pass NULL_PUBKEY as the oracle to remove
+/// the reserve's tertiary oracle.
+pub fn set_reserve_tertiary_oracle(
+    program_id: Pubkey,
+    oracle_pubkey: Pubkey,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+            AccountMeta::new_readonly(oracle_pubkey, false),
+        ],
+        data: LendingInstruction::SetReserveTertiaryOracle.pack(),
+    }
+}
+
+/// Creates a `RefreshCTokenPrice` instruction
+pub fn refresh_ctoken_price(
+    program_id: Pubkey,
+    reserve_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+) -> Instruction {
+    let (reserve_ctoken_price_pubkey, _bump_seed) =
+        find_reserve_ctoken_price_address(&program_id, &reserve_pubkey);
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_ctoken_price_pubkey, false),
+            AccountMeta::new_readonly(reserve_pubkey, false),
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: LendingInstruction::RefreshCTokenPrice.pack(),
+    }
+}
+
+/// Creates the instructions of a flash loan powered leverage loop step: flash borrow from the
+/// borrow reserve, swap the borrowed liquidity into the deposit reserve's liquidity with the
+/// provided swap instruction, refresh the borrow reserve the flash borrow marked stale, deposit
+/// the swapped liquidity as collateral and borrow against it, then repay the flash loan with the
+/// borrowed liquidity.
+///
+/// Flash loans can't be taken through CPI, so this is a sequence of top-level instructions
+/// rather than a single one. `first_instruction_index` is the index of the first returned
+/// instruction in the transaction, and every reserve of the obligation plus the deposit and
+/// borrow reserves must be refreshed before it. `borrow_liquidity_amount` has to cover the flash
+/// loan and its fee.
+#[allow(clippy::too_many_arguments)]
+pub fn leverage_loop(
+    program_id: Pubkey,
+    flash_borrow_amount: u64,
+    deposit_liquidity_amount: u64,
+    borrow_liquidity_amount: u64,
+    swap_instruction: Instruction,
+    first_instruction_index: u8,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    deposit_reserve_pubkey: Pubkey,
+    deposit_reserve: &Reserve,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve: &Reserve,
+    user_deposit_liquidity_pubkey: Pubkey,
+    user_collateral_pubkey: Pubkey,
+    user_borrow_liquidity_pubkey: Pubkey,
+    host_fee_receiver_pubkey: Pubkey,
+) -> Vec<Instruction> {
+    let mut obligation_reserves: Vec<Pubkey> = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .collect();
+    if !obligation_reserves.contains(&deposit_reserve_pubkey) {
+        obligation_reserves.push(deposit_reserve_pubkey);
     }
+    obligation_reserves.extend(
+        obligation
+            .borrows
+            .iter()
+            .map(|liquidity| liquidity.borrow_reserve),
+    );
+
+    let deposit_and_borrow = deposit_reserve_liquidity_and_obligation_collateral_and_borrow(
+        program_id,
+        deposit_liquidity_amount,
+        borrow_liquidity_amount,
+        user_deposit_liquidity_pubkey,
+        user_collateral_pubkey,
+        deposit_reserve_pubkey,
+        deposit_reserve.liquidity.supply_pubkey,
+        deposit_reserve.collateral.mint_pubkey,
+        obligation.lending_market,
+        deposit_reserve.collateral.supply_pubkey,
+        obligation_pubkey,
+        obligation.owner,
+        obligation.owner,
+        borrow_reserve_pubkey,
+        borrow_reserve.liquidity.supply_pubkey,
+        borrow_reserve.config.fee_receiver,
+        user_borrow_liquidity_pubkey,
+        obligation_reserves,
+        Some(host_fee_receiver_pubkey),
+    );
+
+    vec![
+        flash_borrow_reserve_liquidity(
+            program_id,
+            flash_borrow_amount,
+            borrow_reserve.liquidity.supply_pubkey,
+            user_borrow_liquidity_pubkey,
+            borrow_reserve_pubkey,
+            obligation.lending_market,
+        ),
+        swap_instruction,
+        refresh_reserve(
+            program_id,
+            borrow_reserve_pubkey,
+            borrow_reserve.liquidity.pyth_oracle_pubkey,
+            borrow_reserve.liquidity.switchboard_oracle_pubkey,
+            borrow_reserve.config.extra_oracle_pubkey,
+        ),
+        deposit_and_borrow,
+        flash_repay_reserve_liquidity(
+            program_id,
+            flash_borrow_amount,
+            first_instruction_index,
+            user_borrow_liquidity_pubkey,
+            borrow_reserve.liquidity.supply_pubkey,
+            borrow_reserve.config.fee_receiver,
+            host_fee_receiver_pubkey,
+            borrow_reserve_pubkey,
+            obligation.lending_market,
+            obligation.owner,
+        ),
+    ]
+}
+
+/// Creates the instructions that unwind a leveraged position, the inverse of `leverage_loop`:
+/// flash borrow from the borrow reserve, repay the obligation's borrow with it, withdraw and
+/// redeem collateral now that the obligation is healthier, swap the withdrawn liquidity into the
+/// borrow reserve's liquidity with the provided swap instruction, then repay the flash loan.
+///
+/// `first_instruction_index` is the index of the first returned instruction in the transaction,
+/// and every reserve of the obligation must be refreshed before it. A `repay_liquidity_amount` of
+/// u64::MAX repays the whole borrow, which then drops out of the obligation. The swap has to
+/// leave enough in `user_borrow_liquidity_pubkey` to cover the flash loan and its fee.
+#[allow(clippy::too_many_arguments)]
+pub fn deleverage(
+    program_id: Pubkey,
+    flash_borrow_amount: u64,
+    repay_liquidity_amount: u64,
+    withdraw_collateral_amount: u64,
+    swap_instruction: Instruction,
+    first_instruction_index: u8,
+    obligation_pubkey: Pubkey,
+    obligation: &Obligation,
+    withdraw_reserve_pubkey: Pubkey,
+    withdraw_reserve: &Reserve,
+    borrow_reserve_pubkey: Pubkey,
+    borrow_reserve: &Reserve,
+    user_collateral_pubkey: Pubkey,
+    user_withdraw_liquidity_pubkey: Pubkey,
+    user_borrow_liquidity_pubkey: Pubkey,
+    host_fee_receiver_pubkey: Pubkey,
+) -> Vec<Instruction> {
+    let deposit_reserves: Vec<Pubkey> = obligation
+        .deposits
+        .iter()
+        .map(|collateral| collateral.deposit_reserve)
+        .collect();
+    let mut obligation_reserves = deposit_reserves.clone();
+    obligation_reserves.extend(
+        obligation
+            .borrows
+            .iter()
+            .map(|liquidity| liquidity.borrow_reserve)
+            .filter(|reserve| {
+                repay_liquidity_amount != u64::MAX || *reserve != borrow_reserve_pubkey
+            }),
+    );
+
+    // the flash borrow and the repay both mark the borrow reserve stale
+    let refresh_borrow_reserve = refresh_reserve(
+        program_id,
+        borrow_reserve_pubkey,
+        borrow_reserve.liquidity.pyth_oracle_pubkey,
+        borrow_reserve.liquidity.switchboard_oracle_pubkey,
+        borrow_reserve.config.extra_oracle_pubkey,
+    );
+
+    vec![
+        flash_borrow_reserve_liquidity(
+            program_id,
+            flash_borrow_amount,
+            borrow_reserve.liquidity.supply_pubkey,
+            user_borrow_liquidity_pubkey,
+            borrow_reserve_pubkey,
+            obligation.lending_market,
+        ),
+        refresh_borrow_reserve.clone(),
+        repay_obligation_liquidity(
+            program_id,
+            repay_liquidity_amount,
+            user_borrow_liquidity_pubkey,
+            borrow_reserve.liquidity.supply_pubkey,
+            borrow_reserve_pubkey,
+            obligation_pubkey,
+            obligation.lending_market,
+            obligation.owner,
+        ),
+        refresh_borrow_reserve,
+        refresh_obligation(program_id, obligation_pubkey, obligation_reserves),
+        withdraw_obligation_collateral_and_redeem_reserve_collateral(
+            program_id,
+            withdraw_collateral_amount,
+            withdraw_reserve.collateral.supply_pubkey,
+            user_collateral_pubkey,
+            withdraw_reserve_pubkey,
+            obligation_pubkey,
+            obligation.lending_market,
+            user_withdraw_liquidity_pubkey,
+            withdraw_reserve.collateral.mint_pubkey,
+            withdraw_reserve.liquidity.supply_pubkey,
+            obligation.owner,
+            obligation.owner,
+            deposit_reserves,
+        ),
+        swap_instruction,
+        flash_repay_reserve_liquidity(
+            program_id,
+            flash_borrow_amount,
+            first_instruction_index,
+            user_borrow_liquidity_pubkey,
+            borrow_reserve.liquidity.supply_pubkey,
+            borrow_reserve.config.fee_receiver,
+            host_fee_receiver_pubkey,
+            borrow_reserve_pubkey,
+            obligation.lending_market,
+            obligation.owner,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::{MAX_OBLIGATION_RESERVES, MAX_WHITELISTED_LIQUIDATORS};
+    use rand::Rng;
+
+    /// Size of the reserve config fields appended after the InitReserve and UpdateReserveConfig
+    /// payloads
+    const RESERVE_CONFIG_EXTENSION_LEN: usize = 67;
+
+    #[test]
+    fn pack_and_unpack_instructions() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            {
+                let instruction = LendingInstruction::InitLendingMarket {
+                    owner: Pubkey::new_unique(),
+                    quote_currency: [rng.gen::<u8>(); 32],
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // set lending market owner and config
+            {
+                let instruction = LendingInstruction::SetLendingMarketOwnerAndConfig {
+                    new_owner: Pubkey::new_unique(),
+                    rate_limiter_config: RateLimiterConfig {
+                        window_duration: rng.gen::<u64>(),
+                        max_outflow: rng.gen::<u64>(),
+                    },
+                    whitelisted_liquidator: if rng.gen_bool(0.5) {
+                        None
+                    } else {
+                        Some(Pubkey::new_unique())
+                    },
+                    risk_authority: Pubkey::new_unique(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            {
+                let instruction = LendingInstruction::InitReserve {
+                    liquidity_amount: rng.gen::<u64>(),
+                    config: ReserveConfig {
+                        optimal_utilization_rate: rng.gen::<u8>(),
+                        max_utilization_rate: rng.gen::<u8>(),
+                        loan_to_value_ratio: rng.gen::<u8>(),
+                        liquidation_bonus: rng.gen::<u8>(),
+                        max_liquidation_bonus: rng.gen::<u8>(),
+                        liquidation_threshold: rng.gen::<u8>(),
+                        max_liquidation_threshold: rng.gen::<u8>(),
+                        min_borrow_rate: rng.gen::<u8>(),
+                        optimal_borrow_rate: rng.gen::<u8>(),
+                        max_borrow_rate: rng.gen::<u8>(),
+                        super_max_borrow_rate: rng.gen::<u64>(),
+                        super_optimal_utilization_rate: rng.gen::<u8>(),
+                        super_optimal_borrow_rate: rng.gen::<u8>(),
+                        min_borrow_value: rng.gen(),
+                        max_collateral_value_contribution: rng.gen(),
+                        min_price: rng.gen(),
+                        max_price: rng.gen(),
+                        liquidation_auction: rng.gen(),
+                        protocol_liquidation_fee_in_liquidity: rng.gen(),
+                        fees: ReserveFees {
+                            borrow_fee_wad: rng.gen::<u64>(),
+                            flash_loan_fee_wad: rng.gen::<u64>(),
+                            host_fee_percentage: rng.gen::<u8>(),
+                        },
+                        deposit_limit: rng.gen::<u64>(),
+                        borrow_limit: rng.gen::<u64>(),
+                        fee_receiver: Pubkey::new_unique(),
+                        protocol_liquidation_fee: rng.gen::<u8>(),
+                        protocol_take_rate: rng.gen::<u8>(),
+                        added_borrow_weight_bps: rng.gen::<u64>(),
+                        reserve_type: ReserveType::from_u8(rng.gen::<u8>() % 2).unwrap(),
+                        scaled_price_offset_bps: rng.gen(),
+                        extra_oracle_pubkey: if rng.gen_bool(0.5) {
+                            None
+                        } else {
+                            Some(Pubkey::new_unique())
+                        },
+                        attributed_borrow_limit_open: rng.gen(),
+                        attributed_borrow_limit_close: rng.gen(),
+                        max_close_factor_bps: rng.gen(),
+                        liquidation_price_band_bps: rng.gen(),
+                        max_oracle_staleness_secs: rng.gen(),
+                        max_oracle_confidence_bps: rng.gen(),
+                        max_single_borrower_share_bps: rng.gen(),
+                        deposits_paused: rng.gen(),
+                        borrows_paused: rng.gen(),
+                        withdrawals_paused: rng.gen(),
+                        asset_category: rng.gen(),
+                        borrow_rate_cap: rng.gen(),
+                        rate_cap_premium_bps: rng.gen(),
+                        max_flash_loan_amount: rng.gen(),
+                    },
+                    collateral_decimals: rng.gen(),
+                };
 
-    fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
-        if input.len() < PUBKEY_BYTES {
-            msg!("Pubkey cannot be unpacked");
-            return Err(LendingError::InstructionUnpackError.into());
-        }
-        let (key, rest) = input.split_at(PUBKEY_BYTES);
-        let pk = Pubkey::try_from(key).map_err(|_| LendingError::InstructionUnpackError)?;
-        Ok((pk, rest))
-    }
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
 
-    /// Packs a [LendingInstruction](enum.LendingInstruction.html) into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match *self {
-            Self::InitLendingMarket {
-                owner,
-                quote_currency,
-            } => {
-                buf.push(0);
-                buf.extend_from_slice(owner.as_ref());
-                buf.extend_from_slice(quote_currency.as_ref());
-            }
-            Self::SetLendingMarketOwnerAndConfig {
-                new_owner,
-                rate_limiter_config: config,
-                whitelisted_liquidator,
-                risk_authority,
-            } => {
-                buf.push(1);
-                buf.extend_from_slice(new_owner.as_ref());
-                buf.extend_from_slice(&config.window_duration.to_le_bytes());
-                buf.extend_from_slice(&config.max_outflow.to_le_bytes());
-                match whitelisted_liquidator {
-                    Some(liquidator) => {
-                        buf.push(1);
-                        buf.extend_from_slice(liquidator.as_ref());
-                    }
-                    None => {
-                        buf.push(0);
-                    }
+                // instruction data without the appended config fields and collateral decimals is
+                // still accepted
+                let (liquidity_amount, config) = match instruction {
+                    LendingInstruction::InitReserve {
+                        liquidity_amount,
+                        config,
+                        ..
+                    } => (liquidity_amount, config),
+                    _ => unreachable!(),
                 };
-                buf.extend_from_slice(risk_authority.as_ref());
-            }
-            Self::InitReserve {
-                liquidity_amount,
-                config:
-                    ReserveConfig {
-                        optimal_utilization_rate,
-                        max_utilization_rate,
-                        loan_to_value_ratio,
-                        liquidation_bonus,
-                        max_liquidation_bonus,
-                        liquidation_threshold,
-                        max_liquidation_threshold,
-                        min_borrow_rate,
-                        optimal_borrow_rate,
-                        max_borrow_rate,
-                        super_max_borrow_rate,
-                        fees:
-                            ReserveFees {
-                                borrow_fee_wad,
-                                flash_loan_fee_wad,
-                                host_fee_percentage,
-                            },
-                        deposit_limit,
-                        borrow_limit,
-                        fee_receiver,
-                        protocol_liquidation_fee,
-                        protocol_take_rate,
-                        added_borrow_weight_bps: borrow_weight_bps,
-                        reserve_type: asset_type,
-                        scaled_price_offset_bps,
-                        extra_oracle_pubkey,
-                        attributed_borrow_limit_open,
-                        attributed_borrow_limit_close,
+                let instruction = LendingInstruction::InitReserve {
+                    liquidity_amount,
+                    config: ReserveConfig {
+                        max_close_factor_bps: 0,
+                        liquidation_price_band_bps: 0,
+                        max_oracle_staleness_secs: 0,
+                        max_oracle_confidence_bps: 0,
+                        max_single_borrower_share_bps: 0,
+                        deposits_paused: false,
+                        borrows_paused: false,
+                        withdrawals_paused: false,
+                        super_optimal_utilization_rate: 0,
+                        super_optimal_borrow_rate: 0,
+                        min_borrow_value: 0,
+                        max_collateral_value_contribution: 0,
+                        min_price: 0,
+                        max_price: 0,
+                        liquidation_auction: false,
+                        protocol_liquidation_fee_in_liquidity: false,
+                        asset_category: 0,
+                        borrow_rate_cap: 0,
+                        rate_cap_premium_bps: 0,
+                        max_flash_loan_amount: 0,
+                        ..config
                     },
-            } => {
-                buf.push(2);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-                buf.extend_from_slice(&optimal_utilization_rate.to_le_bytes());
-                buf.extend_from_slice(&max_utilization_rate.to_le_bytes());
-                buf.extend_from_slice(&loan_to_value_ratio.to_le_bytes());
-                buf.extend_from_slice(&liquidation_bonus.to_le_bytes());
-                buf.extend_from_slice(&liquidation_threshold.to_le_bytes());
-                buf.extend_from_slice(&min_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&optimal_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&max_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&super_max_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&borrow_fee_wad.to_le_bytes());
-                buf.extend_from_slice(&flash_loan_fee_wad.to_le_bytes());
-                buf.extend_from_slice(&host_fee_percentage.to_le_bytes());
-                buf.extend_from_slice(&deposit_limit.to_le_bytes());
-                buf.extend_from_slice(&borrow_limit.to_le_bytes());
-                buf.extend_from_slice(&fee_receiver.to_bytes());
-                buf.extend_from_slice(&protocol_liquidation_fee.to_le_bytes());
-                buf.extend_from_slice(&protocol_take_rate.to_le_bytes());
-                buf.extend_from_slice(&borrow_weight_bps.to_le_bytes());
-                buf.extend_from_slice(&(asset_type as u8).to_le_bytes());
-                buf.extend_from_slice(&max_liquidation_bonus.to_le_bytes());
-                buf.extend_from_slice(&max_liquidation_threshold.to_le_bytes());
-                buf.extend_from_slice(&scaled_price_offset_bps.to_le_bytes());
-                match extra_oracle_pubkey {
-                    Some(pubkey) => {
-                        buf.push(1);
-                        buf.extend_from_slice(pubkey.as_ref());
-                    }
-                    None => {
-                        buf.push(0);
-                    }
+                    collateral_decimals: None,
                 };
-                buf.extend_from_slice(&attributed_borrow_limit_open.to_le_bytes());
-                buf.extend_from_slice(&attributed_borrow_limit_close.to_le_bytes());
-            }
-            Self::RefreshReserve => {
-                buf.push(3);
-            }
-            Self::DepositReserveLiquidity { liquidity_amount } => {
-                buf.push(4);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-            }
-            Self::RedeemReserveCollateral { collateral_amount } => {
-                buf.push(5);
-                buf.extend_from_slice(&collateral_amount.to_le_bytes());
-            }
-            Self::InitObligation => {
-                buf.push(6);
-            }
-            Self::RefreshObligation => {
-                buf.push(7);
-            }
-            Self::DepositObligationCollateral { collateral_amount } => {
-                buf.push(8);
-                buf.extend_from_slice(&collateral_amount.to_le_bytes());
-            }
-            Self::WithdrawObligationCollateral { collateral_amount } => {
-                buf.push(9);
-                buf.extend_from_slice(&collateral_amount.to_le_bytes());
-            }
-            Self::BorrowObligationLiquidity { liquidity_amount } => {
-                buf.push(10);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-            }
-            Self::RepayObligationLiquidity { liquidity_amount } => {
-                buf.push(11);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-            }
-            Self::LiquidateObligation { liquidity_amount } => {
-                buf.push(12);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-            }
-            Self::FlashLoan { amount } => {
-                buf.push(13);
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
-            Self::DepositReserveLiquidityAndObligationCollateral { liquidity_amount } => {
-                buf.push(14);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(
+                    &packed[..packed.len() - RESERVE_CONFIG_EXTENSION_LEN - 1],
+                )
+                .unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            Self::WithdrawObligationCollateralAndRedeemReserveCollateral { collateral_amount } => {
-                buf.push(15);
-                buf.extend_from_slice(&collateral_amount.to_le_bytes());
+
+            // refresh reserve
+            {
+                let instruction = LendingInstruction::RefreshReserve;
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            Self::UpdateReserveConfig {
-                config,
-                rate_limiter_config,
-            } => {
-                buf.push(16);
-                buf.extend_from_slice(&config.optimal_utilization_rate.to_le_bytes());
-                buf.extend_from_slice(&config.max_utilization_rate.to_le_bytes());
-                buf.extend_from_slice(&config.loan_to_value_ratio.to_le_bytes());
-                buf.extend_from_slice(&config.liquidation_bonus.to_le_bytes());
-                buf.extend_from_slice(&config.liquidation_threshold.to_le_bytes());
-                buf.extend_from_slice(&config.min_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&config.optimal_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&config.max_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&config.super_max_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&config.fees.borrow_fee_wad.to_le_bytes());
-                buf.extend_from_slice(&config.fees.flash_loan_fee_wad.to_le_bytes());
-                buf.extend_from_slice(&config.fees.host_fee_percentage.to_le_bytes());
-                buf.extend_from_slice(&config.deposit_limit.to_le_bytes());
-                buf.extend_from_slice(&config.borrow_limit.to_le_bytes());
-                buf.extend_from_slice(&config.fee_receiver.to_bytes());
-                buf.extend_from_slice(&config.protocol_liquidation_fee.to_le_bytes());
-                buf.extend_from_slice(&config.protocol_take_rate.to_le_bytes());
-                buf.extend_from_slice(&config.added_borrow_weight_bps.to_le_bytes());
-                buf.extend_from_slice(&(config.reserve_type as u8).to_le_bytes());
-                buf.extend_from_slice(&config.max_liquidation_bonus.to_le_bytes());
-                buf.extend_from_slice(&config.max_liquidation_threshold.to_le_bytes());
-                buf.extend_from_slice(&config.scaled_price_offset_bps.to_le_bytes());
-                match config.extra_oracle_pubkey {
-                    Some(pubkey) => {
-                        buf.push(1);
-                        buf.extend_from_slice(pubkey.as_ref());
-                    }
-                    None => {
-                        buf.push(0);
-                    }
+
+            // deposit reserve liquidity
+            {
+                let instruction = LendingInstruction::DepositReserveLiquidity {
+                    liquidity_amount: rng.gen::<u64>(),
+                    min_collateral_out: rng.gen::<u64>(),
                 };
-                buf.extend_from_slice(&config.attributed_borrow_limit_open.to_le_bytes());
-                buf.extend_from_slice(&config.attributed_borrow_limit_close.to_le_bytes());
-                buf.extend_from_slice(&rate_limiter_config.window_duration.to_le_bytes());
-                buf.extend_from_slice(&rate_limiter_config.max_outflow.to_le_bytes());
-            }
-            Self::LiquidateObligationAndRedeemReserveCollateral { liquidity_amount } => {
-                buf.push(17);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            Self::RedeemFees {} => {
-                buf.push(18);
+
+            // redeem reserve collateral
+            {
+                let instruction = LendingInstruction::RedeemReserveCollateral {
+                    collateral_amount: rng.gen::<u64>(),
+                    min_liquidity_out: rng.gen::<u64>(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            Self::FlashBorrowReserveLiquidity { liquidity_amount } => {
-                buf.push(19);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+
+            // init obligation
+            {
+                let instruction = LendingInstruction::InitObligation { referrer: None };
+                let packed = instruction.pack();
+                assert_eq!(packed, vec![6]);
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+
+                let instruction = LendingInstruction::InitObligation {
+                    referrer: Some(Pubkey::new_unique()),
+                };
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            Self::FlashRepayReserveLiquidity {
-                liquidity_amount,
-                borrow_instruction_index,
-            } => {
-                buf.push(20);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-                buf.extend_from_slice(&borrow_instruction_index.to_le_bytes());
+
+            // refresh obligation
+            {
+                let instruction = LendingInstruction::RefreshObligation;
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            Self::ForgiveDebt { liquidity_amount } => {
-                buf.push(21);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+
+            // deposit obligation collateral
+            {
+                let instruction = LendingInstruction::DepositObligationCollateral {
+                    collateral_amount: rng.gen::<u64>(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-            // special handling for this instruction, bc the instruction is too big to deserialize
-            Self::UpdateMarketMetadata => {}
-            Self::SetObligationCloseabilityStatus { closeable } => {
-                buf.push(23);
-                buf.extend_from_slice(&(closeable as u8).to_le_bytes());
+
+            // borrow obligation liquidity
+            {
+                let instruction = LendingInstruction::BorrowObligationLiquidity {
+                    liquidity_amount: rng.gen::<u64>(),
+                    min_liquidity_received: rng.gen::<u64>(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+
+                // instruction data without a minimum is still accepted
+                let unpacked = LendingInstruction::unpack(&packed[..9]).unwrap();
+                assert_eq!(
+                    unpacked,
+                    LendingInstruction::BorrowObligationLiquidity {
+                        liquidity_amount: u64::from_le_bytes(packed[1..9].try_into().unwrap()),
+                        min_liquidity_received: 0,
+                    }
+                );
             }
-            Self::DonateToReserve { liquidity_amount } => {
-                buf.push(24);
-                buf.extend_from_slice(&liquidity_amount.to_le_bytes());
+
+            // repay obligation liquidity
+            {
+                let instruction = LendingInstruction::RepayObligationLiquidity {
+                    liquidity_amount: rng.gen::<u64>(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
             }
-        }
-        buf
-    }
-}
 
-/// Creates an 'InitLendingMarket' instruction.
-pub fn init_lending_market(
-    program_id: Pubkey,
-    owner: Pubkey,
-    quote_currency: [u8; 32],
-    lending_market_pubkey: Pubkey,
-    oracle_program_id: Pubkey,
-    switchboard_oracle_program_id: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(lending_market_pubkey, false),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-            AccountMeta::new_readonly(oracle_program_id, false),
-            AccountMeta::new_readonly(switchboard_oracle_program_id, false),
-        ],
-        data: LendingInstruction::InitLendingMarket {
-            owner,
-            quote_currency,
-        }
-        .pack(),
-    }
-}
+            // liquidate obligation
+            {
+                let instruction = LendingInstruction::LiquidateObligation {
+                    liquidity_amount: rng.gen::<u64>(),
+                };
 
-/// Creates a 'SetLendingMarketOwner' instruction.
-pub fn set_lending_market_owner_and_config(
-    program_id: Pubkey,
-    lending_market_pubkey: Pubkey,
-    lending_market_owner: Pubkey,
-    new_owner: Pubkey,
-    rate_limiter_config: RateLimiterConfig,
-    whitelisted_liquidator: Option<Pubkey>,
-    risk_authority: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_owner, true),
-        ],
-        data: LendingInstruction::SetLendingMarketOwnerAndConfig {
-            new_owner,
-            rate_limiter_config,
-            whitelisted_liquidator,
-            risk_authority,
-        }
-        .pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates an 'InitReserve' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn init_reserve(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    config: ReserveConfig,
-    source_liquidity_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    reserve_liquidity_mint_pubkey: Pubkey,
-    reserve_liquidity_supply_pubkey: Pubkey,
-    reserve_collateral_mint_pubkey: Pubkey,
-    reserve_collateral_supply_pubkey: Pubkey,
-    pyth_product_pubkey: Pubkey,
-    pyth_price_pubkey: Pubkey,
-    switchboard_feed_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    lending_market_owner_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    let mut accounts = vec![
-        AccountMeta::new(source_liquidity_pubkey, false),
-        AccountMeta::new(destination_collateral_pubkey, false),
-        AccountMeta::new(reserve_pubkey, false),
-        AccountMeta::new_readonly(reserve_liquidity_mint_pubkey, false),
-        AccountMeta::new(reserve_liquidity_supply_pubkey, false),
-        AccountMeta::new(config.fee_receiver, false),
-        AccountMeta::new(reserve_collateral_mint_pubkey, false),
-        AccountMeta::new(reserve_collateral_supply_pubkey, false),
-        AccountMeta::new_readonly(pyth_product_pubkey, false),
-        AccountMeta::new_readonly(pyth_price_pubkey, false),
-        AccountMeta::new_readonly(switchboard_feed_pubkey, false),
-        AccountMeta::new_readonly(lending_market_pubkey, false),
-        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new_readonly(sysvar::rent::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
+            // flash loan
+            {
+                let instruction = LendingInstruction::FlashLoan {
+                    amount: rng.gen::<u64>(),
+                };
 
-    if let Some(extra_oracle_pubkey) = config.extra_oracle_pubkey {
-        accounts.push(AccountMeta::new_readonly(extra_oracle_pubkey, false));
-    }
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::InitReserve {
-            liquidity_amount,
-            config,
-        }
-        .pack(),
-    }
-}
+            // deposit reserve liquidity and obligation collateral
+            {
+                let instruction =
+                    LendingInstruction::DepositReserveLiquidityAndObligationCollateral {
+                        liquidity_amount: rng.gen::<u64>(),
+                    };
 
-/// Creates a `RefreshReserve` instruction
-pub fn refresh_reserve(
-    program_id: Pubkey,
-    reserve_pubkey: Pubkey,
-    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
-    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
-    extra_oracle_pubkey: Option<Pubkey>,
-) -> Instruction {
-    let mut accounts = vec![
-        AccountMeta::new(reserve_pubkey, false),
-        AccountMeta::new_readonly(reserve_liquidity_pyth_oracle_pubkey, false),
-        AccountMeta::new_readonly(reserve_liquidity_switchboard_oracle_pubkey, false),
-    ];
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    if let Some(extra_oracle_pubkey) = extra_oracle_pubkey {
-        accounts.push(AccountMeta::new_readonly(extra_oracle_pubkey, false));
-    }
+            // withdraw obligation collateral and redeem reserve collateral
+            {
+                let instruction =
+                    LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
+                        collateral_amount: rng.gen::<u64>(),
+                    };
 
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::RefreshReserve.pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a 'DepositReserveLiquidity' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn deposit_reserve_liquidity(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    reserve_liquidity_supply_pubkey: Pubkey,
-    reserve_collateral_mint_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_collateral_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new(reserve_collateral_mint_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::DepositReserveLiquidity { liquidity_amount }.pack(),
-    }
-}
+            // update reserve config
+            {
+                let instruction = LendingInstruction::UpdateReserveConfig {
+                    config: ReserveConfig {
+                        optimal_utilization_rate: rng.gen::<u8>(),
+                        max_utilization_rate: rng.gen::<u8>(),
+                        loan_to_value_ratio: rng.gen::<u8>(),
+                        liquidation_bonus: rng.gen::<u8>(),
+                        max_liquidation_bonus: rng.gen::<u8>(),
+                        liquidation_threshold: rng.gen::<u8>(),
+                        max_liquidation_threshold: rng.gen::<u8>(),
+                        min_borrow_rate: rng.gen::<u8>(),
+                        optimal_borrow_rate: rng.gen::<u8>(),
+                        max_borrow_rate: rng.gen::<u8>(),
+                        super_max_borrow_rate: rng.gen::<u64>(),
+                        super_optimal_utilization_rate: rng.gen::<u8>(),
+                        super_optimal_borrow_rate: rng.gen::<u8>(),
+                        min_borrow_value: rng.gen(),
+                        max_collateral_value_contribution: rng.gen(),
+                        min_price: rng.gen(),
+                        max_price: rng.gen(),
+                        liquidation_auction: rng.gen(),
+                        protocol_liquidation_fee_in_liquidity: rng.gen(),
+                        fees: ReserveFees {
+                            borrow_fee_wad: rng.gen::<u64>(),
+                            flash_loan_fee_wad: rng.gen::<u64>(),
+                            host_fee_percentage: rng.gen::<u8>(),
+                        },
+                        deposit_limit: rng.gen::<u64>(),
+                        borrow_limit: rng.gen::<u64>(),
+                        fee_receiver: Pubkey::new_unique(),
+                        protocol_liquidation_fee: rng.gen::<u8>(),
+                        protocol_take_rate: rng.gen::<u8>(),
+                        added_borrow_weight_bps: rng.gen::<u64>(),
+                        reserve_type: ReserveType::from_u8(rng.gen::<u8>() % 2).unwrap(),
+                        scaled_price_offset_bps: rng.gen(),
+                        extra_oracle_pubkey: if rng.gen_bool(0.5) {
+                            Some(Pubkey::new_unique())
+                        } else {
+                            None
+                        },
+                        attributed_borrow_limit_open: rng.gen(),
+                        attributed_borrow_limit_close: rng.gen(),
+                        max_close_factor_bps: rng.gen(),
+                        liquidation_price_band_bps: rng.gen(),
+                        max_oracle_staleness_secs: rng.gen(),
+                        max_oracle_confidence_bps: rng.gen(),
+                        max_single_borrower_share_bps: rng.gen(),
+                        deposits_paused: rng.gen(),
+                        borrows_paused: rng.gen(),
+                        withdrawals_paused: rng.gen(),
+                        asset_category: rng.gen(),
+                        borrow_rate_cap: rng.gen(),
+                        rate_cap_premium_bps: rng.gen(),
+                        max_flash_loan_amount: rng.gen(),
+                    },
+                    rate_limiter_config: RateLimiterConfig {
+                        window_duration: rng.gen::<u64>(),
+                        max_outflow: rng.gen::<u64>(),
+                    },
+                };
 
-/// Creates a 'RedeemReserveCollateral' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn redeem_reserve_collateral(
-    program_id: Pubkey,
-    collateral_amount: u64,
-    source_collateral_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    reserve_collateral_mint_pubkey: Pubkey,
-    reserve_liquidity_supply_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_collateral_pubkey, false),
-            AccountMeta::new(destination_liquidity_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new(reserve_collateral_mint_pubkey, false),
-            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::RedeemReserveCollateral { collateral_amount }.pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
 
-/// Creates an 'InitObligation' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn init_obligation(
-    program_id: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    obligation_owner_pubkey: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(obligation_owner_pubkey, true),
-            AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::InitObligation.pack(),
-    }
-}
+                // instruction data without the appended config fields is still accepted
+                let (config, rate_limiter_config) = match instruction {
+                    LendingInstruction::UpdateReserveConfig {
+                        config,
+                        rate_limiter_config,
+                    } => (config, rate_limiter_config),
+                    _ => unreachable!(),
+                };
+                let instruction = LendingInstruction::UpdateReserveConfig {
+                    config: ReserveConfig {
+                        max_close_factor_bps: 0,
+                        liquidation_price_band_bps: 0,
+                        max_oracle_staleness_secs: 0,
+                        max_oracle_confidence_bps: 0,
+                        max_single_borrower_share_bps: 0,
+                        deposits_paused: false,
+                        borrows_paused: false,
+                        withdrawals_paused: false,
+                        super_optimal_utilization_rate: 0,
+                        super_optimal_borrow_rate: 0,
+                        min_borrow_value: 0,
+                        max_collateral_value_contribution: 0,
+                        min_price: 0,
+                        max_price: 0,
+                        liquidation_auction: false,
+                        protocol_liquidation_fee_in_liquidity: false,
+                        asset_category: 0,
+                        borrow_rate_cap: 0,
+                        rate_cap_premium_bps: 0,
+                        max_flash_loan_amount: 0,
+                        ..config
+                    },
+                    rate_limiter_config,
+                };
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(
+                    &packed[..packed.len() - RESERVE_CONFIG_EXTENSION_LEN],
+                )
+                .unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a 'RefreshObligation' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn refresh_obligation(
-    program_id: Pubkey,
-    obligation_pubkey: Pubkey,
-    reserve_pubkeys: Vec<Pubkey>,
-) -> Instruction {
-    let mut accounts = vec![AccountMeta::new(obligation_pubkey, false)];
-    accounts.extend(
-        reserve_pubkeys
-            .into_iter()
-            .map(|pubkey| AccountMeta::new(pubkey, false)),
-    );
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::RefreshObligation.pack(),
-    }
-}
+            // liquidate obligation and redeem reserve collateral
+            {
+                let instruction =
+                    LendingInstruction::LiquidateObligationAndRedeemReserveCollateral {
+                        liquidity_amount: rng.gen::<u64>(),
+                    };
 
-/// Creates a 'DepositObligationCollateral' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn deposit_obligation_collateral(
-    program_id: Pubkey,
-    collateral_amount: u64,
-    source_collateral_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    deposit_reserve_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    obligation_owner_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_collateral_pubkey, false),
-            AccountMeta::new(destination_collateral_pubkey, false),
-            AccountMeta::new(deposit_reserve_pubkey, false),
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(obligation_owner_pubkey, true),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::DepositObligationCollateral { collateral_amount }.pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a 'DepositReserveLiquidityAndObligationCollateral' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn deposit_reserve_liquidity_and_obligation_collateral(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    user_collateral_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    reserve_liquidity_supply_pubkey: Pubkey,
-    reserve_collateral_mint_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    destination_deposit_collateral_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    obligation_owner_pubkey: Pubkey,
-    reserve_liquidity_pyth_oracle_pubkey: Pubkey,
-    reserve_liquidity_switchboard_oracle_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(user_collateral_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new(reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new(reserve_collateral_mint_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-            AccountMeta::new(destination_deposit_collateral_pubkey, false),
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new(obligation_owner_pubkey, true),
-            AccountMeta::new_readonly(reserve_liquidity_pyth_oracle_pubkey, false),
-            AccountMeta::new_readonly(reserve_liquidity_switchboard_oracle_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::DepositReserveLiquidityAndObligationCollateral {
-            liquidity_amount,
-        }
-        .pack(),
-    }
-}
+            // redeem fees
+            {
+                let instruction = LendingInstruction::RedeemFees;
 
-/// Creates a 'WithdrawObligationCollateralAndRedeemReserveCollateral' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral(
-    program_id: Pubkey,
-    collateral_amount: u64,
-    source_collateral_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    withdraw_reserve_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    reserve_collateral_mint_pubkey: Pubkey,
-    reserve_liquidity_supply_pubkey: Pubkey,
-    obligation_owner_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    collateral_reserves: Vec<Pubkey>,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    let mut accounts = vec![
-        AccountMeta::new(source_collateral_pubkey, false),
-        AccountMeta::new(destination_collateral_pubkey, false),
-        AccountMeta::new(withdraw_reserve_pubkey, false),
-        AccountMeta::new(obligation_pubkey, false),
-        AccountMeta::new(lending_market_pubkey, false),
-        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-        AccountMeta::new(destination_liquidity_pubkey, false),
-        AccountMeta::new(reserve_collateral_mint_pubkey, false),
-        AccountMeta::new(reserve_liquidity_supply_pubkey, false),
-        AccountMeta::new_readonly(obligation_owner_pubkey, true),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
+            // flash borrow reserve liquidity
+            {
+                let instruction = LendingInstruction::FlashBorrowReserveLiquidity {
+                    liquidity_amount: rng.gen::<u64>(),
+                };
 
-    accounts.extend(
-        collateral_reserves
-            .into_iter()
-            .map(|pubkey| AccountMeta::new(pubkey, false)),
-    );
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
-            collateral_amount,
-        }
-        .pack(),
-    }
-}
+            // flash repay reserve liquidity
+            {
+                let instruction = LendingInstruction::FlashRepayReserveLiquidity {
+                    liquidity_amount: rng.gen::<u64>(),
+                    borrow_instruction_index: rng.gen::<u8>(),
+                };
 
-/// Creates a 'WithdrawObligationCollateral' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn withdraw_obligation_collateral(
-    program_id: Pubkey,
-    collateral_amount: u64,
-    source_collateral_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    withdraw_reserve_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    obligation_owner_pubkey: Pubkey,
-    collateral_reserves: Vec<Pubkey>,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    let mut accounts = vec![
-        AccountMeta::new(source_collateral_pubkey, false),
-        AccountMeta::new(destination_collateral_pubkey, false),
-        AccountMeta::new_readonly(withdraw_reserve_pubkey, false),
-        AccountMeta::new(obligation_pubkey, false),
-        AccountMeta::new_readonly(lending_market_pubkey, false),
-        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-        AccountMeta::new_readonly(obligation_owner_pubkey, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
+            // forgive debt
+            {
+                let instruction = LendingInstruction::ForgiveDebt {
+                    liquidity_amount: rng.gen::<u64>(),
+                };
 
-    accounts.extend(
-        collateral_reserves
-            .into_iter()
-            .map(|pubkey| AccountMeta::new(pubkey, false)),
-    );
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::WithdrawObligationCollateral { collateral_amount }.pack(),
-    }
-}
+            // MarkObligationAsClosable
+            {
+                let instruction = LendingInstruction::SetObligationCloseabilityStatus {
+                    closeable: rng.gen(),
+                };
 
-/// Creates a 'BorrowObligationLiquidity' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn borrow_obligation_liquidity(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    borrow_reserve_pubkey: Pubkey,
-    borrow_reserve_liquidity_fee_receiver_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    obligation_owner_pubkey: Pubkey,
-    collateral_reserves: Vec<Pubkey>,
-    host_fee_receiver_pubkey: Option<Pubkey>,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    let mut accounts = vec![
-        AccountMeta::new(source_liquidity_pubkey, false),
-        AccountMeta::new(destination_liquidity_pubkey, false),
-        AccountMeta::new(borrow_reserve_pubkey, false),
-        AccountMeta::new(borrow_reserve_liquidity_fee_receiver_pubkey, false),
-        AccountMeta::new(obligation_pubkey, false),
-        AccountMeta::new(lending_market_pubkey, false),
-        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-        AccountMeta::new_readonly(obligation_owner_pubkey, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
-    for collateral_reserve in collateral_reserves {
-        accounts.push(AccountMeta::new(collateral_reserve, false));
-    }
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    if let Some(host_fee_receiver_pubkey) = host_fee_receiver_pubkey {
-        accounts.push(AccountMeta::new(host_fee_receiver_pubkey, false));
-    }
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::BorrowObligationLiquidity { liquidity_amount }.pack(),
-    }
-}
+            // InitObligationArchive
+            {
+                let instruction = LendingInstruction::InitObligationArchive {
+                    max_depth: rng.gen::<u32>(),
+                    max_buffer_size: rng.gen::<u32>(),
+                };
 
-/// Creates a `RepayObligationLiquidity` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn repay_obligation_liquidity(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    repay_reserve_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_liquidity_pubkey, false),
-            AccountMeta::new(repay_reserve_pubkey, false),
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::RepayObligationLiquidity { liquidity_amount }.pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a `LiquidateObligation` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn liquidate_obligation(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    repay_reserve_pubkey: Pubkey,
-    repay_reserve_liquidity_supply_pubkey: Pubkey,
-    withdraw_reserve_pubkey: Pubkey,
-    withdraw_reserve_collateral_supply_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_collateral_pubkey, false),
-            AccountMeta::new(repay_reserve_pubkey, false),
-            AccountMeta::new(repay_reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new_readonly(withdraw_reserve_pubkey, false),
-            AccountMeta::new(withdraw_reserve_collateral_supply_pubkey, false),
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::LiquidateObligation { liquidity_amount }.pack(),
-    }
-}
+            // ArchiveObligation
+            {
+                let instruction = LendingInstruction::ArchiveObligation;
 
-/// Creates an 'UpdateReserveConfig' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn update_reserve_config(
-    program_id: Pubkey,
-    config: ReserveConfig,
-    rate_limiter_config: RateLimiterConfig,
-    reserve_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    lending_market_owner_pubkey: Pubkey,
-    pyth_product_pubkey: Pubkey,
-    pyth_price_pubkey: Pubkey,
-    switchboard_feed_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    let mut accounts = vec![
-        AccountMeta::new(reserve_pubkey, false),
-        AccountMeta::new_readonly(lending_market_pubkey, false),
-        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-        AccountMeta::new_readonly(lending_market_owner_pubkey, true),
-        AccountMeta::new_readonly(pyth_product_pubkey, false),
-        AccountMeta::new_readonly(pyth_price_pubkey, false),
-        AccountMeta::new_readonly(switchboard_feed_pubkey, false),
-    ];
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    if let Some(extra_oracle_pubkey) = config.extra_oracle_pubkey {
-        accounts.push(AccountMeta::new_readonly(extra_oracle_pubkey, false));
-    }
+            // RotateReserveCollateralSupply
+            {
+                let instruction = LendingInstruction::RotateReserveCollateralSupply;
 
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::UpdateReserveConfig {
-            config,
-            rate_limiter_config,
-        }
-        .pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a `LiquidateObligationAndRedeemReserveCollateral` instruction
-#[allow(clippy::too_many_arguments)]
-pub fn liquidate_obligation_and_redeem_reserve_collateral(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_collateral_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    repay_reserve_pubkey: Pubkey,
-    repay_reserve_liquidity_supply_pubkey: Pubkey,
-    withdraw_reserve_pubkey: Pubkey,
-    withdraw_reserve_collateral_mint_pubkey: Pubkey,
-    withdraw_reserve_collateral_supply_pubkey: Pubkey,
-    withdraw_reserve_liquidity_supply_pubkey: Pubkey,
-    withdraw_reserve_liquidity_fee_receiver_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_collateral_pubkey, false),
-            AccountMeta::new(destination_liquidity_pubkey, false),
-            AccountMeta::new(repay_reserve_pubkey, false),
-            AccountMeta::new(repay_reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new(withdraw_reserve_pubkey, false),
-            AccountMeta::new(withdraw_reserve_collateral_mint_pubkey, false),
-            AccountMeta::new(withdraw_reserve_collateral_supply_pubkey, false),
-            AccountMeta::new(withdraw_reserve_liquidity_supply_pubkey, false),
-            AccountMeta::new(withdraw_reserve_liquidity_fee_receiver_pubkey, false),
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::LiquidateObligationAndRedeemReserveCollateral {
-            liquidity_amount,
-        }
-        .pack(),
-    }
-}
+            // RotateReserveFeeReceiver
+            {
+                let instruction = LendingInstruction::RotateReserveFeeReceiver;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetDefaultHostFeeReceiver
+            {
+                let instruction = LendingInstruction::SetDefaultHostFeeReceiver;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a `RedeemFees` instruction
-pub fn redeem_fees(
-    program_id: Pubkey,
-    reserve_pubkey: Pubkey,
-    reserve_liquidity_fee_receiver_pubkey: Pubkey,
-    reserve_supply_liquidity_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
-    let accounts = vec![
-        AccountMeta::new(reserve_pubkey, false),
-        AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
-        AccountMeta::new(reserve_supply_liquidity_pubkey, false),
-        AccountMeta::new_readonly(lending_market_pubkey, false),
-        AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
-    Instruction {
-        program_id,
-        accounts,
-        data: LendingInstruction::RedeemFees.pack(),
-    }
-}
+            // RepayObligationLiquidityMany
+            {
+                let len = rng.gen_range(0..=MAX_OBLIGATION_RESERVES);
+                let instruction = LendingInstruction::RepayObligationLiquidityMany {
+                    liquidity_amounts: (0..len).map(|_| rng.gen()).collect(),
+                };
 
-/// Creates a 'FlashBorrowReserveLiquidity' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn flash_borrow_reserve_liquidity(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-) -> Instruction {
-    let (lending_market_authority_pubkey, _bump_seed) = Pubkey::find_program_address(
-        &[&lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
-        &program_id,
-    );
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_liquidity_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_authority_pubkey, false),
-            AccountMeta::new_readonly(sysvar::instructions::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::FlashBorrowReserveLiquidity { liquidity_amount }.pack(),
-    }
-}
+            // SetReserveUtilizationKillSwitch
+            {
+                let instruction = LendingInstruction::SetReserveUtilizationKillSwitch {
+                    emergency_utilization_threshold: rng.gen(),
+                    emergency_utilization_slots: rng.gen(),
+                };
 
-/// Creates a 'FlashRepayReserveLiquidity' instruction.
-#[allow(clippy::too_many_arguments)]
-pub fn flash_repay_reserve_liquidity(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    borrow_instruction_index: u8,
-    source_liquidity_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    reserve_liquidity_fee_receiver_pubkey: Pubkey,
-    host_fee_receiver_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_liquidity_pubkey, false),
-            AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
-            AccountMeta::new(host_fee_receiver_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(sysvar::instructions::id(), false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::FlashRepayReserveLiquidity {
-            liquidity_amount,
-            borrow_instruction_index,
-        }
-        .pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a `ForgiveDebt` instruction
-pub fn forgive_debt(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    reserve_pubkey: Pubkey,
-    obligation_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    lending_market_owner: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(lending_market_owner, true),
-        ],
-        data: LendingInstruction::ForgiveDebt { liquidity_amount }.pack(),
-    }
-}
+            // CrankCloseEmptyObligation
+            {
+                let instruction = LendingInstruction::CrankCloseEmptyObligation;
 
-/// Creates a `UpdateMarketMetadata` instruction
-pub fn update_market_metadata(
-    program_id: Pubkey,
-    mut metadata: LendingMarketMetadata,
-    lending_market_pubkey: Pubkey,
-    lending_market_owner: Pubkey,
-) -> Instruction {
-    let (lending_market_metadata_pubkey, bump_seed) = Pubkey::find_program_address(
-        &[
-            &lending_market_pubkey.to_bytes()[..PUBKEY_BYTES],
-            b"MetaData",
-        ],
-        &program_id,
-    );
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    metadata.bump_seed = bump_seed;
+            // SetObligationBorrowLock
+            {
+                let instruction = LendingInstruction::SetObligationBorrowLock {
+                    borrows_locked: rng.gen(),
+                };
 
-    let mut data = [0u8; 1 + std::mem::size_of::<LendingMarketMetadata>()];
-    data[0] = 22;
-    data[1..].copy_from_slice(bytes_of(&metadata));
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new(lending_market_owner, true),
-            AccountMeta::new(lending_market_metadata_pubkey, false),
-            AccountMeta::new_readonly(system_program::id(), false),
-        ],
-        data: data.to_vec(),
-    }
-}
+            // SetEmergencyPrice
+            {
+                let instruction = LendingInstruction::SetEmergencyPrice {
+                    price: Decimal::from_scaled_val(rng.gen()),
+                    max_duration_slots: rng.gen(),
+                };
 
-/// Creates a `MarkObligationAsClosable` instruction
-pub fn set_obligation_closeability_status(
-    program_id: Pubkey,
-    obligation_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    risk_authority: Pubkey,
-    closeable: bool,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(obligation_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(reserve_pubkey, false),
-            AccountMeta::new_readonly(risk_authority, true),
-        ],
-        data: LendingInstruction::SetObligationCloseabilityStatus { closeable }.pack(),
-    }
-}
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-/// Creates a `DonateToReserve` instruction
-pub fn donate_to_reserve(
-    program_id: Pubkey,
-    liquidity_amount: u64,
-    source_liquidity_pubkey: Pubkey,
-    destination_liquidity_pubkey: Pubkey,
-    reserve_pubkey: Pubkey,
-    lending_market_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-) -> Instruction {
-    Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(source_liquidity_pubkey, false),
-            AccountMeta::new(destination_liquidity_pubkey, false),
-            AccountMeta::new(reserve_pubkey, false),
-            AccountMeta::new_readonly(lending_market_pubkey, false),
-            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
-        data: LendingInstruction::DonateToReserve { liquidity_amount }.pack(),
-    }
-}
+            // SetRewardRule
+            {
+                let instruction = LendingInstruction::SetRewardRule {
+                    side: RewardSide::from_u8(rng.gen_range(0..2)).unwrap(),
+                    emission_per_slot: rng.gen(),
+                    start_slot: rng.gen(),
+                    end_slot: rng.gen(),
+                };
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use rand::Rng;
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // UpdateRewards
+            {
+                let instruction = LendingInstruction::UpdateRewards;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // ClaimRewards
+            {
+                let instruction = LendingInstruction::ClaimRewards;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetAssetCategory
+            {
+                let instruction = LendingInstruction::SetAssetCategory {
+                    asset_category: rng.gen(),
+                    loan_to_value_ratio: rng.gen(),
+                    liquidation_threshold: rng.gen(),
+                    max_liquidation_threshold: rng.gen(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetRiskAuthority
+            {
+                let instruction = LendingInstruction::SetRiskAuthority {
+                    risk_authority: Pubkey::new_unique(),
+                };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetDustBorrowValue
+            {
+                let instruction = LendingInstruction::SetDustBorrowValue {
+                    dust_borrow_value: rng.gen(),
+                };
 
-    #[test]
-    fn pack_and_unpack_instructions() {
-        let mut rng = rand::thread_rng();
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
 
-        for _ in 0..100 {
+            // SetMaxObligationsPerOwner
             {
-                let instruction = LendingInstruction::InitLendingMarket {
-                    owner: Pubkey::new_unique(),
-                    quote_currency: [rng.gen::<u8>(); 32],
+                let instruction = LendingInstruction::SetMaxObligationsPerOwner {
+                    max_obligations_per_owner: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -1918,20 +6209,15 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // set lending market owner and config
+            // SetProtocolFeeConfig
             {
-                let instruction = LendingInstruction::SetLendingMarketOwnerAndConfig {
-                    new_owner: Pubkey::new_unique(),
+                let instruction = LendingInstruction::SetProtocolFeeConfig {
+                    fee_authority: Pubkey::new_unique(),
+                    treasury: Pubkey::new_unique(),
                     rate_limiter_config: RateLimiterConfig {
                         window_duration: rng.gen::<u64>(),
                         max_outflow: rng.gen::<u64>(),
                     },
-                    whitelisted_liquidator: if rng.gen_bool(0.5) {
-                        None
-                    } else {
-                        Some(Pubkey::new_unique())
-                    },
-                    risk_authority: Pubkey::new_unique(),
                 };
 
                 let packed = instruction.pack();
@@ -1939,42 +6225,10 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
+            // WithdrawProtocolFees
             {
-                let instruction = LendingInstruction::InitReserve {
-                    liquidity_amount: rng.gen::<u64>(),
-                    config: ReserveConfig {
-                        optimal_utilization_rate: rng.gen::<u8>(),
-                        max_utilization_rate: rng.gen::<u8>(),
-                        loan_to_value_ratio: rng.gen::<u8>(),
-                        liquidation_bonus: rng.gen::<u8>(),
-                        max_liquidation_bonus: rng.gen::<u8>(),
-                        liquidation_threshold: rng.gen::<u8>(),
-                        max_liquidation_threshold: rng.gen::<u8>(),
-                        min_borrow_rate: rng.gen::<u8>(),
-                        optimal_borrow_rate: rng.gen::<u8>(),
-                        max_borrow_rate: rng.gen::<u8>(),
-                        super_max_borrow_rate: rng.gen::<u64>(),
-                        fees: ReserveFees {
-                            borrow_fee_wad: rng.gen::<u64>(),
-                            flash_loan_fee_wad: rng.gen::<u64>(),
-                            host_fee_percentage: rng.gen::<u8>(),
-                        },
-                        deposit_limit: rng.gen::<u64>(),
-                        borrow_limit: rng.gen::<u64>(),
-                        fee_receiver: Pubkey::new_unique(),
-                        protocol_liquidation_fee: rng.gen::<u8>(),
-                        protocol_take_rate: rng.gen::<u8>(),
-                        added_borrow_weight_bps: rng.gen::<u64>(),
-                        reserve_type: ReserveType::from_u8(rng.gen::<u8>() % 2).unwrap(),
-                        scaled_price_offset_bps: rng.gen(),
-                        extra_oracle_pubkey: if rng.gen_bool(0.5) {
-                            None
-                        } else {
-                            Some(Pubkey::new_unique())
-                        },
-                        attributed_borrow_limit_open: rng.gen(),
-                        attributed_borrow_limit_close: rng.gen(),
-                    },
+                let instruction = LendingInstruction::WithdrawProtocolFees {
+                    liquidity_amount: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -1982,18 +6236,21 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // refresh reserve
+            // SetObligationReferrer
             {
-                let instruction = LendingInstruction::RefreshReserve;
+                let instruction = LendingInstruction::SetObligationReferrer {
+                    referrer: Pubkey::new_unique(),
+                };
+
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // deposit reserve liquidity
+            // CompoundObligationCollateral
             {
-                let instruction = LendingInstruction::DepositReserveLiquidity {
-                    liquidity_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::CompoundObligationCollateral {
+                    collateral_amount: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2001,10 +6258,10 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // redeem reserve collateral
+            // RepayObligationLiquidityWithCollateral
             {
-                let instruction = LendingInstruction::RedeemReserveCollateral {
-                    collateral_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::RepayObligationLiquidityWithCollateral {
+                    liquidity_amount: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2012,26 +6269,49 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // init obligation
+            // InitReserveRateStats
             {
-                let instruction = LendingInstruction::InitObligation;
+                let instruction = LendingInstruction::InitReserveRateStats;
+
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // refresh obligation
+            // InitReserveActivity
             {
-                let instruction = LendingInstruction::RefreshObligation;
+                let instruction = LendingInstruction::InitReserveActivity;
+
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // deposit obligation collateral
+            // BorrowObligationLiquidityWithWalletCollateral
             {
-                let instruction = LendingInstruction::DepositObligationCollateral {
-                    collateral_amount: rng.gen::<u64>(),
+                let instruction =
+                    LendingInstruction::BorrowObligationLiquidityWithWalletCollateral {
+                        liquidity_amount: rng.gen(),
+                    };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // CloseReserve
+            {
+                let instruction = LendingInstruction::CloseReserve;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // RedeemFeesIfAbove
+            {
+                let instruction = LendingInstruction::RedeemFeesIfAbove {
+                    min_amount: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2039,10 +6319,10 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // borrow obligation liquidity
+            // SetObligationMaxOutflow
             {
-                let instruction = LendingInstruction::BorrowObligationLiquidity {
-                    liquidity_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::SetObligationMaxOutflow {
+                    obligation_max_outflow: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2050,10 +6330,19 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // repay obligation liquidity
+            // ResetRateLimiter
             {
-                let instruction = LendingInstruction::RepayObligationLiquidity {
-                    liquidity_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::ResetRateLimiter;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetObligationBorrowCap
+            {
+                let instruction = LendingInstruction::SetObligationBorrowCap {
+                    borrow_cap: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2061,10 +6350,11 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // liquidate obligation
+            // DelegateBorrowAuthority
             {
-                let instruction = LendingInstruction::LiquidateObligation {
-                    liquidity_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::DelegateBorrowAuthority {
+                    approved_borrower: Pubkey::new_unique(),
+                    delegated_value: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2072,10 +6362,10 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // flash loan
+            // SetReservePairBorrowWeight
             {
-                let instruction = LendingInstruction::FlashLoan {
-                    amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::SetReservePairBorrowWeight {
+                    borrow_weight_bps: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2083,70 +6373,35 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // deposit reserve liquidity and obligation collateral
+            // SetWhitelistRequired
             {
-                let instruction =
-                    LendingInstruction::DepositReserveLiquidityAndObligationCollateral {
-                        liquidity_amount: rng.gen::<u64>(),
-                    };
+                let instruction = LendingInstruction::SetWhitelistRequired {
+                    whitelist_required: rng.gen(),
+                };
 
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // withdraw obligation collateral and redeem reserve collateral
+            // UpdateMarketMember
             {
-                let instruction =
-                    LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
-                        collateral_amount: rng.gen::<u64>(),
-                    };
+                let instruction = LendingInstruction::UpdateMarketMember {
+                    is_member: rng.gen(),
+                };
 
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // update reserve config
+            // SetLiquidatorWhitelist
             {
-                let instruction = LendingInstruction::UpdateReserveConfig {
-                    config: ReserveConfig {
-                        optimal_utilization_rate: rng.gen::<u8>(),
-                        max_utilization_rate: rng.gen::<u8>(),
-                        loan_to_value_ratio: rng.gen::<u8>(),
-                        liquidation_bonus: rng.gen::<u8>(),
-                        max_liquidation_bonus: rng.gen::<u8>(),
-                        liquidation_threshold: rng.gen::<u8>(),
-                        max_liquidation_threshold: rng.gen::<u8>(),
-                        min_borrow_rate: rng.gen::<u8>(),
-                        optimal_borrow_rate: rng.gen::<u8>(),
-                        max_borrow_rate: rng.gen::<u8>(),
-                        super_max_borrow_rate: rng.gen::<u64>(),
-                        fees: ReserveFees {
-                            borrow_fee_wad: rng.gen::<u64>(),
-                            flash_loan_fee_wad: rng.gen::<u64>(),
-                            host_fee_percentage: rng.gen::<u8>(),
-                        },
-                        deposit_limit: rng.gen::<u64>(),
-                        borrow_limit: rng.gen::<u64>(),
-                        fee_receiver: Pubkey::new_unique(),
-                        protocol_liquidation_fee: rng.gen::<u8>(),
-                        protocol_take_rate: rng.gen::<u8>(),
-                        added_borrow_weight_bps: rng.gen::<u64>(),
-                        reserve_type: ReserveType::from_u8(rng.gen::<u8>() % 2).unwrap(),
-                        scaled_price_offset_bps: rng.gen(),
-                        extra_oracle_pubkey: if rng.gen_bool(0.5) {
-                            Some(Pubkey::new_unique())
-                        } else {
-                            None
-                        },
-                        attributed_borrow_limit_open: rng.gen(),
-                        attributed_borrow_limit_close: rng.gen(),
-                    },
-                    rate_limiter_config: RateLimiterConfig {
-                        window_duration: rng.gen::<u64>(),
-                        max_outflow: rng.gen::<u64>(),
-                    },
+                let instruction = LendingInstruction::SetLiquidatorWhitelist {
+                    protected_window_slots: rng.gen(),
+                    liquidators: (0..rng.gen_range(0..=MAX_WHITELISTED_LIQUIDATORS))
+                        .map(|_| Pubkey::new_unique())
+                        .collect(),
                 };
 
                 let packed = instruction.pack();
@@ -2154,31 +6409,30 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // liquidate obligation and redeem reserve collateral
+            // SetInsuranceFee
             {
-                let instruction =
-                    LendingInstruction::LiquidateObligationAndRedeemReserveCollateral {
-                        liquidity_amount: rng.gen::<u64>(),
-                    };
+                let instruction = LendingInstruction::SetInsuranceFee {
+                    insurance_fee_bps: rng.gen(),
+                };
 
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // redeem fees
+            // InitInsuranceFund
             {
-                let instruction = LendingInstruction::RedeemFees;
+                let instruction = LendingInstruction::InitInsuranceFund;
 
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
 
-            // flash borrow reserve liquidity
+            // CoverBadDebt
             {
-                let instruction = LendingInstruction::FlashBorrowReserveLiquidity {
-                    liquidity_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::CoverBadDebt {
+                    liquidity_amount: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2186,11 +6440,10 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // flash repay reserve liquidity
+            // WithdrawObligationLiquidityExact
             {
-                let instruction = LendingInstruction::FlashRepayReserveLiquidity {
-                    liquidity_amount: rng.gen::<u64>(),
-                    borrow_instruction_index: rng.gen::<u8>(),
+                let instruction = LendingInstruction::WithdrawObligationLiquidityExact {
+                    liquidity_amount: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2198,10 +6451,10 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // forgive debt
+            // SetMaxPriceAge
             {
-                let instruction = LendingInstruction::ForgiveDebt {
-                    liquidity_amount: rng.gen::<u64>(),
+                let instruction = LendingInstruction::SetMaxPriceAge {
+                    max_price_age_slots: rng.gen(),
                 };
 
                 let packed = instruction.pack();
@@ -2209,16 +6462,269 @@ mod test {
                 assert_eq!(instruction, unpacked);
             }
 
-            // MarkObligationAsClosable
+            // BuyRateCap
             {
-                let instruction = LendingInstruction::SetObligationCloseabilityStatus {
-                    closeable: rng.gen(),
+                let instruction = LendingInstruction::BuyRateCap;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // ClaimRateCapCredit
+            {
+                let instruction = LendingInstruction::ClaimRateCapCredit;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetReserveSpreadFeeReceiver
+            {
+                let instruction = LendingInstruction::SetReserveSpreadFeeReceiver;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // MigrateAccount
+            {
+                let instruction = LendingInstruction::MigrateAccount;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // SetReserveTertiaryOracle
+            {
+                let instruction = LendingInstruction::SetReserveTertiaryOracle;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // RefreshCTokenPrice
+            {
+                let instruction = LendingInstruction::RefreshCTokenPrice;
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // DevnetMintTestTokens
+            #[cfg(feature = "devnet-utils")]
+            {
+                let instruction = LendingInstruction::DevnetMintTestTokens { amount: rng.gen() };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
+
+            // DevnetSetPrice
+            #[cfg(feature = "devnet-utils")]
+            {
+                let instruction = LendingInstruction::DevnetSetPrice {
+                    price: Decimal::from_scaled_val(rng.gen()),
                 };
 
                 let packed = instruction.pack();
                 let unpacked = LendingInstruction::unpack(&packed).unwrap();
                 assert_eq!(instruction, unpacked);
             }
+
+            // DepositReserveLiquidityAndObligationCollateralAndBorrow
+            {
+                let instruction =
+                    LendingInstruction::DepositReserveLiquidityAndObligationCollateralAndBorrow {
+                        deposit_liquidity_amount: rng.gen(),
+                        borrow_liquidity_amount: rng.gen(),
+                    };
+
+                let packed = instruction.pack();
+                let unpacked = LendingInstruction::unpack(&packed).unwrap();
+                assert_eq!(instruction, unpacked);
+            }
         }
     }
+
+    #[test]
+    fn leverage_loop_instructions() {
+        use crate::state::{ObligationCollateral, ObligationLiquidity};
+
+        let program_id = Pubkey::new_unique();
+        let deposit_reserve_pubkey = Pubkey::new_unique();
+        let borrow_reserve_pubkey = Pubkey::new_unique();
+        let obligation_pubkey = Pubkey::new_unique();
+        let obligation = Obligation {
+            lending_market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            borrows: vec![ObligationLiquidity::new(
+                borrow_reserve_pubkey,
+                Decimal::one(),
+            )],
+            deposits: vec![ObligationCollateral::new(Pubkey::new_unique())],
+            ..Obligation::default()
+        };
+        let swap_instruction = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![],
+            data: vec![],
+        };
+
+        let instructions = leverage_loop(
+            program_id,
+            100,
+            200,
+            101,
+            swap_instruction.clone(),
+            2,
+            obligation_pubkey,
+            &obligation,
+            deposit_reserve_pubkey,
+            &Reserve::default(),
+            borrow_reserve_pubkey,
+            &Reserve::default(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(instructions[1], swap_instruction);
+        assert_eq!(
+            LendingInstruction::unpack(&instructions[2].data).unwrap(),
+            LendingInstruction::RefreshReserve
+        );
+        assert_eq!(instructions[2].accounts[0].pubkey, borrow_reserve_pubkey);
+
+        assert_eq!(
+            LendingInstruction::unpack(&instructions[0].data).unwrap(),
+            LendingInstruction::FlashBorrowReserveLiquidity {
+                liquidity_amount: 100
+            }
+        );
+        assert_eq!(
+            LendingInstruction::unpack(&instructions[3].data).unwrap(),
+            LendingInstruction::DepositReserveLiquidityAndObligationCollateralAndBorrow {
+                deposit_liquidity_amount: 200,
+                borrow_liquidity_amount: 101,
+            }
+        );
+        assert_eq!(
+            LendingInstruction::unpack(&instructions[4].data).unwrap(),
+            LendingInstruction::FlashRepayReserveLiquidity {
+                liquidity_amount: 100,
+                borrow_instruction_index: 2,
+            }
+        );
+
+        // the new deposit reserve goes after the existing deposits and before the borrows
+        let obligation_reserves: Vec<Pubkey> = instructions[3].accounts[16..19]
+            .iter()
+            .map(|account| account.pubkey)
+            .collect();
+        assert_eq!(
+            obligation_reserves,
+            vec![
+                obligation.deposits[0].deposit_reserve,
+                deposit_reserve_pubkey,
+                borrow_reserve_pubkey
+            ]
+        );
+        assert_eq!(instructions[3].accounts.len(), 20);
+    }
+
+    #[test]
+    fn deleverage_instructions() {
+        use crate::state::{ObligationCollateral, ObligationLiquidity};
+
+        let program_id = Pubkey::new_unique();
+        let withdraw_reserve_pubkey = Pubkey::new_unique();
+        let borrow_reserve_pubkey = Pubkey::new_unique();
+        let other_borrow_reserve_pubkey = Pubkey::new_unique();
+        let obligation_pubkey = Pubkey::new_unique();
+        let obligation = Obligation {
+            lending_market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            deposits: vec![ObligationCollateral::new(withdraw_reserve_pubkey)],
+            borrows: vec![
+                ObligationLiquidity::new(borrow_reserve_pubkey, Decimal::one()),
+                ObligationLiquidity::new(other_borrow_reserve_pubkey, Decimal::one()),
+            ],
+            ..Obligation::default()
+        };
+        let swap_instruction = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![],
+            data: vec![],
+        };
+        let build = |repay_liquidity_amount| {
+            deleverage(
+                program_id,
+                100,
+                repay_liquidity_amount,
+                50,
+                swap_instruction.clone(),
+                1,
+                obligation_pubkey,
+                &obligation,
+                withdraw_reserve_pubkey,
+                &Reserve::default(),
+                borrow_reserve_pubkey,
+                &Reserve::default(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            )
+        };
+
+        let instructions = build(100);
+        assert_eq!(
+            instructions
+                .iter()
+                .map(|instruction| LendingInstruction::unpack(&instruction.data).ok())
+                .collect::<Vec<_>>(),
+            vec![
+                Some(LendingInstruction::FlashBorrowReserveLiquidity {
+                    liquidity_amount: 100
+                }),
+                Some(LendingInstruction::RefreshReserve),
+                Some(LendingInstruction::RepayObligationLiquidity {
+                    liquidity_amount: 100
+                }),
+                Some(LendingInstruction::RefreshReserve),
+                Some(LendingInstruction::RefreshObligation),
+                Some(
+                    LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
+                        collateral_amount: 50
+                    }
+                ),
+                None,
+                Some(LendingInstruction::FlashRepayReserveLiquidity {
+                    liquidity_amount: 100,
+                    borrow_instruction_index: 1,
+                }),
+            ]
+        );
+        assert_eq!(instructions[6], swap_instruction);
+        assert_eq!(instructions[4].accounts.len(), 4);
+
+        // a full repay drops the borrow from the obligation refresh
+        let instructions = build(u64::MAX);
+        let refreshed_reserves: Vec<Pubkey> = instructions[4].accounts[1..]
+            .iter()
+            .map(|account| account.pubkey)
+            .collect();
+        assert_eq!(
+            refreshed_reserves,
+            vec![withdraw_reserve_pubkey, other_borrow_reserve_pubkey]
+        );
+    }
 }
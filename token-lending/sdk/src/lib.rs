@@ -1,11 +1,19 @@
 #![deny(missing_docs)]
 
 //! A lending program for the Solana blockchain.
+//!
+//! The default features only cover what on-chain programs need to CPI into the lending program:
+//! instructions, account state and math. Off-chain helpers are behind the `client` feature.
 
 pub mod error;
+pub mod events;
 pub mod instruction;
 pub mod math;
+#[cfg(feature = "client")]
+pub mod migration;
 pub mod state;
+#[cfg(feature = "client")]
+pub mod transaction_plan;
 
 // Export current sdk types for downstream users building with a different sdk version
 pub use solana_program;
@@ -20,6 +28,26 @@ pub mod solend_devnet {
     solana_program::declare_id!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
 }
 
+/// spl account compression program id
+pub mod spl_account_compression {
+    solana_program::declare_id!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+}
+
+/// spl noop program id, used by the account compression program to log tree changes
+pub mod spl_noop {
+    solana_program::declare_id!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+}
+
+/// spl associated token account program id
+pub mod spl_associated_token_account {
+    solana_program::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
+
+/// compute budget program id
+pub mod compute_budget_program {
+    solana_program::declare_id!("ComputeBudget111111111111111111111111111111");
+}
+
 /// Canonical null pubkey. Prints out as "nu11111111111111111111111111111111111111111"
 pub const NULL_PUBKEY: solana_program::pubkey::Pubkey =
     solana_program::pubkey::Pubkey::new_from_array([
@@ -0,0 +1,132 @@
+//! Amounts tagged with their unit, so liquidity, collateral and value can't be mixed up
+
+use crate::{
+    error::LendingError,
+    math::{Decimal, TryAdd, TrySub},
+};
+use solana_program::program_error::ProgramError;
+use std::fmt;
+
+/// Amount of a reserve's liquidity token, in the token's smallest unit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenAmount(pub u64);
+
+/// Amount of a reserve's collateral token (cToken), in the cToken's smallest unit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CTokenAmount(pub u64);
+
+/// Value in the lending market's quote currency
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UsdValue(pub Decimal);
+
+macro_rules! impl_token_amount {
+    ($name:ident) => {
+        impl $name {
+            /// Zero amount
+            pub fn zero() -> Self {
+                Self(0)
+            }
+        }
+
+        impl TryAdd for $name {
+            fn try_add(self, rhs: Self) -> Result<Self, ProgramError> {
+                Ok(Self(
+                    self.0
+                        .checked_add(rhs.0)
+                        .ok_or(LendingError::MathOverflow)?,
+                ))
+            }
+        }
+
+        impl TrySub for $name {
+            fn try_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+                Ok(Self(
+                    self.0
+                        .checked_sub(rhs.0)
+                        .ok_or(LendingError::MathOverflow)?,
+                ))
+            }
+        }
+
+        impl From<$name> for Decimal {
+            fn from(amount: $name) -> Self {
+                Decimal::from(amount.0)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+
+impl_token_amount!(TokenAmount);
+impl_token_amount!(CTokenAmount);
+
+impl UsdValue {
+    /// Zero value
+    pub fn zero() -> Self {
+        Self(Decimal::zero())
+    }
+}
+
+impl TryAdd for UsdValue {
+    fn try_add(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(self.0.try_add(rhs.0)?))
+    }
+}
+
+impl TrySub for UsdValue {
+    fn try_sub(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(self.0.try_sub(rhs.0)?))
+    }
+}
+
+impl fmt::Display for UsdValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_token_amount_math() {
+        assert_eq!(
+            TokenAmount(1).try_add(TokenAmount(2)).unwrap(),
+            TokenAmount(3)
+        );
+        assert_eq!(
+            CTokenAmount(3).try_sub(CTokenAmount(2)).unwrap(),
+            CTokenAmount(1)
+        );
+        assert_eq!(
+            TokenAmount(u64::MAX).try_add(TokenAmount(1)),
+            Err(LendingError::MathOverflow.into())
+        );
+        assert_eq!(
+            CTokenAmount::zero().try_sub(CTokenAmount(1)),
+            Err(LendingError::MathOverflow.into())
+        );
+        assert_eq!(Decimal::from(TokenAmount(7)), Decimal::from(7u64));
+    }
+
+    #[test]
+    fn test_usd_value_math() {
+        assert_eq!(
+            UsdValue(Decimal::from(1u64))
+                .try_add(UsdValue(Decimal::from(2u64)))
+                .unwrap(),
+            UsdValue(Decimal::from(3u64))
+        );
+        assert!(UsdValue::zero().try_sub(UsdValue(Decimal::one())).is_err());
+        assert_eq!(
+            UsdValue(Decimal::from(5u64)).to_string(),
+            "5.000000000000000000"
+        );
+    }
+}
@@ -11,6 +11,9 @@
 #![allow(clippy::assign_op_pattern)]
 #![allow(clippy::ptr_offset_with_cast)]
 #![allow(clippy::manual_range_contains)]
+// manual_div_ceil comes from construct_uint and is unknown to the CI toolchain's clippy
+#![allow(unknown_lints)]
+#![allow(clippy::manual_div_ceil)]
 
 use crate::{
     error::LendingError,
@@ -121,6 +124,34 @@ impl fmt::Display for Decimal {
     }
 }
 
+/// Serialized as its decimal string, eg "1.500000000000000000", since JSON numbers can't hold
+/// the full precision
+#[cfg(feature = "serialize")]
+impl serde::Serialize for Decimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for Decimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let (integer, fraction) = value.split_once('.').unwrap_or((&value, ""));
+        if fraction.len() > SCALE {
+            return Err(D::Error::custom(format!(
+                "Decimal {} has more than {} decimal places",
+                value, SCALE
+            )));
+        }
+        U192::from_dec_str(&format!("{}{:0<width$}", integer, fraction, width = SCALE))
+            .map(Self)
+            .map_err(|_| D::Error::custom(format!("Invalid decimal {}", value)))
+    }
+}
+
 impl fmt::Debug for Decimal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self)
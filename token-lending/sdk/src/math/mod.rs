@@ -1,9 +1,11 @@
 //! Math for preserving precision
 
+mod amount;
 mod common;
 mod decimal;
 mod rate;
 
+pub use amount::*;
 pub use common::*;
 pub use decimal::*;
 pub use rate::*;
@@ -17,6 +17,9 @@
 #![allow(clippy::ptr_offset_with_cast)]
 #![allow(clippy::reversed_empty_ranges)]
 #![allow(clippy::manual_range_contains)]
+// manual_div_ceil comes from construct_uint and is unknown to the CI toolchain's clippy
+#![allow(unknown_lints)]
+#![allow(clippy::manual_div_ceil)]
 
 use crate::{
     error::LendingError,
@@ -75,7 +78,7 @@ impl Rate {
     /// Calculates base^exp
     pub fn try_pow(&self, mut exp: u64) -> Result<Rate, ProgramError> {
         let mut base = *self;
-        let mut ret = if exp % 2 != 0 {
+        let mut ret = if exp % 2 == 1 {
             base
         } else {
             Rate(Self::wad())
@@ -85,7 +88,7 @@ impl Rate {
             exp /= 2;
             base = base.try_mul(base)?;
 
-            if exp % 2 != 0 {
+            if exp % 2 == 1 {
                 ret = ret.try_mul(base)?;
             }
         }
@@ -0,0 +1,139 @@
+//! Adapter for marginfi v2, an Anchor program
+
+use super::ExternalProtocolAdapter;
+use solana_program::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// marginfi v2 program id
+pub mod marginfi_v2 {
+    solana_program::declare_id!("MFv2hWf31Z9kbCa1snEPYctwafyhdvnV7FZnsebVacA");
+}
+
+const LIQUIDITY_VAULT_SEED: &[u8] = b"liquidity_vault";
+const LIQUIDITY_VAULT_AUTHORITY_SEED: &[u8] = b"liquidity_vault_auth";
+
+/// A marginfi account with a single collateral and a single debt balance
+pub struct MarginfiPosition {
+    /// Group of the account
+    pub marginfi_group_pubkey: Pubkey,
+    /// Account holding the position
+    pub marginfi_account_pubkey: Pubkey,
+    /// Bank the collateral is deposited in
+    pub deposit_bank_pubkey: Pubkey,
+    /// Bank the debt is borrowed from
+    pub borrow_bank_pubkey: Pubkey,
+    /// Whether the amounts passed to the adapter close the balances entirely
+    pub close_balances: bool,
+    /// Bank and oracle pairs of the balances still open after the withdraw, for marginfi's
+    /// health check. Empty when the balances are closed.
+    pub health_check_pubkeys: Vec<Pubkey>,
+}
+
+/// Anchor instruction discriminator
+fn discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// Borsh encoding of the `amount: u64, all: Option<bool>` arguments
+fn pack_args(name: &str, amount: u64, all: bool) -> Vec<u8> {
+    let mut data = discriminator(name).to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    if all {
+        data.extend_from_slice(&[1, 1]);
+    } else {
+        data.push(0);
+    }
+    data
+}
+
+fn bank_vault(seed: &[u8], bank_pubkey: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seed, bank_pubkey.as_ref()], &marginfi_v2::id()).0
+}
+
+impl ExternalProtocolAdapter for MarginfiPosition {
+    fn repay(
+        &self,
+        owner_pubkey: Pubkey,
+        source_liquidity_pubkey: Pubkey,
+        liquidity_amount: u64,
+    ) -> Vec<Instruction> {
+        vec![Instruction {
+            program_id: marginfi_v2::id(),
+            accounts: vec![
+                AccountMeta::new_readonly(self.marginfi_group_pubkey, false),
+                AccountMeta::new(self.marginfi_account_pubkey, false),
+                AccountMeta::new_readonly(owner_pubkey, true),
+                AccountMeta::new(self.borrow_bank_pubkey, false),
+                AccountMeta::new(source_liquidity_pubkey, false),
+                AccountMeta::new(
+                    bank_vault(LIQUIDITY_VAULT_SEED, &self.borrow_bank_pubkey),
+                    false,
+                ),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: pack_args(
+                "lending_account_repay",
+                liquidity_amount,
+                self.close_balances,
+            ),
+        }]
+    }
+
+    /// `amount` is in the deposit bank's liquidity token
+    fn withdraw(
+        &self,
+        owner_pubkey: Pubkey,
+        destination_liquidity_pubkey: Pubkey,
+        amount: u64,
+    ) -> Vec<Instruction> {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.marginfi_group_pubkey, false),
+            AccountMeta::new(self.marginfi_account_pubkey, false),
+            AccountMeta::new_readonly(owner_pubkey, true),
+            AccountMeta::new(self.deposit_bank_pubkey, false),
+            AccountMeta::new(destination_liquidity_pubkey, false),
+            AccountMeta::new_readonly(
+                bank_vault(LIQUIDITY_VAULT_AUTHORITY_SEED, &self.deposit_bank_pubkey),
+                false,
+            ),
+            AccountMeta::new(
+                bank_vault(LIQUIDITY_VAULT_SEED, &self.deposit_bank_pubkey),
+                false,
+            ),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+        accounts.extend(
+            self.health_check_pubkeys
+                .iter()
+                .map(|pubkey| AccountMeta::new_readonly(*pubkey, false)),
+        );
+        vec![Instruction {
+            program_id: marginfi_v2::id(),
+            accounts,
+            data: pack_args("lending_account_withdraw", amount, self.close_balances),
+        }]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_args() {
+        let data = pack_args("lending_account_repay", 5, true);
+        assert_eq!(data.len(), 18);
+        assert_eq!(data[..8], discriminator("lending_account_repay"));
+        assert_eq!(data[8..16], 5u64.to_le_bytes());
+        assert_eq!(data[16..], [1, 1]);
+
+        let data = pack_args("lending_account_withdraw", 5, false);
+        assert_eq!(data.len(), 17);
+        assert_eq!(data[16], 0);
+    }
+}
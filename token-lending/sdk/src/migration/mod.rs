@@ -0,0 +1,276 @@
+//! Moving leveraged positions from other lending protocols into Solend in a single transaction.
+//!
+//! Flash loans can't be taken through CPI, so migration is a client side flow rather than an
+//! instruction: [`migrate_from_external`] builds every instruction of the transaction, and an
+//! [`ExternalProtocolAdapter`] supplies the instructions that close the position on the other
+//! protocol.
+
+pub mod marginfi;
+pub mod port;
+
+use crate::{
+    instruction::{
+        borrow_obligation_liquidity, deposit_reserve_liquidity_and_obligation_collateral,
+        flash_borrow_reserve_liquidity, flash_repay_reserve_liquidity, refresh_obligation,
+        refresh_reserve,
+    },
+    state::Reserve,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+/// Builds the instructions that unwind a user's position on another lending protocol
+pub trait ExternalProtocolAdapter {
+    /// Instructions repaying `liquidity_amount` of the user's debt from `source_liquidity_pubkey`
+    fn repay(
+        &self,
+        owner_pubkey: Pubkey,
+        source_liquidity_pubkey: Pubkey,
+        liquidity_amount: u64,
+    ) -> Vec<Instruction>;
+
+    /// Instructions withdrawing `amount` of the user's collateral, denominated the way the
+    /// external protocol accounts for it, as liquidity into `destination_liquidity_pubkey`
+    fn withdraw(
+        &self,
+        owner_pubkey: Pubkey,
+        destination_liquidity_pubkey: Pubkey,
+        amount: u64,
+    ) -> Vec<Instruction>;
+}
+
+/// Accounts and amounts for moving a single collateral / single debt position into a Solend
+/// obligation. The obligation must not hold positions in any reserve other than the deposit and
+/// borrow reserves, and the two reserves must differ.
+pub struct MigrateFromExternal<'a> {
+    /// Solend lending program id
+    pub program_id: Pubkey,
+    /// Lending market of both reserves
+    pub lending_market_pubkey: Pubkey,
+    /// Obligation receiving the position
+    pub obligation_pubkey: Pubkey,
+    /// Owner of the obligation and of the external position, signs the transaction
+    pub owner_pubkey: Pubkey,
+    /// Reserve the collateral is deposited into
+    pub deposit_reserve_pubkey: Pubkey,
+    /// Deposit reserve state
+    pub deposit_reserve: &'a Reserve,
+    /// Reserve the debt is flash borrowed from and then borrowed from
+    pub borrow_reserve_pubkey: Pubkey,
+    /// Borrow reserve state
+    pub borrow_reserve: &'a Reserve,
+    /// Owner's token account for the borrowed liquidity
+    pub user_borrow_liquidity_pubkey: Pubkey,
+    /// Owner's token account for the collateral liquidity
+    pub user_deposit_liquidity_pubkey: Pubkey,
+    /// Owner's token account for the deposit reserve's collateral token
+    pub user_deposit_collateral_pubkey: Pubkey,
+    /// Debt repaid on the external protocol, flash borrowed from the borrow reserve
+    pub repay_amount: u64,
+    /// Collateral withdrawn from the external protocol, in the external protocol's units
+    pub withdraw_amount: u64,
+    /// Liquidity deposited into the deposit reserve
+    pub deposit_amount: u64,
+    /// Liquidity borrowed from the borrow reserve. After the borrow fee this must cover the flash
+    /// loan and its fee.
+    pub borrow_amount: u64,
+}
+
+/// Creates the instructions of a transaction migrating a position from an external protocol.
+/// `instruction_offset` is the index in the transaction of the first returned instruction, eg the
+/// number of compute budget instructions placed before them.
+pub fn migrate_from_external(
+    migration: &MigrateFromExternal,
+    adapter: &dyn ExternalProtocolAdapter,
+    instruction_offset: u8,
+) -> Vec<Instruction> {
+    let program_id = migration.program_id;
+    let deposit_reserve = migration.deposit_reserve;
+    let borrow_reserve = migration.borrow_reserve;
+    let refresh_deposit_reserve = refresh_reserve(
+        program_id,
+        migration.deposit_reserve_pubkey,
+        deposit_reserve.liquidity.pyth_oracle_pubkey,
+        deposit_reserve.liquidity.switchboard_oracle_pubkey,
+        deposit_reserve.config.extra_oracle_pubkey,
+    );
+    let refresh_borrow_reserve = refresh_reserve(
+        program_id,
+        migration.borrow_reserve_pubkey,
+        borrow_reserve.liquidity.pyth_oracle_pubkey,
+        borrow_reserve.liquidity.switchboard_oracle_pubkey,
+        borrow_reserve.config.extra_oracle_pubkey,
+    );
+
+    let mut instructions = vec![refresh_borrow_reserve.clone()];
+
+    let flash_borrow_index = instruction_offset + instructions.len() as u8;
+    instructions.push(flash_borrow_reserve_liquidity(
+        program_id,
+        migration.repay_amount,
+        borrow_reserve.liquidity.supply_pubkey,
+        migration.user_borrow_liquidity_pubkey,
+        migration.borrow_reserve_pubkey,
+        migration.lending_market_pubkey,
+    ));
+
+    instructions.extend(adapter.repay(
+        migration.owner_pubkey,
+        migration.user_borrow_liquidity_pubkey,
+        migration.repay_amount,
+    ));
+    instructions.extend(adapter.withdraw(
+        migration.owner_pubkey,
+        migration.user_deposit_liquidity_pubkey,
+        migration.withdraw_amount,
+    ));
+
+    instructions.push(refresh_deposit_reserve.clone());
+    instructions.push(deposit_reserve_liquidity_and_obligation_collateral(
+        program_id,
+        migration.deposit_amount,
+        migration.user_deposit_liquidity_pubkey,
+        migration.user_deposit_collateral_pubkey,
+        migration.deposit_reserve_pubkey,
+        deposit_reserve.liquidity.supply_pubkey,
+        deposit_reserve.collateral.mint_pubkey,
+        migration.lending_market_pubkey,
+        deposit_reserve.collateral.supply_pubkey,
+        migration.obligation_pubkey,
+        migration.owner_pubkey,
+        deposit_reserve.liquidity.pyth_oracle_pubkey,
+        deposit_reserve.liquidity.switchboard_oracle_pubkey,
+        migration.owner_pubkey,
+    ));
+
+    // the deposit and the flash borrow both leave their reserve stale
+    instructions.push(refresh_deposit_reserve);
+    instructions.push(refresh_borrow_reserve);
+    instructions.push(refresh_obligation(
+        program_id,
+        migration.obligation_pubkey,
+        vec![migration.deposit_reserve_pubkey],
+    ));
+    instructions.push(borrow_obligation_liquidity(
+        program_id,
+        migration.borrow_amount,
+        borrow_reserve.liquidity.supply_pubkey,
+        migration.user_borrow_liquidity_pubkey,
+        migration.borrow_reserve_pubkey,
+        borrow_reserve.config.fee_receiver,
+        migration.obligation_pubkey,
+        migration.lending_market_pubkey,
+        migration.owner_pubkey,
+        vec![migration.deposit_reserve_pubkey],
+        None,
+    ));
+
+    instructions.push(flash_repay_reserve_liquidity(
+        program_id,
+        migration.repay_amount,
+        flash_borrow_index,
+        migration.user_borrow_liquidity_pubkey,
+        borrow_reserve.liquidity.supply_pubkey,
+        borrow_reserve.config.fee_receiver,
+        borrow_reserve.config.fee_receiver,
+        migration.borrow_reserve_pubkey,
+        migration.lending_market_pubkey,
+        migration.owner_pubkey,
+    ));
+
+    instructions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::instruction::LendingInstruction;
+
+    struct TestAdapter {
+        program_id: Pubkey,
+    }
+
+    impl ExternalProtocolAdapter for TestAdapter {
+        fn repay(&self, _: Pubkey, _: Pubkey, liquidity_amount: u64) -> Vec<Instruction> {
+            vec![Instruction::new_with_bytes(
+                self.program_id,
+                &liquidity_amount.to_le_bytes(),
+                vec![],
+            )]
+        }
+
+        fn withdraw(&self, _: Pubkey, _: Pubkey, amount: u64) -> Vec<Instruction> {
+            vec![
+                Instruction::new_with_bytes(self.program_id, &[], vec![]),
+                Instruction::new_with_bytes(self.program_id, &amount.to_le_bytes(), vec![]),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_migrate_from_external() {
+        let program_id = Pubkey::new_unique();
+        let adapter = TestAdapter {
+            program_id: Pubkey::new_unique(),
+        };
+        let deposit_reserve = Reserve::default();
+        let borrow_reserve = Reserve::default();
+        let migration = MigrateFromExternal {
+            program_id,
+            lending_market_pubkey: Pubkey::new_unique(),
+            obligation_pubkey: Pubkey::new_unique(),
+            owner_pubkey: Pubkey::new_unique(),
+            deposit_reserve_pubkey: Pubkey::new_unique(),
+            deposit_reserve: &deposit_reserve,
+            borrow_reserve_pubkey: Pubkey::new_unique(),
+            borrow_reserve: &borrow_reserve,
+            user_borrow_liquidity_pubkey: Pubkey::new_unique(),
+            user_deposit_liquidity_pubkey: Pubkey::new_unique(),
+            user_deposit_collateral_pubkey: Pubkey::new_unique(),
+            repay_amount: 100,
+            withdraw_amount: 200,
+            deposit_amount: 300,
+            borrow_amount: 101,
+        };
+
+        let instructions = migrate_from_external(&migration, &adapter, 2);
+        let unpacked: Vec<Option<LendingInstruction>> = instructions
+            .iter()
+            .map(|ix| {
+                (ix.program_id == program_id).then(|| LendingInstruction::unpack(&ix.data).unwrap())
+            })
+            .collect();
+
+        assert_eq!(instructions.len(), 12);
+        assert_eq!(
+            unpacked[1],
+            Some(LendingInstruction::FlashBorrowReserveLiquidity {
+                liquidity_amount: 100
+            })
+        );
+        assert!(unpacked[2..5].iter().all(|ix| ix.is_none()));
+        assert_eq!(instructions[4].data, 200u64.to_le_bytes());
+        assert_eq!(
+            unpacked[6],
+            Some(
+                LendingInstruction::DepositReserveLiquidityAndObligationCollateral {
+                    liquidity_amount: 300
+                }
+            )
+        );
+        assert_eq!(
+            unpacked[10],
+            Some(LendingInstruction::BorrowObligationLiquidity {
+                liquidity_amount: 101,
+                min_liquidity_received: 0,
+            })
+        );
+        // the flash borrow is the second returned instruction, after the offset
+        assert_eq!(
+            unpacked[11],
+            Some(LendingInstruction::FlashRepayReserveLiquidity {
+                liquidity_amount: 100,
+                borrow_instruction_index: 3,
+            })
+        );
+    }
+}
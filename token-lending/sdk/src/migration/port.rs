@@ -0,0 +1,167 @@
+//! Adapter for Port Finance's variable rate lending program, a fork of SPL token-lending
+
+use super::ExternalProtocolAdapter;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+    sysvar,
+};
+
+/// Port Finance variable rate lending program id
+pub mod port_lending {
+    solana_program::declare_id!("Port7uDYB3wk6GJAw4KT1WpTeMtSu9bTcChBHkX2LfR");
+}
+
+const REFRESH_RESERVE_TAG: u8 = 3;
+const REDEEM_RESERVE_COLLATERAL_TAG: u8 = 5;
+const REFRESH_OBLIGATION_TAG: u8 = 7;
+const WITHDRAW_OBLIGATION_COLLATERAL_TAG: u8 = 9;
+const REPAY_OBLIGATION_LIQUIDITY_TAG: u8 = 11;
+
+/// A Port reserve the user's position touches
+pub struct PortReserve {
+    /// Reserve address
+    pub pubkey: Pubkey,
+    /// Reserve liquidity oracle, if the reserve has one
+    pub oracle_pubkey: Option<Pubkey>,
+    /// Reserve liquidity supply
+    pub liquidity_supply_pubkey: Pubkey,
+    /// Reserve collateral mint
+    pub collateral_mint_pubkey: Pubkey,
+    /// Reserve collateral supply
+    pub collateral_supply_pubkey: Pubkey,
+}
+
+/// A single collateral / single debt Port obligation
+pub struct PortPosition {
+    /// Lending market of the obligation
+    pub lending_market_pubkey: Pubkey,
+    /// Obligation holding the position
+    pub obligation_pubkey: Pubkey,
+    /// Reserve the collateral is deposited in
+    pub deposit_reserve: PortReserve,
+    /// Reserve the debt is borrowed from
+    pub borrow_reserve: PortReserve,
+    /// Owner's token account for the deposit reserve's collateral token, which withdrawn
+    /// collateral passes through before being redeemed
+    pub user_collateral_pubkey: Pubkey,
+}
+
+impl PortPosition {
+    fn lending_market_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[&self.lending_market_pubkey.to_bytes()[..PUBKEY_BYTES]],
+            &port_lending::id(),
+        )
+        .0
+    }
+
+    fn refresh_reserve(reserve: &PortReserve) -> Instruction {
+        let mut accounts = vec![AccountMeta::new(reserve.pubkey, false)];
+        if let Some(oracle_pubkey) = reserve.oracle_pubkey {
+            accounts.push(AccountMeta::new_readonly(oracle_pubkey, false));
+        }
+        accounts.push(AccountMeta::new_readonly(sysvar::clock::id(), false));
+        Instruction {
+            program_id: port_lending::id(),
+            accounts,
+            data: vec![REFRESH_RESERVE_TAG],
+        }
+    }
+
+    /// Port requires a freshly refreshed obligation for repays and withdraws
+    fn refresh(&self) -> Vec<Instruction> {
+        vec![
+            Self::refresh_reserve(&self.deposit_reserve),
+            Self::refresh_reserve(&self.borrow_reserve),
+            Instruction {
+                program_id: port_lending::id(),
+                accounts: vec![
+                    AccountMeta::new(self.obligation_pubkey, false),
+                    AccountMeta::new_readonly(sysvar::clock::id(), false),
+                    AccountMeta::new_readonly(self.deposit_reserve.pubkey, false),
+                    AccountMeta::new_readonly(self.borrow_reserve.pubkey, false),
+                ],
+                data: vec![REFRESH_OBLIGATION_TAG],
+            },
+        ]
+    }
+}
+
+fn pack_amount(tag: u8, amount: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(9);
+    data.push(tag);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+impl ExternalProtocolAdapter for PortPosition {
+    fn repay(
+        &self,
+        owner_pubkey: Pubkey,
+        source_liquidity_pubkey: Pubkey,
+        liquidity_amount: u64,
+    ) -> Vec<Instruction> {
+        let mut instructions = self.refresh();
+        instructions.push(Instruction {
+            program_id: port_lending::id(),
+            accounts: vec![
+                AccountMeta::new(source_liquidity_pubkey, false),
+                AccountMeta::new(self.borrow_reserve.liquidity_supply_pubkey, false),
+                AccountMeta::new(self.borrow_reserve.pubkey, false),
+                AccountMeta::new(self.obligation_pubkey, false),
+                AccountMeta::new_readonly(self.lending_market_pubkey, false),
+                AccountMeta::new_readonly(owner_pubkey, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: pack_amount(REPAY_OBLIGATION_LIQUIDITY_TAG, liquidity_amount),
+        });
+        instructions
+    }
+
+    /// `amount` is in the deposit reserve's collateral token
+    fn withdraw(
+        &self,
+        owner_pubkey: Pubkey,
+        destination_liquidity_pubkey: Pubkey,
+        amount: u64,
+    ) -> Vec<Instruction> {
+        let lending_market_authority_pubkey = self.lending_market_authority();
+        let mut instructions = self.refresh();
+        instructions.push(Instruction {
+            program_id: port_lending::id(),
+            accounts: vec![
+                AccountMeta::new(self.deposit_reserve.collateral_supply_pubkey, false),
+                AccountMeta::new(self.user_collateral_pubkey, false),
+                AccountMeta::new_readonly(self.deposit_reserve.pubkey, false),
+                AccountMeta::new(self.obligation_pubkey, false),
+                AccountMeta::new_readonly(self.lending_market_pubkey, false),
+                AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+                AccountMeta::new_readonly(owner_pubkey, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: pack_amount(WITHDRAW_OBLIGATION_COLLATERAL_TAG, amount),
+        });
+        // withdrawing leaves the reserve stale
+        instructions.push(Self::refresh_reserve(&self.deposit_reserve));
+        instructions.push(Instruction {
+            program_id: port_lending::id(),
+            accounts: vec![
+                AccountMeta::new(self.user_collateral_pubkey, false),
+                AccountMeta::new(destination_liquidity_pubkey, false),
+                AccountMeta::new(self.deposit_reserve.pubkey, false),
+                AccountMeta::new(self.deposit_reserve.collateral_mint_pubkey, false),
+                AccountMeta::new(self.deposit_reserve.liquidity_supply_pubkey, false),
+                AccountMeta::new_readonly(self.lending_market_pubkey, false),
+                AccountMeta::new_readonly(lending_market_authority_pubkey, false),
+                AccountMeta::new_readonly(owner_pubkey, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: pack_amount(REDEEM_RESERVE_COLLATERAL_TAG, amount),
+        });
+        instructions
+    }
+}
@@ -0,0 +1,286 @@
+use super::*;
+use crate::error::LendingError;
+use solana_program::{
+    entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack,
+};
+
+/// Type of a lending program account.
+///
+/// Every account starts with a version byte. Every account but lending markets, reserves and
+/// obligations stores its tag right after it. Those three keep the layouts they had before tags
+/// were added, so their type is inferred from the account size, which differs between every
+/// account type and layout. Obligations have two sizes, the original one and the extended one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccountTag {
+    /// Lending market of either layout
+    LendingMarket = 1,
+    /// Reserve of either layout
+    Reserve = 2,
+    /// Obligation of either size
+    Obligation = 3,
+    /// Reward rule
+    RewardRule = 4,
+    // 5 was the obligation reward account, whose rewards are now kept on the obligation
+    /// Emergency price
+    EmergencyPrice = 6,
+    /// Obligation counter
+    ObligationCounter = 7,
+    // 8 was the protocol fee config account, whose config is now on the lending market
+    // 9 was the obligation referrer account, whose referrer is now on the obligation
+    /// Reserve rate stats
+    ReserveRateStats = 10,
+    /// Reserve activity
+    ReserveActivity = 11,
+    // 12 was the reserve flash loan cap account, whose cap is now in the reserve config
+    // 13 was the reserve tertiary oracle account, whose oracle is now on the reserve
+    /// Reserve ctoken price
+    ReserveCTokenPrice = 14,
+    // 15 was the obligation rate limiter account, whose rate limiter is now on the obligation
+    // 16 was the obligation borrow delegation account, whose delegation is now on the obligation
+    /// Reserve pair borrow weights
+    ReservePairBorrowWeights = 17,
+    /// Market member
+    MarketMember = 18,
+    /// Liquidator whitelist
+    LiquidatorWhitelist = 19,
+    /// Asset categories
+    AssetCategories = 20,
+    /// Max price age
+    MaxPriceAge = 21,
+    /// Reserve spread fee receiver
+    ReserveSpreadFeeReceiver = 22,
+    /// Devnet price
+    #[cfg(feature = "devnet-utils")]
+    DevnetPrice = 23,
+}
+
+impl AccountTag {
+    /// Classifies the data of an account owned by the lending program. Returns None for
+    /// uninitialized accounts, accounts from a newer program version, unknown sizes and tagged
+    /// accounts whose tag doesn't match their size.
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        let tag = Self::from_account_len(data.len())?;
+        match data[0] {
+            UNINITIALIZED_VERSION => None,
+            version if version > PROGRAM_VERSION => None,
+            version if tag.is_stored() && !tag_matches(version, data[1], tag) => None,
+            _ => Some(tag),
+        }
+    }
+
+    /// Whether accounts of this type store their tag after their version byte
+    fn is_stored(self) -> bool {
+        !matches!(self, Self::LendingMarket | Self::Reserve | Self::Obligation)
+    }
+
+    fn from_account_len(len: usize) -> Option<Self> {
+        Some(match len {
+            LendingMarket::LEN | LENDING_MARKET_LEN_V1 => Self::LendingMarket,
+            Reserve::LEN | RESERVE_LEN_V1 => Self::Reserve,
+            len if Obligation::max_reserves_for_len(len).is_some()
+                || Obligation::migrated_len(len).is_some() =>
+            {
+                Self::Obligation
+            }
+            RewardRule::LEN => Self::RewardRule,
+            EmergencyPrice::LEN => Self::EmergencyPrice,
+            ObligationCounter::LEN => Self::ObligationCounter,
+            ReserveRateStats::LEN => Self::ReserveRateStats,
+            ReserveActivity::LEN => Self::ReserveActivity,
+            ReserveCTokenPrice::LEN => Self::ReserveCTokenPrice,
+            ReservePairBorrowWeights::LEN => Self::ReservePairBorrowWeights,
+            MarketMember::LEN => Self::MarketMember,
+            LiquidatorWhitelist::LEN => Self::LiquidatorWhitelist,
+            AssetCategories::LEN => Self::AssetCategories,
+            MaxPriceAge::LEN => Self::MaxPriceAge,
+            ReserveSpreadFeeReceiver::LEN => Self::ReserveSpreadFeeReceiver,
+            #[cfg(feature = "devnet-utils")]
+            DevnetPrice::LEN => Self::DevnetPrice,
+            _ => return None,
+        })
+    }
+}
+
+/// Checks the tag stored in an account against the type it's unpacked as. Uninitialized accounts
+/// have no tag yet.
+pub(crate) fn check_account_tag(
+    version: u8,
+    account_tag: &[u8; 1],
+    expected: AccountTag,
+) -> Result<(), ProgramError> {
+    if version != UNINITIALIZED_VERSION && !tag_matches(version, account_tag[0], expected) {
+        msg!("Account tag does not match the account type");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// Version 1 accounts were written before tags were added and have zeroed padding in their place
+/// until they're packed again
+fn tag_matches(version: u8, stored: u8, expected: AccountTag) -> bool {
+    stored == expected as u8 || (version == 1 && stored == 0)
+}
+
+/// Whether the data is a lending program account written by a newer program version, which this
+/// version can't read or write without losing what the newer version stored
+pub fn is_newer_version_account(data: &[u8]) -> bool {
+    AccountTag::from_account_len(data.len()).is_some() && data[0] > PROGRAM_VERSION
+}
+
+/// Whether the data is a lending market, reserve or obligation that still has its V1 layout.
+/// The program reads it as the current layout with every field added since zeroed, and can write
+/// it back as long as none of those fields were set, until MigrateAccount reallocates it.
+pub fn is_v1_layout_account(data: &[u8]) -> bool {
+    (matches!(data.len(), LENDING_MARKET_LEN_V1 | RESERVE_LEN_V1)
+        || Obligation::migrated_len(data.len()).is_some())
+        && AccountTag::from_account_data(data).is_some()
+}
+
+/// Unpacks an initialized account with the V1 layout, which is the start of the current layout
+/// of `len` bytes, by reading it with the rest zeroed. New accounts have to be created with the
+/// current layout.
+pub(crate) fn unpack_v1_layout<T>(
+    input: &[u8],
+    len: usize,
+    unpack: impl FnOnce(&[u8]) -> Result<T, ProgramError>,
+) -> Result<T, ProgramError> {
+    if input[0] == UNINITIALIZED_VERSION {
+        msg!("New accounts must be created with the current layout");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut data = vec![0; len];
+    data[..input.len()].copy_from_slice(input);
+    unpack(&data)
+}
+
+/// Packs into an account with the V1 layout by packing the current layout of `len` bytes and
+/// keeping its start. Fails if any of the fields added since differ from what the account reads
+/// them as, since it has no room for them until it's migrated.
+pub(crate) fn pack_v1_layout<T>(
+    src: &T,
+    dst: &mut [u8],
+    len: usize,
+    pack: impl Fn(&T, &mut [u8]),
+    unpack: impl Fn(&[u8]) -> Result<T, ProgramError>,
+) -> ProgramResult {
+    let mut data = vec![0; len];
+    pack(src, &mut data);
+
+    let mut v1_data = vec![0; len];
+    v1_data[..dst.len()].copy_from_slice(&data[..dst.len()]);
+    let read_back = unpack(&v1_data)?;
+    pack(&read_back, &mut v1_data);
+    if data[dst.len()..] != v1_data[dst.len()..] {
+        msg!("Account must be migrated with MigrateAccount before using features added since version 1");
+        return Err(LendingError::AccountNotMigrated.into());
+    }
+
+    dst.copy_from_slice(&data[..dst.len()]);
+    Ok(())
+}
+
+/// Whether the data is an initialized lending market
+pub fn is_lending_market(data: &[u8]) -> bool {
+    AccountTag::from_account_data(data) == Some(AccountTag::LendingMarket)
+}
+
+/// Whether the data is an initialized reserve
+pub fn is_reserve(data: &[u8]) -> bool {
+    AccountTag::from_account_data(data) == Some(AccountTag::Reserve)
+}
+
+/// Whether the data is an initialized obligation of either size
+pub fn is_obligation(data: &[u8]) -> bool {
+    AccountTag::from_account_data(data) == Some(AccountTag::Obligation)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn account_tag_from_account_data() {
+        let mut lending_market = vec![0u8; LendingMarket::LEN];
+        assert_eq!(AccountTag::from_account_data(&lending_market), None);
+        lending_market[0] = PROGRAM_VERSION;
+        assert!(is_lending_market(&lending_market));
+        assert!(!is_reserve(&lending_market));
+        assert!(!is_newer_version_account(&lending_market));
+        lending_market[0] = PROGRAM_VERSION + 1;
+        assert_eq!(AccountTag::from_account_data(&lending_market), None);
+        assert!(is_newer_version_account(&lending_market));
+
+        let mut reserve = vec![0u8; Reserve::LEN];
+        reserve[0] = PROGRAM_VERSION;
+        assert!(is_reserve(&reserve));
+        assert!(!is_v1_layout_account(&reserve));
+
+        for (len, tag) in [
+            (LENDING_MARKET_LEN_V1, AccountTag::LendingMarket),
+            (RESERVE_LEN_V1, AccountTag::Reserve),
+            (OBLIGATION_LEN_V1, AccountTag::Obligation),
+        ] {
+            let mut data = vec![0u8; len];
+            assert!(!is_v1_layout_account(&data));
+            data[0] = 1;
+            assert_eq!(AccountTag::from_account_data(&data), Some(tag));
+            assert!(is_v1_layout_account(&data));
+        }
+
+        for len in [Obligation::LEN, OBLIGATION_EXTENDED_LEN] {
+            let mut obligation = vec![0u8; len];
+            obligation[0] = PROGRAM_VERSION;
+            assert!(is_obligation(&obligation));
+        }
+
+        for (len, tag) in [
+            (RewardRule::LEN, AccountTag::RewardRule),
+            (EmergencyPrice::LEN, AccountTag::EmergencyPrice),
+            (ObligationCounter::LEN, AccountTag::ObligationCounter),
+            (ReserveRateStats::LEN, AccountTag::ReserveRateStats),
+            (ReserveActivity::LEN, AccountTag::ReserveActivity),
+            (ReserveCTokenPrice::LEN, AccountTag::ReserveCTokenPrice),
+            (
+                ReservePairBorrowWeights::LEN,
+                AccountTag::ReservePairBorrowWeights,
+            ),
+            (MarketMember::LEN, AccountTag::MarketMember),
+            (LiquidatorWhitelist::LEN, AccountTag::LiquidatorWhitelist),
+            (AssetCategories::LEN, AccountTag::AssetCategories),
+            (MaxPriceAge::LEN, AccountTag::MaxPriceAge),
+            (
+                ReserveSpreadFeeReceiver::LEN,
+                AccountTag::ReserveSpreadFeeReceiver,
+            ),
+        ] {
+            let mut data = vec![0u8; len];
+            data[0] = PROGRAM_VERSION;
+            assert_eq!(AccountTag::from_account_data(&data), None);
+            data[1] = tag as u8;
+            assert_eq!(AccountTag::from_account_data(&data), Some(tag));
+        }
+
+        // version 1 accounts may not have a tag yet, but a wrong tag is rejected at any version
+        let mut untagged = vec![0u8; ObligationCounter::LEN];
+        untagged[0] = 1;
+        assert_eq!(
+            AccountTag::from_account_data(&untagged),
+            Some(AccountTag::ObligationCounter)
+        );
+        let mut obligation_counter = vec![0u8; ObligationCounter::LEN];
+        obligation_counter[0] = PROGRAM_VERSION;
+        obligation_counter[1] = AccountTag::ReserveActivity as u8;
+        assert_eq!(AccountTag::from_account_data(&obligation_counter), None);
+        assert_eq!(
+            ObligationCounter::unpack(&obligation_counter),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        assert_eq!(AccountTag::from_account_data(&[PROGRAM_VERSION; 7]), None);
+        assert_eq!(AccountTag::from_account_data(&[]), None);
+        // accounts of unknown sizes aren't known to start with a version
+        assert!(!is_newer_version_account(&[PROGRAM_VERSION + 1; 7]));
+        assert!(!is_newer_version_account(&[]));
+    }
+}
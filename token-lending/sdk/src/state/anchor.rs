@@ -0,0 +1,71 @@
+use super::*;
+use crate::solend_mainnet;
+use anchor_lang::{AccountDeserialize, AccountSerialize, Owner};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+impl AccountDeserialize for Reserve {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(Reserve::unpack(buf)?)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(Reserve::unpack_unchecked(buf)?)
+    }
+}
+
+impl AccountDeserialize for Obligation {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(Obligation::unpack_account(buf)?)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(Obligation::unpack_account_unchecked(buf)?)
+    }
+}
+
+impl AccountDeserialize for LendingMarket {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(LendingMarket::unpack(buf)?)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(LendingMarket::unpack_unchecked(buf)?)
+    }
+}
+
+impl AccountDeserialize for LendingMarketMetadata {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Ok(*LendingMarketMetadata::new_from_bytes(buf)?)
+    }
+}
+
+// accounts are only ever written by the lending program, so serializing is a no-op on the anchor
+// side, same as for the spl token account wrappers
+impl AccountSerialize for Reserve {}
+impl AccountSerialize for Obligation {}
+impl AccountSerialize for LendingMarket {}
+impl AccountSerialize for LendingMarketMetadata {}
+
+impl Owner for Reserve {
+    fn owner() -> Pubkey {
+        solend_mainnet::id()
+    }
+}
+
+impl Owner for Obligation {
+    fn owner() -> Pubkey {
+        solend_mainnet::id()
+    }
+}
+
+impl Owner for LendingMarket {
+    fn owner() -> Pubkey {
+        solend_mainnet::id()
+    }
+}
+
+impl Owner for LendingMarketMetadata {
+    fn owner() -> Pubkey {
+        solend_mainnet::id()
+    }
+}
@@ -0,0 +1,330 @@
+use super::*;
+use crate::{error::LendingError, math::Rate};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+use std::cmp::max;
+
+/// Seed used with the lending market address to derive its asset categories account
+pub const ASSET_CATEGORIES_SEED: &[u8] = b"AssetCategories";
+
+/// Number of asset categories a lending market can have. Categories are numbered from 1, 0 means
+/// a reserve isn't in any category
+pub const MAX_ASSET_CATEGORIES: usize = 16;
+
+/// Loan to value ratio and liquidation thresholds of an asset category, as percentages. All 0
+/// while the category is unset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssetCategory {
+    /// Loan to value ratio of the category's collateral
+    pub loan_to_value_ratio: u8,
+    /// Liquidation threshold of the category's collateral
+    pub liquidation_threshold: u8,
+    /// Max liquidation threshold of the category's collateral
+    pub max_liquidation_threshold: u8,
+}
+
+impl AssetCategory {
+    /// Loan to value, liquidation threshold and max liquidation threshold rates of a reserve's
+    /// collateral in the category. The category only ever raises the reserve's own limits.
+    pub fn collateral_rates(&self, config: &ReserveConfig) -> (Rate, Rate, Rate) {
+        (
+            Rate::from_percent(max(config.loan_to_value_ratio, self.loan_to_value_ratio)),
+            Rate::from_percent(max(
+                config.liquidation_threshold,
+                self.liquidation_threshold,
+            )),
+            Rate::from_percent(max(
+                config.max_liquidation_threshold,
+                self.max_liquidation_threshold,
+            )),
+        )
+    }
+}
+
+/// Asset categories of a lending market, eg stablecoins or SOL and its LSTs, set by the lending
+/// market owner. Reserves join a category through their config's `asset_category`. Obligations
+/// whose deposits and borrows are all in the same category that are refreshed with this account
+/// count their collateral at the category's limits where they are higher than the reserve's own,
+/// and must keep being refreshed with it while the category's limits apply.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssetCategories {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the asset categories account address
+    pub bump_seed: u8,
+    /// Lending market the categories are for
+    pub lending_market: Pubkey,
+    /// Categories 1 to MAX_ASSET_CATEGORIES
+    pub categories: [AssetCategory; MAX_ASSET_CATEGORIES],
+}
+
+/// Find the asset categories account address for a lending market
+pub fn find_asset_categories_address(program_id: &Pubkey, lending_market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[lending_market.as_ref(), ASSET_CATEGORIES_SEED],
+        program_id,
+    )
+}
+
+/// Asset category every deposit and borrow reserve of an obligation is in, given their categories.
+/// None if they aren't all in the same one
+pub fn shared_asset_category(reserve_asset_categories: &[u8]) -> Option<u8> {
+    let (first, rest) = reserve_asset_categories.split_first()?;
+    if *first == 0 || rest.iter().any(|asset_category| asset_category != first) {
+        return None;
+    }
+    Some(*first)
+}
+
+impl AssetCategories {
+    /// Get a category, None for 0 and unknown categories
+    pub fn get(&self, asset_category: u8) -> Option<&AssetCategory> {
+        (asset_category as usize)
+            .checked_sub(1)
+            .and_then(|index| self.categories.get(index))
+    }
+
+    /// Set the limits of a category. Setting them all to 0 unsets it.
+    pub fn set(&mut self, asset_category: u8, category: AssetCategory) -> ProgramResult {
+        if asset_category == 0 || asset_category as usize > MAX_ASSET_CATEGORIES {
+            msg!(
+                "Asset category must be in range [1, {}]",
+                MAX_ASSET_CATEGORIES
+            );
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if category.loan_to_value_ratio >= 100 {
+            msg!("Loan to value ratio must be in range [0, 100)");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if category.liquidation_threshold < category.loan_to_value_ratio
+            || category.liquidation_threshold > 100
+        {
+            msg!("Liquidation threshold must be in range [LTV, 100]");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if category.max_liquidation_threshold < category.liquidation_threshold
+            || category.max_liquidation_threshold > 100
+        {
+            msg!("Max liquidation threshold must be in range [liquidation threshold, 100]");
+            return Err(LendingError::InvalidConfig.into());
+        }
+
+        self.categories[asset_category as usize - 1] = category;
+        Ok(())
+    }
+}
+
+impl Sealed for AssetCategories {}
+impl IsInitialized for AssetCategories {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const ASSET_CATEGORY_LEN: usize = 3; // 1 + 1 + 1
+const ASSET_CATEGORIES_LEN: usize = 210; // 1 + 1 + 1 + 32 + (3 * 16) + 127
+impl Pack for AssetCategories {
+    const LEN: usize = ASSET_CATEGORIES_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, ASSET_CATEGORIES_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, data_flat, _padding) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            ASSET_CATEGORY_LEN * MAX_ASSET_CATEGORIES,
+            127
+        ];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::AssetCategories as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        lending_market.copy_from_slice(self.lending_market.as_ref());
+
+        for (category, data) in self
+            .categories
+            .iter()
+            .zip(data_flat.chunks_exact_mut(ASSET_CATEGORY_LEN))
+        {
+            data.copy_from_slice(&[
+                category.loan_to_value_ratio,
+                category.liquidation_threshold,
+                category.max_liquidation_threshold,
+            ]);
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, ASSET_CATEGORIES_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, data_flat, _padding) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            ASSET_CATEGORY_LEN * MAX_ASSET_CATEGORIES,
+            127
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Asset categories version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::AssetCategories)?;
+
+        let mut categories = [AssetCategory::default(); MAX_ASSET_CATEGORIES];
+        for (category, data) in categories
+            .iter_mut()
+            .zip(data_flat.chunks_exact(ASSET_CATEGORY_LEN))
+        {
+            *category = AssetCategory {
+                loan_to_value_ratio: data[0],
+                liquidation_threshold: data[1],
+                max_liquidation_threshold: data[2],
+            };
+        }
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            lending_market: Pubkey::new_from_array(*lending_market),
+            categories,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_asset_categories() {
+        let mut rng = rand::thread_rng();
+        let mut asset_categories = AssetCategories {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            lending_market: Pubkey::new_unique(),
+            ..AssetCategories::default()
+        };
+        for category in asset_categories.categories.iter_mut() {
+            *category = AssetCategory {
+                loan_to_value_ratio: rng.gen(),
+                liquidation_threshold: rng.gen(),
+                max_liquidation_threshold: rng.gen(),
+            };
+        }
+
+        let mut packed = [0u8; ASSET_CATEGORIES_LEN];
+        AssetCategories::pack(asset_categories.clone(), &mut packed).unwrap();
+        let unpacked = AssetCategories::unpack(&packed).unwrap();
+        assert_eq!(asset_categories, unpacked);
+    }
+
+    #[test]
+    fn set_asset_category() {
+        let mut asset_categories = AssetCategories::default();
+        let stablecoins = AssetCategory {
+            loan_to_value_ratio: 95,
+            liquidation_threshold: 97,
+            max_liquidation_threshold: 98,
+        };
+
+        asset_categories.set(1, stablecoins).unwrap();
+        assert_eq!(asset_categories.get(1), Some(&stablecoins));
+        asset_categories
+            .set(MAX_ASSET_CATEGORIES as u8, stablecoins)
+            .unwrap();
+
+        assert_eq!(asset_categories.get(0), None);
+        assert_eq!(asset_categories.get(MAX_ASSET_CATEGORIES as u8 + 1), None);
+        for asset_category in [0, MAX_ASSET_CATEGORIES as u8 + 1] {
+            assert_eq!(
+                asset_categories.set(asset_category, stablecoins),
+                Err(LendingError::InvalidConfig.into())
+            );
+        }
+
+        for invalid in [
+            AssetCategory {
+                loan_to_value_ratio: 100,
+                ..stablecoins
+            },
+            AssetCategory {
+                liquidation_threshold: 94,
+                ..stablecoins
+            },
+            AssetCategory {
+                max_liquidation_threshold: 96,
+                ..stablecoins
+            },
+            AssetCategory {
+                max_liquidation_threshold: 101,
+                ..stablecoins
+            },
+        ] {
+            assert_eq!(
+                asset_categories.set(1, invalid),
+                Err(LendingError::InvalidConfig.into())
+            );
+        }
+
+        // unsetting
+        asset_categories.set(1, AssetCategory::default()).unwrap();
+        assert_eq!(asset_categories.get(1), Some(&AssetCategory::default()));
+    }
+
+    #[test]
+    fn shared_asset_category_of_reserves() {
+        assert_eq!(shared_asset_category(&[]), None);
+        assert_eq!(shared_asset_category(&[0, 0]), None);
+        assert_eq!(shared_asset_category(&[2]), Some(2));
+        assert_eq!(shared_asset_category(&[2, 2, 2]), Some(2));
+        assert_eq!(shared_asset_category(&[2, 1]), None);
+        assert_eq!(shared_asset_category(&[2, 0]), None);
+    }
+
+    #[test]
+    fn asset_category_collateral_rates() {
+        let category = AssetCategory {
+            loan_to_value_ratio: 90,
+            liquidation_threshold: 93,
+            max_liquidation_threshold: 95,
+        };
+        let config = ReserveConfig {
+            loan_to_value_ratio: 75,
+            liquidation_threshold: 80,
+            max_liquidation_threshold: 96,
+            ..ReserveConfig::default()
+        };
+
+        assert_eq!(
+            category.collateral_rates(&config),
+            (
+                Rate::from_percent(90),
+                Rate::from_percent(93),
+                Rate::from_percent(96)
+            )
+        );
+        assert_eq!(
+            AssetCategory::default().collateral_rates(&config),
+            (
+                Rate::from_percent(75),
+                Rate::from_percent(80),
+                Rate::from_percent(96)
+            )
+        );
+    }
+}
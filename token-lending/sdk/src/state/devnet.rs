@@ -0,0 +1,113 @@
+use super::*;
+use crate::math::Decimal;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed of the mint authority the devnet faucet mints test tokens with
+pub const DEVNET_FAUCET_SEED: &[u8] = b"DevnetFaucet";
+
+/// Seed used with a mint address to derive its devnet price account
+pub const DEVNET_PRICE_SEED: &[u8] = b"DevnetPrice";
+
+/// Price of a test token that anyone can set, used by reserves in place of an oracle on devnet and
+/// localnet. Only exists in builds with the devnet-utils feature.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DevnetPrice {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the devnet price account address
+    pub bump_seed: u8,
+    /// Mint the price is for
+    pub mint: Pubkey,
+    /// Price of one whole token in the quote currency
+    pub price: Decimal,
+    /// Slot the price was set in
+    pub set_slot: Slot,
+}
+
+/// Find the address of the mint authority the devnet faucet mints with
+pub fn find_devnet_faucet_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DEVNET_FAUCET_SEED], program_id)
+}
+
+/// Find the devnet price account address for a mint
+pub fn find_devnet_price_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[mint.as_ref(), DEVNET_PRICE_SEED], program_id)
+}
+
+impl Sealed for DevnetPrice {}
+impl IsInitialized for DevnetPrice {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const DEVNET_PRICE_LEN: usize = 122; // 1 + 1 + 1 + 32 + 16 + 8 + 63
+impl Pack for DevnetPrice {
+    const LEN: usize = DEVNET_PRICE_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, DEVNET_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, mint, price, set_slot, _padding) =
+            mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, 16, 8, 63];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::DevnetPrice as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        mint.copy_from_slice(self.mint.as_ref());
+        pack_decimal(self.price, price);
+        *set_slot = self.set_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, DEVNET_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, mint, price, set_slot, _padding) =
+            array_refs![input, 1, 1, 1, PUBKEY_BYTES, 16, 8, 63];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Devnet price version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::DevnetPrice)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            mint: Pubkey::new_from_array(*mint),
+            price: unpack_decimal(price),
+            set_slot: u64::from_le_bytes(*set_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_devnet_price() {
+        let mut rng = rand::thread_rng();
+        let devnet_price = DevnetPrice {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            mint: Pubkey::new_unique(),
+            price: Decimal::from_scaled_val(rng.gen::<u64>() as u128),
+            set_slot: rng.gen(),
+        };
+
+        let mut packed = [0u8; DEVNET_PRICE_LEN];
+        DevnetPrice::pack(devnet_price.clone(), &mut packed).unwrap();
+        let unpacked = DevnetPrice::unpack(&packed).unwrap();
+        assert_eq!(devnet_price, unpacked);
+    }
+}
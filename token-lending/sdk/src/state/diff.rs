@@ -0,0 +1,631 @@
+use super::*;
+use crate::math::Decimal;
+use solana_program::{clock::Slot, pubkey::Pubkey};
+
+/// Value of a field before and after a change
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    /// Value in the first snapshot
+    pub before: T,
+    /// Value in the second snapshot
+    pub after: T,
+}
+
+/// A changed reserve field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReserveChange {
+    /// Version of the struct
+    Version(Change<u8>),
+    /// Last update slot
+    LastUpdateSlot(Change<Slot>),
+    /// Last update stale flag
+    LastUpdateStale(Change<bool>),
+    /// Lending market address
+    LendingMarket(Change<Pubkey>),
+    /// Liquidity mint address
+    LiquidityMintPubkey(Change<Pubkey>),
+    /// Liquidity mint decimals
+    LiquidityMintDecimals(Change<u8>),
+    /// Liquidity supply address
+    LiquiditySupplyPubkey(Change<Pubkey>),
+    /// Pyth oracle address
+    PythOraclePubkey(Change<Pubkey>),
+    /// Switchboard oracle address
+    SwitchboardOraclePubkey(Change<Pubkey>),
+    /// Available liquidity
+    AvailableAmount(Change<u64>),
+    /// Borrowed liquidity plus interest
+    BorrowedAmountWads(Change<Decimal>),
+    /// Cumulative borrow rate
+    CumulativeBorrowRateWads(Change<Decimal>),
+    /// Accumulated protocol fees
+    AccumulatedProtocolFeesWads(Change<Decimal>),
+    /// Market price
+    MarketPrice(Change<Decimal>),
+    /// Smoothed market price
+    SmoothedMarketPrice(Change<Decimal>),
+    /// Extra oracle market price
+    ExtraMarketPrice(Change<Option<Decimal>>),
+    /// Collateral mint address
+    CollateralMintPubkey(Change<Pubkey>),
+    /// Collateral mint total supply
+    CollateralMintTotalSupply(Change<u64>),
+    /// Collateral supply address
+    CollateralSupplyPubkey(Change<Pubkey>),
+    /// Collateral decimals offset
+    CollateralDecimalsOffset(Change<i8>),
+    /// Any reserve config value
+    Config(Box<Change<ReserveConfig>>),
+    /// Any rate limiter config or window value
+    RateLimiter(Box<Change<RateLimiter>>),
+    /// Attributed borrow value
+    AttributedBorrowValue(Change<Decimal>),
+    /// Emergency utilization threshold
+    EmergencyUtilizationThreshold(Change<u8>),
+    /// Slots above the emergency utilization threshold that trigger the switch
+    EmergencyUtilizationSlots(Change<u64>),
+    /// Slots so far above the emergency utilization threshold
+    HighUtilizationSlots(Change<u64>),
+    /// Emergency mode flag
+    EmergencyMode(Change<bool>),
+    /// Emergency price flag
+    EmergencyPriceActive(Change<bool>),
+    /// Tertiary oracle
+    TertiaryOraclePubkey(Change<Option<Pubkey>>),
+    /// Pair borrow weights flag
+    HasPairBorrowWeights(Change<bool>),
+    /// Insurance fund flag
+    HasInsuranceFund(Change<bool>),
+    /// Spread fee receiver flag
+    HasSpreadFeeReceiver(Change<bool>),
+}
+
+/// Field-level changes between two snapshots of a reserve
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReserveDiff {
+    /// Changed fields, in struct order
+    pub changes: Vec<ReserveChange>,
+}
+
+/// A changed obligation field. Deposits and borrows are matched by reserve, so reordering them
+/// isn't a change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ObligationChange {
+    /// Version of the struct
+    Version(Change<u8>),
+    /// Last update slot
+    LastUpdateSlot(Change<Slot>),
+    /// Last update stale flag
+    LastUpdateStale(Change<bool>),
+    /// Lending market address
+    LendingMarket(Change<Pubkey>),
+    /// Owner address
+    Owner(Change<Pubkey>),
+    /// Deposit into a reserve the obligation had no deposit in
+    DepositAdded(ObligationCollateral),
+    /// Deposit that is no longer there
+    DepositRemoved(ObligationCollateral),
+    /// Deposited collateral amount in a reserve
+    DepositedAmount(Pubkey, Change<u64>),
+    /// Market value of the deposit in a reserve
+    DepositMarketValue(Pubkey, Change<Decimal>),
+    /// Borrow value attributed to the deposit in a reserve
+    DepositAttributedBorrowValue(Pubkey, Change<Decimal>),
+    /// Borrow from a reserve the obligation had no borrow from
+    BorrowAdded(ObligationLiquidity),
+    /// Borrow that is no longer there
+    BorrowRemoved(ObligationLiquidity),
+    /// Cumulative borrow rate of the borrow from a reserve
+    BorrowCumulativeBorrowRateWads(Pubkey, Change<Decimal>),
+    /// Borrowed amount plus interest from a reserve
+    BorrowedAmountWads(Pubkey, Change<Decimal>),
+    /// Market value of the borrow from a reserve
+    BorrowMarketValue(Pubkey, Change<Decimal>),
+    /// Deposited value
+    DepositedValue(Change<Decimal>),
+    /// Risk-adjusted borrowed value
+    BorrowedValue(Change<Decimal>),
+    /// Unweighted borrowed value
+    UnweightedBorrowedValue(Change<Decimal>),
+    /// Risk-adjusted upper bound borrowed value
+    BorrowedValueUpperBound(Change<Decimal>),
+    /// Allowed borrow value
+    AllowedBorrowValue(Change<Decimal>),
+    /// Unhealthy borrow value
+    UnhealthyBorrowValue(Change<Decimal>),
+    /// Super unhealthy borrow value
+    SuperUnhealthyBorrowValue(Change<Decimal>),
+    /// Isolated asset borrow flag
+    BorrowingIsolatedAsset(Change<bool>),
+    /// Closeable flag
+    Closeable(Change<bool>),
+    /// Borrow lock flag
+    BorrowsLocked(Change<bool>),
+    /// Borrow unlock slot
+    BorrowUnlockSlot(Change<Slot>),
+    /// Referrer
+    Referrer(Change<Option<Pubkey>>),
+    /// Any rate limiter config or window value
+    RateLimiter(Box<Change<RateLimiter>>),
+    /// Approved borrower
+    ApprovedBorrower(Change<Option<Pubkey>>),
+    /// Value the approved borrower can still borrow
+    DelegatedValue(Change<Decimal>),
+}
+
+/// Field-level changes between two snapshots of an obligation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObligationDiff {
+    /// Changed fields, in struct order
+    pub changes: Vec<ObligationChange>,
+}
+
+/// Push `$variant` with the before and after values of a field if they differ
+macro_rules! push_change {
+    ($changes:expr, $variant:expr, $before:expr, $after:expr) => {
+        if $before != $after {
+            $changes.push($variant(Change {
+                before: $before.clone(),
+                after: $after.clone(),
+            }));
+        }
+    };
+}
+
+impl ReserveDiff {
+    /// Compare two snapshots of a reserve
+    pub fn between(a: &Reserve, b: &Reserve) -> Self {
+        use ReserveChange::*;
+        let mut changes = Vec::new();
+
+        push_change!(changes, Version, a.version, b.version);
+        push_change!(
+            changes,
+            LastUpdateSlot,
+            a.last_update.slot,
+            b.last_update.slot
+        );
+        push_change!(
+            changes,
+            LastUpdateStale,
+            a.last_update.stale,
+            b.last_update.stale
+        );
+        push_change!(changes, LendingMarket, a.lending_market, b.lending_market);
+
+        let (al, bl) = (&a.liquidity, &b.liquidity);
+        push_change!(changes, LiquidityMintPubkey, al.mint_pubkey, bl.mint_pubkey);
+        push_change!(
+            changes,
+            LiquidityMintDecimals,
+            al.mint_decimals,
+            bl.mint_decimals
+        );
+        push_change!(
+            changes,
+            LiquiditySupplyPubkey,
+            al.supply_pubkey,
+            bl.supply_pubkey
+        );
+        push_change!(
+            changes,
+            PythOraclePubkey,
+            al.pyth_oracle_pubkey,
+            bl.pyth_oracle_pubkey
+        );
+        push_change!(
+            changes,
+            SwitchboardOraclePubkey,
+            al.switchboard_oracle_pubkey,
+            bl.switchboard_oracle_pubkey
+        );
+        push_change!(
+            changes,
+            AvailableAmount,
+            al.available_amount,
+            bl.available_amount
+        );
+        push_change!(
+            changes,
+            BorrowedAmountWads,
+            al.borrowed_amount_wads,
+            bl.borrowed_amount_wads
+        );
+        push_change!(
+            changes,
+            CumulativeBorrowRateWads,
+            al.cumulative_borrow_rate_wads,
+            bl.cumulative_borrow_rate_wads
+        );
+        push_change!(
+            changes,
+            AccumulatedProtocolFeesWads,
+            al.accumulated_protocol_fees_wads,
+            bl.accumulated_protocol_fees_wads
+        );
+        push_change!(changes, MarketPrice, al.market_price, bl.market_price);
+        push_change!(
+            changes,
+            SmoothedMarketPrice,
+            al.smoothed_market_price,
+            bl.smoothed_market_price
+        );
+        push_change!(
+            changes,
+            ExtraMarketPrice,
+            al.extra_market_price,
+            bl.extra_market_price
+        );
+
+        let (ac, bc) = (&a.collateral, &b.collateral);
+        push_change!(
+            changes,
+            CollateralMintPubkey,
+            ac.mint_pubkey,
+            bc.mint_pubkey
+        );
+        push_change!(
+            changes,
+            CollateralMintTotalSupply,
+            ac.mint_total_supply,
+            bc.mint_total_supply
+        );
+        push_change!(
+            changes,
+            CollateralSupplyPubkey,
+            ac.supply_pubkey,
+            bc.supply_pubkey
+        );
+        push_change!(
+            changes,
+            CollateralDecimalsOffset,
+            ac.decimals_offset,
+            bc.decimals_offset
+        );
+
+        if a.config != b.config {
+            changes.push(Config(Box::new(Change {
+                before: a.config,
+                after: b.config,
+            })));
+        }
+        if a.rate_limiter != b.rate_limiter {
+            changes.push(RateLimiter(Box::new(Change {
+                before: a.rate_limiter,
+                after: b.rate_limiter,
+            })));
+        }
+
+        push_change!(
+            changes,
+            AttributedBorrowValue,
+            a.attributed_borrow_value,
+            b.attributed_borrow_value
+        );
+        push_change!(
+            changes,
+            EmergencyUtilizationThreshold,
+            a.emergency_utilization_threshold,
+            b.emergency_utilization_threshold
+        );
+        push_change!(
+            changes,
+            EmergencyUtilizationSlots,
+            a.emergency_utilization_slots,
+            b.emergency_utilization_slots
+        );
+        push_change!(
+            changes,
+            HighUtilizationSlots,
+            a.high_utilization_slots,
+            b.high_utilization_slots
+        );
+        push_change!(changes, EmergencyMode, a.emergency_mode, b.emergency_mode);
+        push_change!(
+            changes,
+            EmergencyPriceActive,
+            a.emergency_price_active,
+            b.emergency_price_active
+        );
+        push_change!(
+            changes,
+            TertiaryOraclePubkey,
+            a.tertiary_oracle_pubkey,
+            b.tertiary_oracle_pubkey
+        );
+        push_change!(
+            changes,
+            HasPairBorrowWeights,
+            a.has_pair_borrow_weights,
+            b.has_pair_borrow_weights
+        );
+        push_change!(
+            changes,
+            HasInsuranceFund,
+            a.has_insurance_fund,
+            b.has_insurance_fund
+        );
+        push_change!(
+            changes,
+            HasSpreadFeeReceiver,
+            a.has_spread_fee_receiver,
+            b.has_spread_fee_receiver
+        );
+
+        Self { changes }
+    }
+
+    /// True if the snapshots are identical
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl ObligationDiff {
+    /// Compare two snapshots of an obligation
+    pub fn between(a: &Obligation, b: &Obligation) -> Self {
+        use ObligationChange::*;
+        let mut changes = Vec::new();
+
+        push_change!(changes, Version, a.version, b.version);
+        push_change!(
+            changes,
+            LastUpdateSlot,
+            a.last_update.slot,
+            b.last_update.slot
+        );
+        push_change!(
+            changes,
+            LastUpdateStale,
+            a.last_update.stale,
+            b.last_update.stale
+        );
+        push_change!(changes, LendingMarket, a.lending_market, b.lending_market);
+        push_change!(changes, Owner, a.owner, b.owner);
+
+        for before in &a.deposits {
+            let reserve = before.deposit_reserve;
+            match b.deposits.iter().find(|d| d.deposit_reserve == reserve) {
+                Some(after) => {
+                    push_change!(
+                        changes,
+                        |c| DepositedAmount(reserve, c),
+                        before.deposited_amount,
+                        after.deposited_amount
+                    );
+                    push_change!(
+                        changes,
+                        |c| DepositMarketValue(reserve, c),
+                        before.market_value,
+                        after.market_value
+                    );
+                    push_change!(
+                        changes,
+                        |c| DepositAttributedBorrowValue(reserve, c),
+                        before.attributed_borrow_value,
+                        after.attributed_borrow_value
+                    );
+                }
+                None => changes.push(DepositRemoved(before.clone())),
+            }
+        }
+        for after in &b.deposits {
+            if !a
+                .deposits
+                .iter()
+                .any(|d| d.deposit_reserve == after.deposit_reserve)
+            {
+                changes.push(DepositAdded(after.clone()));
+            }
+        }
+
+        for before in &a.borrows {
+            let reserve = before.borrow_reserve;
+            match b.borrows.iter().find(|l| l.borrow_reserve == reserve) {
+                Some(after) => {
+                    push_change!(
+                        changes,
+                        |c| BorrowCumulativeBorrowRateWads(reserve, c),
+                        before.cumulative_borrow_rate_wads,
+                        after.cumulative_borrow_rate_wads
+                    );
+                    push_change!(
+                        changes,
+                        |c| BorrowedAmountWads(reserve, c),
+                        before.borrowed_amount_wads,
+                        after.borrowed_amount_wads
+                    );
+                    push_change!(
+                        changes,
+                        |c| BorrowMarketValue(reserve, c),
+                        before.market_value,
+                        after.market_value
+                    );
+                }
+                None => changes.push(BorrowRemoved(before.clone())),
+            }
+        }
+        for after in &b.borrows {
+            if !a
+                .borrows
+                .iter()
+                .any(|l| l.borrow_reserve == after.borrow_reserve)
+            {
+                changes.push(BorrowAdded(after.clone()));
+            }
+        }
+
+        push_change!(
+            changes,
+            DepositedValue,
+            a.deposited_value,
+            b.deposited_value
+        );
+        push_change!(changes, BorrowedValue, a.borrowed_value, b.borrowed_value);
+        push_change!(
+            changes,
+            UnweightedBorrowedValue,
+            a.unweighted_borrowed_value,
+            b.unweighted_borrowed_value
+        );
+        push_change!(
+            changes,
+            BorrowedValueUpperBound,
+            a.borrowed_value_upper_bound,
+            b.borrowed_value_upper_bound
+        );
+        push_change!(
+            changes,
+            AllowedBorrowValue,
+            a.allowed_borrow_value,
+            b.allowed_borrow_value
+        );
+        push_change!(
+            changes,
+            UnhealthyBorrowValue,
+            a.unhealthy_borrow_value,
+            b.unhealthy_borrow_value
+        );
+        push_change!(
+            changes,
+            SuperUnhealthyBorrowValue,
+            a.super_unhealthy_borrow_value,
+            b.super_unhealthy_borrow_value
+        );
+        push_change!(
+            changes,
+            BorrowingIsolatedAsset,
+            a.borrowing_isolated_asset,
+            b.borrowing_isolated_asset
+        );
+        push_change!(changes, Closeable, a.closeable, b.closeable);
+        push_change!(changes, BorrowsLocked, a.borrows_locked, b.borrows_locked);
+        push_change!(
+            changes,
+            BorrowUnlockSlot,
+            a.borrow_unlock_slot,
+            b.borrow_unlock_slot
+        );
+        push_change!(changes, Referrer, a.referrer, b.referrer);
+        if a.rate_limiter != b.rate_limiter {
+            changes.push(RateLimiter(Box::new(Change {
+                before: a.rate_limiter,
+                after: b.rate_limiter,
+            })));
+        }
+        push_change!(
+            changes,
+            ApprovedBorrower,
+            a.approved_borrower,
+            b.approved_borrower
+        );
+        push_change!(
+            changes,
+            DelegatedValue,
+            a.delegated_value,
+            b.delegated_value
+        );
+
+        Self { changes }
+    }
+
+    /// True if the snapshots are identical
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reserve_diff() {
+        let before = Reserve {
+            last_update: LastUpdate {
+                slot: 10,
+                stale: true,
+            },
+            ..Reserve::default()
+        };
+        assert!(ReserveDiff::between(&before, &before.clone()).is_empty());
+
+        // LastUpdate equality only looks at the slot, the diff doesn't
+        let mut after = before.clone();
+        after.last_update.stale = false;
+        after.liquidity.available_amount = 5;
+        after.liquidity.extra_market_price = Some(Decimal::one());
+        after.config.protocol_take_rate = 10;
+        assert_eq!(
+            ReserveDiff::between(&before, &after).changes,
+            vec![
+                ReserveChange::LastUpdateStale(Change {
+                    before: true,
+                    after: false
+                }),
+                ReserveChange::AvailableAmount(Change {
+                    before: 0,
+                    after: 5
+                }),
+                ReserveChange::ExtraMarketPrice(Change {
+                    before: None,
+                    after: Some(Decimal::one())
+                }),
+                ReserveChange::Config(Box::new(Change {
+                    before: before.config,
+                    after: after.config
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn obligation_diff() {
+        let usdc_reserve = Pubkey::new_unique();
+        let sol_reserve = Pubkey::new_unique();
+        let before = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposited_amount: 100,
+                ..ObligationCollateral::new(usdc_reserve)
+            }],
+            borrows: vec![ObligationLiquidity::new(sol_reserve, Decimal::one())],
+            ..Obligation::default()
+        };
+        assert!(ObligationDiff::between(&before, &before.clone()).is_empty());
+
+        let sol_deposit = ObligationCollateral {
+            deposited_amount: 1,
+            ..ObligationCollateral::new(sol_reserve)
+        };
+        let after = Obligation {
+            deposits: vec![
+                sol_deposit.clone(),
+                ObligationCollateral {
+                    deposited_amount: 60,
+                    ..ObligationCollateral::new(usdc_reserve)
+                },
+            ],
+            borrows: vec![],
+            closeable: true,
+            ..before.clone()
+        };
+        assert_eq!(
+            ObligationDiff::between(&before, &after).changes,
+            vec![
+                ObligationChange::DepositedAmount(
+                    usdc_reserve,
+                    Change {
+                        before: 100,
+                        after: 60
+                    }
+                ),
+                ObligationChange::DepositAdded(sol_deposit),
+                ObligationChange::BorrowRemoved(before.borrows[0].clone()),
+                ObligationChange::Closeable(Change {
+                    before: false,
+                    after: true
+                }),
+            ]
+        );
+    }
+}
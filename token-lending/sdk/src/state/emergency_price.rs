@@ -0,0 +1,135 @@
+use super::*;
+use crate::math::Decimal;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the reserve address to derive its emergency price account
+pub const EMERGENCY_PRICE_SEED: &[u8] = b"EmergencyPrice";
+
+/// Max number of slots an emergency price stays usable for after being set (~1 day)
+pub const MAX_EMERGENCY_PRICE_DURATION_SLOTS: u64 = SLOTS_PER_YEAR / 365;
+
+/// Price set by the market's risk authority for a reserve, used in place of the oracle price
+/// while both of the reserve's oracles are unavailable
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmergencyPrice {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the emergency price account address
+    pub bump_seed: u8,
+    /// Reserve the price applies to
+    pub reserve: Pubkey,
+    /// Price of one whole liquidity token in the quote currency, before the reserve's price
+    /// scale is applied
+    pub price: Decimal,
+    /// Slot the price was set in
+    pub set_slot: Slot,
+    /// Slot from which the price can no longer be used
+    pub expiry_slot: Slot,
+}
+
+impl EmergencyPrice {
+    /// Whether the price can still be used in the given slot
+    pub fn is_active(&self, slot: Slot) -> bool {
+        slot < self.expiry_slot
+    }
+}
+
+/// Find the emergency price account address for a reserve
+pub fn find_emergency_price_address(program_id: &Pubkey, reserve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[reserve.as_ref(), EMERGENCY_PRICE_SEED], program_id)
+}
+
+impl Sealed for EmergencyPrice {}
+impl IsInitialized for EmergencyPrice {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const EMERGENCY_PRICE_LEN: usize = 130; // 1 + 1 + 1 + 32 + 16 + 8 + 8 + 63
+impl Pack for EmergencyPrice {
+    const LEN: usize = EMERGENCY_PRICE_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, EMERGENCY_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, reserve, price, set_slot, expiry_slot, _padding) =
+            mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, 16, 8, 8, 63];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::EmergencyPrice as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        reserve.copy_from_slice(self.reserve.as_ref());
+        pack_decimal(self.price, price);
+        *set_slot = self.set_slot.to_le_bytes();
+        *expiry_slot = self.expiry_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, EMERGENCY_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, reserve, price, set_slot, expiry_slot, _padding) =
+            array_refs![input, 1, 1, 1, PUBKEY_BYTES, 16, 8, 8, 63];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Emergency price version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::EmergencyPrice)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            reserve: Pubkey::new_from_array(*reserve),
+            price: unpack_decimal(price),
+            set_slot: u64::from_le_bytes(*set_slot),
+            expiry_slot: u64::from_le_bytes(*expiry_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_emergency_price() {
+        let mut rng = rand::thread_rng();
+        let emergency_price = EmergencyPrice {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            reserve: Pubkey::new_unique(),
+            price: Decimal::from_scaled_val(rng.gen()),
+            set_slot: rng.gen(),
+            expiry_slot: rng.gen(),
+        };
+
+        let mut packed = [0u8; EMERGENCY_PRICE_LEN];
+        EmergencyPrice::pack(emergency_price.clone(), &mut packed).unwrap();
+        let unpacked = EmergencyPrice::unpack(&packed).unwrap();
+        assert_eq!(emergency_price, unpacked);
+    }
+
+    #[test]
+    fn emergency_price_expiry() {
+        let emergency_price = EmergencyPrice {
+            version: PROGRAM_VERSION,
+            set_slot: 100,
+            expiry_slot: 200,
+            ..EmergencyPrice::default()
+        };
+
+        assert!(emergency_price.is_active(100));
+        assert!(emergency_price.is_active(199));
+        assert!(!emergency_price.is_active(200));
+    }
+}
@@ -0,0 +1,139 @@
+//! getProgramAccounts filters for lending program accounts.
+//!
+//! Accounts don't store a discriminator, so the account size stands in for one, see AccountTag.
+//! Obligations come in two sizes and a single query can't match both, so obligation filters come
+//! as one list per size, each to be sent as its own query.
+
+use super::*;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::{program_pack::Pack, pubkey::Pubkey};
+
+/// Filters matching lending markets, optionally only those of an owner
+pub fn lending_market_filters(owner: Option<&Pubkey>) -> Vec<RpcFilterType> {
+    let mut filters = vec![RpcFilterType::DataSize(LendingMarket::LEN as u64)];
+    if let Some(owner) = owner {
+        filters.push(memcmp(LENDING_MARKET_OWNER_OFFSET, owner));
+    }
+    filters
+}
+
+/// Filters matching reserves, optionally only those of a lending market
+pub fn reserve_filters(lending_market: Option<&Pubkey>) -> Vec<RpcFilterType> {
+    let mut filters = vec![RpcFilterType::DataSize(Reserve::LEN as u64)];
+    if let Some(lending_market) = lending_market {
+        filters.push(memcmp(RESERVE_LENDING_MARKET_OFFSET, lending_market));
+    }
+    filters
+}
+
+/// Filters matching obligations, optionally only those of a lending market and of an owner.
+/// Returns one list for each obligation size.
+pub fn obligation_filters(
+    lending_market: Option<&Pubkey>,
+    owner: Option<&Pubkey>,
+) -> Vec<Vec<RpcFilterType>> {
+    [Obligation::LEN, OBLIGATION_EXTENDED_LEN]
+        .iter()
+        .map(|len| {
+            let mut filters = vec![RpcFilterType::DataSize(*len as u64)];
+            if let Some(lending_market) = lending_market {
+                filters.push(memcmp(OBLIGATION_LENDING_MARKET_OFFSET, lending_market));
+            }
+            if let Some(owner) = owner {
+                filters.push(memcmp(OBLIGATION_OWNER_OFFSET, owner));
+            }
+            filters
+        })
+        .collect()
+}
+
+fn memcmp(offset: usize, pubkey: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, pubkey.as_ref()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrayref::array_ref;
+    use solana_program::pubkey::PUBKEY_BYTES;
+
+    fn pubkey_at(data: &[u8], offset: usize) -> Pubkey {
+        Pubkey::new_from_array(*array_ref![data, offset, PUBKEY_BYTES])
+    }
+
+    #[test]
+    fn offsets_match_pack_layouts() {
+        let lending_market = LendingMarket {
+            version: PROGRAM_VERSION,
+            owner: Pubkey::new_unique(),
+            ..LendingMarket::default()
+        };
+        let mut data = vec![0u8; LendingMarket::LEN];
+        lending_market.pack_into_slice(&mut data);
+        assert_eq!(
+            pubkey_at(&data, LENDING_MARKET_OWNER_OFFSET),
+            lending_market.owner
+        );
+
+        let reserve = Reserve {
+            version: PROGRAM_VERSION,
+            lending_market: Pubkey::new_unique(),
+            liquidity: ReserveLiquidity {
+                mint_pubkey: Pubkey::new_unique(),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                mint_pubkey: Pubkey::new_unique(),
+                ..ReserveCollateral::default()
+            },
+            ..Reserve::default()
+        };
+        let mut data = vec![0u8; Reserve::LEN];
+        reserve.pack_into_slice(&mut data);
+        assert_eq!(
+            pubkey_at(&data, RESERVE_LENDING_MARKET_OFFSET),
+            reserve.lending_market
+        );
+        assert_eq!(
+            pubkey_at(&data, RESERVE_LIQUIDITY_MINT_OFFSET),
+            reserve.liquidity.mint_pubkey
+        );
+        assert_eq!(
+            pubkey_at(&data, RESERVE_COLLATERAL_MINT_OFFSET),
+            reserve.collateral.mint_pubkey
+        );
+
+        let obligation = Obligation {
+            version: PROGRAM_VERSION,
+            lending_market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            ..Obligation::default()
+        };
+        for len in [Obligation::LEN, OBLIGATION_EXTENDED_LEN] {
+            let mut data = vec![0u8; len];
+            obligation.pack_into_slice(&mut data);
+            assert_eq!(
+                pubkey_at(&data, OBLIGATION_LENDING_MARKET_OFFSET),
+                obligation.lending_market
+            );
+            assert_eq!(pubkey_at(&data, OBLIGATION_OWNER_OFFSET), obligation.owner);
+        }
+    }
+
+    #[test]
+    fn obligation_filters_cover_both_sizes() {
+        let owner = Pubkey::new_unique();
+        let filters = obligation_filters(None, Some(&owner));
+        assert_eq!(filters.len(), 2);
+        assert_eq!(
+            filters[1],
+            vec![
+                RpcFilterType::DataSize(OBLIGATION_EXTENDED_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    OBLIGATION_OWNER_OFFSET,
+                    owner.as_ref()
+                )),
+            ]
+        );
+    }
+}
@@ -7,6 +7,7 @@ pub const STALE_AFTER_SLOTS_ELAPSED: u64 = 1;
 
 /// Last update state
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct LastUpdate {
     /// Last slot when updated
     pub slot: Slot,
@@ -1,6 +1,8 @@
 use super::*;
+use crate::spl_associated_token_account;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
+    entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
@@ -9,28 +11,101 @@ use solana_program::{
 
 /// Lending market state
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct LendingMarket {
     /// Version of lending market
     pub version: u8,
     /// Bump seed for derived authority address
     pub bump_seed: u8,
     /// Owner authority which can add new reserves
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub owner: Pubkey,
     /// Currency market prices are quoted in
     /// e.g. "USD" null padded (`*b"USD\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"`) or a SPL token mint pubkey
     pub quote_currency: [u8; 32],
     /// Token program id
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub token_program_id: Pubkey,
     /// Oracle (Pyth) program id
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub oracle_program_id: Pubkey,
     /// Oracle (Switchboard) program id
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub switchboard_oracle_program_id: Pubkey,
     /// Outflow rate limiter denominated in dollars
     pub rate_limiter: RateLimiter,
     /// whitelisted liquidator
+    #[cfg_attr(
+        feature = "serialize",
+        serde(with = "crate::state::serialize::option_pubkey")
+    )]
     pub whitelisted_liquidator: Option<Pubkey>,
     /// risk authority (additional pubkey used for setting params)
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub risk_authority: Pubkey,
+    /// Max number of obligations an owner can initialize in this market, counted by the owner's
+    /// obligation counter account. 0 means no limit
+    pub max_obligations_per_owner: u16,
+    /// Borrow value, in whole USD, at or below which a liquidation repays the whole borrow
+    /// regardless of the close factor. 0 to use the default of DEFAULT_DUST_BORROW_VALUE
+    pub dust_borrow_value: u64,
+    /// Max value, in whole USD, a single obligation can withdraw and borrow per window of the
+    /// market rate limiter. Obligations then need the current layout to withdraw or borrow.
+    /// 0 means no limit
+    pub obligation_max_outflow: u64,
+    /// Whether only wallets with a market member account can deposit, borrow and initialize
+    /// obligations in this market
+    pub whitelist_required: bool,
+    /// Whether liquidations are restricted to the liquidator whitelist account's liquidators for
+    /// its protected window after an obligation becomes unhealthy
+    pub has_liquidator_whitelist: bool,
+    /// Authority allowed to withdraw protocol fees - Pubkey::default() until SetProtocolFeeConfig
+    /// sets it
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
+    pub fee_authority: Pubkey,
+    /// Wallet whose associated token accounts receive the withdrawn protocol fees
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
+    pub treasury: Pubkey,
+    /// Outflow rate limiter of protocol fee withdrawals, denominated in the quote currency
+    pub protocol_fee_rate_limiter: RateLimiter,
+    /// Share of redeemed interest spread sent to the insurance fund instead of the reserve fee
+    /// receiver, in basis points. Only paid by reserves with an insurance fund
+    pub insurance_fee_bps: u16,
+    /// Wallet whose associated token accounts are the only host fee receivers borrows without a
+    /// referrer can pay the host fee to
+    #[cfg_attr(
+        feature = "serialize",
+        serde(with = "crate::state::serialize::option_pubkey")
+    )]
+    pub default_host_fee_receiver: Option<Pubkey>,
+}
+
+/// Borrow value, in USD, at or below which a liquidation can close the whole borrow when the
+/// lending market doesn't set its own
+pub const DEFAULT_DUST_BORROW_VALUE: u64 = 1;
+
+/// Seed used with the lending market and liquidity mint addresses to derive the market's
+/// insurance fund token account for the mint
+pub const INSURANCE_FUND_SEED: &[u8] = b"InsuranceFund";
+
+/// Highest share of redeemed interest spread that can go to the insurance fund, in basis points
+pub const MAX_INSURANCE_FEE_BPS: u16 = 10_000;
+
+/// Find the insurance fund token account address of a lending market for a liquidity mint. The
+/// account is owned by the lending market authority.
+pub fn find_insurance_fund_address(
+    program_id: &Pubkey,
+    lending_market: &Pubkey,
+    liquidity_mint: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            lending_market.as_ref(),
+            liquidity_mint.as_ref(),
+            INSURANCE_FUND_SEED,
+        ],
+        program_id,
+    )
 }
 
 impl LendingMarket {
@@ -53,9 +128,83 @@ impl LendingMarket {
         self.rate_limiter = RateLimiter::default();
         self.whitelisted_liquidator = None;
         self.risk_authority = params.owner;
+        self.max_obligations_per_owner = 0;
+        self.dust_borrow_value = 0;
+        self.obligation_max_outflow = 0;
+        self.whitelist_required = false;
+        self.has_liquidator_whitelist = false;
+        self.fee_authority = Pubkey::default();
+        self.treasury = Pubkey::default();
+        self.protocol_fee_rate_limiter = RateLimiter::default();
+        self.insurance_fee_bps = 0;
+        self.default_host_fee_receiver = None;
+    }
+
+    /// Address of the lending market authority, derived with the stored bump seed rather than
+    /// searched for
+    pub fn authority(
+        &self,
+        program_id: &Pubkey,
+        lending_market_pubkey: &Pubkey,
+    ) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::create_program_address(
+            &[lending_market_pubkey.as_ref(), &[self.bump_seed]],
+            program_id,
+        )?)
+    }
+
+    /// Borrow value at or below which a liquidation repays the whole borrow
+    pub fn dust_borrow_value(&self) -> Decimal {
+        match self.dust_borrow_value {
+            0 => Decimal::from(DEFAULT_DUST_BORROW_VALUE),
+            value => Decimal::from(value),
+        }
+    }
+
+    /// Whether withdraws and borrows are limited per obligation. Needs the market rate limiter's
+    /// window, so it's off while the market rate limiter is disabled.
+    pub fn is_obligation_rate_limited(&self) -> bool {
+        self.obligation_max_outflow > 0 && self.rate_limiter.config.window_duration > 0
+    }
+
+    /// Config of the rate limiter of each obligation
+    pub fn obligation_rate_limiter_config(&self) -> RateLimiterConfig {
+        RateLimiterConfig {
+            window_duration: self.rate_limiter.config.window_duration,
+            max_outflow: self.obligation_max_outflow,
+        }
+    }
+
+    /// Part of a redeemed interest spread that goes to the insurance fund
+    pub fn insurance_share(&self, liquidity_amount: u64) -> u64 {
+        (liquidity_amount as u128 * self.insurance_fee_bps as u128 / 10_000) as u64
+    }
+
+    /// Associated token account of the treasury for a liquidity mint
+    pub fn treasury_token_address(&self, token_program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+        associated_token_address(&self.treasury, token_program_id, mint)
+    }
+
+    /// Associated token account of the default host fee receiver for a liquidity mint, if the
+    /// lending market has one
+    pub fn default_host_fee_receiver_token_address(
+        &self,
+        token_program_id: &Pubkey,
+        mint: &Pubkey,
+    ) -> Option<Pubkey> {
+        self.default_host_fee_receiver
+            .map(|wallet| associated_token_address(&wallet, token_program_id, mint))
     }
 }
 
+fn associated_token_address(wallet: &Pubkey, token_program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program_id.as_ref(), mint.as_ref()],
+        &spl_associated_token_account::id(),
+    )
+    .0
+}
+
 /// Initialize a lending market
 pub struct InitLendingMarketParams {
     /// Bump seed for derived authority address
@@ -80,10 +229,46 @@ impl IsInitialized for LendingMarket {
     }
 }
 
-const LENDING_MARKET_LEN: usize = 290; // 1 + 1 + 32 + 32 + 32 + 32 + 32 + 56 + 32 + 40
+/// Offset of the owner in lending market accounts, for getProgramAccounts memcmp filters
+pub const LENDING_MARKET_OWNER_OFFSET: usize = 2;
+
+/// Size of lending market accounts created before the V2 layout. The program reads and writes
+/// them as the start of the current layout until MigrateAccount reallocates them to
+/// LendingMarket::LEN.
+pub const LENDING_MARKET_LEN_V1: usize = 290; // 1 + 1 + 32 + 32 + 32 + 32 + 32 + 56 + 32 + 40
+
+// V1 layout followed by the V2 fields: 2 + 8 + 8 + 1 + 1 + 32 + 32 + 56 + 2 + 32 + 48 (padding)
+const LENDING_MARKET_LEN: usize = 512;
 impl Pack for LendingMarket {
     const LEN: usize = LENDING_MARKET_LEN;
 
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.len() {
+            LENDING_MARKET_LEN => Self::unpack_from_slice(input),
+            LENDING_MARKET_LEN_V1 => {
+                unpack_v1_layout(input, LENDING_MARKET_LEN, Self::unpack_from_slice)
+            }
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    fn pack(src: Self, dst: &mut [u8]) -> ProgramResult {
+        match dst.len() {
+            LENDING_MARKET_LEN => {
+                src.pack_into_slice(dst);
+                Ok(())
+            }
+            LENDING_MARKET_LEN_V1 => pack_v1_layout(
+                &src,
+                dst,
+                LENDING_MARKET_LEN,
+                Self::pack_into_slice,
+                Self::unpack_from_slice,
+            ),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
     fn pack_into_slice(&self, output: &mut [u8]) {
         let output = array_mut_ref![output, 0, LENDING_MARKET_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
@@ -98,6 +283,17 @@ impl Pack for LendingMarket {
             rate_limiter,
             whitelisted_liquidator,
             risk_authority,
+            _padding_v1,
+            max_obligations_per_owner,
+            dust_borrow_value,
+            obligation_max_outflow,
+            whitelist_required,
+            has_liquidator_whitelist,
+            fee_authority,
+            treasury,
+            protocol_fee_rate_limiter,
+            insurance_fee_bps,
+            default_host_fee_receiver,
             _padding,
         ) = mut_array_refs![
             output,
@@ -111,7 +307,18 @@ impl Pack for LendingMarket {
             RATE_LIMITER_LEN,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
-            8
+            8,
+            2,
+            8,
+            8,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            RATE_LIMITER_LEN,
+            2,
+            PUBKEY_BYTES,
+            48
         ];
 
         *version = self.version.to_le_bytes();
@@ -131,6 +338,22 @@ impl Pack for LendingMarket {
             }
         }
         risk_authority.copy_from_slice(self.risk_authority.as_ref());
+
+        // v2
+        *max_obligations_per_owner = self.max_obligations_per_owner.to_le_bytes();
+        *dust_borrow_value = self.dust_borrow_value.to_le_bytes();
+        *obligation_max_outflow = self.obligation_max_outflow.to_le_bytes();
+        pack_bool(self.whitelist_required, whitelist_required);
+        pack_bool(self.has_liquidator_whitelist, has_liquidator_whitelist);
+        fee_authority.copy_from_slice(self.fee_authority.as_ref());
+        treasury.copy_from_slice(self.treasury.as_ref());
+        self.protocol_fee_rate_limiter
+            .pack_into_slice(protocol_fee_rate_limiter);
+        *insurance_fee_bps = self.insurance_fee_bps.to_le_bytes();
+        match self.default_host_fee_receiver {
+            Some(pubkey) => default_host_fee_receiver.copy_from_slice(pubkey.as_ref()),
+            None => default_host_fee_receiver.copy_from_slice(&[0u8; PUBKEY_BYTES]),
+        };
     }
 
     /// Unpacks a byte buffer into a [LendingMarketInfo](struct.LendingMarketInfo.html)
@@ -148,6 +371,17 @@ impl Pack for LendingMarket {
             rate_limiter,
             whitelisted_liquidator,
             risk_authority,
+            _padding_v1,
+            max_obligations_per_owner,
+            dust_borrow_value,
+            obligation_max_outflow,
+            whitelist_required,
+            has_liquidator_whitelist,
+            fee_authority,
+            treasury,
+            protocol_fee_rate_limiter,
+            insurance_fee_bps,
+            default_host_fee_receiver,
             _padding,
         ) = array_refs![
             input,
@@ -161,9 +395,21 @@ impl Pack for LendingMarket {
             RATE_LIMITER_LEN,
             PUBKEY_BYTES,
             PUBKEY_BYTES,
-            8
+            8,
+            2,
+            8,
+            8,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            RATE_LIMITER_LEN,
+            2,
+            PUBKEY_BYTES,
+            48
         ];
 
+        // version 1 lending markets are read with the v2 fields zeroed, which leaves them disabled
         let version = u8::from_le_bytes(*version);
         if version > PROGRAM_VERSION {
             msg!("Lending market version does not match lending program version");
@@ -193,6 +439,20 @@ impl Pack for LendingMarket {
             } else {
                 Pubkey::new_from_array(*risk_authority)
             },
+            max_obligations_per_owner: u16::from_le_bytes(*max_obligations_per_owner),
+            dust_borrow_value: u64::from_le_bytes(*dust_borrow_value),
+            obligation_max_outflow: u64::from_le_bytes(*obligation_max_outflow),
+            whitelist_required: unpack_bool(whitelist_required)?,
+            has_liquidator_whitelist: unpack_bool(has_liquidator_whitelist)?,
+            fee_authority: Pubkey::new_from_array(*fee_authority),
+            treasury: Pubkey::new_from_array(*treasury),
+            protocol_fee_rate_limiter: RateLimiter::unpack_from_slice(protocol_fee_rate_limiter)?,
+            insurance_fee_bps: u16::from_le_bytes(*insurance_fee_bps),
+            default_host_fee_receiver: if default_host_fee_receiver == &[0; PUBKEY_BYTES] {
+                None
+            } else {
+                Some(Pubkey::new_from_array(*default_host_fee_receiver))
+            },
         })
     }
 }
@@ -200,6 +460,7 @@ impl Pack for LendingMarket {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::error::LendingError;
     use rand::Rng;
 
     #[test]
@@ -220,6 +481,20 @@ mod test {
                 Some(Pubkey::new_unique())
             },
             risk_authority: Pubkey::new_unique(),
+            max_obligations_per_owner: rng.gen(),
+            dust_borrow_value: rng.gen(),
+            obligation_max_outflow: rng.gen(),
+            whitelist_required: rng.gen(),
+            has_liquidator_whitelist: rng.gen(),
+            fee_authority: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            protocol_fee_rate_limiter: rand_rate_limiter(),
+            insurance_fee_bps: rng.gen(),
+            default_host_fee_receiver: if rng.gen_bool(0.5) {
+                Some(Pubkey::new_unique())
+            } else {
+                None
+            },
         };
 
         let mut packed = vec![0u8; LendingMarket::LEN];
@@ -227,4 +502,68 @@ mod test {
         let unpacked = LendingMarket::unpack_from_slice(&packed).unwrap();
         assert_eq!(unpacked, lending_market);
     }
+
+    #[test]
+    fn pack_and_unpack_v1_lending_market() {
+        let lending_market = LendingMarket {
+            version: 1,
+            bump_seed: 255,
+            owner: Pubkey::new_unique(),
+            rate_limiter: rand_rate_limiter(),
+            risk_authority: Pubkey::new_unique(),
+            protocol_fee_rate_limiter: RateLimiter::unpack_from_slice(&[0; RATE_LIMITER_LEN])
+                .unwrap(),
+            ..LendingMarket::default()
+        };
+
+        let mut packed = vec![0u8; LENDING_MARKET_LEN_V1];
+        LendingMarket::pack(lending_market.clone(), &mut packed).unwrap();
+        assert_eq!(LendingMarket::unpack(&packed).unwrap(), lending_market);
+
+        // fields added since version 1 don't fit until the account is migrated
+        let lending_market = LendingMarket {
+            dust_borrow_value: 5,
+            ..lending_market
+        };
+        assert_eq!(
+            LendingMarket::pack(lending_market, &mut packed),
+            Err(LendingError::AccountNotMigrated.into())
+        );
+
+        // new lending markets need the current layout
+        assert_eq!(
+            LendingMarket::unpack_unchecked(&[0u8; LENDING_MARKET_LEN_V1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn insurance_share() {
+        let lending_market = LendingMarket {
+            insurance_fee_bps: 2_000,
+            ..LendingMarket::default()
+        };
+        assert_eq!(lending_market.insurance_share(1_000), 200);
+        assert_eq!(lending_market.insurance_share(4), 0);
+        assert_eq!(lending_market.insurance_share(u64::MAX), u64::MAX / 5);
+    }
+
+    #[test]
+    fn authority_uses_stored_bump_seed() {
+        let program_id = Pubkey::new_unique();
+        let lending_market_pubkey = Pubkey::new_unique();
+        let (authority, bump_seed) =
+            Pubkey::find_program_address(&[lending_market_pubkey.as_ref()], &program_id);
+
+        let lending_market = LendingMarket {
+            bump_seed,
+            ..LendingMarket::default()
+        };
+        assert_eq!(
+            lending_market
+                .authority(&program_id, &lending_market_pubkey)
+                .unwrap(),
+            authority
+        );
+    }
 }
@@ -0,0 +1,202 @@
+use super::*;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the lending market address to derive its liquidator whitelist account
+pub const LIQUIDATOR_WHITELIST_SEED: &[u8] = b"LiquidatorWhitelist";
+
+/// Max number of liquidators a liquidator whitelist can hold
+pub const MAX_WHITELISTED_LIQUIDATORS: usize = 8;
+
+/// Liquidators of a lending market that get a protected window on unhealthy obligations. For
+/// the first `protected_window_slots` after an obligation becomes unhealthy only they can
+/// liquidate it, after which liquidations are permissionless again. Set by the lending market
+/// owner.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LiquidatorWhitelist {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the liquidator whitelist account address
+    pub bump_seed: u8,
+    /// Lending market the whitelist is for
+    pub lending_market: Pubkey,
+    /// Slots after an obligation becomes unhealthy during which only whitelisted liquidators can
+    /// liquidate it
+    pub protected_window_slots: u64,
+    /// Whitelisted liquidators
+    pub liquidators: Vec<Pubkey>,
+}
+
+/// Find the liquidator whitelist account address for a lending market
+pub fn find_liquidator_whitelist_address(
+    program_id: &Pubkey,
+    lending_market: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[lending_market.as_ref(), LIQUIDATOR_WHITELIST_SEED],
+        program_id,
+    )
+}
+
+impl LiquidatorWhitelist {
+    /// Check if a liquidator can liquidate an obligation that became unhealthy at
+    /// `unhealthy_since_slot`
+    pub fn can_liquidate(
+        &self,
+        liquidator: &Pubkey,
+        unhealthy_since_slot: Slot,
+        slot: Slot,
+    ) -> bool {
+        slot >= unhealthy_since_slot.saturating_add(self.protected_window_slots)
+            || self.liquidators.contains(liquidator)
+    }
+}
+
+impl Sealed for LiquidatorWhitelist {}
+impl IsInitialized for LiquidatorWhitelist {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const LIQUIDATOR_WHITELIST_LEN: usize = 363; // 1 + 1 + 1 + 32 + 8 + 1 + (32 * 8) + 63
+impl Pack for LiquidatorWhitelist {
+    const LEN: usize = LIQUIDATOR_WHITELIST_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, LIQUIDATOR_WHITELIST_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            lending_market,
+            protected_window_slots,
+            liquidators_len,
+            data_flat,
+            _padding,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            8,
+            1,
+            PUBKEY_BYTES * MAX_WHITELISTED_LIQUIDATORS,
+            63
+        ];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::LiquidatorWhitelist as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        lending_market.copy_from_slice(self.lending_market.as_ref());
+        *protected_window_slots = self.protected_window_slots.to_le_bytes();
+        *liquidators_len = (self.liquidators.len() as u8).to_le_bytes();
+
+        data_flat.fill(0);
+        for (liquidator, data) in self
+            .liquidators
+            .iter()
+            .zip(data_flat.chunks_exact_mut(PUBKEY_BYTES))
+        {
+            data.copy_from_slice(liquidator.as_ref());
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, LIQUIDATOR_WHITELIST_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            lending_market,
+            protected_window_slots,
+            liquidators_len,
+            data_flat,
+            _padding,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            8,
+            1,
+            PUBKEY_BYTES * MAX_WHITELISTED_LIQUIDATORS,
+            63
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Liquidator whitelist version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::LiquidatorWhitelist)?;
+
+        let liquidators_len = u8::from_le_bytes(*liquidators_len) as usize;
+        if liquidators_len > MAX_WHITELISTED_LIQUIDATORS {
+            msg!("Liquidator whitelist length is invalid");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let liquidators = data_flat
+            .chunks_exact(PUBKEY_BYTES)
+            .take(liquidators_len)
+            .map(|data| Pubkey::new_from_array(*array_ref![data, 0, PUBKEY_BYTES]))
+            .collect();
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            lending_market: Pubkey::new_from_array(*lending_market),
+            protected_window_slots: u64::from_le_bytes(*protected_window_slots),
+            liquidators,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_liquidator_whitelist() {
+        let mut rng = rand::thread_rng();
+        let liquidator_whitelist = LiquidatorWhitelist {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            lending_market: Pubkey::new_unique(),
+            protected_window_slots: rng.gen(),
+            liquidators: (0..rng.gen_range(0..=MAX_WHITELISTED_LIQUIDATORS))
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+        };
+
+        let mut packed = [0u8; LIQUIDATOR_WHITELIST_LEN];
+        LiquidatorWhitelist::pack(liquidator_whitelist.clone(), &mut packed).unwrap();
+        let unpacked = LiquidatorWhitelist::unpack(&packed).unwrap();
+        assert_eq!(liquidator_whitelist, unpacked);
+    }
+
+    #[test]
+    fn can_liquidate_after_protected_window() {
+        let liquidator = Pubkey::new_unique();
+        let liquidator_whitelist = LiquidatorWhitelist {
+            protected_window_slots: 10,
+            liquidators: vec![liquidator],
+            ..LiquidatorWhitelist::default()
+        };
+
+        assert!(liquidator_whitelist.can_liquidate(&liquidator, 100, 100));
+        assert!(!liquidator_whitelist.can_liquidate(&Pubkey::new_unique(), 100, 109));
+        assert!(liquidator_whitelist.can_liquidate(&Pubkey::new_unique(), 100, 110));
+    }
+}
@@ -0,0 +1,111 @@
+use super::*;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the lending market and member addresses to derive a market member account
+pub const MARKET_MEMBER_SEED: &[u8] = b"MarketMember";
+
+/// Membership of a wallet in a lending market that requires a whitelist, eg a KYC'd pool. Only
+/// members can deposit, borrow and initialize obligations in such a market. Set by the lending
+/// market owner.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MarketMember {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the market member account address
+    pub bump_seed: u8,
+    /// Lending market the membership is for
+    pub lending_market: Pubkey,
+    /// Wallet of the member
+    pub member: Pubkey,
+    /// Whether the wallet is currently a member, false once the owner revoked the membership
+    pub is_member: bool,
+}
+
+/// Find the market member account address for a wallet in a lending market
+pub fn find_market_member_address(
+    program_id: &Pubkey,
+    lending_market: &Pubkey,
+    member: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[lending_market.as_ref(), member.as_ref(), MARKET_MEMBER_SEED],
+        program_id,
+    )
+}
+
+impl Sealed for MarketMember {}
+impl IsInitialized for MarketMember {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const MARKET_MEMBER_LEN: usize = 131; // 1 + 1 + 1 + 32 + 32 + 1 + 63
+impl Pack for MarketMember {
+    const LEN: usize = MARKET_MEMBER_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, MARKET_MEMBER_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, member, is_member, _padding) =
+            mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 1, 63];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::MarketMember as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        lending_market.copy_from_slice(self.lending_market.as_ref());
+        member.copy_from_slice(self.member.as_ref());
+        pack_bool(self.is_member, is_member);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, MARKET_MEMBER_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, member, is_member, _padding) =
+            array_refs![input, 1, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 1, 63];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Market member version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::MarketMember)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            lending_market: Pubkey::new_from_array(*lending_market),
+            member: Pubkey::new_from_array(*member),
+            is_member: unpack_bool(is_member)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_market_member() {
+        let mut rng = rand::thread_rng();
+        let market_member = MarketMember {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            lending_market: Pubkey::new_unique(),
+            member: Pubkey::new_unique(),
+            is_member: rng.gen(),
+        };
+
+        let mut packed = [0u8; MARKET_MEMBER_LEN];
+        MarketMember::pack(market_member.clone(), &mut packed).unwrap();
+        let unpacked = MarketMember::unpack(&packed).unwrap();
+        assert_eq!(market_member, unpacked);
+    }
+}
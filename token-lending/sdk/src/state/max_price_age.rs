@@ -0,0 +1,104 @@
+use super::*;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the lending market address to derive its max price age account
+pub const MAX_PRICE_AGE_SEED: &[u8] = b"MaxPriceAge";
+
+/// Highest max price age a lending market can set. Prices older than a few slots shouldn't value
+/// an obligation.
+pub const MAX_PRICE_AGE_SLOTS: u8 = 10;
+
+/// Number of slots after a reserve refresh that RefreshObligation still accepts the reserve's
+/// prices, when passed this account. Kept in its own account because the lending market account
+/// has no room left.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MaxPriceAge {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the max price age account address
+    pub bump_seed: u8,
+    /// Lending market the max price age is for
+    pub lending_market: Pubkey,
+    /// Max price age in slots, at most MAX_PRICE_AGE_SLOTS. 0 means reserves must be refreshed in
+    /// the same slot
+    pub max_price_age_slots: u8,
+}
+
+/// Find the max price age account address for a lending market
+pub fn find_max_price_age_address(program_id: &Pubkey, lending_market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[lending_market.as_ref(), MAX_PRICE_AGE_SEED], program_id)
+}
+
+impl Sealed for MaxPriceAge {}
+impl IsInitialized for MaxPriceAge {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const MAX_PRICE_AGE_LEN: usize = 99; // 1 + 1 + 1 + 32 + 1 + 63
+impl Pack for MaxPriceAge {
+    const LEN: usize = MAX_PRICE_AGE_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, MAX_PRICE_AGE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, max_price_age_slots, _padding) =
+            mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, 1, 63];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::MaxPriceAge as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        lending_market.copy_from_slice(self.lending_market.as_ref());
+        *max_price_age_slots = self.max_price_age_slots.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, MAX_PRICE_AGE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, max_price_age_slots, _padding) =
+            array_refs![input, 1, 1, 1, PUBKEY_BYTES, 1, 63];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Max price age version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::MaxPriceAge)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            lending_market: Pubkey::new_from_array(*lending_market),
+            max_price_age_slots: u8::from_le_bytes(*max_price_age_slots),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_max_price_age() {
+        let mut rng = rand::thread_rng();
+        let max_price_age = MaxPriceAge {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            lending_market: Pubkey::new_unique(),
+            max_price_age_slots: rng.gen_range(0..=MAX_PRICE_AGE_SLOTS),
+        };
+
+        let mut packed = [0u8; MAX_PRICE_AGE_LEN];
+        MaxPriceAge::pack(max_price_age.clone(), &mut packed).unwrap();
+        let unpacked = MaxPriceAge::unpack(&packed).unwrap();
+        assert_eq!(max_price_age, unpacked);
+    }
+}
@@ -1,18 +1,68 @@
 //! State types
 
+mod account_tag;
+#[cfg(feature = "anchor")]
+mod anchor;
+mod asset_categories;
+#[cfg(feature = "devnet-utils")]
+mod devnet;
+#[cfg(feature = "client")]
+mod diff;
+mod emergency_price;
+#[cfg(feature = "client")]
+mod filters;
 mod last_update;
 mod lending_market;
 mod lending_market_metadata;
+mod liquidator_whitelist;
+mod market_member;
+mod max_price_age;
 mod obligation;
+mod obligation_counter;
 mod rate_limiter;
 mod reserve;
+mod reserve_activity;
+mod reserve_ctoken_price;
+mod reserve_pair_borrow_weights;
+mod reserve_rate_stats;
+mod reserve_spread_fee_receiver;
+mod rewards;
+#[cfg(feature = "client")]
+mod risk_report;
+#[cfg(feature = "serialize")]
+mod serialize;
+#[cfg(feature = "client")]
+mod simulation;
 
+pub use account_tag::*;
+pub use asset_categories::*;
+#[cfg(feature = "devnet-utils")]
+pub use devnet::*;
+#[cfg(feature = "client")]
+pub use diff::*;
+pub use emergency_price::*;
+#[cfg(feature = "client")]
+pub use filters::*;
 pub use last_update::*;
 pub use lending_market::*;
 pub use lending_market_metadata::*;
+pub use liquidator_whitelist::*;
+pub use market_member::*;
+pub use max_price_age::*;
 pub use obligation::*;
+pub use obligation_counter::*;
 pub use rate_limiter::*;
 pub use reserve::*;
+pub use reserve_activity::*;
+pub use reserve_ctoken_price::*;
+pub use reserve_pair_borrow_weights::*;
+pub use reserve_rate_stats::*;
+pub use reserve_spread_fee_receiver::*;
+pub use rewards::*;
+#[cfg(feature = "client")]
+pub use risk_report::*;
+#[cfg(feature = "client")]
+pub use simulation::*;
 
 use crate::math::{Decimal, WAD};
 use solana_program::{msg, program_error::ProgramError};
@@ -23,7 +73,7 @@ pub const INITIAL_COLLATERAL_RATIO: u64 = 1;
 const INITIAL_COLLATERAL_RATE: u64 = INITIAL_COLLATERAL_RATIO * WAD;
 
 /// Current version of the program and all new accounts created
-pub const PROGRAM_VERSION: u8 = 1;
+pub const PROGRAM_VERSION: u8 = 2;
 
 /// Accounts are created with data zeroed out, so uninitialized state instances
 /// will have the version set to 0.
@@ -1,7 +1,7 @@
 use super::*;
 use crate::{
     error::LendingError,
-    math::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub},
+    math::{Decimal, Rate, SaturatingSub, TryAdd, TryDiv, TryMul, TrySub, U192},
 };
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
@@ -13,23 +13,49 @@ use solana_program::{
     pubkey::{Pubkey, PUBKEY_BYTES},
 };
 use std::{
-    cmp::{min, Ordering},
+    cmp::{max, min, Ordering},
     convert::{TryFrom, TryInto},
 };
 
-/// Max number of collateral and liquidity reserve accounts combined for an obligation
+/// Max number of collateral and liquidity reserve accounts combined for an obligation account of
+/// the standard size, Obligation::LEN
 pub const MAX_OBLIGATION_RESERVES: usize = 10;
 
+/// Max number of collateral and liquidity reserve accounts combined for an obligation account of
+/// the extended size, OBLIGATION_EXTENDED_LEN
+pub const MAX_OBLIGATION_RESERVES_EXTENDED: usize = 24;
+
+/// Number of slots an obligation with no deposits and no borrows must go without an update before
+/// anyone can close it with CrankCloseEmptyObligation. Roughly 90 days.
+pub const EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS: u64 = SLOTS_PER_YEAR / 4;
+
+/// Percentage of a closed empty obligation's lamports paid to the account that cranked it. The
+/// rest goes back to the obligation owner.
+pub const EMPTY_OBLIGATION_CRANK_BOUNTY_PERCENT: u8 = 50;
+
+/// Number of slots between an owner requesting to unlock borrows and the unlock taking effect.
+/// Roughly one day.
+pub const OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS: u64 = SLOTS_PER_YEAR / 365;
+
+/// Cap on the allowed borrow value of a single obligation, in the quote currency
+pub const GLOBAL_ALLOWED_BORROW_VALUE: u64 = 65_000_000;
+
+/// Cap on the unhealthy borrow values of a single obligation, in the quote currency
+pub const GLOBAL_UNHEALTHY_BORROW_VALUE: u64 = 70_000_000;
+
 /// Lending market obligation state
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Obligation {
     /// Version of the struct
     pub version: u8,
     /// Last update to collateral, liquidity, or their market values
     pub last_update: LastUpdate,
     /// Lending market address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub lending_market: Pubkey,
     /// Owner authority which can borrow liquidity
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub owner: Pubkey,
     /// Deposited collateral for the obligation, unique by deposit reserve address
     pub deposits: Vec<ObligationCollateral>,
@@ -63,6 +89,36 @@ pub struct Obligation {
     pub borrowing_isolated_asset: bool,
     /// Obligation can be marked as closeable
     pub closeable: bool,
+    /// True if the owner has locked new borrows from this obligation
+    pub borrows_locked: bool,
+    /// Slot at which a requested borrow unlock takes effect - 0 if no unlock was requested
+    pub borrow_unlock_slot: Slot,
+    /// Referrer credited with the host fees of the obligation's borrows, which must then pay them
+    /// to a token account the referrer owns. Can only be set once
+    pub referrer: Option<Pubkey>,
+    /// Max borrowed value in whole USD, set by the owner - 0 for no cap
+    pub borrow_cap: u32,
+    /// Slot of the first refresh that found the obligation unhealthy - 0 while it's healthy.
+    /// The fixed fields are full, so it's stored in the padding of the first deposit, which
+    /// every obligation that can be liquidated has.
+    pub unhealthy_since_slot: Slot,
+    /// True if the last refresh valued the obligation at its asset category's limits, because
+    /// they are higher than its reserves' own. Refreshes then require the asset categories
+    /// account until the category's limits no longer apply. Stored next to
+    /// unhealthy_since_slot.
+    pub uses_asset_category_limits: bool,
+    /// Slot of the oldest reserve price the last refresh valued the obligation at. With a max
+    /// price age it can be older than last_update, which is always the slot of the refresh.
+    pub oldest_price_slot: Slot,
+    /// Outflow rate limiter of the obligation, counting the value it withdraws and borrows in
+    /// dollars. Only used in lending markets with an obligation max outflow, whose obligations
+    /// must then be migrated to withdraw or borrow.
+    pub rate_limiter: RateLimiter,
+    /// Wallet the owner delegated borrow authority to, which can borrow against the obligation's
+    /// collateral into its own token accounts until the delegated value runs out
+    pub approved_borrower: Option<Pubkey>,
+    /// Value the approved borrower can still borrow, in USD
+    pub delegated_value: Decimal,
 }
 
 impl Obligation {
@@ -81,6 +137,104 @@ impl Obligation {
         self.owner = params.owner;
         self.deposits = params.deposits;
         self.borrows = params.borrows;
+        self.rate_limiter = RateLimiter::default();
+    }
+
+    /// Match the rate limiter to the lending market's obligation rate limiter config, restarting
+    /// it if the config changed since its last outflow
+    pub fn sync_rate_limiter_config(&mut self, lending_market: &LendingMarket, current_slot: Slot) {
+        let config = lending_market.obligation_rate_limiter_config();
+        if self.rate_limiter.config != config {
+            self.rate_limiter = RateLimiter::new(config, current_slot);
+        }
+    }
+
+    /// Count a borrow of the approved borrower against the delegated value
+    pub fn borrow_delegated_value(&mut self, borrow_value: Decimal) -> ProgramResult {
+        if borrow_value > self.delegated_value {
+            msg!(
+                "Borrow value {} exceeds the value of {} delegated to the approved borrower",
+                borrow_value,
+                self.delegated_value
+            );
+            return Err(LendingError::BorrowTooLarge.into());
+        }
+        self.delegated_value = self.delegated_value.try_sub(borrow_value)?;
+        Ok(())
+    }
+
+    /// Check if the obligation has no deposits and no borrows and hasn't been updated for more
+    /// than EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS. Positions are kept until their rewards are claimed,
+    /// so an obligation with unclaimed rewards is never empty.
+    pub fn is_empty_and_idle(&self, slot: Slot) -> Result<bool, ProgramError> {
+        Ok(self.deposits.is_empty()
+            && self.borrows.is_empty()
+            && self.last_update.slots_elapsed(slot)? > EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS)
+    }
+
+    /// Check if new borrows from the obligation are locked at the given slot
+    pub fn is_borrow_locked(&self, slot: Slot) -> bool {
+        self.borrows_locked && (self.borrow_unlock_slot == 0 || slot < self.borrow_unlock_slot)
+    }
+
+    /// Lock borrows immediately, or request an unlock that takes effect after
+    /// OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS. Locking again cancels a pending unlock.
+    pub fn set_borrow_lock(&mut self, borrows_locked: bool, slot: Slot) -> ProgramResult {
+        if borrows_locked {
+            self.borrows_locked = true;
+            self.borrow_unlock_slot = 0;
+        } else if self.is_borrow_locked(slot) && self.borrow_unlock_slot == 0 {
+            self.borrow_unlock_slot = slot
+                .checked_add(OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS)
+                .ok_or(LendingError::MathOverflow)?;
+        } else if !self.is_borrow_locked(slot) {
+            self.borrows_locked = false;
+            self.borrow_unlock_slot = 0;
+        }
+        Ok(())
+    }
+
+    /// Set the borrow cap. Lowering it takes effect immediately, while raising or removing it also
+    /// locks borrows until OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS have passed, so whoever holds the
+    /// owner key can't lift the cap and borrow against it right away.
+    pub fn set_borrow_cap(&mut self, borrow_cap: u32, slot: Slot) -> ProgramResult {
+        let raised = self.borrow_cap != 0 && (borrow_cap == 0 || borrow_cap > self.borrow_cap);
+        if raised {
+            let unlock_slot = slot
+                .checked_add(OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS)
+                .ok_or(LendingError::MathOverflow)?;
+            if !self.is_borrow_locked(slot) {
+                self.borrows_locked = true;
+                self.borrow_unlock_slot = unlock_slot;
+            } else if self.borrow_unlock_slot != 0 {
+                self.borrow_unlock_slot = max(self.borrow_unlock_slot, unlock_slot);
+            }
+        }
+        self.borrow_cap = borrow_cap;
+        Ok(())
+    }
+
+    /// Borrowed value the borrow cap still allows, in USD - u64::MAX if there is no cap
+    pub fn remaining_borrow_cap(&self) -> Decimal {
+        match self.borrow_cap {
+            0 => Decimal::from(u64::MAX),
+            borrow_cap => {
+                Decimal::from(borrow_cap as u64).saturating_sub(self.unweighted_borrowed_value)
+            }
+        }
+    }
+
+    /// Record the slot the obligation became unhealthy at, or clear it once it's healthy again
+    pub fn update_unhealthy_since_slot(&mut self, slot: Slot) {
+        if self.borrowed_value > Decimal::zero()
+            && self.borrowed_value >= self.unhealthy_borrow_value
+        {
+            if self.unhealthy_since_slot == 0 {
+                self.unhealthy_since_slot = slot;
+            }
+        } else {
+            self.unhealthy_since_slot = 0;
+        }
     }
 
     /// Calculate the current ratio of borrowed value to deposited value
@@ -88,21 +242,75 @@ impl Obligation {
         self.borrowed_value.try_div(self.deposited_value)
     }
 
-    /// Repay liquidity and remove it from borrows if zeroed out
+    /// Settle the rewards of the obligation's deposit and borrow in a reserve, if it has them
+    pub fn settle_rewards(&mut self, reserve_pubkey: &Pubkey, reserve: &Reserve) -> ProgramResult {
+        if let Some(collateral) = self
+            .deposits
+            .iter_mut()
+            .find(|collateral| &collateral.deposit_reserve == reserve_pubkey)
+        {
+            collateral.settle_rewards(reserve)?;
+        }
+        if let Some(liquidity) = self
+            .borrows
+            .iter_mut()
+            .find(|liquidity| &liquidity.borrow_reserve == reserve_pubkey)
+        {
+            liquidity.settle_rewards(reserve)?;
+        }
+        Ok(())
+    }
+
+    /// Settle and claim the whole reward tokens of the obligation's position on one side of a
+    /// reserve, then remove the position if it was only kept open for its rewards
+    pub fn claim_rewards(
+        &mut self,
+        reserve_pubkey: &Pubkey,
+        reserve: &Reserve,
+        side: RewardSide,
+    ) -> Result<u64, ProgramError> {
+        match side {
+            RewardSide::Deposit => {
+                let (_, collateral_index) = self.find_collateral_in_deposits(*reserve_pubkey)?;
+                let collateral = &mut self.deposits[collateral_index];
+                collateral.settle_rewards(reserve)?;
+                let claim_amount = collateral.rewards.claim()?;
+                if collateral.deposited_amount == 0 {
+                    self.deposits.remove(collateral_index);
+                }
+                Ok(claim_amount)
+            }
+            RewardSide::Borrow => {
+                let (liquidity, liquidity_index) =
+                    self.find_liquidity_in_borrows_mut(*reserve_pubkey)?;
+                liquidity.settle_rewards(reserve)?;
+                let claim_amount = liquidity.rewards.claim()?;
+                if liquidity.is_settled() {
+                    self.borrows.remove(liquidity_index);
+                }
+                Ok(claim_amount)
+            }
+        }
+    }
+
+    /// Repay liquidity and remove it from borrows if zeroed out, unless it still has rate cap
+    /// credit or rewards to claim. The borrow's rewards must be settled first.
     pub fn repay(&mut self, settle_amount: Decimal, liquidity_index: usize) -> ProgramResult {
         let liquidity = &mut self.borrows[liquidity_index];
-        if settle_amount == liquidity.borrowed_amount_wads {
+        liquidity.repay(settle_amount)?;
+        if liquidity.is_settled() {
             self.borrows.remove(liquidity_index);
-        } else {
-            liquidity.repay(settle_amount)?;
         }
         Ok(())
     }
 
-    /// Withdraw collateral and remove it from deposits if zeroed out
+    /// Withdraw collateral and remove it from deposits if zeroed out, unless it still has
+    /// rewards to claim. The deposit's rewards must be settled first.
     pub fn withdraw(&mut self, withdraw_amount: u64, collateral_index: usize) -> ProgramResult {
         let collateral = &mut self.deposits[collateral_index];
-        if withdraw_amount == collateral.deposited_amount {
+        if withdraw_amount == collateral.deposited_amount
+            && !collateral.rewards.has_claimable_rewards()
+        {
             self.deposits.remove(collateral_index);
         } else {
             collateral.withdraw(withdraw_amount)?;
@@ -174,14 +382,49 @@ impl Obligation {
             .try_sub(self.borrowed_value_upper_bound)
     }
 
+    /// Fraction of the borrowed value that can be repaid in one liquidation. Scales linearly from
+    /// LIQUIDATION_CLOSE_FACTOR at the unhealthy borrow value up to the max close factor at the
+    /// super unhealthy borrow value.
+    pub fn close_factor(&self, max_close_factor_bps: u16) -> Result<Decimal, ProgramError> {
+        let min_close_factor = Decimal::from_percent(LIQUIDATION_CLOSE_FACTOR);
+        let max_close_factor = max(
+            min_close_factor,
+            Decimal::from_bps(max_close_factor_bps as u64),
+        );
+
+        if self.borrowed_value <= self.unhealthy_borrow_value {
+            return Ok(min_close_factor);
+        }
+        if self.borrowed_value >= self.super_unhealthy_borrow_value {
+            return Ok(max_close_factor);
+        }
+
+        // safety: unhealthy_borrow_value < borrowed_value < super_unhealthy_borrow_value, so the
+        // weight is between 0 and 1
+        let weight = self
+            .borrowed_value
+            .try_sub(self.unhealthy_borrow_value)?
+            .try_div(
+                self.super_unhealthy_borrow_value
+                    .try_sub(self.unhealthy_borrow_value)?,
+            )?;
+
+        min_close_factor.try_add(
+            max_close_factor
+                .try_sub(min_close_factor)?
+                .try_mul(weight)?,
+        )
+    }
+
     /// Calculate the maximum liquidation amount for a given liquidity
     pub fn max_liquidation_amount(
         &self,
         liquidity: &ObligationLiquidity,
+        max_close_factor_bps: u16,
     ) -> Result<Decimal, ProgramError> {
         let max_liquidation_value = self
             .borrowed_value
-            .try_mul(Rate::from_percent(LIQUIDATION_CLOSE_FACTOR))?
+            .try_mul(self.close_factor(max_close_factor_bps)?)?
             .min(liquidity.market_value)
             .min(Decimal::from(MAX_LIQUIDATABLE_VALUE_AT_ONCE));
 
@@ -212,10 +455,10 @@ impl Obligation {
         if let Some(collateral_index) = self._find_collateral_index_in_deposits(deposit_reserve) {
             return Ok(&mut self.deposits[collateral_index]);
         }
-        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVES {
+        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVES_EXTENDED {
             msg!(
                 "Obligation cannot have more than {} deposits and borrows combined",
-                MAX_OBLIGATION_RESERVES
+                MAX_OBLIGATION_RESERVES_EXTENDED
             );
             return Err(LendingError::ObligationReserveLimit.into());
         }
@@ -269,10 +512,10 @@ impl Obligation {
         if let Some(liquidity_index) = self._find_liquidity_index_in_borrows(borrow_reserve) {
             return Ok(&mut self.borrows[liquidity_index]);
         }
-        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVES {
+        if self.deposits.len() + self.borrows.len() >= MAX_OBLIGATION_RESERVES_EXTENDED {
             msg!(
                 "Obligation cannot have more than {} deposits and borrows combined",
-                MAX_OBLIGATION_RESERVES
+                MAX_OBLIGATION_RESERVES_EXTENDED
             );
             return Err(LendingError::ObligationReserveLimit.into());
         }
@@ -311,8 +554,10 @@ impl IsInitialized for Obligation {
 
 /// Obligation collateral state
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObligationCollateral {
     /// Reserve collateral is deposited to
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub deposit_reserve: Pubkey,
     /// Amount of collateral deposited
     pub deposited_amount: u64,
@@ -320,6 +565,8 @@ pub struct ObligationCollateral {
     pub market_value: Decimal,
     /// How much borrow is attributed to this collateral (USD)
     pub attributed_borrow_value: Decimal,
+    /// Rewards earned by the deposit from the reserve's deposit reward rule
+    pub rewards: PositionRewards,
 }
 
 impl ObligationCollateral {
@@ -330,9 +577,18 @@ impl ObligationCollateral {
             deposited_amount: 0,
             market_value: Decimal::zero(),
             attributed_borrow_value: Decimal::zero(),
+            rewards: PositionRewards::default(),
         }
     }
 
+    /// Settle the deposit's rewards up to the reserve's current deposit reward index
+    pub fn settle_rewards(&mut self, deposit_reserve: &Reserve) -> ProgramResult {
+        self.rewards.settle(
+            deposit_reserve.deposit_rewards.reward_index,
+            Decimal::from(self.deposited_amount),
+        )
+    }
+
     /// Increase deposited collateral
     pub fn deposit(&mut self, collateral_amount: u64) -> ProgramResult {
         self.deposited_amount = self
@@ -354,8 +610,10 @@ impl ObligationCollateral {
 
 /// Obligation liquidity state
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObligationLiquidity {
     /// Reserve liquidity is borrowed from
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub borrow_reserve: Pubkey,
     /// Borrow rate used for calculating interest
     pub cumulative_borrow_rate_wads: Decimal,
@@ -363,6 +621,15 @@ pub struct ObligationLiquidity {
     pub borrowed_amount_wads: Decimal,
     /// Liquidity market value in quote currency
     pub market_value: Decimal,
+    /// Borrow APR, in percent, the borrow's interest is capped at. The insurance fund pays the
+    /// reserve the interest above it. 0 means the borrow has no rate cap
+    pub rate_cap: u8,
+    /// Last slot interest was checked against the rate cap
+    pub rate_cap_slot: Slot,
+    /// Interest above the rate cap that the insurance fund hasn't paid the reserve yet
+    pub rate_cap_credit_wads: Decimal,
+    /// Rewards earned by the borrow from the reserve's borrow reward rule
+    pub rewards: PositionRewards,
 }
 
 impl ObligationLiquidity {
@@ -373,9 +640,29 @@ impl ObligationLiquidity {
             cumulative_borrow_rate_wads,
             borrowed_amount_wads: Decimal::zero(),
             market_value: Decimal::zero(),
+            rate_cap: 0,
+            rate_cap_slot: 0,
+            rate_cap_credit_wads: Decimal::zero(),
+            rewards: PositionRewards::default(),
         }
     }
 
+    /// Settle the borrow's rewards up to the reserve's current borrow reward index
+    pub fn settle_rewards(&mut self, borrow_reserve: &Reserve) -> ProgramResult {
+        self.rewards.settle(
+            borrow_reserve.borrow_rewards.reward_index,
+            self.borrowed_amount_wads,
+        )
+    }
+
+    /// Whether the borrow is fully repaid and has no rate cap credit or rewards left to claim, so
+    /// it can be removed from the obligation
+    pub fn is_settled(&self) -> bool {
+        self.borrowed_amount_wads == Decimal::zero()
+            && self.rate_cap_credit_wads == Decimal::zero()
+            && !self.rewards.has_claimable_rewards()
+    }
+
     /// Decrease borrowed liquidity
     pub fn repay(&mut self, settle_amount: Decimal) -> ProgramResult {
         self.borrowed_amount_wads = self.borrowed_amount_wads.try_sub(settle_amount)?;
@@ -410,17 +697,184 @@ impl ObligationLiquidity {
 
         Ok(())
     }
+
+    /// Accrue interest, holding the borrow to its rate cap since the last accrual. Whatever the
+    /// reserve's rate added above the cap is owed to the reserve by the insurance fund, and is
+    /// kept as the borrow's rate cap credit until ClaimRateCapCredit pays it.
+    pub fn accrue_interest_with_rate_cap(
+        &mut self,
+        cumulative_borrow_rate_wads: Decimal,
+        slot: Slot,
+    ) -> ProgramResult {
+        let previous_borrowed_amount_wads = self.borrowed_amount_wads;
+        self.accrue_interest(cumulative_borrow_rate_wads)?;
+
+        if self.rate_cap > 0 {
+            let slots_elapsed = slot.saturating_sub(self.rate_cap_slot);
+            let capped_borrowed_amount_wads = previous_borrowed_amount_wads.try_mul(
+                Rate::one()
+                    .try_add(Rate::from_percent(self.rate_cap).try_div(SLOTS_PER_YEAR)?)?
+                    .try_pow(slots_elapsed)?,
+            )?;
+            if self.borrowed_amount_wads > capped_borrowed_amount_wads {
+                self.rate_cap_credit_wads = self.rate_cap_credit_wads.try_add(
+                    self.borrowed_amount_wads
+                        .try_sub(capped_borrowed_amount_wads)?,
+                )?;
+                self.borrowed_amount_wads = capped_borrowed_amount_wads;
+            }
+            self.rate_cap_slot = slot;
+        }
+
+        Ok(())
+    }
 }
 
+/// Offset of the lending market in obligation accounts of either size, for getProgramAccounts
+/// memcmp filters
+pub const OBLIGATION_LENDING_MARKET_OFFSET: usize = 10;
+/// Offset of the owner in obligation accounts of either size
+pub const OBLIGATION_OWNER_OFFSET: usize = 42;
+
 const OBLIGATION_COLLATERAL_LEN: usize = 88; // 32 + 8 + 16 + 32
-const OBLIGATION_LIQUIDITY_LEN: usize = 112; // 32 + 16 + 16 + 16 + 32
-const OBLIGATION_LEN: usize = 1300; // 1 + 8 + 1 + 32 + 32 + 16 + 16 + 16 + 16 + 64 + 1 + 1 + (88 * 1) + (112 * 9)
-                                    // @TODO: break this up by obligation / collateral / liquidity https://git.io/JOCca
+const OBLIGATION_LIQUIDITY_LEN: usize = 112; // 32 + 16 + 16 + 16 + 1 + 8 + 16 + 7
+const OBLIGATION_POSITION_REWARDS_LEN: usize = 32; // 16 + 16
+
+/// Size of obligation accounts created before the V2 layout. The program reads and writes them as
+/// the start of the current layout until MigrateAccount reallocates them to Obligation::LEN.
+pub const OBLIGATION_LEN_V1: usize = 1300; // 1 + 8 + 1 + 32 + 32 + 16 + 16 + 16 + 16 + 64 + 1 + 1 + (88 * 1) + (112 * 9)
+                                           // @TODO: break this up by obligation / collateral / liquidity https://git.io/JOCca
+const OBLIGATION_DATA_FLAT_LEN: usize =
+    OBLIGATION_COLLATERAL_LEN + (OBLIGATION_LIQUIDITY_LEN * (MAX_OBLIGATION_RESERVES - 1));
+const OBLIGATION_DATA_FLAT_OFFSET: usize = OBLIGATION_LEN_V1 - OBLIGATION_DATA_FLAT_LEN;
+
+const OBLIGATION_V2_FIELDS_LEN: usize = 256; // 8 + 32 + 56 + 32 + 16 + 112 (padding)
+
+// V1 layout followed by the rewards of each deposit and borrow, in the same order, and the fixed
+// fields added in V2
+const OBLIGATION_LEN: usize = OBLIGATION_LEN_V1
+    + OBLIGATION_POSITION_REWARDS_LEN * MAX_OBLIGATION_RESERVES
+    + OBLIGATION_V2_FIELDS_LEN;
+
+/// Size of an obligation account that can hold up to MAX_OBLIGATION_RESERVES_EXTENDED deposits
+/// and borrows. As with Obligation::LEN, there is room for one deposit and the rest as borrows,
+/// the larger of the two.
+pub const OBLIGATION_EXTENDED_LEN: usize = OBLIGATION_LEN_V1
+    + OBLIGATION_LIQUIDITY_LEN * (MAX_OBLIGATION_RESERVES_EXTENDED - MAX_OBLIGATION_RESERVES)
+    + OBLIGATION_POSITION_REWARDS_LEN * MAX_OBLIGATION_RESERVES_EXTENDED
+    + OBLIGATION_V2_FIELDS_LEN;
+
+impl Obligation {
+    /// Max number of deposits and borrows combined an obligation account of the given size can
+    /// hold, or None if the size isn't a valid obligation account size
+    pub fn max_reserves_for_len(len: usize) -> Option<usize> {
+        match len {
+            OBLIGATION_LEN => Some(MAX_OBLIGATION_RESERVES),
+            OBLIGATION_EXTENDED_LEN => Some(MAX_OBLIGATION_RESERVES_EXTENDED),
+            _ => None,
+        }
+    }
+
+    /// Size MigrateAccount reallocates an obligation account with the given V1 layout size to,
+    /// or None if the size isn't a V1 obligation account size
+    pub fn migrated_len(v1_len: usize) -> Option<usize> {
+        match v1_len {
+            OBLIGATION_LEN_V1 => Some(OBLIGATION_LEN),
+            _ => None,
+        }
+    }
+
+    /// Unpacks an initialized obligation account of either the standard or extended size
+    pub fn unpack_account(input: &[u8]) -> Result<Self, ProgramError> {
+        let obligation = Self::unpack_account_unchecked(input)?;
+        if !obligation.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(obligation)
+    }
+
+    /// Unpacks an obligation account of either the standard or extended size without checking
+    /// that it's initialized
+    pub fn unpack_account_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        if input.len() == OBLIGATION_LEN_V1 {
+            let mut obligation = unpack_v1_layout(input, OBLIGATION_LEN, Self::unpack_from_slice)?;
+            // V1 obligations have no room for position rewards, so their positions are read as
+            // paid up to any reward index. They earn nothing until MigrateAccount starts them at
+            // their reserves' current index.
+            for rewards in obligation.position_rewards_mut() {
+                rewards.reward_index = Decimal(U192::MAX);
+            }
+            return Ok(obligation);
+        }
+        if Self::max_reserves_for_len(input.len()).is_none() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Self::unpack_from_slice(input)
+    }
+
+    /// Packs an obligation into an account of either the standard or extended size. Fails if the
+    /// account is too small for the obligation's deposits and borrows.
+    pub fn pack_account(mut src: Self, dst: &mut [u8]) -> ProgramResult {
+        let max_reserves = match dst.len() {
+            OBLIGATION_LEN_V1 => MAX_OBLIGATION_RESERVES,
+            len => Self::max_reserves_for_len(len).ok_or(ProgramError::InvalidAccountData)?,
+        };
+        if src.deposits.len() + src.borrows.len() > max_reserves {
+            msg!(
+                "Obligation cannot have more than {} deposits and borrows combined",
+                max_reserves
+            );
+            return Err(LendingError::ObligationReserveLimit.into());
+        }
+        if dst.len() == OBLIGATION_LEN_V1 {
+            for rewards in src.position_rewards_mut() {
+                *rewards = PositionRewards::default();
+            }
+            src.oldest_price_slot = 0;
+            return pack_v1_layout(
+                &src,
+                dst,
+                OBLIGATION_LEN,
+                Self::pack_into_slice,
+                Self::unpack_from_slice,
+            );
+        }
+        src.pack_into_slice(dst);
+        Ok(())
+    }
+
+    fn position_rewards_mut(&mut self) -> impl Iterator<Item = &mut PositionRewards> {
+        self.deposits
+            .iter_mut()
+            .map(|collateral| &mut collateral.rewards)
+            .chain(
+                self.borrows
+                    .iter_mut()
+                    .map(|liquidity| &mut liquidity.rewards),
+            )
+    }
+}
+
 impl Pack for Obligation {
     const LEN: usize = OBLIGATION_LEN;
 
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        Self::unpack_account_unchecked(input)
+    }
+
+    fn pack(src: Self, dst: &mut [u8]) -> ProgramResult {
+        Self::pack_account(src, dst)
+    }
+
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let output = array_mut_ref![dst, 0, OBLIGATION_LEN];
+        // deposits and borrows run from the fixed fields to their rewards and the V2 fields at
+        // the end of the account, which is longer than OBLIGATION_LEN for extended obligations
+        let max_reserves = Self::max_reserves_for_len(dst.len()).unwrap();
+        let (dst, v2_fields) = dst.split_at_mut(dst.len() - OBLIGATION_V2_FIELDS_LEN);
+        let (dst, data_rewards) =
+            dst.split_at_mut(dst.len() - OBLIGATION_POSITION_REWARDS_LEN * max_reserves);
+        let (output, data_flat) = dst.split_at_mut(OBLIGATION_DATA_FLAT_OFFSET);
+        let output = array_mut_ref![output, 0, OBLIGATION_DATA_FLAT_OFFSET];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
             version,
@@ -437,10 +891,12 @@ impl Pack for Obligation {
             super_unhealthy_borrow_value,
             unweighted_borrowed_value,
             closeable,
+            borrows_locked,
+            borrow_unlock_slot,
             _padding,
+            borrow_cap,
             deposits_len,
             borrows_len,
-            data_flat,
         ) = mut_array_refs![
             output,
             1,
@@ -457,10 +913,12 @@ impl Pack for Obligation {
             16,
             16,
             1,
-            14,
             1,
+            8,
+            1,
+            4,
             1,
-            OBLIGATION_COLLATERAL_LEN + (OBLIGATION_LIQUIDITY_LEN * (MAX_OBLIGATION_RESERVES - 1))
+            1
         ];
 
         // obligation
@@ -481,6 +939,9 @@ impl Pack for Obligation {
         );
         pack_decimal(self.unweighted_borrowed_value, unweighted_borrowed_value);
         pack_bool(self.closeable, closeable);
+        pack_bool(self.borrows_locked, borrows_locked);
+        *borrow_unlock_slot = self.borrow_unlock_slot.to_le_bytes();
+        *borrow_cap = self.borrow_cap.to_le_bytes();
 
         *deposits_len = u8::try_from(self.deposits.len()).unwrap().to_le_bytes();
         *borrows_len = u8::try_from(self.borrows.len()).unwrap().to_le_bytes();
@@ -496,8 +957,14 @@ impl Pack for Obligation {
                 deposited_amount,
                 market_value,
                 attributed_borrow_value,
-                _padding_deposit,
+                padding_deposit,
             ) = mut_array_refs![deposits_flat, PUBKEY_BYTES, 8, 16, 16, 16];
+            if offset == 0 {
+                let (unhealthy_since_slot, uses_asset_category_limits, _padding) =
+                    mut_array_refs![padding_deposit, 8, 1, 7];
+                *unhealthy_since_slot = self.unhealthy_since_slot.to_le_bytes();
+                pack_bool(self.uses_asset_category_limits, uses_asset_category_limits);
+            }
             deposit_reserve.copy_from_slice(collateral.deposit_reserve.as_ref());
             *deposited_amount = collateral.deposited_amount.to_le_bytes();
             pack_decimal(collateral.market_value, market_value);
@@ -514,8 +981,11 @@ impl Pack for Obligation {
                 cumulative_borrow_rate_wads,
                 borrowed_amount_wads,
                 market_value,
+                rate_cap,
+                rate_cap_slot,
+                rate_cap_credit_wads,
                 _padding_borrow,
-            ) = mut_array_refs![borrows_flat, PUBKEY_BYTES, 16, 16, 16, 32];
+            ) = mut_array_refs![borrows_flat, PUBKEY_BYTES, 16, 16, 16, 1, 8, 16, 7];
             borrow_reserve.copy_from_slice(liquidity.borrow_reserve.as_ref());
             pack_decimal(
                 liquidity.cumulative_borrow_rate_wads,
@@ -523,13 +993,70 @@ impl Pack for Obligation {
             );
             pack_decimal(liquidity.borrowed_amount_wads, borrowed_amount_wads);
             pack_decimal(liquidity.market_value, market_value);
+            *rate_cap = liquidity.rate_cap.to_le_bytes();
+            *rate_cap_slot = liquidity.rate_cap_slot.to_le_bytes();
+            pack_decimal(liquidity.rate_cap_credit_wads, rate_cap_credit_wads);
             offset += OBLIGATION_LIQUIDITY_LEN;
         }
+
+        // deposit and borrow rewards
+        let rewards = self
+            .deposits
+            .iter()
+            .map(|collateral| &collateral.rewards)
+            .chain(self.borrows.iter().map(|liquidity| &liquidity.rewards));
+        for (i, position_rewards) in rewards.enumerate() {
+            let rewards_flat = array_mut_ref![
+                data_rewards,
+                i * OBLIGATION_POSITION_REWARDS_LEN,
+                OBLIGATION_POSITION_REWARDS_LEN
+            ];
+            let (reward_index, unclaimed_rewards) = mut_array_refs![rewards_flat, 16, 16];
+            pack_decimal(position_rewards.reward_index, reward_index);
+            pack_decimal(position_rewards.unclaimed_rewards, unclaimed_rewards);
+        }
+
+        // fixed fields added in V2
+        let v2_fields = array_mut_ref![v2_fields, 0, OBLIGATION_V2_FIELDS_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            oldest_price_slot,
+            referrer,
+            rate_limiter,
+            approved_borrower,
+            delegated_value,
+            _padding,
+        ) = mut_array_refs![
+            v2_fields,
+            8,
+            PUBKEY_BYTES,
+            RATE_LIMITER_LEN,
+            PUBKEY_BYTES,
+            16,
+            112
+        ];
+        *oldest_price_slot = self.oldest_price_slot.to_le_bytes();
+        match self.referrer {
+            Some(pubkey) => referrer.copy_from_slice(pubkey.as_ref()),
+            None => referrer.copy_from_slice(&[0u8; PUBKEY_BYTES]),
+        };
+        self.rate_limiter.pack_into_slice(rate_limiter);
+        match self.approved_borrower {
+            Some(pubkey) => approved_borrower.copy_from_slice(pubkey.as_ref()),
+            None => approved_borrower.copy_from_slice(&[0u8; PUBKEY_BYTES]),
+        };
+        pack_decimal(self.delegated_value, delegated_value);
     }
 
     /// Unpacks a byte buffer into an [ObligationInfo](struct.ObligationInfo.html).
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![src, 0, OBLIGATION_LEN];
+        let max_reserves =
+            Self::max_reserves_for_len(src.len()).ok_or(ProgramError::InvalidAccountData)?;
+        let (src, v2_fields) = src.split_at(src.len() - OBLIGATION_V2_FIELDS_LEN);
+        let (src, data_rewards) =
+            src.split_at(src.len() - OBLIGATION_POSITION_REWARDS_LEN * max_reserves);
+        let (input, data_flat) = src.split_at(OBLIGATION_DATA_FLAT_OFFSET);
+        let input = array_ref![input, 0, OBLIGATION_DATA_FLAT_OFFSET];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
             version,
@@ -546,10 +1073,12 @@ impl Pack for Obligation {
             super_unhealthy_borrow_value,
             unweighted_borrowed_value,
             closeable,
+            borrows_locked,
+            borrow_unlock_slot,
             _padding,
+            borrow_cap,
             deposits_len,
             borrows_len,
-            data_flat,
         ) = array_refs![
             input,
             1,
@@ -566,10 +1095,12 @@ impl Pack for Obligation {
             16,
             16,
             1,
-            14,
             1,
+            8,
+            1,
+            4,
             1,
-            OBLIGATION_COLLATERAL_LEN + (OBLIGATION_LIQUIDITY_LEN * (MAX_OBLIGATION_RESERVES - 1))
+            1
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -580,9 +1111,19 @@ impl Pack for Obligation {
 
         let deposits_len = u8::from_le_bytes(*deposits_len);
         let borrows_len = u8::from_le_bytes(*borrows_len);
+        if deposits_len as usize * OBLIGATION_COLLATERAL_LEN
+            + borrows_len as usize * OBLIGATION_LIQUIDITY_LEN
+            > data_flat.len()
+            || deposits_len as usize + borrows_len as usize > max_reserves
+        {
+            msg!("Obligation deposits and borrows do not fit in the account");
+            return Err(ProgramError::InvalidAccountData);
+        }
         let mut deposits = Vec::with_capacity(deposits_len as usize + 1);
         let mut borrows = Vec::with_capacity(borrows_len as usize + 1);
 
+        let mut unhealthy_since_slot = 0;
+        let mut uses_asset_category_limits = false;
         let mut offset = 0;
         for _ in 0..deposits_len {
             let deposits_flat = array_ref![data_flat, offset, OBLIGATION_COLLATERAL_LEN];
@@ -592,13 +1133,20 @@ impl Pack for Obligation {
                 deposited_amount,
                 market_value,
                 attributed_borrow_value,
-                _padding_deposit,
+                padding_deposit,
             ) = array_refs![deposits_flat, PUBKEY_BYTES, 8, 16, 16, 16];
+            if offset == 0 {
+                let (first_unhealthy_slot, first_uses_asset_category_limits, _padding) =
+                    array_refs![padding_deposit, 8, 1, 7];
+                unhealthy_since_slot = u64::from_le_bytes(*first_unhealthy_slot);
+                uses_asset_category_limits = unpack_bool(first_uses_asset_category_limits)?;
+            }
             deposits.push(ObligationCollateral {
                 deposit_reserve: Pubkey::from(*deposit_reserve),
                 deposited_amount: u64::from_le_bytes(*deposited_amount),
                 market_value: unpack_decimal(market_value),
                 attributed_borrow_value: unpack_decimal(attributed_borrow_value),
+                rewards: PositionRewards::default(),
             });
             offset += OBLIGATION_COLLATERAL_LEN;
         }
@@ -610,17 +1158,60 @@ impl Pack for Obligation {
                 cumulative_borrow_rate_wads,
                 borrowed_amount_wads,
                 market_value,
+                rate_cap,
+                rate_cap_slot,
+                rate_cap_credit_wads,
                 _padding_borrow,
-            ) = array_refs![borrows_flat, PUBKEY_BYTES, 16, 16, 16, 32];
+            ) = array_refs![borrows_flat, PUBKEY_BYTES, 16, 16, 16, 1, 8, 16, 7];
             borrows.push(ObligationLiquidity {
                 borrow_reserve: Pubkey::from(*borrow_reserve),
                 cumulative_borrow_rate_wads: unpack_decimal(cumulative_borrow_rate_wads),
                 borrowed_amount_wads: unpack_decimal(borrowed_amount_wads),
                 market_value: unpack_decimal(market_value),
+                rate_cap: u8::from_le_bytes(*rate_cap),
+                rate_cap_slot: u64::from_le_bytes(*rate_cap_slot),
+                rate_cap_credit_wads: unpack_decimal(rate_cap_credit_wads),
+                rewards: PositionRewards::default(),
             });
             offset += OBLIGATION_LIQUIDITY_LEN;
         }
 
+        let rewards = deposits
+            .iter_mut()
+            .map(|collateral| &mut collateral.rewards)
+            .chain(borrows.iter_mut().map(|liquidity| &mut liquidity.rewards));
+        for (i, position_rewards) in rewards.enumerate() {
+            let rewards_flat = array_ref![
+                data_rewards,
+                i * OBLIGATION_POSITION_REWARDS_LEN,
+                OBLIGATION_POSITION_REWARDS_LEN
+            ];
+            let (reward_index, unclaimed_rewards) = array_refs![rewards_flat, 16, 16];
+            *position_rewards = PositionRewards {
+                reward_index: unpack_decimal(reward_index),
+                unclaimed_rewards: unpack_decimal(unclaimed_rewards),
+            };
+        }
+
+        let v2_fields = array_ref![v2_fields, 0, OBLIGATION_V2_FIELDS_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            oldest_price_slot,
+            referrer,
+            rate_limiter,
+            approved_borrower,
+            delegated_value,
+            _padding,
+        ) = array_refs![
+            v2_fields,
+            8,
+            PUBKEY_BYTES,
+            RATE_LIMITER_LEN,
+            PUBKEY_BYTES,
+            16,
+            112
+        ];
+
         Ok(Self {
             version,
             last_update: LastUpdate {
@@ -640,6 +1231,24 @@ impl Pack for Obligation {
             super_unhealthy_borrow_value: unpack_decimal(super_unhealthy_borrow_value),
             borrowing_isolated_asset: unpack_bool(borrowing_isolated_asset)?,
             closeable: unpack_bool(closeable)?,
+            borrows_locked: unpack_bool(borrows_locked)?,
+            borrow_unlock_slot: u64::from_le_bytes(*borrow_unlock_slot),
+            referrer: if referrer == &[0; 32] {
+                None
+            } else {
+                Some(Pubkey::new_from_array(*referrer))
+            },
+            borrow_cap: u32::from_le_bytes(*borrow_cap),
+            unhealthy_since_slot,
+            uses_asset_category_limits,
+            oldest_price_slot: u64::from_le_bytes(*oldest_price_slot),
+            rate_limiter: RateLimiter::unpack_from_slice(rate_limiter)?,
+            approved_borrower: if approved_borrower == &[0; 32] {
+                None
+            } else {
+                Some(Pubkey::new_from_array(*approved_borrower))
+            },
+            delegated_value: unpack_decimal(delegated_value),
         })
     }
 }
@@ -675,12 +1284,23 @@ mod test {
                     deposited_amount: rng.gen(),
                     market_value: rand_decimal(),
                     attributed_borrow_value: rand_decimal(),
+                    rewards: PositionRewards {
+                        reward_index: rand_decimal(),
+                        unclaimed_rewards: rand_decimal(),
+                    },
                 }],
                 borrows: vec![ObligationLiquidity {
                     borrow_reserve: Pubkey::new_unique(),
                     cumulative_borrow_rate_wads: rand_decimal(),
                     borrowed_amount_wads: rand_decimal(),
                     market_value: rand_decimal(),
+                    rate_cap: rng.gen(),
+                    rate_cap_slot: rng.gen(),
+                    rate_cap_credit_wads: rand_decimal(),
+                    rewards: PositionRewards {
+                        reward_index: rand_decimal(),
+                        unclaimed_rewards: rand_decimal(),
+                    },
                 }],
                 deposited_value: rand_decimal(),
                 borrowed_value: rand_decimal(),
@@ -691,6 +1311,24 @@ mod test {
                 super_unhealthy_borrow_value: rand_decimal(),
                 borrowing_isolated_asset: rng.gen(),
                 closeable: rng.gen(),
+                borrows_locked: rng.gen(),
+                borrow_unlock_slot: rng.gen(),
+                referrer: if rng.gen_bool(0.5) {
+                    Some(Pubkey::new_unique())
+                } else {
+                    None
+                },
+                borrow_cap: rng.gen(),
+                unhealthy_since_slot: rng.gen(),
+                uses_asset_category_limits: rng.gen(),
+                oldest_price_slot: rng.gen(),
+                rate_limiter: rand_rate_limiter(),
+                approved_borrower: if rng.gen_bool(0.5) {
+                    Some(Pubkey::new_unique())
+                } else {
+                    None
+                },
+                delegated_value: rand_decimal(),
             };
 
             let mut packed = [0u8; OBLIGATION_LEN];
@@ -700,6 +1338,163 @@ mod test {
         }
     }
 
+    fn obligation_with_positions(deposits: usize, borrows: usize) -> Obligation {
+        Obligation {
+            version: PROGRAM_VERSION,
+            deposits: (0..deposits)
+                .map(|i| ObligationCollateral {
+                    deposit_reserve: Pubkey::new_unique(),
+                    deposited_amount: i as u64,
+                    market_value: Decimal::from(i as u64),
+                    attributed_borrow_value: Decimal::zero(),
+                    rewards: PositionRewards {
+                        reward_index: Decimal::from(i as u64),
+                        unclaimed_rewards: Decimal::from(2 * i as u64),
+                    },
+                })
+                .collect(),
+            borrows: (0..borrows)
+                .map(|i| ObligationLiquidity {
+                    borrow_reserve: Pubkey::new_unique(),
+                    cumulative_borrow_rate_wads: Decimal::one(),
+                    borrowed_amount_wads: Decimal::from(i as u64),
+                    market_value: Decimal::from(i as u64),
+                    rate_cap: i as u8,
+                    rate_cap_slot: i as u64,
+                    rate_cap_credit_wads: Decimal::from(i as u64),
+                    rewards: PositionRewards {
+                        reward_index: Decimal::from(3 * i as u64),
+                        unclaimed_rewards: Decimal::from(4 * i as u64),
+                    },
+                })
+                .collect(),
+            ..Obligation::default()
+        }
+    }
+
+    #[test]
+    fn pack_and_unpack_extended_obligation() {
+        for deposits in [1, 12, MAX_OBLIGATION_RESERVES_EXTENDED] {
+            let obligation =
+                obligation_with_positions(deposits, MAX_OBLIGATION_RESERVES_EXTENDED - deposits);
+
+            let mut packed = vec![0u8; OBLIGATION_EXTENDED_LEN];
+            Obligation::pack_account(obligation.clone(), &mut packed).unwrap();
+            let unpacked = Obligation::unpack_account(&packed).unwrap();
+            assert_eq!(obligation, unpacked);
+        }
+    }
+
+    #[test]
+    fn pack_account_respects_account_size() {
+        let obligation = obligation_with_positions(6, 5);
+
+        let mut packed = vec![0u8; OBLIGATION_LEN];
+        assert_eq!(
+            Obligation::pack_account(obligation.clone(), &mut packed),
+            Err(LendingError::ObligationReserveLimit.into())
+        );
+
+        let mut packed = vec![0u8; OBLIGATION_LEN + 1];
+        assert_eq!(
+            Obligation::pack_account(obligation, &mut packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // legacy accounts are still readable through the size-aware unpack
+        let obligation = obligation_with_positions(1, MAX_OBLIGATION_RESERVES - 1);
+        let mut packed = vec![0u8; OBLIGATION_LEN];
+        Obligation::pack(obligation.clone(), &mut packed).unwrap();
+        assert_eq!(Obligation::unpack_account(&packed).unwrap(), obligation);
+    }
+
+    #[test]
+    fn pack_and_unpack_v1_obligation() {
+        let obligation = Obligation {
+            version: 1,
+            oldest_price_slot: 5,
+            // V1 obligations have no rate limiter, so theirs unpacks zeroed
+            rate_limiter: RateLimiter::unpack_from_slice(&[0; RATE_LIMITER_LEN]).unwrap(),
+            ..obligation_with_positions(1, MAX_OBLIGATION_RESERVES - 1)
+        };
+
+        // V1 obligations don't store position rewards, and earn none until they're migrated
+        let mut packed = vec![0u8; OBLIGATION_LEN_V1];
+        Obligation::pack_account(obligation.clone(), &mut packed).unwrap();
+        let mut unpacked = Obligation::unpack_account(&packed).unwrap();
+        assert_eq!(unpacked.oldest_price_slot, 0);
+        assert_eq!(unpacked.deposits[0].deposited_amount, 0);
+        assert_eq!(unpacked.borrows[1].borrowed_amount_wads, Decimal::one());
+        let reserve = Reserve {
+            borrow_rewards: ReserveRewards {
+                reward_index: Decimal::from(100u64),
+                ..ReserveRewards::default()
+            },
+            ..Reserve::default()
+        };
+        let borrow_reserve = unpacked.borrows[1].borrow_reserve;
+        unpacked.settle_rewards(&borrow_reserve, &reserve).unwrap();
+        assert_eq!(
+            unpacked.borrows[1].rewards.unclaimed_rewards,
+            Decimal::zero()
+        );
+
+        let mut repacked = vec![0u8; OBLIGATION_LEN_V1];
+        Obligation::pack_account(unpacked.clone(), &mut repacked).unwrap();
+        assert_eq!(repacked, packed);
+
+        // limiting their outflow needs the rate limiter, which needs the current layout
+        let lending_market = LendingMarket {
+            obligation_max_outflow: 100,
+            ..LendingMarket::default()
+        };
+        unpacked.sync_rate_limiter_config(&lending_market, 1);
+        assert_eq!(
+            Obligation::pack_account(unpacked, &mut repacked),
+            Err(LendingError::AccountNotMigrated.into())
+        );
+
+        // new obligations need the current layout
+        assert_eq!(
+            Obligation::unpack_account_unchecked(&[0u8; OBLIGATION_LEN_V1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn borrow_delegated_value() {
+        let mut obligation = Obligation {
+            delegated_value: Decimal::from(100u64),
+            ..Obligation::default()
+        };
+
+        obligation
+            .borrow_delegated_value(Decimal::from(60u64))
+            .unwrap();
+        assert_eq!(obligation.delegated_value, Decimal::from(40u64));
+
+        assert_eq!(
+            obligation.borrow_delegated_value(Decimal::from(41u64)),
+            Err(LendingError::BorrowTooLarge.into())
+        );
+        obligation
+            .borrow_delegated_value(Decimal::from(40u64))
+            .unwrap();
+        assert_eq!(obligation.delegated_value, Decimal::zero());
+    }
+
+    #[test]
+    fn unpack_rejects_positions_past_account_end() {
+        let obligation = obligation_with_positions(0, MAX_OBLIGATION_RESERVES);
+        let mut packed = vec![0u8; OBLIGATION_EXTENDED_LEN];
+        Obligation::pack_account(obligation, &mut packed).unwrap();
+
+        assert_eq!(
+            Obligation::unpack_account(&packed[..OBLIGATION_LEN]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
     #[test]
     fn obligation_accrue_interest_failure() {
         assert_eq!(
@@ -731,6 +1526,62 @@ mod test {
         );
     }
 
+    #[test]
+    fn obligation_accrue_interest_with_rate_cap() {
+        let liquidity = ObligationLiquidity {
+            cumulative_borrow_rate_wads: Decimal::one(),
+            borrowed_amount_wads: Decimal::from(1_000_000_000u64),
+            rate_cap: 10,
+            ..ObligationLiquidity::default()
+        };
+
+        // a year at 50% against a 10% cap: the borrow grows to 1e9 * e^0.1 and the excess is
+        // credited
+        let mut capped = liquidity.clone();
+        capped
+            .accrue_interest_with_rate_cap(Decimal::from_percent(150), SLOTS_PER_YEAR)
+            .unwrap();
+        let borrowed = capped.borrowed_amount_wads.try_floor_u64().unwrap();
+        assert!(
+            (1_105_100_000..1_105_200_000).contains(&borrowed),
+            "{}",
+            borrowed
+        );
+        assert_eq!(
+            capped
+                .borrowed_amount_wads
+                .try_add(capped.rate_cap_credit_wads)
+                .unwrap(),
+            Decimal::from(1_500_000_000u64)
+        );
+        let credit = capped.rate_cap_credit_wads.try_floor_u64().unwrap();
+        assert!((394_800_000..394_900_000).contains(&credit), "{}", credit);
+        assert_eq!(capped.rate_cap_slot, SLOTS_PER_YEAR);
+
+        // under the cap nothing is credited
+        let mut under_cap = liquidity.clone();
+        under_cap
+            .accrue_interest_with_rate_cap(Decimal::from_percent(105), SLOTS_PER_YEAR)
+            .unwrap();
+        assert_eq!(
+            under_cap.borrowed_amount_wads,
+            Decimal::from(1_050_000_000u64)
+        );
+        assert_eq!(under_cap.rate_cap_credit_wads, Decimal::zero());
+        assert_eq!(under_cap.rate_cap_slot, SLOTS_PER_YEAR);
+
+        // without a cap it's the same as accrue_interest
+        let mut uncapped = ObligationLiquidity {
+            rate_cap: 0,
+            ..liquidity
+        };
+        uncapped
+            .accrue_interest_with_rate_cap(Decimal::from_percent(150), SLOTS_PER_YEAR)
+            .unwrap();
+        assert_eq!(uncapped.rate_cap_credit_wads, Decimal::zero());
+        assert_eq!(uncapped.rate_cap_slot, 0);
+    }
+
     // Creates rates (r1, r2) where 0 < r1 <= r2 <= 100*r1
     prop_compose! {
         fn cumulative_rates()(rate in 1..=u128::MAX)(
@@ -824,6 +1675,111 @@ mod test {
         }
     }
 
+    #[test]
+    fn borrow_lock() {
+        let mut obligation = Obligation::default();
+        assert!(!obligation.is_borrow_locked(0));
+
+        obligation.set_borrow_lock(true, 10).unwrap();
+        assert!(obligation.is_borrow_locked(10));
+
+        // unlocking only takes effect after the delay, and asking again doesn't extend it
+        obligation.set_borrow_lock(false, 10).unwrap();
+        obligation.set_borrow_lock(false, 20).unwrap();
+        assert!(obligation.is_borrow_locked(9 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+        assert!(!obligation.is_borrow_locked(10 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+
+        // locking again cancels a pending unlock
+        obligation.set_borrow_lock(true, 20).unwrap();
+        assert!(obligation.is_borrow_locked(10 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+
+        obligation.set_borrow_lock(false, 20).unwrap();
+        obligation
+            .set_borrow_lock(false, 20 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS)
+            .unwrap();
+        assert!(!obligation.borrows_locked);
+        assert_eq!(obligation.borrow_unlock_slot, 0);
+    }
+
+    #[test]
+    fn unhealthy_since_slot() {
+        let mut obligation = Obligation {
+            borrowed_value: Decimal::from(10u64),
+            unhealthy_borrow_value: Decimal::from(20u64),
+            ..Obligation::default()
+        };
+        obligation.update_unhealthy_since_slot(10);
+        assert_eq!(obligation.unhealthy_since_slot, 0);
+
+        obligation.borrowed_value = Decimal::from(20u64);
+        obligation.update_unhealthy_since_slot(20);
+        assert_eq!(obligation.unhealthy_since_slot, 20);
+
+        // stays at the first unhealthy slot
+        obligation.update_unhealthy_since_slot(30);
+        assert_eq!(obligation.unhealthy_since_slot, 20);
+
+        obligation.borrowed_value = Decimal::from(15u64);
+        obligation.update_unhealthy_since_slot(40);
+        assert_eq!(obligation.unhealthy_since_slot, 0);
+    }
+
+    #[test]
+    fn borrow_cap() {
+        let mut obligation = Obligation {
+            unweighted_borrowed_value: Decimal::from(40u64),
+            ..Obligation::default()
+        };
+        assert_eq!(obligation.remaining_borrow_cap(), Decimal::from(u64::MAX));
+
+        // setting and lowering a cap doesn't lock borrows
+        obligation.set_borrow_cap(100, 10).unwrap();
+        obligation.set_borrow_cap(50, 10).unwrap();
+        assert!(!obligation.is_borrow_locked(10));
+        assert_eq!(obligation.remaining_borrow_cap(), Decimal::from(10u64));
+        obligation.set_borrow_cap(30, 10).unwrap();
+        assert_eq!(obligation.remaining_borrow_cap(), Decimal::zero());
+
+        // raising it locks borrows for the unlock delay
+        obligation.set_borrow_cap(100, 20).unwrap();
+        assert!(obligation.is_borrow_locked(19 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+        assert!(!obligation.is_borrow_locked(20 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+
+        // removing it while an unlock is pending pushes the unlock out
+        obligation.set_borrow_cap(0, 30).unwrap();
+        assert!(obligation.is_borrow_locked(29 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+        assert!(!obligation.is_borrow_locked(30 + OBLIGATION_BORROW_UNLOCK_DELAY_SLOTS));
+
+        // an owner lock without a pending unlock stays as it is
+        obligation.set_borrow_lock(true, 40).unwrap();
+        obligation.set_borrow_cap(10, 40).unwrap();
+        obligation.set_borrow_cap(20, 40).unwrap();
+        assert_eq!(obligation.borrow_unlock_slot, 0);
+        assert!(obligation.is_borrow_locked(u64::MAX));
+    }
+
+    #[test]
+    fn empty_obligation_idle() {
+        let mut obligation = Obligation {
+            last_update: LastUpdate::new(100),
+            ..Obligation::default()
+        };
+
+        assert!(!obligation
+            .is_empty_and_idle(100 + EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS)
+            .unwrap());
+        assert!(obligation
+            .is_empty_and_idle(101 + EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS)
+            .unwrap());
+
+        obligation
+            .deposits
+            .push(ObligationCollateral::new(Pubkey::new_unique()));
+        assert!(!obligation
+            .is_empty_and_idle(101 + EMPTY_OBLIGATION_CLOSE_AFTER_SLOTS)
+            .unwrap());
+    }
+
     #[test]
     fn max_liquidation_amount_normal() {
         let obligation_liquidity = ObligationLiquidity {
@@ -847,7 +1803,10 @@ mod test {
 
         assert_eq!(
             obligation
-                .max_liquidation_amount(&obligation_liquidity)
+                .max_liquidation_amount(
+                    &obligation_liquidity,
+                    LIQUIDATION_CLOSE_FACTOR as u16 * 100
+                )
                 .unwrap(),
             expected_collateral
         );
@@ -870,7 +1829,10 @@ mod test {
 
         assert_eq!(
             obligation
-                .max_liquidation_amount(&obligation_liquidity)
+                .max_liquidation_amount(
+                    &obligation_liquidity,
+                    LIQUIDATION_CLOSE_FACTOR as u16 * 100
+                )
                 .unwrap(),
             Decimal::from(100u64)
         );
@@ -893,12 +1855,52 @@ mod test {
 
         assert_eq!(
             obligation
-                .max_liquidation_amount(&obligation_liquidity)
+                .max_liquidation_amount(
+                    &obligation_liquidity,
+                    LIQUIDATION_CLOSE_FACTOR as u16 * 100
+                )
                 .unwrap(),
             Decimal::from(MAX_LIQUIDATABLE_VALUE_AT_ONCE)
         );
     }
 
+    #[test]
+    fn close_factor_scales_with_unhealthiness() {
+        let obligation = Obligation {
+            unhealthy_borrow_value: Decimal::from(80u64),
+            super_unhealthy_borrow_value: Decimal::from(90u64),
+            ..Obligation::default()
+        };
+
+        let close_factor = |borrowed_value: u64| {
+            Obligation {
+                borrowed_value: Decimal::from(borrowed_value),
+                ..obligation.clone()
+            }
+            .close_factor(10_000)
+            .unwrap()
+        };
+
+        assert_eq!(
+            close_factor(80),
+            Decimal::from_percent(LIQUIDATION_CLOSE_FACTOR)
+        );
+        assert_eq!(close_factor(85), Decimal::from_percent(60));
+        assert_eq!(close_factor(90), Decimal::one());
+        assert_eq!(close_factor(100), Decimal::one());
+
+        // a max close factor below the minimum is ignored
+        assert_eq!(
+            Obligation {
+                borrowed_value: Decimal::from(100u64),
+                ..obligation
+            }
+            .close_factor(0)
+            .unwrap(),
+            Decimal::from_percent(LIQUIDATION_CLOSE_FACTOR)
+        );
+    }
+
     #[derive(Debug, Clone)]
     struct MaxWithdrawAmountTestCase {
         obligation: Obligation,
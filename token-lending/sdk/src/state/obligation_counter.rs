@@ -0,0 +1,114 @@
+use super::*;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the lending market and owner addresses to derive an obligation counter account
+pub const OBLIGATION_COUNTER_SEED: &[u8] = b"ObligationCounter";
+
+/// Number of obligations an owner has initialized in a lending market while the market's
+/// obligation cap was enforced
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObligationCounter {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the obligation counter account address
+    pub bump_seed: u8,
+    /// Lending market the obligations belong to
+    pub lending_market: Pubkey,
+    /// Owner of the obligations
+    pub owner: Pubkey,
+    /// Number of obligations initialized
+    pub count: u64,
+}
+
+/// Find the obligation counter account address for an owner in a lending market
+pub fn find_obligation_counter_address(
+    program_id: &Pubkey,
+    lending_market: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            lending_market.as_ref(),
+            owner.as_ref(),
+            OBLIGATION_COUNTER_SEED,
+        ],
+        program_id,
+    )
+}
+
+impl Sealed for ObligationCounter {}
+impl IsInitialized for ObligationCounter {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const OBLIGATION_COUNTER_LEN: usize = 138; // 1 + 1 + 1 + 32 + 32 + 8 + 63
+impl Pack for ObligationCounter {
+    const LEN: usize = OBLIGATION_COUNTER_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, OBLIGATION_COUNTER_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, owner, count, _padding) =
+            mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 8, 63];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::ObligationCounter as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        lending_market.copy_from_slice(self.lending_market.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *count = self.count.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, OBLIGATION_COUNTER_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, lending_market, owner, count, _padding) =
+            array_refs![input, 1, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 8, 63];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Obligation counter version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::ObligationCounter)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            lending_market: Pubkey::new_from_array(*lending_market),
+            owner: Pubkey::new_from_array(*owner),
+            count: u64::from_le_bytes(*count),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_obligation_counter() {
+        let mut rng = rand::thread_rng();
+        let obligation_counter = ObligationCounter {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            lending_market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            count: rng.gen(),
+        };
+
+        let mut packed = [0u8; OBLIGATION_COUNTER_LEN];
+        ObligationCounter::pack(obligation_counter.clone(), &mut packed).unwrap();
+        let unpacked = ObligationCounter::unpack(&packed).unwrap();
+        assert_eq!(obligation_counter, unpacked);
+    }
+}
@@ -15,6 +15,7 @@ use solana_program::program_pack::{Pack, Sealed};
 /// is less than 2x max_outflow.
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct RateLimiter {
     /// configuration parameters
     pub config: RateLimiterConfig,
@@ -30,6 +31,7 @@ pub struct RateLimiter {
 
 /// Lending market configuration parameters
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct RateLimiterConfig {
     /// Rate limiter window size in slots
     pub window_duration: u64,
@@ -40,11 +42,9 @@ pub struct RateLimiterConfig {
 impl RateLimiter {
     /// initialize rate limiter
     pub fn new(config: RateLimiterConfig, cur_slot: u64) -> Self {
-        let slot_start = if config.window_duration != 0 {
-            cur_slot / config.window_duration * config.window_duration
-        } else {
-            cur_slot
-        };
+        let slot_start = cur_slot
+            .checked_div(config.window_duration)
+            .map_or(cur_slot, |windows| windows * config.window_duration);
 
         Self {
             config,
@@ -121,6 +121,18 @@ impl RateLimiter {
         Ok(diff)
     }
 
+    /// Calculate remaining outflow at a slot without moving the windows, for callers that only
+    /// read the rate limiter
+    pub fn max_outflow_remaining(&self, cur_slot: u64) -> Result<Decimal, ProgramError> {
+        let mut rate_limiter = *self;
+        rate_limiter.remaining_outflow(cur_slot)
+    }
+
+    /// Clear all outflow, starting a new window at the current slot's window
+    pub fn reset(&mut self, cur_slot: u64) {
+        *self = Self::new(self.config, cur_slot);
+    }
+
     /// update rate limiter with new quantity. errors if rate limit has been reached
     pub fn update(&mut self, cur_slot: u64, qty: Decimal) -> Result<(), ProgramError> {
         // rate limiter is disabled if window duration == 0. this is here because we don't want to
@@ -205,6 +217,7 @@ impl Pack for RateLimiter {
 }
 
 #[cfg(test)]
+/// Rate limiter with random config and state, for pack and unpack tests
 pub fn rand_rate_limiter() -> RateLimiter {
     use rand::Rng;
     let mut rng = rand::thread_rng();
@@ -300,4 +313,78 @@ mod test {
         }
         println!("{:#?}", rate_limiter);
     }
+
+    #[test]
+    fn test_max_outflow_remaining_window_boundaries() {
+        let mut rate_limiter = RateLimiter::new(
+            RateLimiterConfig {
+                window_duration: 10,
+                max_outflow: 100,
+            },
+            15,
+        );
+        assert_eq!(rate_limiter.update(15, Decimal::from(100u64)), Ok(()));
+
+        // last slot of the window
+        assert_eq!(
+            rate_limiter.max_outflow_remaining(19),
+            Ok(Decimal::from(0u64))
+        );
+        // first slot of the next window, the previous window is weighted 9/10
+        assert_eq!(
+            rate_limiter.max_outflow_remaining(20),
+            Ok(Decimal::from(10u64))
+        );
+        // last slot of the next window
+        assert_eq!(
+            rate_limiter.max_outflow_remaining(29),
+            Ok(Decimal::from(100u64))
+        );
+        // two windows ahead, the previous window is dropped
+        assert_eq!(
+            rate_limiter.max_outflow_remaining(30),
+            Ok(Decimal::from(100u64))
+        );
+
+        // a query doesn't move the windows
+        assert_eq!(rate_limiter.max_outflow_remaining(19), Ok(Decimal::zero()));
+        assert_eq!(
+            rate_limiter.update(19, Decimal::from(1u64)),
+            Err(LendingError::OutflowRateLimitExceeded.into())
+        );
+
+        // disabled rate limiter
+        let disabled = RateLimiter::new(RateLimiterConfig::default(), 15);
+        assert_eq!(
+            disabled.max_outflow_remaining(15),
+            Ok(Decimal::from(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_reset_rate_limiter() {
+        let mut rate_limiter = RateLimiter::new(
+            RateLimiterConfig {
+                window_duration: 10,
+                max_outflow: 100,
+            },
+            10,
+        );
+        assert_eq!(rate_limiter.update(10, Decimal::from(100u64)), Ok(()));
+        assert_eq!(rate_limiter.update(20, Decimal::from(10u64)), Ok(()));
+
+        rate_limiter.reset(21);
+        assert_eq!(
+            rate_limiter.max_outflow_remaining(21),
+            Ok(Decimal::from(100u64))
+        );
+        assert_eq!(rate_limiter.config.max_outflow, 100);
+        // the new window starts at the window boundary, not the reset slot, so the outflow of
+        // slot 29 is the previous window at slot 30
+        assert_eq!(rate_limiter.update(29, Decimal::from(100u64)), Ok(()));
+        assert_eq!(
+            rate_limiter.max_outflow_remaining(30),
+            Ok(Decimal::from(10u64))
+        );
+    }
 }
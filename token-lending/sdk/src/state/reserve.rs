@@ -7,7 +7,7 @@ use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use solana_program::{
-    clock::Slot,
+    clock::{Slot, UnixTimestamp},
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
@@ -20,9 +20,13 @@ use std::{
     convert::{TryFrom, TryInto},
 };
 
-/// Percentage of an obligation that can be repaid during each liquidation call
+/// Percentage of an obligation that can be repaid during each liquidation call when the obligation
+/// is at its unhealthy borrow value
 pub const LIQUIDATION_CLOSE_FACTOR: u8 = 20;
 
+/// Upper bound on the max close factor
+pub const MAX_CLOSE_FACTOR_BPS: u16 = 10_000;
+
 /// Obligation borrow amount that is small enough to close out
 pub const LIQUIDATION_CLOSE_AMOUNT: u64 = 2;
 
@@ -32,6 +36,10 @@ pub const MAX_LIQUIDATABLE_VALUE_AT_ONCE: u64 = 500_000;
 /// Maximum bonus received during liquidation. includes protocol fee.
 pub const MAX_BONUS_PCT: u8 = 25;
 
+/// Slots over which the liquidation bonus of a reserve in liquidation auction mode ramps up from
+/// 0 to its max liquidation bonus, starting when the obligation becomes unhealthy
+pub const LIQUIDATION_AUCTION_DURATION_SLOTS: u64 = 150;
+
 /// Maximum protocol liquidation fee in deca bps (1 deca bp = 10 bps)
 pub const MAX_PROTOCOL_LIQUIDATION_FEE_DECA_BPS: u8 = 50;
 
@@ -41,14 +49,19 @@ pub const MAX_SCALED_PRICE_OFFSET_BPS: i64 = 2000;
 /// Lower bound on scaled price offset
 pub const MIN_SCALED_PRICE_OFFSET_BPS: i64 = -2000;
 
+/// Max difference between a reserve's collateral mint decimals and liquidity mint decimals
+pub const MAX_COLLATERAL_DECIMALS_OFFSET: u8 = 9;
+
 /// Lending market reserve state
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Reserve {
     /// Version of the struct
     pub version: u8,
     /// Last slot when supply and rates updated
     pub last_update: LastUpdate,
     /// Lending market address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub lending_market: Pubkey,
     /// Reserve liquidity
     pub liquidity: ReserveLiquidity,
@@ -60,6 +73,39 @@ pub struct Reserve {
     pub rate_limiter: RateLimiter,
     /// Attributed borrows in USD
     pub attributed_borrow_value: Decimal,
+    /// Utilization percent above which the reserve switches to emergency mode, where borrows are
+    /// blocked. Zero disables the switch
+    pub emergency_utilization_threshold: u8,
+    /// Slots utilization must stay above the emergency utilization threshold to trigger the switch
+    pub emergency_utilization_slots: u64,
+    /// Slots so far utilization has stayed above the emergency utilization threshold
+    pub high_utilization_slots: u64,
+    /// Whether the reserve is in emergency mode. Deposits, withdrawals and repays still work so
+    /// utilization can come back down
+    pub emergency_mode: bool,
+    /// Whether the market price was last refreshed from the reserve's emergency price instead of
+    /// its oracles. Borrows, and collateral withdrawals from obligations with borrows, are blocked
+    /// while this is set
+    pub emergency_price_active: bool,
+    /// Oracle the reserve falls back to when its pyth and switchboard oracles fail, the last of the
+    /// up to three oracles refreshes try in order. Refreshes must then be passed it right after the
+    /// switchboard oracle
+    pub tertiary_oracle_pubkey: Option<Pubkey>,
+    /// Whether the reserve's borrow weight is scaled per collateral reserve by its pair borrow
+    /// weights account, which refreshes of obligations borrowing from it must then be passed
+    pub has_pair_borrow_weights: bool,
+    /// Whether part of the reserve's redeemed interest spread goes to the lending market's
+    /// insurance fund for its liquidity mint, which redeeming fees must then be passed
+    pub has_insurance_fund: bool,
+    /// Bump seed of the insurance fund address, set along with has_insurance_fund
+    pub insurance_fund_bump_seed: u8,
+    /// Whether the reserve's redeemed interest spread goes to the token account in its spread fee
+    /// receiver account instead of its fee receiver, which redeeming fees must then be passed
+    pub has_spread_fee_receiver: bool,
+    /// Rewards to the reserve's depositors, set by its deposit reward rule
+    pub deposit_rewards: ReserveRewards,
+    /// Rewards to the reserve's borrowers, set by its borrow reward rule
+    pub borrow_rewards: ReserveRewards,
 }
 
 impl Reserve {
@@ -80,6 +126,47 @@ impl Reserve {
         self.config = params.config;
         self.rate_limiter = RateLimiter::new(params.rate_limiter_config, params.current_slot);
         self.attributed_borrow_value = Decimal::zero();
+        self.emergency_utilization_threshold = 0;
+        self.emergency_utilization_slots = 0;
+        self.high_utilization_slots = 0;
+        self.emergency_mode = false;
+        self.emergency_price_active = false;
+        self.tertiary_oracle_pubkey = None;
+        self.has_pair_borrow_weights = false;
+        self.has_insurance_fund = false;
+        self.insurance_fund_bump_seed = 0;
+        self.has_spread_fee_receiver = false;
+        self.deposit_rewards = ReserveRewards::default();
+        self.borrow_rewards = ReserveRewards::default();
+    }
+
+    /// Record a refresh for the utilization kill switch. Utilization only changes in slots the
+    /// reserve is updated in, so if it's above the threshold, it has been since the last update
+    /// and those slots are counted. Returns true if this refresh switched the reserve to emergency
+    /// mode.
+    pub fn update_utilization_kill_switch(&mut self, slot: Slot) -> Result<bool, ProgramError> {
+        if self.emergency_utilization_threshold == 0
+            || self.emergency_mode
+            || slot <= self.last_update.slot
+        {
+            return Ok(false);
+        }
+
+        let utilization_rate = self.liquidity.utilization_rate()?;
+        if utilization_rate > Rate::from_percent(self.emergency_utilization_threshold) {
+            self.high_utilization_slots = self
+                .high_utilization_slots
+                .saturating_add(slot - self.last_update.slot);
+        } else {
+            self.high_utilization_slots = 0;
+        }
+
+        if self.high_utilization_slots >= max(1, self.emergency_utilization_slots) {
+            self.emergency_mode = true;
+            return Ok(true);
+        }
+
+        Ok(false)
     }
 
     /// get borrow weight. Guaranteed to be greater than 1
@@ -91,18 +178,34 @@ impl Reserve {
 
     /// get price weight. Guaranteed to be greater than 1
     pub fn price_scale(&self) -> Decimal {
-        let scaled_price_offset_bps = min(
-            MAX_SCALED_PRICE_OFFSET_BPS,
-            max(
-                MIN_SCALED_PRICE_OFFSET_BPS,
-                self.config.scaled_price_offset_bps,
-            ),
-        );
+        let scaled_price_offset_bps = self
+            .config
+            .scaled_price_offset_bps
+            .clamp(MIN_SCALED_PRICE_OFFSET_BPS, MAX_SCALED_PRICE_OFFSET_BPS);
 
         let price_weight_bps = 10_000 + scaled_price_offset_bps;
         Decimal::from_bps(price_weight_bps as u64)
     }
 
+    /// Cap an oracle price at the reserve's configured max price
+    pub fn cap_price(&self, price: Decimal) -> Decimal {
+        if self.config.max_price > 0 {
+            min(price, Decimal::from_bps(self.config.max_price))
+        } else {
+            price
+        }
+    }
+
+    /// Raise a price to the reserve's configured min price. Only used when valuing borrows, so
+    /// a depeg below the floor can never understate debt.
+    fn floor_borrow_price(&self, price: Decimal) -> Decimal {
+        if self.config.min_price > 0 {
+            max(price, Decimal::from_bps(self.config.min_price))
+        } else {
+            price
+        }
+    }
+
     /// get loan to value ratio as a Rate
     pub fn loan_to_value_ratio(&self) -> Rate {
         Rate::from_percent(self.config.loan_to_value_ratio)
@@ -116,9 +219,9 @@ impl Reserve {
         );
 
         if let Some(extra_price) = self.liquidity.extra_market_price {
-            std::cmp::max(price, extra_price)
+            self.floor_borrow_price(std::cmp::max(price, extra_price))
         } else {
-            price
+            self.floor_borrow_price(price)
         }
     }
 
@@ -136,6 +239,27 @@ impl Reserve {
         }
     }
 
+    /// Check if the spot price has moved further from the smoothed price than the liquidation
+    /// price band allows. Always false when the band is disabled.
+    pub fn is_price_outside_liquidation_band(&self) -> Result<bool, ProgramError> {
+        if self.config.liquidation_price_band_bps == 0 {
+            return Ok(false);
+        }
+
+        let market_price = self.liquidity.market_price;
+        let smoothed_market_price = self.liquidity.smoothed_market_price;
+        let deviation = if market_price > smoothed_market_price {
+            market_price.try_sub(smoothed_market_price)?
+        } else {
+            smoothed_market_price.try_sub(market_price)?
+        };
+        let band = smoothed_market_price.try_mul(Decimal::from_bps(
+            self.config.liquidation_price_band_bps as u64,
+        ))?;
+
+        Ok(deviation > band)
+    }
+
     /// Convert USD to liquidity tokens.
     /// eg how much SOL can you get for 100USD?
     pub fn usd_to_liquidity_amount_lower_bound(
@@ -164,8 +288,19 @@ impl Reserve {
             ))
     }
 
+    /// find current market value of borrowed tokens, floored at the reserve's min price
+    pub fn borrow_market_value(&self, liquidity_amount: Decimal) -> Result<Decimal, ProgramError> {
+        self.floor_borrow_price(self.liquidity.market_price)
+            .try_mul(liquidity_amount)?
+            .try_div(Decimal::from(
+                (10u128)
+                    .checked_pow(self.liquidity.mint_decimals as u32)
+                    .ok_or(LendingError::MathOverflow)?,
+            ))
+    }
+
     /// find the current upper bound market value of tokens.
-    /// ie max(market_price, smoothed_market_price, extra_market_price) * liquidity_amount
+    /// ie max(market_price, smoothed_market_price, extra_market_price, min_price) * liquidity_amount
     pub fn market_value_upper_bound(
         &self,
         liquidity_amount: Decimal,
@@ -206,6 +341,15 @@ impl Reserve {
         Ok(collateral_amount)
     }
 
+    /// Most liquidity that can still be deposited before the total supply reaches the deposit
+    /// limit
+    pub fn remaining_deposit_capacity(&self) -> Result<u64, ProgramError> {
+        Ok(self
+            .config
+            .deposit_limit
+            .saturating_sub(self.liquidity.total_supply()?.try_floor_u64()?))
+    }
+
     /// Record redeemed collateral and return amount of liquidity to withdraw
     pub fn redeem_collateral(&mut self, collateral_amount: u64) -> Result<u64, ProgramError> {
         let collateral_exchange_rate = self.collateral_exchange_rate()?;
@@ -218,6 +362,32 @@ impl Reserve {
         Ok(liquidity_amount)
     }
 
+    /// Record redeemed collateral that pays out exactly `liquidity_amount`. Whatever the
+    /// collateral is worth beyond that is left in the reserve.
+    pub fn redeem_collateral_exact(
+        &mut self,
+        collateral_amount: u64,
+        liquidity_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let redeemable_amount = self
+            .collateral_exchange_rate()?
+            .collateral_to_liquidity(collateral_amount)?;
+        if liquidity_amount > redeemable_amount {
+            msg!(
+                "Collateral amount {} only redeems {} liquidity, less than the {} requested",
+                collateral_amount,
+                redeemable_amount,
+                liquidity_amount
+            );
+            return Err(LendingError::WithdrawTooLarge.into());
+        }
+
+        self.collateral.burn(collateral_amount)?;
+        self.liquidity.withdraw(liquidity_amount)?;
+
+        Ok(liquidity_amount)
+    }
+
     /// Calculate the current borrow rate
     pub fn current_borrow_rate(&self) -> Result<Rate, ProgramError> {
         let utilization_rate = self.liquidity.utilization_rate()?;
@@ -240,15 +410,45 @@ impl Reserve {
 
             Ok(normalized_rate.try_mul(rate_range)?.try_add(min_rate)?)
         } else if utilization_rate <= max_utilization_rate {
-            let weight = utilization_rate
-                .try_sub(optimal_utilization_rate)?
-                .try_div(max_utilization_rate.try_sub(optimal_utilization_rate)?)?;
+            // with the super optimal kink enabled, the optimal to max segment is split in two
+            let super_optimal_utilization_rate =
+                Rate::from_percent(self.config.super_optimal_utilization_rate);
+            let super_optimal_borrow_rate =
+                Rate::from_percent(self.config.super_optimal_borrow_rate);
+            let (
+                lower_utilization_rate,
+                upper_utilization_rate,
+                lower_borrow_rate,
+                upper_borrow_rate,
+            ) = if self.config.super_optimal_utilization_rate == 0 {
+                (
+                    optimal_utilization_rate,
+                    max_utilization_rate,
+                    Rate::from_percent(self.config.optimal_borrow_rate),
+                    Rate::from_percent(self.config.max_borrow_rate),
+                )
+            } else if utilization_rate <= super_optimal_utilization_rate {
+                (
+                    optimal_utilization_rate,
+                    super_optimal_utilization_rate,
+                    Rate::from_percent(self.config.optimal_borrow_rate),
+                    super_optimal_borrow_rate,
+                )
+            } else {
+                (
+                    super_optimal_utilization_rate,
+                    max_utilization_rate,
+                    super_optimal_borrow_rate,
+                    Rate::from_percent(self.config.max_borrow_rate),
+                )
+            };
 
-            let optimal_borrow_rate = Rate::from_percent(self.config.optimal_borrow_rate);
-            let max_borrow_rate = Rate::from_percent(self.config.max_borrow_rate);
-            let rate_range = max_borrow_rate.try_sub(optimal_borrow_rate)?;
+            let weight = utilization_rate
+                .try_sub(lower_utilization_rate)?
+                .try_div(upper_utilization_rate.try_sub(lower_utilization_rate)?)?;
+            let rate_range = upper_borrow_rate.try_sub(lower_borrow_rate)?;
 
-            weight.try_mul(rate_range)?.try_add(optimal_borrow_rate)
+            weight.try_mul(rate_range)?.try_add(lower_borrow_rate)
         } else {
             let weight: Decimal = utilization_rate
                 .try_sub(max_utilization_rate)?
@@ -272,6 +472,26 @@ impl Reserve {
         }
     }
 
+    /// Calculate the current supply rate, the borrow rate paid on the borrowed share of the
+    /// liquidity less the protocol's take
+    pub fn current_supply_rate(&self) -> Result<Rate, ProgramError> {
+        let take_rate = Rate::from_percent(self.config.protocol_take_rate);
+        // like the borrow rate, done as a Decimal so large rates don't overflow
+        let supply_rate = Decimal::from(self.current_borrow_rate()?)
+            .try_mul(self.liquidity.utilization_rate()?)?
+            .try_mul(Rate::one().try_sub(take_rate)?)?;
+        supply_rate.try_into()
+    }
+
+    /// Calculate the current supply APY, with the supply rate compounded every slot
+    pub fn current_supply_apy(&self) -> Result<Rate, ProgramError> {
+        let slot_supply_rate = self.current_supply_rate()?.try_div(SLOTS_PER_YEAR)?;
+        Rate::one()
+            .try_add(slot_supply_rate)?
+            .try_pow(SLOTS_PER_YEAR)?
+            .try_sub(Rate::one())
+    }
+
     /// Collateral exchange rate
     pub fn collateral_exchange_rate(&self) -> Result<CollateralExchangeRate, ProgramError> {
         let total_liquidity = self.liquidity.total_supply()?;
@@ -290,6 +510,41 @@ impl Reserve {
         Ok(())
     }
 
+    /// Rewards to one side of the reserve
+    pub fn rewards(&self, side: RewardSide) -> &ReserveRewards {
+        match side {
+            RewardSide::Deposit => &self.deposit_rewards,
+            RewardSide::Borrow => &self.borrow_rewards,
+        }
+    }
+
+    /// Mutable rewards to one side of the reserve
+    pub fn rewards_mut(&mut self, side: RewardSide) -> &mut ReserveRewards {
+        match side {
+            RewardSide::Deposit => &mut self.deposit_rewards,
+            RewardSide::Borrow => &mut self.borrow_rewards,
+        }
+    }
+
+    /// Total positions of one side of the reserve - collateral tokens for deposits and borrowed
+    /// liquidity for borrows
+    pub fn total_reward_shares(&self, side: RewardSide) -> Decimal {
+        match side {
+            RewardSide::Deposit => Decimal::from(self.collateral.mint_total_supply),
+            RewardSide::Borrow => self.liquidity.borrowed_amount_wads,
+        }
+    }
+
+    /// Accrue both sides' reward emissions since their last update. Must run before deposits or
+    /// borrows change, so emissions are shared out by the totals they were emitted over.
+    pub fn accrue_rewards(&mut self, current_slot: Slot) -> ProgramResult {
+        for side in [RewardSide::Deposit, RewardSide::Borrow] {
+            let total_shares = self.total_reward_shares(side);
+            self.rewards_mut(side).accrue(current_slot, total_shares)?;
+        }
+        Ok(())
+    }
+
     /// Borrow liquidity up to a maximum market value
     pub fn calculate_borrow(
         &self,
@@ -370,7 +625,11 @@ impl Reserve {
 
     /// Calculate bonus as a percentage
     /// the value will be in range [0, MAX_BONUS_PCT]
-    pub fn calculate_bonus(&self, obligation: &Obligation) -> Result<Bonus, ProgramError> {
+    pub fn calculate_bonus(
+        &self,
+        obligation: &Obligation,
+        slot: Slot,
+    ) -> Result<Bonus, ProgramError> {
         if obligation.borrowed_value < obligation.unhealthy_borrow_value {
             if obligation.closeable {
                 return Ok(Bonus {
@@ -385,7 +644,29 @@ impl Reserve {
 
         let liquidation_bonus = Decimal::from_percent(self.config.liquidation_bonus);
         let max_liquidation_bonus = Decimal::from_percent(self.config.max_liquidation_bonus);
-        let protocol_liquidation_fee = Decimal::from_deca_bps(self.config.protocol_liquidation_fee);
+        // a fee paid in the repaid liquidity isn't carved out of the collateral
+        let protocol_liquidation_fee = if self.config.protocol_liquidation_fee_in_liquidity {
+            Decimal::zero()
+        } else {
+            Decimal::from_deca_bps(self.config.protocol_liquidation_fee)
+        };
+
+        // dutch auction: the liquidator's share grows with the time the obligation has been
+        // unhealthy, so whoever liquidates first takes the smallest bonus they'll accept
+        if self.config.liquidation_auction {
+            let weight = min(
+                Decimal::from(slot.saturating_sub(obligation.unhealthy_since_slot))
+                    .try_div(LIQUIDATION_AUCTION_DURATION_SLOTS)?,
+                Decimal::one(),
+            );
+            let bonus = weight
+                .try_mul(max_liquidation_bonus)?
+                .try_add(protocol_liquidation_fee)?;
+            return Ok(Bonus {
+                total_bonus: min(bonus, Decimal::from_percent(MAX_BONUS_PCT)),
+                protocol_liquidation_fee,
+            });
+        }
 
         // could also return the average of liquidation bonus and max liquidation bonus here, but
         // i don't think it matters
@@ -431,7 +712,8 @@ impl Reserve {
         })
     }
 
-    /// Liquidate some or all of an unhealthy obligation
+    /// Liquidate some or all of an unhealthy obligation. Borrows worth `dust_borrow_value` or
+    /// less are liquidated in full, ignoring the close factor
     pub fn calculate_liquidation(
         &self,
         amount_to_liquidate: u64,
@@ -439,6 +721,7 @@ impl Reserve {
         liquidity: &ObligationLiquidity,
         collateral: &ObligationCollateral,
         bonus: &Bonus,
+        dust_borrow_value: Decimal,
     ) -> Result<CalculateLiquidationResult, ProgramError> {
         if bonus.total_bonus > Decimal::from_percent(MAX_BONUS_PCT) {
             msg!("Bonus rate cannot exceed maximum bonus rate");
@@ -457,8 +740,9 @@ impl Reserve {
         let repay_amount;
         let withdraw_amount;
 
-        // do a full liquidation if the market value of the borrow is less than one.
-        if liquidity.market_value <= Decimal::one() {
+        // do a full liquidation if the borrow is dust, since what a partial one leaves behind
+        // might never be worth liquidating
+        if liquidity.market_value <= dust_borrow_value {
             let liquidation_value = liquidity.market_value.try_mul(bonus_rate)?;
             match liquidation_value.cmp(&collateral.market_value) {
                 Ordering::Greater => {
@@ -501,7 +785,7 @@ impl Reserve {
             // partial liquidation
             // calculate settle_amount and withdraw_amount, repay_amount is settle_amount rounded
             let liquidation_amount = obligation
-                .max_liquidation_amount(liquidity)?
+                .max_liquidation_amount(liquidity, self.config.max_close_factor_bps)?
                 .min(max_amount);
             let liquidation_pct = liquidation_amount.try_div(liquidity.borrowed_amount_wads)?;
             let liquidation_value = liquidity
@@ -565,6 +849,18 @@ impl Reserve {
         ))
     }
 
+    /// Calculate the protocol liquidation fee paid in the repaid liquidity, on top of repay_amount,
+    /// when the reserve takes it that way. 0 otherwise
+    pub fn calculate_protocol_liquidity_fee(&self, repay_amount: u64) -> Result<u64, ProgramError> {
+        if !self.config.protocol_liquidation_fee_in_liquidity {
+            return Ok(0);
+        }
+
+        Decimal::from(repay_amount)
+            .try_mul(Decimal::from_deca_bps(self.config.protocol_liquidation_fee))?
+            .try_ceil_u64()
+    }
+
     /// Calculate protocol fee redemption accounting for availible liquidity and accumulated fees
     pub fn calculate_redeem_fees(&self) -> Result<u64, ProgramError> {
         Ok(min(
@@ -639,16 +935,21 @@ pub struct Bonus {
 
 /// Reserve liquidity
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReserveLiquidity {
     /// Reserve liquidity mint address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub mint_pubkey: Pubkey,
     /// Reserve liquidity mint decimals
     pub mint_decimals: u8,
     /// Reserve liquidity supply address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub supply_pubkey: Pubkey,
     /// Reserve liquidity pyth oracle account
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub pyth_oracle_pubkey: Pubkey,
     /// Reserve liquidity switchboard oracle account
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub switchboard_oracle_pubkey: Pubkey,
     /// Reserve liquidity available
     pub available_amount: u64,
@@ -662,6 +963,11 @@ pub struct ReserveLiquidity {
     pub market_price: Decimal,
     /// Smoothed reserve liquidity market price for the liquidity (eg TWAP, VWAP, EMA)
     pub smoothed_market_price: Decimal,
+    /// Slot RefreshReserve last read market_price in
+    pub price_slot: Slot,
+    /// Publish time of the oracle price market_price was last read from. Emergency prices are
+    /// published when they're read.
+    pub price_publish_time: UnixTimestamp,
     /// Extra price obtained from the optional extra oracle
     pub extra_market_price: Option<Decimal>,
 }
@@ -681,6 +987,8 @@ impl ReserveLiquidity {
             accumulated_protocol_fees_wads: Decimal::zero(),
             market_price: params.market_price,
             smoothed_market_price: params.smoothed_market_price,
+            price_slot: params.price_slot,
+            price_publish_time: params.price_publish_time,
             extra_market_price: None,
         }
     }
@@ -751,7 +1059,8 @@ impl ReserveLiquidity {
         Ok(())
     }
 
-    /// Add donate_amount to available liquidity and subtract settle amount from total borrows
+    /// Add donate_amount to available liquidity. Donations aren't interest, so no protocol fee is
+    /// taken from them.
     pub fn donate(&mut self, donate_amount: u64) -> ProgramResult {
         self.available_amount = self
             .available_amount
@@ -830,17 +1139,27 @@ pub struct NewReserveLiquidityParams {
     pub market_price: Decimal,
     /// Smoothed reserve liquidity market price in quote currency
     pub smoothed_market_price: Decimal,
+    /// Slot the market price was read in
+    pub price_slot: Slot,
+    /// Publish time of the oracle price the market price was read from
+    pub price_publish_time: UnixTimestamp,
 }
 
 /// Reserve collateral
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReserveCollateral {
     /// Reserve collateral mint address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub mint_pubkey: Pubkey,
     /// Reserve collateral mint supply, used for exchange rate
     pub mint_total_supply: u64,
     /// Reserve collateral supply address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub supply_pubkey: Pubkey,
+    /// Collateral mint decimals minus liquidity mint decimals. 0 when the collateral mint
+    /// inherits the liquidity mint's decimals
+    pub decimals_offset: i8,
 }
 
 impl ReserveCollateral {
@@ -850,6 +1169,7 @@ impl ReserveCollateral {
             mint_pubkey: params.mint_pubkey,
             mint_total_supply: 0,
             supply_pubkey: params.supply_pubkey,
+            decimals_offset: params.decimals_offset,
         }
     }
 
@@ -877,7 +1197,16 @@ impl ReserveCollateral {
         total_liquidity: Decimal,
     ) -> Result<CollateralExchangeRate, ProgramError> {
         let rate = if self.mint_total_supply == 0 || total_liquidity == Decimal::zero() {
-            Rate::from_scaled_val(INITIAL_COLLATERAL_RATE)
+            // scaled so a whole collateral token starts out worth a whole liquidity token
+            let initial_rate = Rate::from_scaled_val(INITIAL_COLLATERAL_RATE);
+            let scale = 10u64
+                .checked_pow(self.decimals_offset.unsigned_abs() as u32)
+                .ok_or(LendingError::MathOverflow)?;
+            if self.decimals_offset >= 0 {
+                initial_rate.try_mul(scale)?
+            } else {
+                initial_rate.try_div(scale)?
+            }
         } else {
             let mint_total_supply = Decimal::from(self.mint_total_supply);
             Rate::try_from(mint_total_supply.try_div(total_liquidity)?)?
@@ -893,6 +1222,8 @@ pub struct NewReserveCollateralParams {
     pub mint_pubkey: Pubkey,
     /// Reserve collateral supply address
     pub supply_pubkey: Pubkey,
+    /// Collateral mint decimals minus liquidity mint decimals
+    pub decimals_offset: i8,
 }
 
 /// Collateral exchange rate
@@ -920,6 +1251,13 @@ impl CollateralExchangeRate {
             .try_floor_u64()
     }
 
+    /// Convert reserve liquidity to collateral, rounding up so that redeeming the collateral
+    /// gives at least liquidity_amount
+    pub fn liquidity_to_collateral_ceil(&self, liquidity_amount: u64) -> Result<u64, ProgramError> {
+        self.decimal_liquidity_to_collateral(liquidity_amount.into())?
+            .try_ceil_u64()
+    }
+
     /// Convert reserve liquidity to collateral
     pub fn decimal_liquidity_to_collateral(
         &self,
@@ -937,6 +1275,7 @@ impl From<CollateralExchangeRate> for Rate {
 
 /// Reserve configuration values
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReserveConfig {
     /// Optimal utilization rate, as a percentage
     pub optimal_utilization_rate: u8,
@@ -961,6 +1300,11 @@ pub struct ReserveConfig {
     pub max_borrow_rate: u8,
     /// Supermax borrow APY
     pub super_max_borrow_rate: u64,
+    /// Utilization rate of the kink between the optimal and max utilization rates, as a
+    /// percentage. 0 disables the kink
+    pub super_optimal_utilization_rate: u8,
+    /// Borrow APY at the super optimal utilization rate
+    pub super_optimal_borrow_rate: u8,
     /// Program owner fees assessed, separate from gains due to interest accrual
     pub fees: ReserveFees,
     /// Maximum deposit limit of liquidity in native units, u64::MAX for inf
@@ -968,6 +1312,7 @@ pub struct ReserveConfig {
     /// Borrows disabled
     pub borrow_limit: u64,
     /// Reserve liquidity fee receiver address
+    #[cfg_attr(feature = "serialize", serde(with = "crate::state::serialize::pubkey"))]
     pub fee_receiver: Pubkey,
     /// Cut of the liquidation bonus that the protocol receives, in deca bps
     pub protocol_liquidation_fee: u8,
@@ -982,11 +1327,71 @@ pub struct ReserveConfig {
     /// staked assets (mSOL, stETH). Not used on extra oracle
     pub scaled_price_offset_bps: i64,
     /// Extra oracle. Only used to limit borrows and withdrawals.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(with = "crate::state::serialize::option_pubkey")
+    )]
     pub extra_oracle_pubkey: Option<Pubkey>,
     /// Open Attributed Borrow limit in USD
     pub attributed_borrow_limit_open: u64,
     /// Close Attributed Borrow limit in USD
     pub attributed_borrow_limit_close: u64,
+    /// Close factor applied once an obligation reaches its super unhealthy borrow value. Scaled
+    /// down towards LIQUIDATION_CLOSE_FACTOR for obligations that are barely unhealthy. Values
+    /// below LIQUIDATION_CLOSE_FACTOR are treated as LIQUIDATION_CLOSE_FACTOR
+    pub max_close_factor_bps: u16,
+    /// Max deviation of the spot price from the smoothed price, in basis points, for obligations
+    /// to be liquidated against this reserve's collateral. 0 to disable
+    pub liquidation_price_band_bps: u16,
+    /// Max age of an oracle price, in seconds, before it is rejected as stale. 0 to use the
+    /// default of 120 seconds
+    pub max_oracle_staleness_secs: u64,
+    /// Max width of an oracle's confidence interval relative to its price, in basis points. 0 to
+    /// use the default of 1000 (10%)
+    pub max_oracle_confidence_bps: u16,
+    /// Max share of the reserve's total liquidity a single obligation may borrow, in basis points.
+    /// 0 means no limit.
+    pub max_single_borrower_share_bps: u16,
+    /// Deposits into the reserve, including as obligation collateral, are rejected
+    pub deposits_paused: bool,
+    /// Borrows and flash borrows from the reserve are rejected
+    pub borrows_paused: bool,
+    /// Withdrawals of obligation collateral and redemptions of collateral are rejected.
+    /// Liquidations are still allowed.
+    pub withdrawals_paused: bool,
+    /// Min market value, in whole USD, of an obligation's borrow from the reserve right after a
+    /// borrow. 0 means no minimum
+    pub min_borrow_value: u64,
+    /// Max borrowing power, in whole USD, a deposit of this reserve's collateral adds to an
+    /// obligation however large it is. 0 means no limit
+    pub max_collateral_value_contribution: u64,
+    /// Lowest price borrows of the liquidity are valued at whatever the oracles report, in
+    /// ten-thousandths of the quote currency. Deposits are still valued at the oracle price.
+    /// 0 means no bound
+    pub min_price: u64,
+    /// Highest price the liquidity is valued at whatever the oracles report, in ten-thousandths
+    /// of the quote currency, so oracle noise above a stablecoin's peg doesn't add borrowing
+    /// power. 0 means no bound
+    pub max_price: u64,
+    /// Liquidations against this reserve's collateral pay a bonus that ramps up from 0 to the max
+    /// liquidation bonus over LIQUIDATION_AUCTION_DURATION_SLOTS after the obligation becomes
+    /// unhealthy, instead of one based on how unhealthy the obligation is
+    pub liquidation_auction: bool,
+    /// Liquidations against this reserve's collateral pay the protocol liquidation fee in the
+    /// repaid liquidity, on top of the repay amount, instead of out of the collateral bonus
+    pub protocol_liquidation_fee_in_liquidity: bool,
+    /// Asset category of the reserve in the lending market's asset categories, 0 for none.
+    /// Obligations whose deposits and borrows all share a category use its limits
+    pub asset_category: u8,
+    /// Borrow APR, in percent, above which the insurance fund repays the interest of borrows with a
+    /// rate cap bought with BuyRateCap. 0 means rate caps aren't offered
+    pub borrow_rate_cap: u8,
+    /// Upfront premium for a rate cap, in basis points of the borrowed amount it covers, paid into
+    /// the insurance fund
+    pub rate_cap_premium_bps: u16,
+    /// Max liquidity the flash borrows of a single transaction can take from the reserve
+    /// combined. 0 means no cap
+    pub max_flash_loan_amount: u64,
 }
 
 /// validates reserve configs
@@ -1039,6 +1444,22 @@ pub fn validate_reserve_config(config: ReserveConfig) -> ProgramResult {
         msg!("Super max borrow rate must be >= max borrow rate");
         return Err(LendingError::InvalidConfig.into());
     }
+    if config.super_optimal_utilization_rate != 0 {
+        if config.super_optimal_utilization_rate < config.optimal_utilization_rate
+            || config.super_optimal_utilization_rate > config.max_utilization_rate
+        {
+            msg!("Super optimal utilization rate must be in range [optimal_utilization_rate, max_utilization_rate]");
+            return Err(LendingError::InvalidConfig.into());
+        }
+        if config.super_optimal_borrow_rate < config.optimal_borrow_rate
+            || config.super_optimal_borrow_rate > config.max_borrow_rate
+        {
+            msg!(
+                "Super optimal borrow rate must be in range [optimal_borrow_rate, max_borrow_rate]"
+            );
+            return Err(LendingError::InvalidConfig.into());
+        }
+    }
     if config.fees.borrow_fee_wad >= WAD {
         msg!("Borrow fee must be in range [0, 1_000_000_000_000_000_000)");
         return Err(LendingError::InvalidConfig.into());
@@ -1086,15 +1507,49 @@ pub fn validate_reserve_config(config: ReserveConfig) -> ProgramResult {
         return Err(LendingError::InvalidConfig.into());
     }
 
+    if config.min_price > 0 && config.max_price > 0 && config.min_price > config.max_price {
+        msg!("min price must be <= max price");
+        return Err(LendingError::InvalidConfig.into());
+    }
+
+    if config.rate_cap_premium_bps > 10_000 {
+        msg!("Rate cap premium must be in range [0, 10_000] bps");
+        return Err(LendingError::InvalidConfig.into());
+    }
+
     if config.attributed_borrow_limit_open > config.attributed_borrow_limit_close {
         msg!("open attributed borrow limit must be <= close attributed borrow limit");
         return Err(LendingError::InvalidConfig.into());
     }
+    if config.max_close_factor_bps > MAX_CLOSE_FACTOR_BPS {
+        msg!("max close factor must be <= {} bps", MAX_CLOSE_FACTOR_BPS);
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.liquidation_price_band_bps > 10_000 {
+        msg!("liquidation price band must be in range [0, 10000] bps");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.max_oracle_confidence_bps > 10_000 {
+        msg!("max oracle confidence must be in range [0, 10000] bps");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.max_single_borrower_share_bps > 10_000 {
+        msg!("max single borrower share must be in range [0, 10000] bps");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.asset_category as usize > MAX_ASSET_CATEGORIES {
+        msg!(
+            "asset category must be in range [0, {}]",
+            MAX_ASSET_CATEGORIES
+        );
+        return Err(LendingError::InvalidConfig.into());
+    }
 
     Ok(())
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromPrimitive)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 /// Asset Type of the reserve
 pub enum ReserveType {
     #[default]
@@ -1121,6 +1576,7 @@ impl FromStr for ReserveType {
 /// and frontend host. The fees are paid out as a percentage of liquidity token amounts during
 /// repayments and liquidations.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReserveFees {
     /// Fee assessed on `BorrowObligationLiquidity`, expressed as a Wad.
     /// Must be between 0 and 10^18, such that 10^18 = 1.  A few examples for
@@ -1221,6 +1677,25 @@ pub enum FeeCalculation {
     Inclusive,
 }
 
+/// Kind of fee in a "Fee" log event, see `events::FeeEvent`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+#[repr(u8)]
+pub enum FeeKind {
+    /// Borrow origination fee, paid to the reserve's fee receiver
+    Origination = 0,
+    /// Flash loan fee, paid to the reserve's fee receiver
+    FlashLoan = 1,
+    /// Share of an origination or flash loan fee paid to the host fee receiver
+    Host = 2,
+    /// Protocol liquidation fee, paid to the reserve's fee receiver. Out of the collateral bonus
+    /// in the withdraw reserve's liquidity, or on top of the repay amount in the repay reserve's
+    /// liquidity when the withdraw reserve takes it in the repaid liquidity
+    LiquidationProtocol = 3,
+    /// Protocol take rate share of borrow interest, accumulated on the reserve and redeemed to its
+    /// fee receiver, and its insurance fund if it has one, by RedeemFees
+    Spread = 4,
+}
+
 impl Sealed for Reserve {}
 impl IsInitialized for Reserve {
     fn is_initialized(&self) -> bool {
@@ -1228,10 +1703,57 @@ impl IsInitialized for Reserve {
     }
 }
 
-const RESERVE_LEN: usize = 619; // 1 + 8 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16 + 32 + 8 + 32 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 8 + 8 + 32 + 1 + 1 + 16 + 230
+/// Offset of the lending market in reserve accounts, for getProgramAccounts memcmp filters
+pub const RESERVE_LENDING_MARKET_OFFSET: usize = 10;
+/// Offset of the liquidity mint in reserve accounts
+pub const RESERVE_LIQUIDITY_MINT_OFFSET: usize = 42;
+/// Offset of the collateral mint in reserve accounts
+pub const RESERVE_COLLATERAL_MINT_OFFSET: usize = 227;
+
+/// Size of reserve accounts created before the V2 layout. The program reads and writes them as
+/// the start of the current layout until MigrateAccount reallocates them to Reserve::LEN.
+// 1 + 8 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16 + 32 + 8 + 32 + 1 + 1 + 1 + 1 + 1 + 1 +
+// 1 + 8 + 8 + 1 + 8 + 8 + 32 + 1 + 1 + 16 + 56 + 8 + 16 + 1 + 1 + 8 + 1 + 1 + 8 + 32 + 1 + 16 + 16 +
+// 8 + 8 + 49 (padding)
+pub const RESERVE_LEN_V1: usize = 619;
+// V1 layout followed by the V2 fields:
+// 1 + 1 + 1 + 2 + 2 + 8 + 2 + 2 + 1 + 1 + 1 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 1 + 2 + 8 + 1 + 8 + 8 +
+// 1 + 1 + 32 + 1 + 1 + 1 + 8 + 8 + 48 + 48 + 1 + 170 (padding)
+const RESERVE_LEN: usize = 1024;
 impl Pack for Reserve {
     const LEN: usize = RESERVE_LEN;
 
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.len() {
+            RESERVE_LEN => Self::unpack_from_slice(input),
+            RESERVE_LEN_V1 => unpack_v1_layout(input, RESERVE_LEN, Self::unpack_from_slice),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    fn pack(mut src: Self, dst: &mut [u8]) -> ProgramResult {
+        match dst.len() {
+            RESERVE_LEN => {
+                src.pack_into_slice(dst);
+                Ok(())
+            }
+            RESERVE_LEN_V1 => {
+                // V1 reserves have nowhere to cache their price, so RefreshObligation only
+                // accepts them refreshed in the same slot
+                src.liquidity.price_slot = 0;
+                src.liquidity.price_publish_time = 0;
+                pack_v1_layout(
+                    &src,
+                    dst,
+                    RESERVE_LEN,
+                    Self::pack_into_slice,
+                    Self::unpack_from_slice,
+                )
+            }
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
     // @TODO: break this up by reserve / liquidity / collateral / config https://git.io/JOCca
     fn pack_into_slice(&self, output: &mut [u8]) {
         let output = array_mut_ref![output, 0, RESERVE_LEN];
@@ -1284,6 +1806,42 @@ impl Pack for Reserve {
             attributed_borrow_value,
             config_attributed_borrow_limit_open,
             config_attributed_borrow_limit_close,
+            _padding_v1,
+            collateral_decimals_offset,
+            config_super_optimal_utilization_rate,
+            config_super_optimal_borrow_rate,
+            config_max_close_factor_bps,
+            config_liquidation_price_band_bps,
+            config_max_oracle_staleness_secs,
+            config_max_oracle_confidence_bps,
+            config_max_single_borrower_share_bps,
+            config_deposits_paused,
+            config_borrows_paused,
+            config_withdrawals_paused,
+            config_min_borrow_value,
+            config_max_collateral_value_contribution,
+            config_min_price,
+            config_max_price,
+            config_liquidation_auction,
+            config_protocol_liquidation_fee_in_liquidity,
+            config_asset_category,
+            config_borrow_rate_cap,
+            config_rate_cap_premium_bps,
+            config_max_flash_loan_amount,
+            emergency_utilization_threshold,
+            emergency_utilization_slots,
+            high_utilization_slots,
+            emergency_mode,
+            emergency_price_active,
+            tertiary_oracle_pubkey,
+            has_pair_borrow_weights,
+            has_insurance_fund,
+            has_spread_fee_receiver,
+            liquidity_price_slot,
+            liquidity_price_publish_time,
+            deposit_rewards,
+            borrow_rewards,
+            insurance_fund_bump_seed,
             _padding,
         ) = mut_array_refs![
             output,
@@ -1334,7 +1892,43 @@ impl Pack for Reserve {
             16,
             8,
             8,
-            49
+            49,
+            1,
+            1,
+            1,
+            2,
+            2,
+            8,
+            2,
+            2,
+            1,
+            1,
+            1,
+            8,
+            8,
+            8,
+            8,
+            1,
+            1,
+            1,
+            1,
+            2,
+            8,
+            1,
+            8,
+            8,
+            1,
+            1,
+            PUBKEY_BYTES,
+            1,
+            1,
+            1,
+            8,
+            8,
+            RESERVE_REWARDS_LEN,
+            RESERVE_REWARDS_LEN,
+            1,
+            170
         ];
 
         // reserve
@@ -1383,6 +1977,7 @@ impl Pack for Reserve {
         collateral_mint_pubkey.copy_from_slice(self.collateral.mint_pubkey.as_ref());
         *collateral_mint_total_supply = self.collateral.mint_total_supply.to_le_bytes();
         collateral_supply_pubkey.copy_from_slice(self.collateral.supply_pubkey.as_ref());
+        *collateral_decimals_offset = self.collateral.decimals_offset.to_le_bytes();
 
         // config
         *config_optimal_utilization_rate = self.config.optimal_utilization_rate.to_le_bytes();
@@ -1420,6 +2015,53 @@ impl Pack for Reserve {
             self.config.attributed_borrow_limit_close.to_le_bytes();
 
         pack_decimal(self.attributed_borrow_value, attributed_borrow_value);
+
+        // v2 config
+        *config_super_optimal_utilization_rate =
+            self.config.super_optimal_utilization_rate.to_le_bytes();
+        *config_super_optimal_borrow_rate = self.config.super_optimal_borrow_rate.to_le_bytes();
+        *config_max_close_factor_bps = self.config.max_close_factor_bps.to_le_bytes();
+        *config_liquidation_price_band_bps = self.config.liquidation_price_band_bps.to_le_bytes();
+        *config_max_oracle_staleness_secs = self.config.max_oracle_staleness_secs.to_le_bytes();
+        *config_max_oracle_confidence_bps = self.config.max_oracle_confidence_bps.to_le_bytes();
+        *config_max_single_borrower_share_bps =
+            self.config.max_single_borrower_share_bps.to_le_bytes();
+        pack_bool(self.config.deposits_paused, config_deposits_paused);
+        pack_bool(self.config.borrows_paused, config_borrows_paused);
+        pack_bool(self.config.withdrawals_paused, config_withdrawals_paused);
+        *config_min_borrow_value = self.config.min_borrow_value.to_le_bytes();
+        *config_max_collateral_value_contribution =
+            self.config.max_collateral_value_contribution.to_le_bytes();
+        *config_min_price = self.config.min_price.to_le_bytes();
+        *config_max_price = self.config.max_price.to_le_bytes();
+        pack_bool(self.config.liquidation_auction, config_liquidation_auction);
+        pack_bool(
+            self.config.protocol_liquidation_fee_in_liquidity,
+            config_protocol_liquidation_fee_in_liquidity,
+        );
+        *config_asset_category = self.config.asset_category.to_le_bytes();
+        *config_borrow_rate_cap = self.config.borrow_rate_cap.to_le_bytes();
+        *config_rate_cap_premium_bps = self.config.rate_cap_premium_bps.to_le_bytes();
+        *config_max_flash_loan_amount = self.config.max_flash_loan_amount.to_le_bytes();
+
+        // v2 state
+        *emergency_utilization_threshold = self.emergency_utilization_threshold.to_le_bytes();
+        *emergency_utilization_slots = self.emergency_utilization_slots.to_le_bytes();
+        *high_utilization_slots = self.high_utilization_slots.to_le_bytes();
+        pack_bool(self.emergency_mode, emergency_mode);
+        pack_bool(self.emergency_price_active, emergency_price_active);
+        match self.tertiary_oracle_pubkey {
+            Some(pubkey) => tertiary_oracle_pubkey.copy_from_slice(pubkey.as_ref()),
+            None => tertiary_oracle_pubkey.copy_from_slice(&[0u8; PUBKEY_BYTES]),
+        };
+        pack_bool(self.has_pair_borrow_weights, has_pair_borrow_weights);
+        pack_bool(self.has_insurance_fund, has_insurance_fund);
+        pack_bool(self.has_spread_fee_receiver, has_spread_fee_receiver);
+        *liquidity_price_slot = self.liquidity.price_slot.to_le_bytes();
+        *liquidity_price_publish_time = self.liquidity.price_publish_time.to_le_bytes();
+        pack_reserve_rewards(&self.deposit_rewards, deposit_rewards);
+        pack_reserve_rewards(&self.borrow_rewards, borrow_rewards);
+        *insurance_fund_bump_seed = self.insurance_fund_bump_seed.to_le_bytes();
     }
 
     /// Unpacks a byte buffer into a [ReserveInfo](struct.ReserveInfo.html).
@@ -1474,6 +2116,42 @@ impl Pack for Reserve {
             attributed_borrow_value,
             config_attributed_borrow_limit_open,
             config_attributed_borrow_limit_close,
+            _padding_v1,
+            collateral_decimals_offset,
+            config_super_optimal_utilization_rate,
+            config_super_optimal_borrow_rate,
+            config_max_close_factor_bps,
+            config_liquidation_price_band_bps,
+            config_max_oracle_staleness_secs,
+            config_max_oracle_confidence_bps,
+            config_max_single_borrower_share_bps,
+            config_deposits_paused,
+            config_borrows_paused,
+            config_withdrawals_paused,
+            config_min_borrow_value,
+            config_max_collateral_value_contribution,
+            config_min_price,
+            config_max_price,
+            config_liquidation_auction,
+            config_protocol_liquidation_fee_in_liquidity,
+            config_asset_category,
+            config_borrow_rate_cap,
+            config_rate_cap_premium_bps,
+            config_max_flash_loan_amount,
+            emergency_utilization_threshold,
+            emergency_utilization_slots,
+            high_utilization_slots,
+            emergency_mode,
+            emergency_price_active,
+            tertiary_oracle_pubkey,
+            has_pair_borrow_weights,
+            has_insurance_fund,
+            has_spread_fee_receiver,
+            liquidity_price_slot,
+            liquidity_price_publish_time,
+            deposit_rewards,
+            borrow_rewards,
+            insurance_fund_bump_seed,
             _padding,
         ) = array_refs![
             input,
@@ -1524,9 +2202,46 @@ impl Pack for Reserve {
             16,
             8,
             8,
-            49
+            49,
+            1,
+            1,
+            1,
+            2,
+            2,
+            8,
+            2,
+            2,
+            1,
+            1,
+            1,
+            8,
+            8,
+            8,
+            8,
+            1,
+            1,
+            1,
+            1,
+            2,
+            8,
+            1,
+            8,
+            8,
+            1,
+            1,
+            PUBKEY_BYTES,
+            1,
+            1,
+            1,
+            8,
+            8,
+            RESERVE_REWARDS_LEN,
+            RESERVE_REWARDS_LEN,
+            1,
+            170
         ];
 
+        // version 1 reserves are read with the v2 fields zeroed, which leaves them disabled
         let version = u8::from_le_bytes(*version);
         if version > PROGRAM_VERSION {
             msg!("Reserve version does not match lending program version");
@@ -1571,6 +2286,8 @@ impl Pack for Reserve {
                 ),
                 market_price: unpack_decimal(liquidity_market_price),
                 smoothed_market_price: unpack_decimal(liquidity_smoothed_market_price),
+                price_slot: u64::from_le_bytes(*liquidity_price_slot),
+                price_publish_time: i64::from_le_bytes(*liquidity_price_publish_time),
                 extra_market_price: match liquidity_extra_market_price_flag[0] {
                     0 => None,
                     1 => Some(unpack_decimal(liquidity_extra_market_price)),
@@ -1584,6 +2301,7 @@ impl Pack for Reserve {
                 mint_pubkey: Pubkey::new_from_array(*collateral_mint_pubkey),
                 mint_total_supply: u64::from_le_bytes(*collateral_mint_total_supply),
                 supply_pubkey: Pubkey::new_from_array(*collateral_supply_pubkey),
+                decimals_offset: i8::from_le_bytes(*collateral_decimals_offset),
             },
             config: ReserveConfig {
                 optimal_utilization_rate,
@@ -1603,6 +2321,10 @@ impl Pack for Reserve {
                     max_borrow_rate as u64,
                     u64::from_le_bytes(*config_super_max_borrow_rate),
                 ),
+                super_optimal_utilization_rate: u8::from_le_bytes(
+                    *config_super_optimal_utilization_rate,
+                ),
+                super_optimal_borrow_rate: u8::from_le_bytes(*config_super_optimal_borrow_rate),
                 fees: ReserveFees {
                     borrow_fee_wad: u64::from_le_bytes(*config_fees_borrow_fee_wad),
                     flash_loan_fee_wad: u64::from_le_bytes(*config_fees_flash_loan_fee_wad),
@@ -1649,13 +2371,84 @@ impl Pack for Reserve {
                         value
                     }
                 },
+                // zero on reserves created before the dynamic close factor, which keeps their
+                // close factor fixed at LIQUIDATION_CLOSE_FACTOR.
+                max_close_factor_bps: max(
+                    LIQUIDATION_CLOSE_FACTOR as u16 * 100,
+                    u16::from_le_bytes(*config_max_close_factor_bps),
+                ),
+                liquidation_price_band_bps: u16::from_le_bytes(*config_liquidation_price_band_bps),
+                max_oracle_staleness_secs: u64::from_le_bytes(*config_max_oracle_staleness_secs),
+                max_oracle_confidence_bps: u16::from_le_bytes(*config_max_oracle_confidence_bps),
+                max_single_borrower_share_bps: u16::from_le_bytes(
+                    *config_max_single_borrower_share_bps,
+                ),
+                deposits_paused: unpack_bool(config_deposits_paused)?,
+                borrows_paused: unpack_bool(config_borrows_paused)?,
+                withdrawals_paused: unpack_bool(config_withdrawals_paused)?,
+                min_borrow_value: u64::from_le_bytes(*config_min_borrow_value),
+                min_price: u64::from_le_bytes(*config_min_price),
+                max_price: u64::from_le_bytes(*config_max_price),
+                liquidation_auction: unpack_bool(config_liquidation_auction)?,
+                protocol_liquidation_fee_in_liquidity: unpack_bool(
+                    config_protocol_liquidation_fee_in_liquidity,
+                )?,
+                max_collateral_value_contribution: u64::from_le_bytes(
+                    *config_max_collateral_value_contribution,
+                ),
+                asset_category: u8::from_le_bytes(*config_asset_category),
+                borrow_rate_cap: u8::from_le_bytes(*config_borrow_rate_cap),
+                rate_cap_premium_bps: u16::from_le_bytes(*config_rate_cap_premium_bps),
+                max_flash_loan_amount: u64::from_le_bytes(*config_max_flash_loan_amount),
             },
             rate_limiter: RateLimiter::unpack_from_slice(rate_limiter)?,
             attributed_borrow_value: unpack_decimal(attributed_borrow_value),
+            emergency_utilization_threshold: u8::from_le_bytes(*emergency_utilization_threshold),
+            emergency_utilization_slots: u64::from_le_bytes(*emergency_utilization_slots),
+            high_utilization_slots: u64::from_le_bytes(*high_utilization_slots),
+            emergency_mode: unpack_bool(emergency_mode)?,
+            emergency_price_active: unpack_bool(emergency_price_active)?,
+            tertiary_oracle_pubkey: if tertiary_oracle_pubkey == &[0; 32] {
+                None
+            } else {
+                Some(Pubkey::new_from_array(*tertiary_oracle_pubkey))
+            },
+            has_pair_borrow_weights: unpack_bool(has_pair_borrow_weights)?,
+            has_insurance_fund: unpack_bool(has_insurance_fund)?,
+            insurance_fund_bump_seed: u8::from_le_bytes(*insurance_fund_bump_seed),
+            has_spread_fee_receiver: unpack_bool(has_spread_fee_receiver)?,
+            deposit_rewards: unpack_reserve_rewards(deposit_rewards),
+            borrow_rewards: unpack_reserve_rewards(borrow_rewards),
         })
     }
 }
 
+const RESERVE_REWARDS_LEN: usize = 48; // 8 + 8 + 8 + 8 + 16
+
+fn pack_reserve_rewards(rewards: &ReserveRewards, dst: &mut [u8; RESERVE_REWARDS_LEN]) {
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (emission_per_slot, start_slot, end_slot, last_update_slot, reward_index) =
+        mut_array_refs![dst, 8, 8, 8, 8, 16];
+    *emission_per_slot = rewards.emission_per_slot.to_le_bytes();
+    *start_slot = rewards.start_slot.to_le_bytes();
+    *end_slot = rewards.end_slot.to_le_bytes();
+    *last_update_slot = rewards.last_update_slot.to_le_bytes();
+    pack_decimal(rewards.reward_index, reward_index);
+}
+
+fn unpack_reserve_rewards(src: &[u8; RESERVE_REWARDS_LEN]) -> ReserveRewards {
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (emission_per_slot, start_slot, end_slot, last_update_slot, reward_index) =
+        array_refs![src, 8, 8, 8, 8, 16];
+    ReserveRewards {
+        emission_per_slot: u64::from_le_bytes(*emission_per_slot),
+        start_slot: u64::from_le_bytes(*start_slot),
+        end_slot: u64::from_le_bytes(*end_slot),
+        last_update_slot: u64::from_le_bytes(*last_update_slot),
+        reward_index: unpack_decimal(reward_index),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1670,6 +2463,40 @@ mod test {
         Decimal::from_scaled_val(rand::thread_rng().gen())
     }
 
+    #[test]
+    fn pack_and_unpack_v1_reserve() {
+        // the start of a current reserve, as a version 1 account that was never migrated
+        let mut packed = vec![0u8; Reserve::LEN];
+        Reserve {
+            version: 1,
+            lending_market: Pubkey::new_unique(),
+            liquidity: ReserveLiquidity {
+                available_amount: 100,
+                ..ReserveLiquidity::default()
+            },
+            ..Reserve::default()
+        }
+        .pack_into_slice(&mut packed);
+        packed.truncate(RESERVE_LEN_V1);
+
+        // the cached price doesn't fit, so the reserve reads as unrefreshed since version 1
+        let mut reserve = Reserve::unpack(&packed).unwrap();
+        assert_eq!(reserve.liquidity.available_amount, 100);
+        reserve.liquidity.price_slot = 10;
+        let mut repacked = vec![0u8; RESERVE_LEN_V1];
+        Reserve::pack(reserve.clone(), &mut repacked).unwrap();
+        let mut unpacked = Reserve::unpack(&repacked).unwrap();
+        assert_eq!(unpacked.liquidity.price_slot, 0);
+        unpacked.liquidity.price_slot = 10;
+        assert_eq!(unpacked, reserve);
+
+        reserve.config.min_borrow_value = 1;
+        assert_eq!(
+            Reserve::pack(reserve, &mut repacked),
+            Err(LendingError::AccountNotMigrated.into())
+        );
+    }
+
     #[test]
     fn pack_and_unpack_reserve() {
         let mut rng = rand::thread_rng();
@@ -1707,12 +2534,15 @@ mod test {
                     accumulated_protocol_fees_wads: rand_decimal(),
                     market_price: rand_decimal(),
                     smoothed_market_price: rand_decimal(),
+                    price_slot: rng.gen(),
+                    price_publish_time: rng.gen(),
                     extra_market_price,
                 },
                 collateral: ReserveCollateral {
                     mint_pubkey: Pubkey::new_unique(),
                     mint_total_supply: rng.gen(),
                     supply_pubkey: Pubkey::new_unique(),
+                    decimals_offset: rng.gen(),
                 },
                 config: ReserveConfig {
                     optimal_utilization_rate,
@@ -1726,6 +2556,8 @@ mod test {
                     optimal_borrow_rate: rng.gen(),
                     max_borrow_rate: rng.gen(),
                     super_max_borrow_rate: max(u8::MAX as u64, rng.gen()),
+                    super_optimal_utilization_rate: rng.gen(),
+                    super_optimal_borrow_rate: rng.gen(),
                     fees: ReserveFees {
                         borrow_fee_wad: rng.gen(),
                         flash_loan_fee_wad: rng.gen(),
@@ -1738,13 +2570,60 @@ mod test {
                     protocol_take_rate: rng.gen(),
                     added_borrow_weight_bps: rng.gen(),
                     reserve_type: ReserveType::from_u8(rng.gen::<u8>() % 2).unwrap(),
-                    scaled_price_offset_bps: rng.gen(),
+                    scaled_price_offset_bps: rng
+                        .gen_range(MIN_SCALED_PRICE_OFFSET_BPS..=MAX_SCALED_PRICE_OFFSET_BPS),
                     extra_oracle_pubkey,
                     attributed_borrow_limit_open: rng.gen(),
                     attributed_borrow_limit_close: rng.gen(),
+                    max_close_factor_bps: max(LIQUIDATION_CLOSE_FACTOR as u16 * 100, rng.gen()),
+                    liquidation_price_band_bps: rng.gen(),
+                    max_oracle_staleness_secs: rng.gen(),
+                    max_oracle_confidence_bps: rng.gen(),
+                    max_single_borrower_share_bps: rng.gen(),
+                    deposits_paused: rng.gen(),
+                    borrows_paused: rng.gen(),
+                    withdrawals_paused: rng.gen(),
+                    min_borrow_value: rng.gen(),
+                    max_collateral_value_contribution: rng.gen(),
+                    min_price: rng.gen(),
+                    max_price: rng.gen(),
+                    liquidation_auction: rng.gen(),
+                    protocol_liquidation_fee_in_liquidity: rng.gen(),
+                    asset_category: rng.gen_range(0..=MAX_ASSET_CATEGORIES as u8),
+                    borrow_rate_cap: rng.gen(),
+                    rate_cap_premium_bps: rng.gen(),
+                    max_flash_loan_amount: rng.gen(),
                 },
                 rate_limiter: rand_rate_limiter(),
                 attributed_borrow_value: rand_decimal(),
+                emergency_utilization_threshold: rng.gen(),
+                emergency_utilization_slots: rng.gen(),
+                high_utilization_slots: rng.gen(),
+                emergency_mode: rng.gen(),
+                emergency_price_active: rng.gen(),
+                tertiary_oracle_pubkey: if rng.gen_bool(0.5) {
+                    Some(Pubkey::new_unique())
+                } else {
+                    None
+                },
+                has_pair_borrow_weights: rng.gen(),
+                has_insurance_fund: rng.gen(),
+                insurance_fund_bump_seed: rng.gen(),
+                has_spread_fee_receiver: rng.gen(),
+                deposit_rewards: ReserveRewards {
+                    emission_per_slot: rng.gen(),
+                    start_slot: rng.gen(),
+                    end_slot: rng.gen(),
+                    last_update_slot: rng.gen(),
+                    reward_index: rand_decimal(),
+                },
+                borrow_rewards: ReserveRewards {
+                    emission_per_slot: rng.gen(),
+                    start_slot: rng.gen(),
+                    end_slot: rng.gen(),
+                    last_update_slot: rng.gen(),
+                    reward_index: rand_decimal(),
+                },
             };
 
             let mut packed = [0u8; Reserve::LEN];
@@ -1875,6 +2754,48 @@ mod test {
             assert!(current_rate <= Rate::one());
         }
 
+        #[test]
+        fn current_supply_rate(
+            total_liquidity in 0..=MAX_LIQUIDITY,
+            borrowed_percent in 0..=WAD,
+            (optimal_utilization_rate, max_utilization_rate) in utilizations(),
+            (min_borrow_rate, optimal_borrow_rate, max_borrow_rate, super_max_borrow_rate) in borrow_rates(),
+            protocol_take_rate in 0..=100u8,
+        ) {
+            let borrowed_amount_wads = Decimal::from(total_liquidity)
+                .try_mul(Rate::from_scaled_val(borrowed_percent))?;
+            let reserve = Reserve {
+                liquidity: ReserveLiquidity {
+                    borrowed_amount_wads,
+                    available_amount: total_liquidity - borrowed_amount_wads.try_round_u64()?,
+                    ..ReserveLiquidity::default()
+                },
+                config: ReserveConfig {
+                    optimal_utilization_rate,
+                    max_utilization_rate,
+                    min_borrow_rate,
+                    optimal_borrow_rate,
+                    max_borrow_rate,
+                    super_max_borrow_rate,
+                    protocol_take_rate,
+                    ..ReserveConfig::default()
+                },
+                ..Reserve::default()
+            };
+
+            let current_supply_rate = reserve.current_supply_rate()?;
+            assert!(current_supply_rate <= reserve.current_borrow_rate()?);
+            if protocol_take_rate == 100 || reserve.liquidity.utilization_rate()? == Rate::zero() {
+                assert_eq!(current_supply_rate, Rate::zero());
+            }
+            // compounding overflows for the most extreme rates, otherwise per-slot compounding can
+            // round away up to one wad unit per slot
+            if let Ok(current_supply_apy) = reserve.current_supply_apy() {
+                let rounding = Rate::from_scaled_val(SLOTS_PER_YEAR);
+                assert!(current_supply_apy.try_add(rounding)? >= current_supply_rate);
+            }
+        }
+
         #[test]
         fn collateral_exchange_rate(
             total_liquidity in 0..=MAX_LIQUIDITY,
@@ -1919,6 +2840,16 @@ mod test {
             }
         }
 
+        #[test]
+        fn liquidity_to_collateral_ceil(
+            liquidity_amount in 0..=MAX_LIQUIDITY,
+            exchange_rate in collateral_exchange_rate_range(),
+        ) {
+            let collateral_amount = exchange_rate.liquidity_to_collateral_ceil(liquidity_amount)?;
+            assert!(collateral_amount >= exchange_rate.liquidity_to_collateral(liquidity_amount)?);
+            assert!(exchange_rate.collateral_to_liquidity(collateral_amount)? >= liquidity_amount);
+        }
+
         #[test]
         fn compound_interest(
             slots_elapsed in 0..=SLOTS_PER_YEAR,
@@ -2053,6 +2984,123 @@ mod test {
         }
     }
 
+    #[test]
+    fn super_optimal_borrow_rate() {
+        let mut reserve = Reserve {
+            config: ReserveConfig {
+                optimal_utilization_rate: 80,
+                super_optimal_utilization_rate: 95,
+                max_utilization_rate: 100,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 10,
+                super_optimal_borrow_rate: 30,
+                max_borrow_rate: 200,
+                super_max_borrow_rate: 300,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        assert_eq!(validate_reserve_config(reserve.config), Ok(()));
+
+        for (utilization, borrow_rate) in [(40, 5), (86, 18), (95, 30), (99, 166)] {
+            reserve.liquidity.borrowed_amount_wads = Decimal::from(utilization);
+            reserve.liquidity.available_amount = 100 - utilization;
+            assert_eq!(
+                reserve.current_borrow_rate().unwrap(),
+                Rate::from_percent(borrow_rate as u8)
+            );
+        }
+
+        // without the kink, optimal to max is a single segment
+        reserve.config.super_optimal_utilization_rate = 0;
+        reserve.liquidity.borrowed_amount_wads = Decimal::from(86u64);
+        reserve.liquidity.available_amount = 14;
+        assert_eq!(
+            reserve.current_borrow_rate().unwrap(),
+            Rate::from_percent(67)
+        );
+
+        // the kink has to sit between the optimal and max points
+        reserve.config.super_optimal_utilization_rate = 70;
+        assert_eq!(
+            validate_reserve_config(reserve.config),
+            Err(LendingError::InvalidConfig.into())
+        );
+        reserve.config.super_optimal_utilization_rate = 95;
+        reserve.config.super_optimal_borrow_rate = 201;
+        assert_eq!(
+            validate_reserve_config(reserve.config),
+            Err(LendingError::InvalidConfig.into())
+        );
+    }
+
+    #[test]
+    fn initial_collateral_exchange_rate_decimals_offset() {
+        // 9 decimal liquidity, 6 decimal collateral
+        let mut reserve = Reserve {
+            collateral: ReserveCollateral {
+                decimals_offset: -3,
+                ..ReserveCollateral::default()
+            },
+            ..Reserve::default()
+        };
+        let exchange_rate = reserve.collateral_exchange_rate().unwrap();
+        assert_eq!(
+            exchange_rate
+                .liquidity_to_collateral(1_000_000_000)
+                .unwrap(),
+            1_000_000
+        );
+        assert_eq!(
+            exchange_rate.collateral_to_liquidity(1_000_000).unwrap(),
+            1_000_000_000
+        );
+
+        // 0 decimal liquidity, 6 decimal collateral
+        reserve.collateral.decimals_offset = 6;
+        let exchange_rate = reserve.collateral_exchange_rate().unwrap();
+        assert_eq!(exchange_rate.liquidity_to_collateral(1).unwrap(), 1_000_000);
+        assert_eq!(exchange_rate.collateral_to_liquidity(1_000_000).unwrap(), 1);
+
+        // once collateral is minted the rate follows the supplies
+        reserve.deposit_liquidity(1).unwrap();
+        reserve.liquidity.deposit(1).unwrap();
+        let exchange_rate = reserve.collateral_exchange_rate().unwrap();
+        assert_eq!(exchange_rate.liquidity_to_collateral(1).unwrap(), 500_000);
+    }
+
+    #[test]
+    fn redeem_collateral_exact() {
+        // 1.9 liquidity per collateral token
+        let mut reserve = Reserve::default();
+        reserve.liquidity.deposit(190).unwrap();
+        reserve.collateral.mint(100).unwrap();
+
+        let collateral_amount = reserve
+            .collateral_exchange_rate()
+            .unwrap()
+            .liquidity_to_collateral_ceil(101)
+            .unwrap();
+        assert_eq!(collateral_amount, 54);
+        // the rounded up collateral is worth more than was asked for
+        assert_eq!(
+            reserve
+                .collateral_exchange_rate()
+                .unwrap()
+                .collateral_to_liquidity(collateral_amount)
+                .unwrap(),
+            102
+        );
+
+        assert_eq!(
+            reserve.redeem_collateral_exact(54, 103).unwrap_err(),
+            LendingError::WithdrawTooLarge.into()
+        );
+        assert_eq!(reserve.redeem_collateral_exact(54, 101).unwrap(), 101);
+        assert_eq!(reserve.liquidity.available_amount, 89);
+        assert_eq!(reserve.collateral.mint_total_supply, 46);
+    }
+
     #[test]
     fn borrow_fee_calculation_min_host() {
         let fees = ReserveFees {
@@ -2081,6 +3129,78 @@ mod test {
         assert_eq!(host_fee, 0);
     }
 
+    #[test]
+    fn liquidation_price_band() {
+        let mut reserve = Reserve {
+            liquidity: ReserveLiquidity {
+                market_price: Decimal::from(95u64),
+                smoothed_market_price: Decimal::from(100u64),
+                ..ReserveLiquidity::default()
+            },
+            ..Reserve::default()
+        };
+
+        // disabled by default
+        assert!(!reserve.is_price_outside_liquidation_band().unwrap());
+
+        reserve.config.liquidation_price_band_bps = 500;
+        assert!(!reserve.is_price_outside_liquidation_band().unwrap());
+
+        reserve.liquidity.market_price = Decimal::from(94u64);
+        assert!(reserve.is_price_outside_liquidation_band().unwrap());
+
+        reserve.liquidity.market_price = Decimal::from(106u64);
+        assert!(reserve.is_price_outside_liquidation_band().unwrap());
+    }
+
+    #[test]
+    fn utilization_kill_switch() {
+        let mut reserve = Reserve {
+            liquidity: ReserveLiquidity {
+                available_amount: 5,
+                borrowed_amount_wads: Decimal::from(95u64),
+                ..ReserveLiquidity::default()
+            },
+            emergency_utilization_threshold: 90,
+            emergency_utilization_slots: 10,
+            ..Reserve::default()
+        };
+
+        // the slots since the last update are counted, refreshes in the same slot aren't
+        assert!(!reserve.update_utilization_kill_switch(4).unwrap());
+        reserve.last_update.update_slot(4);
+        assert!(!reserve.update_utilization_kill_switch(4).unwrap());
+        assert_eq!(reserve.high_utilization_slots, 4);
+
+        // dropping below the threshold resets the count
+        reserve.liquidity.available_amount = 50;
+        assert!(!reserve.update_utilization_kill_switch(5).unwrap());
+        reserve.last_update.update_slot(5);
+        assert_eq!(reserve.high_utilization_slots, 0);
+
+        reserve.liquidity.available_amount = 5;
+        assert!(!reserve.update_utilization_kill_switch(14).unwrap());
+        reserve.last_update.update_slot(14);
+        assert_eq!(reserve.high_utilization_slots, 9);
+        assert!(reserve.update_utilization_kill_switch(15).unwrap());
+        assert!(reserve.emergency_mode);
+
+        // once triggered, the switch stays on until reset
+        reserve.last_update.update_slot(15);
+        assert!(!reserve.update_utilization_kill_switch(16).unwrap());
+        assert!(reserve.emergency_mode);
+
+        // a zero threshold disables the switch
+        let mut reserve = Reserve {
+            emergency_utilization_threshold: 0,
+            emergency_utilization_slots: 1,
+            emergency_mode: false,
+            ..reserve
+        };
+        assert!(!reserve.update_utilization_kill_switch(17).unwrap());
+        assert!(!reserve.emergency_mode);
+    }
+
     #[test]
     fn borrow_fee_calculation_min_no_host() {
         let fees = ReserveFees {
@@ -2205,6 +3325,28 @@ mod test {
         );
     }
 
+    #[test]
+    fn calculate_protocol_liquidity_fee() {
+        let mut reserve = Reserve {
+            config: ReserveConfig {
+                protocol_liquidation_fee: 10,
+                ..Default::default()
+            },
+            ..Reserve::default()
+        };
+
+        // taken out of the collateral bonus instead
+        assert_eq!(reserve.calculate_protocol_liquidity_fee(10000).unwrap(), 0);
+
+        reserve.config.protocol_liquidation_fee_in_liquidity = true;
+        assert_eq!(
+            reserve.calculate_protocol_liquidity_fee(10000).unwrap(),
+            100
+        );
+        assert_eq!(reserve.calculate_protocol_liquidity_fee(105).unwrap(), 2);
+        assert_eq!(reserve.calculate_protocol_liquidity_fee(0).unwrap(), 0);
+    }
+
     #[test]
     fn price() {
         let mut reserve = Reserve {
@@ -2227,6 +3369,47 @@ mod test {
         assert_eq!(reserve.price_lower_bound(), Decimal::from(10u64));
     }
 
+    #[test]
+    fn price_bounds() {
+        let mut reserve = Reserve::default();
+        assert_eq!(reserve.cap_price(Decimal::from(2u64)), Decimal::from(2u64));
+
+        reserve.config.min_price = 9_900;
+        reserve.config.max_price = 10_050;
+        assert_eq!(
+            reserve.cap_price(Decimal::from_percent(103)),
+            Decimal::from_bps(10_050)
+        );
+        assert_eq!(
+            reserve.cap_price(Decimal::from_percent(90)),
+            Decimal::from_percent(90)
+        );
+
+        // a depeg below the min price only raises the value of borrows
+        reserve.liquidity.market_price = Decimal::from_percent(90);
+        reserve.liquidity.smoothed_market_price = Decimal::from_percent(90);
+        assert_eq!(
+            reserve.market_value(Decimal::from(10u64)).unwrap(),
+            Decimal::from(9u64)
+        );
+        assert_eq!(
+            reserve
+                .market_value_lower_bound(Decimal::from(10u64))
+                .unwrap(),
+            Decimal::from(9u64)
+        );
+        assert_eq!(
+            reserve.borrow_market_value(Decimal::from(10u64)).unwrap(),
+            Decimal::from_bps(99_000)
+        );
+        assert_eq!(
+            reserve
+                .market_value_upper_bound(Decimal::from(10u64))
+                .unwrap(),
+            Decimal::from_bps(99_000)
+        );
+    }
+
     #[test]
     fn market_value() {
         let mut reserve = Reserve {
@@ -2306,6 +3489,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn remaining_deposit_capacity() {
+        let mut reserve = Reserve {
+            liquidity: ReserveLiquidity {
+                available_amount: 600,
+                borrowed_amount_wads: Decimal::from(300u64)
+                    .try_add(Decimal::from_percent(50))
+                    .unwrap(),
+                accumulated_protocol_fees_wads: Decimal::from(100u64),
+                ..ReserveLiquidity::default()
+            },
+            config: ReserveConfig {
+                deposit_limit: 1_000,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        // the total supply of 800.5 is floored, like the deposit limit check
+        assert_eq!(reserve.remaining_deposit_capacity(), Ok(200));
+
+        reserve.config.deposit_limit = 700;
+        assert_eq!(reserve.remaining_deposit_capacity(), Ok(0));
+    }
+
     #[derive(Debug, Clone)]
     struct ReserveConfigTestCase {
         config: ReserveConfig,
@@ -2408,6 +3615,28 @@ mod test {
                 },
                 result: Ok(())
             }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    min_price: 10_001,
+                    max_price: 10_000,
+                    ..ReserveConfig::default()
+                },
+                result: Err(LendingError::InvalidConfig.into()),
+            }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    min_price: 10_001,
+                    ..ReserveConfig::default()
+                },
+                result: Ok(())
+            }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    rate_cap_premium_bps: 10_001,
+                    ..ReserveConfig::default()
+                },
+                result: Err(LendingError::InvalidConfig.into()),
+            }),
             Just(ReserveConfigTestCase {
                 config: ReserveConfig {
                     attributed_borrow_limit_open: 51,
@@ -2415,6 +3644,34 @@ mod test {
                     ..ReserveConfig::default()
                 },
                 result: Err(LendingError::InvalidConfig.into()),
+            }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    max_close_factor_bps: MAX_CLOSE_FACTOR_BPS + 1,
+                    ..ReserveConfig::default()
+                },
+                result: Err(LendingError::InvalidConfig.into()),
+            }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    liquidation_price_band_bps: 10_001,
+                    ..ReserveConfig::default()
+                },
+                result: Err(LendingError::InvalidConfig.into()),
+            }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    max_oracle_confidence_bps: 10_001,
+                    ..ReserveConfig::default()
+                },
+                result: Err(LendingError::InvalidConfig.into()),
+            }),
+            Just(ReserveConfigTestCase {
+                config: ReserveConfig {
+                    max_single_borrower_share_bps: 10_001,
+                    ..ReserveConfig::default()
+                },
+                result: Err(LendingError::InvalidConfig.into()),
             })
         ]
     }
@@ -2597,12 +3854,86 @@ mod test {
             };
 
             assert_eq!(
-                reserve.calculate_bonus(&obligation),
+                reserve.calculate_bonus(&obligation, 0),
                 test_case.result
             );
         }
     }
 
+    #[test]
+    fn calculate_bonus_liquidation_auction() {
+        let reserve = Reserve {
+            config: ReserveConfig {
+                liquidation_bonus: 5,
+                max_liquidation_bonus: 20,
+                protocol_liquidation_fee: 10,
+                liquidation_auction: true,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(100u64),
+            unhealthy_borrow_value: Decimal::from(100u64),
+            super_unhealthy_borrow_value: Decimal::from(150u64),
+            unhealthy_since_slot: 1000,
+            ..Obligation::default()
+        };
+
+        for (slot, total_bonus) in [
+            (1000, Decimal::from_percent(1)),
+            (
+                1000 + LIQUIDATION_AUCTION_DURATION_SLOTS / 2,
+                Decimal::from_percent(11),
+            ),
+            (
+                1000 + LIQUIDATION_AUCTION_DURATION_SLOTS,
+                Decimal::from_percent(21),
+            ),
+            (
+                1000 + 2 * LIQUIDATION_AUCTION_DURATION_SLOTS,
+                Decimal::from_percent(21),
+            ),
+        ] {
+            assert_eq!(
+                reserve.calculate_bonus(&obligation, slot),
+                Ok(Bonus {
+                    total_bonus,
+                    protocol_liquidation_fee: Decimal::from_percent(1),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn calculate_bonus_protocol_liquidation_fee_in_liquidity() {
+        let reserve = Reserve {
+            config: ReserveConfig {
+                liquidation_bonus: 5,
+                max_liquidation_bonus: 5,
+                protocol_liquidation_fee: 10,
+                protocol_liquidation_fee_in_liquidity: true,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let obligation = Obligation {
+            borrowed_value: Decimal::from(100u64),
+            unhealthy_borrow_value: Decimal::from(100u64),
+            super_unhealthy_borrow_value: Decimal::from(100u64),
+            ..Obligation::default()
+        };
+
+        // the liquidator keeps the whole bonus, the fee is paid on top of the repay amount
+        assert_eq!(
+            reserve.calculate_bonus(&obligation, 0),
+            Ok(Bonus {
+                total_bonus: Decimal::from_percent(5),
+                protocol_liquidation_fee: Decimal::zero(),
+            })
+        );
+    }
+
     #[derive(Debug, Clone)]
     struct LiquidationTestCase {
         deposit_amount: u64,
@@ -2790,12 +4121,14 @@ mod test {
                     deposited_amount: test_case.deposit_amount,
                     market_value: test_case.deposit_market_value,
                     attributed_borrow_value: test_case.borrow_market_value,
+                    rewards: PositionRewards::default(),
                 }],
                 borrows: vec![ObligationLiquidity {
                     borrow_reserve: Pubkey::new_unique(),
                     cumulative_borrow_rate_wads: Decimal::one(),
                     borrowed_amount_wads: Decimal::from(test_case.borrow_amount),
                     market_value: test_case.borrow_market_value,
+                    ..ObligationLiquidity::default()
                 }],
                 borrowed_value: test_case.borrow_market_value,
                 unhealthy_borrow_value: test_case.borrow_market_value,
@@ -2810,11 +4143,66 @@ mod test {
                     &obligation.borrows[0],
                     &obligation.deposits[0],
                     &test_case.bonus,
+                    Decimal::one(),
                 ).unwrap(),
                 test_case.liquidation_result);
         }
     }
 
+    #[test]
+    fn calculate_liquidation_of_dust_borrow() {
+        let reserve = Reserve::default();
+        let obligation = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposit_reserve: Pubkey::new_unique(),
+                deposited_amount: 100,
+                market_value: Decimal::from(100u64),
+                attributed_borrow_value: Decimal::from(5u64),
+                rewards: PositionRewards::default(),
+            }],
+            borrows: vec![ObligationLiquidity {
+                borrow_reserve: Pubkey::new_unique(),
+                cumulative_borrow_rate_wads: Decimal::one(),
+                borrowed_amount_wads: Decimal::from(10u64),
+                market_value: Decimal::from(5u64),
+                ..ObligationLiquidity::default()
+            }],
+            borrowed_value: Decimal::from(5u64),
+            unhealthy_borrow_value: Decimal::from(5u64),
+            super_unhealthy_borrow_value: Decimal::from(5u64),
+            ..Obligation::default()
+        };
+        let bonus = Bonus {
+            total_bonus: Decimal::zero(),
+            protocol_liquidation_fee: Decimal::zero(),
+        };
+        let liquidate = |dust_borrow_value| {
+            reserve
+                .calculate_liquidation(
+                    u64::MAX,
+                    &obligation,
+                    &obligation.borrows[0],
+                    &obligation.deposits[0],
+                    &bonus,
+                    dust_borrow_value,
+                )
+                .unwrap()
+        };
+
+        // a $5 borrow is only partially liquidated with the default $1 dust threshold
+        assert!(liquidate(Decimal::one()).settle_amount < Decimal::from(10u64));
+
+        // and closed in full with a $5 one
+        assert_eq!(
+            liquidate(Decimal::from(5u64)),
+            CalculateLiquidationResult {
+                settle_amount: Decimal::from(10u64),
+                repay_amount: 10,
+                withdraw_amount: 5,
+            }
+        );
+    }
+
     #[derive(Debug, Clone)]
     struct CalculateBorrowTestCase {
         // args
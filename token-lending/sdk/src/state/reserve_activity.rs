@@ -0,0 +1,261 @@
+use super::*;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the reserve address to derive its activity account
+pub const RESERVE_ACTIVITY_SEED: &[u8] = b"ReserveActivity";
+
+/// Kind of user action counted by a reserve activity account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReserveAction {
+    /// Liquidity deposited into the reserve
+    Deposit,
+    /// Collateral redeemed for the reserve's liquidity
+    Withdraw,
+    /// Liquidity borrowed from the reserve
+    Borrow,
+    /// Borrowed liquidity repaid to the reserve
+    Repay,
+    /// Borrowed liquidity repaid by a liquidator
+    Liquidation,
+}
+
+/// Counts and cumulative volumes of the actions taken against a reserve, for usage dashboards and
+/// fee share audits. Volumes are in the reserve's liquidity token and saturate instead of
+/// overflowing. Kept in its own account because the reserve account has no room left, and only
+/// updated by instructions it's passed to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReserveActivity {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the reserve activity account address
+    pub bump_seed: u8,
+    /// Reserve the activity is for
+    pub reserve: Pubkey,
+    /// Number of deposits
+    pub deposit_count: u64,
+    /// Liquidity deposited
+    pub deposit_volume: u64,
+    /// Number of withdrawals
+    pub withdraw_count: u64,
+    /// Liquidity withdrawn
+    pub withdraw_volume: u64,
+    /// Number of borrows
+    pub borrow_count: u64,
+    /// Liquidity borrowed, including fees
+    pub borrow_volume: u64,
+    /// Number of repays
+    pub repay_count: u64,
+    /// Liquidity repaid
+    pub repay_volume: u64,
+    /// Number of liquidations repaying this reserve's liquidity
+    pub liquidation_count: u64,
+    /// Liquidity repaid by liquidators
+    pub liquidation_volume: u64,
+}
+
+/// Find the activity account address for a reserve
+pub fn find_reserve_activity_address(program_id: &Pubkey, reserve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[reserve.as_ref(), RESERVE_ACTIVITY_SEED], program_id)
+}
+
+impl ReserveActivity {
+    /// Count an action moving `amount` of the reserve's liquidity
+    pub fn record(&mut self, action: ReserveAction, amount: u64) {
+        let (count, volume) = match action {
+            ReserveAction::Deposit => (&mut self.deposit_count, &mut self.deposit_volume),
+            ReserveAction::Withdraw => (&mut self.withdraw_count, &mut self.withdraw_volume),
+            ReserveAction::Borrow => (&mut self.borrow_count, &mut self.borrow_volume),
+            ReserveAction::Repay => (&mut self.repay_count, &mut self.repay_volume),
+            ReserveAction::Liquidation => {
+                (&mut self.liquidation_count, &mut self.liquidation_volume)
+            }
+        };
+        *count = count.saturating_add(1);
+        *volume = volume.saturating_add(amount);
+    }
+}
+
+impl Sealed for ReserveActivity {}
+impl IsInitialized for ReserveActivity {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const RESERVE_ACTIVITY_LEN: usize = 242; // 1 + 1 + 1 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 127
+impl Pack for ReserveActivity {
+    const LEN: usize = RESERVE_ACTIVITY_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RESERVE_ACTIVITY_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            deposit_count,
+            deposit_volume,
+            withdraw_count,
+            withdraw_volume,
+            borrow_count,
+            borrow_volume,
+            repay_count,
+            repay_volume,
+            liquidation_count,
+            liquidation_volume,
+            _padding,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            127
+        ];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::ReserveActivity as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        reserve.copy_from_slice(self.reserve.as_ref());
+        *deposit_count = self.deposit_count.to_le_bytes();
+        *deposit_volume = self.deposit_volume.to_le_bytes();
+        *withdraw_count = self.withdraw_count.to_le_bytes();
+        *withdraw_volume = self.withdraw_volume.to_le_bytes();
+        *borrow_count = self.borrow_count.to_le_bytes();
+        *borrow_volume = self.borrow_volume.to_le_bytes();
+        *repay_count = self.repay_count.to_le_bytes();
+        *repay_volume = self.repay_volume.to_le_bytes();
+        *liquidation_count = self.liquidation_count.to_le_bytes();
+        *liquidation_volume = self.liquidation_volume.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, RESERVE_ACTIVITY_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            deposit_count,
+            deposit_volume,
+            withdraw_count,
+            withdraw_volume,
+            borrow_count,
+            borrow_volume,
+            repay_count,
+            repay_volume,
+            liquidation_count,
+            liquidation_volume,
+            _padding,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            8,
+            127
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Reserve activity version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::ReserveActivity)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            reserve: Pubkey::new_from_array(*reserve),
+            deposit_count: u64::from_le_bytes(*deposit_count),
+            deposit_volume: u64::from_le_bytes(*deposit_volume),
+            withdraw_count: u64::from_le_bytes(*withdraw_count),
+            withdraw_volume: u64::from_le_bytes(*withdraw_volume),
+            borrow_count: u64::from_le_bytes(*borrow_count),
+            borrow_volume: u64::from_le_bytes(*borrow_volume),
+            repay_count: u64::from_le_bytes(*repay_count),
+            repay_volume: u64::from_le_bytes(*repay_volume),
+            liquidation_count: u64::from_le_bytes(*liquidation_count),
+            liquidation_volume: u64::from_le_bytes(*liquidation_volume),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_reserve_activity() {
+        let mut rng = rand::thread_rng();
+        let reserve_activity = ReserveActivity {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            reserve: Pubkey::new_unique(),
+            deposit_count: rng.gen(),
+            deposit_volume: rng.gen(),
+            withdraw_count: rng.gen(),
+            withdraw_volume: rng.gen(),
+            borrow_count: rng.gen(),
+            borrow_volume: rng.gen(),
+            repay_count: rng.gen(),
+            repay_volume: rng.gen(),
+            liquidation_count: rng.gen(),
+            liquidation_volume: rng.gen(),
+        };
+
+        let mut packed = [0u8; RESERVE_ACTIVITY_LEN];
+        ReserveActivity::pack(reserve_activity.clone(), &mut packed).unwrap();
+        let unpacked = ReserveActivity::unpack(&packed).unwrap();
+        assert_eq!(reserve_activity, unpacked);
+    }
+
+    #[test]
+    fn record_reserve_activity() {
+        let mut reserve_activity = ReserveActivity::default();
+
+        reserve_activity.record(ReserveAction::Deposit, 100);
+        reserve_activity.record(ReserveAction::Deposit, 50);
+        reserve_activity.record(ReserveAction::Borrow, 30);
+        assert_eq!(reserve_activity.deposit_count, 2);
+        assert_eq!(reserve_activity.deposit_volume, 150);
+        assert_eq!(reserve_activity.borrow_count, 1);
+        assert_eq!(reserve_activity.borrow_volume, 30);
+        assert_eq!(reserve_activity.repay_count, 0);
+
+        // volumes saturate
+        reserve_activity.record(ReserveAction::Liquidation, u64::MAX);
+        reserve_activity.record(ReserveAction::Liquidation, 1);
+        assert_eq!(reserve_activity.liquidation_count, 2);
+        assert_eq!(reserve_activity.liquidation_volume, u64::MAX);
+    }
+}
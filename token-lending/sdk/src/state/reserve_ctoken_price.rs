@@ -0,0 +1,232 @@
+use super::*;
+use crate::{
+    error::LendingError,
+    math::{TryDiv, TryMul},
+};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the reserve address to derive its ctoken price account
+pub const RESERVE_CTOKEN_PRICE_SEED: &[u8] = b"ReserveCTokenPrice";
+
+/// Price of a reserve's collateral token, for other programs to read as an oracle. Written by
+/// RefreshCTokenPrice from a reserve refreshed in the same slot, so consumers should check
+/// `last_update_slot` like they would an oracle's publish slot.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReserveCTokenPrice {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the reserve ctoken price account address
+    pub bump_seed: u8,
+    /// Reserve the price is for
+    pub reserve: Pubkey,
+    /// Collateral mint of the reserve
+    pub collateral_mint: Pubkey,
+    /// Slot the price was written in
+    pub last_update_slot: Slot,
+    /// Market price of one whole liquidity token in the quote currency
+    pub liquidity_market_price: Decimal,
+    /// Whole liquidity tokens one whole collateral token redeems for
+    pub collateral_exchange_rate: Decimal,
+    /// Market price of one whole collateral token in the quote currency
+    pub ctoken_market_price: Decimal,
+}
+
+/// Find the ctoken price account address for a reserve
+pub fn find_reserve_ctoken_price_address(program_id: &Pubkey, reserve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[reserve.as_ref(), RESERVE_CTOKEN_PRICE_SEED], program_id)
+}
+
+impl ReserveCTokenPrice {
+    /// Set the prices from a refreshed reserve
+    pub fn update(&mut self, reserve: &Reserve, current_slot: Slot) -> Result<(), ProgramError> {
+        // liquidity per collateral in base units, scaled to whole tokens by the decimals offset
+        let base_exchange_rate = reserve
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(Decimal::one())?;
+        let scale = Decimal::from(
+            10u64
+                .checked_pow(reserve.collateral.decimals_offset.unsigned_abs() as u32)
+                .ok_or(LendingError::MathOverflow)?,
+        );
+        let collateral_exchange_rate = if reserve.collateral.decimals_offset >= 0 {
+            base_exchange_rate.try_mul(scale)?
+        } else {
+            base_exchange_rate.try_div(scale)?
+        };
+
+        self.collateral_mint = reserve.collateral.mint_pubkey;
+        self.last_update_slot = current_slot;
+        self.liquidity_market_price = reserve.liquidity.market_price;
+        self.collateral_exchange_rate = collateral_exchange_rate;
+        self.ctoken_market_price = reserve
+            .liquidity
+            .market_price
+            .try_mul(collateral_exchange_rate)?;
+
+        Ok(())
+    }
+}
+
+impl Sealed for ReserveCTokenPrice {}
+impl IsInitialized for ReserveCTokenPrice {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const RESERVE_CTOKEN_PRICE_LEN: usize = 250; // 1 + 1 + 1 + 32 + 32 + 8 + 16 + 16 + 16 + 127
+impl Pack for ReserveCTokenPrice {
+    const LEN: usize = RESERVE_CTOKEN_PRICE_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RESERVE_CTOKEN_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            collateral_mint,
+            last_update_slot,
+            liquidity_market_price,
+            collateral_exchange_rate,
+            ctoken_market_price,
+            _padding,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            8,
+            16,
+            16,
+            16,
+            127
+        ];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::ReserveCTokenPrice as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        reserve.copy_from_slice(self.reserve.as_ref());
+        collateral_mint.copy_from_slice(self.collateral_mint.as_ref());
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+        pack_decimal(self.liquidity_market_price, liquidity_market_price);
+        pack_decimal(self.collateral_exchange_rate, collateral_exchange_rate);
+        pack_decimal(self.ctoken_market_price, ctoken_market_price);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, RESERVE_CTOKEN_PRICE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            collateral_mint,
+            last_update_slot,
+            liquidity_market_price,
+            collateral_exchange_rate,
+            ctoken_market_price,
+            _padding,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            8,
+            16,
+            16,
+            16,
+            127
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Reserve ctoken price version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::ReserveCTokenPrice)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            reserve: Pubkey::new_from_array(*reserve),
+            collateral_mint: Pubkey::new_from_array(*collateral_mint),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
+            liquidity_market_price: unpack_decimal(liquidity_market_price),
+            collateral_exchange_rate: unpack_decimal(collateral_exchange_rate),
+            ctoken_market_price: unpack_decimal(ctoken_market_price),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_reserve_ctoken_price() {
+        let mut rng = rand::thread_rng();
+        let reserve_ctoken_price = ReserveCTokenPrice {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            reserve: Pubkey::new_unique(),
+            collateral_mint: Pubkey::new_unique(),
+            last_update_slot: rng.gen(),
+            liquidity_market_price: Decimal::from_scaled_val(rng.gen::<u64>() as u128),
+            collateral_exchange_rate: Decimal::from_scaled_val(rng.gen::<u64>() as u128),
+            ctoken_market_price: Decimal::from_scaled_val(rng.gen::<u64>() as u128),
+        };
+
+        let mut packed = [0u8; RESERVE_CTOKEN_PRICE_LEN];
+        ReserveCTokenPrice::pack(reserve_ctoken_price.clone(), &mut packed).unwrap();
+        let unpacked = ReserveCTokenPrice::unpack(&packed).unwrap();
+        assert_eq!(reserve_ctoken_price, unpacked);
+    }
+
+    #[test]
+    fn update_reserve_ctoken_price() {
+        let mut reserve = Reserve::default();
+        reserve.liquidity.market_price = Decimal::from(20u64);
+        reserve.liquidity.available_amount = 5_000;
+        reserve.collateral.mint_total_supply = 4_000;
+
+        let mut reserve_ctoken_price = ReserveCTokenPrice::default();
+        reserve_ctoken_price.update(&reserve, 10).unwrap();
+        assert_eq!(reserve_ctoken_price.last_update_slot, 10);
+        assert_eq!(
+            reserve_ctoken_price.collateral_exchange_rate,
+            Decimal::from_percent(125)
+        );
+        assert_eq!(
+            reserve_ctoken_price.ctoken_market_price,
+            Decimal::from(25u64)
+        );
+
+        // collateral with 3 more decimals than the liquidity
+        reserve.collateral.decimals_offset = 3;
+        reserve.collateral.mint_total_supply = 4_000_000;
+        reserve_ctoken_price.update(&reserve, 11).unwrap();
+        assert_eq!(
+            reserve_ctoken_price.collateral_exchange_rate,
+            Decimal::from_percent(125)
+        );
+        assert_eq!(
+            reserve_ctoken_price.ctoken_market_price,
+            Decimal::from(25u64)
+        );
+    }
+}
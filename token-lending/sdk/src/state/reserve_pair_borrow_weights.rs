@@ -0,0 +1,332 @@
+use super::*;
+use crate::{
+    error::LendingError,
+    math::{Decimal, TryAdd, TryDiv, TryMul},
+};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the reserve address to derive its pair borrow weights account
+pub const RESERVE_PAIR_BORROW_WEIGHTS_SEED: &[u8] = b"ReservePairBorrowWeights";
+
+/// Max number of collateral reserves a reserve can have pair borrow weights for
+pub const MAX_PAIR_BORROW_WEIGHTS: usize = 8;
+
+/// Borrow weight multiplier of a borrow reserve for obligations backed by a collateral reserve
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PairBorrowWeight {
+    /// Collateral reserve the multiplier applies to
+    pub collateral_reserve: Pubkey,
+    /// Multiplier of the borrow reserve's borrow weight in bps, eg 5_000 halves it for
+    /// collateral that is correlated with the borrowed asset
+    pub borrow_weight_bps: u16,
+}
+
+/// Borrow weight multipliers of a reserve per collateral reserve, one row of the lending market's
+/// collateral/debt matrix. Obligations borrowing from a reserve with `has_pair_borrow_weights`
+/// set must be refreshed with this account, and the reserve's borrow weight is then scaled by
+/// the multipliers of their collateral, weighted by its market value. Collateral without a
+/// multiplier counts at 1x.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReservePairBorrowWeights {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the reserve pair borrow weights account address
+    pub bump_seed: u8,
+    /// Borrow reserve the multipliers are for
+    pub reserve: Pubkey,
+    /// Multipliers per collateral reserve
+    pub pair_borrow_weights: Vec<PairBorrowWeight>,
+}
+
+/// Find the pair borrow weights account address for a reserve
+pub fn find_reserve_pair_borrow_weights_address(
+    program_id: &Pubkey,
+    reserve: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[reserve.as_ref(), RESERVE_PAIR_BORROW_WEIGHTS_SEED],
+        program_id,
+    )
+}
+
+impl ReservePairBorrowWeights {
+    /// Set the multiplier for a collateral reserve, replacing any previous one. 0 removes it.
+    pub fn set(&mut self, collateral_reserve: Pubkey, borrow_weight_bps: u16) -> ProgramResult {
+        let existing = self
+            .pair_borrow_weights
+            .iter()
+            .position(|pair| pair.collateral_reserve == collateral_reserve);
+        match (existing, borrow_weight_bps) {
+            (Some(index), 0) => {
+                self.pair_borrow_weights.remove(index);
+            }
+            (Some(index), _) => {
+                self.pair_borrow_weights[index].borrow_weight_bps = borrow_weight_bps
+            }
+            (None, 0) => {}
+            (None, _) => {
+                if self.pair_borrow_weights.len() >= MAX_PAIR_BORROW_WEIGHTS {
+                    msg!(
+                        "Reserve already has pair borrow weights for {} collateral reserves",
+                        MAX_PAIR_BORROW_WEIGHTS
+                    );
+                    return Err(LendingError::InvalidConfig.into());
+                }
+                self.pair_borrow_weights.push(PairBorrowWeight {
+                    collateral_reserve,
+                    borrow_weight_bps,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Multiplier of the reserve's borrow weight for an obligation, given the market value of
+    /// each of its collateral reserves
+    pub fn borrow_weight_multiplier(
+        &self,
+        collateral_values: &[(Pubkey, Decimal)],
+    ) -> Result<Decimal, ProgramError> {
+        let mut deposited_value = Decimal::zero();
+        let mut weighted_value = Decimal::zero();
+        for (collateral_reserve, market_value) in collateral_values {
+            let multiplier = match self
+                .pair_borrow_weights
+                .iter()
+                .find(|pair| pair.collateral_reserve == *collateral_reserve)
+            {
+                Some(pair) => Decimal::from_bps(pair.borrow_weight_bps as u64),
+                None => Decimal::one(),
+            };
+            deposited_value = deposited_value.try_add(*market_value)?;
+            weighted_value = weighted_value.try_add(market_value.try_mul(multiplier)?)?;
+        }
+
+        if deposited_value == Decimal::zero() {
+            return Ok(Decimal::one());
+        }
+        weighted_value.try_div(deposited_value)
+    }
+}
+
+impl Sealed for ReservePairBorrowWeights {}
+impl IsInitialized for ReservePairBorrowWeights {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const PAIR_BORROW_WEIGHT_LEN: usize = 34; // 32 + 2
+const RESERVE_PAIR_BORROW_WEIGHTS_LEN: usize = 371; // 1 + 1 + 1 + 32 + 1 + (34 * 8) + 63
+impl Pack for ReservePairBorrowWeights {
+    const LEN: usize = RESERVE_PAIR_BORROW_WEIGHTS_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RESERVE_PAIR_BORROW_WEIGHTS_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            pair_borrow_weights_len,
+            data_flat,
+            _padding,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            1,
+            PAIR_BORROW_WEIGHT_LEN * MAX_PAIR_BORROW_WEIGHTS,
+            63
+        ];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::ReservePairBorrowWeights as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        reserve.copy_from_slice(self.reserve.as_ref());
+        *pair_borrow_weights_len = (self.pair_borrow_weights.len() as u8).to_le_bytes();
+
+        data_flat.fill(0);
+        for (pair, data) in self
+            .pair_borrow_weights
+            .iter()
+            .zip(data_flat.chunks_exact_mut(PAIR_BORROW_WEIGHT_LEN))
+        {
+            let data = array_mut_ref![data, 0, PAIR_BORROW_WEIGHT_LEN];
+            #[allow(clippy::ptr_offset_with_cast)]
+            let (collateral_reserve, borrow_weight_bps) = mut_array_refs![data, PUBKEY_BYTES, 2];
+            collateral_reserve.copy_from_slice(pair.collateral_reserve.as_ref());
+            *borrow_weight_bps = pair.borrow_weight_bps.to_le_bytes();
+        }
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, RESERVE_PAIR_BORROW_WEIGHTS_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            pair_borrow_weights_len,
+            data_flat,
+            _padding,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            1,
+            PAIR_BORROW_WEIGHT_LEN * MAX_PAIR_BORROW_WEIGHTS,
+            63
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Reserve pair borrow weights version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::ReservePairBorrowWeights)?;
+
+        let pair_borrow_weights_len = u8::from_le_bytes(*pair_borrow_weights_len) as usize;
+        if pair_borrow_weights_len > MAX_PAIR_BORROW_WEIGHTS {
+            msg!("Reserve pair borrow weights length is invalid");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let pair_borrow_weights = data_flat
+            .chunks_exact(PAIR_BORROW_WEIGHT_LEN)
+            .take(pair_borrow_weights_len)
+            .map(|data| {
+                let data = array_ref![data, 0, PAIR_BORROW_WEIGHT_LEN];
+                #[allow(clippy::ptr_offset_with_cast)]
+                let (collateral_reserve, borrow_weight_bps) = array_refs![data, PUBKEY_BYTES, 2];
+                PairBorrowWeight {
+                    collateral_reserve: Pubkey::new_from_array(*collateral_reserve),
+                    borrow_weight_bps: u16::from_le_bytes(*borrow_weight_bps),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            reserve: Pubkey::new_from_array(*reserve),
+            pair_borrow_weights,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_reserve_pair_borrow_weights() {
+        let mut rng = rand::thread_rng();
+        let reserve_pair_borrow_weights = ReservePairBorrowWeights {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            reserve: Pubkey::new_unique(),
+            pair_borrow_weights: (0..rng.gen_range(0..=MAX_PAIR_BORROW_WEIGHTS))
+                .map(|_| PairBorrowWeight {
+                    collateral_reserve: Pubkey::new_unique(),
+                    borrow_weight_bps: rng.gen(),
+                })
+                .collect(),
+        };
+
+        let mut packed = [0u8; RESERVE_PAIR_BORROW_WEIGHTS_LEN];
+        ReservePairBorrowWeights::pack(reserve_pair_borrow_weights.clone(), &mut packed).unwrap();
+        let unpacked = ReservePairBorrowWeights::unpack(&packed).unwrap();
+        assert_eq!(reserve_pair_borrow_weights, unpacked);
+    }
+
+    #[test]
+    fn set_pair_borrow_weights() {
+        let mut reserve_pair_borrow_weights = ReservePairBorrowWeights::default();
+        let collateral_reserves: Vec<Pubkey> = (0..=MAX_PAIR_BORROW_WEIGHTS)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+
+        for collateral_reserve in &collateral_reserves[..MAX_PAIR_BORROW_WEIGHTS] {
+            reserve_pair_borrow_weights
+                .set(*collateral_reserve, 5_000)
+                .unwrap();
+        }
+        assert_eq!(
+            reserve_pair_borrow_weights.set(collateral_reserves[MAX_PAIR_BORROW_WEIGHTS], 5_000),
+            Err(LendingError::InvalidConfig.into())
+        );
+
+        // replacing and removing don't need a free slot
+        reserve_pair_borrow_weights
+            .set(collateral_reserves[0], 20_000)
+            .unwrap();
+        assert_eq!(
+            reserve_pair_borrow_weights.pair_borrow_weights[0].borrow_weight_bps,
+            20_000
+        );
+        reserve_pair_borrow_weights
+            .set(collateral_reserves[0], 0)
+            .unwrap();
+        assert_eq!(
+            reserve_pair_borrow_weights.pair_borrow_weights.len(),
+            MAX_PAIR_BORROW_WEIGHTS - 1
+        );
+        reserve_pair_borrow_weights
+            .set(collateral_reserves[MAX_PAIR_BORROW_WEIGHTS], 5_000)
+            .unwrap();
+    }
+
+    #[test]
+    fn pair_borrow_weight_multiplier() {
+        let lst = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let reserve_pair_borrow_weights = ReservePairBorrowWeights {
+            pair_borrow_weights: vec![PairBorrowWeight {
+                collateral_reserve: lst,
+                borrow_weight_bps: 5_000,
+            }],
+            ..ReservePairBorrowWeights::default()
+        };
+
+        // no collateral
+        assert_eq!(
+            reserve_pair_borrow_weights
+                .borrow_weight_multiplier(&[])
+                .unwrap(),
+            Decimal::one()
+        );
+
+        // only correlated collateral
+        assert_eq!(
+            reserve_pair_borrow_weights
+                .borrow_weight_multiplier(&[(lst, Decimal::from(100u64))])
+                .unwrap(),
+            Decimal::from_percent(50)
+        );
+
+        // 75% correlated collateral by value
+        assert_eq!(
+            reserve_pair_borrow_weights
+                .borrow_weight_multiplier(&[
+                    (lst, Decimal::from(300u64)),
+                    (usdc, Decimal::from(100u64))
+                ])
+                .unwrap(),
+            Decimal::from_bps(6_250)
+        );
+    }
+}
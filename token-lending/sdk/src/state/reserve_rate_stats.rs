@@ -0,0 +1,225 @@
+use super::*;
+use crate::math::{Rate, TryAdd, TryDiv, TryMul, TrySub};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+use std::cmp::min;
+
+/// Seed used with the reserve address to derive its rate stats account
+pub const RESERVE_RATE_STATS_SEED: &[u8] = b"ReserveRateStats";
+
+/// Slots after which a new sample fully replaces the averages, about a day
+pub const RESERVE_RATE_STATS_WINDOW_SLOTS: u64 = SLOTS_PER_YEAR / 365;
+
+/// Time weighted moving averages of a reserve's utilization and borrow rate. Kept in its own
+/// account because the reserve account has no room left, and sampled whenever it's passed to
+/// RefreshReserve.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReserveRateStats {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the reserve rate stats account address
+    pub bump_seed: u8,
+    /// Reserve the stats are for
+    pub reserve: Pubkey,
+    /// Slot of the last sample
+    pub last_update_slot: Slot,
+    /// Moving average of the utilization rate
+    pub average_utilization_rate: Decimal,
+    /// Moving average of the borrow rate
+    pub average_borrow_rate: Decimal,
+}
+
+/// Find the rate stats account address for a reserve
+pub fn find_reserve_rate_stats_address(program_id: &Pubkey, reserve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[reserve.as_ref(), RESERVE_RATE_STATS_SEED], program_id)
+}
+
+impl ReserveRateStats {
+    /// Blend a sample of the rates in effect since the last sample into the averages. A sample
+    /// taken RESERVE_RATE_STATS_WINDOW_SLOTS or more after the last one replaces them.
+    pub fn update(
+        &mut self,
+        utilization_rate: Rate,
+        borrow_rate: Rate,
+        current_slot: Slot,
+    ) -> ProgramResult {
+        let slots_elapsed = current_slot.saturating_sub(self.last_update_slot);
+        let weight = Decimal::from(min(slots_elapsed, RESERVE_RATE_STATS_WINDOW_SLOTS))
+            .try_div(RESERVE_RATE_STATS_WINDOW_SLOTS)?;
+
+        self.average_utilization_rate = moving_average(
+            self.average_utilization_rate,
+            utilization_rate.into(),
+            weight,
+        )?;
+        self.average_borrow_rate =
+            moving_average(self.average_borrow_rate, borrow_rate.into(), weight)?;
+        self.last_update_slot = current_slot;
+
+        Ok(())
+    }
+}
+
+fn moving_average(
+    average: Decimal,
+    sample: Decimal,
+    weight: Decimal,
+) -> Result<Decimal, ProgramError> {
+    average
+        .try_mul(Decimal::one().try_sub(weight)?)?
+        .try_add(sample.try_mul(weight)?)
+}
+
+impl Sealed for ReserveRateStats {}
+impl IsInitialized for ReserveRateStats {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const RESERVE_RATE_STATS_LEN: usize = 202; // 1 + 1 + 1 + 32 + 8 + 16 + 16 + 127
+impl Pack for ReserveRateStats {
+    const LEN: usize = RESERVE_RATE_STATS_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RESERVE_RATE_STATS_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            last_update_slot,
+            average_utilization_rate,
+            average_borrow_rate,
+            _padding,
+        ) = mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, 8, 16, 16, 127];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::ReserveRateStats as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        reserve.copy_from_slice(self.reserve.as_ref());
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+        pack_decimal(self.average_utilization_rate, average_utilization_rate);
+        pack_decimal(self.average_borrow_rate, average_borrow_rate);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, RESERVE_RATE_STATS_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            reserve,
+            last_update_slot,
+            average_utilization_rate,
+            average_borrow_rate,
+            _padding,
+        ) = array_refs![input, 1, 1, 1, PUBKEY_BYTES, 8, 16, 16, 127];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Reserve rate stats version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::ReserveRateStats)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            reserve: Pubkey::new_from_array(*reserve),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
+            average_utilization_rate: unpack_decimal(average_utilization_rate),
+            average_borrow_rate: unpack_decimal(average_borrow_rate),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_reserve_rate_stats() {
+        let mut rng = rand::thread_rng();
+        let reserve_rate_stats = ReserveRateStats {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            reserve: Pubkey::new_unique(),
+            last_update_slot: rng.gen(),
+            average_utilization_rate: Decimal::from_scaled_val(rng.gen::<u64>() as u128),
+            average_borrow_rate: Decimal::from_scaled_val(rng.gen::<u64>() as u128),
+        };
+
+        let mut packed = [0u8; RESERVE_RATE_STATS_LEN];
+        ReserveRateStats::pack(reserve_rate_stats.clone(), &mut packed).unwrap();
+        let unpacked = ReserveRateStats::unpack(&packed).unwrap();
+        assert_eq!(reserve_rate_stats, unpacked);
+    }
+
+    #[test]
+    fn update_reserve_rate_stats() {
+        let mut reserve_rate_stats = ReserveRateStats {
+            last_update_slot: 100,
+            average_utilization_rate: Decimal::from_percent(50),
+            average_borrow_rate: Decimal::from_percent(10),
+            ..ReserveRateStats::default()
+        };
+
+        // a quarter of the window moves the averages a quarter of the way to the sample
+        reserve_rate_stats
+            .update(
+                Rate::from_percent(90),
+                Rate::from_percent(30),
+                100 + RESERVE_RATE_STATS_WINDOW_SLOTS / 4,
+            )
+            .unwrap();
+        assert_eq!(
+            reserve_rate_stats.average_utilization_rate,
+            Decimal::from_percent(60)
+        );
+        assert_eq!(
+            reserve_rate_stats.average_borrow_rate,
+            Decimal::from_percent(15)
+        );
+
+        // no time has passed, so the sample has no weight
+        reserve_rate_stats
+            .update(
+                Rate::from_percent(0),
+                Rate::from_percent(0),
+                reserve_rate_stats.last_update_slot,
+            )
+            .unwrap();
+        assert_eq!(
+            reserve_rate_stats.average_utilization_rate,
+            Decimal::from_percent(60)
+        );
+
+        // a sample after more than a window replaces the averages
+        reserve_rate_stats
+            .update(
+                Rate::from_percent(20),
+                Rate::from_percent(5),
+                reserve_rate_stats.last_update_slot + 2 * RESERVE_RATE_STATS_WINDOW_SLOTS,
+            )
+            .unwrap();
+        assert_eq!(
+            reserve_rate_stats.average_utilization_rate,
+            Decimal::from_percent(20)
+        );
+        assert_eq!(
+            reserve_rate_stats.average_borrow_rate,
+            Decimal::from_percent(5)
+        );
+    }
+}
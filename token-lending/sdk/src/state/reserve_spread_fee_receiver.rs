@@ -0,0 +1,106 @@
+use super::*;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+/// Seed used with the reserve address to derive its spread fee receiver account
+pub const RESERVE_SPREAD_FEE_RECEIVER_SEED: &[u8] = b"ReserveSpreadFeeReceiver";
+
+/// Token account RedeemFees sends a reserve's interest spread to, so it can go somewhere other
+/// than the origination fees paid to the reserve's fee receiver. Kept in its own account because
+/// the reserve account has no room left. Redeeming fees of a reserve with
+/// `has_spread_fee_receiver` set must be passed this account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReserveSpreadFeeReceiver {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the reserve spread fee receiver account address
+    pub bump_seed: u8,
+    /// Reserve the spread fee receiver is for
+    pub reserve: Pubkey,
+    /// Token account of the reserve's liquidity mint receiving the redeemed spread
+    pub spread_fee_receiver: Pubkey,
+}
+
+/// Find the spread fee receiver account address for a reserve
+pub fn find_reserve_spread_fee_receiver_address(
+    program_id: &Pubkey,
+    reserve: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[reserve.as_ref(), RESERVE_SPREAD_FEE_RECEIVER_SEED],
+        program_id,
+    )
+}
+
+impl Sealed for ReserveSpreadFeeReceiver {}
+impl IsInitialized for ReserveSpreadFeeReceiver {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const RESERVE_SPREAD_FEE_RECEIVER_LEN: usize = 98; // 1 + 1 + 1 + 32 + 32 + 31
+impl Pack for ReserveSpreadFeeReceiver {
+    const LEN: usize = RESERVE_SPREAD_FEE_RECEIVER_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, RESERVE_SPREAD_FEE_RECEIVER_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, reserve, spread_fee_receiver, _padding) =
+            mut_array_refs![output, 1, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 31];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::ReserveSpreadFeeReceiver as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        reserve.copy_from_slice(self.reserve.as_ref());
+        spread_fee_receiver.copy_from_slice(self.spread_fee_receiver.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, RESERVE_SPREAD_FEE_RECEIVER_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (version, account_tag, bump_seed, reserve, spread_fee_receiver, _padding) =
+            array_refs![input, 1, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 31];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Reserve spread fee receiver version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::ReserveSpreadFeeReceiver)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            reserve: Pubkey::new_from_array(*reserve),
+            spread_fee_receiver: Pubkey::new_from_array(*spread_fee_receiver),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_reserve_spread_fee_receiver() {
+        let mut rng = rand::thread_rng();
+        let reserve_spread_fee_receiver = ReserveSpreadFeeReceiver {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            reserve: Pubkey::new_unique(),
+            spread_fee_receiver: Pubkey::new_unique(),
+        };
+
+        let mut packed = [0u8; RESERVE_SPREAD_FEE_RECEIVER_LEN];
+        ReserveSpreadFeeReceiver::pack(reserve_spread_fee_receiver.clone(), &mut packed).unwrap();
+        let unpacked = ReserveSpreadFeeReceiver::unpack(&packed).unwrap();
+        assert_eq!(reserve_spread_fee_receiver, unpacked);
+    }
+}
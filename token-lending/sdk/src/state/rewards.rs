@@ -0,0 +1,321 @@
+use super::*;
+use crate::{
+    error::LendingError,
+    math::{Decimal, TryAdd, TryDiv, TryMul, TrySub},
+};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::{
+    clock::Slot,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+use std::cmp::{max, min};
+
+/// Seed used with the reserve address and reward side to derive a reward rule account
+pub const REWARD_RULE_SEED: &[u8] = b"RewardRule";
+
+/// Which positions in a reserve a reward rule pays out to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromPrimitive)]
+pub enum RewardSide {
+    /// Obligation deposits, weighted by deposited collateral tokens
+    #[default]
+    Deposit = 0,
+    /// Obligation borrows, weighted by borrowed liquidity
+    Borrow = 1,
+}
+
+/// Streams a reward token to the depositors or borrowers of a reserve, in proportion to each
+/// obligation's share of the reserve's total deposits or borrows. The emission schedule and
+/// reward index are kept in the reserve's ReserveRewards for the rule's side.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RewardRule {
+    /// Version of the struct
+    pub version: u8,
+    /// Bump seed of the reward rule account address
+    pub bump_seed: u8,
+    /// Lending market address
+    pub lending_market: Pubkey,
+    /// Reserve the rule rewards positions in
+    pub reserve: Pubkey,
+    /// Whether deposits or borrows are rewarded
+    pub side: RewardSide,
+    /// Mint of the reward token
+    pub reward_mint: Pubkey,
+    /// Token account rewards are paid from, owned by the lending market authority
+    pub reward_supply: Pubkey,
+}
+
+/// Reward emissions to one side of a reserve. They live on the reserve rather than its reward
+/// rule so every refresh accrues them, which lets positions be settled whenever their balance
+/// changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReserveRewards {
+    /// Reward tokens emitted per slot, shared between all positions
+    pub emission_per_slot: u64,
+    /// First slot rewards are emitted in
+    pub start_slot: Slot,
+    /// Slot from which rewards are no longer emitted
+    pub end_slot: Slot,
+    /// Slot the reward index was last brought up to date in
+    pub last_update_slot: Slot,
+    /// Cumulative reward tokens emitted per unit of position
+    pub reward_index: Decimal,
+}
+
+impl ReserveRewards {
+    /// Accrue the emissions since the last update into the reward index. Emissions for slots
+    /// in which the reserve had no positions of this side are not paid out to anyone.
+    pub fn accrue(&mut self, slot: Slot, total_shares: Decimal) -> ProgramResult {
+        // reserves without a schedule are left untouched
+        if self.emission_per_slot == 0 {
+            return Ok(());
+        }
+        let from = max(self.last_update_slot, self.start_slot);
+        let to = min(slot, self.end_slot);
+        if to > from && total_shares > Decimal::zero() {
+            let emitted = Decimal::from(self.emission_per_slot).try_mul(to - from)?;
+            self.reward_index = self.reward_index.try_add(emitted.try_div(total_shares)?)?;
+        }
+        self.last_update_slot = max(self.last_update_slot, slot);
+        Ok(())
+    }
+}
+
+/// A deposit's or borrow's progress through the rewards of its side of the reserve
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionRewards {
+    /// Reserve reward index the position has been paid up to
+    pub reward_index: Decimal,
+    /// Rewards earned and not yet claimed
+    pub unclaimed_rewards: Decimal,
+}
+
+impl PositionRewards {
+    /// Pay out the rewards the position earned since it was last settled. Has to be called with
+    /// the position's share before every change to it, so the old share is paid up to the change
+    /// and the new one only from there. A new position has a zero share, so it earns nothing
+    /// for rewards emitted before it was opened.
+    pub fn settle(&mut self, reward_index: Decimal, share: Decimal) -> ProgramResult {
+        if reward_index > self.reward_index {
+            let earned = share.try_mul(reward_index.try_sub(self.reward_index)?)?;
+            self.unclaimed_rewards = self.unclaimed_rewards.try_add(earned)?;
+            self.reward_index = reward_index;
+        }
+        Ok(())
+    }
+
+    /// Take the whole tokens out of the unclaimed rewards, leaving any fraction for later
+    pub fn claim(&mut self) -> Result<u64, ProgramError> {
+        let amount = self.unclaimed_rewards.try_floor_u64()?;
+        self.unclaimed_rewards = self.unclaimed_rewards.try_sub(Decimal::from(amount))?;
+        Ok(amount)
+    }
+
+    /// Whether there is at least one whole reward token left to claim
+    pub fn has_claimable_rewards(&self) -> bool {
+        self.unclaimed_rewards >= Decimal::one()
+    }
+}
+
+/// Find the reward rule account address for one side of a reserve
+pub fn find_reward_rule_address(
+    program_id: &Pubkey,
+    reserve: &Pubkey,
+    side: RewardSide,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[reserve.as_ref(), REWARD_RULE_SEED, &[side as u8]],
+        program_id,
+    )
+}
+
+impl Sealed for RewardRule {}
+impl IsInitialized for RewardRule {
+    fn is_initialized(&self) -> bool {
+        self.version != UNINITIALIZED_VERSION
+    }
+}
+
+const REWARD_RULE_LEN: usize = 256; // 1 + 1 + 1 + 32 + 32 + 1 + 32 + 32 + 124
+impl Pack for RewardRule {
+    const LEN: usize = REWARD_RULE_LEN;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, REWARD_RULE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            lending_market,
+            reserve,
+            side,
+            reward_mint,
+            reward_supply,
+            _padding,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            124
+        ];
+
+        *version = self.version.to_le_bytes();
+        *account_tag = (AccountTag::RewardRule as u8).to_le_bytes();
+        *bump_seed = self.bump_seed.to_le_bytes();
+        lending_market.copy_from_slice(self.lending_market.as_ref());
+        reserve.copy_from_slice(self.reserve.as_ref());
+        *side = (self.side as u8).to_le_bytes();
+        reward_mint.copy_from_slice(self.reward_mint.as_ref());
+        reward_supply.copy_from_slice(self.reward_supply.as_ref());
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, REWARD_RULE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            version,
+            account_tag,
+            bump_seed,
+            lending_market,
+            reserve,
+            side,
+            reward_mint,
+            reward_supply,
+            _padding,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            124
+        ];
+
+        let version = u8::from_le_bytes(*version);
+        if version > PROGRAM_VERSION {
+            msg!("Reward rule version does not match lending program version");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_account_tag(version, account_tag, AccountTag::RewardRule)?;
+
+        Ok(Self {
+            version,
+            bump_seed: u8::from_le_bytes(*bump_seed),
+            lending_market: Pubkey::new_from_array(*lending_market),
+            reserve: Pubkey::new_from_array(*reserve),
+            side: RewardSide::from_u8(side[0]).ok_or(LendingError::InvalidAccountInput)?,
+            reward_mint: Pubkey::new_from_array(*reward_mint),
+            reward_supply: Pubkey::new_from_array(*reward_supply),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn pack_and_unpack_reward_rule() {
+        let mut rng = rand::thread_rng();
+        let reward_rule = RewardRule {
+            version: PROGRAM_VERSION,
+            bump_seed: rng.gen(),
+            lending_market: Pubkey::new_unique(),
+            reserve: Pubkey::new_unique(),
+            side: RewardSide::Borrow,
+            reward_mint: Pubkey::new_unique(),
+            reward_supply: Pubkey::new_unique(),
+        };
+
+        let mut packed = [0u8; REWARD_RULE_LEN];
+        RewardRule::pack(reward_rule.clone(), &mut packed).unwrap();
+        let unpacked = RewardRule::unpack(&packed).unwrap();
+        assert_eq!(reward_rule, unpacked);
+    }
+
+    #[test]
+    fn reserve_rewards_accrue() {
+        let mut rewards = ReserveRewards {
+            emission_per_slot: 100,
+            start_slot: 10,
+            end_slot: 50,
+            last_update_slot: 0,
+            ..ReserveRewards::default()
+        };
+
+        // nothing is emitted before the start slot
+        rewards.accrue(10, Decimal::from(1000u64)).unwrap();
+        assert_eq!(rewards.reward_index, Decimal::zero());
+        assert_eq!(rewards.last_update_slot, 10);
+
+        rewards.accrue(20, Decimal::from(1000u64)).unwrap();
+        assert_eq!(rewards.reward_index, Decimal::one());
+
+        // emissions with no positions are not paid out
+        rewards.accrue(30, Decimal::zero()).unwrap();
+        assert_eq!(rewards.reward_index, Decimal::one());
+
+        // nothing is emitted after the end slot
+        rewards.accrue(100, Decimal::from(2000u64)).unwrap();
+        assert_eq!(rewards.reward_index, Decimal::from(2u64));
+        assert_eq!(rewards.last_update_slot, 100);
+
+        rewards.accrue(200, Decimal::from(2000u64)).unwrap();
+        assert_eq!(rewards.reward_index, Decimal::from(2u64));
+    }
+
+    #[test]
+    fn position_rewards_settle_and_claim() {
+        let mut rewards = PositionRewards::default();
+
+        // a new position earns nothing for rewards emitted before it was opened
+        rewards
+            .settle(Decimal::from(5u64), Decimal::zero())
+            .unwrap();
+        assert_eq!(rewards.unclaimed_rewards, Decimal::zero());
+        assert_eq!(rewards.reward_index, Decimal::from(5u64));
+
+        rewards
+            .settle(Decimal::from(7u64), Decimal::from(10u64))
+            .unwrap();
+        assert_eq!(rewards.unclaimed_rewards, Decimal::from(20u64));
+
+        // an increase settled right after the last settlement earns only from there
+        rewards
+            .settle(Decimal::from(7u64), Decimal::from(10u64))
+            .unwrap();
+        rewards
+            .settle(Decimal::from(8u64), Decimal::from(100u64))
+            .unwrap();
+        assert_eq!(rewards.unclaimed_rewards, Decimal::from(120u64));
+
+        assert!(rewards.has_claimable_rewards());
+        rewards.unclaimed_rewards = rewards
+            .unclaimed_rewards
+            .try_add(Decimal::from_percent(50))
+            .unwrap();
+        assert_eq!(rewards.claim().unwrap(), 120);
+        assert_eq!(rewards.unclaimed_rewards, Decimal::from_percent(50));
+        assert!(!rewards.has_claimable_rewards());
+        assert_eq!(rewards.claim().unwrap(), 0);
+    }
+}
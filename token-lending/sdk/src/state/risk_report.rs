@@ -0,0 +1,370 @@
+use super::*;
+use crate::{
+    error::LendingError,
+    math::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub},
+};
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+use std::convert::TryFrom;
+
+/// Off-chain risk summary of a refreshed obligation.
+///
+/// All values are derived from the market values cached on the obligation during the last
+/// RefreshObligation, so the obligation should be refreshed in the same slot as the reserves
+/// for the report to be meaningful.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RiskReport {
+    /// Risk-adjusted borrow value of the obligation
+    pub borrowed_value: Decimal,
+    /// Borrow value at which the obligation can be liquidated
+    pub unhealthy_borrow_value: Decimal,
+    /// unhealthy_borrow_value - borrowed_value, or zero if the obligation is already unhealthy
+    pub liquidation_buffer: Decimal,
+    /// Per-reserve breakdown, in the order reserves first appear in deposits then borrows
+    pub positions: Vec<PositionRisk>,
+}
+
+/// Risk contribution of a single reserve to an obligation
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionRisk {
+    /// Reserve address
+    pub reserve: Pubkey,
+    /// Market value of the deposit in this reserve
+    pub deposited_value: Decimal,
+    /// Deposit value weighted by the reserve liquidation threshold, ie the amount this deposit
+    /// adds to the obligation's unhealthy borrow value
+    pub weighted_collateral_value: Decimal,
+    /// Market value of the borrow in this reserve
+    pub borrowed_value: Decimal,
+    /// Borrow value weighted by the reserve borrow weight, ie the amount this borrow adds to
+    /// the obligation's borrowed value
+    pub weighted_borrowed_value: Decimal,
+    /// Share of the obligation's borrowed value contributed by this borrow
+    pub borrowed_value_share: Rate,
+    /// Price move of this asset alone that would make the obligation liquidatable.
+    /// None if no price move of this asset can trigger a liquidation.
+    pub liquidation_price_change: Option<PriceChange>,
+    /// Price of this asset at which the obligation becomes liquidatable
+    pub liquidation_price: Option<Decimal>,
+}
+
+/// Relative price move of an asset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceChange {
+    /// Price falls by the given fraction of the current price
+    Drop(Rate),
+    /// Price rises by the given fraction of the current price
+    Rise(Rate),
+}
+
+impl RiskReport {
+    /// Build a risk report for a refreshed obligation. `reserves` must contain every reserve the
+    /// obligation deposits into or borrows from.
+    pub fn for_obligation(
+        obligation: &Obligation,
+        reserves: &[(Pubkey, Reserve)],
+    ) -> Result<Self, ProgramError> {
+        let liquidation_buffer = obligation
+            .unhealthy_borrow_value
+            .try_sub(obligation.borrowed_value)
+            .unwrap_or_else(|_| Decimal::zero());
+
+        let mut positions: Vec<PositionRisk> = Vec::new();
+        for collateral in &obligation.deposits {
+            let reserve = find_reserve(reserves, &collateral.deposit_reserve)?;
+            let position = find_or_add_position(&mut positions, collateral.deposit_reserve);
+            position.deposited_value = collateral.market_value;
+            position.weighted_collateral_value = collateral
+                .market_value
+                .try_mul(Rate::from_percent(reserve.config.liquidation_threshold))?;
+        }
+
+        for liquidity in &obligation.borrows {
+            let reserve = find_reserve(reserves, &liquidity.borrow_reserve)?;
+            let position = find_or_add_position(&mut positions, liquidity.borrow_reserve);
+            position.borrowed_value = liquidity.market_value;
+            position.weighted_borrowed_value =
+                liquidity.market_value.try_mul(reserve.borrow_weight())?;
+            position.borrowed_value_share = if obligation.borrowed_value == Decimal::zero() {
+                Rate::zero()
+            } else {
+                Rate::try_from(
+                    position
+                        .weighted_borrowed_value
+                        .try_div(obligation.borrowed_value)?,
+                )?
+            };
+        }
+
+        let has_borrows = obligation.borrowed_value > Decimal::zero();
+        for position in positions.iter_mut() {
+            if !has_borrows {
+                continue;
+            }
+
+            let reserve = find_reserve(reserves, &position.reserve)?;
+
+            // a price move of x (as a fraction of the current price) changes the buffer by
+            // x * (weighted_collateral_value - weighted_borrowed_value). liquidation happens
+            // once the buffer is used up.
+            let price_change =
+                if position.weighted_collateral_value > position.weighted_borrowed_value {
+                    let sensitivity = position
+                        .weighted_collateral_value
+                        .try_sub(position.weighted_borrowed_value)?;
+                    let drop = liquidation_buffer.try_div(sensitivity)?;
+                    if drop > Decimal::one() {
+                        None
+                    } else {
+                        Some(PriceChange::Drop(Rate::try_from(drop)?))
+                    }
+                } else if position.weighted_borrowed_value > position.weighted_collateral_value {
+                    let sensitivity = position
+                        .weighted_borrowed_value
+                        .try_sub(position.weighted_collateral_value)?;
+                    Some(PriceChange::Rise(Rate::try_from(
+                        liquidation_buffer.try_div(sensitivity)?,
+                    )?))
+                } else {
+                    None
+                };
+
+            position.liquidation_price = match price_change {
+                Some(PriceChange::Drop(drop)) => Some(
+                    reserve
+                        .liquidity
+                        .market_price
+                        .try_mul(Rate::one().try_sub(drop)?)?,
+                ),
+                Some(PriceChange::Rise(rise)) => Some(
+                    reserve
+                        .liquidity
+                        .market_price
+                        .try_mul(Rate::one().try_add(rise)?)?,
+                ),
+                None => None,
+            };
+            position.liquidation_price_change = price_change;
+        }
+
+        Ok(Self {
+            borrowed_value: obligation.borrowed_value,
+            unhealthy_borrow_value: obligation.unhealthy_borrow_value,
+            liquidation_buffer,
+            positions,
+        })
+    }
+
+    /// Find the risk breakdown for a reserve
+    pub fn find_position(&self, reserve: &Pubkey) -> Option<&PositionRisk> {
+        self.positions.iter().find(|p| &p.reserve == reserve)
+    }
+}
+
+pub(super) fn find_reserve<'a>(
+    reserves: &'a [(Pubkey, Reserve)],
+    reserve_pubkey: &Pubkey,
+) -> Result<&'a Reserve, ProgramError> {
+    reserves
+        .iter()
+        .find(|(pubkey, _)| pubkey == reserve_pubkey)
+        .map(|(_, reserve)| reserve)
+        .ok_or_else(|| {
+            msg!("Reserve {} was not provided", reserve_pubkey);
+            LendingError::InvalidAccountInput.into()
+        })
+}
+
+fn find_or_add_position(positions: &mut Vec<PositionRisk>, reserve: Pubkey) -> &mut PositionRisk {
+    if let Some(index) = positions.iter().position(|p| p.reserve == reserve) {
+        return &mut positions[index];
+    }
+    positions.push(PositionRisk {
+        reserve,
+        ..PositionRisk::default()
+    });
+    positions.last_mut().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reserve(liquidation_threshold: u8, added_borrow_weight_bps: u64, price: u64) -> Reserve {
+        Reserve {
+            liquidity: ReserveLiquidity {
+                market_price: Decimal::from(price),
+                ..ReserveLiquidity::default()
+            },
+            config: ReserveConfig {
+                liquidation_threshold,
+                added_borrow_weight_bps,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        }
+    }
+
+    #[test]
+    fn risk_report_single_deposit_single_borrow() {
+        let sol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let reserves = vec![(sol, reserve(80, 0, 20)), (usdc, reserve(90, 0, 1))];
+
+        // $100 of SOL deposited, $40 of USDC borrowed
+        let obligation = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposit_reserve: sol,
+                market_value: Decimal::from(100u64),
+                ..ObligationCollateral::default()
+            }],
+            borrows: vec![ObligationLiquidity {
+                borrow_reserve: usdc,
+                market_value: Decimal::from(40u64),
+                ..ObligationLiquidity::default()
+            }],
+            deposited_value: Decimal::from(100u64),
+            borrowed_value: Decimal::from(40u64),
+            unhealthy_borrow_value: Decimal::from(80u64),
+            ..Obligation::default()
+        };
+
+        let report = RiskReport::for_obligation(&obligation, &reserves).unwrap();
+        assert_eq!(report.liquidation_buffer, Decimal::from(40u64));
+
+        // SOL can drop 50% ($80 * 0.5 = $40) before liquidation
+        let sol_position = report.find_position(&sol).unwrap();
+        assert_eq!(sol_position.weighted_collateral_value, Decimal::from(80u64));
+        assert_eq!(
+            sol_position.liquidation_price_change,
+            Some(PriceChange::Drop(Rate::from_percent(50)))
+        );
+        assert_eq!(sol_position.liquidation_price, Some(Decimal::from(10u64)));
+
+        // USDC can rise 100% before liquidation
+        let usdc_position = report.find_position(&usdc).unwrap();
+        assert_eq!(usdc_position.weighted_borrowed_value, Decimal::from(40u64));
+        assert_eq!(usdc_position.borrowed_value_share, Rate::one());
+        assert_eq!(
+            usdc_position.liquidation_price_change,
+            Some(PriceChange::Rise(Rate::from_percent(100)))
+        );
+        assert_eq!(usdc_position.liquidation_price, Some(Decimal::from(2u64)));
+    }
+
+    #[test]
+    fn risk_report_multiple_assets() {
+        let sol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let bonk = Pubkey::new_unique();
+        let reserves = vec![
+            (sol, reserve(50, 0, 20)),
+            (usdc, reserve(80, 0, 1)),
+            (bonk, reserve(0, 10_000, 1)),
+        ];
+
+        // $100 SOL + $100 USDC deposited, $20 USDC + $40 BONK (2x borrow weight) borrowed
+        let obligation = Obligation {
+            deposits: vec![
+                ObligationCollateral {
+                    deposit_reserve: sol,
+                    market_value: Decimal::from(100u64),
+                    ..ObligationCollateral::default()
+                },
+                ObligationCollateral {
+                    deposit_reserve: usdc,
+                    market_value: Decimal::from(100u64),
+                    ..ObligationCollateral::default()
+                },
+            ],
+            borrows: vec![
+                ObligationLiquidity {
+                    borrow_reserve: bonk,
+                    market_value: Decimal::from(40u64),
+                    ..ObligationLiquidity::default()
+                },
+                ObligationLiquidity {
+                    borrow_reserve: usdc,
+                    market_value: Decimal::from(20u64),
+                    ..ObligationLiquidity::default()
+                },
+            ],
+            deposited_value: Decimal::from(200u64),
+            borrowed_value: Decimal::from(100u64),
+            unhealthy_borrow_value: Decimal::from(130u64),
+            ..Obligation::default()
+        };
+
+        let report = RiskReport::for_obligation(&obligation, &reserves).unwrap();
+        assert_eq!(report.positions.len(), 3);
+        assert_eq!(report.liquidation_buffer, Decimal::from(30u64));
+
+        // SOL can drop 60% ($50 * 0.6 = $30) before liquidation
+        let sol_position = report.find_position(&sol).unwrap();
+        assert_eq!(sol_position.weighted_collateral_value, Decimal::from(50u64));
+        assert_eq!(
+            sol_position.liquidation_price_change,
+            Some(PriceChange::Drop(Rate::from_percent(60)))
+        );
+
+        // USDC is both deposited and borrowed, so only the net exposure counts
+        let usdc_position = report.find_position(&usdc).unwrap();
+        assert_eq!(
+            usdc_position.weighted_collateral_value,
+            Decimal::from(80u64)
+        );
+        assert_eq!(usdc_position.weighted_borrowed_value, Decimal::from(20u64));
+        assert_eq!(usdc_position.borrowed_value_share, Rate::from_percent(20));
+        assert_eq!(
+            usdc_position.liquidation_price_change,
+            Some(PriceChange::Drop(Rate::from_percent(50)))
+        );
+
+        let bonk_position = report.find_position(&bonk).unwrap();
+        assert_eq!(bonk_position.weighted_borrowed_value, Decimal::from(80u64));
+        assert_eq!(bonk_position.borrowed_value_share, Rate::from_percent(80));
+        assert_eq!(
+            bonk_position.liquidation_price_change,
+            Some(PriceChange::Rise(
+                Rate::try_from(Decimal::from(30u64).try_div(80u64).unwrap()).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn risk_report_missing_reserve() {
+        let obligation = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposit_reserve: Pubkey::new_unique(),
+                market_value: Decimal::from(100u64),
+                ..ObligationCollateral::default()
+            }],
+            ..Obligation::default()
+        };
+
+        assert_eq!(
+            RiskReport::for_obligation(&obligation, &[]),
+            Err(LendingError::InvalidAccountInput.into())
+        );
+    }
+
+    #[test]
+    fn risk_report_no_borrows() {
+        let sol = Pubkey::new_unique();
+        let reserves = vec![(sol, reserve(80, 0, 20))];
+        let obligation = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposit_reserve: sol,
+                market_value: Decimal::from(100u64),
+                ..ObligationCollateral::default()
+            }],
+            deposited_value: Decimal::from(100u64),
+            unhealthy_borrow_value: Decimal::from(80u64),
+            ..Obligation::default()
+        };
+
+        let report = RiskReport::for_obligation(&obligation, &reserves).unwrap();
+        let sol_position = report.find_position(&sol).unwrap();
+        assert_eq!(sol_position.liquidation_price_change, None);
+        assert_eq!(sol_position.liquidation_price, None);
+    }
+}
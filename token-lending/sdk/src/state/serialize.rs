@@ -0,0 +1,88 @@
+//! Serde helpers for the `serialize` feature. Pubkeys are written as base58 strings rather than
+//! byte arrays, to match how they're shown everywhere else.
+
+pub(crate) mod pubkey {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use solana_program::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        Pubkey::from_str(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+pub(crate) mod option_pubkey {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use solana_program::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(
+        pubkey: &Option<Pubkey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match pubkey {
+            Some(pubkey) => serializer.collect_str(pubkey),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Pubkey>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|pubkey| Pubkey::from_str(&pubkey).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Decimal,
+        state::{Obligation, ObligationLiquidity, Reserve, ReserveConfig},
+    };
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn json_roundtrip() {
+        let reserve = Reserve {
+            lending_market: Pubkey::new_unique(),
+            config: ReserveConfig {
+                extra_oracle_pubkey: Some(Pubkey::new_unique()),
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let json = serde_json::to_value(&reserve).unwrap();
+        assert_eq!(json["lending_market"], reserve.lending_market.to_string());
+        assert_eq!(json["config"]["reserve_type"], "Regular");
+        assert_eq!(serde_json::from_value::<Reserve>(json).unwrap(), reserve);
+
+        let obligation = Obligation {
+            borrows: vec![ObligationLiquidity {
+                borrowed_amount_wads: Decimal::from_scaled_val(1_500_000_000_000_000_001),
+                ..ObligationLiquidity::default()
+            }],
+            ..Obligation::default()
+        };
+        let json = serde_json::to_value(&obligation).unwrap();
+        assert_eq!(
+            json["borrows"][0]["borrowed_amount_wads"],
+            "1.500000000000000001"
+        );
+        assert_eq!(
+            serde_json::from_value::<Obligation>(json).unwrap(),
+            obligation
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Decimal>("\"2.5\"").unwrap(),
+            Decimal::from_scaled_val(2_500_000_000_000_000_000)
+        );
+        assert!(serde_json::from_str::<Decimal>("\"0.0000000000000000001\"").is_err());
+    }
+}
@@ -0,0 +1,446 @@
+use super::*;
+use crate::{
+    error::LendingError,
+    math::{Decimal, Rate, TryAdd, TryMul, TrySub},
+};
+use risk_report::find_reserve;
+use solana_program::{clock::Slot, msg, program_error::ProgramError, pubkey::Pubkey};
+use std::cmp::min;
+
+/// Off-chain RefreshObligation. Accrues interest on a copy of each reserve up to `slot` and
+/// recomputes the obligation's values the way the program would, at the prices cached on the
+/// reserves.
+///
+/// `reserves` must contain every reserve the obligation deposits into or borrows from, and
+/// `reserve_pair_borrow_weights` the pair borrow weights of every borrow reserve that has them.
+/// `asset_categories` are the lending market's, required if the obligation is valued at its asset
+/// category's limits. Borrow attribution is left alone since it also updates the
+/// deposit reserves.
+pub fn simulate_refresh_obligation(
+    obligation: &Obligation,
+    reserves: &[(Pubkey, Reserve)],
+    reserve_pair_borrow_weights: &[ReservePairBorrowWeights],
+    asset_categories: Option<&AssetCategories>,
+    slot: Slot,
+) -> Result<Obligation, ProgramError> {
+    let refresh_reserve = |reserve_pubkey: &Pubkey| -> Result<Reserve, ProgramError> {
+        let mut reserve = find_reserve(reserves, reserve_pubkey)?.clone();
+        reserve.accrue_interest(slot)?;
+        reserve.last_update.update_slot(slot);
+        Ok(reserve)
+    };
+
+    let mut obligation = obligation.clone();
+    let mut collateral_values = Vec::with_capacity(obligation.deposits.len());
+
+    let mut deposited_value = Decimal::zero();
+    let mut borrowed_value = Decimal::zero();
+    let mut unweighted_borrowed_value = Decimal::zero();
+    let mut borrowed_value_upper_bound = Decimal::zero();
+    let mut allowed_borrow_value = Decimal::zero();
+    let mut unhealthy_borrow_value = Decimal::zero();
+    let mut super_unhealthy_borrow_value = Decimal::zero();
+
+    let mut reserve_asset_categories =
+        Vec::with_capacity(obligation.deposits.len() + obligation.borrows.len());
+    let mut category_allowed_borrow_value = Decimal::zero();
+    let mut category_unhealthy_borrow_value = Decimal::zero();
+    let mut category_super_unhealthy_borrow_value = Decimal::zero();
+
+    for collateral in obligation.deposits.iter_mut() {
+        let deposit_reserve = refresh_reserve(&collateral.deposit_reserve)?;
+
+        let liquidity_amount = deposit_reserve
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(collateral.deposited_amount.into())?;
+        let market_value = deposit_reserve.market_value(liquidity_amount)?;
+        let market_value_lower_bound =
+            deposit_reserve.market_value_lower_bound(liquidity_amount)?;
+
+        let (loan_to_value_rate, liquidation_threshold_rate, max_liquidation_threshold_rate) =
+            if deposit_reserve.config.reserve_type == ReserveType::Isolated {
+                (Rate::zero(), Rate::zero(), Rate::zero())
+            } else {
+                (
+                    Rate::from_percent(deposit_reserve.config.loan_to_value_ratio),
+                    Rate::from_percent(deposit_reserve.config.liquidation_threshold),
+                    Rate::from_percent(deposit_reserve.config.max_liquidation_threshold),
+                )
+            };
+
+        let (
+            category_loan_to_value_rate,
+            category_liquidation_threshold_rate,
+            category_max_liquidation_threshold_rate,
+        ) = match asset_categories.and_then(|asset_categories| {
+            asset_categories.get(deposit_reserve.config.asset_category)
+        }) {
+            Some(category) if deposit_reserve.config.reserve_type != ReserveType::Isolated => {
+                category.collateral_rates(&deposit_reserve.config)
+            }
+            _ => (
+                loan_to_value_rate,
+                liquidation_threshold_rate,
+                max_liquidation_threshold_rate,
+            ),
+        };
+
+        let borrowing_power = |loan_to_value_rate| -> Result<Decimal, ProgramError> {
+            let borrowing_power = market_value_lower_bound.try_mul(loan_to_value_rate)?;
+            if deposit_reserve.config.max_collateral_value_contribution > 0 {
+                return Ok(min(
+                    borrowing_power,
+                    Decimal::from(deposit_reserve.config.max_collateral_value_contribution),
+                ));
+            }
+            Ok(borrowing_power)
+        };
+
+        collateral.market_value = market_value;
+        collateral_values.push((collateral.deposit_reserve, market_value));
+        reserve_asset_categories.push(deposit_reserve.config.asset_category);
+        deposited_value = deposited_value.try_add(market_value)?;
+        allowed_borrow_value =
+            allowed_borrow_value.try_add(borrowing_power(loan_to_value_rate)?)?;
+        unhealthy_borrow_value =
+            unhealthy_borrow_value.try_add(market_value.try_mul(liquidation_threshold_rate)?)?;
+        super_unhealthy_borrow_value = super_unhealthy_borrow_value
+            .try_add(market_value.try_mul(max_liquidation_threshold_rate)?)?;
+        category_allowed_borrow_value =
+            category_allowed_borrow_value.try_add(borrowing_power(category_loan_to_value_rate)?)?;
+        category_unhealthy_borrow_value = category_unhealthy_borrow_value
+            .try_add(market_value.try_mul(category_liquidation_threshold_rate)?)?;
+        category_super_unhealthy_borrow_value = category_super_unhealthy_borrow_value
+            .try_add(market_value.try_mul(category_max_liquidation_threshold_rate)?)?;
+    }
+
+    let mut borrowing_isolated_asset = false;
+    let mut max_borrow_weight = None;
+    for (index, liquidity) in obligation.borrows.iter_mut().enumerate() {
+        let borrow_reserve = refresh_reserve(&liquidity.borrow_reserve)?;
+        if borrow_reserve.config.reserve_type == ReserveType::Isolated {
+            borrowing_isolated_asset = true;
+        }
+        reserve_asset_categories.push(borrow_reserve.config.asset_category);
+
+        liquidity.accrue_interest_with_rate_cap(
+            borrow_reserve.liquidity.cumulative_borrow_rate_wads,
+            slot,
+        )?;
+
+        let borrow_weight_and_pubkey = (
+            borrow_reserve.config.added_borrow_weight_bps,
+            liquidity.borrow_reserve,
+        );
+        max_borrow_weight = match max_borrow_weight {
+            Some((max_borrow_weight_and_pubkey, _))
+                if borrow_weight_and_pubkey <= max_borrow_weight_and_pubkey =>
+            {
+                max_borrow_weight
+            }
+            _ if liquidity.borrowed_amount_wads > Decimal::zero() => {
+                Some((borrow_weight_and_pubkey, index))
+            }
+            _ => max_borrow_weight,
+        };
+
+        let market_value = borrow_reserve.borrow_market_value(liquidity.borrowed_amount_wads)?;
+        let market_value_upper_bound =
+            borrow_reserve.market_value_upper_bound(liquidity.borrowed_amount_wads)?;
+        liquidity.market_value = market_value;
+
+        let mut borrow_weight = borrow_reserve.borrow_weight();
+        if borrow_reserve.has_pair_borrow_weights {
+            let pair_borrow_weights = reserve_pair_borrow_weights
+                .iter()
+                .find(|weights| weights.reserve == liquidity.borrow_reserve)
+                .ok_or_else(|| {
+                    msg!(
+                        "Pair borrow weights of reserve {} were not provided",
+                        liquidity.borrow_reserve
+                    );
+                    LendingError::InvalidAccountInput
+                })?;
+            borrow_weight = borrow_weight
+                .try_mul(pair_borrow_weights.borrow_weight_multiplier(&collateral_values)?)?;
+        }
+
+        borrowed_value = borrowed_value.try_add(market_value.try_mul(borrow_weight)?)?;
+        borrowed_value_upper_bound =
+            borrowed_value_upper_bound.try_add(market_value_upper_bound.try_mul(borrow_weight)?)?;
+        unweighted_borrowed_value = unweighted_borrowed_value.try_add(market_value)?;
+    }
+
+    // as RefreshObligation, the category's limits apply if they changed any value
+    let (allowed_borrow_value, unhealthy_borrow_value, super_unhealthy_borrow_value) =
+        match shared_asset_category(&reserve_asset_categories) {
+            Some(_) if asset_categories.is_some() => {
+                obligation.uses_asset_category_limits = category_allowed_borrow_value
+                    != allowed_borrow_value
+                    || category_unhealthy_borrow_value != unhealthy_borrow_value
+                    || category_super_unhealthy_borrow_value != super_unhealthy_borrow_value;
+                (
+                    category_allowed_borrow_value,
+                    category_unhealthy_borrow_value,
+                    category_super_unhealthy_borrow_value,
+                )
+            }
+            Some(asset_category) if obligation.uses_asset_category_limits => {
+                msg!(
+                    "Asset categories were not provided for asset category {}",
+                    asset_category
+                );
+                return Err(LendingError::InvalidAccountInput.into());
+            }
+            _ => {
+                obligation.uses_asset_category_limits = false;
+                (
+                    allowed_borrow_value,
+                    unhealthy_borrow_value,
+                    super_unhealthy_borrow_value,
+                )
+            }
+        };
+
+    obligation.deposited_value = deposited_value;
+    obligation.borrowed_value = borrowed_value;
+    obligation.unweighted_borrowed_value = unweighted_borrowed_value;
+    obligation.borrowed_value_upper_bound = borrowed_value_upper_bound;
+    obligation.borrowing_isolated_asset = borrowing_isolated_asset;
+
+    let global_unhealthy_borrow_value = Decimal::from(GLOBAL_UNHEALTHY_BORROW_VALUE);
+    obligation.allowed_borrow_value = min(
+        allowed_borrow_value,
+        Decimal::from(GLOBAL_ALLOWED_BORROW_VALUE),
+    );
+    obligation.unhealthy_borrow_value = min(unhealthy_borrow_value, global_unhealthy_borrow_value);
+    obligation.super_unhealthy_borrow_value =
+        min(super_unhealthy_borrow_value, global_unhealthy_borrow_value);
+
+    obligation.last_update.update_slot(slot);
+    obligation.update_unhealthy_since_slot(slot);
+
+    if let Some((_, max_borrow_weight_index)) = max_borrow_weight {
+        obligation.borrows.swap(0, max_borrow_weight_index);
+    }
+    obligation
+        .deposits
+        .retain(|collateral| collateral.deposited_amount > 0);
+    obligation
+        .borrows
+        .retain(|liquidity| liquidity.borrowed_amount_wads > Decimal::zero());
+
+    Ok(obligation)
+}
+
+/// Result of a BorrowObligationLiquidity with u64::MAX as the amount, for an obligation and
+/// reserve refreshed in `slot`. Accounts for the obligation's borrowing power and borrow cap, the
+/// reserve's borrow limit and available liquidity, and the lending market and reserve outflow
+/// limits. Obligation rate limiters and borrow delegations are not taken into account.
+pub fn max_borrowable(
+    lending_market: &LendingMarket,
+    obligation: &Obligation,
+    reserve_pubkey: &Pubkey,
+    reserve: &Reserve,
+    slot: Slot,
+) -> Result<CalculateBorrowResult, ProgramError> {
+    let isolated_violation = match reserve.config.reserve_type {
+        ReserveType::Isolated => obligation
+            .borrows
+            .iter()
+            .any(|liquidity| &liquidity.borrow_reserve != reserve_pubkey),
+        ReserveType::Regular => obligation.borrowing_isolated_asset,
+    };
+    if isolated_violation {
+        return Err(LendingError::IsolatedTierAssetViolation.into());
+    }
+
+    let remaining_borrow_value = obligation
+        .remaining_borrow_value()
+        .unwrap_or_else(|_| Decimal::zero());
+    let remaining_reserve_capacity = Decimal::from(reserve.config.borrow_limit)
+        .try_sub(reserve.liquidity.borrowed_amount_wads)
+        .unwrap_or_else(|_| Decimal::zero());
+
+    let max_outflow_usd = min(
+        lending_market
+            .rate_limiter
+            .clone()
+            .remaining_outflow(slot)?,
+        obligation.remaining_borrow_cap(),
+    );
+    let max_outflow_liquidity_amount = min(
+        reserve
+            .usd_to_liquidity_amount_lower_bound(min(max_outflow_usd, remaining_borrow_value))?,
+        reserve.rate_limiter.clone().remaining_outflow(slot)?,
+    );
+
+    reserve.calculate_borrow(
+        u64::MAX,
+        remaining_borrow_value,
+        min(remaining_reserve_capacity, max_outflow_liquidity_amount),
+    )
+}
+
+/// Collateral tokens a WithdrawObligationCollateral with u64::MAX as the amount would take out of
+/// an obligation and reserve refreshed in `slot`, including the lending market and reserve outflow
+/// limits. Obligation rate limiters are not taken into account.
+pub fn max_withdrawable(
+    lending_market: &LendingMarket,
+    obligation: &Obligation,
+    reserve_pubkey: &Pubkey,
+    reserve: &Reserve,
+    slot: Slot,
+) -> Result<u64, ProgramError> {
+    let (collateral, _) = obligation.find_collateral_in_deposits(*reserve_pubkey)?;
+
+    let max_lending_market_outflow_liquidity_amount =
+        reserve.usd_to_liquidity_amount_lower_bound(min(
+            lending_market.rate_limiter.max_outflow_remaining(slot)?,
+            obligation.deposited_value.try_mul(2)?,
+        ))?;
+    let max_outflow_liquidity_amount = min(
+        max_lending_market_outflow_liquidity_amount,
+        reserve.rate_limiter.max_outflow_remaining(slot)?,
+    );
+    let max_outflow_collateral_amount = reserve
+        .collateral_exchange_rate()?
+        .decimal_liquidity_to_collateral(max_outflow_liquidity_amount)?
+        .try_floor_u64()?;
+
+    Ok(min(
+        obligation.max_withdraw_amount(collateral, reserve)?,
+        max_outflow_collateral_amount,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reserve(loan_to_value_ratio: u8, liquidation_threshold: u8, price: u64) -> Reserve {
+        Reserve {
+            version: PROGRAM_VERSION,
+            liquidity: ReserveLiquidity {
+                mint_decimals: 0,
+                available_amount: 1_000,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                market_price: Decimal::from(price),
+                smoothed_market_price: Decimal::from(price),
+                ..ReserveLiquidity::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 1_000,
+                ..ReserveCollateral::default()
+            },
+            config: ReserveConfig {
+                loan_to_value_ratio,
+                liquidation_threshold,
+                max_liquidation_threshold: liquidation_threshold,
+                borrow_limit: u64::MAX,
+                ..ReserveConfig::default()
+            },
+            rate_limiter: RateLimiter::default(),
+            ..Reserve::default()
+        }
+    }
+
+    #[test]
+    fn simulate_refresh_and_max_amounts() {
+        let sol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let reserves = [(sol, reserve(50, 80, 10)), (usdc, reserve(80, 90, 1))];
+        let obligation = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposit_reserve: sol,
+                deposited_amount: 100,
+                ..ObligationCollateral::default()
+            }],
+            borrows: vec![ObligationLiquidity {
+                borrow_reserve: usdc,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                borrowed_amount_wads: Decimal::from(200u64),
+                ..ObligationLiquidity::default()
+            }],
+            ..Obligation::default()
+        };
+
+        let refreshed = simulate_refresh_obligation(&obligation, &reserves, &[], None, 1).unwrap();
+        assert_eq!(refreshed.deposited_value, Decimal::from(1_000u64));
+        assert_eq!(refreshed.borrowed_value, Decimal::from(200u64));
+        assert_eq!(refreshed.allowed_borrow_value, Decimal::from(500u64));
+        assert_eq!(refreshed.unhealthy_borrow_value, Decimal::from(800u64));
+        assert_eq!(refreshed.unhealthy_since_slot, 0);
+
+        let lending_market = LendingMarket {
+            rate_limiter: RateLimiter::default(),
+            ..LendingMarket::default()
+        };
+        let max_borrow =
+            max_borrowable(&lending_market, &refreshed, &usdc, &reserves[1].1, 1).unwrap();
+        assert_eq!(max_borrow.borrow_amount, Decimal::from(300u64));
+        assert_eq!(max_borrow.receive_amount, 300);
+
+        // 300 of borrowing power left at a 50% LTV frees up $600 of SOL at $10
+        let max_withdraw =
+            max_withdrawable(&lending_market, &refreshed, &sol, &reserves[0].1, 1).unwrap();
+        assert_eq!(max_withdraw, 60);
+
+        // reserves missing from the input can't be simulated
+        assert!(simulate_refresh_obligation(&obligation, &reserves[..1], &[], None, 1).is_err());
+    }
+
+    #[test]
+    fn simulate_refresh_in_asset_category() {
+        let usdc = Pubkey::new_unique();
+        let usdt = Pubkey::new_unique();
+        let mut reserves = [(usdc, reserve(80, 90, 1)), (usdt, reserve(80, 90, 1))];
+        for (_, reserve) in reserves.iter_mut() {
+            reserve.config.asset_category = 1;
+        }
+        let mut asset_categories = AssetCategories::default();
+        asset_categories
+            .set(
+                1,
+                AssetCategory {
+                    loan_to_value_ratio: 95,
+                    liquidation_threshold: 97,
+                    max_liquidation_threshold: 97,
+                },
+            )
+            .unwrap();
+        let obligation = Obligation {
+            deposits: vec![ObligationCollateral {
+                deposit_reserve: usdc,
+                deposited_amount: 100,
+                ..ObligationCollateral::default()
+            }],
+            borrows: vec![ObligationLiquidity {
+                borrow_reserve: usdt,
+                cumulative_borrow_rate_wads: Decimal::one(),
+                borrowed_amount_wads: Decimal::from(50u64),
+                ..ObligationLiquidity::default()
+            }],
+            ..Obligation::default()
+        };
+
+        let refreshed =
+            simulate_refresh_obligation(&obligation, &reserves, &[], Some(&asset_categories), 1)
+                .unwrap();
+        assert_eq!(refreshed.allowed_borrow_value, Decimal::from(95u64));
+        assert_eq!(refreshed.unhealthy_borrow_value, Decimal::from(97u64));
+        assert_eq!(refreshed.super_unhealthy_borrow_value, Decimal::from(97u64));
+
+        // the obligation is valued at the category's limits, so the categories are required
+        assert!(refreshed.uses_asset_category_limits);
+        assert!(simulate_refresh_obligation(&refreshed, &reserves, &[], None, 1).is_err());
+
+        // a borrow outside the category falls back to the reserves' own limits
+        reserves[1].1.config.asset_category = 2;
+        let refreshed =
+            simulate_refresh_obligation(&obligation, &reserves, &[], Some(&asset_categories), 1)
+                .unwrap();
+        assert_eq!(refreshed.allowed_borrow_value, Decimal::from(80u64));
+        assert_eq!(refreshed.unhealthy_borrow_value, Decimal::from(90u64));
+    }
+}
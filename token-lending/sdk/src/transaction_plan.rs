@@ -0,0 +1,309 @@
+//! Estimating the size and compute cost of lending transactions, and splitting instructions that
+//! don't fit in one transaction.
+//!
+//! Instructions are planned in groups that are never split across transactions. A group should
+//! hold an action together with the refreshes it needs, since reserves and obligations refreshed
+//! in an earlier transaction may be stale by the time a later one lands.
+
+use crate::{compute_budget_program, instruction::LendingInstruction};
+use solana_program::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+use std::collections::BTreeSet;
+
+/// Max size of a serialized transaction, in bytes
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+/// Max compute units a transaction can request
+pub const MAX_TRANSACTION_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Compute units assumed for instructions of other programs, the runtime's default limit per
+/// instruction
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u32 = 200_000;
+
+const SIGNATURE_BYTES: usize = 64;
+
+/// Estimated size and compute cost of a transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionEstimate {
+    /// Size of the serialized transaction, signatures included, in bytes
+    pub size: usize,
+    /// Compute units the transaction is expected to use
+    pub compute_units: u32,
+}
+
+impl TransactionEstimate {
+    /// True if the transaction is within both limits
+    pub fn fits(&self) -> bool {
+        self.size <= MAX_TRANSACTION_SIZE && self.compute_units <= MAX_TRANSACTION_COMPUTE_UNITS
+    }
+}
+
+/// Why a group of instructions can't be sent, even in a transaction of its own
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionPlanError {
+    /// The group's transaction is too large. Loading `lookup_table_addresses` from an address
+    /// lookup table would bring a versioned transaction down to about `size_with_lookup_table`
+    TooLarge {
+        /// Index of the group
+        group_index: usize,
+        /// Estimated size of the group's transaction
+        size: usize,
+        /// Addresses that can be loaded from an address lookup table
+        lookup_table_addresses: Vec<Pubkey>,
+        /// Estimated size of the group's transaction with those addresses in a lookup table
+        size_with_lookup_table: usize,
+    },
+    /// The group needs more compute units than a transaction can request
+    TooManyComputeUnits {
+        /// Index of the group
+        group_index: usize,
+        /// Estimated compute units of the group
+        compute_units: u32,
+    },
+}
+
+/// Estimate the compute units an instruction uses. Lending instructions are priced by kind,
+/// refreshing an obligation by the number of reserves passed to it, and instructions of other
+/// programs at DEFAULT_INSTRUCTION_COMPUTE_UNITS.
+pub fn estimate_instruction_compute_units(program_id: &Pubkey, instruction: &Instruction) -> u32 {
+    if instruction.program_id == compute_budget_program::id() {
+        return 150;
+    }
+    if instruction.program_id != *program_id {
+        return DEFAULT_INSTRUCTION_COMPUTE_UNITS;
+    }
+
+    match LendingInstruction::unpack(&instruction.data) {
+        Ok(LendingInstruction::RefreshReserve) => 30_000,
+        Ok(LendingInstruction::RefreshObligation) => {
+            let reserves = instruction.accounts.len().saturating_sub(1) as u32;
+            10_000 + 8_000 * reserves
+        }
+        Ok(LendingInstruction::FlashBorrowReserveLiquidity { .. })
+        | Ok(LendingInstruction::FlashRepayReserveLiquidity { .. }) => 40_000,
+        Ok(LendingInstruction::LiquidateObligation { .. })
+        | Ok(LendingInstruction::LiquidateObligationAndRedeemReserveCollateral { .. }) => 120_000,
+        _ => 80_000,
+    }
+}
+
+/// Estimate the size and compute cost of a legacy transaction with the instructions, paid for by
+/// `payer`
+pub fn estimate_transaction(
+    program_id: &Pubkey,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+) -> TransactionEstimate {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+
+    TransactionEstimate {
+        size: short_vec_len(signatures) + signatures * SIGNATURE_BYTES + message.serialize().len(),
+        compute_units: instructions
+            .iter()
+            .map(|instruction| estimate_instruction_compute_units(program_id, instruction))
+            .fold(0u32, u32::saturating_add),
+    }
+}
+
+/// Pack groups of instructions, in order, into as few transactions as fit the size and compute
+/// limits. Groups are never split, and keep their order.
+pub fn plan_transactions(
+    program_id: &Pubkey,
+    groups: &[Vec<Instruction>],
+    payer: &Pubkey,
+) -> Result<Vec<Vec<Instruction>>, TransactionPlanError> {
+    let mut transactions: Vec<Vec<Instruction>> = Vec::new();
+    let mut current: Vec<Instruction> = Vec::new();
+
+    for (group_index, group) in groups.iter().enumerate() {
+        let estimate = estimate_transaction(program_id, group, payer);
+        if estimate.compute_units > MAX_TRANSACTION_COMPUTE_UNITS {
+            return Err(TransactionPlanError::TooManyComputeUnits {
+                group_index,
+                compute_units: estimate.compute_units,
+            });
+        }
+        if estimate.size > MAX_TRANSACTION_SIZE {
+            let lookup_table_addresses = lookup_table_addresses(group, payer);
+            return Err(TransactionPlanError::TooLarge {
+                group_index,
+                size: estimate.size,
+                size_with_lookup_table: size_with_lookup_table(
+                    estimate.size,
+                    lookup_table_addresses.len(),
+                ),
+                lookup_table_addresses,
+            });
+        }
+
+        let mut candidate = current.clone();
+        candidate.extend(group.iter().cloned());
+        if current.is_empty() || estimate_transaction(program_id, &candidate, payer).fits() {
+            current = candidate;
+        } else {
+            transactions.push(std::mem::replace(&mut current, group.clone()));
+        }
+    }
+
+    if !current.is_empty() {
+        transactions.push(current);
+    }
+    Ok(transactions)
+}
+
+/// Accounts of the instructions that can be loaded from an address lookup table: everything but
+/// signers and the invoked programs
+fn lookup_table_addresses(instructions: &[Instruction], payer: &Pubkey) -> Vec<Pubkey> {
+    let program_ids: BTreeSet<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
+    let signers: BTreeSet<Pubkey> = instructions
+        .iter()
+        .flat_map(|ix| ix.accounts.iter())
+        .filter(|meta| meta.is_signer)
+        .map(|meta| meta.pubkey)
+        .collect();
+
+    let mut addresses = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+        if meta.pubkey != *payer
+            && !signers.contains(&meta.pubkey)
+            && !program_ids.contains(&meta.pubkey)
+            && !addresses.contains(&meta.pubkey)
+        {
+            addresses.push(meta.pubkey);
+        }
+    }
+    addresses
+}
+
+/// Each address in the table shrinks from a pubkey to a one byte index, and the versioned message
+/// adds its version byte and the table's address and index counts
+fn size_with_lookup_table(size: usize, addresses: usize) -> usize {
+    let writable_and_readonly_index_counts = 2;
+    size.saturating_sub(addresses * (PUBKEY_BYTES - 1))
+        + 1
+        + short_vec_len(1)
+        + PUBKEY_BYTES
+        + writable_and_readonly_index_counts
+}
+
+/// Bytes taken by the compact-u16 length prefix of a serialized vector
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::instruction::{refresh_obligation, refresh_reserve};
+
+    fn refreshes(program_id: Pubkey, reserves: usize) -> Vec<Instruction> {
+        let reserve_pubkeys: Vec<Pubkey> = (0..reserves).map(|_| Pubkey::new_unique()).collect();
+        let mut instructions: Vec<Instruction> = reserve_pubkeys
+            .iter()
+            .map(|reserve| {
+                refresh_reserve(
+                    program_id,
+                    *reserve,
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    None,
+                )
+            })
+            .collect();
+        instructions.push(refresh_obligation(
+            program_id,
+            Pubkey::new_unique(),
+            reserve_pubkeys,
+        ));
+        instructions
+    }
+
+    #[test]
+    fn test_estimate_transaction() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let reserve = Pubkey::new_unique();
+        let instructions = vec![refresh_reserve(
+            program_id,
+            reserve,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            None,
+        )];
+
+        // 1 + 64 signature, 3 header, 1 + 5 * 32 keys, 32 blockhash, 1 + (1 + 1 + 3 + 1 + 1) ix
+        assert_eq!(
+            estimate_transaction(&program_id, &instructions, &payer),
+            TransactionEstimate {
+                size: 269,
+                compute_units: 30_000,
+            }
+        );
+
+        // another program's instruction is priced at the default
+        assert_eq!(
+            estimate_instruction_compute_units(&Pubkey::new_unique(), &instructions[0]),
+            DEFAULT_INSTRUCTION_COMPUTE_UNITS
+        );
+    }
+
+    #[test]
+    fn test_plan_transactions() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        // small groups share a transaction
+        let groups = vec![refreshes(program_id, 1), refreshes(program_id, 1)];
+        let transactions = plan_transactions(&program_id, &groups, &payer).unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].len(), 4);
+
+        // groups that don't fit together are sent in order, unsplit
+        let groups = vec![
+            refreshes(program_id, 6),
+            refreshes(program_id, 6),
+            refreshes(program_id, 1),
+        ];
+        let transactions = plan_transactions(&program_id, &groups, &payer).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0], groups[0]);
+        assert_eq!(
+            transactions[1],
+            [groups[1].clone(), groups[2].clone()].concat()
+        );
+        for transaction in &transactions {
+            assert!(estimate_transaction(&program_id, transaction, &payer).fits());
+        }
+    }
+
+    #[test]
+    fn test_plan_transactions_too_large() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        let groups = vec![refreshes(program_id, 1), refreshes(program_id, 12)];
+        match plan_transactions(&program_id, &groups, &payer) {
+            Err(TransactionPlanError::TooLarge {
+                group_index,
+                size,
+                lookup_table_addresses,
+                size_with_lookup_table,
+            }) => {
+                assert_eq!(group_index, 1);
+                assert!(size > MAX_TRANSACTION_SIZE);
+                // 12 reserves with 2 oracles each, and the obligation
+                assert_eq!(lookup_table_addresses.len(), 37);
+                assert!(size_with_lookup_table <= MAX_TRANSACTION_SIZE);
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}